@@ -1,7 +1,46 @@
-use std::{any::Any, collections::HashMap, fmt::Debug, hash::Hash};
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt::Debug,
+    hash::Hash,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use indexmap::IndexMap;
 
+/// 页面的唯一标识，后台线程据此指明通知对应的页面
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubviewKey(u64);
+
+impl SubviewKey {
+    pub fn next() -> Self {
+        static NEXT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        SubviewKey(NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed))
+    }
+
+    #[cfg(feature = "gui")]
+    fn focus_id() -> egui::Id {
+        egui::Id::new("subview-focus")
+    }
+
+    /// 请求主界面切换到该页面，在本帧结束时生效
+    #[cfg(feature = "gui")]
+    pub fn request_focus(self, ctx: &egui::Context) {
+        ctx.data_mut(|data| data.insert_temp(Self::focus_id(), self));
+    }
+
+    /// 取出尚未处理的切换请求
+    #[cfg(feature = "gui")]
+    pub fn take_focus_request(ctx: &egui::Context) -> Option<Self> {
+        ctx.data_mut(|data| {
+            let key = data.get_temp(Self::focus_id());
+            data.remove::<Self>(Self::focus_id());
+            key
+        })
+    }
+}
+
+#[cfg(feature = "gui")]
 pub trait Subview: Send {
     fn view(&mut self, ui: &mut egui::Ui);
 
@@ -12,6 +51,16 @@ pub trait Subview: Send {
     fn description(&self) -> String {
         String::new()
     }
+
+    /// 通知跳转时用于找到该页面，None 表示不接受跳转
+    fn key(&self) -> Option<SubviewKey> {
+        None
+    }
+
+    /// 重启后恢复该页面所需的信息，None 表示不恢复
+    fn workspace_entry(&self) -> Option<crate::workspace::WorkspaceEntry> {
+        None
+    }
 }
 
 pub trait SolveContext: Send + Any {
@@ -19,8 +68,10 @@ pub trait SolveContext: Send + Any {
     type ItemIdentType: ItemIdent;
 }
 
+/// 机制与提供器的编辑界面，关闭 gui 特性时没有任何方法
 pub trait EditorView: SolveContext {
     // 返回值表示是否产生了需要重新计算的更改
+    #[cfg(feature = "gui")]
     fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool;
 }
 
@@ -35,11 +86,34 @@ pub trait AsFlow: SolveContext {
     }
 }
 
+static NEXT_MECHANIC_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 机制的持久标识符，在创建时分配，随机制一起序列化，用作求解结果的键
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(transparent)]
+pub struct MechanicId(pub u64);
+
+impl MechanicId {
+    /// 分配一个本次运行中未使用过的标识符
+    pub fn generate() -> Self {
+        MechanicId(NEXT_MECHANIC_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// 登记从存档中读取的标识符，保证之后分配的标识符不与其冲突
+    pub fn reserve(self) -> Self {
+        NEXT_MECHANIC_ID.fetch_max(self.0 + 1, Ordering::Relaxed);
+        self
+    }
+}
+
 pub type MechanicSender<I, C> =
     std::sync::mpsc::Sender<Box<dyn Mechanic<ItemIdentType = I, GameContext = C>>>;
 
 pub trait ItemIdent: Debug + Clone + Eq + Hash + Send + 'static {}
 impl<T> ItemIdent for T where T: Debug + Clone + Eq + Hash + Send + 'static {}
+#[cfg(feature = "gui")]
 pub trait GameContextCreatorView: Subview {
     fn set_subview_sender(&mut self, sender: std::sync::mpsc::Sender<Box<dyn Subview>>);
 }
@@ -77,7 +151,7 @@ pub trait MechanicProvider:
     fn auto_populate(
         &self,
         _ctx: &Self::GameContext,
-        _flows: &HashMap<usize, Flow<Self::ItemIdentType>>,
+        _flows: &HashMap<MechanicId, Flow<Self::ItemIdentType>>,
     ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
     {
         // 默认不实现任何自动填充逻辑
@@ -98,6 +98,7 @@ fn test_dyn_deserializer() {
         machine: "electric-mining-drill".into(),
         module_config: ModuleConfig::new(),
         instance_fuel: None,
+        patch_size: None,
     };
     dbg!(&recipe);
     dbg!(&mining);
@@ -21,6 +21,271 @@ pub type FactorioMechanicProvider =
 #[derive(Debug, Clone, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct IdWithQuality(pub String, pub u8);
 
+/// 机制的简短描述，用于列表、表格等只能显示文字的地方
+pub fn mechanic_display_name(mechanic: &FactorioMechanic, ctx: &FactorioContext) -> String {
+    let any = mechanic as &dyn std::any::Any;
+    if let Some(recipe_config) = any.downcast_ref::<RecipeConfig>() {
+        format!(
+            "{} @ {}",
+            ctx.get_quality_suffixed_name(
+                "recipe",
+                &recipe_config.recipe.0,
+                recipe_config.recipe.1
+            ),
+            ctx.get_quality_suffixed_name(
+                "entity",
+                &recipe_config.machine.0,
+                recipe_config.machine.1
+            ),
+        )
+    } else if let Some(mining_config) = any.downcast_ref::<MiningConfig>() {
+        format!(
+            "{} @ {}",
+            ctx.get_display_name("entity", &mining_config.resource),
+            ctx.get_quality_suffixed_name(
+                "entity",
+                &mining_config.machine.0,
+                mining_config.machine.1
+            ),
+        )
+    } else if let Some(pumpjack_config) = any.downcast_ref::<PumpjackConfig>() {
+        format!(
+            "{} @ {}",
+            ctx.get_display_name("entity", &pumpjack_config.resource),
+            ctx.get_quality_suffixed_name(
+                "entity",
+                &pumpjack_config.machine.0,
+                pumpjack_config.machine.1
+            ),
+        )
+    } else if let Some(lab_config) = any.downcast_ref::<LabConfig>() {
+        format!(
+            "{} @ {}",
+            ctx.get_display_name("technology", &lab_config.technology),
+            ctx.get_quality_suffixed_name("entity", &lab_config.machine.0, lab_config.machine.1),
+        )
+    } else if let Some(pump_config) = any.downcast_ref::<OffshorePumpConfig>() {
+        format!(
+            "{} @ {}",
+            ctx.get_display_name("tile", &pump_config.tile),
+            ctx.get_quality_suffixed_name("entity", &pump_config.machine.0, pump_config.machine.1),
+        )
+    } else if let Some(boiler_config) = any.downcast_ref::<BoilerConfig>() {
+        ctx.get_quality_suffixed_name("entity", &boiler_config.machine.0, boiler_config.machine.1)
+    } else if let Some(reactor_config) = any.downcast_ref::<ReactorConfig>() {
+        format!(
+            "{} ({}×{})",
+            ctx.get_quality_suffixed_name(
+                "entity",
+                &reactor_config.machine.0,
+                reactor_config.machine.1
+            ),
+            reactor_config.layout.rows,
+            reactor_config.layout.columns,
+        )
+    } else if let Some(generator_config) = any.downcast_ref::<GeneratorConfig>() {
+        ctx.get_quality_suffixed_name(
+            "entity",
+            &generator_config.machine.0,
+            generator_config.machine.1,
+        )
+    } else if let Some(reactor_config) = any.downcast_ref::<FusionReactorConfig>() {
+        ctx.get_quality_suffixed_name(
+            "entity",
+            &reactor_config.machine.0,
+            reactor_config.machine.1,
+        )
+    } else if let Some(generator_config) = any.downcast_ref::<FusionGeneratorConfig>() {
+        ctx.get_quality_suffixed_name(
+            "entity",
+            &generator_config.machine.0,
+            generator_config.machine.1,
+        )
+    } else if let Some(solar_config) = any.downcast_ref::<SolarPowerConfig>() {
+        format!(
+            "{} ({})",
+            ctx.get_quality_suffixed_name(
+                "entity",
+                &solar_config.machine.0,
+                solar_config.machine.1
+            ),
+            ctx.get_display_name("space-location", &solar_config.planet),
+        )
+    } else if let Some(lightning_config) = any.downcast_ref::<LightningConfig>() {
+        format!(
+            "{} ({})",
+            ctx.get_quality_suffixed_name(
+                "entity",
+                &lightning_config.machine.0,
+                lightning_config.machine.1
+            ),
+            ctx.get_display_name("space-location", &lightning_config.planet),
+        )
+    } else if let Some(spawner_config) = any.downcast_ref::<SpawnerConfig>() {
+        ctx.get_quality_suffixed_name(
+            "entity",
+            &spawner_config.machine.0,
+            spawner_config.machine.1,
+        )
+    } else if let Some(collector_config) = any.downcast_ref::<AsteroidCollectorConfig>() {
+        format!(
+            "{} @ {}",
+            ctx.get_quality_suffixed_name(
+                "entity",
+                &collector_config.machine.0,
+                collector_config.machine.1
+            ),
+            ctx.route_display_name(&collector_config.route),
+        )
+    } else if let Some(thruster_config) = any.downcast_ref::<ThrusterConfig>() {
+        format!(
+            "{} × {} ({} km/s)",
+            ctx.get_quality_suffixed_name(
+                "entity",
+                &thruster_config.machine.0,
+                thruster_config.machine.1
+            ),
+            thruster_config.thrusters,
+            thruster_config.speed,
+        )
+    } else if let Some(defense_config) = any.downcast_ref::<DefenseConfig>() {
+        format!(
+            "{} × {} ({}%)",
+            ctx.get_quality_suffixed_name(
+                "entity",
+                &defense_config.machine.0,
+                defense_config.machine.1
+            ),
+            defense_config.turrets,
+            defense_config.fire_rate,
+        )
+    } else {
+        "未知机制".to_string()
+    }
+}
+
+/// 每单位机制放置的实体及其数量，不含插件等放入机器的物品
+/// 可安装插件的机器及其插件配置
+pub fn mechanic_module_config(
+    mechanic: &FactorioMechanic,
+) -> Option<(&IdWithQuality, &ModuleConfig)> {
+    let any = mechanic as &dyn std::any::Any;
+    if let Some(recipe_config) = any.downcast_ref::<RecipeConfig>() {
+        Some((&recipe_config.machine, &recipe_config.module_config))
+    } else if let Some(mining_config) = any.downcast_ref::<MiningConfig>() {
+        Some((&mining_config.machine, &mining_config.module_config))
+    } else if let Some(pumpjack_config) = any.downcast_ref::<PumpjackConfig>() {
+        Some((&pumpjack_config.machine, &pumpjack_config.module_config))
+    } else {
+        any.downcast_ref::<LabConfig>()
+            .map(|lab_config| (&lab_config.machine, &lab_config.module_config))
+    }
+}
+
+pub fn mechanic_placed_entities(
+    mechanic: &FactorioMechanic,
+    ctx: &FactorioContext,
+) -> Vec<(String, f64)> {
+    let any = mechanic as &dyn std::any::Any;
+    if let Some((machine, module_config)) = mechanic_module_config(mechanic) {
+        let mut entities = vec![(machine.0.clone(), 1.0)];
+        for beacon in &module_config.beacons {
+            entities.push((beacon.beacon.0.clone(), beacon.count as f64));
+        }
+        return entities;
+    }
+    if let Some(solar_config) = any.downcast_ref::<SolarPowerConfig>() {
+        let mut entities = vec![(solar_config.machine.0.clone(), 1.0)];
+        if let Some(accumulator) = &solar_config.accumulator {
+            entities.push((
+                accumulator.0.clone(),
+                solar_config.accumulators_per_panel(ctx),
+            ));
+        }
+        return entities;
+    }
+    if let Some(thruster_config) = any.downcast_ref::<ThrusterConfig>() {
+        return vec![(
+            thruster_config.machine.0.clone(),
+            thruster_config.thrusters as f64,
+        )];
+    }
+    if let Some(defense_config) = any.downcast_ref::<DefenseConfig>() {
+        return vec![(
+            defense_config.machine.0.clone(),
+            defense_config.turrets as f64,
+        )];
+    }
+    let machine = if let Some(pump_config) = any.downcast_ref::<OffshorePumpConfig>() {
+        &pump_config.machine
+    } else if let Some(boiler_config) = any.downcast_ref::<BoilerConfig>() {
+        &boiler_config.machine
+    } else if let Some(reactor_config) = any.downcast_ref::<ReactorConfig>() {
+        &reactor_config.machine
+    } else if let Some(generator_config) = any.downcast_ref::<GeneratorConfig>() {
+        &generator_config.machine
+    } else if let Some(reactor_config) = any.downcast_ref::<FusionReactorConfig>() {
+        &reactor_config.machine
+    } else if let Some(generator_config) = any.downcast_ref::<FusionGeneratorConfig>() {
+        &generator_config.machine
+    } else if let Some(lightning_config) = any.downcast_ref::<LightningConfig>() {
+        &lightning_config.machine
+    } else if let Some(spawner_config) = any.downcast_ref::<SpawnerConfig>() {
+        &spawner_config.machine
+    } else if let Some(collector_config) = any.downcast_ref::<AsteroidCollectorConfig>() {
+        &collector_config.machine
+    } else {
+        return vec![];
+    };
+    vec![(machine.0.clone(), 1.0)]
+}
+
+/// 在需要供暖的地表上，每单位机制放置的实体每秒需要的热量
+pub fn mechanic_heating_power(mechanic: &FactorioMechanic, ctx: &FactorioContext) -> f64 {
+    mechanic_placed_entities(mechanic, ctx)
+        .into_iter()
+        .map(|(entity, count)| {
+            ctx.entities
+                .get(&entity)
+                .and_then(|entity| entity.heating_energy.as_ref())
+                .map_or(0.0, |energy| energy.amount * 60.0 * count)
+        })
+        .sum()
+}
+
+/// 机制的配方或放置的实体在地点上不满足的地表条件，每条为一句说明
+pub fn mechanic_surface_violations(
+    mechanic: &FactorioMechanic,
+    ctx: &FactorioContext,
+    location: &str,
+) -> Vec<String> {
+    let mut violations = vec![];
+    if let Some(recipe_config) = (mechanic as &dyn std::any::Any).downcast_ref::<RecipeConfig>()
+        && let Some(recipe) = ctx.recipes.get(&recipe_config.recipe.0)
+    {
+        for condition in ctx.unmet_surface_conditions(location, &recipe.surface_conditions) {
+            violations.push(format!(
+                "{}：需要 {}",
+                ctx.get_display_name("recipe", &recipe_config.recipe.0),
+                condition.describe(ctx)
+            ));
+        }
+    }
+    for (entity, _) in mechanic_placed_entities(mechanic, ctx) {
+        let Some(prototype) = ctx.entities.get(&entity) else {
+            continue;
+        };
+        for condition in ctx.unmet_surface_conditions(location, &prototype.surface_conditions) {
+            violations.push(format!(
+                "{}：需要 {}",
+                ctx.get_display_name("entity", &entity),
+                condition.describe(ctx)
+            ));
+        }
+    }
+    violations
+}
+
 impl From<String> for IdWithQuality {
     fn from(s: String) -> Self {
         IdWithQuality(s, 0)
@@ -94,6 +359,7 @@ pub fn version_string_to_triplet(version: &str) -> (u16, u16, u16) {
 #[derive(Debug, Clone)]
 pub struct Color(u8, u8, u8, u8);
 
+#[cfg(feature = "gui")]
 impl From<Color> for egui::Color32 {
     fn from(val: Color) -> Self {
         egui::Color32::from_rgba_unmultiplied(val.0, val.1, val.2, val.3)
@@ -161,9 +427,35 @@ impl<'de> serde::Deserialize<'de> for Color {
     }
 }
 
+impl serde::Serialize for Color {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // 反序列化时乘以 255 后截断，这里取区间中点避免舍入误差
+        let channel = |c: u8| (c as f64 + 0.5) / 255.0;
+        [
+            channel(self.0),
+            channel(self.1),
+            channel(self.2),
+            channel(self.3),
+        ]
+        .serialize(serializer)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MapPosition(pub f64, pub f64);
 
+impl serde::Serialize for MapPosition {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        [self.0, self.1].serialize(serializer)
+    }
+}
+
 impl<'de> serde::Deserialize<'de> for MapPosition {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -199,7 +491,7 @@ impl<'de> serde::Deserialize<'de> for MapPosition {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(untagged)]
 pub enum BoundingBox {
     Struct {
@@ -211,6 +503,22 @@ pub enum BoundingBox {
     Triplet(MapPosition, MapPosition, f64),
 }
 
+impl BoundingBox {
+    /// 碰撞箱覆盖的格子数，各边向上取整
+    pub fn tile_area(&self) -> f64 {
+        let (left_top, right_bottom) = match self {
+            BoundingBox::Struct {
+                left_top,
+                right_bottom,
+                ..
+            } => (left_top, right_bottom),
+            BoundingBox::Pair(left_top, right_bottom)
+            | BoundingBox::Triplet(left_top, right_bottom, _) => (left_top, right_bottom),
+        };
+        f64::ceil(right_bottom.1 - left_top.1) * f64::ceil(right_bottom.0 - left_top.0)
+    }
+}
+
 pub fn as_vec_or_empty<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -236,7 +544,7 @@ where
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 /// PrototypeBase 基类中我们关心的字段
 #[derive(Default)]
@@ -271,6 +579,16 @@ pub struct EnergyAmount {
     pub amount: f64,
 }
 
+impl serde::Serialize for EnergyAmount {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // 与原始数据相同的字符串格式，单位统一为焦耳
+        format!("{}J", self.amount).serialize(serializer)
+    }
+}
+
 impl<'de> serde::Deserialize<'de> for EnergyAmount {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -332,7 +650,7 @@ impl Display for EnergyAmount {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum EnergySource {
     Electric(ElectricEnergySource),
@@ -342,23 +660,25 @@ pub enum EnergySource {
     Void(VoidEnergySource),
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 #[derive(Default)]
 pub struct ElectricEnergySource {
-    buffer_capacity: Option<EnergyAmount>,
+    pub buffer_capacity: Option<EnergyAmount>,
     input_flow_limit: Option<EnergyAmount>,
-    output_flow_limit: Option<EnergyAmount>,
+    pub output_flow_limit: Option<EnergyAmount>,
     pub drain: Option<EnergyAmount>,
     pub emissions_per_minute: Option<Emissions>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 pub struct BurnerEnergySource {
     pub burnt_inventory_size: f64,
     pub effectivity: f64,
     pub burner_usage: String,
+    /// 可以使用的燃料类别
+    pub fuel_categories: Vec<String>,
     pub emissions_per_minute: Option<Dict<f64>>,
 }
 
@@ -368,19 +688,20 @@ impl Default for BurnerEnergySource {
             burnt_inventory_size: 0.0,
             effectivity: 1.0,
             burner_usage: "fuel".to_string(),
+            fuel_categories: vec!["chemical".to_string()],
             emissions_per_minute: None,
         }
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Default)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
 #[serde(default)]
 pub struct HeatEnergySource {
     pub max_temperature: f64,
     pub emissions_per_minute: Option<Dict<f64>>,
 }
 
-#[derive(Debug, serde::Deserialize, Clone, PartialEq, Default)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "kebab-case")]
 pub enum FluidIOMode {
     #[default]
@@ -390,7 +711,7 @@ pub enum FluidIOMode {
     Output,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct FluidBox {
     #[serde(default)]
     pub filter: Option<String>,
@@ -400,7 +721,7 @@ pub struct FluidBox {
     pub production_type: FluidIOMode,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 pub struct FluidEnergySource {
     pub effectivity: f64,
@@ -430,13 +751,13 @@ impl Default for FluidEnergySource {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Default)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
 #[serde(default)]
 pub struct VoidEnergySource {
     pub emissions_per_minute: Option<Dict<f64>>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 pub struct EffectReceiver {
     pub base_effect: Effect,
@@ -504,7 +825,7 @@ impl Effect {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum EffectType {
     Consumption,
@@ -514,7 +835,7 @@ pub enum EffectType {
     Quality,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(untagged)]
 pub enum EffectTypeLimitation {
     Single(EffectType),
@@ -586,7 +907,7 @@ fn test_energy_amount_deserialize() {
     println!("{}", EnergyAmount { amount: 150000.0 });
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 /// 子组
 pub struct ItemSubgroup {
     #[serde(flatten)]
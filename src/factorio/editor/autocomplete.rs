@@ -0,0 +1,228 @@
+use std::{
+    any::Any,
+    collections::{HashSet, VecDeque},
+};
+
+use indexmap::IndexMap;
+
+use crate::{
+    concept::*,
+    factorio::{common::*, model::*},
+};
+
+/// 自动补全时单次最多添加的机制数量，防止在复杂的配方网络中失控
+pub const MAX_AUTO_COMPLETE_STEPS: usize = 64;
+
+/// 为缺口物品挑选生产方式时的偏好
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChainPreference {
+    /// 单位产量占地最小
+    #[default]
+    Cheapest,
+    /// 单位产量耗电最少
+    LeastPower,
+    /// 单位产量污染最低
+    LowestPollution,
+}
+
+impl ChainPreference {
+    pub const ALL: [ChainPreference; 3] = [
+        ChainPreference::Cheapest,
+        ChainPreference::LeastPower,
+        ChainPreference::LowestPollution,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChainPreference::Cheapest => "代价最低",
+            ChainPreference::LeastPower => "耗电最少",
+            ChainPreference::LowestPollution => "污染最低",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ChainCompletion {
+    /// 按添加顺序排列的机制
+    pub added: Vec<Box<FactorioMechanic>>,
+    /// 只能通过采矿获得、留给外部输入或手动处理的原料
+    pub raw: Vec<GenericItem>,
+    /// 找不到合适生产方式的物品
+    pub unresolved: Vec<GenericItem>,
+}
+
+fn is_material(item: &GenericItem) -> bool {
+    matches!(item, GenericItem::Item(_) | GenericItem::Fluid { .. })
+}
+
+/// 按偏好计算单位产量的评分，越小越好
+fn score(
+    mechanic: &FactorioMechanic,
+    flow: &Flow<GenericItem>,
+    rate: f64,
+    preference: ChainPreference,
+    ctx: &FactorioContext,
+) -> f64 {
+    let cost = mechanic.cost(ctx) / rate;
+    let secondary = match preference {
+        ChainPreference::Cheapest => 0.0,
+        ChainPreference::LeastPower => {
+            -flow.get(&GenericItem::Electricity).cloned().unwrap_or(0.0) / rate
+        }
+        ChainPreference::LowestPollution => {
+            flow.iter()
+                .filter(|(item, _)| matches!(item, GenericItem::Pollution { .. }))
+                .map(|(_, amount)| *amount)
+                .sum::<f64>()
+                / rate
+        }
+    };
+    // 次要指标相同（例如都不耗电）时按代价比较
+    secondary * 1e6 + cost
+}
+
+/// 从目标与已有机制出发，反复为缺口物品贪心地添加最优的生产方式，直到没有缺口或只剩原料
+///
+/// 为避免回收等配方形成循环，为某个物品选择生产方式时，不会选用消耗其下游物品的配方。
+pub fn complete_chain(
+    mechanics: &IndexMap<MechanicId, Box<FactorioMechanic>>,
+    targets: &[(GenericItem, f64)],
+    external: &[(GenericItem, f64)],
+    providers: &[Box<FactorioMechanicProvider>],
+    preference: ChainPreference,
+    ctx: &FactorioContext,
+) -> ChainCompletion {
+    let mut result = ChainCompletion::default();
+    let mut produced: HashSet<GenericItem> = external
+        .iter()
+        .filter(|(_, amount)| *amount > 0.0)
+        .map(|(item, _)| item.clone())
+        .collect();
+    let mut queue: VecDeque<(GenericItem, HashSet<GenericItem>)> = VecDeque::new();
+    for mechanic in mechanics.values() {
+        let flow = mechanic.as_flow(ctx);
+        produced.extend(
+            flow.iter()
+                .filter(|(_, amount)| **amount > 0.0)
+                .map(|(item, _)| item.clone()),
+        );
+    }
+    for (item, amount) in targets {
+        if *amount > 0.0 {
+            queue.push_back((item.clone(), HashSet::new()));
+        }
+    }
+    for mechanic in mechanics.values() {
+        let flow = mechanic.as_flow(ctx);
+        let downstream: HashSet<GenericItem> = flow
+            .iter()
+            .filter(|(_, amount)| **amount > 0.0)
+            .map(|(item, _)| item.clone())
+            .collect();
+        for (item, amount) in flow.iter() {
+            if *amount < 0.0 {
+                queue.push_back((item.clone(), downstream.clone()));
+            }
+        }
+    }
+    let mut visited = HashSet::new();
+    while let Some((item, downstream)) = queue.pop_front() {
+        if result.added.len() >= MAX_AUTO_COMPLETE_STEPS {
+            break;
+        }
+        if !is_material(&item) || produced.contains(&item) || !visited.insert(item.clone()) {
+            continue;
+        }
+        let candidates = providers
+            .iter()
+            .flat_map(|provider| provider.hint_populate(ctx, &item, -1.0))
+            .collect::<Vec<_>>();
+        if candidates.iter().any(|candidate| {
+            let any = candidate.as_ref() as &dyn Any;
+            any.is::<MiningConfig>() || any.is::<PumpjackConfig>() || any.is::<OffshorePumpConfig>()
+        }) {
+            result.raw.push(item);
+            continue;
+        }
+        let best = candidates
+            .into_iter()
+            .filter_map(|candidate| {
+                let flow = candidate.as_flow(ctx);
+                let rate = flow.get(&item).cloned().unwrap_or(0.0);
+                let cyclic = flow.iter().any(|(consumed, amount)| {
+                    *amount < 0.0 && (*consumed == item || downstream.contains(consumed))
+                });
+                if rate <= 0.0 || cyclic {
+                    return None;
+                }
+                let score = score(candidate.as_ref(), &flow, rate, preference, ctx);
+                Some((score, candidate, flow))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        let Some((_, mechanic, flow)) = best else {
+            result.unresolved.push(item);
+            continue;
+        };
+        let mut next_downstream = downstream.clone();
+        next_downstream.insert(item.clone());
+        for (other, amount) in flow.iter() {
+            if *amount > 0.0 {
+                produced.insert(other.clone());
+            } else if *amount < 0.0 {
+                queue.push_back((other.clone(), next_downstream.clone()));
+            }
+        }
+        result.added.push(mechanic);
+    }
+    result
+}
+
+#[test]
+fn test_complete_chain() {
+    let ctx = FactorioContext::test_load();
+    let providers: Vec<Box<FactorioMechanicProvider>> = vec![
+        Box::new(RecipeConfigProvider::new()),
+        Box::new(MiningConfigProvider::new()),
+    ];
+    let targets = vec![(GenericItem::Item("electronic-circuit".into()), 1.0)];
+    let completion = complete_chain(
+        &IndexMap::new(),
+        &targets,
+        &[],
+        &providers,
+        ChainPreference::Cheapest,
+        &ctx,
+    );
+    let recipes = completion
+        .added
+        .iter()
+        .filter_map(|mechanic| {
+            (mechanic.as_ref() as &dyn Any)
+                .downcast_ref::<RecipeConfig>()
+                .map(|config| config.recipe.0.clone())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        recipes.first().map(String::as_str),
+        Some("electronic-circuit")
+    );
+    // 补全后的每种原料要么由新增的机制生产，要么被列为原料或无法解决
+    let flows = completion
+        .added
+        .iter()
+        .map(|mechanic| mechanic.as_flow(&ctx))
+        .collect::<Vec<_>>();
+    for flow in &flows {
+        for (item, amount) in flow {
+            if *amount < 0.0 && is_material(item) {
+                assert!(
+                    flows
+                        .iter()
+                        .any(|other| other.get(item).is_some_and(|a| *a > 0.0))
+                        || completion.raw.contains(item)
+                        || completion.unresolved.contains(item)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,510 @@
+//! 原型数据浏览器。
+//!
+//! 按类别列出上下文中的原型，可按内部名或本地化名搜索，
+//! 并展示原型解析后的全部字段以及它与其他原型的关系（由谁生产、用于何处等）。
+
+use crate::{
+    concept::*,
+    factorio::{common::*, editor::icon::Icon, model::*},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrototypeCategory {
+    Item,
+    Recipe,
+    Entity,
+    Fluid,
+    Module,
+    Technology,
+}
+
+impl PrototypeCategory {
+    pub const ALL: [PrototypeCategory; 6] = [
+        PrototypeCategory::Item,
+        PrototypeCategory::Recipe,
+        PrototypeCategory::Entity,
+        PrototypeCategory::Fluid,
+        PrototypeCategory::Module,
+        PrototypeCategory::Technology,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PrototypeCategory::Item => "物品",
+            PrototypeCategory::Recipe => "配方",
+            PrototypeCategory::Entity => "实体",
+            PrototypeCategory::Fluid => "流体",
+            PrototypeCategory::Module => "插件",
+            PrototypeCategory::Technology => "科技",
+        }
+    }
+
+    /// 翻译与图标所用的类别名，插件属于物品
+    pub fn locale_category(&self) -> &'static str {
+        match self {
+            PrototypeCategory::Item | PrototypeCategory::Module => "item",
+            PrototypeCategory::Recipe => "recipe",
+            PrototypeCategory::Entity => "entity",
+            PrototypeCategory::Fluid => "fluid",
+            PrototypeCategory::Technology => "technology",
+        }
+    }
+
+    pub fn base<'a>(&self, ctx: &'a FactorioContext, name: &str) -> Option<&'a PrototypeBase> {
+        match self {
+            PrototypeCategory::Item => ctx.items.get(name).map(|p| p.base()),
+            PrototypeCategory::Recipe => ctx.recipes.get(name).map(|p| p.base()),
+            PrototypeCategory::Entity => ctx.entities.get(name).map(|p| p.base()),
+            PrototypeCategory::Fluid => ctx.fluids.get(name).map(|p| p.base()),
+            PrototypeCategory::Module => ctx.modules.get(name).map(|p| &p.base),
+            PrototypeCategory::Technology => ctx.technologies.get(name).map(|p| p.base()),
+        }
+    }
+
+    /// 原型解析后的字段
+    pub fn fields(&self, ctx: &FactorioContext, name: &str) -> Option<serde_json::Value> {
+        match self {
+            PrototypeCategory::Item => ctx.items.get(name).map(serde_json::to_value),
+            PrototypeCategory::Recipe => ctx.recipes.get(name).map(serde_json::to_value),
+            PrototypeCategory::Entity => ctx.entities.get(name).map(serde_json::to_value),
+            PrototypeCategory::Fluid => ctx.fluids.get(name).map(serde_json::to_value),
+            PrototypeCategory::Module => ctx.modules.get(name).map(serde_json::to_value),
+            PrototypeCategory::Technology => ctx.technologies.get(name).map(serde_json::to_value),
+        }
+        .and_then(Result::ok)
+    }
+
+    /// 该类别的全部原型名，按游戏内的排序排列
+    pub fn sorted_names(&self, ctx: &FactorioContext) -> Vec<String> {
+        let mut names: Vec<&String> = match self {
+            PrototypeCategory::Item => ctx.items.keys().collect(),
+            PrototypeCategory::Recipe => ctx.recipes.keys().collect(),
+            PrototypeCategory::Entity => ctx.entities.keys().collect(),
+            PrototypeCategory::Fluid => ctx.fluids.keys().collect(),
+            PrototypeCategory::Module => ctx.modules.keys().collect(),
+            PrototypeCategory::Technology => ctx.technologies.keys().collect(),
+        };
+        let order = ctx.order_of_entries.get(self.locale_category());
+        names.sort_by_cached_key(|name| {
+            (
+                order
+                    .and_then(|order| order.get(*name))
+                    .cloned()
+                    .unwrap_or((usize::MAX, usize::MAX, usize::MAX)),
+                self.base(ctx, name)
+                    .map(|base| base.order.clone())
+                    .unwrap_or_default(),
+                (*name).clone(),
+            )
+        });
+        names.into_iter().cloned().collect()
+    }
+}
+
+/// 一个原型的引用，用于在关系列表中跳转
+pub type PrototypeRef = (PrototypeCategory, String);
+
+fn ingredient_name(ingredient: &RecipeIngredient) -> PrototypeRef {
+    match ingredient {
+        RecipeIngredient::Item(i) => (PrototypeCategory::Item, i.name.clone()),
+        RecipeIngredient::Fluid(f) => (PrototypeCategory::Fluid, f.name.clone()),
+    }
+}
+
+fn result_name(result: &RecipeResult) -> PrototypeRef {
+    match result {
+        RecipeResult::Item(r) => (PrototypeCategory::Item, r.name.clone()),
+        RecipeResult::Fluid(r) => (PrototypeCategory::Fluid, r.name.clone()),
+    }
+}
+
+fn sorted_refs(
+    category: PrototypeCategory,
+    names: impl Iterator<Item = String>,
+) -> Vec<PrototypeRef> {
+    let mut names: Vec<String> = names.collect();
+    names.sort();
+    names.dedup();
+    names.into_iter().map(|name| (category, name)).collect()
+}
+
+/// 原型与其他原型的关系，每项为标题与相关原型的列表
+pub fn relations(
+    ctx: &FactorioContext,
+    category: PrototypeCategory,
+    name: &str,
+) -> Vec<(&'static str, Vec<PrototypeRef>)> {
+    let mut sections = vec![];
+    match category {
+        PrototypeCategory::Item | PrototypeCategory::Module | PrototypeCategory::Fluid => {
+            let item = match category {
+                PrototypeCategory::Fluid => GenericItem::Fluid {
+                    name: name.to_string(),
+                    temperature: None,
+                },
+                _ => GenericItem::Item(name.into()),
+            };
+            let produced_by = ctx.producers_of(&item).iter().cloned();
+            sections.push((
+                "由以下配方生产",
+                sorted_refs(PrototypeCategory::Recipe, produced_by),
+            ));
+            let used_in = ctx.consumers_of(&item).iter().cloned();
+            sections.push((
+                "用于以下配方",
+                sorted_refs(PrototypeCategory::Recipe, used_in),
+            ));
+            if let Some(entity) = ctx
+                .items
+                .get(name)
+                .and_then(|item| item.place_result.clone())
+            {
+                sections.push(("放置为", vec![(PrototypeCategory::Entity, entity)]));
+            }
+        }
+        PrototypeCategory::Recipe => {
+            if let Some(recipe) = ctx.recipes.get(name) {
+                sections.push((
+                    "原料",
+                    recipe.ingredients.iter().map(ingredient_name).collect(),
+                ));
+                sections.push(("产物", recipe.results.iter().map(result_name).collect()));
+                let machines = ctx
+                    .crafters
+                    .values()
+                    .filter(|crafter| machine_fits_for_recipe(crafter, recipe))
+                    .map(|crafter| crafter.base().name.clone());
+                sections.push(("可用机器", sorted_refs(PrototypeCategory::Entity, machines)));
+            }
+            let unlocked_by = ctx
+                .technologies
+                .values()
+                .filter(|tech| tech.unlocked_recipes().any(|r| r == name))
+                .map(|tech| tech.base.name.clone());
+            sections.push((
+                "由以下科技解锁",
+                sorted_refs(PrototypeCategory::Technology, unlocked_by),
+            ));
+        }
+        PrototypeCategory::Entity => {
+            let placed_by = ctx.items_placing(name).iter().cloned();
+            sections.push((
+                "由以下物品放置",
+                sorted_refs(PrototypeCategory::Item, placed_by),
+            ));
+            if let Some(crafter) = ctx.crafters.get(name) {
+                let recipes = ctx
+                    .recipes
+                    .values()
+                    .filter(|recipe| machine_fits_for_recipe(crafter, recipe))
+                    .map(|recipe| recipe.base.name.clone());
+                sections.push((
+                    "可制作配方",
+                    sorted_refs(PrototypeCategory::Recipe, recipes),
+                ));
+            }
+        }
+        PrototypeCategory::Technology => {
+            if let Some(tech) = ctx.technologies.get(name) {
+                sections.push((
+                    "前置科技",
+                    sorted_refs(
+                        PrototypeCategory::Technology,
+                        tech.prerequisites.iter().cloned(),
+                    ),
+                ));
+                sections.push((
+                    "解锁配方",
+                    tech.unlocked_recipes()
+                        .map(|r| (PrototypeCategory::Recipe, r.clone()))
+                        .collect(),
+                ));
+            }
+            let followers = ctx
+                .technologies
+                .values()
+                .filter(|tech| tech.prerequisites.iter().any(|p| p == name))
+                .map(|tech| tech.base.name.clone());
+            sections.push((
+                "后续科技",
+                sorted_refs(PrototypeCategory::Technology, followers),
+            ));
+        }
+    }
+    sections.retain(|(_, refs)| !refs.is_empty());
+    sections
+}
+
+/// 以可折叠的树形式展示 JSON 值
+fn json_tree(ui: &mut egui::Ui, key: &str, value: &serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            egui::CollapsingHeader::new(format!("{} {{{}}}", key, map.len()))
+                .id_salt(ui.next_auto_id())
+                .show(ui, |ui| {
+                    for (k, v) in map {
+                        json_tree(ui, k, v);
+                    }
+                });
+        }
+        serde_json::Value::Array(list) => {
+            egui::CollapsingHeader::new(format!("{} [{}]", key, list.len()))
+                .id_salt(ui.next_auto_id())
+                .show(ui, |ui| {
+                    for (i, v) in list.iter().enumerate() {
+                        json_tree(ui, &i.to_string(), v);
+                    }
+                });
+        }
+        _ => {
+            ui.label(format!("{}: {}", key, value));
+        }
+    }
+}
+
+/// 原型浏览器视图，持有打开时上下文的副本
+pub struct PrototypeBrowserView {
+    pub ctx: FactorioContext,
+    pub context_name: String,
+    pub category: PrototypeCategory,
+    pub query: String,
+    pub show_hidden: bool,
+    pub selected: Option<String>,
+    /// 当前类别下符合搜索条件的原型
+    results: Vec<String>,
+    /// 浏览过的原型，用于返回
+    history: Vec<PrototypeRef>,
+}
+
+impl PrototypeBrowserView {
+    pub fn new(ctx: FactorioContext, context_name: String) -> Self {
+        let mut view = Self {
+            ctx,
+            context_name,
+            category: PrototypeCategory::Item,
+            query: String::new(),
+            show_hidden: false,
+            selected: None,
+            results: vec![],
+            history: vec![],
+        };
+        view.refresh();
+        view
+    }
+
+    /// 按当前类别与搜索词重新筛选
+    pub fn refresh(&mut self) {
+        let query = self.query.trim().to_lowercase();
+        let category = self.category;
+        let ctx = &self.ctx;
+        self.results = category
+            .sorted_names(ctx)
+            .into_iter()
+            .filter(|name| {
+                self.show_hidden
+                    || !category
+                        .base(ctx, name)
+                        .is_some_and(|base| base.hidden || base.parameter)
+            })
+            .filter(|name| {
+                query.is_empty()
+                    || name.to_lowercase().contains(&query)
+                    || ctx
+                        .get_display_name(category.locale_category(), name)
+                        .to_lowercase()
+                        .contains(&query)
+            })
+            .collect();
+    }
+
+    /// 跳转到另一个原型，记录当前位置以便返回
+    pub fn navigate(&mut self, target: PrototypeRef) {
+        if let Some(selected) = self.selected.take() {
+            self.history.push((self.category, selected));
+        }
+        let (category, name) = target;
+        if category != self.category {
+            self.category = category;
+            self.query.clear();
+            self.refresh();
+        }
+        self.selected = Some(name);
+    }
+
+    fn back(&mut self) {
+        if let Some((category, name)) = self.history.pop() {
+            if category != self.category {
+                self.category = category;
+                self.query.clear();
+                self.refresh();
+            }
+            self.selected = Some(name);
+        }
+    }
+
+    fn prototype_button(
+        ui: &mut egui::Ui,
+        ctx: &FactorioContext,
+        target: &PrototypeRef,
+        selected: bool,
+    ) -> egui::Response {
+        let (category, name) = target;
+        ui.horizontal(|ui| {
+            ui.add_sized(
+                [20.0, 20.0],
+                Icon::new(ctx, category.locale_category(), name).with_size(20.0),
+            );
+            ui.selectable_label(
+                selected,
+                ctx.get_display_name(category.locale_category(), name),
+            )
+            .on_hover_text(format!("{}: {}", category.name(), name))
+        })
+        .inner
+    }
+
+    fn detail_view(&mut self, ui: &mut egui::Ui, name: &str) {
+        let category = self.category;
+        let ctx = &self.ctx;
+        let mut target = None;
+        ui.horizontal(|ui| {
+            ui.add_sized(
+                [48.0, 48.0],
+                Icon::new(ctx, category.locale_category(), name).with_size(48.0),
+            );
+            ui.vertical(|ui| {
+                ui.heading(ctx.get_display_name(category.locale_category(), name));
+                ui.label(format!("{}：{}", category.name(), name));
+                if let Some(base) = category.base(ctx, name) {
+                    ui.label(format!("原型类型：{}", base.r#type));
+                }
+            });
+        });
+        if let Some(description) = ctx
+            .localized_description
+            .get(category.locale_category())
+            .and_then(|descriptions| descriptions.get(name))
+        {
+            ui.label(description);
+        }
+        ui.separator();
+        for (title, refs) in relations(ctx, category, name) {
+            egui::CollapsingHeader::new(format!("{}（{}）", title, refs.len()))
+                .id_salt(title)
+                .default_open(true)
+                .show(ui, |ui| {
+                    for r in &refs {
+                        if Self::prototype_button(ui, ctx, r, false).clicked() {
+                            target = Some(r.clone());
+                        }
+                    }
+                });
+        }
+        ui.separator();
+        egui::CollapsingHeader::new("解析字段")
+            .id_salt("prototype-fields")
+            .show(ui, |ui| match category.fields(ctx, name) {
+                Some(serde_json::Value::Object(map)) => {
+                    for (k, v) in &map {
+                        json_tree(ui, k, v);
+                    }
+                }
+                Some(value) => {
+                    json_tree(ui, name, &value);
+                }
+                None => {
+                    ui.label("原型不存在");
+                }
+            });
+        if let Some(target) = target {
+            self.navigate(target);
+        }
+    }
+}
+
+impl Subview for PrototypeBrowserView {
+    fn view(&mut self, ui: &mut egui::Ui) {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            for category in PrototypeCategory::ALL {
+                changed |= ui
+                    .selectable_value(&mut self.category, category, category.name())
+                    .changed();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("搜索：");
+            changed |= ui
+                .add(egui::TextEdit::singleline(&mut self.query).hint_text("内部名或显示名"))
+                .changed();
+            changed |= ui.checkbox(&mut self.show_hidden, "显示隐藏原型").changed();
+            ui.label(format!("共 {} 项", self.results.len()));
+        });
+        if changed {
+            self.refresh();
+        }
+        ui.separator();
+        egui::SidePanel::left("prototype-browser-list")
+            .resizable(true)
+            .default_width(240.0)
+            .show_inside(ui, |ui| {
+                let mut clicked = None;
+                egui::ScrollArea::vertical().show_rows(
+                    ui,
+                    24.0,
+                    self.results.len(),
+                    |ui, range| {
+                        for name in &self.results[range] {
+                            let target = (self.category, name.clone());
+                            let selected = self.selected.as_ref() == Some(name);
+                            if Self::prototype_button(ui, &self.ctx, &target, selected).clicked() {
+                                clicked = Some(target);
+                            }
+                        }
+                    },
+                );
+                if let Some(target) = clicked {
+                    self.navigate(target);
+                }
+            });
+        egui::CentralPanel::default().show_inside(ui, |ui| {
+            if ui
+                .add_enabled(!self.history.is_empty(), egui::Button::new("返回"))
+                .clicked()
+            {
+                self.back();
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| match self.selected.clone() {
+                Some(name) => self.detail_view(ui, &name),
+                None => {
+                    ui.label("在左侧选择一个原型");
+                }
+            });
+        });
+    }
+
+    fn name(&self) -> String {
+        format!("原型浏览器 - {}", self.context_name)
+    }
+
+    fn description(&self) -> String {
+        "搜索并查看上下文中的原型数据及其关联".to_string()
+    }
+}
+
+#[test]
+fn test_prototype_relations() {
+    let ctx = FactorioContext::test_load();
+    let gear = relations(&ctx, PrototypeCategory::Item, "iron-gear-wheel");
+    let (_, produced_by) = gear
+        .iter()
+        .find(|(title, _)| *title == "由以下配方生产")
+        .unwrap();
+    assert!(produced_by.contains(&(PrototypeCategory::Recipe, "iron-gear-wheel".to_string())));
+    let assembler = relations(&ctx, PrototypeCategory::Recipe, "assembling-machine-1");
+    let (_, unlocked_by) = assembler
+        .iter()
+        .find(|(title, _)| *title == "由以下科技解锁")
+        .unwrap();
+    assert!(unlocked_by.contains(&(PrototypeCategory::Technology, "automation".to_string())));
+}
@@ -0,0 +1,357 @@
+use std::any::Any;
+
+use indexmap::IndexMap;
+
+use crate::{
+    concept::*,
+    factorio::{common::*, editor::planner::FactoryInstance, format::*, model::*},
+};
+
+/// 展开配方时的最大深度，防止配方网络中的环导致无限递归
+const MAX_EXPAND_DEPTH: usize = 32;
+
+/// 方案的一次性建造成本
+#[derive(Debug, Clone, Default)]
+pub struct BuildList {
+    pub factory_name: String,
+    /// 需要放置的建筑与插件（物品名与品质），数量已向上取整
+    pub buildings: IndexMap<IdWithQuality, f64>,
+    /// 展开过程中需要制作的中间产物
+    pub intermediates: IndexMap<IdWithQuality, f64>,
+    /// 无法继续展开的原料
+    pub raw: IndexMap<IdWithQuality, f64>,
+}
+
+fn add_entry(map: &mut IndexMap<IdWithQuality, f64>, key: IdWithQuality, amount: f64) {
+    *map.entry(key).or_insert(0.0) += amount;
+}
+
+/// 放置该实体所用的物品
+fn item_placing_entity(ctx: &FactorioContext, entity: &str) -> Option<String> {
+    if ctx
+        .items
+        .get(entity)
+        .is_some_and(|item| item.place_result.as_deref() == Some(entity))
+    {
+        return Some(entity.to_string());
+    }
+    ctx.items_placing(entity).first().cloned()
+}
+
+/// 可以直接开采的物品视为原料，不再通过配方展开
+pub(crate) fn is_mined(ctx: &FactorioContext, item: &str) -> bool {
+    ctx.resources.values().any(|resource| {
+        resource.base.minable.as_ref().is_some_and(|mining| {
+            mining.result.as_deref() == Some(item)
+                || mining.results.as_ref().is_some_and(|results| {
+                    results.iter().any(|result| match result {
+                        RecipeResult::Item(result) => result.name == item,
+                        RecipeResult::Fluid(result) => result.name == item,
+                    })
+                })
+        })
+    })
+}
+
+/// 选择制作物品的配方：优先使用同名配方，否则使用原料种类最少的配方，不考虑回收
+fn recipe_for_item<'a>(ctx: &'a FactorioContext, item: &str) -> Option<&'a RecipePrototype> {
+    let produces = |recipe: &RecipePrototype| {
+        recipe.results.iter().any(|result| match result {
+            RecipeResult::Item(result) => result.name == item,
+            RecipeResult::Fluid(_) => false,
+        })
+    };
+    if is_mined(ctx, item) {
+        return None;
+    }
+    if let Some(recipe) = ctx.recipes.get(item)
+        && produces(recipe)
+    {
+        return Some(recipe);
+    }
+    ctx.recipes
+        .values()
+        .filter(|recipe| !recipe.base.hidden && recipe.category() != "recycling")
+        .filter(|recipe| produces(recipe))
+        .min_by(|a, b| {
+            (a.ingredients.len(), &a.base.name).cmp(&(b.ingredients.len(), &b.base.name))
+        })
+}
+
+impl BuildList {
+    pub fn collect(factory: &FactoryInstance, ctx: &FactorioContext) -> Self {
+        let mut list = BuildList {
+            factory_name: factory.name.clone(),
+            ..Default::default()
+        };
+        for (id, mechanic) in &factory.mechanics {
+            let count = factory.solution.0.get(id).cloned().unwrap_or(0.0);
+            if count < 1e-6 {
+                continue;
+            }
+            let machines = count.ceil();
+            let any = mechanic.as_ref() as &dyn Any;
+            if let Some(pump_config) = any.downcast_ref::<OffshorePumpConfig>() {
+                list.add_entity(ctx, &pump_config.machine, machines);
+                continue;
+            }
+            if let Some(boiler_config) = any.downcast_ref::<BoilerConfig>() {
+                list.add_entity(ctx, &boiler_config.machine, machines);
+                continue;
+            }
+            if let Some(reactor_config) = any.downcast_ref::<ReactorConfig>() {
+                list.add_entity(ctx, &reactor_config.machine, machines);
+                continue;
+            }
+            if let Some(generator_config) = any.downcast_ref::<GeneratorConfig>() {
+                list.add_entity(ctx, &generator_config.machine, machines);
+                continue;
+            }
+            if let Some(reactor_config) = any.downcast_ref::<FusionReactorConfig>() {
+                list.add_entity(ctx, &reactor_config.machine, machines);
+                continue;
+            }
+            if let Some(generator_config) = any.downcast_ref::<FusionGeneratorConfig>() {
+                list.add_entity(ctx, &generator_config.machine, machines);
+                continue;
+            }
+            if let Some(lightning_config) = any.downcast_ref::<LightningConfig>() {
+                list.add_entity(ctx, &lightning_config.machine, machines);
+                continue;
+            }
+            if let Some(spawner_config) = any.downcast_ref::<SpawnerConfig>() {
+                list.add_entity(ctx, &spawner_config.machine, machines);
+                continue;
+            }
+            if let Some(collector_config) = any.downcast_ref::<AsteroidCollectorConfig>() {
+                list.add_entity(ctx, &collector_config.machine, machines);
+                continue;
+            }
+            if let Some(thruster_config) = any.downcast_ref::<ThrusterConfig>() {
+                list.add_entity(
+                    ctx,
+                    &thruster_config.machine,
+                    machines * thruster_config.thrusters as f64,
+                );
+                continue;
+            }
+            if let Some(defense_config) = any.downcast_ref::<DefenseConfig>() {
+                list.add_entity(
+                    ctx,
+                    &defense_config.machine,
+                    machines * defense_config.turrets as f64,
+                );
+                continue;
+            }
+            if let Some(solar_config) = any.downcast_ref::<SolarPowerConfig>() {
+                list.add_entity(ctx, &solar_config.machine, machines);
+                if let Some(accumulator) = &solar_config.accumulator {
+                    let accumulators = (count * solar_config.accumulators_per_panel(ctx)).ceil();
+                    list.add_entity(ctx, accumulator, accumulators);
+                }
+                continue;
+            }
+            let (machine, module_config) =
+                if let Some(recipe_config) = any.downcast_ref::<RecipeConfig>() {
+                    (&recipe_config.machine, &recipe_config.module_config)
+                } else if let Some(mining_config) = any.downcast_ref::<MiningConfig>() {
+                    (&mining_config.machine, &mining_config.module_config)
+                } else if let Some(pumpjack_config) = any.downcast_ref::<PumpjackConfig>() {
+                    (&pumpjack_config.machine, &pumpjack_config.module_config)
+                } else if let Some(lab_config) = any.downcast_ref::<LabConfig>() {
+                    (&lab_config.machine, &lab_config.module_config)
+                } else {
+                    continue;
+                };
+            list.add_entity(ctx, machine, machines);
+            for module in &module_config.modules {
+                add_entry(&mut list.buildings, module.clone(), machines);
+            }
+            // 插件塔按每台机器独立计算，相邻机器共享插件塔时实际数量会更少
+            for beacon in &module_config.beacons {
+                let beacons = (beacon.count as f64) * machines;
+                list.add_entity(ctx, &beacon.beacon, beacons);
+                for (module, amount) in &beacon.modules {
+                    add_entry(
+                        &mut list.buildings,
+                        module.clone(),
+                        *amount as f64 * beacons,
+                    );
+                }
+            }
+        }
+        for (item, amount) in list.buildings.clone() {
+            list.expand(ctx, &item, amount, &mut vec![]);
+        }
+        list
+    }
+
+    fn add_entity(&mut self, ctx: &FactorioContext, entity: &IdWithQuality, amount: f64) {
+        let item = item_placing_entity(ctx, &entity.0).unwrap_or_else(|| entity.0.clone());
+        add_entry(&mut self.buildings, IdWithQuality(item, entity.1), amount);
+    }
+
+    /// 把物品展开为原料，stack 记录当前展开路径上的物品
+    fn expand(
+        &mut self,
+        ctx: &FactorioContext,
+        item: &IdWithQuality,
+        amount: f64,
+        stack: &mut Vec<String>,
+    ) {
+        let recipe = recipe_for_item(ctx, &item.0);
+        let Some(recipe) =
+            recipe.filter(|_| stack.len() < MAX_EXPAND_DEPTH && !stack.contains(&item.0))
+        else {
+            add_entry(&mut self.raw, item.clone(), amount);
+            return;
+        };
+        let yield_per_craft = recipe
+            .results
+            .iter()
+            .map(|result| match result {
+                RecipeResult::Item(result) if result.name == item.0 => result.normalized_output().0,
+                _ => 0.0,
+            })
+            .sum::<f64>();
+        if yield_per_craft <= 0.0 {
+            add_entry(&mut self.raw, item.clone(), amount);
+            return;
+        }
+        let crafts = amount / yield_per_craft;
+        stack.push(item.0.clone());
+        for ingredient in &recipe.ingredients {
+            match ingredient {
+                RecipeIngredient::Item(ingredient) => {
+                    let ingredient_item = IdWithQuality(ingredient.name.clone(), item.1);
+                    let needed = ingredient.amount * crafts;
+                    if recipe_for_item(ctx, &ingredient.name).is_some() {
+                        add_entry(&mut self.intermediates, ingredient_item.clone(), needed);
+                    }
+                    self.expand(ctx, &ingredient_item, needed, stack);
+                }
+                RecipeIngredient::Fluid(ingredient) => {
+                    // 流体不区分品质，也不再继续展开
+                    add_entry(
+                        &mut self.raw,
+                        IdWithQuality(ingredient.name.clone(), 0),
+                        ingredient.amount * crafts,
+                    );
+                }
+            }
+        }
+        stack.pop();
+    }
+}
+
+fn build_list_table(
+    ui: &mut egui::Ui,
+    id: &str,
+    entries: &IndexMap<IdWithQuality, f64>,
+    names: &IndexMap<IdWithQuality, String>,
+) {
+    egui::Grid::new(id)
+        .striped(true)
+        .num_columns(2)
+        .spacing([16.0, 4.0])
+        .show(ui, |ui| {
+            for (key, amount) in entries {
+                ui.label(names.get(key).cloned().unwrap_or_else(|| key.0.clone()));
+                ui.add(CompactLabel::new(*amount));
+                ui.end_row();
+            }
+        });
+}
+
+/// 建造清单视图，物品名称在创建时从上下文中取出
+pub struct BuildListView {
+    pub list: BuildList,
+    pub names: IndexMap<IdWithQuality, String>,
+}
+
+impl BuildListView {
+    pub fn new(list: BuildList, ctx: &FactorioContext) -> Self {
+        let names = list
+            .buildings
+            .keys()
+            .chain(list.intermediates.keys())
+            .chain(list.raw.keys())
+            .map(|key| {
+                let category = if ctx.fluids.contains_key(&key.0) {
+                    "fluid"
+                } else {
+                    "item"
+                };
+                (
+                    key.clone(),
+                    ctx.get_quality_suffixed_name(category, &key.0, key.1),
+                )
+            })
+            .collect();
+        Self { list, names }
+    }
+}
+
+impl Subview for BuildListView {
+    fn view(&mut self, ui: &mut egui::Ui) {
+        ui.heading(format!("建造清单：{}", self.list.factory_name));
+        ui.label("机器数量向上取整；插件塔按每台机器单独计算，相邻机器共享插件塔时实际所需更少");
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.heading("建筑与插件");
+            build_list_table(
+                ui,
+                "build-list-buildings",
+                &self.list.buildings,
+                &self.names,
+            );
+            ui.separator();
+            ui.heading("中间产物");
+            build_list_table(
+                ui,
+                "build-list-intermediates",
+                &self.list.intermediates,
+                &self.names,
+            );
+            ui.separator();
+            ui.heading("原料总计");
+            build_list_table(ui, "build-list-raw", &self.list.raw, &self.names);
+        });
+    }
+
+    fn name(&self) -> String {
+        format!("建造清单 - {}", self.list.factory_name)
+    }
+
+    fn description(&self) -> String {
+        "方案所需的建筑、插件及其展开后的原料".to_string()
+    }
+}
+
+#[test]
+fn test_build_list() {
+    let ctx = FactorioContext::test_load();
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let id = MechanicId::generate();
+    factory.mechanics.insert(
+        id,
+        Box::new(RecipeConfig {
+            recipe: "iron-gear-wheel".into(),
+            machine: "assembling-machine-1".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+    factory.solution.0.insert(id, 1.5);
+    let list = BuildList::collect(&factory, &ctx);
+    assert_eq!(
+        list.buildings[&IdWithQuality::from("assembling-machine-1")],
+        2.0
+    );
+    // 组装机 1 型：3 铁齿轮、5 电路板、9 铁板
+    assert!(
+        list.intermediates
+            .contains_key(&IdWithQuality::from("iron-gear-wheel"))
+    );
+    assert!(list.raw.contains_key(&IdWithQuality::from("iron-ore")));
+}
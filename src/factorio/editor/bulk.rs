@@ -0,0 +1,293 @@
+use crate::factorio::{
+    common::*,
+    editor::{
+        icon::GenericIcon,
+        modal::{ItemSelectorModal, ItemWithQualitySelectorModal},
+    },
+    model::*,
+};
+
+/// 批量编辑时套用到选中配方的模板，未勾选的部分保持不变
+#[derive(Debug, Clone)]
+pub struct BulkEditTemplate {
+    pub apply_machine: bool,
+    pub machine: IdWithQuality,
+    pub apply_modules: bool,
+    pub module_config: ModuleConfig,
+    pub apply_fuel: bool,
+    /// None 表示使用抽象燃料
+    pub fuel: Option<(String, i32)>,
+}
+
+impl Default for BulkEditTemplate {
+    fn default() -> Self {
+        Self {
+            apply_machine: false,
+            machine: "entity-unknown".into(),
+            apply_modules: false,
+            module_config: ModuleConfig::new(),
+            apply_fuel: false,
+            fuel: None,
+        }
+    }
+}
+
+/// 批量编辑的结果统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BulkEditOutcome {
+    pub edited: usize,
+    /// 机器不能制作该配方或不能使用该燃料而保持原样的次数
+    pub skipped: usize,
+}
+
+fn fuel_fits(crafter: &CraftingMachinePrototype, fuel: &str, ctx: &FactorioContext) -> bool {
+    let EnergySource::Burner(source) = &crafter.energy_source else {
+        return false;
+    };
+    ctx.items
+        .get(fuel)
+        .and_then(|item| item.burn.as_ref())
+        .is_some_and(|burn| {
+            source.fuel_categories.contains(
+                burn.fuel_category
+                    .as_ref()
+                    .unwrap_or(&"chemical".to_string()),
+            )
+        })
+}
+
+impl BulkEditTemplate {
+    /// 将模板套用到一个配方上，机器不能制作该配方时保留原机器，插件按机器与配方的限制筛选
+    pub fn apply(
+        &self,
+        config: &mut RecipeConfig,
+        ctx: &FactorioContext,
+        outcome: &mut BulkEditOutcome,
+    ) {
+        let Some(recipe) = ctx.recipes.get(&config.recipe.0) else {
+            outcome.skipped += 1;
+            return;
+        };
+        let mut skipped = false;
+        if self.apply_machine && self.machine.0 != config.machine.0 {
+            match ctx.crafters.get(&self.machine.0) {
+                Some(crafter) if machine_fits_for_recipe(crafter, recipe) => {
+                    config.machine = self.machine.clone();
+                    // 新机器的能源类型可能不同，原有的燃料不再适用
+                    if config
+                        .instance_fuel
+                        .as_ref()
+                        .is_some_and(|(fuel, _)| !fuel_fits(crafter, fuel, ctx))
+                    {
+                        config.instance_fuel = None;
+                    }
+                }
+                _ => skipped = true,
+            }
+        } else if self.apply_machine {
+            config.machine.1 = self.machine.1;
+        }
+        let crafter = ctx.crafters.get(&config.machine.0);
+        if self.apply_modules
+            && let Some(crafter) = crafter
+        {
+            let (allowed_effects, allowed_module_categories) =
+                recipe_module_limits(crafter, recipe);
+            config.module_config = self.module_config.restricted(
+                ctx,
+                crafter.module_slots as usize,
+                &allowed_effects,
+                &allowed_module_categories,
+            );
+        }
+        if self.apply_fuel {
+            match (&self.fuel, crafter) {
+                (None, _) => config.instance_fuel = None,
+                (Some(fuel), Some(crafter)) if fuel_fits(crafter, &fuel.0, ctx) => {
+                    config.instance_fuel = Some(fuel.clone());
+                }
+                _ => skipped = true,
+            }
+        }
+        if skipped {
+            outcome.skipped += 1;
+        } else {
+            outcome.edited += 1;
+        }
+    }
+}
+
+/// 批量编辑窗口的内容，点击应用时设置 apply
+pub struct BulkEditor<'a> {
+    pub ctx: &'a FactorioContext,
+    pub template: &'a mut BulkEditTemplate,
+    /// 选中机制的配方，用于筛选可选的机器
+    pub recipes: Vec<&'a RecipePrototype>,
+    pub apply: Option<&'a mut bool>,
+}
+
+impl<'a> BulkEditor<'a> {
+    pub fn new(
+        ctx: &'a FactorioContext,
+        template: &'a mut BulkEditTemplate,
+        recipes: Vec<&'a RecipePrototype>,
+    ) -> Self {
+        Self {
+            ctx,
+            template,
+            recipes,
+            apply: None,
+        }
+    }
+
+    pub fn notify_apply(mut self, apply: &'a mut bool) -> Self {
+        self.apply = Some(apply);
+        self
+    }
+}
+
+impl egui::Widget for BulkEditor<'_> {
+    fn ui(mut self, ui: &mut egui::Ui) -> egui::Response {
+        let ctx = self.ctx;
+        ui.heading("批量编辑");
+        ui.label(format!("将套用到选中的 {} 个配方", self.recipes.len()));
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.template.apply_machine, "机器");
+            ui.add_enabled_ui(self.template.apply_machine, |ui| {
+                let button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        GenericIcon::new(ctx, &GenericItem::Entity(self.template.machine.clone())),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text("不能制作某个配方的机器不会套用到该配方");
+                let recipes = &self.recipes;
+                let whitelist = MachineWhitelist::current(ui.ctx());
+                ui.add(
+                    ItemWithQualitySelectorModal::new(button.id, ctx, "选择制造设备", "entity")
+                        .with_toggle(button.clicked())
+                        .with_filter(|name, ctx| {
+                            whitelist.is_allowed(name)
+                                && ctx.crafters.get(name).is_some_and(|crafter| {
+                                    recipes
+                                        .iter()
+                                        .any(|recipe| machine_fits_for_recipe(crafter, recipe))
+                                })
+                        })
+                        .with_current(&mut self.template.machine),
+                );
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.template.apply_modules, "插件")
+                .on_hover_text("每个配方只保留其机器与配方允许的插件");
+            ui.add_enabled_ui(self.template.apply_modules, |ui| {
+                // 模板本身不受限制，套用时再按各配方筛选
+                let module_slots = ctx
+                    .crafters
+                    .values()
+                    .map(|crafter| crafter.module_slots as usize)
+                    .max()
+                    .unwrap_or(0);
+                let allowed_effects = Some(EffectTypeLimitation::new(true, true, true, true, true));
+                ui.add(ModuleConfigEditor::new(
+                    ctx,
+                    &mut self.template.module_config,
+                    module_slots,
+                    &allowed_effects,
+                    &None,
+                ));
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.template.apply_fuel, "燃料")
+                .on_hover_text("只套用到使用该类燃料的机器");
+            ui.add_enabled_ui(self.template.apply_fuel, |ui| {
+                let fuel = self.template.fuel.as_ref().map(|(name, quality)| {
+                    GenericItem::Item(IdWithQuality(name.clone(), *quality as u8))
+                });
+                let button = match &fuel {
+                    Some(fuel) => {
+                        ui.add_sized(crate::settings::icon_cell(), GenericIcon::new(ctx, fuel))
+                    }
+                    None => ui.label("抽象燃料"),
+                }
+                .interact(egui::Sense::click());
+                let mut selected = None;
+                ui.add(
+                    ItemSelectorModal::new(button.id, ctx, "选择燃料", "item")
+                        .with_toggle(button.clicked())
+                        .with_filter(|name, ctx| {
+                            ctx.items.get(name).is_some_and(|item| item.burn.is_some())
+                        })
+                        .with_output(&mut selected),
+                );
+                if let Some(name) = selected {
+                    self.template.fuel = Some((name, 0));
+                }
+                if self.template.fuel.is_some() && ui.button("改用抽象燃料").clicked() {
+                    self.template.fuel = None;
+                }
+            });
+        });
+        ui.separator();
+        let enabled =
+            self.template.apply_machine || self.template.apply_modules || self.template.apply_fuel;
+        if ui.add_enabled(enabled, egui::Button::new("应用")).clicked()
+            && let Some(apply) = &mut self.apply
+        {
+            **apply = true;
+        }
+        ui.response().clone()
+    }
+}
+
+#[test]
+fn test_bulk_edit() {
+    let ctx = FactorioContext::test_load();
+    let mut gear = RecipeConfig {
+        recipe: "iron-gear-wheel".into(),
+        machine: "assembling-machine-1".into(),
+        ..Default::default()
+    };
+    let mut plate = RecipeConfig {
+        recipe: "iron-plate".into(),
+        machine: "stone-furnace".into(),
+        ..Default::default()
+    };
+    let mut template = BulkEditTemplate {
+        apply_machine: true,
+        machine: "assembling-machine-3".into(),
+        apply_modules: true,
+        ..Default::default()
+    };
+    template.module_config.modules = vec!["productivity-module-3".into(); 6];
+    let mut outcome = BulkEditOutcome::default();
+    template.apply(&mut gear, &ctx, &mut outcome);
+    template.apply(&mut plate, &ctx, &mut outcome);
+    // 组装机不能冶炼，石炉保持不变，且石炉没有插件槽
+    assert_eq!(
+        outcome,
+        BulkEditOutcome {
+            edited: 1,
+            skipped: 1
+        }
+    );
+    assert_eq!(gear.machine.0, "assembling-machine-3");
+    assert_eq!(gear.module_config.modules.len(), 4);
+    assert_eq!(plate.machine.0, "stone-furnace");
+    assert!(plate.module_config.modules.is_empty());
+    // 燃料只套用到燃烧供能的机器
+    let template = BulkEditTemplate {
+        apply_fuel: true,
+        fuel: Some(("coal".to_string(), 0)),
+        ..Default::default()
+    };
+    let mut outcome = BulkEditOutcome::default();
+    template.apply(&mut gear, &ctx, &mut outcome);
+    template.apply(&mut plate, &ctx, &mut outcome);
+    assert_eq!(gear.instance_fuel, None);
+    assert_eq!(plate.instance_fuel, Some(("coal".to_string(), 0)));
+    assert_eq!(outcome.skipped, 1);
+}
@@ -1,4 +1,112 @@
-#[derive(Debug, Clone)]
+use indexmap::IndexMap;
+
+use crate::{
+    error::AppError,
+    factorio::{
+        common::*,
+        editor::{
+            migrate::{MissingPrototype, validate_mechanic},
+            planner::MECHANIC_REGISTRY,
+        },
+        model::FactorioContext,
+    },
+};
+
+/// 剪贴板文本中机制列表的类型标记，用于识别本程序复制的内容
+pub const MECHANICS_KIND: &str = "metatorio:mechanics";
+
+/// 写入系统剪贴板的内容，带有类型标记，可在工厂之间乃至不同的程序实例之间粘贴
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CopyPastePayload<T> {
+    pub kind: String,
     pub data: T,
 }
+
+/// 将机制序列化为可写入剪贴板的 JSON 文本
+pub fn encode_mechanics<'a>(mechanics: impl IntoIterator<Item = &'a FactorioMechanic>) -> String {
+    let payload = CopyPastePayload {
+        kind: MECHANICS_KIND.to_string(),
+        data: mechanics
+            .into_iter()
+            .filter_map(|mechanic| serde_json::to_value(mechanic).ok())
+            .collect::<Vec<_>>(),
+    };
+    serde_json::to_string_pretty(&payload).unwrap_or_default()
+}
+
+/// 从剪贴板粘贴得到的机制
+#[derive(Default)]
+pub struct PastedMechanics {
+    /// 通过校验、可以加入工厂的机制
+    pub mechanics: Vec<Box<FactorioMechanic>>,
+    /// 因引用了当前上下文中不存在的原型而跳过的机制数量
+    pub skipped: usize,
+    /// 无法识别的机制数量，通常来自更新版本的程序
+    pub failed: usize,
+    /// 被跳过的机制引用的缺失原型
+    pub missing: IndexMap<MissingPrototype, usize>,
+}
+
+/// 解析剪贴板文本，逐个校验机制引用的原型是否存在于当前上下文
+pub fn decode_mechanics(text: &str, ctx: &FactorioContext) -> Result<PastedMechanics, AppError> {
+    let payload: CopyPastePayload<Vec<serde_json::Value>> = serde_json::from_str(text.trim())
+        .map_err(|_| AppError::Custom("剪贴板中没有机制".into()))?;
+    if payload.kind != MECHANICS_KIND {
+        return Err(AppError::Custom(format!(
+            "剪贴板内容的类型 {} 不是机制",
+            payload.kind
+        )));
+    }
+    let mut pasted = PastedMechanics::default();
+    for value in payload.data {
+        let Ok(mechanic) = MECHANIC_REGISTRY.deserialize(value) else {
+            pasted.failed += 1;
+            continue;
+        };
+        let mut missing = IndexMap::new();
+        validate_mechanic(&mut missing, mechanic.as_ref(), ctx);
+        if missing.is_empty() {
+            pasted.mechanics.push(mechanic);
+        } else {
+            pasted.skipped += 1;
+            for (prototype, count) in missing {
+                *pasted.missing.entry(prototype).or_insert(0) += count;
+            }
+        }
+    }
+    Ok(pasted)
+}
+
+#[test]
+fn test_mechanics_clipboard() {
+    use crate::factorio::model::RecipeConfig;
+    let ctx = FactorioContext::test_load();
+    let gear: Box<FactorioMechanic> = Box::new(RecipeConfig {
+        recipe: "iron-gear-wheel".into(),
+        machine: "assembling-machine-1".into(),
+        ..Default::default()
+    });
+    let unknown: Box<FactorioMechanic> = Box::new(RecipeConfig {
+        recipe: "modded-gear".into(),
+        machine: "assembling-machine-1".into(),
+        ..Default::default()
+    });
+    let text = encode_mechanics([gear.as_ref(), unknown.as_ref()]);
+    let pasted = decode_mechanics(&text, &ctx).unwrap();
+    assert_eq!(pasted.mechanics.len(), 1);
+    assert_eq!(pasted.skipped, 1);
+    assert_eq!(pasted.missing.keys().next().unwrap().name, "modded-gear");
+    let original = serde_json::to_value(gear.as_ref()).unwrap();
+    assert_eq!(
+        serde_json::to_value(pasted.mechanics[0].as_ref()).unwrap(),
+        original
+    );
+    // 其他内容与其他类型的载荷都不能粘贴为机制
+    assert!(decode_mechanics("iron-gear-wheel", &ctx).is_err());
+    let other = serde_json::to_string(&CopyPastePayload {
+        kind: "other".to_string(),
+        data: vec![original],
+    })
+    .unwrap();
+    assert!(decode_mechanics(&other, &ctx).is_err());
+}
@@ -0,0 +1,421 @@
+use std::sync::mpsc::Receiver;
+
+use indexmap::IndexMap;
+
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        editor::planner::{FactoryInstance, register_solver},
+        format::*,
+        model::*,
+    },
+    settings::RateUnit,
+    solver::{SolverHandle, SolverSolution},
+};
+
+/// 某一时刻的求解结果，只保留文字信息，脱离上下文也能显示
+#[derive(Debug, Clone)]
+pub struct SolutionSnapshot {
+    pub factory_name: String,
+    pub cost: f64,
+    /// 机制描述与机器数量
+    pub mechanics: IndexMap<MechanicId, (String, f64)>,
+    /// 物品描述与净流量
+    pub items: IndexMap<GenericItem, (String, f64)>,
+    /// 物料流的显示单位
+    pub rate_unit: RateUnit,
+}
+
+impl SolutionSnapshot {
+    pub fn capture(factory: &FactoryInstance, ctx: &FactorioContext) -> Self {
+        let mechanics = factory
+            .mechanics
+            .iter()
+            .map(|(id, mechanic)| {
+                (
+                    *id,
+                    (
+                        mechanic_display_name(mechanic.as_ref(), ctx),
+                        factory.solution.0.get(id).cloned().unwrap_or(0.0),
+                    ),
+                )
+            })
+            .collect();
+        let items = factory
+            .total_flow_sorted_keys
+            .iter()
+            .map(|item| {
+                (
+                    item.clone(),
+                    (
+                        ctx.get_generic_item_display_name(item),
+                        factory.total_flow.get(item).cloned().unwrap_or(0.0),
+                    ),
+                )
+            })
+            .collect();
+        Self {
+            factory_name: factory.name.clone(),
+            cost: factory.solution.1,
+            mechanics,
+            items,
+            rate_unit: factory.rate_unit(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffRow {
+    pub name: String,
+    pub before: Option<f64>,
+    pub after: Option<f64>,
+}
+
+impl DiffRow {
+    pub fn delta(&self) -> f64 {
+        self.after.unwrap_or(0.0) - self.before.unwrap_or(0.0)
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.before.is_none() != self.after.is_none() || self.delta().abs() > 1e-6
+    }
+}
+
+/// 按键合并两组数据，先列出快照中的条目，再列出新增的条目
+pub fn diff_rows<K: ItemIdent>(
+    before: &IndexMap<K, (String, f64)>,
+    after: &IndexMap<K, (String, f64)>,
+) -> Vec<DiffRow> {
+    let mut rows = vec![];
+    for (key, (name, value)) in before {
+        rows.push(DiffRow {
+            name: name.clone(),
+            before: Some(*value),
+            after: after.get(key).map(|(_, value)| *value),
+        });
+    }
+    for (key, (name, value)) in after {
+        if !before.contains_key(key) {
+            rows.push(DiffRow {
+                name: name.clone(),
+                before: None,
+                after: Some(*value),
+            });
+        }
+    }
+    rows
+}
+
+/// 快照与当前方案的对比视图
+pub struct SolutionDiffView {
+    pub before: SolutionSnapshot,
+    pub after: SolutionSnapshot,
+    pub mechanic_rows: Vec<DiffRow>,
+    pub item_rows: Vec<DiffRow>,
+    pub hide_unchanged: bool,
+}
+
+impl SolutionDiffView {
+    pub fn new(before: SolutionSnapshot, after: SolutionSnapshot) -> Self {
+        let mechanic_rows = diff_rows(&before.mechanics, &after.mechanics);
+        let item_rows = diff_rows(&before.items, &after.items);
+        Self {
+            before,
+            after,
+            mechanic_rows,
+            item_rows,
+            hide_unchanged: true,
+        }
+    }
+}
+
+/// 物料流按 unit 换算后显示，机制数量不换算
+fn diff_table(
+    ui: &mut egui::Ui,
+    id: &str,
+    rows: &[DiffRow],
+    hide_unchanged: bool,
+    unit: Option<RateUnit>,
+) {
+    let multiplier = unit.map_or(1.0, |unit| unit.multiplier());
+    egui::Grid::new(id)
+        .striped(true)
+        .num_columns(5)
+        .spacing([16.0, 4.0])
+        .show(ui, |ui| {
+            ui.strong("名称");
+            ui.strong("快照");
+            ui.strong("当前");
+            ui.strong("变化");
+            ui.strong("状态");
+            ui.end_row();
+            for row in rows {
+                if hide_unchanged && !row.is_changed() {
+                    continue;
+                }
+                ui.label(&row.name);
+                match row.before {
+                    Some(value) => ui.add(CompactLabel::new(value * multiplier)),
+                    None => ui.label("-"),
+                };
+                match row.after {
+                    Some(value) => ui.add(CompactLabel::new(value * multiplier)),
+                    None => ui.label("-"),
+                };
+                let delta = row.delta();
+                if delta.abs() > 1e-6 {
+                    ui.colored_label(
+                        if delta > 0.0 {
+                            egui::Color32::LIGHT_GREEN
+                        } else {
+                            egui::Color32::LIGHT_RED
+                        },
+                        signed_compact_number(delta * multiplier),
+                    );
+                } else {
+                    ui.label("");
+                }
+                match (row.before, row.after) {
+                    (None, Some(_)) => ui.label("新增"),
+                    (Some(_), None) => ui.label("移除"),
+                    _ if delta > 1e-6 => ui.label("增加"),
+                    _ if delta < -1e-6 => ui.label("减少"),
+                    _ => ui.label(""),
+                };
+                ui.end_row();
+            }
+        });
+}
+
+impl Subview for SolutionDiffView {
+    fn view(&mut self, ui: &mut egui::Ui) {
+        ui.heading(format!(
+            "方案对比：{} → {}",
+            self.before.factory_name, self.after.factory_name
+        ));
+        ui.horizontal(|ui| {
+            ui.label("总代价");
+            ui.add(CompactLabel::new(self.before.cost));
+            ui.label("→");
+            ui.add(CompactLabel::new(self.after.cost));
+            ui.add(SignedCompactLabel::new(self.after.cost - self.before.cost));
+        });
+        ui.checkbox(&mut self.hide_unchanged, "隐藏未变化的条目");
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.heading("机制数量");
+            diff_table(
+                ui,
+                "mechanic-diff",
+                &self.mechanic_rows,
+                self.hide_unchanged,
+                None,
+            );
+            ui.separator();
+            ui.heading(format!("物料流（{}）", self.after.rate_unit.name()));
+            diff_table(
+                ui,
+                "item-diff",
+                &self.item_rows,
+                self.hide_unchanged,
+                Some(self.after.rate_unit),
+            );
+        });
+    }
+
+    fn name(&self) -> String {
+        format!("方案对比 - {}", self.after.factory_name)
+    }
+
+    fn description(&self) -> String {
+        "快照与当前方案之间的机制数量、物料流差异".to_string()
+    }
+}
+
+/// 一个目标模式的求解句柄与结果通道
+type ModeSolver = (
+    SolverHandle<GenericItem, MechanicId>,
+    Receiver<SolverSolution<MechanicId>>,
+);
+
+/// 同一组机制在不同目标模式下的求解结果，各模式在后台求解线程中分别求解
+pub struct TargetSetComparisonView {
+    pub factory_name: String,
+    /// 模式名称与求解结果，求解中为 None，求解失败时为错误信息
+    pub modes: Vec<(String, Option<Result<f64, String>>)>,
+    /// 机制与其描述，按工厂中的顺序排列
+    pub mechanics: Vec<(MechanicId, String)>,
+    /// 各模式求解得到的机器数量
+    pub counts: Vec<Flow<MechanicId>>,
+    /// 锁定的机制及其代价，补入每个模式的求解结果
+    locked: (Flow<MechanicId>, f64),
+    /// 丢弃视图时取消尚未完成的求解
+    solvers: Vec<ModeSolver>,
+}
+
+impl TargetSetComparisonView {
+    /// 为每个目标模式提交一个求解请求，结果在 view 中陆续收取
+    pub fn solve(factory: &FactoryInstance, ctx: &FactorioContext) -> Self {
+        let mut modes = vec![];
+        let mut solvers = vec![];
+        for target_set in factory.synced_target_sets() {
+            let (solution_tx, solution_rx) = std::sync::mpsc::channel();
+            let solver = register_solver(solution_tx);
+            solver.submit(factory.solver_args(&target_set.targets, ctx));
+            modes.push((target_set.name, None));
+            solvers.push((solver, solution_rx));
+        }
+        let mechanics = factory
+            .mechanics
+            .iter()
+            .map(|(id, mechanic)| (*id, mechanic_display_name(mechanic.as_ref(), ctx)))
+            .collect();
+        let mut locked = (Flow::new(), 0.0);
+        factory.complete_solution(&mut locked, ctx);
+        Self {
+            factory_name: factory.name.clone(),
+            counts: vec![Flow::new(); modes.len()],
+            modes,
+            mechanics,
+            locked,
+            solvers,
+        }
+    }
+
+    /// 收取已完成的求解结果
+    fn receive(&mut self) {
+        for (index, (_, receiver)) in self.solvers.iter().enumerate() {
+            while let Ok(solution) = receiver.try_recv() {
+                self.modes[index].1 = Some(match solution {
+                    Ok((mut counts, cost)) => {
+                        counts.extend(self.locked.0.iter().map(|(id, count)| (*id, *count)));
+                        self.counts[index] = counts;
+                        Ok(cost + self.locked.1)
+                    }
+                    Err(err) => {
+                        self.counts[index] = Flow::new();
+                        Err(format!("{:?}", err))
+                    }
+                });
+            }
+        }
+    }
+}
+
+impl Subview for TargetSetComparisonView {
+    fn view(&mut self, ui: &mut egui::Ui) {
+        self.receive();
+        ui.heading(format!("目标模式对比：{}", self.factory_name));
+        ui.label(
+            "机器数量按各模式分别求解，利用率为按机器最多的模式建造时，机器最少的模式下的利用率，即最小值与最大值之比",
+        );
+        ui.separator();
+        egui::ScrollArea::both().show(ui, |ui| {
+            egui::Grid::new("target-set-comparison")
+                .striped(true)
+                .num_columns(self.modes.len() + 2)
+                .spacing([16.0, 4.0])
+                .show(ui, |ui| {
+                    ui.strong("机制");
+                    for (name, _) in &self.modes {
+                        ui.strong(name);
+                    }
+                    ui.strong("利用率");
+                    ui.end_row();
+                    ui.label("总代价");
+                    for (_, result) in &self.modes {
+                        match result {
+                            None => ui.add(egui::Spinner::new()),
+                            Some(Ok(cost)) => ui.add(CompactLabel::new(*cost)),
+                            Some(Err(err)) => ui
+                                .colored_label(egui::Color32::LIGHT_RED, "无解")
+                                .on_hover_text(err),
+                        };
+                    }
+                    ui.label("");
+                    ui.end_row();
+                    let solved = self.modes.iter().all(|(_, result)| result.is_some());
+                    for (id, name) in &self.mechanics {
+                        ui.label(name);
+                        let counts = self
+                            .counts
+                            .iter()
+                            .map(|counts| counts.get(id).copied().unwrap_or(0.0))
+                            .collect::<Vec<_>>();
+                        for count in &counts {
+                            ui.add(CompactLabel::new(*count));
+                        }
+                        // 按机器数量最多的模式建造时，机器最少的模式下的利用率
+                        let max = counts.iter().cloned().fold(0.0, f64::max);
+                        let min = counts.iter().cloned().fold(f64::INFINITY, f64::min);
+                        if solved && max > 1e-6 {
+                            ui.label(format!("{:.0}%", min / max * 100.0));
+                        } else {
+                            ui.label("-");
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    fn name(&self) -> String {
+        format!("模式对比 - {}", self.factory_name)
+    }
+
+    fn description(&self) -> String {
+        "同一组机制在不同目标模式下的机器数量".to_string()
+    }
+}
+
+#[test]
+fn test_diff_rows() {
+    let before = IndexMap::from([(1, ("a".to_string(), 1.0)), (2, ("b".to_string(), 2.0))]);
+    let after = IndexMap::from([(2, ("b".to_string(), 3.0)), (3, ("c".to_string(), 1.0))]);
+    let rows = diff_rows(&before, &after);
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0].after, None);
+    assert_eq!(rows[1].delta(), 1.0);
+    assert_eq!(rows[2].before, None);
+    assert!(rows.iter().all(DiffRow::is_changed));
+}
+
+#[test]
+fn test_target_set_comparison() {
+    use crate::factorio::editor::preset::TargetPreset;
+
+    let ctx = FactorioContext::test_load();
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let gear = GenericItem::Item("iron-gear-wheel".into());
+    let assembler = MechanicId::generate();
+    factory.mechanics.insert(
+        assembler,
+        Box::new(RecipeConfig {
+            recipe: "iron-gear-wheel".into(),
+            machine: "assembling-machine-2".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+    factory.target = vec![(gear.clone(), 1.0)];
+    factory.target_sets.push(TargetPreset {
+        name: "双倍".to_string(),
+        targets: vec![(gear, 2.0)],
+    });
+    let mut view = TargetSetComparisonView::solve(&factory, &ctx);
+    // 各模式在后台求解，结果陆续送达
+    let deadline = web_time::Instant::now() + std::time::Duration::from_secs(10);
+    while view.modes.iter().any(|(_, result)| result.is_none())
+        && web_time::Instant::now() < deadline
+    {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        view.receive();
+    }
+    assert!(
+        view.modes
+            .iter()
+            .all(|(_, result)| matches!(result, Some(Ok(_))))
+    );
+    assert!((view.counts[1][&assembler] - 2.0 * view.counts[0][&assembler]).abs() < 1e-6);
+}
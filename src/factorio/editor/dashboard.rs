@@ -0,0 +1,483 @@
+use std::any::Any;
+
+use indexmap::IndexMap;
+
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        editor::{
+            buildlist::is_mined,
+            metrics::{MetricsExport, MetricsFormat},
+            planner::FactoryInstance,
+            stats::StatsKey,
+        },
+        format::*,
+        model::*,
+    },
+    settings::RateUnit,
+};
+
+/// 单个工厂按求解结果汇总的数据，速率均为每秒
+#[derive(Debug, Clone, Default)]
+pub struct FactorySummary {
+    /// 工厂在规划器中的下标，跳转时使用
+    pub index: usize,
+    pub name: String,
+    /// 所在的星球或太空平台
+    pub location: Option<String>,
+    pub solved: bool,
+    /// 耗电的机制消耗的总功率（W）
+    pub power_consumption: f64,
+    /// 发电与耗电相抵后的净功率（W），负数表示需要外部供电
+    pub power_net: f64,
+    /// 各污染物的排放量，按污染物名称
+    pub pollution: IndexMap<String, f64>,
+    /// 开采与从外部输入的原料
+    pub raw: IndexMap<StatsKey, f64>,
+    /// 放置的实体数量，每个机制的台数向上取整
+    pub machines: IndexMap<String, f64>,
+    /// 研究中心消耗的科技包
+    pub science: f64,
+}
+
+fn add_entry<K: std::hash::Hash + Eq>(map: &mut IndexMap<K, f64>, key: K, amount: f64) {
+    *map.entry(key).or_insert(0.0) += amount;
+}
+
+impl FactorySummary {
+    pub fn collect(index: usize, factory: &FactoryInstance, ctx: &FactorioContext) -> Self {
+        let mut summary = FactorySummary {
+            index,
+            name: factory.name.clone(),
+            location: factory.location.clone(),
+            solved: !factory.solution.0.is_empty() && !factory.solution.1.is_nan(),
+            ..Default::default()
+        };
+        for (id, mechanic) in &factory.mechanics {
+            let count = factory.solution.0.get(id).cloned().unwrap_or(0.0);
+            if count < 1e-6 {
+                continue;
+            }
+            let is_lab = (mechanic.as_ref() as &dyn Any).is::<LabConfig>();
+            for (item, amount) in factory.mechanic_flow(mechanic.as_ref(), ctx) {
+                match &item {
+                    GenericItem::Electricity if amount < 0.0 => {
+                        summary.power_consumption -= amount * count;
+                    }
+                    GenericItem::Item(IdWithQuality(_, _)) if is_lab && amount < 0.0 => {
+                        summary.science -= amount * count;
+                    }
+                    _ => {}
+                }
+                // 开采得到的原料按产出计入，外部输入的部分在下面按净流量补上
+                if amount > 0.0
+                    && let Some(key) = StatsKey::from_generic_item(&item)
+                    && is_raw(ctx, &key)
+                {
+                    add_entry(&mut summary.raw, key, amount * count);
+                }
+            }
+            for (entity, amount) in mechanic_placed_entities(mechanic.as_ref(), ctx) {
+                add_entry(&mut summary.machines, entity, amount * count.ceil());
+            }
+        }
+        for (item, amount) in &factory.total_flow {
+            match item {
+                GenericItem::Electricity => summary.power_net += amount,
+                GenericItem::Pollution { name } => {
+                    add_entry(&mut summary.pollution, name.clone(), *amount)
+                }
+                _ => {
+                    if *amount < 0.0
+                        && let Some(key) = StatsKey::from_generic_item(item)
+                        && is_raw(ctx, &key)
+                    {
+                        add_entry(&mut summary.raw, key, -amount);
+                    }
+                }
+            }
+        }
+        summary
+    }
+
+    /// 把另一个工厂的数据累加到该汇总中
+    pub fn merge(&mut self, other: &FactorySummary) {
+        self.solved &= other.solved;
+        self.power_consumption += other.power_consumption;
+        self.power_net += other.power_net;
+        for (name, amount) in &other.pollution {
+            add_entry(&mut self.pollution, name.clone(), *amount);
+        }
+        for (key, amount) in &other.raw {
+            add_entry(&mut self.raw, key.clone(), *amount);
+        }
+        for (entity, amount) in &other.machines {
+            add_entry(&mut self.machines, entity.clone(), *amount);
+        }
+        self.science += other.science;
+    }
+}
+
+fn is_raw(ctx: &FactorioContext, key: &StatsKey) -> bool {
+    match key {
+        StatsKey::Item(name) | StatsKey::Fluid(name) => is_mined(ctx, name),
+    }
+}
+
+/// 全部打开的工厂的汇总，名称在创建时从各工厂的上下文中取出
+#[derive(Debug, Clone, Default)]
+pub struct Dashboard {
+    pub factories: Vec<FactorySummary>,
+    pub total: FactorySummary,
+    /// 原料、实体、污染物与地点的显示名称，按 (类别, 名称) 索引
+    pub names: IndexMap<(String, String), String>,
+}
+
+impl Dashboard {
+    pub fn collect<'a>(
+        factories: impl IntoIterator<Item = (&'a FactoryInstance, &'a FactorioContext)>,
+    ) -> Self {
+        let mut dashboard = Dashboard {
+            total: FactorySummary {
+                solved: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for (index, (factory, ctx)) in factories.into_iter().enumerate() {
+            let summary = FactorySummary::collect(index, factory, ctx);
+            let mut name = |category: &str, name: &str| {
+                dashboard
+                    .names
+                    .entry((category.to_string(), name.to_string()))
+                    .or_insert_with(|| ctx.get_display_name(category, name));
+            };
+            for key in summary.raw.keys() {
+                match key {
+                    StatsKey::Item(item) => name("item", item),
+                    StatsKey::Fluid(fluid) => name("fluid", fluid),
+                }
+            }
+            for entity in summary.machines.keys() {
+                name("entity", entity);
+            }
+            for pollutant in summary.pollution.keys() {
+                name("airborne-pollutant", pollutant);
+            }
+            if let Some(location) = &summary.location {
+                name("space-location", location);
+            }
+            dashboard.total.merge(&summary);
+            dashboard.factories.push(summary);
+        }
+        dashboard.total.raw.sort_by(|_, a, _, b| b.total_cmp(a));
+        dashboard
+            .total
+            .machines
+            .sort_by(|_, a, _, b| b.total_cmp(a));
+        dashboard
+    }
+
+    fn name_of(&self, category: &str, name: &str) -> String {
+        self.names
+            .get(&(category.to_string(), name.to_string()))
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    fn raw_name(&self, key: &StatsKey) -> String {
+        match key {
+            StatsKey::Item(item) => self.name_of("item", item),
+            StatsKey::Fluid(fluid) => self.name_of("fluid", fluid),
+        }
+    }
+}
+
+/// 从总览跳转到规划器中的某个工厂，规划器下一次绘制时取出
+#[derive(Debug, Clone)]
+pub struct FactoryJump {
+    pub planner: SubviewKey,
+    pub index: usize,
+    /// 打开总览之后工厂可能被关闭或重新排列，下标对不上时按名称查找
+    pub name: String,
+}
+
+impl FactoryJump {
+    fn memory_id() -> egui::Id {
+        egui::Id::new("factory-jump")
+    }
+
+    pub fn request(self, ctx: &egui::Context) {
+        self.planner.request_focus(ctx);
+        ctx.data_mut(|data| data.insert_temp(Self::memory_id(), self));
+    }
+
+    /// 取出发给该规划器的跳转请求
+    pub fn take(ctx: &egui::Context, planner: SubviewKey) -> Option<Self> {
+        ctx.data_mut(|data| {
+            let jump = data.get_temp::<Self>(Self::memory_id())?;
+            if jump.planner != planner {
+                return None;
+            }
+            data.remove::<Self>(Self::memory_id());
+            Some(jump)
+        })
+    }
+}
+
+/// 多工厂总览视图，数据为打开时的求解结果
+pub struct DashboardView {
+    pub dashboard: Dashboard,
+    pub planner: Option<SubviewKey>,
+    pub unit: RateUnit,
+}
+
+impl DashboardView {
+    pub fn new(dashboard: Dashboard, planner: Option<SubviewKey>) -> Self {
+        Self {
+            dashboard,
+            planner,
+            unit: RateUnit::global(),
+        }
+    }
+
+    fn factories_table(&self, ui: &mut egui::Ui) {
+        egui::Grid::new("dashboard-factories")
+            .striped(true)
+            .num_columns(6)
+            .spacing([16.0, 4.0])
+            .show(ui, |ui| {
+                ui.strong("工厂");
+                ui.strong("位置");
+                ui.strong("耗电");
+                ui.strong("净功率");
+                ui.strong("机器");
+                ui.strong("科技包");
+                ui.end_row();
+                for summary in &self.dashboard.factories {
+                    let label = if summary.solved {
+                        summary.name.clone()
+                    } else {
+                        format!("{}（未求解）", summary.name)
+                    };
+                    match self.planner {
+                        Some(planner) => {
+                            if ui.link(label).on_hover_text("切换到该工厂").clicked() {
+                                FactoryJump {
+                                    planner,
+                                    index: summary.index,
+                                    name: summary.name.clone(),
+                                }
+                                .request(ui.ctx());
+                            }
+                        }
+                        None => {
+                            ui.label(label);
+                        }
+                    }
+                    ui.label(
+                        summary
+                            .location
+                            .as_ref()
+                            .map(|location| self.dashboard.name_of("space-location", location))
+                            .unwrap_or_default(),
+                    );
+                    ui.add(CompactLabel::new(summary.power_consumption).with_format("{}W"));
+                    ui.add(SignedCompactLabel::new(summary.power_net).with_format("{}W"));
+                    ui.add(CompactLabel::new(summary.machines.values().sum::<f64>()));
+                    ui.add(CompactLabel::new(summary.science * 60.0).with_format("{}/min"));
+                    ui.end_row();
+                }
+                ui.strong("合计");
+                ui.label("");
+                let total = &self.dashboard.total;
+                ui.add(CompactLabel::new(total.power_consumption).with_format("{}W"));
+                ui.add(SignedCompactLabel::new(total.power_net).with_format("{}W"));
+                ui.add(CompactLabel::new(total.machines.values().sum::<f64>()));
+                ui.add(CompactLabel::new(total.science * 60.0).with_format("{}/min"));
+                ui.end_row();
+            });
+    }
+
+    /// unit 给出每行的速率单位，为 None 时按数量显示
+    fn amount_table<K>(
+        &self,
+        ui: &mut egui::Ui,
+        id: &str,
+        entries: &IndexMap<K, f64>,
+        name: impl Fn(&K) -> String,
+        unit: impl Fn(&K) -> Option<RateUnit>,
+    ) {
+        egui::Grid::new(id)
+            .striped(true)
+            .num_columns(2)
+            .spacing([16.0, 4.0])
+            .show(ui, |ui| {
+                for (key, amount) in entries {
+                    ui.label(name(key));
+                    match unit(key) {
+                        Some(unit) => ui.add(
+                            CompactLabel::rate(*amount, unit)
+                                .with_format(&format!("{{}}{}", unit.suffix())),
+                        ),
+                        None => ui.add(CompactLabel::new(*amount)),
+                    };
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+impl Subview for DashboardView {
+    fn view(&mut self, ui: &mut egui::Ui) {
+        ui.heading("工厂总览");
+        ui.label("汇总打开总览时全部工厂的求解结果；点击工厂名称切换到该工厂");
+        ui.horizontal(|ui| {
+            ui.label("速率单位");
+            egui::ComboBox::from_id_salt("dashboard-rate-unit")
+                .selected_text(self.unit.name())
+                .show_ui(ui, |ui| {
+                    for unit in RateUnit::ALL {
+                        ui.selectable_value(&mut self.unit, unit, unit.name());
+                    }
+                });
+            for (label, format) in [
+                ("导出 JSON……", MetricsFormat::Json),
+                ("导出 Prometheus……", MetricsFormat::Prometheus),
+            ] {
+                if ui
+                    .button(label)
+                    .on_hover_text("按星球与太空平台分组导出指标，供外部绘图工具使用")
+                    .clicked()
+                    && let Some(path) = crate::file_dialog::FileDialog::new()
+                        .add_filter(label.trim_end_matches('…'), &[format.extension()])
+                        .set_file_name(format!("metrics.{}", format.extension()).as_str())
+                        .save_file()
+                {
+                    let text = MetricsExport::new(&self.dashboard).render(format);
+                    match std::fs::write(&path, text) {
+                        Ok(()) => crate::toast::success(format!("指标已导出到 {}", path.display())),
+                        Err(err) => crate::toast::error(format!("导出失败：{}", err)),
+                    }
+                }
+            }
+        });
+        ui.separator();
+        if self.dashboard.factories.is_empty() {
+            ui.label("没有打开的工厂");
+            return;
+        }
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            self.factories_table(ui);
+            ui.separator();
+            ui.heading("原料消耗");
+            self.amount_table(
+                ui,
+                "dashboard-raw",
+                &self.dashboard.total.raw,
+                |key| self.dashboard.raw_name(key),
+                |key| match key {
+                    StatsKey::Item(_) => Some(self.unit),
+                    StatsKey::Fluid(_) => Some(RateUnit::PerSecond),
+                },
+            );
+            ui.separator();
+            ui.heading("污染");
+            self.amount_table(
+                ui,
+                "dashboard-pollution",
+                &self.dashboard.total.pollution,
+                |name| self.dashboard.name_of("airborne-pollutant", name),
+                |_| Some(RateUnit::PerSecond),
+            );
+            ui.separator();
+            ui.heading("机器数量");
+            self.amount_table(
+                ui,
+                "dashboard-machines",
+                &self.dashboard.total.machines,
+                |entity| self.dashboard.name_of("entity", entity),
+                |_| None,
+            );
+        });
+    }
+
+    fn name(&self) -> String {
+        "工厂总览".to_string()
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "{} 个工厂的耗电、污染、原料与机器数量",
+            self.dashboard.factories.len()
+        )
+    }
+}
+
+#[test]
+fn test_dashboard() {
+    let ctx = FactorioContext::test_load();
+    let mut gears = FactoryInstance::new("齿轮".to_string());
+    let id = MechanicId::generate();
+    gears.mechanics.insert(
+        id,
+        Box::new(RecipeConfig {
+            recipe: "iron-gear-wheel".into(),
+            machine: "assembling-machine-2".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+    gears.solution = (IndexMap::from([(id, 1.5)]), 1.5);
+    gears.total_flow = gears.mechanic_flow(gears.mechanics[&id].as_ref(), &ctx);
+    for amount in gears.total_flow.values_mut() {
+        *amount *= 1.5;
+    }
+    let mut plates = FactoryInstance::new("铁板".to_string());
+    let id = MechanicId::generate();
+    plates.mechanics.insert(
+        id,
+        Box::new(RecipeConfig {
+            recipe: "iron-plate".into(),
+            machine: "electric-furnace".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+    plates.solution = (IndexMap::from([(id, 2.0)]), 2.0);
+    plates.total_flow = plates.mechanic_flow(plates.mechanics[&id].as_ref(), &ctx);
+    for amount in plates.total_flow.values_mut() {
+        *amount *= 2.0;
+    }
+    let unsolved = FactoryInstance::new("空".to_string());
+
+    let dashboard = Dashboard::collect([(&gears, &ctx), (&plates, &ctx), (&unsolved, &ctx)]);
+    assert_eq!(dashboard.factories.len(), 3);
+    assert!(dashboard.factories[0].solved);
+    assert!(!dashboard.factories[2].solved);
+    assert!(!dashboard.total.solved);
+    // 2 台组装机 2 型与 2 台电炉，台数向上取整
+    assert_eq!(dashboard.total.machines["assembling-machine-2"], 2.0);
+    assert_eq!(dashboard.total.machines["electric-furnace"], 2.0);
+    // 齿轮的铁板从外部输入，不算原料；铁板厂输入的铁矿算原料
+    assert!(
+        !dashboard
+            .total
+            .raw
+            .contains_key(&StatsKey::Item("iron-plate".into()))
+    );
+    assert!(dashboard.total.raw[&StatsKey::Item("iron-ore".into())] > 0.0);
+    let power = dashboard.factories[0].power_consumption + dashboard.factories[1].power_consumption;
+    assert!(power > 0.0);
+    assert!((dashboard.total.power_consumption - power).abs() < 1e-6);
+    assert!((dashboard.total.power_net + power).abs() < 1e-6);
+    assert!(
+        dashboard
+            .total
+            .pollution
+            .get("pollution")
+            .is_some_and(|amount| *amount > 0.0)
+    );
+    assert_eq!(dashboard.total.science, 0.0);
+}
@@ -0,0 +1,254 @@
+use std::any::Any;
+
+use crate::{
+    concept::*,
+    factorio::{common::*, editor::modal::show_modal, format::*, model::*, style::card_frame},
+    settings::RateUnit,
+    tutorial::{self, TutorialStep},
+};
+
+/// 备选方案中的一行：机制本身与每台机器的关键指标
+#[derive(Clone)]
+pub struct AlternativeRow {
+    pub mechanic: Box<FactorioMechanic>,
+    /// 每台机器每秒产出（正）或消耗（负）该物品的数量，不区分品质
+    pub item_rate: f64,
+    /// 每台机器的耗电功率
+    pub power: f64,
+    /// 每台机器每秒的污染
+    pub pollution: f64,
+}
+
+impl AlternativeRow {
+    pub fn new(mechanic: Box<FactorioMechanic>, item: &GenericItem, ctx: &FactorioContext) -> Self {
+        let flow = mechanic.as_flow(ctx);
+        let item_rate = flow
+            .iter()
+            .filter(|(key, _)| same_item_ignoring_quality(key, item))
+            .map(|(_, amount)| *amount)
+            .sum();
+        let power = -flow.get(&GenericItem::Electricity).cloned().unwrap_or(0.0);
+        let pollution = flow
+            .iter()
+            .filter(|(key, _)| matches!(key, GenericItem::Pollution { .. }))
+            .map(|(_, amount)| *amount)
+            .sum();
+        Self {
+            mechanic,
+            item_rate,
+            power,
+            pollution,
+        }
+    }
+}
+
+fn same_item_ignoring_quality(a: &GenericItem, b: &GenericItem) -> bool {
+    match (a, b) {
+        (GenericItem::Item(IdWithQuality(a, _)), GenericItem::Item(IdWithQuality(b, _))) => a == b,
+        (GenericItem::Fluid { name: a, .. }, GenericItem::Fluid { name: b, .. }) => a == b,
+        (GenericItem::Entity(IdWithQuality(a, _)), GenericItem::Entity(IdWithQuality(b, _))) => {
+            a == b
+        }
+        _ => a == b,
+    }
+}
+
+/// 列出某个物品的全部生产与消耗方式，便于横向比较
+#[derive(Clone, Default)]
+pub struct AlternativesExplorer {
+    pub item: Option<GenericItem>,
+    pub producers: Vec<AlternativeRow>,
+    pub consumers: Vec<AlternativeRow>,
+    /// 为每个配方额外列出其他品质的版本
+    pub expand_quality: bool,
+}
+
+impl AlternativesExplorer {
+    pub fn populate(
+        &mut self,
+        ctx: &FactorioContext,
+        item: &GenericItem,
+        providers: &[Box<FactorioMechanicProvider>],
+    ) {
+        self.item = Some(item.clone());
+        let collect = |value: f64| {
+            let mut rows = vec![];
+            for provider in providers {
+                for mechanic in provider.hint_populate(ctx, item, value) {
+                    for variant in quality_variants(mechanic, ctx, self.expand_quality) {
+                        rows.push(AlternativeRow::new(variant, item, ctx));
+                    }
+                }
+            }
+            rows
+        };
+        self.producers = collect(-1.0);
+        self.consumers = collect(1.0);
+    }
+}
+
+/// 展开配方的全部品质版本，原本的品质排在最前
+fn quality_variants(
+    mechanic: Box<FactorioMechanic>,
+    ctx: &FactorioContext,
+    expand: bool,
+) -> Vec<Box<FactorioMechanic>> {
+    let Some(recipe_config) = (mechanic.as_ref() as &dyn Any).downcast_ref::<RecipeConfig>() else {
+        return vec![mechanic];
+    };
+    if !expand {
+        return vec![mechanic];
+    }
+    let mut variants = vec![];
+    for quality in 0..ctx.qualities.len() as u8 {
+        if quality == recipe_config.recipe.1 {
+            continue;
+        }
+        let mut variant = recipe_config.clone();
+        variant.recipe.1 = quality;
+        variants.push(Box::new(variant) as Box<FactorioMechanic>);
+    }
+    variants.insert(0, mechanic);
+    variants
+}
+
+/// 备选方案浏览窗口，点击添加后通过 sender 发送到工厂
+pub struct AlternativesModal<'a> {
+    id: egui::Id,
+    ctx: &'a FactorioContext,
+    sender: &'a MechanicSender<GenericItem, FactorioContext>,
+    explorer: &'a mut AlternativesExplorer,
+    providers: &'a [Box<FactorioMechanicProvider>],
+    toggle: bool,
+}
+
+impl<'a> AlternativesModal<'a> {
+    pub fn new(
+        id: egui::Id,
+        ctx: &'a FactorioContext,
+        sender: &'a MechanicSender<GenericItem, FactorioContext>,
+        explorer: &'a mut AlternativesExplorer,
+        providers: &'a [Box<FactorioMechanicProvider>],
+    ) -> Self {
+        Self {
+            id,
+            ctx,
+            sender,
+            explorer,
+            providers,
+            toggle: false,
+        }
+    }
+
+    pub fn with_update(mut self, update: bool, item: &GenericItem) -> Self {
+        if update {
+            self.toggle = true;
+            self.explorer.populate(self.ctx, item, self.providers);
+        }
+        self
+    }
+}
+
+fn alternatives_table(
+    ui: &mut egui::Ui,
+    id: &str,
+    rows: &[AlternativeRow],
+    ctx: &FactorioContext,
+    sender: &MechanicSender<GenericItem, FactorioContext>,
+) {
+    if rows.is_empty() {
+        ui.label("无");
+        return;
+    }
+    egui::Grid::new(id)
+        .striped(true)
+        .num_columns(5)
+        .spacing([16.0, 4.0])
+        .show(ui, |ui| {
+            ui.strong("配方 @ 机器");
+            ui.strong("每台速率");
+            ui.strong("每台功率");
+            ui.strong("每台污染");
+            ui.label("");
+            ui.end_row();
+            let unit = RateUnit::global();
+            let rate_format = format!("{{}}{}", unit.suffix());
+            for row in rows {
+                ui.label(mechanic_display_name(row.mechanic.as_ref(), ctx));
+                ui.add(SignedCompactLabel::rate(row.item_rate, unit).with_format(&rate_format));
+                ui.add(CompactLabel::new(row.power).with_format("{}W"));
+                ui.add(CompactLabel::new(row.pollution).with_format("{}/s"));
+                if ui.button("添加").clicked() {
+                    sender.send(row.mechanic.clone()).unwrap();
+                    tutorial::complete(TutorialStep::AcceptHint);
+                    crate::toast::success(format!(
+                        "已添加 {}",
+                        mechanic_display_name(row.mechanic.as_ref(), ctx)
+                    ));
+                }
+                ui.end_row();
+            }
+        });
+}
+
+impl egui::Widget for AlternativesModal<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        show_modal(self.id, self.toggle, ui, |ui| {
+            let Some(item) = self.explorer.item.clone() else {
+                return;
+            };
+            ui.heading(format!(
+                "备选方案：{}",
+                self.ctx.get_generic_item_display_name(&item)
+            ));
+            if ui
+                .checkbox(&mut self.explorer.expand_quality, "展开品质版本")
+                .on_hover_text("为每个配方额外列出其他品质的版本")
+                .changed()
+            {
+                self.explorer.populate(self.ctx, &item, self.providers);
+            }
+            ui.separator();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.set_min_width(384.0);
+                card_frame(ui).show(ui, |ui| {
+                    ui.heading("生产方式");
+                    alternatives_table(
+                        ui,
+                        "alternatives-producers",
+                        &self.explorer.producers,
+                        self.ctx,
+                        self.sender,
+                    );
+                });
+                card_frame(ui).show(ui, |ui| {
+                    ui.heading("消耗方式");
+                    alternatives_table(
+                        ui,
+                        "alternatives-consumers",
+                        &self.explorer.consumers,
+                        self.ctx,
+                        self.sender,
+                    );
+                });
+            });
+        });
+        ui.response().clone()
+    }
+}
+
+#[test]
+fn test_alternatives_explorer() {
+    let ctx = FactorioContext::test_load();
+    let providers: Vec<Box<FactorioMechanicProvider>> = vec![Box::new(RecipeConfigProvider::new())];
+    let item = GenericItem::Item("iron-gear-wheel".into());
+    let mut explorer = AlternativesExplorer::default();
+    explorer.populate(&ctx, &item, &providers);
+    assert!(!explorer.producers.is_empty());
+    assert!(explorer.producers.iter().all(|row| row.item_rate > 0.0));
+    assert!(explorer.consumers.iter().all(|row| row.item_rate < 0.0));
+    let count = explorer.producers.len();
+    explorer.expand_quality = true;
+    explorer.populate(&ctx, &item, &providers);
+    assert_eq!(explorer.producers.len(), count * ctx.qualities.len());
+}
@@ -0,0 +1,321 @@
+use std::any::Any;
+
+use crate::{
+    concept::Flow,
+    factorio::{common::*, model::*},
+    i18n::tr,
+};
+
+/// 配方配置列表上方的筛选条件
+#[derive(Debug, Clone, Default)]
+pub struct MechanicFilter {
+    /// 匹配配方、机器、物品的内部名与本地化名
+    pub text: String,
+    /// 只显示消耗了净缺口物品的机制
+    pub has_deficits: bool,
+    /// 只显示使用燃烧能源的机制
+    pub uses_burner_fuel: bool,
+    /// 只显示涉及普通以上品质的机制
+    pub above_normal_quality: bool,
+    /// 只显示不能在工厂所在地点运行的机制
+    pub location_blocked: bool,
+}
+
+impl MechanicFilter {
+    pub fn is_active(&self) -> bool {
+        !self.text.is_empty()
+            || self.has_deficits
+            || self.uses_burner_fuel
+            || self.above_normal_quality
+            || self.location_blocked
+    }
+
+    pub fn matches(
+        &self,
+        mechanic: &FactorioMechanic,
+        flow: &Flow<GenericItem>,
+        total_flow: &Flow<GenericItem>,
+        location: Option<&str>,
+        ctx: &FactorioContext,
+    ) -> bool {
+        if !self.text.is_empty() {
+            let needle = self.text.to_lowercase();
+            if !mechanic_keywords(mechanic, flow, ctx)
+                .iter()
+                .any(|keyword| keyword.to_lowercase().contains(&needle))
+            {
+                return false;
+            }
+        }
+        if self.has_deficits
+            && !flow.iter().any(|(item, amount)| {
+                *amount < 0.0 && total_flow.get(item).is_some_and(|total| *total < -1e-6)
+            })
+        {
+            return false;
+        }
+        if self.uses_burner_fuel && !mechanic_uses_burner_fuel(mechanic, flow, ctx) {
+            return false;
+        }
+        if self.above_normal_quality && !mechanic_above_normal_quality(mechanic, flow) {
+            return false;
+        }
+        if self.location_blocked
+            && !location.is_some_and(|location| {
+                !mechanic_surface_violations(mechanic, ctx, location).is_empty()
+            })
+        {
+            return false;
+        }
+        true
+    }
+}
+
+impl egui::Widget for &mut MechanicFilter {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.horizontal_wrapped(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.text)
+                    .hint_text(tr("filter.text_hint"))
+                    .desired_width(200.0),
+            );
+            ui.toggle_value(&mut self.has_deficits, tr("filter.deficits"))
+                .on_hover_text(tr("filter.deficits_hint"));
+            ui.toggle_value(&mut self.uses_burner_fuel, tr("filter.burner_fuel"))
+                .on_hover_text(tr("filter.burner_fuel_hint"));
+            ui.toggle_value(&mut self.above_normal_quality, tr("filter.quality"))
+                .on_hover_text(tr("filter.quality_hint"));
+            ui.toggle_value(&mut self.location_blocked, tr("filter.location"))
+                .on_hover_text(tr("filter.location_hint"));
+            if self.is_active() && ui.button(tr("filter.clear")).clicked() {
+                *self = MechanicFilter::default();
+            }
+        })
+        .response
+    }
+}
+
+fn push_generic_item_names(keywords: &mut Vec<String>, item: &GenericItem, ctx: &FactorioContext) {
+    let (category, name) = match item {
+        GenericItem::Item(IdWithQuality(name, _)) => ("item", name),
+        GenericItem::Fluid { name, .. } => ("fluid", name),
+        GenericItem::Entity(IdWithQuality(name, _)) => ("entity", name),
+        _ => return,
+    };
+    keywords.push(name.clone());
+    keywords.push(ctx.get_display_name(category, name));
+}
+
+fn mechanic_keywords(
+    mechanic: &FactorioMechanic,
+    flow: &Flow<GenericItem>,
+    ctx: &FactorioContext,
+) -> Vec<String> {
+    let mut keywords = vec![];
+    let any = mechanic as &dyn Any;
+    if let Some(recipe_config) = any.downcast_ref::<RecipeConfig>() {
+        keywords.push(recipe_config.recipe.0.clone());
+        keywords.push(ctx.get_display_name("recipe", &recipe_config.recipe.0));
+        keywords.push(recipe_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &recipe_config.machine.0));
+    } else if let Some(mining_config) = any.downcast_ref::<MiningConfig>() {
+        keywords.push(mining_config.resource.clone());
+        keywords.push(ctx.get_display_name("entity", &mining_config.resource));
+        keywords.push(mining_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &mining_config.machine.0));
+    } else if let Some(pumpjack_config) = any.downcast_ref::<PumpjackConfig>() {
+        keywords.push(pumpjack_config.resource.clone());
+        keywords.push(ctx.get_display_name("entity", &pumpjack_config.resource));
+        keywords.push(pumpjack_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &pumpjack_config.machine.0));
+    } else if let Some(lab_config) = any.downcast_ref::<LabConfig>() {
+        keywords.push(lab_config.technology.clone());
+        keywords.push(ctx.get_display_name("technology", &lab_config.technology));
+        keywords.push(lab_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &lab_config.machine.0));
+    } else if let Some(pump_config) = any.downcast_ref::<OffshorePumpConfig>() {
+        keywords.push(pump_config.tile.clone());
+        keywords.push(ctx.get_display_name("tile", &pump_config.tile));
+        keywords.push(pump_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &pump_config.machine.0));
+    } else if let Some(boiler_config) = any.downcast_ref::<BoilerConfig>() {
+        keywords.push(boiler_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &boiler_config.machine.0));
+    } else if let Some(reactor_config) = any.downcast_ref::<ReactorConfig>() {
+        keywords.push(reactor_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &reactor_config.machine.0));
+    } else if let Some(generator_config) = any.downcast_ref::<GeneratorConfig>() {
+        keywords.push(generator_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &generator_config.machine.0));
+    } else if let Some(solar_config) = any.downcast_ref::<SolarPowerConfig>() {
+        keywords.push(solar_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &solar_config.machine.0));
+        keywords.push(ctx.get_display_name("space-location", &solar_config.planet));
+    } else if let Some(lightning_config) = any.downcast_ref::<LightningConfig>() {
+        keywords.push(lightning_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &lightning_config.machine.0));
+        keywords.push(ctx.get_display_name("space-location", &lightning_config.planet));
+    } else if let Some(reactor_config) = any.downcast_ref::<FusionReactorConfig>() {
+        keywords.push(reactor_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &reactor_config.machine.0));
+    } else if let Some(generator_config) = any.downcast_ref::<FusionGeneratorConfig>() {
+        keywords.push(generator_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &generator_config.machine.0));
+    } else if let Some(spawner_config) = any.downcast_ref::<SpawnerConfig>() {
+        keywords.push(spawner_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &spawner_config.machine.0));
+    } else if let Some(collector_config) = any.downcast_ref::<AsteroidCollectorConfig>() {
+        keywords.push(collector_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &collector_config.machine.0));
+        keywords.push(ctx.route_display_name(&collector_config.route));
+    } else if let Some(thruster_config) = any.downcast_ref::<ThrusterConfig>() {
+        keywords.push(thruster_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &thruster_config.machine.0));
+    } else if let Some(defense_config) = any.downcast_ref::<DefenseConfig>() {
+        keywords.push(defense_config.machine.0.clone());
+        keywords.push(ctx.get_display_name("entity", &defense_config.machine.0));
+    }
+    for item in flow.keys() {
+        push_generic_item_names(&mut keywords, item, ctx);
+    }
+    keywords
+}
+
+fn mechanic_uses_burner_fuel(
+    mechanic: &FactorioMechanic,
+    flow: &Flow<GenericItem>,
+    ctx: &FactorioContext,
+) -> bool {
+    if flow.keys().any(|item| {
+        matches!(
+            item,
+            GenericItem::ItemFuel { .. } | GenericItem::FluidFuel { .. }
+        )
+    }) {
+        return true;
+    }
+    // 指定了具体燃料时，流中只有燃料物品本身，需要回到机器的能源类型判断
+    let any = mechanic as &dyn Any;
+    let energy_source = if let Some(recipe_config) = any.downcast_ref::<RecipeConfig>() {
+        ctx.crafters
+            .get(&recipe_config.machine.0)
+            .map(|crafter| &crafter.energy_source)
+    } else if let Some(mining_config) = any.downcast_ref::<MiningConfig>() {
+        ctx.miners
+            .get(&mining_config.machine.0)
+            .map(|miner| &miner.energy_source)
+    } else if let Some(pumpjack_config) = any.downcast_ref::<PumpjackConfig>() {
+        ctx.miners
+            .get(&pumpjack_config.machine.0)
+            .map(|miner| &miner.energy_source)
+    } else if let Some(lab_config) = any.downcast_ref::<LabConfig>() {
+        ctx.labs
+            .get(&lab_config.machine.0)
+            .map(|lab| &lab.energy_source)
+    } else if let Some(pump_config) = any.downcast_ref::<OffshorePumpConfig>() {
+        ctx.offshore_pumps
+            .get(&pump_config.machine.0)
+            .map(|pump| &pump.energy_source)
+    } else if let Some(boiler_config) = any.downcast_ref::<BoilerConfig>() {
+        ctx.boilers
+            .get(&boiler_config.machine.0)
+            .map(|boiler| &boiler.energy_source)
+    } else if let Some(reactor_config) = any.downcast_ref::<ReactorConfig>() {
+        ctx.reactors
+            .get(&reactor_config.machine.0)
+            .map(|reactor| &reactor.energy_source)
+    } else if let Some(spawner_config) = any.downcast_ref::<SpawnerConfig>() {
+        ctx.crafters
+            .get(&spawner_config.machine.0)
+            .map(|crafter| &crafter.energy_source)
+    } else if let Some(reactor_config) = any.downcast_ref::<FusionReactorConfig>() {
+        // 聚变反应堆的电力能源之外另有燃烧能源
+        return ctx.fusion_reactors.contains_key(&reactor_config.machine.0);
+    } else {
+        None
+    };
+    match energy_source {
+        Some(EnergySource::Burner(_)) => true,
+        Some(EnergySource::Fluid(source)) => source.burns_fluid,
+        _ => false,
+    }
+}
+
+fn mechanic_above_normal_quality(mechanic: &FactorioMechanic, flow: &Flow<GenericItem>) -> bool {
+    let any = mechanic as &dyn Any;
+    let machine_quality = if let Some(recipe_config) = any.downcast_ref::<RecipeConfig>() {
+        recipe_config.machine.1.max(recipe_config.recipe.1)
+    } else if let Some(mining_config) = any.downcast_ref::<MiningConfig>() {
+        mining_config.machine.1
+    } else if let Some(pumpjack_config) = any.downcast_ref::<PumpjackConfig>() {
+        pumpjack_config.machine.1
+    } else if let Some(lab_config) = any.downcast_ref::<LabConfig>() {
+        lab_config.machine.1
+    } else if let Some(pump_config) = any.downcast_ref::<OffshorePumpConfig>() {
+        pump_config.machine.1
+    } else if let Some(boiler_config) = any.downcast_ref::<BoilerConfig>() {
+        boiler_config.machine.1
+    } else if let Some(reactor_config) = any.downcast_ref::<ReactorConfig>() {
+        reactor_config.machine.1
+    } else if let Some(generator_config) = any.downcast_ref::<GeneratorConfig>() {
+        generator_config.machine.1
+    } else if let Some(reactor_config) = any.downcast_ref::<FusionReactorConfig>() {
+        reactor_config.machine.1
+    } else if let Some(generator_config) = any.downcast_ref::<FusionGeneratorConfig>() {
+        generator_config.machine.1
+    } else if let Some(lightning_config) = any.downcast_ref::<LightningConfig>() {
+        lightning_config.machine.1
+    } else if let Some(spawner_config) = any.downcast_ref::<SpawnerConfig>() {
+        spawner_config.machine.1
+    } else if let Some(collector_config) = any.downcast_ref::<AsteroidCollectorConfig>() {
+        collector_config.machine.1
+    } else if let Some(thruster_config) = any.downcast_ref::<ThrusterConfig>() {
+        thruster_config.machine.1
+    } else if let Some(defense_config) = any.downcast_ref::<DefenseConfig>() {
+        defense_config.machine.1
+    } else if let Some(solar_config) = any.downcast_ref::<SolarPowerConfig>() {
+        solar_config.machine.1.max(
+            solar_config
+                .accumulator
+                .as_ref()
+                .map_or(0, |accumulator| accumulator.1),
+        )
+    } else {
+        0
+    };
+    machine_quality > 0
+        || flow.keys().any(|item| match item {
+            GenericItem::Item(IdWithQuality(_, quality))
+            | GenericItem::Entity(IdWithQuality(_, quality)) => *quality > 0,
+            _ => false,
+        })
+}
+
+#[test]
+fn test_mechanic_filter() {
+    use crate::concept::AsFlow;
+    let ctx = FactorioContext::test_load();
+    let recipe_config = RecipeConfig {
+        recipe: "iron-gear-wheel".into(),
+        machine: "assembling-machine-2".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+    };
+    let flow = recipe_config.as_flow(&ctx);
+    let total_flow = Flow::new();
+    let mut filter = MechanicFilter {
+        text: "GEAR".to_string(),
+        ..Default::default()
+    };
+    assert!(filter.matches(&recipe_config, &flow, &total_flow, None, &ctx));
+    filter.text = "iron-plate".to_string();
+    assert!(filter.matches(&recipe_config, &flow, &total_flow, None, &ctx));
+    filter.text = "copper-cable".to_string();
+    assert!(!filter.matches(&recipe_config, &flow, &total_flow, None, &ctx));
+    filter.text.clear();
+    filter.uses_burner_fuel = true;
+    assert!(!filter.matches(&recipe_config, &flow, &total_flow, None, &ctx));
+    filter.uses_burner_fuel = false;
+    filter.above_normal_quality = true;
+    assert!(!filter.matches(&recipe_config, &flow, &total_flow, None, &ctx));
+}
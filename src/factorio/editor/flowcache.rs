@@ -0,0 +1,87 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    concept::*,
+    factorio::{common::*, model::*},
+};
+
+/// 按全局加成计算的机制物料流的缓存，绘制与构造求解参数时复用，不必每帧重新计算。
+/// 机制被编辑时逐个失效；上下文重新加载或全局加成变化时整体失效
+#[derive(Debug, Default)]
+pub struct FlowCache {
+    /// 计算缓存时使用的上下文地址，上下文重新加载后地址改变
+    context: usize,
+    bonuses: GlobalBonuses,
+    flows: HashMap<MechanicId, Arc<Flow<GenericItem>>>,
+}
+
+impl FlowCache {
+    /// 上下文或全局加成与缓存时不同则清空缓存，每帧或每次构造求解参数前调用一次
+    pub fn validate(&mut self, bonuses: &GlobalBonuses, ctx: &FactorioContext) {
+        let context = ctx as *const FactorioContext as usize;
+        if self.context != context || self.bonuses != *bonuses {
+            self.context = context;
+            self.bonuses = bonuses.clone();
+            self.flows.clear();
+        }
+    }
+
+    pub fn invalidate(&mut self, id: MechanicId) {
+        self.flows.remove(&id);
+    }
+
+    pub fn clear(&mut self) {
+        self.flows.clear();
+    }
+
+    pub fn get(
+        &mut self,
+        id: MechanicId,
+        mechanic: &FactorioMechanic,
+        bonuses: &GlobalBonuses,
+        ctx: &FactorioContext,
+    ) -> Arc<Flow<GenericItem>> {
+        self.flows
+            .entry(id)
+            .or_insert_with(|| Arc::new(bonuses.mechanic_flow(mechanic, ctx)))
+            .clone()
+    }
+}
+
+#[test]
+fn test_flow_cache() {
+    let ctx = FactorioContext::test_load();
+    let mut config = RecipeConfig {
+        recipe: "iron-gear-wheel".into(),
+        machine: "assembling-machine-2".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+    };
+    let id = MechanicId::generate();
+    let mut bonuses = GlobalBonuses::default();
+    let mut cache = FlowCache::default();
+    cache.validate(&bonuses, &ctx);
+    let gear = GenericItem::Item("iron-gear-wheel".into());
+    let first = cache.get(id, &config, &bonuses, &ctx);
+    // 未失效时返回同一份结果，即使机制已被修改
+    config.machine = "assembling-machine-3".into();
+    assert!(Arc::ptr_eq(&first, &cache.get(id, &config, &bonuses, &ctx)));
+    cache.invalidate(id);
+    let faster = cache.get(id, &config, &bonuses, &ctx);
+    assert!(faster[&gear] > first[&gear]);
+    // 全局加成变化后整体失效
+    cache.validate(&bonuses, &ctx);
+    assert!(Arc::ptr_eq(
+        &faster,
+        &cache.get(id, &config, &bonuses, &ctx)
+    ));
+    bonuses
+        .research
+        .levels
+        .insert("steel-plate-productivity".to_string(), 1);
+    cache.validate(&bonuses, &ctx);
+    assert!(!Arc::ptr_eq(
+        &faster,
+        &cache.get(id, &config, &bonuses, &ctx)
+    ));
+}
@@ -31,9 +31,9 @@ impl<'a> egui::Widget for PrototypeHover<'a, RecipePrototype> {
                     ui.label("无原料");
                 } else {
                     egui::Grid::new("recipe")
-                        .min_col_width(35.0)
+                        .min_col_width(crate::settings::icon_cell()[0])
                         .max_col_width(105.0)
-                        .min_row_height(35.0)
+                        .min_row_height(crate::settings::icon_cell()[1])
                         .spacing(Vec2 { x: 0.0, y: 0.0 })
                         .show(ui, |ui| {
                             for ingredient in ingredients.iter() {
@@ -100,9 +100,9 @@ impl<'a> egui::Widget for PrototypeHover<'a, RecipePrototype> {
                     ui.end_row();
                 } else {
                     egui::Grid::new("result")
-                        .min_col_width(35.0)
+                        .min_col_width(crate::settings::icon_cell()[0])
                         .max_col_width(105.0)
-                        .min_row_height(35.0)
+                        .min_row_height(crate::settings::icon_cell()[1])
                         .spacing(Vec2 { x: 0.0, y: 0.0 })
                         .show(ui, |ui| {
                             for result in results.iter() {
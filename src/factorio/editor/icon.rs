@@ -1,6 +1,8 @@
+use std::path::PathBuf;
+
 use egui::Vec2;
 
-use crate::factorio::*;
+use crate::factorio::{editor::icon_cache::*, *};
 
 #[derive(Debug)]
 
@@ -19,7 +21,7 @@ impl<'a> Icon<'a> {
             type_name,
             item_name,
             quality: 0,
-            size: 32.0,
+            size: crate::settings::get().icon_size,
         }
     }
 
@@ -33,66 +35,102 @@ impl<'a> Icon<'a> {
         self
     }
 
-    pub fn image(&'_ self) -> egui::Image<'_> {
+    /// 图标文件路径
+    pub fn path(&self) -> PathBuf {
         let root_path = &self.ctx.icon_path;
         // 某个 type 的 order info 存在，但是没有对应的物品，视为物品不存在
         // 某个 type 的 order info 不存在，当作存在
-        let icon_path = if self
+        if self
             .ctx
             .order_of_entries
             .get(self.type_name)
             .is_some_and(|v| v.get(self.item_name).is_none())
         {
-            format!(
-                "file://{}/{}/{}.png",
-                root_path.to_string_lossy(),
-                "item",
-                "item-unknown"
-            )
+            root_path.join("item").join("item-unknown.png")
         } else {
-            format!(
-                "file://{}/{}/{}.png",
-                root_path.to_string_lossy(),
-                self.type_name,
-                self.item_name
-            )
-        };
-        egui::Image::new(icon_path)
+            root_path
+                .join(self.type_name)
+                .join(format!("{}.png", self.item_name))
+        }
+    }
+
+    /// 自身图标缺失时依次尝试的图标：配方使用主产物的图标，最后是未知物品
+    pub fn fallback_paths(&self) -> Vec<PathBuf> {
+        let root_path = &self.ctx.icon_path;
+        let mut paths = vec![];
+        if self.type_name == "recipe"
+            && let Some(recipe) = self.ctx.recipes.get(self.item_name)
+        {
+            let product = match (&recipe.main_product, recipe.results.as_slice()) {
+                (Some(main_product), _) => Some(main_product.clone()),
+                (None, [RecipeResult::Item(r)]) => Some(r.name.clone()),
+                (None, [RecipeResult::Fluid(r)]) => Some(r.name.clone()),
+                _ => None,
+            };
+            if let Some(product) = product {
+                let folder = if self.ctx.fluids.contains_key(&product) {
+                    "fluid"
+                } else {
+                    "item"
+                };
+                paths.push(root_path.join(folder).join(format!("{}.png", product)));
+            }
+        }
+        paths.push(root_path.join("item").join("item-unknown.png"));
+        paths
     }
 }
 
+/// 绘制缓存中第一个可用的图标，加载中显示转圈，全部缺失时显示问号
+fn cached_image(ui: &mut egui::Ui, paths: &[PathBuf], size: f32) -> egui::Response {
+    for path in paths {
+        match icon_texture(ui.ctx(), path) {
+            IconState::Ready(texture) => {
+                return ui.add(
+                    egui::Image::new(&texture)
+                        .max_size(Vec2 { x: size, y: size })
+                        .maintain_aspect_ratio(true)
+                        .shrink_to_fit(),
+                );
+            }
+            IconState::Loading => {
+                return ui.add_sized([size, size], egui::Spinner::new().size(size * 0.5));
+            }
+            IconState::Missing => {}
+        }
+    }
+    ui.add_sized([size, size], egui::Label::new("?"))
+}
+
 impl<'a> egui::Widget for Icon<'a> {
     fn ui(self, ui: &mut egui::Ui) -> egui::Response {
-        let root_path = &self.ctx.icon_path;
         egui::Frame::NONE
             .fill(egui::Color32::from_rgba_premultiplied(
                 0xaa, 0xaa, 0xaa, 0xcc,
             ))
             .corner_radius(4.0)
             .show(ui, |ui| {
-                let icon = ui.add(
-                    self.image()
-                        .max_size(Vec2 {
-                            x: self.size,
-                            y: self.size,
-                        })
-                        .maintain_aspect_ratio(true)
-                        .shrink_to_fit()
-                        .show_loading_spinner(true),
-                );
-                if self.quality > 0 {
+                let mut paths = vec![self.path()];
+                paths.extend(self.fallback_paths());
+                let icon = cached_image(ui, &paths, self.size);
+                if self.quality > 0
+                    && let Some(quality) = self.ctx.qualities.get(self.quality as usize)
+                    && let IconState::Ready(texture) = icon_texture(
+                        ui.ctx(),
+                        &self
+                            .ctx
+                            .icon_path
+                            .join("quality")
+                            .join(format!("{}.png", quality.base.name)),
+                    )
+                {
                     ui.put(
                         icon.rect
                             .split_left_right_at_fraction(0.5)
                             .0
                             .split_top_bottom_at_fraction(0.5)
                             .1,
-                        egui::Image::new(format!(
-                            "file://{}/{}/{}.png",
-                            root_path.to_string_lossy(),
-                            "quality",
-                            self.ctx.qualities[self.quality as usize].base.name
-                        )),
+                        egui::Image::new(&texture),
                     );
                 }
             })
@@ -112,7 +150,7 @@ impl<'a> GenericIcon<'a> {
         Self {
             ctx,
             item,
-            size: 32.0,
+            size: crate::settings::get().icon_size,
         }
     }
 
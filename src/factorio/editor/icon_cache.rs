@@ -0,0 +1,198 @@
+//! 图标纹理缓存。
+//!
+//! 图标文件在后台线程解码，解码结果在界面线程上传为纹理，并按最近使用的顺序保留；
+//! 超出容量时淘汰最久未使用的纹理。解码完成前由调用方绘制占位图。
+//! 网页版没有后台线程，图标在界面线程直接解码；上传的图标保存在内存中，按虚拟路径读取。
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        mpsc::{Receiver, Sender},
+    },
+};
+
+/// 缓存中最多保留的纹理数量
+pub const ICON_CACHE_CAPACITY: usize = 2048;
+
+/// 后台解码线程数
+#[cfg(not(target_arch = "wasm32"))]
+const DECODE_THREADS: usize = 2;
+
+pub enum IconState {
+    Ready(egui::TextureHandle),
+    Loading,
+    /// 文件不存在或无法解码
+    Missing,
+}
+
+enum IconSlot {
+    Loading,
+    Ready(egui::TextureHandle),
+    Missing,
+}
+
+struct IconCache {
+    /// 每个图标文件的状态与最近一次使用的时刻
+    slots: HashMap<PathBuf, (IconSlot, u64)>,
+    clock: u64,
+    request_sender: Sender<PathBuf>,
+    result_receiver: Receiver<(PathBuf, Option<egui::ColorImage>)>,
+}
+
+lazy_static::lazy_static! {
+    static ref ICON_CACHE: Mutex<IconCache> = Mutex::new(IconCache::new());
+    static ref MEMORY_ICONS: Mutex<HashMap<PathBuf, Arc<[u8]>>> = Mutex::new(HashMap::new());
+}
+
+/// 登记保存在内存中的图标，之后读取这些路径时不再访问文件系统
+pub fn register_memory_icons(icons: impl IntoIterator<Item = (PathBuf, Vec<u8>)>) {
+    let mut memory = MEMORY_ICONS.lock().unwrap();
+    for (path, content) in icons {
+        memory.insert(path, content.into());
+    }
+}
+
+/// 读取并解码图标文件
+pub fn decode_icon(path: &Path) -> Option<egui::ColorImage> {
+    let content = MEMORY_ICONS.lock().unwrap().get(path).cloned();
+    let image = match content {
+        Some(content) => image::load_from_memory(&content).ok()?,
+        None => image::open(path).ok()?,
+    }
+    .to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Some(egui::ColorImage::from_rgba_unmultiplied(
+        size,
+        image.as_flat_samples().as_slice(),
+    ))
+}
+
+impl IconCache {
+    fn new() -> Self {
+        let (request_sender, request_receiver) = std::sync::mpsc::channel::<PathBuf>();
+        let (result_sender, result_receiver) = std::sync::mpsc::channel();
+        let request_receiver = Arc::new(Mutex::new(request_receiver));
+        // 网页版没有线程，图标在 get 中同步解码
+        #[cfg(target_arch = "wasm32")]
+        let _ = (request_receiver, result_sender);
+        #[cfg(not(target_arch = "wasm32"))]
+        for _ in 0..DECODE_THREADS {
+            let request_receiver = request_receiver.clone();
+            let result_sender = result_sender.clone();
+            std::thread::spawn(move || {
+                loop {
+                    let Ok(path) = request_receiver.lock().unwrap().recv() else {
+                        return;
+                    };
+                    let image = decode_icon(&path);
+                    if result_sender.send((path, image)).is_err() {
+                        return;
+                    }
+                    crate::repaint::wake();
+                }
+            });
+        }
+        Self {
+            slots: HashMap::new(),
+            clock: 0,
+            request_sender,
+            result_receiver,
+        }
+    }
+
+    /// 把解码结果上传为纹理
+    fn upload(ctx: &egui::Context, path: &Path, image: Option<egui::ColorImage>) -> IconSlot {
+        match image {
+            Some(image) => IconSlot::Ready(ctx.load_texture(
+                path.to_string_lossy(),
+                image,
+                egui::TextureOptions::LINEAR,
+            )),
+            None => {
+                log::warn!("无法加载图标 {}", path.display());
+                IconSlot::Missing
+            }
+        }
+    }
+
+    /// 上传已解码完成的图标
+    fn receive(&mut self, ctx: &egui::Context) {
+        while let Ok((path, image)) = self.result_receiver.try_recv() {
+            let slot = Self::upload(ctx, &path, image);
+            let used = self.slots.get(&path).map_or(self.clock, |(_, used)| *used);
+            self.slots.insert(path, (slot, used));
+        }
+    }
+
+    /// 超出容量时淘汰最久未使用的图标，一次淘汰到容量的四分之三，避免每帧排序
+    fn evict(&mut self) {
+        if self.slots.len() <= ICON_CACHE_CAPACITY {
+            return;
+        }
+        let mut used = self
+            .slots
+            .iter()
+            .filter(|(_, (slot, _))| !matches!(slot, IconSlot::Loading))
+            .map(|(path, (_, used))| (*used, path.clone()))
+            .collect::<Vec<_>>();
+        used.sort();
+        let excess = self.slots.len() - ICON_CACHE_CAPACITY * 3 / 4;
+        for (_, path) in used.into_iter().take(excess) {
+            self.slots.remove(&path);
+        }
+    }
+
+    fn get(&mut self, ctx: &egui::Context, path: &Path) -> IconState {
+        self.receive(ctx);
+        self.clock += 1;
+        let clock = self.clock;
+        match self.slots.get_mut(path) {
+            Some((slot, used)) => {
+                *used = clock;
+                match slot {
+                    IconSlot::Ready(texture) => IconState::Ready(texture.clone()),
+                    IconSlot::Loading => IconState::Loading,
+                    IconSlot::Missing => IconState::Missing,
+                }
+            }
+            None if cfg!(target_arch = "wasm32") => {
+                self.evict();
+                let slot = Self::upload(ctx, path, decode_icon(path));
+                let state = match &slot {
+                    IconSlot::Ready(texture) => IconState::Ready(texture.clone()),
+                    _ => IconState::Missing,
+                };
+                self.slots.insert(path.to_path_buf(), (slot, clock));
+                state
+            }
+            None => {
+                self.evict();
+                self.slots
+                    .insert(path.to_path_buf(), (IconSlot::Loading, clock));
+                let _ = self.request_sender.send(path.to_path_buf());
+                IconState::Loading
+            }
+        }
+    }
+}
+
+/// 取出图标纹理，首次请求时在后台开始加载
+pub fn icon_texture(ctx: &egui::Context, path: &Path) -> IconState {
+    ICON_CACHE.lock().unwrap().get(ctx, path)
+}
+
+#[test]
+fn test_decode_icon() {
+    let image = decode_icon(Path::new("assets/icon.png")).unwrap();
+    assert!(image.width() > 0 && image.height() > 0);
+    assert!(decode_icon(Path::new("assets/missing-icon.png")).is_none());
+    let virtual_path = PathBuf::from("upload/script-output/item/test-icon.png");
+    assert!(decode_icon(&virtual_path).is_none());
+    register_memory_icons([(
+        virtual_path.clone(),
+        std::fs::read("assets/icon.png").unwrap(),
+    )]);
+    assert!(decode_icon(&virtual_path).is_some());
+}
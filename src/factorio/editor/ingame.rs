@@ -0,0 +1,297 @@
+//! 把方案导回游戏。
+//!
+//! 提供两种字符串：
+//! - 方案字符串：`MP1` 前缀加上 zlib 压缩并 base64 编码的 [`PlanExport`] JSON，供配套模组读取，
+//!   用于按目标速率设置请求箱、按机器台数核对建筑；
+//! - 常量运算器蓝图：游戏原生的蓝图字符串，一个运算器的第一组信号为目标物品每分钟的数量，
+//!   第二组为各配方的机器台数，可直接接入电路网络。
+
+use std::{any::Any, io::Read, io::Write};
+
+use base64::Engine;
+
+use crate::{
+    error::AppError,
+    factorio::{common::*, editor::planner::FactoryInstance, model::*},
+};
+
+/// 方案字符串的前缀，格式变化时递增版本号
+pub const PLAN_STRING_PREFIX: &str = "MP1";
+
+/// 蓝图字符串中的游戏版本 2.0.0
+const BLUEPRINT_VERSION: u64 = 2 << 48;
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PlanTarget {
+    /// item 或 fluid
+    pub r#type: String,
+    pub name: String,
+    pub quality: String,
+    /// 每秒的目标速率
+    pub rate: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PlanRecipe {
+    pub recipe: String,
+    pub quality: String,
+    pub machine: String,
+    pub machine_quality: String,
+    /// 求解得到的机器台数，未取整
+    pub machines: f64,
+}
+
+/// 配套模组读取的方案数据
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PlanExport {
+    pub name: String,
+    pub targets: Vec<PlanTarget>,
+    pub recipes: Vec<PlanRecipe>,
+}
+
+fn quality_name(ctx: &FactorioContext, quality: u8) -> String {
+    ctx.qualities
+        .get(quality as usize)
+        .map(|quality| quality.base.name.clone())
+        .unwrap_or_else(|| "normal".to_string())
+}
+
+impl PlanExport {
+    /// 收集工厂的目标与求解得到的配方台数，非物品、流体的目标与未使用的配方不导出
+    pub fn collect(factory: &FactoryInstance, ctx: &FactorioContext) -> Self {
+        let targets = factory
+            .target
+            .iter()
+            .filter_map(|(item, rate)| {
+                let (r#type, name, quality) = match item {
+                    GenericItem::Item(IdWithQuality(name, quality)) => {
+                        ("item", name.clone(), quality_name(ctx, *quality))
+                    }
+                    GenericItem::Fluid { name, .. } => {
+                        ("fluid", name.clone(), quality_name(ctx, 0))
+                    }
+                    _ => return None,
+                };
+                Some(PlanTarget {
+                    r#type: r#type.to_string(),
+                    name,
+                    quality,
+                    rate: *rate,
+                })
+            })
+            .collect();
+        let recipes = factory
+            .mechanics
+            .iter()
+            .filter_map(|(id, mechanic)| {
+                let config = (mechanic.as_ref() as &dyn Any).downcast_ref::<RecipeConfig>()?;
+                let machines = factory.solution.0.get(id).cloned().unwrap_or(0.0);
+                (machines > 1e-6).then(|| PlanRecipe {
+                    recipe: config.recipe.0.clone(),
+                    quality: quality_name(ctx, config.recipe.1),
+                    machine: config.machine.0.clone(),
+                    machine_quality: quality_name(ctx, config.machine.1),
+                    machines,
+                })
+            })
+            .collect();
+        Self {
+            name: factory.name.clone(),
+            targets,
+            recipes,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        format!(
+            "{}{}",
+            PLAN_STRING_PREFIX,
+            compress(&serde_json::to_string(self).unwrap_or_default())
+        )
+    }
+
+    pub fn decode(text: &str) -> Result<Self, AppError> {
+        let data = text
+            .trim()
+            .strip_prefix(PLAN_STRING_PREFIX)
+            .ok_or_else(|| AppError::Custom("不是方案字符串".into()))?;
+        Ok(serde_json::from_str(&decompress(data)?)?)
+    }
+
+    /// 生成常量运算器的蓝图字符串，目标按每分钟向上取整，机器台数向上取整
+    pub fn combinator_blueprint(&self) -> String {
+        let targets = self
+            .targets
+            .iter()
+            .map(|target| {
+                (
+                    target.r#type.as_str(),
+                    &target.name,
+                    &target.quality,
+                    target.rate * 60.0,
+                )
+            })
+            .collect::<Vec<_>>();
+        let recipes = self
+            .recipes
+            .iter()
+            .map(|recipe| ("recipe", &recipe.recipe, &recipe.quality, recipe.machines))
+            .collect::<Vec<_>>();
+        let section = |index: usize, signals: &[(&str, &String, &String, f64)]| {
+            serde_json::json!({
+                "index": index,
+                "filters": signals
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, (r#type, name, quality, count))| serde_json::json!({
+                        "index": idx + 1,
+                        "type": r#type,
+                        "name": name,
+                        "quality": quality,
+                        "comparator": "=",
+                        "count": count.ceil() as i64,
+                    }))
+                    .collect::<Vec<_>>(),
+            })
+        };
+        let blueprint = serde_json::json!({
+            "blueprint": {
+                "item": "blueprint",
+                "label": self.name,
+                "entities": [{
+                    "entity_number": 1,
+                    "name": "constant-combinator",
+                    "position": { "x": 0.5, "y": 0.5 },
+                    "control_behavior": {
+                        "sections": {
+                            "sections": [section(1, &targets), section(2, &recipes)],
+                        },
+                    },
+                }],
+                "version": BLUEPRINT_VERSION,
+            }
+        });
+        format!("0{}", compress(&blueprint.to_string()))
+    }
+}
+
+fn compress(json: &str) -> String {
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+    // 写入内存不会失败
+    let _ = encoder.write_all(json.as_bytes());
+    base64::engine::general_purpose::STANDARD.encode(encoder.finish().unwrap_or_default())
+}
+
+fn decompress(data: &str) -> Result<String, AppError> {
+    let bytes = base64::engine::general_purpose::STANDARD.decode(data.trim())?;
+    let mut json = String::new();
+    flate2::read::ZlibDecoder::new(bytes.as_slice()).read_to_string(&mut json)?;
+    Ok(json)
+}
+
+/// 解码蓝图字符串，返回其中的 JSON
+pub fn decode_blueprint(text: &str) -> Result<serde_json::Value, AppError> {
+    let data = text
+        .trim()
+        .strip_prefix('0')
+        .ok_or_else(|| AppError::Custom("不支持的蓝图字符串版本".into()))?;
+    Ok(serde_json::from_str(&decompress(data)?)?)
+}
+
+/// 显示导出字符串的界面，放在方案页的对话框中
+pub struct PlanExportPanel<'a> {
+    pub export: &'a PlanExport,
+}
+
+impl egui::Widget for PlanExportPanel<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.vertical(|ui| {
+            ui.heading("导出到游戏");
+            ui.label(format!(
+                "{} 个目标，{} 个配方",
+                self.export.targets.len(),
+                self.export.recipes.len()
+            ));
+            for (title, hint, text) in [
+                (
+                    "方案字符串",
+                    "在配套模组中粘贴，用于设置请求箱与核对建筑",
+                    self.export.encode(),
+                ),
+                (
+                    "常量运算器蓝图",
+                    "第一组信号为目标物品每分钟的数量，第二组为各配方的机器台数",
+                    self.export.combinator_blueprint(),
+                ),
+            ] {
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.strong(title);
+                    if ui.button("复制").clicked() {
+                        ui.ctx().copy_text(text.clone());
+                        crate::toast::info(format!("已复制{}", title));
+                    }
+                });
+                ui.label(hint);
+                ui.add(
+                    egui::TextEdit::multiline(&mut text.as_str())
+                        .code_editor()
+                        .desired_rows(3)
+                        .desired_width(f32::INFINITY),
+                );
+            }
+        })
+        .response
+    }
+}
+
+#[test]
+fn test_plan_export() {
+    let ctx = FactorioContext::test_load();
+    let mut factory = FactoryInstance::new("测试".to_string());
+    factory.target.push((
+        GenericItem::Item(IdWithQuality("iron-gear-wheel".into(), 1)),
+        1.5,
+    ));
+    factory.target.push((GenericItem::Electricity, 1.0));
+    use crate::concept::MechanicId;
+    let id = MechanicId::generate();
+    factory.mechanics.insert(
+        id,
+        Box::new(RecipeConfig {
+            recipe: "iron-gear-wheel".into(),
+            machine: "assembling-machine-1".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+    factory.solution.0.insert(id, 2.5);
+    factory.mechanics.insert(
+        MechanicId::generate(),
+        Box::new(RecipeConfig {
+            recipe: "copper-cable".into(),
+            machine: "assembling-machine-1".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+
+    let export = PlanExport::collect(&factory, &ctx);
+    assert_eq!(export.targets.len(), 1);
+    assert_eq!(export.targets[0].quality, ctx.qualities[1].base.name);
+    assert_eq!(export.recipes.len(), 1);
+    assert_eq!(export.recipes[0].machines, 2.5);
+
+    let encoded = export.encode();
+    assert!(encoded.starts_with(PLAN_STRING_PREFIX));
+    assert_eq!(PlanExport::decode(&encoded).unwrap(), export);
+    assert!(PlanExport::decode("0eNqrVg==").is_err());
+
+    let blueprint = decode_blueprint(&export.combinator_blueprint()).unwrap();
+    let sections =
+        &blueprint["blueprint"]["entities"][0]["control_behavior"]["sections"]["sections"];
+    assert_eq!(sections[0]["filters"][0]["name"], "iron-gear-wheel");
+    assert_eq!(sections[0]["filters"][0]["count"], 90);
+    assert_eq!(sections[1]["filters"][0]["type"], "recipe");
+    assert_eq!(sections[1]["filters"][0]["count"], 3);
+}
@@ -0,0 +1,220 @@
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        editor::{
+            icon::*,
+            planner::{FactoryInstance, can_toggle_external},
+        },
+        format::*,
+        model::*,
+    },
+    i18n::{tr, trf},
+};
+
+/// 机制与其涉及物品的速率
+type Participants = Vec<(MechanicId, f64)>;
+
+/// 高亮持续的秒数
+const HIGHLIGHT_SECS: f64 = 2.0;
+
+/// 物料流检查器：列出方案中生产或消耗某一物品的所有机制，可跳转到对应的机制卡片
+#[derive(Debug, Clone, Default)]
+pub struct ItemInspector {
+    pub item: Option<GenericItem>,
+    /// 需要滚动到可见位置的机制卡片，滚动后清除
+    pub scroll_to: Option<MechanicId>,
+    /// 高亮的机制卡片与开始高亮的时间
+    pub highlight: Option<(MechanicId, f64)>,
+}
+
+impl ItemInspector {
+    pub fn open(&mut self, item: GenericItem) {
+        self.item = Some(item);
+    }
+
+    /// 跳转到机制卡片并短暂高亮
+    pub fn focus(&mut self, id: MechanicId, now: f64) {
+        self.scroll_to = Some(id);
+        self.highlight = Some((id, now));
+    }
+
+    /// 卡片当前是否高亮，高亮期间持续请求重绘以便到时熄灭
+    pub fn is_highlighted(&self, id: MechanicId, ui: &egui::Ui) -> bool {
+        let Some((highlighted, since)) = self.highlight else {
+            return false;
+        };
+        let now = ui.input(|input| input.time);
+        if highlighted != id || now - since > HIGHLIGHT_SECS {
+            return false;
+        }
+        ui.ctx().request_repaint();
+        true
+    }
+}
+
+/// 方案中涉及该物品的机制及其按求解结果计算的速率，产出为正、消耗为负，按速率大小降序排列。
+/// 尚未求解的机制速率按 0 计
+pub fn item_participants(
+    factory: &FactoryInstance,
+    item: &GenericItem,
+    ctx: &FactorioContext,
+) -> (Participants, Participants) {
+    let mut producers = Vec::new();
+    let mut consumers = Vec::new();
+    for (id, mechanic) in &factory.mechanics {
+        let Some(amount) = factory
+            .mechanic_flow(mechanic.as_ref(), ctx)
+            .get(item)
+            .copied()
+        else {
+            continue;
+        };
+        let rate = amount * factory.solution.0.get(id).copied().unwrap_or(0.0);
+        if amount > 0.0 {
+            producers.push((*id, rate));
+        } else if amount < 0.0 {
+            consumers.push((*id, rate));
+        }
+    }
+    producers.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+    consumers.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+    (producers, consumers)
+}
+
+impl FactoryInstance {
+    /// 检查器侧栏，未选择物品时不显示。切换了额外输入时返回 true，需要重新求解
+    pub fn inspector_panel(&mut self, ui: &mut egui::Ui, ctx: &FactorioContext) -> bool {
+        let Some(item) = self.inspector.item.clone() else {
+            return false;
+        };
+        let rate_unit = self.rate_unit().for_item(&item);
+        let (producers, consumers) = item_participants(self, &item, ctx);
+        let can_toggle_external =
+            can_toggle_external(&item, self.is_external(&item), &self.produced_items(ctx));
+        let mut external_clicked = false;
+        let mut close = false;
+        let mut focus = None;
+        egui::SidePanel::right(egui::Id::new("item-inspector"))
+            .show_separator_line(true)
+            .frame(egui::Frame::NONE.corner_radius(8.0).inner_margin(4.0))
+            .show_inside(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add_sized(crate::settings::icon_cell(), GenericIcon::new(ctx, &item));
+                    ui.heading(ctx.get_generic_item_display_name(&item));
+                    if ui.button("✖").on_hover_text(tr("common.close")).clicked() {
+                        close = true;
+                    }
+                });
+                let produced = producers.iter().map(|(_, rate)| rate).sum::<f64>();
+                let consumed = consumers.iter().map(|(_, rate)| rate).sum::<f64>();
+                ui.label(trf(
+                    "inspector.summary",
+                    &[
+                        &compact_number(produced * rate_unit.multiplier()),
+                        &compact_number(-consumed * rate_unit.multiplier()),
+                        &signed_compact_number((produced + consumed) * rate_unit.multiplier()),
+                        &rate_unit.suffix(),
+                    ],
+                ));
+                if can_toggle_external {
+                    let external = self.is_external(&item);
+                    external_clicked = ui
+                        .add(egui::Button::new(tr("planner.mark_external")).selected(external))
+                        .on_hover_text(if external {
+                            tr("planner.unmark_external_hint")
+                        } else {
+                            tr("planner.mark_external_hint")
+                        })
+                        .clicked();
+                }
+                if self.mechanic_filter.is_active() {
+                    ui.label(egui::RichText::new(tr("inspector.filter_hint")).weak());
+                }
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .id_salt("item-inspector")
+                    .show(ui, |ui| {
+                        for (heading, participants) in [
+                            (tr("inspector.producers"), &producers),
+                            (tr("inspector.consumers"), &consumers),
+                        ] {
+                            ui.strong(heading);
+                            if participants.is_empty() {
+                                ui.label(tr("inspector.none"));
+                            }
+                            for (id, rate) in participants {
+                                let Some(mechanic) = self.mechanics.get(id) else {
+                                    continue;
+                                };
+                                ui.horizontal(|ui| {
+                                    ui.add_sized(
+                                        crate::settings::label_cell(),
+                                        SignedCompactLabel::rate(*rate, rate_unit),
+                                    );
+                                    if ui
+                                        .link(mechanic_display_name(mechanic.as_ref(), ctx))
+                                        .on_hover_text(tr("inspector.jump_hint"))
+                                        .clicked()
+                                    {
+                                        focus = Some(*id);
+                                    }
+                                });
+                            }
+                            ui.add_space(8.0);
+                        }
+                    });
+            });
+        if close {
+            self.inspector.item = None;
+        }
+        if let Some(id) = focus {
+            self.inspector.focus(id, ui.input(|input| input.time));
+        }
+        if external_clicked {
+            self.toggle_external(&item);
+        }
+        external_clicked
+    }
+}
+
+#[test]
+fn test_item_participants() {
+    let ctx = FactorioContext::test_load();
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let recipe = |recipe: &str| -> Box<FactorioMechanic> {
+        Box::new(RecipeConfig {
+            recipe: recipe.into(),
+            machine: "assembling-machine-2".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        })
+    };
+    let gear = MechanicId::generate();
+    let cable = MechanicId::generate();
+    let circuit = MechanicId::generate();
+    factory.mechanics.insert(gear, recipe("iron-gear-wheel"));
+    factory.mechanics.insert(cable, recipe("copper-cable"));
+    factory
+        .mechanics
+        .insert(circuit, recipe("electronic-circuit"));
+    factory.solution.0.insert(cable, 3.0);
+    factory.solution.0.insert(circuit, 2.0);
+    let (producers, consumers) =
+        item_participants(&factory, &GenericItem::Item("copper-cable".into()), &ctx);
+    assert_eq!(producers.len(), 1);
+    assert_eq!(producers[0].0, cable);
+    assert!(producers[0].1 > 0.0);
+    assert_eq!(consumers.len(), 1);
+    assert_eq!(consumers[0].0, circuit);
+    assert!(consumers[0].1 < 0.0);
+    // 铁板同时被齿轮与电路消耗，未求解的齿轮速率为 0，排在后面
+    let (producers, consumers) =
+        item_participants(&factory, &GenericItem::Item("iron-plate".into()), &ctx);
+    assert!(producers.is_empty());
+    assert_eq!(
+        consumers.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![circuit, gear]
+    );
+    assert_eq!(consumers[1].1, 0.0);
+}
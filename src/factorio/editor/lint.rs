@@ -0,0 +1,408 @@
+use std::any::Any;
+
+use indexmap::IndexMap;
+
+use crate::{
+    concept::MechanicId,
+    factorio::{common::*, model::*},
+};
+
+/// 方案检查规则，默认全部关闭，需要在健康报告中手动开启
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintRule {
+    /// 插件塔中放置了产能插件
+    BeaconProductivity,
+    /// 配方不允许品质加成，却安装了品质插件
+    DisallowedQuality,
+    /// 指定的燃料与机器的能源类型不符
+    MismatchedFuel,
+    /// 目标数量看起来是按单次制作而不是按每秒填写的
+    PerCraftTarget,
+    /// 机器只能执行固定的配方或固定品质的配方，与所选配方不符
+    FixedRecipe,
+}
+
+impl LintRule {
+    pub const ALL: [LintRule; 5] = [
+        LintRule::BeaconProductivity,
+        LintRule::DisallowedQuality,
+        LintRule::MismatchedFuel,
+        LintRule::PerCraftTarget,
+        LintRule::FixedRecipe,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintRule::BeaconProductivity => "插件塔中的产能插件",
+            LintRule::DisallowedQuality => "无效的品质插件",
+            LintRule::MismatchedFuel => "燃料与能源类型不符",
+            LintRule::PerCraftTarget => "按单次制作填写的目标",
+            LintRule::FixedRecipe => "机器的固定配方",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            LintRule::BeaconProductivity => "插件塔不能传递产能效果，产能插件放在插件塔中不起作用",
+            LintRule::DisallowedQuality => "配方不允许品质加成时，品质插件只会带来负面效果",
+            LintRule::MismatchedFuel => {
+                "非燃烧能源的机器指定了燃料，或燃烧能源的机器指定了不能作为燃料的物品"
+            }
+            LintRule::PerCraftTarget => {
+                "目标数量按每秒计算，与某个配方单次制作的产量恰好相同时可能填错了单位"
+            }
+            LintRule::FixedRecipe => "火箭发射井等机器只能执行固定的配方，部分机器还限定了配方品质",
+        }
+    }
+}
+
+/// 每条规则是否开启
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LintSettings {
+    pub beacon_productivity: bool,
+    pub disallowed_quality: bool,
+    pub mismatched_fuel: bool,
+    pub per_craft_target: bool,
+    pub fixed_recipe: bool,
+}
+
+impl LintSettings {
+    pub fn all() -> Self {
+        Self {
+            beacon_productivity: true,
+            disallowed_quality: true,
+            mismatched_fuel: true,
+            per_craft_target: true,
+            fixed_recipe: true,
+        }
+    }
+
+    pub fn is_enabled(&self, rule: LintRule) -> bool {
+        *self.get(rule)
+    }
+
+    pub fn any_enabled(&self) -> bool {
+        LintRule::ALL.iter().any(|rule| self.is_enabled(*rule))
+    }
+
+    fn get(&self, rule: LintRule) -> &bool {
+        match rule {
+            LintRule::BeaconProductivity => &self.beacon_productivity,
+            LintRule::DisallowedQuality => &self.disallowed_quality,
+            LintRule::MismatchedFuel => &self.mismatched_fuel,
+            LintRule::PerCraftTarget => &self.per_craft_target,
+            LintRule::FixedRecipe => &self.fixed_recipe,
+        }
+    }
+
+    fn get_mut(&mut self, rule: LintRule) -> &mut bool {
+        match rule {
+            LintRule::BeaconProductivity => &mut self.beacon_productivity,
+            LintRule::DisallowedQuality => &mut self.disallowed_quality,
+            LintRule::MismatchedFuel => &mut self.mismatched_fuel,
+            LintRule::PerCraftTarget => &mut self.per_craft_target,
+            LintRule::FixedRecipe => &mut self.fixed_recipe,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    pub rule: LintRule,
+    /// 问题所在的机制，目标相关的问题为 None
+    pub mechanic: Option<MechanicId>,
+    pub message: String,
+}
+
+/// 对方案运行已开启的检查规则
+pub fn run_lints(
+    mechanics: &IndexMap<MechanicId, Box<FactorioMechanic>>,
+    targets: &[(GenericItem, f64)],
+    ctx: &FactorioContext,
+    settings: &LintSettings,
+) -> Vec<LintIssue> {
+    let mut issues = vec![];
+    for (id, mechanic) in mechanics {
+        let any = mechanic.as_ref() as &dyn Any;
+        let name = mechanic_display_name(mechanic.as_ref(), ctx);
+        let (module_config, energy_source, fuel) = if let Some(recipe_config) =
+            any.downcast_ref::<RecipeConfig>()
+        {
+            if settings.disallowed_quality
+                && let Some(recipe) = ctx.recipes.get(&recipe_config.recipe.0)
+                && !recipe.allow_quality
+                && recipe_config
+                    .module_config
+                    .modules
+                    .iter()
+                    .any(|module| module_effect(ctx, module).quality > 0.0)
+            {
+                issues.push(LintIssue {
+                    rule: LintRule::DisallowedQuality,
+                    mechanic: Some(*id),
+                    message: format!("{}：配方不允许品质加成，品质插件无效", name),
+                });
+            }
+            if settings.fixed_recipe
+                && let Some(crafter) = ctx.crafters.get(&recipe_config.machine.0)
+                && let Some(reason) = fixed_recipe_violation(ctx, crafter, &recipe_config.recipe)
+            {
+                issues.push(LintIssue {
+                    rule: LintRule::FixedRecipe,
+                    mechanic: Some(*id),
+                    message: format!("{}：{}", name, reason),
+                });
+            }
+            (
+                &recipe_config.module_config,
+                ctx.crafters
+                    .get(&recipe_config.machine.0)
+                    .map(|crafter| &crafter.energy_source),
+                recipe_config.instance_fuel.as_ref().map(|fuel| &fuel.0),
+            )
+        } else if let Some(mining_config) = any.downcast_ref::<MiningConfig>() {
+            (
+                &mining_config.module_config,
+                ctx.miners
+                    .get(&mining_config.machine.0)
+                    .map(|miner| &miner.energy_source),
+                mining_config.instance_fuel.as_ref().map(|fuel| &fuel.0),
+            )
+        } else if let Some(pumpjack_config) = any.downcast_ref::<PumpjackConfig>() {
+            (
+                &pumpjack_config.module_config,
+                ctx.miners
+                    .get(&pumpjack_config.machine.0)
+                    .map(|miner| &miner.energy_source),
+                pumpjack_config.instance_fuel.as_ref().map(|fuel| &fuel.0),
+            )
+        } else if let Some(lab_config) = any.downcast_ref::<LabConfig>() {
+            (
+                &lab_config.module_config,
+                ctx.labs
+                    .get(&lab_config.machine.0)
+                    .map(|lab| &lab.energy_source),
+                lab_config.instance_fuel.as_ref().map(|fuel| &fuel.0),
+            )
+        } else {
+            continue;
+        };
+        if settings.beacon_productivity
+            && module_config.beacons.iter().any(|beacon| {
+                beacon
+                    .modules
+                    .iter()
+                    .any(|(module, _)| module_effect(ctx, module).productivity > 0.0)
+            })
+        {
+            issues.push(LintIssue {
+                rule: LintRule::BeaconProductivity,
+                mechanic: Some(*id),
+                message: format!("{}：插件塔中的产能插件不起作用", name),
+            });
+        }
+        if settings.mismatched_fuel
+            && let (Some(energy_source), Some(fuel)) = (energy_source, fuel)
+            && let Some(reason) = fuel_mismatch(ctx, energy_source, fuel)
+        {
+            issues.push(LintIssue {
+                rule: LintRule::MismatchedFuel,
+                mechanic: Some(*id),
+                message: format!("{}：{}", name, reason),
+            });
+        }
+    }
+    if settings.per_craft_target {
+        for (item, amount) in targets {
+            if let Some(recipe) = per_craft_match(mechanics, item, *amount, ctx) {
+                issues.push(LintIssue {
+                    rule: LintRule::PerCraftTarget,
+                    mechanic: None,
+                    message: format!(
+                        "目标 {} × {} 与配方 {} 单次制作的产量相同，目标数量应按每秒填写",
+                        ctx.get_generic_item_display_name(item),
+                        amount,
+                        ctx.get_display_name("recipe", &recipe)
+                    ),
+                });
+            }
+        }
+    }
+    issues
+}
+
+fn module_effect(ctx: &FactorioContext, module: &IdWithQuality) -> Effect {
+    ctx.modules
+        .get(&module.0)
+        .map(|module| module.effect.clone())
+        .unwrap_or_default()
+}
+
+fn fuel_mismatch(
+    ctx: &FactorioContext,
+    energy_source: &EnergySource,
+    fuel: &str,
+) -> Option<String> {
+    match energy_source {
+        EnergySource::Burner(source) => {
+            let Some(item) = ctx.items.get(fuel) else {
+                return Some(format!("燃料 {} 不是物品", fuel));
+            };
+            match &item.burn {
+                None => Some(format!(
+                    "{} 不能作为燃料",
+                    ctx.get_display_name("item", fuel)
+                )),
+                Some(burn)
+                    if burn
+                        .fuel_category
+                        .as_ref()
+                        .is_some_and(|category| !source.fuel_categories.contains(category)) =>
+                {
+                    Some(format!(
+                        "{} 的燃料类别与机器不符",
+                        ctx.get_display_name("item", fuel)
+                    ))
+                }
+                _ => None,
+            }
+        }
+        EnergySource::Fluid(_) => {
+            (!ctx.fluids.contains_key(fuel)).then(|| format!("燃料 {} 不是流体", fuel))
+        }
+        EnergySource::Electric(_) => Some("电力驱动的机器指定了燃料".to_string()),
+        EnergySource::Heat(_) | EnergySource::Void(_) => {
+            Some("机器不消耗燃料，指定的燃料不会生效".to_string())
+        }
+    }
+}
+
+/// 在方案已有的配方中寻找单次产量恰好等于目标数量的配方
+fn per_craft_match(
+    mechanics: &IndexMap<MechanicId, Box<FactorioMechanic>>,
+    item: &GenericItem,
+    amount: f64,
+    ctx: &FactorioContext,
+) -> Option<String> {
+    // 小于 1 的非整数目标基本不会是按单次制作填写的
+    if amount < 1.0 || amount.fract() != 0.0 {
+        return None;
+    }
+    mechanics.values().find_map(|mechanic| {
+        let recipe_config = (mechanic.as_ref() as &dyn Any).downcast_ref::<RecipeConfig>()?;
+        let recipe = ctx.recipes.get(&recipe_config.recipe.0)?;
+        recipe
+            .results
+            .iter()
+            .any(|result| match (result, item) {
+                (RecipeResult::Item(result), GenericItem::Item(IdWithQuality(name, _))) => {
+                    result.name == *name && result.normalized_output().0 == amount
+                }
+                (RecipeResult::Fluid(result), GenericItem::Fluid { name, .. }) => {
+                    result.name == *name && result.amount == Some(amount)
+                }
+                _ => false,
+            })
+            .then(|| recipe_config.recipe.0.clone())
+    })
+}
+
+/// 健康报告：检查规则开关与发现的问题
+pub struct HealthReport<'a> {
+    settings: &'a mut LintSettings,
+    issues: &'a [LintIssue],
+}
+
+impl<'a> HealthReport<'a> {
+    pub fn new(settings: &'a mut LintSettings, issues: &'a [LintIssue]) -> Self {
+        Self { settings, issues }
+    }
+}
+
+impl egui::Widget for HealthReport<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let title = if self.issues.is_empty() {
+            "健康报告".to_string()
+        } else {
+            format!("健康报告（{} 个问题）", self.issues.len())
+        };
+        egui::CollapsingHeader::new(title)
+            .id_salt("health-report")
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for rule in LintRule::ALL {
+                        ui.checkbox(self.settings.get_mut(rule), rule.name())
+                            .on_hover_text(rule.description());
+                    }
+                });
+                if !self.settings.any_enabled() {
+                    ui.label("尚未开启任何检查规则");
+                } else if self.issues.is_empty() {
+                    ui.label("未发现问题");
+                }
+                for issue in self.issues {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(egui::Color32::YELLOW, "⚠");
+                        ui.label(&issue.message)
+                            .on_hover_text(issue.rule.description());
+                    });
+                }
+            })
+            .header_response
+    }
+}
+
+#[test]
+fn test_fuelled_burner_lints() {
+    let ctx = FactorioContext::test_load();
+    let mut mechanics: IndexMap<MechanicId, Box<FactorioMechanic>> = IndexMap::new();
+    // 石炉可以燃烧化学燃料，煤炭不应被报告为燃料不符
+    mechanics.insert(
+        MechanicId(1),
+        Box::new(RecipeConfig {
+            recipe: "iron-plate".into(),
+            machine: "stone-furnace".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: Some(("coal".to_string(), 0)),
+        }),
+    );
+    assert!(run_lints(&mechanics, &[], &ctx, &LintSettings::all()).is_empty());
+}
+
+#[test]
+fn test_run_lints() {
+    let ctx = FactorioContext::test_load();
+    let mut mechanics: IndexMap<MechanicId, Box<FactorioMechanic>> = IndexMap::new();
+    mechanics.insert(
+        MechanicId(1),
+        Box::new(RecipeConfig {
+            recipe: "iron-gear-wheel".into(),
+            machine: "assembling-machine-2".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: Some(("coal".to_string(), 0)),
+        }),
+    );
+    // 火箭发射井只能执行火箭部件配方
+    mechanics.insert(
+        MechanicId(2),
+        Box::new(RecipeConfig {
+            recipe: "iron-gear-wheel".into(),
+            machine: "rocket-silo".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+    let targets = vec![(GenericItem::Item("iron-gear-wheel".into()), 1.0)];
+    assert!(run_lints(&mechanics, &targets, &ctx, &LintSettings::default()).is_empty());
+    let issues = run_lints(&mechanics, &targets, &ctx, &LintSettings::all());
+    let rules = issues.iter().map(|issue| issue.rule).collect::<Vec<_>>();
+    assert_eq!(
+        rules,
+        vec![
+            LintRule::MismatchedFuel,
+            LintRule::FixedRecipe,
+            LintRule::PerCraftTarget
+        ]
+    );
+}
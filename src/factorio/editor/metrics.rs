@@ -0,0 +1,500 @@
+//! 把总览的汇总数据按地点（星球或太空平台）分组导出，格式为 JSON 或 Prometheus 文本，
+//! 便于用外部的绘图工具记录规划随时间的变化
+//!
+//! 命令行下使用 `--export-metrics[=json|prometheus] [--workspace 路径]` 读取保存的工作区，
+//! 重新求解其中的工厂后把指标写到标准输出，不启动界面。
+
+use std::fmt::Write;
+
+use indexmap::IndexMap;
+
+use crate::{
+    error::AppError,
+    factorio::{
+        FactorioContext, LoadMonitor,
+        editor::{
+            dashboard::{Dashboard, FactorySummary},
+            migrate::validate_factory,
+            planner::FactoryInstance,
+            stats::StatsKey,
+        },
+    },
+    workspace::Workspace,
+};
+
+/// 一条序列的额外标签与数值
+type Sample = (Vec<(&'static str, String)>, f64);
+
+/// 工作区中读取的上下文，以及各工厂与其上下文在前者中的下标
+type WorkspaceFactories = (Vec<FactorioContext>, Vec<(FactoryInstance, usize)>);
+
+/// 没有设置地点的工厂归入的分组
+pub const UNASSIGNED_SURFACE: &str = "unassigned";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsFormat {
+    Json,
+    Prometheus,
+}
+
+impl MetricsFormat {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.to_ascii_lowercase().as_str() {
+            "json" => Some(MetricsFormat::Json),
+            "prometheus" | "prom" => Some(MetricsFormat::Prometheus),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            MetricsFormat::Json => "json",
+            MetricsFormat::Prometheus => "prom",
+        }
+    }
+}
+
+/// 一个工厂或一组工厂的指标，速率按文件中的单位换算
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct SummaryMetrics {
+    pub solved: bool,
+    pub power_consumption_watts: f64,
+    pub power_net_watts: f64,
+    pub science_per_minute: f64,
+    pub pollution_per_minute: IndexMap<String, f64>,
+    pub raw_items_per_second: IndexMap<String, f64>,
+    pub raw_fluids_per_second: IndexMap<String, f64>,
+    pub machines: IndexMap<String, f64>,
+}
+
+impl From<&FactorySummary> for SummaryMetrics {
+    fn from(summary: &FactorySummary) -> Self {
+        let mut metrics = SummaryMetrics {
+            solved: summary.solved,
+            power_consumption_watts: summary.power_consumption,
+            power_net_watts: summary.power_net,
+            science_per_minute: summary.science * 60.0,
+            pollution_per_minute: summary
+                .pollution
+                .iter()
+                .map(|(name, amount)| (name.clone(), amount * 60.0))
+                .collect(),
+            machines: summary.machines.clone(),
+            ..Default::default()
+        };
+        for (key, amount) in &summary.raw {
+            match key {
+                StatsKey::Item(name) => metrics.raw_items_per_second.insert(name.clone(), *amount),
+                StatsKey::Fluid(name) => {
+                    metrics.raw_fluids_per_second.insert(name.clone(), *amount)
+                }
+            };
+        }
+        metrics
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct FactoryMetrics {
+    pub name: String,
+    #[serde(flatten)]
+    pub metrics: SummaryMetrics,
+}
+
+/// 同一地点的全部工厂
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct SurfaceMetrics {
+    pub total: SummaryMetrics,
+    pub factories: Vec<FactoryMetrics>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct MetricsExport {
+    /// 导出时间，Unix 时间戳（秒）
+    pub generated_at: u64,
+    pub total: SummaryMetrics,
+    /// 按地点的内部名称分组，顺序与工厂的打开顺序一致
+    pub surfaces: IndexMap<String, SurfaceMetrics>,
+}
+
+impl MetricsExport {
+    pub fn new(dashboard: &Dashboard) -> Self {
+        let mut surfaces: IndexMap<String, (FactorySummary, Vec<FactoryMetrics>)> = IndexMap::new();
+        for summary in &dashboard.factories {
+            let surface = summary
+                .location
+                .clone()
+                .unwrap_or_else(|| UNASSIGNED_SURFACE.to_string());
+            let (total, factories) = surfaces.entry(surface).or_insert_with(|| {
+                (
+                    FactorySummary {
+                        solved: true,
+                        ..Default::default()
+                    },
+                    Vec::new(),
+                )
+            });
+            total.merge(summary);
+            factories.push(FactoryMetrics {
+                name: summary.name.clone(),
+                metrics: summary.into(),
+            });
+        }
+        MetricsExport {
+            generated_at: web_time::SystemTime::now()
+                .duration_since(web_time::UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs()),
+            total: (&dashboard.total).into(),
+            surfaces: surfaces
+                .into_iter()
+                .map(|(surface, (total, factories))| {
+                    (
+                        surface,
+                        SurfaceMetrics {
+                            total: (&total).into(),
+                            factories,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn render(&self, format: MetricsFormat) -> String {
+        match format {
+            MetricsFormat::Json => serde_json::to_string_pretty(self).unwrap_or_default(),
+            MetricsFormat::Prometheus => self.to_prometheus(),
+        }
+    }
+
+    /// Prometheus 文本格式，每个工厂一组序列，按地点与工厂名称区分；不输出合计以免求和时重复
+    pub fn to_prometheus(&self) -> String {
+        let factories = self
+            .surfaces
+            .iter()
+            .flat_map(|(surface, metrics)| {
+                metrics
+                    .factories
+                    .iter()
+                    .map(move |factory| (surface.as_str(), factory))
+            })
+            .collect::<Vec<_>>();
+        let mut out = String::new();
+        let mut gauge =
+            |name: &str, help: &str, samples: &dyn Fn(&SummaryMetrics) -> Vec<Sample>| {
+                let _ = writeln!(out, "# HELP {} {}", name, help);
+                let _ = writeln!(out, "# TYPE {} gauge", name);
+                for (surface, factory) in &factories {
+                    for (extra, value) in samples(&factory.metrics) {
+                        let mut labels = vec![
+                            ("surface", surface.to_string()),
+                            ("factory", factory.name.clone()),
+                        ];
+                        labels.extend(extra);
+                        let labels = labels
+                            .iter()
+                            .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+                            .collect::<Vec<_>>()
+                            .join(",");
+                        let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+                    }
+                }
+            };
+        gauge(
+            "metatorio_factory_solved",
+            "Whether the factory has a solution (1) or not (0).",
+            &|metrics| vec![(vec![], if metrics.solved { 1.0 } else { 0.0 })],
+        );
+        gauge(
+            "metatorio_power_consumption_watts",
+            "Electric power consumed by machines.",
+            &|metrics| vec![(vec![], metrics.power_consumption_watts)],
+        );
+        gauge(
+            "metatorio_power_net_watts",
+            "Generated minus consumed electric power.",
+            &|metrics| vec![(vec![], metrics.power_net_watts)],
+        );
+        gauge(
+            "metatorio_science_per_minute",
+            "Science packs consumed by labs.",
+            &|metrics| vec![(vec![], metrics.science_per_minute)],
+        );
+        gauge(
+            "metatorio_pollution_per_minute",
+            "Net pollution emitted, by pollutant.",
+            &|metrics| {
+                metrics
+                    .pollution_per_minute
+                    .iter()
+                    .map(|(name, amount)| (vec![("pollutant", name.clone())], *amount))
+                    .collect()
+            },
+        );
+        gauge(
+            "metatorio_raw_resource_per_second",
+            "Mined or imported raw resources, by kind and name.",
+            &|metrics| {
+                let items = metrics.raw_items_per_second.iter().map(|(name, amount)| {
+                    (
+                        vec![("kind", "item".to_string()), ("name", name.clone())],
+                        *amount,
+                    )
+                });
+                let fluids = metrics.raw_fluids_per_second.iter().map(|(name, amount)| {
+                    (
+                        vec![("kind", "fluid".to_string()), ("name", name.clone())],
+                        *amount,
+                    )
+                });
+                items.chain(fluids).collect()
+            },
+        );
+        gauge(
+            "metatorio_machines",
+            "Placed entities, by entity name.",
+            &|metrics| {
+                metrics
+                    .machines
+                    .iter()
+                    .map(|(entity, amount)| (vec![("entity", entity.clone())], *amount))
+                    .collect()
+            },
+        );
+        out
+    }
+}
+
+/// 转义 Prometheus 标签值中的反斜杠、引号与换行
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// 命令行参数中与指标导出相关的部分
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricsCommand {
+    pub format: MetricsFormat,
+    /// 为 None 时读取默认位置的工作区
+    pub workspace: Option<std::path::PathBuf>,
+}
+
+impl MetricsCommand {
+    /// 解析命令行参数（不含程序名），没有 --export-metrics 时返回 None
+    pub fn parse(args: &[String]) -> Option<Result<Self, AppError>> {
+        let mut format = None;
+        let mut workspace = Ok(None);
+        let mut args = args.iter().peekable();
+        while let Some(arg) = args.next() {
+            if let Some(value) = arg.strip_prefix("--export-metrics=") {
+                format = Some(MetricsFormat::parse(value).ok_or(value.to_string()));
+            } else if arg == "--export-metrics" {
+                let value = args.next_if(|value| !value.starts_with("--"));
+                format = Some(match value {
+                    Some(value) => MetricsFormat::parse(value).ok_or(value.clone()),
+                    None => Ok(MetricsFormat::Json),
+                });
+            } else if let Some(value) = arg.strip_prefix("--workspace=") {
+                workspace = Ok(Some(std::path::PathBuf::from(value)));
+            } else if arg == "--workspace" {
+                workspace = args
+                    .next_if(|value| !value.starts_with("--"))
+                    .map(|value| Some(std::path::PathBuf::from(value)))
+                    .ok_or_else(|| AppError::Custom("--workspace 缺少工作区文件路径".to_string()));
+            }
+        }
+        Some(match format? {
+            Ok(format) => workspace.map(|workspace| MetricsCommand { format, workspace }),
+            Err(value) => Err(AppError::Custom(format!(
+                "未知的指标格式 {}，可选 json 或 prometheus",
+                value
+            ))),
+        })
+    }
+
+    /// 读取工作区并重新求解其中的工厂，返回导出的文本
+    pub fn run(&self) -> Result<String, AppError> {
+        let workspace = match &self.workspace {
+            Some(path) => Workspace::load_from(path)?,
+            None => Workspace::load(),
+        };
+        let (contexts, factories) = load_workspace_factories(&workspace)?;
+        let dashboard = Dashboard::collect(
+            factories
+                .iter()
+                .map(|(factory, context)| (factory, &contexts[*context])),
+        );
+        Ok(MetricsExport::new(&dashboard).render(self.format))
+    }
+}
+
+/// 读取工作区中规划器打开的全部工厂并在当前线程求解，每个工厂使用其绑定的上下文。
+/// 返回读取的上下文与各工厂及其上下文的下标，读取失败的工厂记录日志后跳过
+pub fn load_workspace_factories(workspace: &Workspace) -> Result<WorkspaceFactories, AppError> {
+    let mut keys: Vec<String> = Vec::new();
+    let mut contexts = Vec::new();
+    let mut factories = Vec::new();
+    for entry in &workspace.entries {
+        let Some((entry_keys, paths)) = entry.planner_contexts() else {
+            continue;
+        };
+        // 多个规划器页面使用同一份导出数据时只读取一次
+        let mut indices = Vec::new();
+        for key in entry_keys {
+            let index = match keys.iter().position(|loaded| *loaded == key) {
+                Some(index) => index,
+                None => {
+                    let ctx =
+                        FactorioContext::load_from_tmp_no_dump(&key, &LoadMonitor::default())?
+                            .build_order_info()
+                            .build_producer_info();
+                    keys.push(key);
+                    contexts.push(ctx);
+                    contexts.len() - 1
+                }
+            };
+            indices.push(index);
+        }
+        for (path, context) in paths {
+            let context = indices[context];
+            let ctx = &contexts[context];
+            let factory = std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| {
+                    serde_json::from_str::<FactoryInstance>(&content).map_err(|e| e.to_string())
+                });
+            let mut factory = match factory {
+                Ok(factory) => factory,
+                Err(err) => {
+                    log::warn!("读取工厂 {} 失败，已跳过: {}", path.display(), err);
+                    continue;
+                }
+            };
+            let missing = validate_factory(&factory, ctx);
+            if !missing.is_empty() {
+                log::warn!(
+                    "工厂 {} 引用了 {} 个当前上下文中不存在的原型，已跳过",
+                    path.display(),
+                    missing.len()
+                );
+                continue;
+            }
+            if let Err(err) = factory.solve_blocking(ctx) {
+                log::warn!("求解工厂 {} 失败: {:?}", path.display(), err);
+            }
+            factories.push((factory, context));
+        }
+    }
+    Ok((contexts, factories))
+}
+
+#[test]
+fn test_metrics_export() {
+    let mut dashboard = Dashboard::default();
+    let mut nauvis = FactorySummary {
+        name: "铁板 \"A\"".to_string(),
+        location: Some("nauvis".to_string()),
+        solved: true,
+        power_consumption: 1000.0,
+        power_net: -1000.0,
+        science: 0.5,
+        ..Default::default()
+    };
+    nauvis
+        .raw
+        .insert(StatsKey::Item("iron-ore".to_string()), 2.0);
+    nauvis.pollution.insert("pollution".to_string(), 0.1);
+    nauvis.machines.insert("electric-furnace".to_string(), 3.0);
+    let mut water = nauvis.clone();
+    water.name = "水".to_string();
+    water.raw = IndexMap::from([(StatsKey::Fluid("water".to_string()), 100.0)]);
+    let platform = FactorySummary {
+        name: "平台".to_string(),
+        solved: false,
+        ..Default::default()
+    };
+    for summary in [nauvis, water, platform] {
+        dashboard.total.merge(&summary);
+        dashboard.factories.push(summary);
+    }
+
+    let export = MetricsExport::new(&dashboard);
+    assert_eq!(
+        export.surfaces.keys().collect::<Vec<_>>(),
+        ["nauvis", UNASSIGNED_SURFACE]
+    );
+    let nauvis = &export.surfaces["nauvis"];
+    assert_eq!(nauvis.factories.len(), 2);
+    assert!(nauvis.total.solved);
+    assert_eq!(nauvis.total.power_consumption_watts, 2000.0);
+    assert_eq!(nauvis.total.science_per_minute, 60.0);
+    assert_eq!(nauvis.total.machines["electric-furnace"], 6.0);
+    assert_eq!(nauvis.total.raw_items_per_second["iron-ore"], 2.0);
+    assert_eq!(nauvis.total.raw_fluids_per_second["water"], 100.0);
+    assert!((nauvis.total.pollution_per_minute["pollution"] - 12.0).abs() < 1e-9);
+    assert!(!export.surfaces[UNASSIGNED_SURFACE].total.solved);
+
+    let json: serde_json::Value =
+        serde_json::from_str(&export.render(MetricsFormat::Json)).unwrap();
+    assert_eq!(
+        json["surfaces"]["nauvis"]["factories"][1]["name"],
+        serde_json::json!("水")
+    );
+
+    let text = export.render(MetricsFormat::Prometheus);
+    assert!(text.contains("# TYPE metatorio_power_net_watts gauge"));
+    assert!(text.contains(
+        "metatorio_power_net_watts{surface=\"nauvis\",factory=\"铁板 \\\"A\\\"\"} -1000"
+    ));
+    assert!(text.contains(
+        "metatorio_raw_resource_per_second{surface=\"nauvis\",factory=\"水\",kind=\"fluid\",name=\"water\"} 100"
+    ));
+    assert!(text.contains("metatorio_factory_solved{surface=\"unassigned\",factory=\"平台\"} 0"));
+}
+
+#[test]
+fn test_metrics_command() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        MetricsCommand::parse(&args(&["--workspace", "a.json"])),
+        None
+    );
+    assert_eq!(
+        MetricsCommand::parse(&args(&["--export-metrics"])),
+        Some(Ok(MetricsCommand {
+            format: MetricsFormat::Json,
+            workspace: None,
+        }))
+    );
+    assert_eq!(
+        MetricsCommand::parse(&args(&[
+            "--export-metrics",
+            "prometheus",
+            "--workspace",
+            "a.json"
+        ])),
+        Some(Ok(MetricsCommand {
+            format: MetricsFormat::Prometheus,
+            workspace: Some("a.json".into()),
+        }))
+    );
+    assert_eq!(
+        MetricsCommand::parse(&args(&["--export-metrics=json", "--workspace=b.json"]))
+            .map(|command| command.map(|command| command.workspace)),
+        Some(Ok(Some("b.json".into())))
+    );
+    assert!(matches!(
+        MetricsCommand::parse(&args(&["--export-metrics=csv"])),
+        Some(Err(_))
+    ));
+    assert!(matches!(
+        MetricsCommand::parse(&args(&["--export-metrics", "--workspace"])),
+        Some(Err(AppError::Custom(_)))
+    ));
+    assert!(matches!(
+        MetricsCommand::parse(&args(&["--workspace", "--export-metrics"])),
+        Some(Err(AppError::Custom(_)))
+    ));
+}
@@ -0,0 +1,836 @@
+//! 检查工厂存档中引用的原型在当前上下文中是否存在，并按用户的选择替换或移除缺失的引用。
+
+use std::any::Any;
+
+use indexmap::IndexMap;
+
+use crate::factorio::{common::*, editor::planner::FactoryInstance, model::*};
+
+/// 替换候选的最大数量
+const MAX_SUBSTITUTES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrototypeKind {
+    Recipe,
+    CraftingMachine,
+    MiningDrill,
+    Resource,
+    OffshorePump,
+    Tile,
+    Boiler,
+    Reactor,
+    Generator,
+    FusionReactor,
+    FusionGenerator,
+    SolarPanel,
+    Accumulator,
+    LightningAttractor,
+    Thruster,
+    AsteroidCollector,
+    Turret,
+    Ammo,
+    Lab,
+    Technology,
+    Planet,
+    Module,
+    Beacon,
+    Fuel,
+    Item,
+    Fluid,
+    /// 品质按等级引用，名称为等级数字
+    Quality,
+}
+
+impl PrototypeKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PrototypeKind::Recipe => "配方",
+            PrototypeKind::CraftingMachine => "制造设备",
+            PrototypeKind::MiningDrill => "采矿设备",
+            PrototypeKind::Resource => "矿物",
+            PrototypeKind::OffshorePump => "抽水泵",
+            PrototypeKind::Tile => "地块",
+            PrototypeKind::Boiler => "锅炉",
+            PrototypeKind::Reactor => "反应堆",
+            PrototypeKind::Generator => "发电机",
+            PrototypeKind::FusionReactor => "聚变反应堆",
+            PrototypeKind::FusionGenerator => "聚变发电机",
+            PrototypeKind::SolarPanel => "太阳能板",
+            PrototypeKind::Accumulator => "蓄电池",
+            PrototypeKind::LightningAttractor => "集电器",
+            PrototypeKind::Thruster => "推进器",
+            PrototypeKind::AsteroidCollector => "小行星收集器",
+            PrototypeKind::Turret => "炮塔",
+            PrototypeKind::Ammo => "弹药",
+            PrototypeKind::Lab => "研究中心",
+            PrototypeKind::Technology => "科技",
+            PrototypeKind::Planet => "星球",
+            PrototypeKind::Module => "插件",
+            PrototypeKind::Beacon => "插件塔",
+            PrototypeKind::Fuel => "燃料",
+            PrototypeKind::Item => "物品",
+            PrototypeKind::Fluid => "流体",
+            PrototypeKind::Quality => "品质等级",
+        }
+    }
+
+    /// 当前上下文中该类别的全部原型名称
+    pub fn candidates(&self, ctx: &FactorioContext) -> Vec<String> {
+        let mut names: Vec<&String> = match self {
+            PrototypeKind::Recipe => ctx.recipes.keys().collect(),
+            PrototypeKind::CraftingMachine => ctx.crafters.keys().collect(),
+            PrototypeKind::MiningDrill => ctx.miners.keys().collect(),
+            PrototypeKind::Resource => ctx.resources.keys().collect(),
+            PrototypeKind::OffshorePump => ctx.offshore_pumps.keys().collect(),
+            PrototypeKind::Tile => ctx.tiles.keys().collect(),
+            PrototypeKind::Boiler => ctx.boilers.keys().collect(),
+            PrototypeKind::Reactor => ctx.reactors.keys().collect(),
+            PrototypeKind::Generator => ctx.generators.keys().collect(),
+            PrototypeKind::FusionReactor => ctx.fusion_reactors.keys().collect(),
+            PrototypeKind::FusionGenerator => ctx.fusion_generators.keys().collect(),
+            PrototypeKind::SolarPanel => ctx.solar_panels.keys().collect(),
+            PrototypeKind::Accumulator => ctx.accumulators.keys().collect(),
+            PrototypeKind::LightningAttractor => ctx.lightning_attractors.keys().collect(),
+            PrototypeKind::Thruster => ctx.thrusters.keys().collect(),
+            PrototypeKind::AsteroidCollector => ctx.asteroid_collectors.keys().collect(),
+            PrototypeKind::Turret => ctx.turrets.keys().collect(),
+            PrototypeKind::Ammo => ctx.items.keys().chain(ctx.fluids.keys()).collect(),
+            PrototypeKind::Lab => ctx.labs.keys().collect(),
+            PrototypeKind::Technology => ctx.technologies.keys().collect(),
+            PrototypeKind::Planet => ctx.planets.keys().collect(),
+            PrototypeKind::Module => ctx.modules.keys().collect(),
+            PrototypeKind::Beacon => ctx.beacons.keys().collect(),
+            PrototypeKind::Fuel => ctx.items.keys().chain(ctx.fluids.keys()).collect(),
+            PrototypeKind::Item => ctx.items.keys().collect(),
+            PrototypeKind::Fluid => ctx.fluids.keys().collect(),
+            PrototypeKind::Quality => {
+                return (0..ctx.qualities.len())
+                    .map(|level| level.to_string())
+                    .collect();
+            }
+        };
+        names.sort();
+        names.into_iter().cloned().collect()
+    }
+
+    pub fn exists(&self, ctx: &FactorioContext, name: &str) -> bool {
+        match self {
+            PrototypeKind::Recipe => ctx.recipes.contains_key(name),
+            PrototypeKind::CraftingMachine => ctx.crafters.contains_key(name),
+            PrototypeKind::MiningDrill => ctx.miners.contains_key(name),
+            PrototypeKind::Resource => ctx.resources.contains_key(name),
+            PrototypeKind::OffshorePump => ctx.offshore_pumps.contains_key(name),
+            PrototypeKind::Tile => ctx.tiles.contains_key(name),
+            PrototypeKind::Boiler => ctx.boilers.contains_key(name),
+            PrototypeKind::Reactor => ctx.reactors.contains_key(name),
+            PrototypeKind::Generator => ctx.generators.contains_key(name),
+            PrototypeKind::FusionReactor => ctx.fusion_reactors.contains_key(name),
+            PrototypeKind::FusionGenerator => ctx.fusion_generators.contains_key(name),
+            PrototypeKind::SolarPanel => ctx.solar_panels.contains_key(name),
+            PrototypeKind::Accumulator => ctx.accumulators.contains_key(name),
+            PrototypeKind::LightningAttractor => ctx.lightning_attractors.contains_key(name),
+            PrototypeKind::Thruster => ctx.thrusters.contains_key(name),
+            PrototypeKind::AsteroidCollector => ctx.asteroid_collectors.contains_key(name),
+            PrototypeKind::Turret => ctx.turrets.contains_key(name),
+            PrototypeKind::Ammo => ctx.items.contains_key(name) || ctx.fluids.contains_key(name),
+            PrototypeKind::Lab => ctx.labs.contains_key(name),
+            PrototypeKind::Technology => ctx.technologies.contains_key(name),
+            PrototypeKind::Planet => ctx.planets.contains_key(name),
+            PrototypeKind::Module => ctx.modules.contains_key(name),
+            PrototypeKind::Beacon => ctx.beacons.contains_key(name),
+            PrototypeKind::Fuel => ctx.items.contains_key(name) || ctx.fluids.contains_key(name),
+            PrototypeKind::Item => ctx.items.contains_key(name),
+            PrototypeKind::Fluid => ctx.fluids.contains_key(name),
+            // 普通品质总是可用，即使上下文中没有品质原型
+            PrototypeKind::Quality => name
+                .parse::<usize>()
+                .is_ok_and(|level| level == 0 || level < ctx.qualities.len()),
+        }
+    }
+}
+
+/// 存档中引用、但当前上下文中不存在的原型
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MissingPrototype {
+    pub kind: PrototypeKind,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationAction {
+    /// 移除引用；配方、设备与矿物缺失时移除整个机制
+    Remove,
+    Substitute(String),
+}
+
+/// 未选择设备时使用的占位名称，不视为缺失
+fn is_placeholder(name: &str) -> bool {
+    name == "entity-unknown"
+        || name == "recipe-unknown"
+        || name == "tile-unknown"
+        || name == "technology-unknown"
+}
+
+fn check(
+    missing: &mut IndexMap<MissingPrototype, usize>,
+    ctx: &FactorioContext,
+    kind: PrototypeKind,
+    name: &str,
+) {
+    if !is_placeholder(name) && !kind.exists(ctx, name) {
+        *missing
+            .entry(MissingPrototype {
+                kind,
+                name: name.to_string(),
+            })
+            .or_insert(0) += 1;
+    }
+}
+
+fn check_quality(
+    missing: &mut IndexMap<MissingPrototype, usize>,
+    ctx: &FactorioContext,
+    level: usize,
+) {
+    check(missing, ctx, PrototypeKind::Quality, &level.to_string());
+}
+
+/// 检查带品质的引用，名称与品质等级分别记录
+fn check_id(
+    missing: &mut IndexMap<MissingPrototype, usize>,
+    ctx: &FactorioContext,
+    kind: PrototypeKind,
+    id: &IdWithQuality,
+) {
+    check(missing, ctx, kind, &id.0);
+    check_quality(missing, ctx, id.1 as usize);
+}
+
+fn check_module_config(
+    missing: &mut IndexMap<MissingPrototype, usize>,
+    ctx: &FactorioContext,
+    module_config: &ModuleConfig,
+) {
+    for module in &module_config.modules {
+        check_id(missing, ctx, PrototypeKind::Module, module);
+    }
+    for beacon in &module_config.beacons {
+        check_id(missing, ctx, PrototypeKind::Beacon, &beacon.beacon);
+        for (module, _) in &beacon.modules {
+            check_id(missing, ctx, PrototypeKind::Module, module);
+        }
+    }
+}
+
+fn check_item(
+    missing: &mut IndexMap<MissingPrototype, usize>,
+    ctx: &FactorioContext,
+    item: &GenericItem,
+) {
+    match item {
+        GenericItem::Item(item) => check_id(missing, ctx, PrototypeKind::Item, item),
+        GenericItem::Fluid { name, .. } => check(missing, ctx, PrototypeKind::Fluid, name),
+        _ => {}
+    }
+}
+
+/// 收集单个机制引用的缺失原型
+pub fn validate_mechanic(
+    missing: &mut IndexMap<MissingPrototype, usize>,
+    mechanic: &FactorioMechanic,
+    ctx: &FactorioContext,
+) {
+    let any = mechanic as &dyn Any;
+    if let Some(config) = any.downcast_ref::<RecipeConfig>() {
+        check_id(missing, ctx, PrototypeKind::Recipe, &config.recipe);
+        check_id(
+            missing,
+            ctx,
+            PrototypeKind::CraftingMachine,
+            &config.machine,
+        );
+        check_module_config(missing, ctx, &config.module_config);
+        if let Some((fuel, quality)) = &config.instance_fuel {
+            check(missing, ctx, PrototypeKind::Fuel, fuel);
+            check_quality(missing, ctx, *quality as usize);
+        }
+    } else if let Some(config) = any.downcast_ref::<MiningConfig>() {
+        check(missing, ctx, PrototypeKind::Resource, &config.resource);
+        check_id(missing, ctx, PrototypeKind::MiningDrill, &config.machine);
+        check_module_config(missing, ctx, &config.module_config);
+        if let Some(fuel) = &config.instance_fuel {
+            check_id(missing, ctx, PrototypeKind::Fuel, fuel);
+        }
+    } else if let Some(config) = any.downcast_ref::<PumpjackConfig>() {
+        check(missing, ctx, PrototypeKind::Resource, &config.resource);
+        check_id(missing, ctx, PrototypeKind::MiningDrill, &config.machine);
+        check_module_config(missing, ctx, &config.module_config);
+        if let Some(fuel) = &config.instance_fuel {
+            check_id(missing, ctx, PrototypeKind::Fuel, fuel);
+        }
+    } else if let Some(config) = any.downcast_ref::<LabConfig>() {
+        check(missing, ctx, PrototypeKind::Technology, &config.technology);
+        check_id(missing, ctx, PrototypeKind::Lab, &config.machine);
+        check_module_config(missing, ctx, &config.module_config);
+        if let Some(fuel) = &config.instance_fuel {
+            check_id(missing, ctx, PrototypeKind::Fuel, fuel);
+        }
+    } else if let Some(config) = any.downcast_ref::<OffshorePumpConfig>() {
+        check(missing, ctx, PrototypeKind::Tile, &config.tile);
+        check_id(missing, ctx, PrototypeKind::OffshorePump, &config.machine);
+        if let Some(fuel) = &config.instance_fuel {
+            check_id(missing, ctx, PrototypeKind::Fuel, fuel);
+        }
+    } else if let Some(config) = any.downcast_ref::<BoilerConfig>() {
+        check_id(missing, ctx, PrototypeKind::Boiler, &config.machine);
+        if let Some(fuel) = &config.instance_fuel {
+            check_id(missing, ctx, PrototypeKind::Fuel, fuel);
+        }
+    } else if let Some(config) = any.downcast_ref::<ReactorConfig>() {
+        check_id(missing, ctx, PrototypeKind::Reactor, &config.machine);
+        if let Some(fuel) = &config.instance_fuel {
+            check_id(missing, ctx, PrototypeKind::Fuel, fuel);
+        }
+    } else if let Some(config) = any.downcast_ref::<GeneratorConfig>() {
+        check_id(missing, ctx, PrototypeKind::Generator, &config.machine);
+    } else if let Some(config) = any.downcast_ref::<FusionReactorConfig>() {
+        check_id(missing, ctx, PrototypeKind::FusionReactor, &config.machine);
+        if let Some(fuel) = &config.instance_fuel {
+            check_id(missing, ctx, PrototypeKind::Fuel, fuel);
+        }
+    } else if let Some(config) = any.downcast_ref::<FusionGeneratorConfig>() {
+        check_id(
+            missing,
+            ctx,
+            PrototypeKind::FusionGenerator,
+            &config.machine,
+        );
+    } else if let Some(config) = any.downcast_ref::<SolarPowerConfig>() {
+        check_id(missing, ctx, PrototypeKind::SolarPanel, &config.machine);
+        if let Some(accumulator) = &config.accumulator {
+            check_id(missing, ctx, PrototypeKind::Accumulator, accumulator);
+        }
+        check(missing, ctx, PrototypeKind::Planet, &config.planet);
+    } else if let Some(config) = any.downcast_ref::<LightningConfig>() {
+        check_id(
+            missing,
+            ctx,
+            PrototypeKind::LightningAttractor,
+            &config.machine,
+        );
+        check(missing, ctx, PrototypeKind::Planet, &config.planet);
+    } else if let Some(config) = any.downcast_ref::<SpawnerConfig>() {
+        check_id(
+            missing,
+            ctx,
+            PrototypeKind::CraftingMachine,
+            &config.machine,
+        );
+        if let Some(fuel) = &config.instance_fuel {
+            check_id(missing, ctx, PrototypeKind::Fuel, fuel);
+        }
+    } else if let Some(config) = any.downcast_ref::<ThrusterConfig>() {
+        check_id(missing, ctx, PrototypeKind::Thruster, &config.machine);
+    } else if let Some(config) = any.downcast_ref::<AsteroidCollectorConfig>() {
+        check_id(
+            missing,
+            ctx,
+            PrototypeKind::AsteroidCollector,
+            &config.machine,
+        );
+    } else if let Some(config) = any.downcast_ref::<DefenseConfig>() {
+        if let Some(ammo) = &config.ammo {
+            check_id(missing, ctx, PrototypeKind::Ammo, ammo);
+        }
+        check_id(missing, ctx, PrototypeKind::Turret, &config.machine);
+    }
+}
+
+/// 收集工厂中引用的缺失原型及其引用次数
+pub fn validate_factory(
+    factory: &FactoryInstance,
+    ctx: &FactorioContext,
+) -> IndexMap<MissingPrototype, usize> {
+    let mut missing = IndexMap::new();
+    for mechanic in factory.mechanics.values() {
+        validate_mechanic(&mut missing, mechanic.as_ref(), ctx);
+    }
+    for (item, _) in factory.external.iter().chain(factory.target.iter()).chain(
+        factory
+            .target_sets
+            .iter()
+            .flat_map(|target_set| target_set.targets.iter()),
+    ) {
+        check_item(&mut missing, ctx, item);
+    }
+    for target in &factory.quality_targets {
+        check_item(
+            &mut missing,
+            ctx,
+            &GenericItem::Item(target.item.as_str().into()),
+        );
+        check_quality(&mut missing, ctx, target.min_quality as usize);
+    }
+    missing
+}
+
+/// 按名称中相同的词段数量排序的替换候选
+pub fn suggest_substitutes(missing: &MissingPrototype, ctx: &FactorioContext) -> Vec<String> {
+    if missing.kind == PrototypeKind::Quality {
+        // 等级没有可比较的词段，从高到低列出当前上下文中的全部品质
+        let mut levels = missing.kind.candidates(ctx);
+        levels.reverse();
+        return levels;
+    }
+    let words = missing.name.split('-').collect::<Vec<_>>();
+    let mut scored = missing
+        .kind
+        .candidates(ctx)
+        .into_iter()
+        .filter_map(|candidate| {
+            let score = candidate
+                .split('-')
+                .filter(|word| words.contains(word))
+                .count();
+            (score > 0).then_some((score, candidate))
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    scored
+        .into_iter()
+        .take(MAX_SUBSTITUTES)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// 查询某个引用的处理方式，None 表示无需处理
+fn action_for<'a>(
+    actions: &'a IndexMap<MissingPrototype, MigrationAction>,
+    kind: PrototypeKind,
+    name: &str,
+) -> Option<&'a MigrationAction> {
+    actions.get(&MissingPrototype {
+        kind,
+        name: name.to_string(),
+    })
+}
+
+/// 对单个名称应用处理方式，返回 false 表示该引用应当被移除
+fn migrate_name(
+    actions: &IndexMap<MissingPrototype, MigrationAction>,
+    kind: PrototypeKind,
+    name: &mut String,
+) -> bool {
+    match action_for(actions, kind, name) {
+        None => true,
+        Some(MigrationAction::Remove) => false,
+        Some(MigrationAction::Substitute(substitute)) => {
+            *name = substitute.clone();
+            true
+        }
+    }
+}
+
+/// 对品质等级应用处理方式，移除时退回普通品质
+fn migrate_quality(actions: &IndexMap<MissingPrototype, MigrationAction>, quality: &mut u8) {
+    match action_for(actions, PrototypeKind::Quality, &quality.to_string()) {
+        None => {}
+        Some(MigrationAction::Remove) => *quality = 0,
+        Some(MigrationAction::Substitute(level)) => *quality = level.parse().unwrap_or(0),
+    }
+}
+
+/// 对带品质的引用应用处理方式，返回 false 表示该引用应当被移除
+fn migrate_id(
+    actions: &IndexMap<MissingPrototype, MigrationAction>,
+    kind: PrototypeKind,
+    id: &mut IdWithQuality,
+) -> bool {
+    migrate_quality(actions, &mut id.1);
+    migrate_name(actions, kind, &mut id.0)
+}
+
+fn migrate_module_config(
+    actions: &IndexMap<MissingPrototype, MigrationAction>,
+    module_config: &mut ModuleConfig,
+) {
+    module_config
+        .modules
+        .retain_mut(|module| migrate_id(actions, PrototypeKind::Module, module));
+    module_config.beacons.retain_mut(|beacon| {
+        beacon
+            .modules
+            .retain_mut(|(module, _)| migrate_id(actions, PrototypeKind::Module, module));
+        migrate_id(actions, PrototypeKind::Beacon, &mut beacon.beacon)
+    });
+}
+
+fn migrate_items(
+    actions: &IndexMap<MissingPrototype, MigrationAction>,
+    items: &mut Vec<(GenericItem, f64)>,
+) {
+    items.retain_mut(|(item, _)| match item {
+        GenericItem::Item(item) => migrate_id(actions, PrototypeKind::Item, item),
+        GenericItem::Fluid { name, .. } => migrate_name(actions, PrototypeKind::Fluid, name),
+        _ => true,
+    });
+}
+
+/// 按选择的处理方式修改工厂，之后应重新求解
+pub fn apply_migration(
+    factory: &mut FactoryInstance,
+    actions: &IndexMap<MissingPrototype, MigrationAction>,
+) {
+    factory.mechanics.retain(|_, mechanic| {
+        let any = mechanic.as_mut() as &mut dyn Any;
+        if let Some(config) = any.downcast_mut::<RecipeConfig>() {
+            migrate_module_config(actions, &mut config.module_config);
+            if let Some((fuel, quality)) = &mut config.instance_fuel {
+                let mut level = u8::try_from(*quality).unwrap_or(u8::MAX);
+                migrate_quality(actions, &mut level);
+                *quality = level as i32;
+                if !migrate_name(actions, PrototypeKind::Fuel, fuel) {
+                    config.instance_fuel = None;
+                }
+            }
+            migrate_id(actions, PrototypeKind::Recipe, &mut config.recipe)
+                && migrate_id(actions, PrototypeKind::CraftingMachine, &mut config.machine)
+        } else if let Some(config) = any.downcast_mut::<MiningConfig>() {
+            migrate_module_config(actions, &mut config.module_config);
+            if let Some(fuel) = &mut config.instance_fuel
+                && !migrate_id(actions, PrototypeKind::Fuel, fuel)
+            {
+                config.instance_fuel = None;
+            }
+            migrate_name(actions, PrototypeKind::Resource, &mut config.resource)
+                && migrate_id(actions, PrototypeKind::MiningDrill, &mut config.machine)
+        } else if let Some(config) = any.downcast_mut::<PumpjackConfig>() {
+            migrate_module_config(actions, &mut config.module_config);
+            if let Some(fuel) = &mut config.instance_fuel
+                && !migrate_id(actions, PrototypeKind::Fuel, fuel)
+            {
+                config.instance_fuel = None;
+            }
+            migrate_name(actions, PrototypeKind::Resource, &mut config.resource)
+                && migrate_id(actions, PrototypeKind::MiningDrill, &mut config.machine)
+        } else if let Some(config) = any.downcast_mut::<LabConfig>() {
+            migrate_module_config(actions, &mut config.module_config);
+            if let Some(fuel) = &mut config.instance_fuel
+                && !migrate_id(actions, PrototypeKind::Fuel, fuel)
+            {
+                config.instance_fuel = None;
+            }
+            migrate_name(actions, PrototypeKind::Technology, &mut config.technology)
+                && migrate_id(actions, PrototypeKind::Lab, &mut config.machine)
+        } else if let Some(config) = any.downcast_mut::<OffshorePumpConfig>() {
+            if let Some(fuel) = &mut config.instance_fuel
+                && !migrate_id(actions, PrototypeKind::Fuel, fuel)
+            {
+                config.instance_fuel = None;
+            }
+            migrate_name(actions, PrototypeKind::Tile, &mut config.tile)
+                && migrate_id(actions, PrototypeKind::OffshorePump, &mut config.machine)
+        } else if let Some(config) = any.downcast_mut::<BoilerConfig>() {
+            if let Some(fuel) = &mut config.instance_fuel
+                && !migrate_id(actions, PrototypeKind::Fuel, fuel)
+            {
+                config.instance_fuel = None;
+            }
+            migrate_id(actions, PrototypeKind::Boiler, &mut config.machine)
+        } else if let Some(config) = any.downcast_mut::<ReactorConfig>() {
+            if let Some(fuel) = &mut config.instance_fuel
+                && !migrate_id(actions, PrototypeKind::Fuel, fuel)
+            {
+                config.instance_fuel = None;
+            }
+            migrate_id(actions, PrototypeKind::Reactor, &mut config.machine)
+        } else if let Some(config) = any.downcast_mut::<GeneratorConfig>() {
+            migrate_id(actions, PrototypeKind::Generator, &mut config.machine)
+        } else if let Some(config) = any.downcast_mut::<FusionReactorConfig>() {
+            if let Some(fuel) = &mut config.instance_fuel
+                && !migrate_id(actions, PrototypeKind::Fuel, fuel)
+            {
+                config.instance_fuel = None;
+            }
+            migrate_id(actions, PrototypeKind::FusionReactor, &mut config.machine)
+        } else if let Some(config) = any.downcast_mut::<FusionGeneratorConfig>() {
+            migrate_id(actions, PrototypeKind::FusionGenerator, &mut config.machine)
+        } else if let Some(config) = any.downcast_mut::<SolarPowerConfig>() {
+            if let Some(accumulator) = &mut config.accumulator
+                && !migrate_id(actions, PrototypeKind::Accumulator, accumulator)
+            {
+                config.accumulator = None;
+            }
+            migrate_name(actions, PrototypeKind::Planet, &mut config.planet)
+                && migrate_id(actions, PrototypeKind::SolarPanel, &mut config.machine)
+        } else if let Some(config) = any.downcast_mut::<LightningConfig>() {
+            migrate_name(actions, PrototypeKind::Planet, &mut config.planet)
+                && migrate_id(
+                    actions,
+                    PrototypeKind::LightningAttractor,
+                    &mut config.machine,
+                )
+        } else if let Some(config) = any.downcast_mut::<SpawnerConfig>() {
+            if let Some(fuel) = &mut config.instance_fuel
+                && !migrate_id(actions, PrototypeKind::Fuel, fuel)
+            {
+                config.instance_fuel = None;
+            }
+            migrate_id(actions, PrototypeKind::CraftingMachine, &mut config.machine)
+        } else if let Some(config) = any.downcast_mut::<ThrusterConfig>() {
+            migrate_id(actions, PrototypeKind::Thruster, &mut config.machine)
+        } else if let Some(config) = any.downcast_mut::<AsteroidCollectorConfig>() {
+            migrate_id(
+                actions,
+                PrototypeKind::AsteroidCollector,
+                &mut config.machine,
+            )
+        } else if let Some(config) = any.downcast_mut::<DefenseConfig>() {
+            if let Some(ammo) = &mut config.ammo
+                && !migrate_id(actions, PrototypeKind::Ammo, ammo)
+            {
+                config.ammo = None;
+            }
+            migrate_id(actions, PrototypeKind::Turret, &mut config.machine)
+        } else {
+            true
+        }
+    });
+    migrate_items(actions, &mut factory.target);
+    migrate_items(actions, &mut factory.external);
+    factory.quality_targets.retain_mut(|target| {
+        migrate_quality(actions, &mut target.min_quality);
+        migrate_name(actions, PrototypeKind::Item, &mut target.item)
+    });
+    for target_set in &mut factory.target_sets {
+        migrate_items(actions, &mut target_set.targets);
+    }
+    factory.flow_cache.get_mut().unwrap().clear();
+}
+
+/// 等待用户确认的迁移方式，确认后由调用方对工厂执行 [`apply_migration`]
+pub struct PendingMigration {
+    /// 对话框中显示的工厂来源，如存档路径
+    pub source: String,
+    pub missing: IndexMap<MissingPrototype, usize>,
+    pub actions: IndexMap<MissingPrototype, MigrationAction>,
+    pub suggestions: IndexMap<MissingPrototype, Vec<String>>,
+}
+
+pub enum MigrationDecision {
+    Pending,
+    Apply,
+    Cancel,
+}
+
+impl PendingMigration {
+    pub fn new(
+        source: String,
+        missing: IndexMap<MissingPrototype, usize>,
+        ctx: &FactorioContext,
+    ) -> Self {
+        let actions = missing
+            .keys()
+            .map(|key| (key.clone(), MigrationAction::Remove))
+            .collect();
+        let suggestions = missing
+            .keys()
+            .map(|key| (key.clone(), suggest_substitutes(key, ctx)))
+            .collect();
+        Self {
+            source,
+            missing,
+            actions,
+            suggestions,
+        }
+    }
+
+    /// 显示迁移对话框，返回用户的选择
+    pub fn show(&mut self, ui: &mut egui::Ui, ctx: &FactorioContext) -> MigrationDecision {
+        let mut decision = MigrationDecision::Pending;
+        egui::Modal::new(egui::Id::new("factory-migration")).show(ui.ctx(), |ui| {
+            ui.heading("存档迁移");
+            ui.label(format!(
+                "{} 中引用了目标上下文中不存在的原型，请选择替换或移除。",
+                self.source
+            ));
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .max_height(384.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("factory-migration-grid")
+                        .striped(true)
+                        .num_columns(4)
+                        .spacing([16.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.strong("类别");
+                            ui.strong("名称");
+                            ui.strong("引用次数");
+                            ui.strong("处理方式");
+                            ui.end_row();
+                            for (missing, count) in &self.missing {
+                                ui.label(missing.kind.name());
+                                ui.label(&missing.name);
+                                ui.label(count.to_string());
+                                let action = self
+                                    .actions
+                                    .entry(missing.clone())
+                                    .or_insert(MigrationAction::Remove);
+                                let display = |action: &MigrationAction| match action {
+                                    MigrationAction::Remove
+                                        if missing.kind == PrototypeKind::Quality =>
+                                    {
+                                        "改为普通品质".to_string()
+                                    }
+                                    MigrationAction::Remove => "移除".to_string(),
+                                    MigrationAction::Substitute(level)
+                                        if missing.kind == PrototypeKind::Quality =>
+                                    {
+                                        let name = level
+                                            .parse::<usize>()
+                                            .ok()
+                                            .and_then(|level| ctx.qualities.get(level))
+                                            .map(|quality| quality.base.name.as_str())
+                                            .unwrap_or(level);
+                                        format!("替换为 {}", ctx.get_display_name("quality", name))
+                                    }
+                                    MigrationAction::Substitute(name) => {
+                                        let category = match missing.kind {
+                                            PrototypeKind::Recipe => "recipe",
+                                            PrototypeKind::Item => "item",
+                                            PrototypeKind::Fluid => "fluid",
+                                            PrototypeKind::Fuel
+                                                if ctx.fluids.contains_key(name) =>
+                                            {
+                                                "fluid"
+                                            }
+                                            PrototypeKind::Fuel | PrototypeKind::Module => "item",
+                                            _ => "entity",
+                                        };
+                                        format!("替换为 {}", ctx.get_display_name(category, name))
+                                    }
+                                };
+                                egui::ComboBox::from_id_salt(("factory-migration", missing))
+                                    .selected_text(display(action))
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(
+                                            action,
+                                            MigrationAction::Remove,
+                                            display(&MigrationAction::Remove),
+                                        );
+                                        for substitute in
+                                            self.suggestions.get(missing).into_iter().flatten()
+                                        {
+                                            let option =
+                                                MigrationAction::Substitute(substitute.clone());
+                                            let text = display(&option);
+                                            ui.selectable_value(action, option, text);
+                                        }
+                                    });
+                                ui.end_row();
+                            }
+                        });
+                });
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("应用").clicked() {
+                    decision = MigrationDecision::Apply;
+                }
+                if ui.button("取消").clicked() {
+                    decision = MigrationDecision::Cancel;
+                }
+            });
+        });
+        decision
+    }
+}
+
+#[test]
+fn test_migrate_factory() {
+    use crate::concept::MechanicId;
+
+    let ctx = FactorioContext::test_load();
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let kept = MechanicId::generate();
+    let removed = MechanicId::generate();
+    factory.mechanics.insert(
+        kept,
+        Box::new(RecipeConfig {
+            recipe: "iron-gear-wheel".into(),
+            machine: "assembling-machine-9".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+    factory.mechanics.insert(
+        removed,
+        Box::new(RecipeConfig {
+            recipe: "removed-recipe".into(),
+            ..Default::default()
+        }),
+    );
+    factory
+        .target
+        .push((GenericItem::Item("removed-item".into()), 1.0));
+    let missing = validate_factory(&factory, &ctx);
+    assert_eq!(missing.len(), 3);
+    let machine = MissingPrototype {
+        kind: PrototypeKind::CraftingMachine,
+        name: "assembling-machine-9".to_string(),
+    };
+    assert!(suggest_substitutes(&machine, &ctx).contains(&"assembling-machine-2".to_string()));
+    let mut actions: IndexMap<_, _> = missing
+        .keys()
+        .map(|key| (key.clone(), MigrationAction::Remove))
+        .collect();
+    actions.insert(
+        machine,
+        MigrationAction::Substitute("assembling-machine-2".to_string()),
+    );
+    apply_migration(&mut factory, &actions);
+    assert!(validate_factory(&factory, &ctx).is_empty());
+    assert!(factory.mechanics.contains_key(&kept));
+    assert!(!factory.mechanics.contains_key(&removed));
+    assert!(factory.target.is_empty());
+}
+
+#[test]
+fn test_migrate_quality() {
+    use crate::concept::{AsFlow, MechanicId};
+
+    let ctx = FactorioContext::test_load();
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let id = MechanicId::generate();
+    let level = ctx.qualities.len() as u8 + 2;
+    let mut module_config = ModuleConfig::new();
+    module_config
+        .modules
+        .push(IdWithQuality("speed-module".to_string(), level));
+    let config = RecipeConfig {
+        recipe: "iron-gear-wheel".into(),
+        machine: IdWithQuality("assembling-machine-2".to_string(), level),
+        module_config,
+        instance_fuel: None,
+    };
+    // 迁移前按普通品质计算，不会越界
+    assert!(!config.as_flow(&ctx).is_empty());
+    factory.mechanics.insert(id, Box::new(config));
+    let missing = validate_factory(&factory, &ctx);
+    let quality = MissingPrototype {
+        kind: PrototypeKind::Quality,
+        name: level.to_string(),
+    };
+    assert_eq!(missing.get(&quality), Some(&2));
+    assert_eq!(
+        suggest_substitutes(&quality, &ctx).first(),
+        Some(&(ctx.qualities.len() - 1).to_string())
+    );
+    let actions = IndexMap::from([(quality, MigrationAction::Remove)]);
+    apply_migration(&mut factory, &actions);
+    assert!(validate_factory(&factory, &ctx).is_empty());
+    let config = (factory.mechanics[&id].as_ref() as &dyn Any)
+        .downcast_ref::<RecipeConfig>()
+        .unwrap();
+    assert_eq!(config.machine.1, 0);
+    assert_eq!(config.module_config.modules[0].1, 0);
+}
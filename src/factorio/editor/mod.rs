@@ -1,7 +1,30 @@
+pub mod autocomplete;
+pub mod browser;
+pub mod buildlist;
+pub mod bulk;
 pub mod clipboard;
+pub mod compare;
+pub mod dashboard;
+pub mod explorer;
+pub mod filter;
+pub mod flowcache;
 pub mod hover;
 pub mod icon;
+pub mod icon_cache;
+pub mod ingame;
+pub mod inspector;
+pub mod lint;
+pub mod metrics;
+pub mod migrate;
 pub mod modal;
 pub mod planner;
+pub mod preset;
+pub mod price;
+pub mod qualitytarget;
+pub mod rampup;
 pub mod selector;
+pub mod stats;
 pub mod style;
+pub mod tag;
+pub mod template;
+pub mod usage;
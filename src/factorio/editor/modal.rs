@@ -1,12 +1,13 @@
 use egui::ModalResponse;
 
 use crate::{
-    concept::{ItemIdent, Mechanic, MechanicProvider, MechanicSender},
+    concept::{Flow, ItemIdent, Mechanic, MechanicProvider, MechanicSender},
     factorio::{
-        FactorioContext, IdWithQuality,
-        selector::{FilterFn, HoverUi, ItemSelector, ItemWithQualitySelector},
+        FactorioContext, GenericItem, IdWithQuality,
+        selector::{BadgeFn, FilterFn, HoverUi, ItemSelector, ItemWithQualitySelector},
         style::card_frame,
     },
+    tutorial::{self, TutorialStep},
 };
 
 pub fn show_modal<R>(
@@ -46,6 +47,7 @@ pub struct ItemSelectorModal<'a> {
     current: Option<&'a mut String>,
     output: Option<&'a mut Option<String>>,
     hover: Option<Box<HoverUi<'a>>>,
+    badge: Option<Box<BadgeFn<'a>>>,
     changed: Option<&'a mut bool>,
 }
 
@@ -65,6 +67,7 @@ impl<'a> ItemSelectorModal<'a> {
             current: None,
             output: None,
             hover: None,
+            badge: None,
             toggle: false,
             changed: None,
         }
@@ -97,6 +100,14 @@ impl<'a> ItemSelectorModal<'a> {
         self
     }
 
+    pub fn with_badge(
+        mut self,
+        badge: impl Fn(&str, &FactorioContext) -> Option<String> + 'a,
+    ) -> Self {
+        self.badge = Some(Box::new(badge));
+        self
+    }
+
     pub fn notify_change(mut self, changed: &'a mut bool) -> Self {
         self.changed = Some(changed);
         self
@@ -146,6 +157,9 @@ impl egui::Widget for ItemSelectorModal<'_> {
             if let Some(hover) = self.hover {
                 widget = widget.with_hover(hover);
             }
+            if let Some(badge) = self.badge {
+                widget = widget.with_badge(badge);
+            }
             if let Some(changed) = self.changed {
                 widget = widget.notify_change(changed);
             }
@@ -176,6 +190,7 @@ pub struct ItemWithQualitySelectorModal<'a> {
     current: Option<&'a mut IdWithQuality>,
     output: Option<&'a mut Option<IdWithQuality>>,
     hover: Option<Box<HoverUi<'a>>>,
+    badge: Option<Box<BadgeFn<'a>>>,
     changed: Option<&'a mut bool>,
 }
 
@@ -196,6 +211,7 @@ impl<'a> ItemWithQualitySelectorModal<'a> {
             current: None,
             output: None,
             hover: None,
+            badge: None,
             changed: None,
         }
     }
@@ -227,6 +243,14 @@ impl<'a> ItemWithQualitySelectorModal<'a> {
         self
     }
 
+    pub fn with_badge(
+        mut self,
+        badge: impl Fn(&str, &FactorioContext) -> Option<String> + 'a,
+    ) -> Self {
+        self.badge = Some(Box::new(badge));
+        self
+    }
+
     pub fn notify_change(mut self, changed: &'a mut bool) -> Self {
         self.changed = Some(changed);
         self
@@ -256,6 +280,9 @@ impl egui::Widget for ItemWithQualitySelectorModal<'_> {
             if let Some(hover) = self.hover {
                 widget = widget.with_hover(hover);
             }
+            if let Some(badge) = self.badge {
+                widget = widget.with_badge(badge);
+            }
             if self.output.is_some() {
                 widget = widget.with_output(&mut degenerated);
             }
@@ -306,6 +333,9 @@ impl egui::Widget for ItemWithQualitySelectorModal<'_> {
             if let Some(hover) = self.hover {
                 widget = widget.with_hover(hover);
             }
+            if let Some(badge) = self.badge {
+                widget = widget.with_badge(badge);
+            }
             if let Some(changed) = self.changed {
                 widget = widget.notify_change(changed);
             }
@@ -326,6 +356,99 @@ impl egui::Widget for ItemWithQualitySelectorModal<'_> {
     }
 }
 
+/// 推荐列表默认展示的数量，其余收起在“显示更多”中
+pub const HINT_LIMIT: usize = 5;
+
+/// 推荐的相关程度，越小越靠前。参数为推荐机制的物料流、缺口或盈余的物品及其数量
+pub type HintRanker<I> = fn(&Flow<I>, &I, f64) -> u8;
+
+/// 弥补缺口时取产出，消耗盈余时取消耗
+fn helpful_sign(amount: f64) -> f64 {
+    if amount < 0.0 { 1.0 } else { -1.0 }
+}
+
+/// 通用的相关程度：该物品是机制在该方向上最大的一项时为 0，否则为 1
+pub fn main_output_first<I: ItemIdent>(flow: &Flow<I>, item: &I, amount: f64) -> u8 {
+    let sign = helpful_sign(amount);
+    let main = flow
+        .iter()
+        .filter(|(_, value)| **value * sign > 0.0)
+        .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()));
+    match main {
+        Some((main, _)) if main == item => 0,
+        _ => 1,
+    }
+}
+
+/// 异星工厂的相关程度：直接以该物品为主产物的配方为 0，
+/// 主产物是该物品的其他品质或同时产出其他品质的为 1，该物品只是副产物的为 2。
+/// 电力与热量等能量项不参与主产物的判断
+pub fn hint_relevance(flow: &Flow<GenericItem>, item: &GenericItem, amount: f64) -> u8 {
+    let sign = helpful_sign(amount);
+    let helpful = || {
+        flow.iter().filter(|(key, value)| {
+            **value * sign > 0.0 && matches!(key, GenericItem::Item(_) | GenericItem::Fluid { .. })
+        })
+    };
+    let quality_variant = |other: &GenericItem| match (item, other) {
+        (GenericItem::Item(IdWithQuality(name, quality)), GenericItem::Item(other)) => {
+            other.0 == *name && other.1 != *quality
+        }
+        _ => false,
+    };
+    let main = helpful().max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()));
+    match main {
+        Some((main, _)) if main == item && !helpful().any(|(key, _)| quality_variant(key)) => 0,
+        Some((main, _)) if main == item || quality_variant(main) => 1,
+        _ => 2,
+    }
+}
+
+/// 两个物料流涉及的物品相同且按该物品的速率归一后各项相等，视为重复的推荐
+fn same_proportions<I: ItemIdent>(a: &Flow<I>, b: &Flow<I>, item: &I) -> bool {
+    let (Some(scale_a), Some(scale_b)) = (a.get(item), b.get(item)) else {
+        return false;
+    };
+    if *scale_a == 0.0 || *scale_b == 0.0 || a.len() != b.len() {
+        return false;
+    }
+    a.iter().all(|(key, value)| {
+        b.get(key).is_some_and(|other| {
+            let (x, y) = (value / scale_a, other / scale_b);
+            (x - y).abs() <= 1e-9 * x.abs().max(y.abs()).max(1.0)
+        })
+    })
+}
+
+/// 按相关程度稳定排序并去除重复的推荐，相关程度相同的保持来源给出的顺序
+pub fn rank_hints<I: ItemIdent, C: 'static>(
+    hints: Vec<Box<dyn Mechanic<GameContext = C, ItemIdentType = I> + 'static>>,
+    ctx: &C,
+    item: &I,
+    amount: f64,
+    ranker: HintRanker<I>,
+) -> Vec<Box<dyn Mechanic<GameContext = C, ItemIdentType = I> + 'static>> {
+    let mut ranked = hints
+        .into_iter()
+        .map(|hint| {
+            let flow = hint.as_flow(ctx);
+            (ranker(&flow, item, amount), flow, hint)
+        })
+        .collect::<Vec<_>>();
+    ranked.sort_by_key(|(rank, _, _)| *rank);
+    let mut kept: Vec<(Flow<I>, _)> = Vec::with_capacity(ranked.len());
+    for (_, flow, hint) in ranked {
+        if kept
+            .iter()
+            .any(|(existing, _)| same_proportions(existing, &flow, item))
+        {
+            continue;
+        }
+        kept.push((flow, hint));
+    }
+    kept.into_iter().map(|(_, hint)| hint).collect()
+}
+
 pub struct HintModal<'a, I: ItemIdent, C: 'static> {
     ctx: &'a C,
     id: egui::Id,
@@ -333,6 +456,7 @@ pub struct HintModal<'a, I: ItemIdent, C: 'static> {
     flow_sender: &'a MechanicSender<I, C>,
     hint_flows: &'a mut Vec<Box<dyn Mechanic<GameContext = C, ItemIdentType = I> + 'static>>,
     editor_sources: &'a [Box<dyn MechanicProvider<ItemIdentType = I, GameContext = C>>],
+    ranker: HintRanker<I>,
 }
 
 impl<'a, I: ItemIdent, C: 'static> HintModal<'a, I, C> {
@@ -350,17 +474,25 @@ impl<'a, I: ItemIdent, C: 'static> HintModal<'a, I, C> {
             flow_sender,
             hint_flows,
             editor_sources,
+            ranker: main_output_first,
         }
     }
 
+    /// 替换推荐的排序方式，需在 with_update 之前调用
+    pub fn with_ranker(mut self, ranker: HintRanker<I>) -> Self {
+        self.ranker = ranker;
+        self
+    }
+
     pub fn with_update(mut self, update: bool, item: &'a I, amount: f64) -> Self {
         if update {
             self.toggle = true;
-            self.hint_flows.clear();
-            for source in self.editor_sources {
-                self.hint_flows
-                    .extend(source.hint_populate(self.ctx, item, amount));
-            }
+            let hints = self
+                .editor_sources
+                .iter()
+                .flat_map(|source| source.hint_populate(self.ctx, item, amount))
+                .collect();
+            *self.hint_flows = rank_hints(hints, self.ctx, item, amount, self.ranker);
         } else {
             self.toggle = false;
         }
@@ -378,7 +510,10 @@ impl<'a, I: ItemIdent, C: 'static> egui::Widget for HintModal<'a, I, C> {
                 if self.hint_flows.is_empty() {
                     ui.label("无推荐配方");
                 } else {
-                    for hint_flow in self.hint_flows.iter_mut() {
+                    let hint_card = |ui: &mut egui::Ui,
+                                     hint_flow: &mut Box<
+                        dyn Mechanic<GameContext = C, ItemIdentType = I> + 'static,
+                    >| {
                         card_frame(ui).show(ui, |ui| {
                             ui.set_min_width(ui.available_width());
                             ui.horizontal(|ui| {
@@ -386,8 +521,23 @@ impl<'a, I: ItemIdent, C: 'static> egui::Widget for HintModal<'a, I, C> {
                             });
                             if ui.button("添加").clicked() {
                                 self.flow_sender.send(hint_flow.clone()).unwrap();
+                                tutorial::complete(TutorialStep::AcceptHint);
                             }
                         });
+                    };
+                    let limit = HINT_LIMIT.min(self.hint_flows.len());
+                    let (shown, more) = self.hint_flows.split_at_mut(limit);
+                    for hint_flow in shown {
+                        hint_card(ui, hint_flow);
+                    }
+                    if !more.is_empty() {
+                        egui::CollapsingHeader::new(format!("显示更多（{}）", more.len()))
+                            .id_salt(self.id.with("more"))
+                            .show(ui, |ui| {
+                                for hint_flow in more {
+                                    hint_card(ui, hint_flow);
+                                }
+                            });
                     }
                 }
             });
@@ -396,3 +546,47 @@ impl<'a, I: ItemIdent, C: 'static> egui::Widget for HintModal<'a, I, C> {
         ui.response().clone()
     }
 }
+
+#[test]
+fn test_rank_hints() {
+    use crate::factorio::{FactorioMechanic, ModuleConfig, RecipeConfig};
+    let ctx = FactorioContext::test_load();
+    let recipe = |recipe: IdWithQuality, machine: &str| -> Box<FactorioMechanic> {
+        Box::new(RecipeConfig {
+            recipe,
+            machine: machine.into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        })
+    };
+    let gear = GenericItem::Item("iron-gear-wheel".into());
+    let hints = vec![
+        recipe("electronic-circuit".into(), "assembling-machine-2"),
+        recipe("iron-gear-wheel".into(), "assembling-machine-2"),
+        recipe("iron-gear-wheel".into(), "assembling-machine-2"),
+        recipe("iron-gear-wheel".into(), "assembling-machine-3"),
+    ];
+    // 齿轮缺口时直接产出齿轮的配方排在前面；完全相同的推荐只保留一个，
+    // 机器不同导致单位耗电不同的推荐都保留
+    let ranked = rank_hints(hints, &ctx, &gear, -1.0, hint_relevance);
+    assert_eq!(ranked.len(), 3);
+    let flows = ranked
+        .iter()
+        .map(|hint| hint.as_flow(&ctx))
+        .collect::<Vec<_>>();
+    assert!(flows[0][&gear] > 0.0);
+    assert!(flows[1][&gear] > 0.0);
+    assert!(!flows[2].contains_key(&gear));
+
+    let mut flow = Flow::new();
+    flow.insert(gear.clone(), 1.0);
+    flow.insert(GenericItem::Item("iron-plate".into()), -2.0);
+    assert_eq!(hint_relevance(&flow, &gear, -1.0), 0);
+    flow.insert(
+        GenericItem::Item(("iron-gear-wheel".to_string(), 1).into()),
+        0.1,
+    );
+    assert_eq!(hint_relevance(&flow, &gear, -1.0), 1);
+    flow.insert(GenericItem::Item("copper-plate".into()), 5.0);
+    assert_eq!(hint_relevance(&flow, &gear, -1.0), 2);
+}
@@ -3,46 +3,217 @@ use crate::{
     dyn_serde::*,
     factorio::{
         common::*,
-        editor::{icon::*, modal::*},
+        editor::{
+            autocomplete::*, browser::PrototypeBrowserView, buildlist::*, bulk::*, clipboard::*,
+            compare::*, dashboard::*, explorer::*, filter::MechanicFilter, flowcache::FlowCache,
+            icon::*, icon_cache, ingame::*, inspector::*, lint::*, migrate::*, modal::*, preset::*,
+            price::*, qualitytarget::*, rampup::*, stats::*, tag::*, template::FACTORY_TEMPLATES,
+            usage::FactoryUsage,
+        },
         format::*,
         model::*,
         style::card_frame,
     },
+    i18n::{self, Language, tr, trf},
+    settings::RateUnit,
     solver::*,
+    tutorial::{self, TutorialStep},
 };
 
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
+
+/// 电网取电的默认价格（每兆焦）
+pub const DEFAULT_GRID_PRICE: f64 = 0.01;
 
 lazy_static::lazy_static! {
-    static ref MECHANIC_REGISTRY: DynDeserializeRegistry<FactorioMechanic> = {
+    pub(crate) static ref MECHANIC_REGISTRY: DynDeserializeRegistry<FactorioMechanic> = {
         let mut registry = DynDeserializeRegistry::default();
         RecipeConfig::register(&mut registry);
         MiningConfig::register(&mut registry);
+        PumpjackConfig::register(&mut registry);
+        LabConfig::register(&mut registry);
+        OffshorePumpConfig::register(&mut registry);
+        BoilerConfig::register(&mut registry);
+        ReactorConfig::register(&mut registry);
+        GeneratorConfig::register(&mut registry);
+        FusionReactorConfig::register(&mut registry);
+        FusionGeneratorConfig::register(&mut registry);
+        SolarPowerConfig::register(&mut registry);
+        LightningConfig::register(&mut registry);
+        SpawnerConfig::register(&mut registry);
+        AsteroidCollectorConfig::register(&mut registry);
+        ThrusterConfig::register(&mut registry);
+        DefenseConfig::register(&mut registry);
+        GenericUserMechanic::register(&mut registry);
         registry
     };
     static ref MECHANIC_PROVIDER_REGISTRY: DynDeserializeRegistry<FactorioMechanicProvider> = {
         let mut registry = DynDeserializeRegistry::default();
         RecipeConfigProvider::register(&mut registry);
         MiningConfigProvider::register(&mut registry);
+        PumpjackConfigProvider::register(&mut registry);
+        LabConfigProvider::register(&mut registry);
+        OffshorePumpConfigProvider::register(&mut registry);
+        BoilerConfigProvider::register(&mut registry);
+        ReactorConfigProvider::register(&mut registry);
+        GeneratorConfigProvider::register(&mut registry);
+        FusionReactorConfigProvider::register(&mut registry);
+        FusionGeneratorConfigProvider::register(&mut registry);
+        SolarPowerConfigProvider::register(&mut registry);
+        LightningConfigProvider::register(&mut registry);
+        SpawnerConfigProvider::register(&mut registry);
+        AsteroidCollectorConfigProvider::register(&mut registry);
+        ThrusterConfigProvider::register(&mut registry);
+        DefenseConfigProvider::register(&mut registry);
+        RecyclerConfigProvider::register(&mut registry);
+        UserMechanicProvider::register(&mut registry);
         registry
     };
+    static ref SOLVER_CACHE: std::sync::Arc<SolverCache<MechanicId>> =
+        std::sync::Arc::new(SolverCache::new(256));
+    /// 所有工厂共享的求解线程池，线程数不超过 SOLVER_WORKERS
+    static ref SOLVER_POOL: SolverPool<GenericItem, MechanicId> = SolverPool::new(
+        std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(SOLVER_WORKERS),
+        Some(SOLVER_CACHE.clone()),
+    );
 }
 
+/// 求解线程池的最大线程数
+const SOLVER_WORKERS: usize = 4;
+
 pub struct FactoryInstance {
     pub name: String,
+    /// 工厂的备注，记录设计思路等
+    pub notes: String,
+    /// 标签栏中显示的颜色标签
+    pub tag: Option<FactoryTag>,
+    pub status: FactoryStatus,
+    /// 当前目标模式下的优化目标
     pub target: Vec<(GenericItem, f64)>,
+    /// 共用同一组机制的多个目标模式，当前模式的目标以 target 为准
+    pub target_sets: Vec<TargetPreset>,
+    pub active_target_set: usize,
+    /// 按品质展开的目标，各目标模式共用
+    pub quality_targets: Vec<QualityTarget>,
+    /// 各目标物品的约束方式，未列出的物品按恰好满足处理
+    pub target_modes: IndexMap<GenericItem, TargetMode>,
+    /// 各物品盈余的处理方式，未列出的物品允许盈余
+    pub byproduct_policies: IndexMap<GenericItem, ByproductPolicy<MechanicId>>,
     pub external: Vec<(GenericItem, f64)>,
-    pub solution: (Flow<usize>, f64),
+    /// 选用的共享价格表名称，表中列出的额外输入按表中价格计算
+    pub price_table: Option<String>,
+    /// 允许从电网取电，电力缺口按每兆焦的价格作为额外输入
+    pub grid_import: bool,
+    pub grid_price: f64,
+    pub solution: (Flow<MechanicId>, f64),
     pub total_flow: Flow<GenericItem>,
     /// Cached sorted keys for total_flow to avoid sorting every frame
     pub total_flow_sorted_keys: Vec<GenericItem>,
+    /// 已使用的配方与物品，供选择器标注
+    pub usage: std::sync::Arc<FactoryUsage>,
     pub mechanic_providers: Vec<Box<FactorioMechanicProvider>>,
-    pub mechanics: Vec<Box<FactorioMechanic>>,
+    pub mechanics: IndexMap<MechanicId, Box<FactorioMechanic>>,
+    /// 锁定为固定台数的机制，求解时作为常数项，只优化其余机制
+    pub locked: IndexMap<MechanicId, f64>,
+    /// 各机制的备注，记录选择该机器或插件的原因
+    pub mechanic_notes: IndexMap<MechanicId, String>,
+    /// 折叠为单行摘要的机制卡片
+    pub collapsed: IndexSet<MechanicId>,
+    /// 机制卡片上次绘制的高度，按是否折叠区分；滚动区域外的卡片按该高度占位而不绘制
+    pub card_heights: std::collections::HashMap<(MechanicId, bool), f32>,
+    /// 按全局加成计算的机制物料流缓存
+    pub flow_cache: std::sync::Mutex<FlowCache>,
     pub mechanic_suggestions: Vec<Box<FactorioMechanic>>,
+    /// 右键总物料流中的物品时打开的备选方案列表
+    pub alternatives: AlternativesExplorer,
+    /// 自动补全缺失原料时的偏好
+    pub chain_preference: ChainPreference,
+    pub mechanic_filter: MechanicFilter,
+    /// 目标物品选择器只显示当前上下文中能够生产的物品
+    pub target_producible_only: bool,
+    /// 机制卡片显示机器台数与每台速率，而不是求解得到的倍数与总速率
+    pub show_machine_count: bool,
+    /// 总物料流中同时显示概率产物的波动与平滑所需的缓冲
+    pub show_variance: bool,
+    /// 波动分析的平滑窗口（秒）
+    pub variance_window: f64,
+    /// 点击物品图标后打开的物料流检查器
+    pub inspector: ItemInspector,
+    /// 从机制卡片打开建议或复制时记录来源机制，新加入的机制插入到它之后
+    pub insert_after: Option<MechanicId>,
+    /// 机制卡片中勾选的机制，用于批量编辑
+    pub selected: IndexSet<MechanicId>,
+    /// 上次批量编辑使用的模板
+    pub bulk_template: BulkEditTemplate,
+    /// 用于对比的求解结果快照
+    pub solution_snapshot: Option<SolutionSnapshot>,
+    /// 健康报告中开启的检查规则
+    pub lint_settings: LintSettings,
+    /// 地表需要供暖，放置的实体按 heating_energy 持续消耗热量
+    pub requires_heating: bool,
+    /// 废料经济模式：所有物品与流体都不允许盈余，多余产物必须由回收等机制消耗
+    pub scrap_economy: bool,
+    /// 工厂所在的星球或太空平台，用于检查配方与机器的地表条件
+    pub location: Option<String>,
+    /// 太空平台停留的星球轨道或航行的航线，决定小行星的生成频率
+    pub route: Option<String>,
+    /// 采矿设置与已研究的加成科技
+    pub bonuses: GlobalBonuses,
+    /// 按物品吞吐量计入的物流开销
+    pub logistics: LogisticsOverhead,
+    /// 可用的机器，限制机器选择器与推荐的范围
+    pub machine_whitelist: MachineWhitelist,
+    /// 是否把插件与插件塔的摊销成本计入物料流
+    pub module_cost: ModuleCost,
+    /// 物料流的显示单位，为空时跟随全局设置
+    pub rate_unit: Option<RateUnit>,
+    /// 等待 PlannerView 发送到主界面的子视图
+    pub pending_subviews: Vec<Box<dyn Subview>>,
+    /// 与求解线程共享，耗时较长的求解完成时据此通知
+    pub solve_notice: std::sync::Arc<std::sync::Mutex<SolveNotice>>,
     pub mechanic_receiver: std::sync::mpsc::Receiver<Box<FactorioMechanic>>,
     pub mechanic_sender: std::sync::mpsc::Sender<Box<FactorioMechanic>>,
-    pub arg_sender: std::sync::mpsc::Sender<SolverArgs<GenericItem, usize>>,
-    pub solution_receiver: std::sync::mpsc::Receiver<SolverSolution<usize>>,
+    /// 在共享线程池中求解的句柄，工厂关闭时随之丢弃，尚未完成的求解被取消
+    pub solver: SolverHandle<GenericItem, MechanicId>,
+    pub solution_receiver: std::sync::mpsc::Receiver<SolverSolution<MechanicId>>,
+}
+
+impl FactoryInstance {
+    /// 序列化时写入的字段，新增字段时需要同步更新
+    const SERIALIZED_FIELDS: &[&str] = &[
+        "name",
+        "notes",
+        "tag",
+        "status",
+        "target",
+        "target_sets",
+        "active_target_set",
+        "quality_targets",
+        "external",
+        "price_table",
+        "grid_import",
+        "grid_price",
+        "target_modes",
+        "mechanics",
+        "mechanic_providers",
+        "lint",
+        "requires_heating",
+        "scrap_economy",
+        "location",
+        "route",
+        "mining",
+        "research",
+        "logistics",
+        "machine_whitelist",
+        "module_cost",
+        "rate_unit",
+        "locked",
+        "mechanic_notes",
+        "collapsed",
+        "byproduct_policies",
+    ];
 }
 
 impl serde::Serialize for FactoryInstance {
@@ -50,16 +221,114 @@ impl serde::Serialize for FactoryInstance {
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct("FactoryInstance", 5)?;
+        let mut state =
+            serializer.serialize_struct("FactoryInstance", Self::SERIALIZED_FIELDS.len())?;
         serde::ser::SerializeStruct::serialize_field(&mut state, "name", &self.name)?;
+        serde::ser::SerializeStruct::serialize_field(&mut state, "notes", &self.notes)?;
+        serde::ser::SerializeStruct::serialize_field(&mut state, "tag", &self.tag)?;
+        serde::ser::SerializeStruct::serialize_field(&mut state, "status", &self.status)?;
         serde::ser::SerializeStruct::serialize_field(&mut state, "target", &self.target)?;
+        serde::ser::SerializeStruct::serialize_field(
+            &mut state,
+            "target_sets",
+            &self.synced_target_sets(),
+        )?;
+        serde::ser::SerializeStruct::serialize_field(
+            &mut state,
+            "active_target_set",
+            &self.active_target_set,
+        )?;
+        serde::ser::SerializeStruct::serialize_field(
+            &mut state,
+            "quality_targets",
+            &self.quality_targets,
+        )?;
         serde::ser::SerializeStruct::serialize_field(&mut state, "external", &self.external)?;
-        serde::ser::SerializeStruct::serialize_field(&mut state, "mechanics", &self.mechanics)?;
+        serde::ser::SerializeStruct::serialize_field(&mut state, "price_table", &self.price_table)?;
+        serde::ser::SerializeStruct::serialize_field(&mut state, "grid_import", &self.grid_import)?;
+        serde::ser::SerializeStruct::serialize_field(&mut state, "grid_price", &self.grid_price)?;
+        let target_modes = self
+            .target_modes
+            .iter()
+            .filter(|(_, mode)| **mode != TargetMode::Exact)
+            .collect::<Vec<_>>();
+        serde::ser::SerializeStruct::serialize_field(&mut state, "target_modes", &target_modes)?;
+        // 机制本身不知道自己的标识符，序列化时将其写入 id 字段
+        let mechanics = self
+            .mechanics
+            .iter()
+            .map(|(id, mechanic)| {
+                let mut value =
+                    serde_json::to_value(mechanic).map_err(serde::ser::Error::custom)?;
+                if let Some(object) = value.as_object_mut() {
+                    object.insert("id".to_string(), serde_json::Value::from(id.0));
+                }
+                Ok(value)
+            })
+            .collect::<Result<Vec<_>, S::Error>>()?;
+        serde::ser::SerializeStruct::serialize_field(&mut state, "mechanics", &mechanics)?;
         serde::ser::SerializeStruct::serialize_field(
             &mut state,
             "mechanic_providers",
             &self.mechanic_providers,
         )?;
+        serde::ser::SerializeStruct::serialize_field(&mut state, "lint", &self.lint_settings)?;
+        serde::ser::SerializeStruct::serialize_field(
+            &mut state,
+            "requires_heating",
+            &self.requires_heating,
+        )?;
+        serde::ser::SerializeStruct::serialize_field(
+            &mut state,
+            "scrap_economy",
+            &self.scrap_economy,
+        )?;
+        serde::ser::SerializeStruct::serialize_field(&mut state, "location", &self.location)?;
+        serde::ser::SerializeStruct::serialize_field(&mut state, "route", &self.route)?;
+        serde::ser::SerializeStruct::serialize_field(&mut state, "mining", &self.bonuses.mining)?;
+        serde::ser::SerializeStruct::serialize_field(
+            &mut state,
+            "research",
+            &self.bonuses.research,
+        )?;
+        serde::ser::SerializeStruct::serialize_field(&mut state, "logistics", &self.logistics)?;
+        serde::ser::SerializeStruct::serialize_field(
+            &mut state,
+            "machine_whitelist",
+            &self.machine_whitelist,
+        )?;
+        serde::ser::SerializeStruct::serialize_field(&mut state, "module_cost", &self.module_cost)?;
+        serde::ser::SerializeStruct::serialize_field(&mut state, "rate_unit", &self.rate_unit)?;
+        let locked = self
+            .locked
+            .iter()
+            .map(|(id, count)| (id.0, *count))
+            .collect::<Vec<_>>();
+        serde::ser::SerializeStruct::serialize_field(&mut state, "locked", &locked)?;
+        let mechanic_notes = self
+            .mechanic_notes
+            .iter()
+            .filter(|(id, note)| !note.trim().is_empty() && self.mechanics.contains_key(*id))
+            .map(|(id, note)| (id.0, note))
+            .collect::<Vec<_>>();
+        serde::ser::SerializeStruct::serialize_field(
+            &mut state,
+            "mechanic_notes",
+            &mechanic_notes,
+        )?;
+        let collapsed = self
+            .collapsed
+            .iter()
+            .filter(|id| self.mechanics.contains_key(*id))
+            .map(|id| id.0)
+            .collect::<Vec<_>>();
+        serde::ser::SerializeStruct::serialize_field(&mut state, "collapsed", &collapsed)?;
+        let byproduct_policies = self.byproduct_policies.iter().collect::<Vec<_>>();
+        serde::ser::SerializeStruct::serialize_field(
+            &mut state,
+            "byproduct_policies",
+            &byproduct_policies,
+        )?;
         serde::ser::SerializeStruct::end(state)
     }
 }
@@ -77,18 +346,141 @@ impl<'de> serde::Deserialize<'de> for FactoryInstance {
             serde_json::from_value(value["target"].clone()).map_err(serde::de::Error::custom)?;
         factory_instance.external =
             serde_json::from_value(value["external"].clone()).map_err(serde::de::Error::custom)?;
+        if let Some(quality_targets) = value.get("quality_targets") {
+            factory_instance.quality_targets = serde_json::from_value(quality_targets.clone())
+                .map_err(serde::de::Error::custom)?;
+        }
+        factory_instance.grid_import = value["grid_import"].as_bool().unwrap_or(false);
+        if let Some(price) = value["grid_price"].as_f64() {
+            factory_instance.grid_price = price;
+        }
+        factory_instance.price_table = value
+            .get("price_table")
+            .and_then(|table| table.as_str())
+            .map(str::to_string);
+        factory_instance.notes = value["notes"].as_str().unwrap_or_default().to_string();
+        factory_instance.tag = value
+            .get("tag")
+            .and_then(|tag| serde_json::from_value(tag.clone()).ok());
+        factory_instance.status = value
+            .get("status")
+            .and_then(|status| serde_json::from_value(status.clone()).ok())
+            .unwrap_or_default();
+        if let Some(target_modes) = value.get("target_modes") {
+            let target_modes: Vec<(GenericItem, TargetMode)> =
+                serde_json::from_value(target_modes.clone()).map_err(serde::de::Error::custom)?;
+            factory_instance.target_modes = target_modes.into_iter().collect();
+        }
+        // 旧存档没有目标模式，保持默认的单一模式
+        if let Some(target_sets) = value.get("target_sets") {
+            let target_sets: Vec<TargetPreset> =
+                serde_json::from_value(target_sets.clone()).map_err(serde::de::Error::custom)?;
+            if !target_sets.is_empty() {
+                factory_instance.active_target_set =
+                    (value["active_target_set"].as_u64().unwrap_or(0) as usize)
+                        .min(target_sets.len() - 1);
+                factory_instance.target_sets = target_sets;
+            }
+        }
         for mechanic in value["mechanics"].as_array().unwrap_or(&vec![]) {
+            let mut mechanic = mechanic.clone();
+            let id = mechanic
+                .as_object_mut()
+                .and_then(|object| object.remove("id"))
+                .and_then(|id| id.as_u64())
+                .map(MechanicId)
+                .filter(|id| !factory_instance.mechanics.contains_key(id))
+                .map(MechanicId::reserve)
+                .unwrap_or_else(MechanicId::generate);
             let mech = MECHANIC_REGISTRY
-                .deserialize(mechanic.clone())
+                .deserialize(mechanic)
                 .map_err(|_| serde::de::Error::custom("反序列化 Mechanic 失败"))?;
-            factory_instance.mechanics.push(mech);
+            factory_instance.mechanics.insert(id, mech);
         }
         for mechanic_provider in value["mechanic_providers"].as_array().unwrap_or(&vec![]) {
-            let mech_provider = MECHANIC_PROVIDER_REGISTRY
+            let mut mech_provider = MECHANIC_PROVIDER_REGISTRY
                 .deserialize(mechanic_provider.clone())
                 .map_err(|_| serde::de::Error::custom("反序列化 MechanicProvider 失败"))?;
+            mech_provider.set_mechanic_sender(factory_instance.mechanic_sender.clone());
             factory_instance.mechanic_providers.push(mech_provider);
         }
+        // 旧存档中没有后来新增的机制来源
+        let mut factory_instance = factory_instance.with_default_providers();
+        if let Some(lint) = value.get("lint") {
+            factory_instance.lint_settings =
+                serde_json::from_value(lint.clone()).map_err(serde::de::Error::custom)?;
+        }
+        factory_instance.requires_heating = value["requires_heating"].as_bool().unwrap_or(false);
+        factory_instance.scrap_economy = value["scrap_economy"].as_bool().unwrap_or(false);
+        factory_instance.location = value["location"].as_str().map(str::to_string);
+        factory_instance.route = value
+            .get("route")
+            .and_then(|route| route.as_str())
+            .map(str::to_string);
+        if let Some(mining) = value.get("mining") {
+            factory_instance.bonuses.mining =
+                serde_json::from_value(mining.clone()).map_err(serde::de::Error::custom)?;
+        }
+        if let Some(research) = value.get("research") {
+            factory_instance.bonuses.research =
+                serde_json::from_value(research.clone()).map_err(serde::de::Error::custom)?;
+        }
+        if let Some(logistics) = value.get("logistics") {
+            factory_instance.logistics =
+                serde_json::from_value(logistics.clone()).map_err(serde::de::Error::custom)?;
+        }
+        if let Some(whitelist) = value.get("machine_whitelist") {
+            factory_instance.machine_whitelist =
+                serde_json::from_value(whitelist.clone()).map_err(serde::de::Error::custom)?;
+        }
+        if let Some(module_cost) = value.get("module_cost") {
+            factory_instance.module_cost =
+                serde_json::from_value(module_cost.clone()).map_err(serde::de::Error::custom)?;
+        }
+        factory_instance.rate_unit = value
+            .get("rate_unit")
+            .and_then(|unit| serde_json::from_value(unit.clone()).ok());
+        if let Some(locked) = value.get("locked") {
+            let locked: Vec<(u64, f64)> =
+                serde_json::from_value(locked.clone()).map_err(serde::de::Error::custom)?;
+            factory_instance.locked = locked
+                .into_iter()
+                .map(|(id, count)| (MechanicId(id), count))
+                .filter(|(id, _)| factory_instance.mechanics.contains_key(id))
+                .collect();
+        }
+        if let Some(mechanic_notes) = value.get("mechanic_notes") {
+            let mechanic_notes: Vec<(u64, String)> =
+                serde_json::from_value(mechanic_notes.clone()).map_err(serde::de::Error::custom)?;
+            factory_instance.mechanic_notes = mechanic_notes
+                .into_iter()
+                .map(|(id, note)| (MechanicId(id), note))
+                .filter(|(id, _)| factory_instance.mechanics.contains_key(id))
+                .collect();
+        }
+        if let Some(collapsed) = value.get("collapsed") {
+            let collapsed: Vec<u64> =
+                serde_json::from_value(collapsed.clone()).map_err(serde::de::Error::custom)?;
+            factory_instance.collapsed = collapsed
+                .into_iter()
+                .map(MechanicId)
+                .filter(|id| factory_instance.mechanics.contains_key(id))
+                .collect();
+        }
+        if let Some(byproduct_policies) = value.get("byproduct_policies") {
+            let byproduct_policies: Vec<(GenericItem, ByproductPolicy<MechanicId>)> =
+                serde_json::from_value(byproduct_policies.clone())
+                    .map_err(serde::de::Error::custom)?;
+            factory_instance.byproduct_policies = byproduct_policies
+                .into_iter()
+                .map(|(item, mut policy)| {
+                    if let ByproductPolicy::Sinks(sinks) = &mut policy {
+                        sinks.retain(|id| factory_instance.mechanics.contains_key(id));
+                    }
+                    (item, policy)
+                })
+                .collect();
+        }
         Ok(factory_instance)
     }
 }
@@ -97,44 +489,169 @@ impl Clone for FactoryInstance {
     fn clone(&self) -> Self {
         FactoryInstance {
             name: self.name.clone(),
+            notes: self.notes.clone(),
+            tag: self.tag,
+            status: self.status,
             target: self.target.clone(),
+            target_sets: self.target_sets.clone(),
+            active_target_set: self.active_target_set,
+            quality_targets: self.quality_targets.clone(),
+            target_modes: self.target_modes.clone(),
+            byproduct_policies: self.byproduct_policies.clone(),
             external: self.external.clone(),
+            price_table: self.price_table.clone(),
+            grid_import: self.grid_import,
+            grid_price: self.grid_price,
             solution: self.solution.clone(),
             total_flow: self.total_flow.clone(),
             total_flow_sorted_keys: self.total_flow_sorted_keys.clone(),
+            usage: self.usage.clone(),
             mechanic_providers: self.mechanic_providers.clone(),
             mechanics: self.mechanics.clone(),
+            locked: self.locked.clone(),
+            mechanic_notes: self.mechanic_notes.clone(),
+            collapsed: self.collapsed.clone(),
+            card_heights: std::collections::HashMap::new(),
+            flow_cache: std::sync::Mutex::new(FlowCache::default()),
             mechanic_suggestions: self.mechanic_suggestions.clone(),
+            alternatives: self.alternatives.clone(),
+            chain_preference: self.chain_preference,
+            mechanic_filter: self.mechanic_filter.clone(),
+            target_producible_only: self.target_producible_only,
+            show_machine_count: self.show_machine_count,
+            show_variance: self.show_variance,
+            variance_window: self.variance_window,
+            inspector: self.inspector.clone(),
+            insert_after: self.insert_after,
+            selected: self.selected.clone(),
+            bulk_template: self.bulk_template.clone(),
+            solution_snapshot: self.solution_snapshot.clone(),
+            lint_settings: self.lint_settings.clone(),
+            requires_heating: self.requires_heating,
+            scrap_economy: self.scrap_economy,
+            location: self.location.clone(),
+            route: self.route.clone(),
+            bonuses: self.bonuses.clone(),
+            logistics: self.logistics.clone(),
+            machine_whitelist: self.machine_whitelist.clone(),
+            module_cost: self.module_cost.clone(),
+            rate_unit: self.rate_unit,
             ..Default::default()
         }
     }
 }
 
+/// 在共享的求解线程池中登记一个请求方，供工厂以外需要后台求解的页面使用
+pub fn register_solver(
+    solution_tx: std::sync::mpsc::Sender<SolverSolution<MechanicId>>,
+) -> SolverHandle<GenericItem, MechanicId> {
+    SOLVER_POOL.register(solution_tx, |_, _| {})
+}
+
 impl Default for FactoryInstance {
     fn default() -> Self {
         let (mechanic_tx, mechanic_rx) = std::sync::mpsc::channel();
-        let (arg_tx, arg_rx) = std::sync::mpsc::channel();
         let (solution_tx, solution_rx) = std::sync::mpsc::channel();
-        SolverData::make_solver_thread(solution_tx, arg_rx);
+        let solve_notice = std::sync::Arc::new(std::sync::Mutex::new(SolveNotice::default()));
+        let thread_notice = solve_notice.clone();
+        let solver = SOLVER_POOL.register(solution_tx, move |solution, elapsed| {
+            thread_notice.lock().unwrap().notify(solution, elapsed)
+        });
 
         FactoryInstance {
             name: "工厂".to_string(),
+            notes: String::new(),
+            tag: None,
+            status: FactoryStatus::default(),
             target: Vec::new(),
+            target_sets: vec![TargetPreset {
+                name: "默认模式".to_string(),
+                targets: Vec::new(),
+            }],
+            active_target_set: 0,
+            quality_targets: Vec::new(),
+            target_modes: IndexMap::new(),
+            byproduct_policies: IndexMap::new(),
             external: Vec::new(),
+            price_table: None,
+            grid_import: false,
+            grid_price: DEFAULT_GRID_PRICE,
             solution: (IndexMap::new(), 0.0),
             total_flow: IndexMap::new(),
             total_flow_sorted_keys: Vec::new(),
+            usage: std::sync::Arc::default(),
             mechanic_providers: Vec::new(),
-            mechanics: Vec::new(),
+            mechanics: IndexMap::new(),
+            locked: IndexMap::new(),
+            mechanic_notes: IndexMap::new(),
+            collapsed: IndexSet::new(),
+            card_heights: std::collections::HashMap::new(),
+            flow_cache: std::sync::Mutex::new(FlowCache::default()),
             mechanic_suggestions: Vec::new(),
+            alternatives: AlternativesExplorer::default(),
+            chain_preference: ChainPreference::default(),
+            mechanic_filter: MechanicFilter::default(),
+            target_producible_only: false,
+            show_machine_count: false,
+            show_variance: false,
+            variance_window: 60.0,
+            inspector: ItemInspector::default(),
+            insert_after: None,
+            selected: IndexSet::new(),
+            bulk_template: BulkEditTemplate::default(),
+            solution_snapshot: None,
+            lint_settings: LintSettings::default(),
+            requires_heating: false,
+            scrap_economy: false,
+            location: None,
+            route: None,
+            bonuses: GlobalBonuses::default(),
+            logistics: LogisticsOverhead::default(),
+            machine_whitelist: MachineWhitelist::default(),
+            module_cost: ModuleCost::default(),
+            rate_unit: None,
+            pending_subviews: Vec::new(),
+            solve_notice,
             mechanic_receiver: mechanic_rx,
             mechanic_sender: mechanic_tx,
-            arg_sender: arg_tx,
+            solver,
             solution_receiver: solution_rx,
         }
     }
 }
 
+/// 求解线程发送通知时使用的工厂名称与所在页面
+#[derive(Debug, Clone, Default)]
+pub struct SolveNotice {
+    pub factory: String,
+    pub target: Option<SubviewKey>,
+}
+
+impl SolveNotice {
+    fn notify(&self, solution: &SolverSolution<MechanicId>, elapsed: std::time::Duration) {
+        let notification = match solution {
+            Ok(_) => crate::toast::Notification::new(
+                crate::toast::Severity::Success,
+                trf(
+                    "planner.solve_finished",
+                    &[&self.factory, &format!("{:.1}", elapsed.as_secs_f64())],
+                ),
+            ),
+            Err(err) => {
+                let reason = match err {
+                    crate::error::AppError::Solver(reason) => reason.clone(),
+                    err => format!("{:?}", err),
+                };
+                crate::toast::Notification::new(
+                    crate::toast::Severity::Error,
+                    trf("planner.solve_failed", &[&self.factory, &reason]),
+                )
+            }
+        };
+        crate::toast::notify(notification.with_target(self.target));
+    }
+}
+
 impl FactoryInstance {
     pub fn new(name: String) -> Self {
         FactoryInstance {
@@ -143,21 +660,527 @@ impl FactoryInstance {
         }
     }
 
+    /// 机制的物料流，按工厂的全局加成计算，需要供暖时加上放置的实体消耗的热量，
+    /// 启用物流开销时加上搬运物品的耗电
+    pub fn mechanic_flow(
+        &self,
+        mechanic: &FactorioMechanic,
+        ctx: &FactorioContext,
+    ) -> Flow<GenericItem> {
+        self.finish_flow(self.bonuses.mechanic_flow(mechanic, ctx), mechanic, ctx)
+    }
+
+    /// 与 mechanic_flow 相同，按全局加成计算的部分取自缓存
+    pub fn cached_mechanic_flow(
+        &self,
+        id: MechanicId,
+        mechanic: &FactorioMechanic,
+        ctx: &FactorioContext,
+    ) -> Flow<GenericItem> {
+        let flow = Flow::clone(&self.bonus_flow(id, mechanic, ctx));
+        self.finish_flow(flow, mechanic, ctx)
+    }
+
+    /// 按全局加成计算的机制物料流，取自缓存。调用前应已用 validate_flow_cache 检查缓存
+    pub fn bonus_flow(
+        &self,
+        id: MechanicId,
+        mechanic: &FactorioMechanic,
+        ctx: &FactorioContext,
+    ) -> std::sync::Arc<Flow<GenericItem>> {
+        self.flow_cache
+            .lock()
+            .unwrap()
+            .get(id, mechanic, &self.bonuses, ctx)
+    }
+
+    /// 上下文或全局加成变化后清空物料流缓存
+    pub fn validate_flow_cache(&self, ctx: &FactorioContext) {
+        self.flow_cache.lock().unwrap().validate(&self.bonuses, ctx);
+    }
+
+    /// 在按全局加成计算的物料流上加入供暖与物流开销
+    fn finish_flow(
+        &self,
+        mut flow: Flow<GenericItem>,
+        mechanic: &FactorioMechanic,
+        ctx: &FactorioContext,
+    ) -> Flow<GenericItem> {
+        if self.requires_heating {
+            let heating = mechanic_heating_power(mechanic, ctx);
+            if heating > 0.0 {
+                index_map_update_entry(&mut flow, GenericItem::Heat, -heating);
+            }
+        }
+        if let Some((_, module_config)) = mechanic_module_config(mechanic) {
+            self.module_cost.apply(ctx, module_config, &mut flow);
+        }
+        self.logistics.apply(&mut flow);
+        flow
+    }
+
+    /// 按求解结果汇总的产量方差增长速率（每秒），各台机器的产出相互独立
+    pub fn output_variance(&self, ctx: &FactorioContext) -> Flow<GenericItem> {
+        let mut variance = Flow::new();
+        for (id, mechanic) in &self.mechanics {
+            let count = self.solution.0.get(id).cloned().unwrap_or(0.0);
+            if count <= 0.0 {
+                continue;
+            }
+            if let Some(recipe) =
+                (mechanic.as_ref() as &dyn std::any::Any).downcast_ref::<RecipeConfig>()
+            {
+                for (item, value) in recipe.output_variance(ctx) {
+                    index_map_update_entry(&mut variance, item, value * count);
+                }
+            }
+        }
+        variance
+    }
+
+    /// 机制的成本，启用物流开销时按物品吞吐量增加
+    pub fn mechanic_cost(&self, mechanic: &FactorioMechanic, ctx: &FactorioContext) -> f64 {
+        mechanic.cost(ctx)
+            + self
+                .logistics
+                .cost(&self.bonuses.mechanic_flow(mechanic, ctx))
+    }
+
+    /// 与 mechanic_cost 相同，物料流取自缓存
+    fn cached_mechanic_cost(
+        &self,
+        id: MechanicId,
+        mechanic: &FactorioMechanic,
+        ctx: &FactorioContext,
+    ) -> f64 {
+        mechanic.cost(ctx) + self.logistics.cost(&self.bonus_flow(id, mechanic, ctx))
+    }
+
+    /// 工厂实际使用的速率单位
+    pub fn rate_unit(&self) -> RateUnit {
+        self.rate_unit.unwrap_or_else(RateUnit::global)
+    }
+
+    fn rate_unit_selector(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(tr("planner.rate_unit"));
+            let selected = match self.rate_unit {
+                Some(unit) => unit.name(),
+                None => tr("planner.rate_unit_global"),
+            };
+            egui::ComboBox::from_id_salt("factory-rate-unit")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.rate_unit, None, tr("planner.rate_unit_global"));
+                    for unit in RateUnit::ALL {
+                        ui.selectable_value(&mut self.rate_unit, Some(unit), unit.name());
+                    }
+                });
+        });
+    }
+
+    /// 选择工厂所在的地点，切换到星球时按星球设置是否需要供暖
+    fn location_selector(&mut self, ui: &mut egui::Ui, ctx: &FactorioContext) -> bool {
+        let mut changed = false;
+        let had_route = self.route.is_some();
+        ui.horizontal(|ui| {
+            ui.label(tr("planner.location"))
+                .on_hover_text(tr("planner.location_hint"));
+            let selected = match &self.location {
+                Some(location) => ctx.location_display_name(location),
+                None => tr("planner.location_any").to_string(),
+            };
+            egui::ComboBox::from_id_salt("factory-location")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    changed |= ui
+                        .selectable_value(&mut self.location, None, tr("planner.location_any"))
+                        .changed();
+                    for location in ctx.locations() {
+                        let text = ctx.location_display_name(&location);
+                        changed |= ui
+                            .selectable_value(&mut self.location, Some(location), text)
+                            .changed();
+                    }
+                });
+            if self
+                .location
+                .as_ref()
+                .is_some_and(|location| ctx.surfaces.contains_key(location))
+            {
+                changed |= self.route_selector(ui, ctx);
+            }
+        });
+        if changed {
+            self.apply_location_heating(ctx);
+            let kept = self.apply_route();
+            if had_route && self.route.is_none() && kept > 0 {
+                crate::toast::info(trf("planner.route_cleared", &[&kept]));
+            }
+        }
+        changed
+    }
+
+    /// 太空平台的星球轨道或航线，悬停时显示各种小行星的生成频率
+    fn route_selector(&mut self, ui: &mut egui::Ui, ctx: &FactorioContext) -> bool {
+        let mut changed = false;
+        ui.label(tr("planner.route"))
+            .on_hover_text(tr("planner.route_hint"));
+        let selected = match &self.route {
+            Some(route) => ctx.route_display_name(route),
+            None => tr("planner.location_any").to_string(),
+        };
+        let response = egui::ComboBox::from_id_salt("factory-route")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                changed |= ui
+                    .selectable_value(&mut self.route, None, tr("planner.location_any"))
+                    .changed();
+                for route in ctx.routes() {
+                    let text = ctx.route_display_name(&route);
+                    changed |= ui
+                        .selectable_value(&mut self.route, Some(route), text)
+                        .changed();
+                }
+            })
+            .response;
+        if let Some(route) = &self.route {
+            response.on_hover_ui(|ui| {
+                ui.label(tr("planner.asteroid_spawn_rates"));
+                for (asteroid, rate) in ctx.route_asteroid_spawn_rates(route) {
+                    let category = if ctx.entities.contains_key(&asteroid) {
+                        "entity"
+                    } else {
+                        "asteroid-chunk"
+                    };
+                    ui.label(format!(
+                        "{}: {}/s",
+                        ctx.get_display_name(category, &asteroid),
+                        compact_number(rate)
+                    ));
+                }
+            });
+        }
+        changed
+    }
+
+    /// 地点为星球时按星球的 entities_require_heating 设置是否需要供暖，太空平台与不限地点保持原设置
+    pub fn apply_location_heating(&mut self, ctx: &FactorioContext) {
+        if let Some(planet) = self
+            .location
+            .as_ref()
+            .and_then(|location| ctx.planets.get(location))
+        {
+            self.requires_heating = planet.entities_require_heating;
+        }
+    }
+
+    /// 选择了航线时，小行星收集器都按工厂的航线收集碎块；航线为不限时收集器保留各自的航线。
+    /// 返回保留了各自航线的收集器数量
+    pub fn apply_route(&mut self) -> usize {
+        let mut kept = 0;
+        let flow_cache = self.flow_cache.get_mut().unwrap();
+        for (id, mechanic) in self.mechanics.iter_mut() {
+            if !(mechanic.as_ref() as &dyn std::any::Any).is::<AsteroidCollectorConfig>() {
+                continue;
+            }
+            match &self.route {
+                Some(route) => apply_route_to_collector(mechanic.as_mut(), route),
+                None => kept += 1,
+            }
+            // 收集器的产量取决于航线，缓存的物料流需要重新计算
+            flow_cache.invalidate(*id);
+        }
+        kept
+    }
+
+    /// 更新求解通知使用的工厂名称与所在页面
+    pub fn set_solve_notice(&self, target: SubviewKey) {
+        let mut notice = self.solve_notice.lock().unwrap();
+        if notice.target != Some(target) || notice.factory != self.name {
+            notice.factory = self.name.clone();
+            notice.target = Some(target);
+        }
+    }
+
     pub fn send_solve_request(&self, ctx: &FactorioContext) {
-        let flows = self
+        self.solver.submit(self.solver_args(&self.target, ctx));
+    }
+
+    /// 以给定的目标构造求解参数，机制与外部输入取自当前工厂，锁定的机制合并为常数项
+    pub fn solver_args(
+        &self,
+        target: &[(GenericItem, f64)],
+        ctx: &FactorioContext,
+    ) -> SolverArgs<GenericItem, MechanicId> {
+        self.validate_flow_cache(ctx);
+        let mut constant = Flow::new();
+        for (id, count) in &self.locked {
+            if let Some(mechanic) = self.mechanics.get(id) {
+                constant = flow_add(
+                    &constant,
+                    &self.cached_mechanic_flow(*id, mechanic.as_ref(), ctx),
+                    *count,
+                );
+            }
+        }
+        let mut flows = self
             .mechanics
             .iter()
-            .map(|fe| (box_as_ptr(fe), (fe.as_flow(ctx), fe.cost(ctx))))
-            .collect::<IndexMap<usize, (_, _)>>();
-        let target = self
-            .target
+            .filter(|(id, _)| !self.locked.contains_key(*id))
+            .map(|(id, fe)| {
+                (
+                    *id,
+                    (
+                        self.cached_mechanic_flow(*id, fe.as_ref(), ctx),
+                        self.cached_mechanic_cost(*id, fe.as_ref(), ctx),
+                    ),
+                )
+            })
+            .collect::<IndexMap<MechanicId, (_, _)>>();
+        // 每条航线只供应一份碎块，同一航线上的收集器合计最多捕获航线上生成的全部碎块
+        let mut captures = IndexSet::new();
+        for (id, mechanic) in &self.mechanics {
+            let Some((capture, share)) = (mechanic.as_ref() as &dyn std::any::Any)
+                .downcast_ref::<AsteroidCollectorConfig>()
+                .and_then(|collector| collector.route_capture(ctx))
+            else {
+                continue;
+            };
+            match self.locked.get(id) {
+                Some(count) => {
+                    index_map_update_entry(&mut constant, capture.clone(), -share * count)
+                }
+                None => {
+                    if let Some((flow, _)) = flows.get_mut(id) {
+                        index_map_update_entry(flow, capture.clone(), -share);
+                    }
+                }
+            }
+            captures.insert(capture);
+        }
+        for capture in captures {
+            index_map_update_entry(&mut constant, capture, 1.0);
+        }
+        let bridges = temperature_bridges(
+            ctx,
+            flows
+                .values()
+                .map(|(flow, _)| flow)
+                .chain(std::iter::once(&constant)),
+            target,
+        );
+        flows.extend(bridges.into_iter().map(|(id, flow)| (id, (flow, 0.0))));
+        let quality_bridges = quality_target_bridges(
+            &self.quality_targets,
+            ctx,
+            flows
+                .values()
+                .map(|(flow, _)| flow)
+                .chain(std::iter::once(&constant)),
+        );
+        flows.extend(
+            quality_bridges
+                .into_iter()
+                .map(|(id, flow)| (id, (flow, 0.0))),
+        );
+        let target = target
             .iter()
             .map(|(item, amount)| (item.clone(), *amount))
+            .chain(
+                self.quality_targets
+                    .iter()
+                    .map(|quality_target| (quality_target.aggregate(), quality_target.total)),
+            )
             .fold(IndexMap::new(), |mut acc, (item, amount)| {
                 *acc.entry(item).or_insert(0.0) += amount;
                 acc
             });
-        let external = self
+        let external = self.external_costs(
+            self.price_table
+                .as_ref()
+                .and_then(|name| PRICE_TABLES.lock().unwrap().get(name).cloned())
+                .as_ref(),
+        );
+        // 没有产量上限时最大化按至少处理
+        let can_maximize = self.can_maximize();
+        let target_modes = self
+            .target_modes
+            .iter()
+            .filter(|(item, mode)| **mode != TargetMode::Exact && target.contains_key(*item))
+            .map(|(item, mode)| (item.clone(), *mode))
+            .chain(
+                self.quality_targets
+                    .iter()
+                    .filter(|quality_target| quality_target.mode != TargetMode::Exact)
+                    .map(|quality_target| (quality_target.aggregate(), quality_target.mode)),
+            )
+            .map(|(item, mode)| match mode {
+                TargetMode::Maximize if !can_maximize => (item, TargetMode::AtLeast),
+                _ => (item, mode),
+            })
+            .collect();
+        let mut byproduct_policies = self
+            .byproduct_policies
+            .iter()
+            .filter(|(_, policy)| {
+                // 没有选择消耗机制的限定策略无效，按允许盈余处理
+                !matches!(policy, ByproductPolicy::Allow)
+                    && !matches!(policy, ByproductPolicy::Sinks(sinks) if sinks.is_empty())
+            })
+            .map(|(item, policy)| (item.clone(), policy.clone()))
+            .collect::<IndexMap<_, _>>();
+        if self.scrap_economy {
+            // 不能凭空销毁，未指定消耗机制的物品与流体一律要求恰好用完
+            let all_flows = flows
+                .values()
+                .map(|(flow, _)| flow)
+                .chain(std::iter::once(&constant));
+            for flow in all_flows {
+                for item in flow.keys() {
+                    if matches!(item, GenericItem::Item(..) | GenericItem::Fluid { .. }) {
+                        byproduct_policies
+                            .entry(item.clone())
+                            .or_insert(ByproductPolicy::Zero);
+                    }
+                }
+            }
+        }
+        (
+            target,
+            flows,
+            external,
+            constant,
+            target_modes,
+            byproduct_policies,
+        )
+    }
+
+    /// 求解结果中补上锁定的机制及其成本
+    pub fn complete_solution(&self, solution: &mut (Flow<MechanicId>, f64), ctx: &FactorioContext) {
+        for (id, count) in &self.locked {
+            if let Some(mechanic) = self.mechanics.get(id) {
+                solution.0.insert(*id, *count);
+                solution.1 += self.mechanic_cost(mechanic.as_ref(), ctx) * count;
+            }
+        }
+    }
+
+    /// 采用求解结果，补上锁定的机制并按结果汇总总物料流
+    pub fn apply_solution(&mut self, mut solution: (Flow<MechanicId>, f64), ctx: &FactorioContext) {
+        self.total_flow.clear();
+        self.complete_solution(&mut solution, ctx);
+        let exact = crate::settings::get().exact_rational;
+        if exact {
+            // 线性规划以浮点求解，结果吸附为有理数，消除 0.9999999 之类的误差
+            for count in solution.0.values_mut() {
+                *count = crate::rational::snap_value(*count);
+            }
+        }
+        self.solution = solution;
+        let flows = self
+            .mechanics
+            .iter()
+            .map(|(id, fe)| (*id, self.cached_mechanic_flow(*id, fe.as_ref(), ctx)))
+            .collect::<Vec<_>>();
+        let bridges = temperature_bridges(ctx, flows.iter().map(|(_, flow)| flow), &self.target);
+        let terms = flows
+            .iter()
+            .chain(&bridges)
+            .map(|(id, flow)| (flow, self.solution.0.get(id).cloned().unwrap_or(0.0)));
+        if exact {
+            self.total_flow = crate::rational::exact_flow_sum(terms);
+        } else {
+            for (flow, var_value) in terms {
+                self.total_flow = flow_add(&self.total_flow, flow, var_value);
+            }
+        }
+        // Update sorted keys cache when total_flow changes
+        self.total_flow_sorted_keys = self.total_flow.keys().cloned().collect();
+        sort_generic_items_owned(&mut self.total_flow_sorted_keys, ctx);
+        self.usage = std::sync::Arc::new(FactoryUsage::collect(
+            flows.iter().filter_map(|(id, flow)| {
+                let mechanic = self.mechanics.get(id)?;
+                let count = self.solution.0.get(id).cloned().unwrap_or(0.0);
+                Some((mechanic.as_ref(), flow, count))
+            }),
+            ctx,
+        ));
+    }
+
+    /// 在当前线程中求解当前目标并采用结果，用于没有界面的导出
+    pub fn solve_blocking(&mut self, ctx: &FactorioContext) -> Result<(), crate::error::AppError> {
+        let (target, flows, external, constant, target_modes, byproduct_policies) =
+            self.solver_args(&self.target, ctx);
+        let solution = SolverData::new(target, flows)
+            .with_external(external)
+            .with_constant(constant)
+            .with_target_modes(target_modes)
+            .with_byproduct_policies(byproduct_policies)
+            .solve()?;
+        self.apply_solution(solution, ctx);
+        Ok(())
+    }
+
+    /// 选择共享价格表并打开价格表编辑页面，返回是否更换了价格表
+    fn price_table_selector(&mut self, ui: &mut egui::Ui, ctx: &FactorioContext) -> bool {
+        let mut changed = false;
+        let names = PRICE_TABLES
+            .lock()
+            .unwrap()
+            .tables
+            .iter()
+            .map(|table| table.name.clone())
+            .collect::<Vec<_>>();
+        ui.horizontal(|ui| {
+            ui.label(tr("planner.price_table"));
+            egui::ComboBox::from_id_salt("factory-price-table")
+                .selected_text(
+                    self.price_table
+                        .clone()
+                        .unwrap_or_else(|| tr("planner.price_table_none").to_string()),
+                )
+                .show_ui(ui, |ui| {
+                    changed |= ui
+                        .selectable_value(
+                            &mut self.price_table,
+                            None,
+                            tr("planner.price_table_none"),
+                        )
+                        .changed();
+                    for name in names.iter() {
+                        changed |= ui
+                            .selectable_value(&mut self.price_table, Some(name.clone()), name)
+                            .changed();
+                    }
+                });
+            if ui
+                .button(tr("planner.edit_price_tables"))
+                .on_hover_text(tr("planner.edit_price_tables_hint"))
+                .clicked()
+            {
+                self.pending_subviews
+                    .push(Box::new(PriceTableEditorView::new(
+                        ctx.clone(),
+                        self.price_table.as_deref(),
+                    )));
+            }
+        });
+        if let Some(name) = &self.price_table
+            && !names.contains(name)
+        {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                trf("planner.price_table_missing", &[name]),
+            );
+        }
+        changed
+    }
+
+    /// 额外输入的单位价值，同一物品的多项相加；价格表中列出的物品改用表中价格，
+    /// 允许电网取电时电力按每兆焦的价格计算
+    pub fn external_costs(&self, table: Option<&PriceTable>) -> IndexMap<GenericItem, f64> {
+        let mut external = self
             .external
             .iter()
             .map(|(item, amount)| (item.clone(), *amount))
@@ -165,7 +1188,142 @@ impl FactoryInstance {
                 *acc.entry(item).or_insert(0.0) += amount;
                 acc
             });
-        let _ = self.arg_sender.send((target, flows, external));
+        if let Some(table) = table {
+            for (item, cost) in external.iter_mut() {
+                if let Some(price) = table.price_of(item) {
+                    *cost = price;
+                }
+            }
+        }
+        if self.grid_import {
+            // 物料流中的电力以瓦为单位
+            external.insert(GenericItem::Electricity, self.grid_price / 1e6);
+        }
+        external
+    }
+
+    /// 求解结果中从电网净取用的功率（瓦），电力有盈余时为 0
+    pub fn grid_draw(&self) -> f64 {
+        let net = self
+            .total_flow
+            .get(&GenericItem::Electricity)
+            .copied()
+            .unwrap_or(0.0);
+        (-net).max(0.0)
+    }
+
+    /// 是否可以最大化目标：至少锁定一个机制作为产量的上限，否则求解结果无界
+    pub fn can_maximize(&self) -> bool {
+        !self.locked.is_empty()
+    }
+
+    /// 物品是否已由额外输入供应
+    pub fn is_external(&self, item: &GenericItem) -> bool {
+        self.external.iter().any(|(external, _)| external == item)
+    }
+
+    /// 工厂中有机制产出的物品，这些物品改由额外输入供应后可以省去产出它们的机制
+    pub fn produced_items(&self, ctx: &FactorioContext) -> IndexSet<GenericItem> {
+        self.validate_flow_cache(ctx);
+        let mut produced = IndexSet::new();
+        for (id, mechanic) in &self.mechanics {
+            let flow = self.bonus_flow(*id, mechanic.as_ref(), ctx);
+            produced.extend(
+                flow.iter()
+                    .filter(|(_, amount)| **amount > 0.0)
+                    .map(|(item, _)| item.clone()),
+            );
+        }
+        produced
+    }
+
+    /// 切换物品的额外输入：已有时全部移除，否则以零成本加入，返回切换后是否由额外输入供应
+    pub fn toggle_external(&mut self, item: &GenericItem) -> bool {
+        if self.is_external(item) {
+            self.external.retain(|(external, _)| external != item);
+            false
+        } else {
+            self.external.push((item.clone(), 0.0));
+            true
+        }
+    }
+
+    /// 所有目标模式，当前模式的目标替换为 target
+    pub fn synced_target_sets(&self) -> Vec<TargetPreset> {
+        let mut target_sets = self.target_sets.clone();
+        if let Some(active) = target_sets.get_mut(self.active_target_set) {
+            active.targets = self.target.clone();
+        }
+        target_sets
+    }
+
+    /// 切换目标模式，返回是否发生了切换
+    pub fn switch_target_set(&mut self, index: usize) -> bool {
+        if index == self.active_target_set || index >= self.target_sets.len() {
+            return false;
+        }
+        self.target_sets[self.active_target_set].targets = std::mem::take(&mut self.target);
+        self.active_target_set = index;
+        self.target = self.target_sets[index].targets.clone();
+        true
+    }
+
+    fn target_set_bar(&mut self, ui: &mut egui::Ui, ctx: &FactorioContext, changed: &mut bool) {
+        ui.horizontal_wrapped(|ui| {
+            let mut selected = self.active_target_set;
+            egui::ComboBox::new(ui.id().with("target-set"), "")
+                .selected_text(&self.target_sets[self.active_target_set].name)
+                .show_ui(ui, |ui| {
+                    for (idx, target_set) in self.target_sets.iter().enumerate() {
+                        ui.selectable_value(&mut selected, idx, &target_set.name);
+                    }
+                });
+            *changed |= self.switch_target_set(selected);
+            *changed |= ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.target_sets[self.active_target_set].name)
+                        .desired_width(80.0),
+                )
+                .on_hover_text(tr("planner.target_set_name"))
+                .changed();
+            if ui
+                .button(tr("planner.new_target_set"))
+                .on_hover_text(tr("planner.new_target_set_hint"))
+                .clicked()
+            {
+                self.target_sets.push(TargetPreset {
+                    name: trf(
+                        "planner.target_set_default_name",
+                        &[&(self.target_sets.len() + 1)],
+                    ),
+                    targets: self.target.clone(),
+                });
+                *changed |= self.switch_target_set(self.target_sets.len() - 1);
+            }
+            if ui
+                .add_enabled(
+                    self.target_sets.len() > 1,
+                    egui::Button::new(tr("planner.delete_target_set")),
+                )
+                .clicked()
+            {
+                self.target_sets.remove(self.active_target_set);
+                self.active_target_set = self.active_target_set.saturating_sub(1);
+                self.target = self.target_sets[self.active_target_set].targets.clone();
+                *changed = true;
+            }
+            if ui
+                .add_enabled(
+                    self.target_sets.len() > 1,
+                    egui::Button::new(tr("planner.compare_target_sets")),
+                )
+                .on_hover_text(tr("planner.compare_target_sets_hint"))
+                .clicked()
+            {
+                self.pending_subviews
+                    .push(Box::new(TargetSetComparisonView::solve(self, ctx)));
+            }
+        });
     }
 
     pub fn add_flow_source<
@@ -179,86 +1337,927 @@ impl FactoryInstance {
         self
     }
 
-    fn flows_panel(&mut self, ui: &mut egui::Ui, ctx: &FactorioContext, changed: &mut bool) {
-        let label = ui.label(format!("总代价: {:.2} | 总物料流", self.solution.1));
-        ui.horizontal_wrapped(|ui| {
-            card_frame(ui).show(ui, |ui| {
-                ui.set_min_width(ui.available_width());
-                ui.set_min_height(50.0);
-                let mut modal = HintModal::new(
-                    label.id,
-                    ctx,
-                    &self.mechanic_sender,
-                    &mut self.mechanic_suggestions,
-                    &self.mechanic_providers,
-                );
-                let mut final_clicked = None;
-                for item in &self.total_flow_sorted_keys {
-                    let amount = self.total_flow.get(item).cloned().unwrap_or(0.0);
-                    if amount.abs() < 1e-6 {
-                        continue;
-                    }
-                    ui.vertical(|ui| {
-                        ui.add_sized([35.0, 15.0], SignedCompactLabel::new(amount));
-                        let icon = ui
-                            .push_id(item, |ui| {
-                                ui.add_sized([35.0, 35.0], GenericIcon::new(ctx, item))
-                                    .interact(egui::Sense::click())
-                            })
-                            .inner;
+    /// 补齐默认的机制来源，已有的同类来源不重复添加
+    pub fn with_default_providers(mut self) -> Self {
+        let defaults: Vec<Box<FactorioMechanicProvider>> = vec![
+            Box::new(RecipeConfigProvider::new()),
+            Box::new(MiningConfigProvider::new()),
+            Box::new(PumpjackConfigProvider::new()),
+            Box::new(LabConfigProvider::new()),
+            Box::new(OffshorePumpConfigProvider::new()),
+            Box::new(BoilerConfigProvider::new()),
+            Box::new(ReactorConfigProvider::new()),
+            Box::new(GeneratorConfigProvider::new()),
+            Box::new(FusionReactorConfigProvider::new()),
+            Box::new(FusionGeneratorConfigProvider::new()),
+            Box::new(SolarPowerConfigProvider::new()),
+            Box::new(LightningConfigProvider::new()),
+            Box::new(SpawnerConfigProvider::new()),
+            Box::new(AsteroidCollectorConfigProvider::new()),
+            Box::new(ThrusterConfigProvider::new()),
+            Box::new(DefenseConfigProvider::new()),
+            Box::new(RecyclerConfigProvider::new()),
+            Box::new(UserMechanicProvider::new()),
+        ];
+        for mut provider in defaults {
+            let type_id = (provider.as_ref() as &dyn std::any::Any).type_id();
+            if self
+                .mechanic_providers
+                .iter()
+                .any(|existing| (existing.as_ref() as &dyn std::any::Any).type_id() == type_id)
+            {
+                continue;
+            }
+            provider.set_mechanic_sender(self.mechanic_sender.clone());
+            self.mechanic_providers.push(provider);
+        }
+        self
+    }
 
-                        if icon.clicked_by(egui::PointerButton::Secondary) || icon.clicked() {
-                            final_clicked = Some((item, amount));
-                        }
-                    });
-                    if ui.available_size_before_wrap().x < 35.0 {
-                        ui.end_row();
+    /// 导入游戏导出的研究进度，写入全局加成与配方来源的插件研究设置
+    fn import_research(&mut self, path: &std::path::Path, ctx: &FactorioContext) -> bool {
+        let snapshot = match ResearchSnapshot::load(path) {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                crate::toast::error(format!("{:?}", err));
+                return false;
+            }
+        };
+        let mut module_research = ModuleResearch::default();
+        let provider = self.mechanic_providers.iter_mut().find_map(|provider| {
+            (provider.as_mut() as &mut dyn std::any::Any).downcast_mut::<RecipeConfigProvider>()
+        });
+        let summary = snapshot.apply(
+            ctx,
+            &mut self.bonuses,
+            provider.map_or(&mut module_research, |provider| {
+                &mut provider.module_research
+            }),
+        );
+        let quality = ctx
+            .qualities
+            .get(summary.max_quality as usize)
+            .map(|quality| ctx.get_display_name("quality", &quality.base.name))
+            .unwrap_or_default();
+        crate::toast::success(trf(
+            "planner.research_imported",
+            &[
+                &summary.bonus_technologies,
+                &summary.mining_productivity_level,
+                &quality,
+            ],
+        ));
+        if summary.unknown_technologies > 0 {
+            crate::toast::warning(trf(
+                "planner.research_unknown",
+                &[&summary.unknown_technologies],
+            ));
+        }
+        true
+    }
+
+    /// 将工厂中按使用次数排序的制造机器告知配方来源，推荐配方时优先选用；
+    /// 可用机器的白名单同步给各个来源，推荐时只选用白名单中的机器
+    fn update_hint_preferences(&mut self) {
+        let mut counts: IndexMap<&str, usize> = IndexMap::new();
+        for mechanic in self.mechanics.values() {
+            if let Some(config) =
+                (mechanic.as_ref() as &dyn std::any::Any).downcast_ref::<RecipeConfig>()
+            {
+                *counts.entry(config.machine.0.as_str()).or_default() += 1;
+            }
+        }
+        counts.sort_by(|a, count_a, b, count_b| count_b.cmp(count_a).then_with(|| a.cmp(b)));
+        let preferred = counts.keys().map(|name| name.to_string()).collect();
+        if let Some(provider) = self.mechanic_providers.iter_mut().find_map(|provider| {
+            (provider.as_mut() as &mut dyn std::any::Any).downcast_mut::<RecipeConfigProvider>()
+        }) {
+            provider.preferred_machines = preferred;
+        }
+        for provider in &mut self.mechanic_providers {
+            let any = provider.as_mut() as &mut dyn std::any::Any;
+            let whitelist = if let Some(provider) = any.downcast_mut::<RecipeConfigProvider>() {
+                &mut provider.machine_whitelist
+            } else if let Some(provider) = any.downcast_mut::<MiningConfigProvider>() {
+                &mut provider.machine_whitelist
+            } else if let Some(provider) = any.downcast_mut::<PumpjackConfigProvider>() {
+                &mut provider.machine_whitelist
+            } else if let Some(provider) = any.downcast_mut::<LabConfigProvider>() {
+                &mut provider.machine_whitelist
+            } else {
+                continue;
+            };
+            if *whitelist != self.machine_whitelist {
+                *whitelist = self.machine_whitelist.clone();
+            }
+        }
+    }
+
+    /// 配方来源中记录的品质研究进度，没有配方来源时视为全部解锁
+    fn quality_availability(&self) -> QualityAvailability {
+        self.mechanic_providers
+            .iter()
+            .find_map(|provider| {
+                (provider.as_ref() as &dyn std::any::Any).downcast_ref::<RecipeConfigProvider>()
+            })
+            .map(|provider| provider.module_research.quality)
+            .unwrap_or_default()
+    }
+
+    fn auto_complete_chain(&mut self, ctx: &FactorioContext) {
+        let completion = complete_chain(
+            &self.mechanics,
+            &self.target,
+            &self.external,
+            &self.mechanic_providers,
+            self.chain_preference,
+            ctx,
+        );
+        if completion.added.is_empty() {
+            crate::toast::info(tr("planner.nothing_to_complete"));
+        } else {
+            crate::toast::success(trf(
+                "planner.completion_added",
+                &[&completion.added.len(), &completion.raw.len()],
+            ));
+        }
+        if !completion.unresolved.is_empty() {
+            crate::toast::info(trf(
+                "planner.completion_unresolved",
+                &[
+                    &completion.unresolved.len(),
+                    &completion
+                        .unresolved
+                        .iter()
+                        .map(|item| ctx.get_generic_item_display_name(item))
+                        .collect::<Vec<_>>()
+                        .join(tr("common.list_separator")),
+                ],
+            ));
+        }
+        if !completion.added.is_empty() {
+            tutorial::complete(TutorialStep::AcceptHint);
+        }
+        for mechanic in completion.added {
+            let _ = self.mechanic_sender.send(mechanic);
+        }
+    }
+
+    /// 加入一个机制。有来源机制且 adjacent 时插入到来源之后，并把来源更新为新机制，
+    /// 使连续加入的机制保持顺序；否则加在末尾
+    pub fn insert_mechanic(
+        &mut self,
+        mechanic: Box<FactorioMechanic>,
+        adjacent: bool,
+    ) -> MechanicId {
+        let id = MechanicId::generate();
+        match self
+            .insert_after
+            .and_then(|origin| self.mechanics.get_index_of(&origin))
+            .filter(|_| adjacent)
+        {
+            Some(index) => {
+                self.mechanics.shift_insert(index + 1, id, mechanic);
+                self.insert_after = Some(id);
+            }
+            None => {
+                self.mechanics.insert(id, mechanic);
+            }
+        }
+        id
+    }
+
+    /// 将剪贴板文本中的机制加入工厂，引用了缺失原型的机制被跳过，返回是否有机制加入
+    fn paste_mechanics(&mut self, text: &str, ctx: &FactorioContext) -> bool {
+        let pasted = match decode_mechanics(text, ctx) {
+            Ok(pasted) => pasted,
+            Err(err) => {
+                crate::toast::error(format!("{:?}", err));
+                return false;
+            }
+        };
+        if pasted.skipped > 0 || pasted.failed > 0 {
+            crate::toast::info(trf(
+                "planner.paste_skipped",
+                &[
+                    &(pasted.skipped + pasted.failed),
+                    &pasted
+                        .missing
+                        .keys()
+                        .map(|missing| missing.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(tr("common.list_separator")),
+                ],
+            ));
+        }
+        if pasted.mechanics.is_empty() {
+            return false;
+        }
+        crate::toast::success(trf("planner.pasted", &[&pasted.mechanics.len()]));
+        for mechanic in pasted.mechanics {
+            let _ = self.mechanic_sender.send(mechanic);
+        }
+        true
+    }
+
+    /// 添加原油处理与裂解的机制，并要求重油、轻油不留盈余，由求解器自动配平裂解
+    fn add_oil_balancer(&mut self, ctx: &FactorioContext) {
+        let mechanics = oil_balancer_mechanics(ctx);
+        if mechanics.is_empty() {
+            crate::toast::error(tr("planner.oil_balancer_missing"));
+            return;
+        }
+        for name in OIL_BALANCED_FLUIDS {
+            let item = GenericItem::Fluid {
+                name: name.to_string(),
+                temperature: None,
+            };
+            let policy = self.byproduct_policies.entry(item).or_default();
+            if *policy == ByproductPolicy::Allow {
+                *policy = ByproductPolicy::Zero;
+            }
+        }
+        crate::toast::success(trf("planner.oil_balancer_added", &[&mechanics.len()]));
+        for mechanic in mechanics {
+            let _ = self.mechanic_sender.send(mechanic);
+        }
+    }
+
+    /// 选中机制的数量、全选与批量编辑，套用后只触发一次求解
+    fn selection_toolbar(&mut self, ui: &mut egui::Ui, ctx: &FactorioContext, changed: &mut bool) {
+        self.selected.retain(|id| self.mechanics.contains_key(id));
+        ui.label(trf("planner.selected_count", &[&self.selected.len()]));
+        if ui.button(tr("planner.select_all")).clicked() {
+            self.selected = self.mechanics.keys().copied().collect();
+        }
+        if ui
+            .add_enabled(
+                !self.selected.is_empty(),
+                egui::Button::new(tr("planner.clear_selection")),
+            )
+            .clicked()
+        {
+            self.selected.clear();
+        }
+        let recipes = self
+            .selected
+            .iter()
+            .filter_map(|id| self.mechanics.get(id))
+            .filter_map(|mechanic| {
+                (mechanic.as_ref() as &dyn std::any::Any).downcast_ref::<RecipeConfig>()
+            })
+            .filter_map(|config| ctx.recipes.get(&config.recipe.0))
+            .collect::<Vec<_>>();
+        let button = ui
+            .add_enabled(
+                !recipes.is_empty(),
+                egui::Button::new(tr("planner.bulk_edit")),
+            )
+            .on_hover_text(tr("planner.bulk_edit_hint"));
+        let mut apply = false;
+        show_modal(button.id, button.clicked(), ui, |ui| {
+            ui.add(BulkEditor::new(ctx, &mut self.bulk_template, recipes).notify_apply(&mut apply));
+        });
+        if apply {
+            let mut outcome = BulkEditOutcome::default();
+            for id in &self.selected {
+                if let Some(mechanic) = self.mechanics.get_mut(id)
+                    && let Some(config) =
+                        (mechanic.as_mut() as &mut dyn std::any::Any).downcast_mut::<RecipeConfig>()
+                {
+                    self.bulk_template.apply(config, ctx, &mut outcome);
+                    self.flow_cache.lock().unwrap().invalidate(*id);
+                }
+            }
+            crate::toast::success(trf(
+                "planner.bulk_edited",
+                &[&outcome.edited, &outcome.skipped],
+            ));
+            *changed = true;
+        }
+    }
+
+    /// 盈余物品与已设置策略的物品，逐个选择盈余的处理方式
+    fn byproduct_panel(&mut self, ui: &mut egui::Ui, ctx: &FactorioContext, changed: &mut bool) {
+        let items = self
+            .total_flow_sorted_keys
+            .iter()
+            .filter(|item| {
+                self.total_flow
+                    .get(*item)
+                    .is_some_and(|amount| *amount > 1e-6)
+                    && !self.target.iter().any(|(target, _)| target == *item)
+            })
+            .chain(
+                self.byproduct_policies
+                    .keys()
+                    .filter(|item| !self.total_flow_sorted_keys.contains(item)),
+            )
+            .cloned()
+            .collect::<Vec<_>>();
+        egui::CollapsingHeader::new(tr("planner.byproduct_policy"))
+            .id_salt("byproduct-policy")
+            .show(ui, |ui| {
+                if items.is_empty() {
+                    ui.label(tr("planner.byproduct_empty"));
+                    return;
+                }
+                for item in items {
+                    let policy = self.byproduct_policies.entry(item.clone()).or_default();
+                    ui.horizontal(|ui| {
+                        ui.add_sized(crate::settings::icon_cell(), GenericIcon::new(ctx, &item));
+                        ui.label(ctx.get_generic_item_display_name(&item));
+                        let mut kind = byproduct_policy_kind(policy);
+                        egui::ComboBox::new(("byproduct", &item), "")
+                            .selected_text(byproduct_policy_name(kind))
+                            .show_ui(ui, |ui| {
+                                for option in 0..3 {
+                                    ui.selectable_value(
+                                        &mut kind,
+                                        option,
+                                        byproduct_policy_name(option),
+                                    );
+                                }
+                            });
+                        if kind != byproduct_policy_kind(policy) {
+                            *policy = match kind {
+                                1 => ByproductPolicy::Zero,
+                                2 => ByproductPolicy::Sinks(vec![]),
+                                _ => ByproductPolicy::Allow,
+                            };
+                            *changed = true;
+                        }
+                    });
+                    // 只能由选中的机制消耗，候选为所有消耗该物品的机制
+                    if let ByproductPolicy::Sinks(sinks) = policy {
+                        ui.indent(("byproduct-sinks", &item), |ui| {
+                            let mut any = false;
+                            for (id, mechanic) in &self.mechanics {
+                                let consumes = self
+                                    .flow_cache
+                                    .lock()
+                                    .unwrap()
+                                    .get(*id, mechanic.as_ref(), &self.bonuses, ctx)
+                                    .get(&item)
+                                    .is_some_and(|amount| *amount < 0.0);
+                                if !consumes {
+                                    continue;
+                                }
+                                any = true;
+                                let mut selected = sinks.contains(id);
+                                if ui
+                                    .checkbox(
+                                        &mut selected,
+                                        mechanic_display_name(mechanic.as_ref(), ctx),
+                                    )
+                                    .changed()
+                                {
+                                    if selected {
+                                        sinks.push(*id);
+                                    } else {
+                                        sinks.retain(|sink| sink != id);
+                                    }
+                                    *changed = true;
+                                }
+                            }
+                            if !any {
+                                ui.label(tr("planner.byproduct_no_sinks"));
+                            } else if sinks.is_empty() {
+                                ui.colored_label(
+                                    egui::Color32::LIGHT_RED,
+                                    tr("planner.byproduct_sinks_empty"),
+                                );
+                            }
+                        });
+                    }
+                }
+            });
+        self.byproduct_policies
+            .retain(|_, policy| *policy != ByproductPolicy::Allow);
+    }
+
+    fn flows_panel(&mut self, ui: &mut egui::Ui, ctx: &FactorioContext, changed: &mut bool) {
+        let rate_unit = self.rate_unit();
+        let label = ui.label(trf(
+            "planner.total_flow",
+            &[&compact_number(self.solution.1), &rate_unit.suffix()],
+        ));
+        tutorial::anchor(TutorialStep::ReadSolution, &label);
+        if self.grid_import && !self.total_flow.is_empty() {
+            let draw = self.grid_draw();
+            ui.label(
+                egui::RichText::new(trf(
+                    "planner.grid_draw",
+                    &[
+                        &format!("{}W", compact_number(draw)),
+                        &compact_number(draw / 1e6 * self.grid_price),
+                    ],
+                ))
+                .strong()
+                .color(ui.visuals().warn_fg_color),
+            )
+            .on_hover_text(tr("planner.grid_draw_hint"));
+        }
+        let telemetry = self.solver.telemetry();
+        if let Some(duration) = telemetry.last_duration() {
+            let busy = if telemetry.is_busy() { " ⏳" } else { "" };
+            ui.label(
+                egui::RichText::new(trf(
+                    "planner.last_solve",
+                    &[&format!("{:.1}", duration.as_secs_f64() * 1000.0), &busy],
+                ))
+                .weak(),
+            )
+            .on_hover_text(trf(
+                "planner.solver_telemetry",
+                &[&telemetry.solves(), &telemetry.skipped()],
+            ));
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .button(tr("planner.save_snapshot"))
+                .on_hover_text(tr("planner.save_snapshot_hint"))
+                .clicked()
+            {
+                self.solution_snapshot = Some(SolutionSnapshot::capture(self, ctx));
+                crate::toast::info(tr("planner.snapshot_saved"));
+            }
+            if ui
+                .add_enabled(
+                    self.solution_snapshot.is_some(),
+                    egui::Button::new(tr("planner.compare_snapshot")),
+                )
+                .clicked()
+                && let Some(before) = self.solution_snapshot.clone()
+            {
+                let after = SolutionSnapshot::capture(self, ctx);
+                self.pending_subviews
+                    .push(Box::new(SolutionDiffView::new(before, after)));
+            }
+            if ui
+                .button(tr("planner.build_list"))
+                .on_hover_text(tr("planner.build_list_hint"))
+                .clicked()
+            {
+                self.pending_subviews.push(Box::new(BuildListView::new(
+                    BuildList::collect(self, ctx),
+                    ctx,
+                )));
+            }
+            if ui
+                .button(tr("planner.ramp_up"))
+                .on_hover_text(tr("planner.ramp_up_hint"))
+                .clicked()
+            {
+                self.pending_subviews
+                    .push(Box::new(RampUpView::new(self, ctx)));
+            }
+            let export = ui
+                .button(tr("planner.export_ingame"))
+                .on_hover_text(tr("planner.export_ingame_hint"));
+            show_modal(export.id, export.clicked(), ui, |ui| {
+                ui.add(PlanExportPanel {
+                    export: &PlanExport::collect(self, ctx),
+                });
+            });
+            if ui
+                .button(tr("planner.compare_stats"))
+                .on_hover_text(tr("planner.compare_stats_hint"))
+                .clicked()
+                && let Some(path) = crate::file_dialog::FileDialog::new()
+                    .add_filter(tr("planner.stats_filter"), &["json"])
+                    .pick_file()
+            {
+                match GameStatistics::load(&path) {
+                    Ok(stats) => self
+                        .pending_subviews
+                        .push(Box::new(StatsComparisonView::new(self, &stats, ctx))),
+                    Err(err) => crate::toast::error(format!("{:?}", err)),
+                }
+            }
+            let import = ui
+                .button(tr("planner.import_research"))
+                .on_hover_text(tr("planner.import_research_hint"));
+            show_modal(import.id, import.clicked(), ui, |ui| {
+                ui.heading(tr("planner.import_research"));
+                ui.label(tr("planner.import_research_steps"));
+                ui.add(egui::Label::new(egui::RichText::new(EXPORT_COMMAND).monospace()).wrap());
+                ui.horizontal(|ui| {
+                    if ui.button(tr("planner.copy_command")).clicked() {
+                        ui.ctx().copy_text(EXPORT_COMMAND.to_string());
+                    }
+                    if ui.button(tr("planner.choose_export")).clicked()
+                        && let Some(path) = crate::file_dialog::FileDialog::new()
+                            .add_filter(tr("planner.research_filter"), &["json"])
+                            .set_file_name(EXPORT_FILE_NAME)
+                            .pick_file()
+                    {
+                        *changed |= self.import_research(&path, ctx);
+                    }
+                });
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.show_variance, tr("planner.variance"))
+                .on_hover_text(tr("planner.variance_hint"));
+            if self.show_variance {
+                ui.label(tr("planner.variance_window"));
+                ui.add(
+                    egui::DragValue::new(&mut self.variance_window)
+                        .range(1.0..=86400.0)
+                        .suffix("s"),
+                );
+            }
+        });
+        let variance = if self.show_variance {
+            self.output_variance(ctx)
+        } else {
+            Flow::new()
+        };
+        let issues = run_lints(&self.mechanics, &self.target, ctx, &self.lint_settings);
+        ui.add(HealthReport::new(&mut self.lint_settings, &issues));
+        let produced = self.produced_items(ctx);
+        ui.horizontal_wrapped(|ui| {
+            card_frame(ui).show(ui, |ui| {
+                ui.set_min_width(ui.available_width());
+                ui.set_min_height(50.0);
+                let mut modal = HintModal::new(
+                    label.id,
+                    ctx,
+                    &self.mechanic_sender,
+                    &mut self.mechanic_suggestions,
+                    &self.mechanic_providers,
+                )
+                .with_ranker(hint_relevance);
+                let mut final_clicked = None;
+                let mut explore_clicked = None;
+                let mut inspect_clicked = None;
+                let mut external_clicked = None;
+                for item in &self.total_flow_sorted_keys {
+                    let amount = self.total_flow.get(item).cloned().unwrap_or(0.0);
+                    let external = self.external.iter().any(|(external, _)| external == item);
+                    if amount.abs() < 1e-6 && !external {
+                        continue;
+                    }
+                    ui.vertical(|ui| {
+                        ui.add_sized(
+                            crate::settings::label_cell(),
+                            SignedCompactLabel::rate(amount, rate_unit.for_item(item)),
+                        );
+                        if let Some(variance) = variance.get(item) {
+                            let window = self.variance_window;
+                            ui.add_sized(
+                                crate::settings::label_cell(),
+                                egui::Label::new(
+                                    egui::RichText::new(format!(
+                                        "±{}",
+                                        compact_number(
+                                            (variance / window).sqrt()
+                                                * rate_unit.for_item(item).multiplier()
+                                        )
+                                    ))
+                                    .small()
+                                    .weak(),
+                                ),
+                            )
+                            .on_hover_text(trf(
+                                "planner.variance_item",
+                                &[
+                                    &window,
+                                    &compact_number((variance * window).sqrt()),
+                                    &compact_number(VARIANCE_BUFFER_Z * (variance * window).sqrt()),
+                                ],
+                            ));
+                        }
+                        let icon = ui
+                            .push_id(item, |ui| {
+                                ui.add_sized(
+                                    crate::settings::icon_cell(),
+                                    GenericIcon::new(ctx, item),
+                                )
+                                .interact(egui::Sense::click())
+                                .on_hover_text(tr("inspector.total_icon_hint"))
+                            })
+                            .inner;
+                        if external {
+                            paint_external_badge(ui, icon.rect);
+                        }
+                        // 工厂中没有产出的物品已经只能从外部获得，标记后求解结果不变
+                        if can_toggle_external(item, external, &produced) {
+                            let toggle = ui
+                                .add_sized(
+                                    crate::settings::label_cell(),
+                                    egui::Button::new(
+                                        egui::RichText::new(tr("planner.mark_external")).small(),
+                                    )
+                                    .selected(external),
+                                )
+                                .on_hover_text(if external {
+                                    tr("planner.unmark_external_hint")
+                                } else {
+                                    tr("planner.mark_external_hint")
+                                });
+                            if toggle.clicked() {
+                                external_clicked = Some(item.clone());
+                            }
+                        }
+
+                        if icon.clicked_by(egui::PointerButton::Secondary) {
+                            self.insert_after = None;
+                            explore_clicked = Some(item);
+                        } else if icon.clicked() && ui.input(|input| input.modifiers.shift) {
+                            inspect_clicked = Some(item.clone());
+                        } else if icon.clicked() {
+                            self.insert_after = None;
+                            final_clicked = Some((item, amount));
+                        }
+                    });
+                    if ui.available_size_before_wrap().x < crate::settings::icon_cell()[0] {
+                        ui.end_row();
                     }
                 }
                 if let Some((item, amount)) = final_clicked {
                     modal = modal.with_update(true, item, amount);
                 }
+                if let Some(item) = inspect_clicked {
+                    self.inspector.open(item);
+                }
                 ui.add(modal);
+                let mut explorer = AlternativesModal::new(
+                    label.id.with("alternatives"),
+                    ctx,
+                    &self.mechanic_sender,
+                    &mut self.alternatives,
+                    &self.mechanic_providers,
+                );
+                if let Some(item) = explore_clicked {
+                    explorer = explorer.with_update(true, item);
+                }
+                ui.add(explorer);
+                if let Some(item) = external_clicked {
+                    self.toggle_external(&item);
+                    *changed = true;
+                }
             });
         });
+        self.byproduct_panel(ui, ctx, changed);
         ui.separator();
-        self.mechanics.retain_mut(|flow_config| {
+        ui.horizontal(|ui| {
+            ui.selectable_value(
+                &mut self.show_machine_count,
+                false,
+                tr("planner.display_multiplier"),
+            )
+            .on_hover_text(tr("planner.display_multiplier_hint"));
+            ui.selectable_value(
+                &mut self.show_machine_count,
+                true,
+                tr("planner.display_machine_count"),
+            )
+            .on_hover_text(tr("planner.display_machine_count_hint"));
+            ui.separator();
+            let all_collapsed = !self.mechanics.is_empty()
+                && self.mechanics.keys().all(|id| self.collapsed.contains(id));
+            if all_collapsed {
+                if ui.button(tr("planner.expand_all")).clicked() {
+                    self.collapsed.clear();
+                }
+            } else if ui.button(tr("planner.collapse_all")).clicked() {
+                self.collapsed = self.mechanics.keys().copied().collect();
+            }
+            ui.separator();
+            self.selection_toolbar(ui, ctx, changed);
+        });
+        let show_machine_count = self.show_machine_count;
+        let mut shown = 0;
+        let total = self.mechanics.len();
+        self.mechanics.retain(|id, flow_config| {
+            if self.mechanic_filter.is_active()
+                && !self.mechanic_filter.matches(
+                    flow_config.as_ref(),
+                    &self.flow_cache.lock().unwrap().get(
+                        *id,
+                        flow_config.as_ref(),
+                        &self.bonuses,
+                        ctx,
+                    ),
+                    &self.total_flow,
+                    self.location.as_deref(),
+                    ctx,
+                )
+            {
+                return true;
+            }
+            shown += 1;
+            // 已知高度且不在可见范围内的卡片只占位，大型工厂中每帧只绘制可见的卡片
+            let height_key = (*id, self.collapsed.contains(id));
+            if self.inspector.scroll_to != Some(*id)
+                && let Some(height) = self.card_heights.get(&height_key)
+            {
+                let rect = egui::Rect::from_min_size(
+                    ui.cursor().min,
+                    egui::vec2(ui.available_width(), *height),
+                );
+                if !ui.is_rect_visible(rect) {
+                    ui.allocate_space(rect.size());
+                    return true;
+                }
+            }
             let mut deleted = false;
-            card_frame(ui).show(ui, {
+            let mut frame = card_frame(ui);
+            if self.inspector.is_highlighted(*id, ui) {
+                frame = frame.stroke(egui::Stroke::new(2.0, ui.visuals().selection.stroke.color));
+            }
+            let card = frame.show(ui, {
                 |ui| {
                     ui.set_min_width(ui.available_width());
+                    if self.collapsed.contains(id) {
+                        if collapsed_card(
+                            ui,
+                            ctx,
+                            flow_config.as_ref(),
+                            &self.flow_cache.lock().unwrap().get(
+                                *id,
+                                flow_config.as_ref(),
+                                &self.bonuses,
+                                ctx,
+                            ),
+                            self.solution.0.get(id).cloned(),
+                            show_machine_count,
+                            rate_unit,
+                        ) {
+                            self.collapsed.shift_remove(id);
+                        }
+                        return;
+                    }
                     ui.horizontal(|ui| {
-                        let ptr = box_as_ptr(flow_config);
-                        let solution_val = self.solution.0.get(&ptr).cloned();
+                        let solution_val = self.solution.0.get(id).cloned();
 
                         ui.vertical(|ui| {
-                            if ui.button("删除").clicked() {
+                            if ui
+                                .small_button("⏶")
+                                .on_hover_text(tr("planner.collapse_card"))
+                                .clicked()
+                            {
+                                self.collapsed.insert(*id);
+                            }
+                            let mut selected = self.selected.contains(id);
+                            if ui
+                                .checkbox(&mut selected, tr("planner.select"))
+                                .on_hover_text(tr("planner.select_hint"))
+                                .changed()
+                            {
+                                if selected {
+                                    self.selected.insert(*id);
+                                } else {
+                                    self.selected.shift_remove(id);
+                                }
+                            }
+                            if ui.button(tr("common.delete")).clicked() {
                                 deleted = true;
                                 *changed = true;
                             }
-                            if ui.button("复制").clicked() {
+                            if ui
+                                .button(tr("common.cut"))
+                                .on_hover_text(tr("planner.cut_hint"))
+                                .clicked()
+                            {
+                                ui.ctx().copy_text(encode_mechanics([flow_config.as_ref()]));
+                                deleted = true;
+                                *changed = true;
+                            }
+                            if ui
+                                .button(tr("common.copy"))
+                                .on_hover_text(tr("planner.copy_hint"))
+                                .clicked()
+                            {
+                                ui.ctx().copy_text(encode_mechanics([flow_config.as_ref()]));
+                                crate::toast::info(tr("planner.copied"));
+                            }
+                            if ui.button(tr("common.duplicate")).clicked() {
                                 let serialized = serde_json::to_value(&flow_config);
                                 let deserialized =
                                     MECHANIC_REGISTRY.deserialize(serialized.unwrap());
                                 if let Ok(deserialized) = deserialized {
+                                    self.insert_after = Some(*id);
                                     self.mechanic_sender.send(deserialized).unwrap();
                                 }
                                 *changed = true;
                             }
+                            let mut locked = self.locked.contains_key(id);
+                            if ui
+                                .checkbox(&mut locked, tr("planner.lock"))
+                                .on_hover_text(tr("planner.lock_hint"))
+                                .changed()
+                            {
+                                if locked {
+                                    // 默认锁定为当前方案所需的台数
+                                    let count = solution_val
+                                        .map_or(1, |solution| machine_count(solution).0.max(1));
+                                    self.locked.insert(*id, count as f64);
+                                } else {
+                                    self.locked.shift_remove(id);
+                                }
+                                *changed = true;
+                            }
+                            if let Some(count) = self.locked.get_mut(id) {
+                                *changed |= ui
+                                    .add(
+                                        egui::DragValue::new(count)
+                                            .range(0.0..=f64::INFINITY)
+                                            .speed(0.1),
+                                    )
+                                    .changed();
+                            }
                             if let Some(solution) = solution_val {
+                                *changed |= back_solve_editor(
+                                    ui,
+                                    *id,
+                                    solution,
+                                    &mut self.locked,
+                                    &mut self.target,
+                                    &mut self.quality_targets,
+                                );
+                            }
+                            if let Some(solution) = solution_val
+                                && show_machine_count
+                            {
+                                let (count, utilization) = machine_count(solution);
+                                ui.label(trf("planner.machine_count", &[&count]))
+                                    .on_hover_text(trf(
+                                        "planner.machine_count_hint",
+                                        &[&compact_number(solution)],
+                                    ));
+                                if count > 0 {
+                                    ui.label(trf(
+                                        "planner.last_machine_utilization",
+                                        &[&format!("{:.0}", utilization * 100.0)],
+                                    ));
+                                }
+                            } else if let Some(solution) = solution_val {
                                 ui.add(CompactLabel::new(solution));
                             } else {
-                                ui.label("待解");
+                                ui.label(tr("planner.unsolved"));
+                            }
+                            if let Some(mining) = (flow_config.as_ref() as &dyn std::any::Any)
+                                .downcast_ref::<MiningConfig>()
+                                && let Some(solution) = solution_val
+                            {
+                                let flow = self.flow_cache.lock().unwrap().get(
+                                    *id,
+                                    flow_config.as_ref(),
+                                    &self.bonuses,
+                                    ctx,
+                                );
+                                if let Some(lifetime) = mining.patch_lifetime(&flow, solution) {
+                                    ui.label(trf(
+                                        "planner.patch_lifetime",
+                                        &[&format_duration(lifetime)],
+                                    ))
+                                    .on_hover_text(trf(
+                                        "planner.patch_lifetime_hint",
+                                        &[
+                                            &compact_number(
+                                                mining.resource_drain(&flow) * solution,
+                                            ),
+                                            &compact_number(
+                                                mining.patch_yield(&flow).unwrap_or(0.0),
+                                            ),
+                                        ],
+                                    ));
+                                }
+                            }
+                            if let Some(location) = &self.location {
+                                let violations = mechanic_surface_violations(
+                                    flow_config.as_ref(),
+                                    ctx,
+                                    location,
+                                );
+                                if !violations.is_empty() {
+                                    ui.colored_label(
+                                        egui::Color32::YELLOW,
+                                        tr("planner.location_blocked"),
+                                    )
+                                    .on_hover_text(violations.join("\n"));
+                                }
                             }
                         });
 
                         ui.separator();
                         ui.vertical(|ui: &mut egui::Ui| {
-                            *changed |= flow_config.editor_view(ui, ctx)
+                            if flow_config.editor_view(ui, ctx) {
+                                self.flow_cache.lock().unwrap().invalidate(*id);
+                                *changed = true;
+                            }
                         });
 
                         ui.separator();
-                        let flow = flow_config.as_flow(ctx);
+                        let flow = self.flow_cache.lock().unwrap().get(
+                            *id,
+                            flow_config.as_ref(),
+                            &self.bonuses,
+                            ctx,
+                        );
                         let mut keys = flow.keys().collect::<Vec<_>>();
                         sort_generic_items(&mut keys, ctx);
                         ui.horizontal_top(|ui| {
@@ -268,16 +2267,32 @@ impl FactoryInstance {
 
                                     ui.vertical(|ui| {
                                         ui.add_sized(
-                                            [35.0, 15.0],
-                                            SignedCompactLabel::new(
-                                                amount * solution_val.unwrap_or(1.0),
+                                            crate::settings::label_cell(),
+                                            SignedCompactLabel::rate(
+                                                if show_machine_count {
+                                                    amount
+                                                } else {
+                                                    amount * solution_val.unwrap_or(1.0)
+                                                },
+                                                rate_unit.for_item(item),
                                             ),
                                         );
                                         let icon = ui
-                                            .add_sized([35.0, 35.0], GenericIcon::new(ctx, item))
-                                            .interact(egui::Sense::click());
+                                            .add_sized(
+                                                crate::settings::icon_cell(),
+                                                GenericIcon::new(ctx, item),
+                                            )
+                                            .interact(egui::Sense::click())
+                                            .on_hover_text(tr("inspector.card_icon_hint"));
+                                        if icon.clicked() {
+                                            self.inspector.open(item.clone());
+                                        }
                                         let toggle =
                                             icon.clicked_by(egui::PointerButton::Secondary);
+                                        if toggle {
+                                            self.insert_after = Some(*id);
+                                        }
+                                        tutorial::anchor(TutorialStep::AcceptHint, &icon);
                                         ui.add(
                                             HintModal::new(
                                                 icon.id,
@@ -286,27 +2301,333 @@ impl FactoryInstance {
                                                 &mut self.mechanic_suggestions,
                                                 &self.mechanic_providers,
                                             )
+                                            .with_ranker(hint_relevance)
                                             .with_update(toggle, item, amount),
                                         );
                                     });
-                                    if ui.available_size_before_wrap().x < 35.0 {
+                                    if ui.available_size_before_wrap().x
+                                        < crate::settings::icon_cell()[0]
+                                    {
                                         ui.end_row();
                                     }
                                 }
                             });
                         });
-                    })
+                    });
+                    note_view(
+                        ui,
+                        ("mechanic_notes", id),
+                        self.mechanic_notes.entry(*id).or_default(),
+                    );
                 }
             });
+            self.card_heights
+                .insert(height_key, card.response.rect.height());
+            if self.inspector.scroll_to == Some(*id) {
+                card.response.scroll_to_me(Some(egui::Align::Center));
+                self.inspector.scroll_to = None;
+            }
+            if deleted {
+                self.locked.shift_remove(id);
+                self.mechanic_notes.shift_remove(id);
+                self.collapsed.shift_remove(id);
+                self.selected.shift_remove(id);
+                for policy in self.byproduct_policies.values_mut() {
+                    if let ByproductPolicy::Sinks(sinks) = policy {
+                        sinks.retain(|sink| sink != id);
+                    }
+                }
+            }
             !deleted
         });
+        if self.mechanic_filter.is_active() {
+            ui.label(trf("planner.filtered", &[&shown, &total]));
+        }
+    }
+}
+
+/// 缓冲大小取波动标准差的倍数，对应约 99% 的时间不会断供
+const VARIANCE_BUFFER_Z: f64 = 2.326;
+
+/// 折叠后机制卡片的单行摘要：展开按钮、主产物图标、名称、台数与绝对值最大的几项物料流，
+/// 返回是否点击了展开
+fn collapsed_card(
+    ui: &mut egui::Ui,
+    ctx: &FactorioContext,
+    mechanic: &FactorioMechanic,
+    flow: &Flow<GenericItem>,
+    solution: Option<f64>,
+    show_machine_count: bool,
+    rate_unit: RateUnit,
+) -> bool {
+    const SUMMARY_FLOWS: usize = 3;
+    let mut expand = false;
+    ui.horizontal(|ui| {
+        expand = ui
+            .small_button("⏷")
+            .on_hover_text(tr("planner.expand_card"))
+            .clicked();
+        let product = flow
+            .iter()
+            .filter(|(_, amount)| **amount > 0.0)
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(item, _)| item);
+        if let Some(product) = product {
+            ui.add_sized(crate::settings::icon_cell(), GenericIcon::new(ctx, product));
+        }
+        ui.strong(mechanic_display_name(mechanic, ctx));
+        match solution {
+            Some(solution) if show_machine_count => {
+                ui.label(trf("planner.machine_count", &[&machine_count(solution).0]));
+            }
+            Some(solution) => {
+                ui.add(CompactLabel::new(solution));
+            }
+            None => {
+                ui.label(tr("planner.unsolved"));
+            }
+        }
+        ui.separator();
+        let mut flows = flow.iter().collect::<Vec<_>>();
+        flows.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+        for (item, amount) in flows.into_iter().take(SUMMARY_FLOWS) {
+            let amount = if show_machine_count {
+                *amount
+            } else {
+                amount * solution.unwrap_or(1.0)
+            };
+            ui.add(GenericIcon::new(ctx, item).with_size(crate::settings::get().icon_size * 0.6));
+            ui.add(SignedCompactLabel::rate(amount, rate_unit.for_item(item)));
+        }
+    });
+    expand
+}
+
+/// 物品能否切换额外输入：已经标记的物品，或工厂中有机制产出的物品，电力与热量除外
+pub fn can_toggle_external(
+    item: &GenericItem,
+    external: bool,
+    produced: &IndexSet<GenericItem>,
+) -> bool {
+    !matches!(item, GenericItem::Electricity | GenericItem::Heat)
+        && (external || produced.contains(item))
+}
+
+fn apply_route_to_collector(mechanic: &mut FactorioMechanic, route: &str) {
+    if let Some(collector) =
+        (mechanic as &mut dyn std::any::Any).downcast_mut::<AsteroidCollectorConfig>()
+    {
+        collector.route = route.to_string();
+    }
+}
+
+/// 卡片上正在进行的反推编辑
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BackSolveEdit {
+    count: f64,
+    /// true 表示按比例调整目标，false 表示锁定台数
+    scale_targets: bool,
+}
+
+/// 在机制卡片上输入期望的台数，锁定该机制或按比例缩放目标，返回是否需要重新求解
+fn back_solve_editor(
+    ui: &mut egui::Ui,
+    id: MechanicId,
+    solution: f64,
+    locked: &mut IndexMap<MechanicId, f64>,
+    target: &mut [(GenericItem, f64)],
+    quality_targets: &mut [QualityTarget],
+) -> bool {
+    let memory_id = ui.id().with(("back-solve", id));
+    let Some(mut edit) = ui.data(|data| data.get_temp::<BackSolveEdit>(memory_id)) else {
+        if ui
+            .small_button(tr("planner.back_solve"))
+            .on_hover_text(tr("planner.back_solve_hint"))
+            .clicked()
+        {
+            let edit = BackSolveEdit {
+                count: locked
+                    .get(&id)
+                    .copied()
+                    .unwrap_or(machine_count(solution).0.max(1) as f64),
+                scale_targets: !locked.contains_key(&id),
+            };
+            ui.data_mut(|data| data.insert_temp(memory_id, edit));
+        }
+        return false;
+    };
+    let mut changed = false;
+    let mut close = false;
+    ui.add(
+        egui::DragValue::new(&mut edit.count)
+            .range(0.0..=f64::INFINITY)
+            .speed(0.1)
+            .suffix(trf("planner.machine_count", &[&""])),
+    );
+    ui.selectable_value(&mut edit.scale_targets, false, tr("planner.lock"));
+    ui.selectable_value(
+        &mut edit.scale_targets,
+        true,
+        tr("planner.back_solve_target"),
+    );
+    ui.weak(if edit.scale_targets {
+        tr("planner.back_solve_target_mode")
+    } else {
+        tr("planner.back_solve_lock_mode")
+    });
+    ui.horizontal(|ui| {
+        if ui.button(tr("planner.back_solve_apply")).clicked() {
+            if !edit.scale_targets {
+                locked.insert(id, edit.count);
+                changed = true;
+            } else if scale_targets_for_count(target, quality_targets, solution, edit.count) {
+                locked.shift_remove(&id);
+                changed = true;
+            } else {
+                crate::toast::warning(tr("planner.back_solve_unavailable"));
+            }
+            close = true;
+        }
+        if ui.button(tr("common.cancel")).clicked() {
+            close = true;
+        }
+    });
+    ui.data_mut(|data| {
+        if close {
+            data.remove::<BackSolveEdit>(memory_id);
+        } else {
+            data.insert_temp(memory_id, edit);
+        }
+    });
+    changed
+}
+
+/// 按比例缩放全部目标（包括按品质展开的目标），使当前倍数为 solution 的机制变为 count，
+/// 锁定的机制与外部输入的上限不随之缩放，因此结果可能略有出入
+fn scale_targets_for_count(
+    target: &mut [(GenericItem, f64)],
+    quality_targets: &mut [QualityTarget],
+    solution: f64,
+    count: f64,
+) -> bool {
+    if solution <= 1e-9 || (target.is_empty() && quality_targets.is_empty()) {
+        return false;
+    }
+    let ratio = count / solution;
+    for (_, amount) in target.iter_mut() {
+        *amount *= ratio;
+    }
+    for quality_target in quality_targets.iter_mut() {
+        quality_target.total *= ratio;
+    }
+    true
+}
+
+/// 在由额外输入供应的物品图标上画出边框与角标
+fn paint_external_badge(ui: &egui::Ui, rect: egui::Rect) {
+    let color = egui::Color32::from_rgb(90, 170, 230);
+    let painter = ui.painter();
+    painter.rect_stroke(
+        rect,
+        2.0,
+        egui::Stroke::new(1.5, color),
+        egui::StrokeKind::Inside,
+    );
+    painter.text(
+        rect.right_top() + egui::vec2(-2.0, 1.0),
+        egui::Align2::RIGHT_TOP,
+        "⇲",
+        egui::FontId::proportional(10.0),
+        color,
+    );
+}
+
+/// 求解得到的倍数所需的机器台数，以及最后一台机器的利用率
+fn machine_count(multiplier: f64) -> (u64, f64) {
+    // 容许求解器的舍入误差，避免 2.0000001 被算作 3 台
+    let count = (multiplier - 1e-6).ceil().max(0.0);
+    if count == 0.0 {
+        return (0, 0.0);
+    }
+    (count as u64, (multiplier - (count - 1.0)).min(1.0))
+}
+
+/// 流体温度的中转流，标识符从最大值向下分配，不会与机制的标识符冲突
+fn temperature_bridges<'a>(
+    ctx: &FactorioContext,
+    flows: impl IntoIterator<Item = &'a Flow<GenericItem>>,
+    target: &[(GenericItem, f64)],
+) -> Vec<(MechanicId, Flow<GenericItem>)> {
+    // 目标物品同样视为需求，才能由更高温度的流体满足
+    let items = flows
+        .into_iter()
+        .flat_map(|flow| flow.iter().map(|(item, amount)| (item, *amount)))
+        .chain(target.iter().map(|(item, _)| (item, -1.0)));
+    fluid_temperature_bridges(ctx, items)
+        .into_iter()
+        .enumerate()
+        .map(|(index, flow)| (MechanicId(u64::MAX - index as u64), flow))
+        .collect()
+}
+
+/// 粘贴窗口中尚未提交的文本
+#[derive(Debug, Clone, Default)]
+struct PasteText(String);
+
+/// 盈余处理方式在选择框中的序号，Sinks 的机制列表单独编辑
+fn byproduct_policy_kind(policy: &ByproductPolicy<MechanicId>) -> usize {
+    match policy {
+        ByproductPolicy::Allow => 0,
+        ByproductPolicy::Zero => 1,
+        ByproductPolicy::Sinks(_) => 2,
+    }
+}
+
+fn byproduct_policy_name(kind: usize) -> &'static str {
+    match kind {
+        1 => tr("byproduct.zero"),
+        2 => tr("byproduct.sinks"),
+        _ => tr("byproduct.allow"),
     }
 }
 
+pub fn target_mode_name(mode: TargetMode) -> &'static str {
+    match mode {
+        TargetMode::Exact => tr("target_mode.exact"),
+        TargetMode::AtLeast => tr("target_mode.at_least"),
+        TargetMode::Maximize => tr("target_mode.maximize"),
+    }
+}
+
+/// 目标与外部输入卡片上的物品类型名称
+fn generic_item_kind_name(item: &GenericItem) -> &'static str {
+    match item {
+        GenericItem::Item { .. } => tr("item_kind.item"),
+        GenericItem::Fluid { .. } => tr("item_kind.fluid"),
+        GenericItem::Entity { .. } => tr("item_kind.entity"),
+        GenericItem::Heat => tr("item_kind.heat"),
+        GenericItem::Electricity => tr("item_kind.electricity"),
+        GenericItem::FluidHeat { .. } => tr("item_kind.fluid_heat"),
+        GenericItem::FluidFuel { .. } => tr("item_kind.fluid_fuel"),
+        GenericItem::ItemFuel { .. } => tr("item_kind.item_fuel"),
+        GenericItem::RocketPayloadWeight => tr("item_kind.payload_weight"),
+        GenericItem::RocketPayloadStack => tr("item_kind.payload_stack"),
+        GenericItem::Pollution { .. } => tr("item_kind.pollution"),
+        _ => tr("item_kind.special"),
+    }
+}
+
+/// 在目标选择器的图标上标出能产出该物品的配方与资源数量
+fn producer_badge(category: &str) -> impl Fn(&str, &FactorioContext) -> Option<String> + '_ {
+    move |name, ctx| Some(ctx.producer_count(category, name).to_string())
+}
+
 pub struct StatefulFactoryInstance {
     pub factory: FactoryInstance,
     pub saved: bool,
     pub file_path: Option<std::path::PathBuf>,
+    /// 工厂绑定的上下文在 PlannerView::contexts 中的下标
+    pub context: usize,
 }
 
 impl From<FactoryInstance> for StatefulFactoryInstance {
@@ -315,18 +2636,117 @@ impl From<FactoryInstance> for StatefulFactoryInstance {
             factory,
             saved: false,
             file_path: None,
+            context: 0,
         }
     }
 }
 
+/// 带名称的游戏上下文，用于同时加载多套模组的数据
+pub struct NamedContext {
+    pub name: String,
+    pub ctx: FactorioContext,
+}
+
+/// 迁移确认后的去向
+enum MigrationTarget {
+    /// 从存档加载的新工厂
+    Open {
+        factory: Box<FactoryInstance>,
+        path: std::path::PathBuf,
+    },
+    /// 已打开的工厂切换到另一个上下文
+    Rebind { factory: usize },
+    /// 由示例模板新建的工厂
+    Template { factory: Box<FactoryInstance> },
+}
+
 pub struct PlannerView {
     /// 存储游戏逻辑数据的全部上下文
-    pub ctx: FactorioContext,
+    pub contexts: Vec<NamedContext>,
+    /// 新建或加载工厂时使用的上下文
+    pub selected_context: usize,
 
     pub factories: Vec<StatefulFactoryInstance>,
 
     pub selected_factory: usize,
     pub new_factory_name: String,
+    /// 标签栏只显示带有该颜色标签的工厂
+    pub tab_filter: Option<FactoryTag>,
+
+    pub subview_sender: Option<std::sync::mpsc::Sender<Box<dyn Subview>>>,
+
+    /// 引用了缺失原型、等待用户确认迁移方式的工厂，以及迁移所依据的上下文
+    pending_migration: Option<(PendingMigration, MigrationTarget, usize)>,
+
+    /// 添加上下文时嵌入的创建界面
+    context_creator: Option<FactorioContextCreatorView>,
+    /// 是否显示示例工厂模板窗口
+    template_gallery: bool,
+    context_sender: std::sync::mpsc::Sender<FactorioContext>,
+    context_receiver: std::sync::mpsc::Receiver<FactorioContext>,
+    /// 通知中用于跳转到该规划器
+    key: SubviewKey,
+}
+
+/// 保存工厂到文件，成功时标记为已保存，失败时发送带跳转的通知
+fn save_factory(
+    factory: &mut StatefulFactoryInstance,
+    path: &std::path::Path,
+    target: SubviewKey,
+) -> bool {
+    match save_to_file(&factory.factory, path) {
+        Ok(()) => {
+            factory.saved = true;
+            crate::toast::success(trf("planner.factory_saved", &[&path.display()]));
+            true
+        }
+        Err(err) => {
+            crate::toast::notify(
+                crate::toast::Notification::new(
+                    crate::toast::Severity::Error,
+                    trf(
+                        "planner.save_failed",
+                        &[&path.display(), &format!("{:?}", err)],
+                    ),
+                )
+                .with_target(target),
+            );
+            false
+        }
+    }
+}
+
+/// 由启用的模组生成上下文的默认名称
+pub fn default_context_name(ctx: &FactorioContext) -> String {
+    let mods = ctx
+        .mods
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .filter(|name| !["base", "core"].contains(name))
+        .collect::<Vec<_>>();
+    if mods.is_empty() {
+        tr("context.vanilla").to_string()
+    } else {
+        mods.join(" + ")
+    }
+}
+
+/// 可折叠的备注，折叠时在标题中显示第一行
+fn note_view(ui: &mut egui::Ui, id_salt: impl std::hash::Hash, note: &mut String) {
+    let title = match note.lines().find(|line| !line.trim().is_empty()) {
+        Some(line) => format!("📝 {}", line),
+        None => format!("📝 {}", tr("planner.notes")),
+    };
+    egui::CollapsingHeader::new(title)
+        .id_salt(id_salt)
+        .show(ui, |ui| {
+            ui.add(
+                egui::TextEdit::multiline(note)
+                    .hint_text(tr("planner.notes_hint"))
+                    .desired_rows(3)
+                    .desired_width(f32::INFINITY),
+            );
+        });
 }
 
 impl SolveContext for FactoryInstance {
@@ -339,24 +2759,19 @@ impl EditorView for FactoryInstance {
         ui.add(
             egui::text_edit::TextEdit::singleline(&mut self.name).font(egui::TextStyle::Heading),
         );
+        note_view(ui, "factory_notes", &mut self.notes);
         ui.separator();
+        self.validate_flow_cache(ctx);
+        self.update_hint_preferences();
         let id = ui.id();
         let mut changed = false;
+        self.quality_availability().publish(ui.ctx());
+        self.machine_whitelist.publish(ui.ctx());
 
         while let Ok(result) = self.solution_receiver.try_recv() {
             match result {
                 Ok(solution) => {
-                    self.total_flow.clear();
-                    self.solution = solution;
-                    for fe in self.mechanics.iter_mut() {
-                        let var_value =
-                            self.solution.0.get(&box_as_ptr(fe)).cloned().unwrap_or(0.0);
-                        let flow = fe.as_flow(ctx);
-                        self.total_flow = flow_add(&self.total_flow, &flow, var_value);
-                    }
-                    // Update sorted keys cache when total_flow changes
-                    self.total_flow_sorted_keys = self.total_flow.keys().cloned().collect();
-                    sort_generic_items_owned(&mut self.total_flow_sorted_keys, ctx);
+                    self.apply_solution(solution, ctx);
                     ui.memory_mut(|mem| {
                         mem.data.remove::<String>(id);
                     })
@@ -381,7 +2796,16 @@ impl EditorView for FactoryInstance {
                 egui::ScrollArea::vertical().id_salt(1).show(ui, |ui| {
                     ui.horizontal_top(|ui| {
                         ui.vertical(|ui| {
-                            ui.heading("优化目标");
+                            ui.heading(tr("planner.targets"));
+                            self.target_set_bar(ui, ctx, &mut changed);
+                            ui.checkbox(
+                                &mut self.target_producible_only,
+                                tr("planner.producible_only"),
+                            )
+                            .on_hover_text(tr("planner.producible_only_hint"));
+                            let producible_only = self.target_producible_only;
+                            let can_maximize = self.can_maximize();
+                            let rate_unit = self.rate_unit();
                             self.target.retain_mut(|(item, amount)| {
                                 let mut deleted = false;
                                 card_frame(ui).show(ui, |ui| {
@@ -391,11 +2815,11 @@ impl EditorView for FactoryInstance {
                                             .vertical(|ui| {
                                                 let icon = ui
                                                     .add_sized(
-                                                        [35.0, 35.0],
+                                                        crate::settings::icon_cell(),
                                                         GenericIcon::new(ctx, item),
                                                     )
                                                     .interact(egui::Sense::click());
-                                                if ui.button("删除").clicked() {
+                                                if ui.button(tr("common.delete")).clicked() {
                                                     deleted = true;
                                                     changed = true;
                                                 }
@@ -404,6 +2828,9 @@ impl EditorView for FactoryInstance {
                                             .inner;
                                         let toggle =
                                             icon.clicked_by(egui::PointerButton::Secondary);
+                                        if toggle {
+                                            self.insert_after = None;
+                                        }
                                         ui.add(
                                             HintModal::new(
                                                 icon.id,
@@ -412,29 +2839,17 @@ impl EditorView for FactoryInstance {
                                                 &mut self.mechanic_suggestions,
                                                 &self.mechanic_providers,
                                             )
+                                            .with_ranker(hint_relevance)
                                             .with_update(toggle, item, -*amount),
                                         );
                                         ui.vertical(|ui| {
                                             egui::ComboBox::new(icon.id, "")
-                                                .selected_text(match item {
-                                                    GenericItem::Item { .. } => "物品",
-                                                    GenericItem::Fluid { .. } => "流体",
-                                                    GenericItem::Entity { .. } => "实体",
-                                                    GenericItem::Heat => "热量",
-                                                    GenericItem::Electricity => "电力",
-                                                    GenericItem::FluidHeat { .. } => "流体热量",
-                                                    GenericItem::FluidFuel { .. } => "流体燃料",
-                                                    GenericItem::ItemFuel { .. } => "物体燃料",
-                                                    GenericItem::RocketPayloadWeight => "重量载荷",
-                                                    GenericItem::RocketPayloadStack => "堆叠载荷",
-                                                    GenericItem::Pollution { .. } => "污染",
-                                                    _ => "特殊",
-                                                })
+                                                .selected_text(generic_item_kind_name(item))
                                                 .show_ui(ui, |ui| {
                                                     ui.selectable_value(
                                                         item,
                                                         GenericItem::Item("item-unknown".into()),
-                                                        "物品",
+                                                        tr("item_kind.item"),
                                                     );
                                                     ui.selectable_value(
                                                         item,
@@ -442,7 +2857,7 @@ impl EditorView for FactoryInstance {
                                                             name: "fluid-unknown".to_string(),
                                                             temperature: None,
                                                         },
-                                                        "流体",
+                                                        tr("item_kind.fluid"),
                                                     );
                                                 });
                                             ui.horizontal(|ui| {
@@ -452,10 +2867,17 @@ impl EditorView for FactoryInstance {
                                                             ItemWithQualitySelectorModal::new(
                                                                 icon.id.with("target-select-item"),
                                                                 ctx,
-                                                                "选择物品",
+                                                                tr("planner.select_item"),
                                                                 "item",
                                                             )
                                                             .with_toggle(icon.clicked())
+                                                            .with_filter(move |name, ctx| {
+                                                                !producible_only
+                                                                    || ctx.producer_count(
+                                                                        "item", name,
+                                                                    ) > 0
+                                                            })
+                                                            .with_badge(producer_badge("item"))
                                                             .with_current(item_with_quality)
                                                             .notify_change(&mut changed),
                                                         );
@@ -470,22 +2892,98 @@ impl EditorView for FactoryInstance {
                                                                     "target-select-fluid",
                                                                 ),
                                                                 ctx,
-                                                                "选择流体",
+                                                                tr("planner.select_fluid"),
                                                                 "fluid",
                                                             )
                                                             .with_toggle(icon.clicked())
+                                                            .with_filter(move |name, ctx| {
+                                                                !producible_only
+                                                                    || ctx.producer_count(
+                                                                        "fluid", name,
+                                                                    ) > 0
+                                                            })
+                                                            .with_badge(producer_badge("fluid"))
                                                             .with_current(name)
                                                             .notify_change(&mut changed),
                                                         );
                                                     }
                                                     _ => {}
                                                 }
-                                                if ui.vertical(|ui| {
-                                                    ui.label("目标产量");
-                                                    ui.add(
-                                                        egui::DragValue::new(amount).suffix("/秒"),
-                                                    )
-                                                }).inner.changed() {
+                                                if ui
+                                                    .vertical(|ui| {
+                                                        ui.label(tr("planner.target_rate"));
+                                                        ui.add(
+                                                            egui::DragValue::new(amount)
+                                                                .suffix(tr("unit.per_second")),
+                                                        )
+                                                    })
+                                                    .inner
+                                                    .changed()
+                                                {
+                                                    changed = true;
+                                                }
+                                                let mut mode = self
+                                                    .target_modes
+                                                    .get(item)
+                                                    .copied()
+                                                    .unwrap_or_default();
+                                                let mut mode_changed = false;
+                                                ui.vertical(|ui| {
+                                                    ui.label(tr("planner.target_mode"));
+                                                    egui::ComboBox::new(icon.id.with("mode"), "")
+                                                        .selected_text(target_mode_name(mode))
+                                                        .show_ui(ui, |ui| {
+                                                            for option in TargetMode::ALL {
+                                                                if ui
+                                                                    .add_enabled(
+                                                                        can_maximize
+                                                                            || option
+                                                                                != TargetMode::Maximize,
+                                                                        egui::Button::selectable(
+                                                                            mode == option,
+                                                                            target_mode_name(option),
+                                                                        ),
+                                                                    )
+                                                                    .on_disabled_hover_text(tr(
+                                                                        "planner.maximize_unbounded",
+                                                                    ))
+                                                                    .clicked()
+                                                                    && mode != option
+                                                                {
+                                                                    mode = option;
+                                                                    mode_changed = true;
+                                                                }
+                                                            }
+                                                        })
+                                                        .response
+                                                        .on_hover_text(tr(
+                                                            "planner.target_mode_hint",
+                                                        ));
+                                                });
+                                                if mode_changed {
+                                                    self.target_modes.insert(item.clone(), mode);
+                                                    changed = true;
+                                                }
+                                                if let GenericItem::Item(IdWithQuality(
+                                                    name,
+                                                    quality,
+                                                )) = item
+                                                    && ctx.qualities.len() > 1
+                                                    && ui
+                                                        .small_button(tr("planner.expand_quality"))
+                                                        .on_hover_text(tr(
+                                                            "planner.expand_quality_hint",
+                                                        ))
+                                                        .clicked()
+                                                {
+                                                    let mut expanded = QualityTarget::new(
+                                                        name.clone(),
+                                                        *quality,
+                                                        *amount,
+                                                    );
+                                                    expanded.mode = mode;
+                                                    self.quality_targets.push(expanded);
+                                                    deleted = true;
                                                     changed = true;
                                                 }
                                             });
@@ -494,16 +2992,95 @@ impl EditorView for FactoryInstance {
                                 });
                                 !deleted
                             });
-                            if ui.button("添加目标产物").clicked() {
-                                self.target
-                                    .push((GenericItem::Item("item-unknown".into()), 1.0));
-                                changed = true;
+                            let mut collapsed = Vec::new();
+                            self.quality_targets.retain_mut(|quality_target| {
+                                let action = card_frame(ui)
+                                    .show(ui, |ui| {
+                                        ui.set_min_width(ui.available_width());
+                                        QualityTargetCard::new(
+                                            ctx,
+                                            quality_target,
+                                            &self.total_flow,
+                                        )
+                                        .with_rate_unit(rate_unit)
+                                        .with_maximize(can_maximize)
+                                        .notify_change(&mut changed)
+                                        .show(ui)
+                                    })
+                                    .inner;
+                                match action {
+                                    None => true,
+                                    Some(QualityTargetAction::Delete) => {
+                                        changed = true;
+                                        false
+                                    }
+                                    Some(QualityTargetAction::Collapse) => {
+                                        collapsed.push(quality_target.clone());
+                                        changed = true;
+                                        false
+                                    }
+                                }
+                            });
+                            for quality_target in collapsed {
+                                let item = GenericItem::Item(IdWithQuality(
+                                    quality_target.item,
+                                    quality_target.min_quality,
+                                ));
+                                self.target_modes.insert(item.clone(), quality_target.mode);
+                                self.target.push((item, quality_target.total));
                             }
+                            ui.horizontal(|ui| {
+                                let add_target = ui.button(tr("planner.add_target"));
+                                tutorial::anchor(TutorialStep::AddTarget, &add_target);
+                                if add_target.clicked() {
+                                    self.target
+                                        .push((GenericItem::Item("item-unknown".into()), 1.0));
+                                    changed = true;
+                                    tutorial::complete(TutorialStep::AddTarget);
+                                }
+                                if ctx.qualities.len() > 1
+                                    && ui
+                                        .button(tr("planner.add_quality_target"))
+                                        .on_hover_text(tr("planner.expand_quality_hint"))
+                                        .clicked()
+                                {
+                                    self.quality_targets.push(QualityTarget::new(
+                                        "item-unknown".to_string(),
+                                        2.min(ctx.qualities.len() - 1) as u8,
+                                        1.0,
+                                    ));
+                                    changed = true;
+                                }
+                                ui.add(
+                                    TargetPresetModal::new(ctx, &mut self.target)
+                                        .notify_change(&mut changed),
+                                );
+                            });
                         })
                     });
                     ui.separator();
                     ui.vertical(|ui| {
-                        ui.heading("额外输入");
+                        ui.heading(tr("planner.external"));
+                        changed |= self.price_table_selector(ui, ctx);
+                        ui.horizontal(|ui| {
+                            changed |= ui
+                                .checkbox(&mut self.grid_import, tr("planner.grid_import"))
+                                .on_hover_text(tr("planner.grid_import_hint"))
+                                .changed();
+                            changed |= ui
+                                .add_enabled(
+                                    self.grid_import,
+                                    egui::DragValue::new(&mut self.grid_price)
+                                        .range(0.0..=f64::MAX)
+                                        .speed(0.001)
+                                        .suffix(tr("unit.cost_mj")),
+                                )
+                                .changed();
+                        });
+                        let price_table = self
+                            .price_table
+                            .as_ref()
+                            .and_then(|name| PRICE_TABLES.lock().unwrap().get(name).cloned());
                         self.external.retain_mut(|(item, penalty)| {
                             let mut deleted = false;
                             card_frame(ui).show(ui, |ui| {
@@ -513,11 +3090,11 @@ impl EditorView for FactoryInstance {
                                         .vertical(|ui| {
                                             let icon = ui
                                                 .add_sized(
-                                                    [35.0, 35.0],
+                                                    crate::settings::icon_cell(),
                                                     GenericIcon::new(ctx, item),
                                                 )
                                                 .interact(egui::Sense::click());
-                                            if ui.button("删除").clicked() {
+                                            if ui.button(tr("common.delete")).clicked() {
                                                 deleted = true;
                                                 changed = true;
                                             }
@@ -525,9 +3102,13 @@ impl EditorView for FactoryInstance {
                                         })
                                         .inner;
                                     if let GenericItem::Entity(..) = item {
-                                        icon = icon.on_hover_text("⚠️ 指完成机制所消耗的实体资源（主要是矿物），不包括为了完成机制所需要收集的组装机、采矿机、插件塔等。")
+                                        icon =
+                                            icon.on_hover_text(tr("planner.external_entity_hint"))
                                     }
                                     let toggle = icon.clicked_by(egui::PointerButton::Secondary);
+                                    if toggle {
+                                        self.insert_after = None;
+                                    }
                                     ui.add(
                                         HintModal::new(
                                             icon.id,
@@ -536,29 +3117,17 @@ impl EditorView for FactoryInstance {
                                             &mut self.mechanic_suggestions,
                                             &self.mechanic_providers,
                                         )
+                                        .with_ranker(hint_relevance)
                                         .with_update(toggle, item, -*penalty),
                                     );
                                     ui.vertical(|ui| {
                                         egui::ComboBox::new(icon.id, "")
-                                            .selected_text(match item {
-                                                GenericItem::Item { .. } => "物品",
-                                                GenericItem::Fluid { .. } => "流体",
-                                                GenericItem::Entity { .. } => "实体",
-                                                GenericItem::Heat => "热量",
-                                                GenericItem::Electricity => "电力",
-                                                GenericItem::FluidHeat { .. } => "流体热量",
-                                                GenericItem::FluidFuel { .. } => "流体燃料",
-                                                GenericItem::ItemFuel { .. } => "物体燃料",
-                                                GenericItem::RocketPayloadWeight => "重量载荷",
-                                                GenericItem::RocketPayloadStack => "堆叠载荷",
-                                                GenericItem::Pollution { .. } => "污染",
-                                                _ => "特殊",
-                                            })
+                                            .selected_text(generic_item_kind_name(item))
                                             .show_ui(ui, |ui| {
                                                 ui.selectable_value(
                                                     item,
                                                     GenericItem::Item("item-unknown".into()),
-                                                    "物品",
+                                                    tr("item_kind.item"),
                                                 );
                                                 ui.selectable_value(
                                                     item,
@@ -566,12 +3135,12 @@ impl EditorView for FactoryInstance {
                                                         name: "fluid-unknown".to_string(),
                                                         temperature: None,
                                                     },
-                                                    "流体",
+                                                    tr("item_kind.fluid"),
                                                 );
                                                 ui.selectable_value(
                                                     item,
                                                     GenericItem::Entity("entity-unknown".into()),
-                                                    "实体",
+                                                    tr("item_kind.entity"),
                                                 );
                                             });
                                         ui.horizontal(|ui| {
@@ -581,7 +3150,7 @@ impl EditorView for FactoryInstance {
                                                         ItemWithQualitySelectorModal::new(
                                                             icon.id.with("target-select-item"),
                                                             ctx,
-                                                            "选择物品",
+                                                            tr("planner.select_item"),
                                                             "item",
                                                         )
                                                         .with_toggle(icon.clicked())
@@ -597,7 +3166,7 @@ impl EditorView for FactoryInstance {
                                                         ItemSelectorModal::new(
                                                             egui::Id::new("target-selecte-fluid"),
                                                             ctx,
-                                                            "选择流体",
+                                                            tr("planner.select_fluid"),
                                                             "fluid",
                                                         )
                                                         .with_toggle(icon.clicked())
@@ -610,7 +3179,7 @@ impl EditorView for FactoryInstance {
                                                         ItemWithQualitySelectorModal::new(
                                                             icon.id.with("target-select-entity"),
                                                             ctx,
-                                                            "选择实体",
+                                                            tr("planner.select_entity"),
                                                             "entity",
                                                         )
                                                         .with_toggle(icon.clicked())
@@ -620,10 +3189,30 @@ impl EditorView for FactoryInstance {
                                                 }
                                                 _ => {}
                                             }
-                                            if ui.vertical(|ui| {
-                                                ui.label("单位价值");
-                                                ui.add(egui::DragValue::new(penalty).suffix("·秒"))
-                                            }).inner.changed() {
+                                            if let Some(price) = price_table
+                                                .as_ref()
+                                                .and_then(|table| table.price_of(item))
+                                            {
+                                                ui.vertical(|ui| {
+                                                    ui.label(tr("planner.unit_cost"));
+                                                    ui.label(format!(
+                                                        "{}{}",
+                                                        price,
+                                                        tr("unit.cost_second")
+                                                    ))
+                                                    .on_hover_text(tr("planner.price_from_table"));
+                                                });
+                                            } else if ui
+                                                .vertical(|ui| {
+                                                    ui.label(tr("planner.unit_cost"));
+                                                    ui.add(
+                                                        egui::DragValue::new(penalty)
+                                                            .suffix(tr("unit.cost_second")),
+                                                    )
+                                                })
+                                                .inner
+                                                .changed()
+                                            {
                                                 changed = true;
                                             };
                                             if *penalty < 0.0 {
@@ -635,7 +3224,7 @@ impl EditorView for FactoryInstance {
                             });
                             !deleted
                         });
-                        if ui.button("添加外部输入").clicked() {
+                        if ui.button(tr("planner.add_external")).clicked() {
                             self.external
                                 .push((GenericItem::Item("item-unknown".into()), 1.0));
                             changed = true;
@@ -643,7 +3232,34 @@ impl EditorView for FactoryInstance {
                     });
                     ui.separator();
                     ui.vertical(|ui| {
-                        ui.heading("游戏机制");
+                        ui.heading(tr("planner.mechanics"));
+                        changed |= self.location_selector(ui, ctx);
+                        self.rate_unit_selector(ui);
+                        changed |= ui
+                            .checkbox(&mut self.requires_heating, tr("planner.requires_heating"))
+                            .on_hover_text(tr("planner.requires_heating_hint"))
+                            .changed();
+                        changed |= ui
+                            .checkbox(&mut self.scrap_economy, tr("planner.scrap_economy"))
+                            .on_hover_text(tr("planner.scrap_economy_hint"))
+                            .changed();
+                        ui.add(
+                            GlobalBonusesEditor::new(ctx, &mut self.bonuses)
+                                .notify_change(&mut changed),
+                        );
+                        ui.add(
+                            LogisticsOverheadEditor::new(&mut self.logistics)
+                                .notify_change(&mut changed),
+                        );
+                        ui.add(
+                            MachineWhitelistEditor::new(ctx, &mut self.machine_whitelist)
+                                .notify_change(&mut changed),
+                        );
+                        ui.add(
+                            ModuleCostEditor::new(&mut self.module_cost)
+                                .notify_change(&mut changed),
+                        );
+                        ui.separator();
                         for flow_source in &mut self.mechanic_providers {
                             changed |= flow_source.editor_view(ui, ctx);
                             ui.separator();
@@ -652,15 +3268,110 @@ impl EditorView for FactoryInstance {
                 });
             });
 
-        while let Ok(flow_source) = self.mechanic_receiver.try_recv() {
-            self.mechanics.push(flow_source);
+        changed |= self.inspector_panel(ui, ctx);
+
+        // 没有输入框获得焦点时，Ctrl+V 直接粘贴剪贴板中的机制
+        if ui.memory(|mem| mem.focused().is_none()) {
+            let pasted = ui.input(|input| {
+                input.events.iter().find_map(|event| match event {
+                    egui::Event::Paste(text) if text.contains(MECHANICS_KIND) => Some(text.clone()),
+                    _ => None,
+                })
+            });
+            if let Some(text) = pasted {
+                changed |= self.paste_mechanics(&text, ctx);
+            }
+        }
+        let insert_adjacent = crate::settings::get().insert_adjacent;
+        let mut added = None;
+        while let Ok(mut flow_source) = self.mechanic_receiver.try_recv() {
+            if let Some(route) = &self.route {
+                apply_route_to_collector(flow_source.as_mut(), route);
+            }
+            added = Some(self.insert_mechanic(flow_source, insert_adjacent));
             changed = true;
         }
+        // 滚动到新加入的机制并闪烁高亮
+        if let Some(added) = added {
+            self.insert_after = None;
+            self.inspector.focus(added, ui.input(|input| input.time));
+        }
         egui::Frame::NONE
             .corner_radius(8.0)
             .outer_margin(4.0)
             .show(ui, |ui| {
-                ui.heading("配方配置");
+                ui.heading(tr("planner.recipe_config"));
+                ui.horizontal(|ui| {
+                    if ui
+                        .button(tr("planner.auto_complete"))
+                        .on_hover_text(tr("planner.auto_complete_hint"))
+                        .clicked()
+                    {
+                        self.auto_complete_chain(ctx);
+                    }
+                    if ui
+                        .button(tr("planner.oil_balancer"))
+                        .on_hover_text(tr("planner.oil_balancer_hint"))
+                        .clicked()
+                    {
+                        self.add_oil_balancer(ctx);
+                        changed = true;
+                    }
+                    if ui
+                        .button(tr("planner.copy_all"))
+                        .on_hover_text(tr("planner.copy_all_hint"))
+                        .clicked()
+                    {
+                        ui.ctx().copy_text(encode_mechanics(
+                            self.mechanics.values().map(|mechanic| mechanic.as_ref()),
+                        ));
+                        crate::toast::info(trf("planner.copied_all", &[&self.mechanics.len()]));
+                    }
+                    let paste = ui
+                        .button(tr("planner.paste"))
+                        .on_hover_text(tr("planner.paste_hint"));
+                    show_modal(paste.id, paste.clicked(), ui, |ui| {
+                        ui.heading(tr("planner.paste"));
+                        ui.label(tr("planner.paste_modal_hint"));
+                        let mut text = ui
+                            .memory(|mem| mem.data.get_temp::<PasteText>(paste.id))
+                            .unwrap_or_default()
+                            .0;
+                        egui::ScrollArea::vertical()
+                            .max_height(300.0)
+                            .show(ui, |ui| {
+                                ui.add(
+                                    egui::TextEdit::multiline(&mut text)
+                                        .code_editor()
+                                        .desired_width(f32::INFINITY),
+                                );
+                            });
+                        if ui
+                            .add_enabled(!text.is_empty(), egui::Button::new(tr("planner.paste")))
+                            .clicked()
+                            && self.paste_mechanics(&text, ctx)
+                        {
+                            text.clear();
+                            changed = true;
+                        }
+                        ui.memory_mut(|mem| mem.data.insert_temp(paste.id, PasteText(text)));
+                    });
+                    egui::ComboBox::new(
+                        id.with("chain-preference"),
+                        tr("planner.chain_preference"),
+                    )
+                    .selected_text(self.chain_preference.name())
+                    .show_ui(ui, |ui| {
+                        for preference in ChainPreference::ALL {
+                            ui.selectable_value(
+                                &mut self.chain_preference,
+                                preference,
+                                preference.name(),
+                            );
+                        }
+                    });
+                });
+                ui.add(&mut self.mechanic_filter);
                 egui::ScrollArea::vertical().id_salt(3).show(ui, |ui| {
                     ui.vertical(|ui| {
                         // Use cached sorted keys instead of sorting every frame
@@ -679,17 +3390,435 @@ impl EditorView for FactoryInstance {
 
 impl PlannerView {
     pub fn new(ctx: FactorioContext) -> Self {
-        PlannerView {
-            ctx: ctx.build_order_info(),
+        tutorial::complete(TutorialStep::LoadContext);
+        let (context_sender, context_receiver) = std::sync::mpsc::channel();
+        let mut planner = PlannerView {
+            contexts: Vec::new(),
+            selected_context: 0,
             factories: Vec::new(),
             selected_factory: 0,
             new_factory_name: String::new(),
+            tab_filter: None,
+            subview_sender: None,
+            pending_migration: None,
+            context_creator: None,
+            template_gallery: false,
+            context_sender,
+            context_receiver,
+            key: SubviewKey::next(),
+        };
+        if !ctx.locale.is_empty() {
+            i18n::set_language(Language::from_locale(&ctx.locale));
+        }
+        planner.add_context(ctx);
+        planner
+    }
+
+    /// 打开的工厂带有颜色标签时，在标签栏开头提供按标签筛选
+    fn tab_filter_menu(&mut self, ui: &mut egui::Ui) {
+        let tags = FactoryTag::ALL
+            .into_iter()
+            .filter(|tag| self.factories.iter().any(|f| f.factory.tag == Some(*tag)))
+            .collect::<Vec<_>>();
+        if tags.is_empty() {
+            self.tab_filter = None;
+            return;
+        }
+        let title = match self.tab_filter {
+            Some(tag) => tag.dot(),
+            None => egui::RichText::new("⏷"),
+        };
+        ui.menu_button(title, |ui| {
+            ui.radio_value(&mut self.tab_filter, None, tr("tag.all"));
+            for tag in tags {
+                ui.radio_value(&mut self.tab_filter, Some(tag), tag.dot())
+                    .on_hover_text(tag.name());
+            }
+        })
+        .response
+        .on_hover_text(tr("tag.filter_hint"));
+        ui.separator();
+    }
+
+    /// 按上次的工作区加入各上下文并重新打开工厂文件，factories 给出工厂绑定的上下文下标。
+    /// 引用了缺失原型的工厂只询问第一个，其余跳过
+    pub fn restore(
+        contexts: Vec<FactorioContext>,
+        factories: Vec<(std::path::PathBuf, usize)>,
+        selected_factory: usize,
+    ) -> Self {
+        let mut contexts = contexts.into_iter();
+        let mut planner = Self::new(contexts.next().expect("恢复规划器至少需要一个上下文"));
+        for ctx in contexts {
+            planner.add_context(ctx);
+        }
+        for (path, context) in factories {
+            if planner.pending_migration.is_some() {
+                crate::toast::info(trf("planner.restore_skipped", &[&path.display()]));
+                continue;
+            }
+            planner.selected_context = context;
+            planner.load_factory_file(path);
+        }
+        planner.selected_context = 0;
+        planner.selected_factory = selected_factory.min(planner.factories.len().saturating_sub(1));
+        planner
+    }
+
+    /// 添加一个上下文，名称与已有上下文重复时加上序号
+    pub fn add_context(&mut self, mut ctx: FactorioContext) {
+        // 新加入的上下文尽量沿用当前语言
+        if let Some(named) = self.contexts.get(self.selected_context) {
+            ctx.set_locale(&named.ctx.locale.clone());
+        }
+        let base_name = default_context_name(&ctx);
+        let mut name = base_name.clone();
+        let mut suffix = 2;
+        while self.contexts.iter().any(|named| named.name == name) {
+            name = format!("{} ({})", base_name, suffix);
+            suffix += 1;
+        }
+        self.contexts.push(NamedContext {
+            name,
+            ctx: ctx.build_order_info().build_producer_info(),
+        });
+        self.selected_context = self.contexts.len() - 1;
+    }
+
+    /// 当前选中的上下文，新建、加载工厂与导出数据时使用
+    pub fn ctx(&self) -> &FactorioContext {
+        &self.contexts[self.selected_context].ctx
+    }
+
+    /// 将工厂切换到另一个上下文，存在缺失原型时先让用户确认迁移方式
+    fn rebind_factory(&mut self, index: usize, context: usize) {
+        let ctx = &self.contexts[context].ctx;
+        let factory = &mut self.factories[index];
+        let missing = validate_factory(&factory.factory, ctx);
+        if missing.is_empty() {
+            factory.context = context;
+            factory.factory.send_solve_request(ctx);
+            crate::toast::success(trf(
+                "context.switched",
+                &[&factory.factory.name, &self.contexts[context].name],
+            ));
+        } else {
+            let source = trf("context.migration_source", &[&factory.factory.name]);
+            self.pending_migration = Some((
+                PendingMigration::new(source, missing, ctx),
+                MigrationTarget::Rebind { factory: index },
+                context,
+            ));
+        }
+    }
+
+    /// 嵌入的上下文创建界面与后台加载完成的上下文
+    fn context_creator_window(&mut self, ui: &mut egui::Ui) {
+        while let Ok(ctx) = self.context_receiver.try_recv() {
+            self.add_context(ctx);
+            self.context_creator = None;
+            crate::toast::notify(
+                crate::toast::Notification::new(
+                    crate::toast::Severity::Success,
+                    trf(
+                        "context.added",
+                        &[&self.contexts[self.selected_context].name],
+                    ),
+                )
+                .with_target(self.key),
+            );
+        }
+        let Some(creator) = self.context_creator.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new(tr("context.add_title"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ui.ctx(), |ui| creator.view(ui));
+        if !open {
+            self.context_creator = None;
+        }
+    }
+
+    /// 切换游戏内容与界面的语言，游戏内容的语言对所有加载了该语言的上下文生效
+    fn language_menu(&mut self, ui: &mut egui::Ui) {
+        ui.strong(tr("language.game"));
+        let current = self.ctx().locale.clone();
+        let locales = self.ctx().locales.keys().cloned().collect::<Vec<_>>();
+        if locales.len() <= 1 {
+            ui.label(tr("language.none_loaded"));
+        }
+        for lang in locales {
+            if ui
+                .radio(lang == current, locale_display_name(&lang))
+                .clicked()
+                && lang != current
+            {
+                for named in &mut self.contexts {
+                    named.ctx.set_locale(&lang);
+                }
+                i18n::set_language(Language::from_locale(&lang));
+                ui.close();
+            }
+        }
+        ui.separator();
+        ui.strong(tr("language.ui"));
+        for language in Language::ALL {
+            if ui
+                .radio(i18n::language() == language, language.name())
+                .clicked()
+            {
+                i18n::set_language(language);
+                ui.close();
+            }
+        }
+    }
+
+    fn context_menu(&mut self, ui: &mut egui::Ui) {
+        for (i, named) in self.contexts.iter().enumerate() {
+            let bound = self.factories.iter().filter(|f| f.context == i).count();
+            ui.radio_value(
+                &mut self.selected_context,
+                i,
+                trf("context.entry", &[&named.name, &bound]),
+            )
+            .on_hover_text(trf(
+                "context.entry_hint",
+                &[&named
+                    .ctx
+                    .mods
+                    .iter()
+                    .map(|(name, version)| format!("{} ({})", name, version))
+                    .collect::<Vec<_>>()
+                    .join("\n")],
+            ));
+        }
+        ui.separator();
+        if ui.button(tr("context.browse")).clicked() {
+            if let Some(sender) = &self.subview_sender {
+                let named = &self.contexts[self.selected_context];
+                let _ = sender.send(Box::new(PrototypeBrowserView::new(
+                    named.ctx.clone(),
+                    named.name.clone(),
+                )));
+            }
+            ui.close();
+        }
+        if ui.button(tr("context.add")).clicked() {
+            self.context_creator = Some(
+                FactorioContextCreatorView::default()
+                    .with_context_sender(self.context_sender.clone()),
+            );
+            ui.close();
+        }
+        let removable = self.contexts.len() > 1
+            && !self
+                .factories
+                .iter()
+                .any(|factory| factory.context == self.selected_context);
+        if ui
+            .add_enabled(removable, egui::Button::new(tr("context.remove")))
+            .on_disabled_hover_text(tr("context.remove_disabled_hint"))
+            .clicked()
+        {
+            let removed = self.selected_context;
+            self.contexts.remove(removed);
+            for factory in &mut self.factories {
+                if factory.context > removed {
+                    factory.context -= 1;
+                }
+            }
+            self.selected_context = removed.min(self.contexts.len() - 1);
+            ui.close();
+        }
+    }
+
+    pub fn with_subview_sender(
+        mut self,
+        sender: std::sync::mpsc::Sender<Box<dyn Subview>>,
+    ) -> Self {
+        self.subview_sender = Some(sender);
+        self
+    }
+
+    /// 读取工厂文件，引用了缺失原型时先询问迁移方式
+    fn load_factory_file(&mut self, path: std::path::PathBuf) {
+        match std::fs::read_to_string(&path) {
+            Err(err) => {
+                crate::toast::error(trf("planner.read_failed", &[&path.display(), &err]));
+            }
+            Ok(content) => match serde_json::from_str::<FactoryInstance>(&content) {
+                Err(err) => {
+                    crate::toast::error(trf("planner.parse_failed", &[&path.display(), &err]));
+                }
+                Ok(factory) => {
+                    let context = self.selected_context;
+                    let missing = validate_factory(&factory, self.ctx());
+                    if missing.is_empty() {
+                        self.open_factory(factory, path, true, context);
+                    } else {
+                        self.pending_migration = Some((
+                            PendingMigration::new(path.display().to_string(), missing, self.ctx()),
+                            MigrationTarget::Open {
+                                factory: Box::new(factory),
+                                path,
+                            },
+                            context,
+                        ));
+                    }
+                }
+            },
+        }
+    }
+
+    fn open_factory(
+        &mut self,
+        factory: FactoryInstance,
+        path: std::path::PathBuf,
+        saved: bool,
+        context: usize,
+    ) {
+        let thread_path = path.clone();
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            crate::toast::success(trf("planner.factory_loaded", &[&thread_path.display()]));
+        });
+        // 网页版没有线程，直接提示
+        #[cfg(target_arch = "wasm32")]
+        crate::toast::success(trf("planner.factory_loaded", &[&thread_path.display()]));
+        factory.set_solve_notice(self.key);
+        factory.send_solve_request(&self.contexts[context].ctx);
+        self.factories.push(StatefulFactoryInstance {
+            factory,
+            saved,
+            file_path: Some(path),
+            context,
+        });
+        tutorial::complete(TutorialStep::CreateFactory);
+    }
+
+    /// 加入尚未保存到文件的新工厂并开始求解
+    fn add_new_factory(&mut self, factory: FactoryInstance, context: usize) {
+        factory.set_solve_notice(self.key);
+        factory.send_solve_request(&self.contexts[context].ctx);
+        self.factories.push(StatefulFactoryInstance {
+            factory,
+            saved: false,
+            file_path: None,
+            context,
+        });
+        tutorial::complete(TutorialStep::CreateFactory);
+    }
+
+    /// 示例工厂列表，新建时按当前上下文校验，缺失的原型先经迁移对话框替换
+    fn template_gallery_window(&mut self, ui: &mut egui::Ui) {
+        if !self.template_gallery {
+            return;
+        }
+        let mut open = true;
+        let mut selected = None;
+        egui::Window::new(tr("planner.template_gallery"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ui.ctx(), |ui| {
+                ui.label(tr("planner.template_gallery_hint"));
+                for (idx, template) in FACTORY_TEMPLATES.iter().enumerate() {
+                    card_frame(ui).show(ui, |ui| {
+                        ui.set_min_width(ui.available_width());
+                        ui.horizontal(|ui| {
+                            ui.vertical(|ui| {
+                                ui.strong(template.name);
+                                ui.label(template.description);
+                            });
+                            if ui.button(tr("planner.template_create")).clicked() {
+                                selected = Some(idx);
+                            }
+                        });
+                    });
+                }
+            });
+        if let Some(idx) = selected {
+            let template = &FACTORY_TEMPLATES[idx];
+            let context = self.selected_context;
+            let ctx = &self.contexts[context].ctx;
+            let factory = template.instantiate(ctx);
+            let missing = validate_factory(&factory, ctx);
+            if missing.is_empty() {
+                self.add_new_factory(factory, context);
+            } else {
+                self.pending_migration = Some((
+                    PendingMigration::new(template.name.to_string(), missing, ctx),
+                    MigrationTarget::Template {
+                        factory: Box::new(factory),
+                    },
+                    context,
+                ));
+            }
+            open = false;
+        }
+        self.template_gallery = open;
+    }
+
+    fn migration_dialog(&mut self, ui: &mut egui::Ui) {
+        let Some((pending, _, context)) = self.pending_migration.as_mut() else {
+            return;
+        };
+        match pending.show(ui, &self.contexts[*context].ctx) {
+            MigrationDecision::Pending => {}
+            MigrationDecision::Cancel => {
+                self.pending_migration = None;
+            }
+            MigrationDecision::Apply => {
+                let (pending, target, context) = self.pending_migration.take().unwrap();
+                match target {
+                    MigrationTarget::Open { mut factory, path } => {
+                        apply_migration(&mut factory, &pending.actions);
+                        // 迁移后的工厂与文件内容不同，标记为未保存
+                        self.open_factory(*factory, path, false, context);
+                    }
+                    MigrationTarget::Template { mut factory } => {
+                        apply_migration(&mut factory, &pending.actions);
+                        self.add_new_factory(*factory, context);
+                    }
+                    MigrationTarget::Rebind { factory } => {
+                        let factory = &mut self.factories[factory];
+                        apply_migration(&mut factory.factory, &pending.actions);
+                        factory.context = context;
+                        factory.saved = false;
+                        factory
+                            .factory
+                            .send_solve_request(&self.contexts[context].ctx);
+                    }
+                }
+            }
         }
     }
 }
 
 impl Subview for PlannerView {
     fn view(&mut self, ui: &mut egui::Ui) {
+        if let Some(jump) = FactoryJump::take(ui.ctx(), self.key) {
+            let index = if self
+                .factories
+                .get(jump.index)
+                .is_some_and(|factory| factory.factory.name == jump.name)
+            {
+                Some(jump.index)
+            } else {
+                self.factories
+                    .iter()
+                    .position(|factory| factory.factory.name == jump.name)
+            };
+            match index {
+                Some(index) => self.selected_factory = index,
+                None => crate::toast::warning(trf("planner.dashboard_missing", &[&jump.name])),
+            }
+        }
+        self.migration_dialog(ui);
+        self.context_creator_window(ui);
+        self.template_gallery_window(ui);
         egui::Frame::group(ui.style())
             .corner_radius(8.0)
             .stroke(egui::Stroke::new(
@@ -698,131 +3827,224 @@ impl Subview for PlannerView {
             ))
             .show(ui, |ui| {
                 egui::containers::menu::MenuBar::new().ui(ui, |ui| {
-                    ui.menu_button("文件", |ui| {
-                        if ui.button("新建工厂").clicked() {
-                            let name = "新工厂".to_string();
-                            self.factories.push(
-                                FactoryInstance::new(name)
-                                    .add_flow_source(|s| {
-                                        Box::new(
-                                            RecipeConfigProvider::new().with_mechanic_sender(s),
-                                        )
-                                    })
-                                    .add_flow_source(|s| {
-                                        Box::new(
-                                            MiningConfigProvider::new().with_mechanic_sender(s),
-                                        )
-                                    })
-                                    .into(),
-                            );
+                    let file_menu = ui.menu_button(tr("menu.file"), |ui| {
+                        if ui.button(tr("planner.new_factory")).clicked() {
+                            let name = tr("planner.new_factory_name").to_string();
+                            self.factories
+                                .push(FactoryInstance::new(name).with_default_providers().into());
+                            self.factories.last_mut().unwrap().context = self.selected_context;
+                            tutorial::complete(TutorialStep::CreateFactory);
                         }
-                        if ui.button("从文件加载工厂……").clicked() {
-                            if let Some(path) = rfd::FileDialog::new()
-                                .add_filter("异星工厂规划配置", &["fpc", "json"])
+                        if ui
+                            .button(tr("planner.template_gallery"))
+                            .on_hover_text(tr("planner.template_gallery_hint"))
+                            .clicked()
+                        {
+                            self.template_gallery = true;
+                        }
+                        if ui.button(tr("planner.load_factory")).clicked()
+                            && let Some(path) = crate::file_dialog::FileDialog::new()
+                                .add_filter(tr("planner.factory_filter"), &["fpc", "json"])
                                 .pick_file()
-                            {
-                                match std::fs::read_to_string(&path) {
-                                    Err(err) => {
-                                        crate::toast::error(format!(
-                                            "无法读取文件 {}: {}",
-                                            path.display(),
-                                            err
-                                        ));
-                                    }
-                                    Ok(content) => {
-                                        match serde_json::from_str::<FactoryInstance>(&content) {
-                                            Err(err) => {
-                                                crate::toast::error(format!(
-                                                    "无法解析文件 {}: {}",
-                                                    path.display(),
-                                                    err
-                                                ));
-                                            }
-                                            Ok(factory) => {
-                                                let thread_path = path.clone();
-                                                std::thread::spawn(move || {
-                                                    std::thread::sleep(
-                                                        std::time::Duration::from_millis(500),
-                                                    );
-                                                    crate::toast::success(format!(
-                                                        "从 {} 加载了新工厂",
-                                                        thread_path.display()
-                                                    ));
-                                                });
-                                                factory.send_solve_request(&self.ctx);
-                                                self.factories.push(StatefulFactoryInstance {
-                                                    factory,
-                                                    saved: true,
-                                                    file_path: Some(path),
-                                                });
-                                            }
-                                        }
-                                    }
-                                }
+                        {
+                            self.load_factory_file(path);
+                        }
+                        if ui
+                            .add_enabled(
+                                !self.factories.is_empty(),
+                                egui::Button::new(tr("planner.dashboard")),
+                            )
+                            .on_hover_text(tr("planner.dashboard_hint"))
+                            .clicked()
+                            && let Some(sender) = &self.subview_sender
+                        {
+                            let dashboard =
+                                Dashboard::collect(self.factories.iter().map(|factory| {
+                                    (&factory.factory, &self.contexts[factory.context].ctx)
+                                }));
+                            let _ = sender
+                                .send(Box::new(DashboardView::new(dashboard, Some(self.key))));
+                        }
+                        ui.separator();
+                        if ui
+                            .button(tr("planner.export_context"))
+                            .on_hover_text(tr("planner.export_context_hint"))
+                            .clicked()
+                            && let Some(path) = crate::file_dialog::FileDialog::new()
+                                .add_filter("JSON", &["json"])
+                                .set_file_name("context-export.json")
+                                .save_file()
+                        {
+                            match save_to_file(&ContextExport::new(self.ctx()), &path) {
+                                Ok(()) => crate::toast::success(trf(
+                                    "planner.context_exported",
+                                    &[&path.display()],
+                                )),
+                                Err(err) => crate::toast::error(trf(
+                                    "common.export_failed",
+                                    &[&format!("{:?}", err)],
+                                )),
                             }
                         }
                     });
+                    tutorial::anchor(TutorialStep::CreateFactory, &file_menu.response);
+                    ui.menu_button(tr("menu.context"), |ui| self.context_menu(ui));
+                    ui.menu_button(tr("menu.language"), |ui| self.language_menu(ui));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        let (hits, misses, cached) = SOLVER_CACHE.stats();
+                        let clear = ui
+                            .small_button(tr("common.clear"))
+                            .on_hover_text(tr("planner.clear_solver_cache"));
+                        ui.label(trf("planner.solver_cache", &[&hits, &misses, &cached]));
+                        if clear.clicked() {
+                            SOLVER_CACHE.clear();
+                        }
+                        ui.separator();
+                        let (workers, queued) = SOLVER_POOL.stats();
+                        ui.label(trf("planner.solver_pool", &[&workers, &queued]));
+                    });
                 });
                 ui.separator();
                 egui::containers::menu::MenuBar::new().ui(ui, |ui| {
+                    let key = self.key;
                     ui.horizontal(|ui| {
+                        self.tab_filter_menu(ui);
                         for i in 0..self.factories.len() {
-                            let button = ui.add(
-                                egui::Button::new(format!(
-                                    "{}{}",
-                                    &self.factories[i].factory.name,
-                                    if self.factories[i].saved { "" } else { " *" }
-                                ))
-                                .selected(self.selected_factory == i),
+                            let factory = &self.factories[i];
+                            if let Some(filter) = self.tab_filter
+                                && factory.factory.tag != Some(filter)
+                                && self.selected_factory != i
+                            {
+                                continue;
+                            }
+                            let mut label = egui::text::LayoutJob::default();
+                            if let Some(tag) = factory.factory.tag {
+                                tag.dot().append_to(
+                                    &mut label,
+                                    ui.style(),
+                                    egui::FontSelection::Default,
+                                    egui::Align::Center,
+                                );
+                            }
+                            egui::RichText::new(format!(
+                                "{}{} {}{}{}",
+                                if factory.factory.tag.is_some() {
+                                    " "
+                                } else {
+                                    ""
+                                },
+                                factory.factory.status.icon(),
+                                &factory.factory.name,
+                                if self.contexts.len() > 1 {
+                                    format!(" [{}]", self.contexts[factory.context].name)
+                                } else {
+                                    String::new()
+                                },
+                                if factory.saved { "" } else { " *" }
+                            ))
+                            .append_to(
+                                &mut label,
+                                ui.style(),
+                                egui::FontSelection::Default,
+                                egui::Align::Center,
                             );
+                            let button = ui
+                                .add(egui::Button::new(label).selected(self.selected_factory == i))
+                                .on_hover_text(match factory.factory.tag {
+                                    Some(tag) => {
+                                        format!(
+                                            "{} · {}",
+                                            tag.name(),
+                                            factory.factory.status.name()
+                                        )
+                                    }
+                                    None => factory.factory.status.name().to_string(),
+                                });
                             if button.clicked() {
                                 self.selected_factory = i;
                             }
+                            let mut rebind = None;
                             button.context_menu(|ui| {
                                 let factory = &mut self.factories[i];
-                                if let Some(file_path) = factory.file_path.as_ref() {
-                                    if ui
-                                        .add(egui::Button::new("保存").shortcut_text("Ctrl+S"))
-                                        .clicked()
-                                    {
-                                        if let Ok(()) = save_to_file(&factory.factory, file_path) {
-                                            factory.saved = true;
-                                            crate::toast::success(format!(
-                                                "工厂已保存到 {}",
-                                                file_path.display()
-                                            ));
-                                        }
-                                        ui.close();
+                                ui.menu_button(tr("tag.menu"), |ui| {
+                                    let mut tag = factory.factory.tag;
+                                    ui.radio_value(&mut tag, None, tr("tag.none"));
+                                    for option in FactoryTag::ALL {
+                                        ui.radio_value(&mut tag, Some(option), option.dot())
+                                            .on_hover_text(option.name());
+                                    }
+                                    if tag != factory.factory.tag {
+                                        factory.factory.tag = tag;
+                                        factory.saved = false;
+                                    }
+                                });
+                                ui.menu_button(tr("status.menu"), |ui| {
+                                    let mut status = factory.factory.status;
+                                    for option in FactoryStatus::ALL {
+                                        ui.radio_value(
+                                            &mut status,
+                                            option,
+                                            format!("{} {}", option.icon(), option.name()),
+                                        );
                                     }
+                                    if status != factory.factory.status {
+                                        factory.factory.status = status;
+                                        factory.saved = false;
+                                    }
+                                });
+                                ui.separator();
+                                if self.contexts.len() > 1 {
+                                    ui.menu_button(tr("context.switch"), |ui| {
+                                        for (c, named) in self.contexts.iter().enumerate() {
+                                            if ui
+                                                .add_enabled(
+                                                    self.factories[i].context != c,
+                                                    egui::Button::new(&named.name),
+                                                )
+                                                .clicked()
+                                            {
+                                                rebind = Some(c);
+                                                ui.close();
+                                            }
+                                        }
+                                    });
+                                }
+                                let factory = &mut self.factories[i];
+                                if let Some(file_path) = factory.file_path.as_ref()
+                                    && ui
+                                        .add(
+                                            egui::Button::new(tr("common.save"))
+                                                .shortcut_text("Ctrl+S"),
+                                        )
+                                        .clicked()
+                                {
+                                    let file_path = file_path.clone();
+                                    save_factory(factory, &file_path, key);
+                                    ui.close();
                                 }
                                 if ui
                                     .add(if factory.file_path.is_some() {
-                                        egui::Button::new("另存为……")
+                                        egui::Button::new(tr("common.save_as"))
                                     } else {
-                                        egui::Button::new("保存……").shortcut_text("Ctrl+S")
+                                        egui::Button::new(tr("common.save_new"))
+                                            .shortcut_text("Ctrl+S")
                                     })
                                     .clicked()
                                 {
-                                    if let Some(path) = rfd::FileDialog::new()
-                                        .add_filter("异星工厂规划配置", &["fpc", "json"])
+                                    if let Some(path) = crate::file_dialog::FileDialog::new()
+                                        .add_filter(tr("planner.factory_filter"), &["fpc", "json"])
                                         .set_file_name(
                                             format!("{}.fpc", &factory.factory.name).as_str(),
                                         )
                                         .save_file()
+                                        && save_factory(factory, &path, key)
                                     {
-                                        if let Ok(()) = save_to_file(&factory.factory, &path) {
-                                            factory.saved = true;
-                                            factory.file_path = Some(path.clone());
-                                            crate::toast::success(format!(
-                                                "工厂已保存到 {}",
-                                                path.display()
-                                            ));
-                                        }
+                                        factory.file_path = Some(path);
                                     }
                                     ui.close();
                                 }
 
-                                if ui.button("关闭").clicked() {
+                                if ui.button(tr("common.close")).clicked() {
                                     self.factories.remove(i);
                                     if self.selected_factory >= i && self.selected_factory > 0 {
                                         self.selected_factory -= 1;
@@ -830,46 +4052,56 @@ impl Subview for PlannerView {
                                     ui.close();
                                 }
                             });
+                            if let Some(context) = rebind {
+                                self.rebind_factory(i, context);
+                            }
                         }
                     });
                 });
                 ui.separator();
                 if self.factories.is_empty() {
                     let mut layout_job = egui::text::LayoutJob::default();
-                    egui::RichText::new("没有工厂\n").size(32.0).append_to(
-                        &mut layout_job,
-                        ui.style(),
-                        egui::FontSelection::Default,
-                        egui::Align::Center,
-                    );
-                    egui::RichText::new("点击上方的文件菜单新建工厂或加载一个工厂存档。")
+                    egui::RichText::new(tr("planner.no_factory"))
+                        .size(32.0)
                         .append_to(
                             &mut layout_job,
                             ui.style(),
                             egui::FontSelection::Default,
                             egui::Align::Center,
                         );
+                    egui::RichText::new(tr("planner.no_factory_hint")).append_to(
+                        &mut layout_job,
+                        ui.style(),
+                        egui::FontSelection::Default,
+                        egui::Align::Center,
+                    );
                     ui.add_sized(ui.available_size(), egui::Label::new(layout_job));
                 } else {
                     let factory = &mut self.factories[self.selected_factory];
-                    factory.saved &= !factory.factory.editor_view(ui, &self.ctx);
+                    let ctx = &self.contexts[factory.context].ctx;
+                    factory.factory.set_solve_notice(self.key);
+                    FactoryUsage::publish(factory.factory.usage.clone(), ui.ctx());
+                    factory.saved &= !factory.factory.editor_view(ui, ctx);
+                    FactoryUsage::retract(ui.ctx());
+                    for subview in factory.factory.pending_subviews.drain(..) {
+                        if let Some(sender) = &self.subview_sender {
+                            let _ = sender.send(subview);
+                        }
+                    }
                     if ui
                         .ctx()
                         .input(|input| input.modifiers.command && input.key_pressed(egui::Key::S))
                         && !factory.saved
                     {
                         if factory.file_path.is_none() {
-                            let file_path = rfd::FileDialog::new()
-                                .add_filter("异星工厂规划配置", &["fpc", "json"])
+                            let file_path = crate::file_dialog::FileDialog::new()
+                                .add_filter(tr("planner.factory_filter"), &["fpc", "json"])
                                 .set_file_name(format!("{}.fpc", &factory.factory.name).as_str())
                                 .save_file();
                             factory.file_path = file_path;
                         }
-                        if let Some(path) = factory.file_path.as_ref() {
-                            if let Ok(()) = save_to_file(&factory.factory, path) {
-                                crate::toast::success(format!("工厂已保存到 {}", path.display()));
-                                factory.saved = true;
-                            }
+                        if let Some(path) = factory.file_path.clone() {
+                            save_factory(factory, &path, self.key);
                         }
                     }
                 }
@@ -877,12 +4109,41 @@ impl Subview for PlannerView {
     }
 
     fn name(&self) -> String {
-        "异星工厂 - 工厂规划器".to_string()
+        tr("planner.name").to_string()
+    }
+
+    fn key(&self) -> Option<SubviewKey> {
+        Some(self.key)
+    }
+
+    fn workspace_entry(&self) -> Option<crate::workspace::WorkspaceEntry> {
+        let mut selected_factory = 0;
+        let mut factories = Vec::new();
+        let mut factory_contexts = Vec::new();
+        for (idx, factory) in self.factories.iter().enumerate() {
+            if let Some(path) = &factory.file_path {
+                if idx == self.selected_factory {
+                    selected_factory = factories.len();
+                }
+                factories.push(path.clone());
+                factory_contexts.push(factory.context);
+            }
+        }
+        Some(crate::workspace::WorkspaceEntry::FactorioPlanner {
+            factories,
+            selected_factory,
+            contexts: self
+                .contexts
+                .iter()
+                .map(|named| named.ctx.dump_key.clone())
+                .collect(),
+            factory_contexts,
+        })
     }
 
     fn description(&self) -> String {
-        self.ctx.mods.iter().fold(
-            "使用以下模组: ".to_string(),
+        self.ctx().mods.iter().fold(
+            tr("planner.mods_in_use").to_string(),
             |mut acc, (mod_name, mod_version)| {
                 acc.push_str(&format!("\n{} ({}), ", mod_name, mod_version));
                 acc
@@ -891,127 +4152,666 @@ impl Subview for PlannerView {
     }
 }
 
+/// 加载完成的上下文交给谁
+#[derive(Debug, Clone)]
+enum ContextDestination {
+    /// 以新的规划器页面打开
+    NewPlanner(std::sync::mpsc::Sender<Box<dyn Subview>>),
+    /// 添加到已有的规划器
+    Planner(std::sync::mpsc::Sender<FactorioContext>),
+}
+
+impl ContextDestination {
+    fn deliver(self, ctx: FactorioContext) {
+        match self {
+            ContextDestination::NewPlanner(sender) => {
+                let name = default_context_name(&ctx);
+                let planner = PlannerView::new(ctx).with_subview_sender(sender.clone());
+                let key = planner.key;
+                let _ = sender.send(Box::new(planner));
+                crate::toast::notify(
+                    crate::toast::Notification::new(
+                        crate::toast::Severity::Success,
+                        trf("planner.context_loaded", &[&name]),
+                    )
+                    .with_target(key),
+                );
+            }
+            ContextDestination::Planner(sender) => {
+                let _ = sender.send(ctx);
+            }
+        }
+        crate::repaint::wake();
+    }
+}
+
+// 网页版只能上传导出的数据，导出与模组配置相关的状态不会用到
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
 #[derive(Default, Debug)]
 pub struct FactorioContextCreatorView {
     path: Option<std::path::PathBuf>,
     mod_path: Option<std::path::PathBuf>,
     subview_sender: Option<std::sync::mpsc::Sender<Box<dyn Subview>>>,
-    thread: Option<std::thread::JoinHandle<()>>,
+    /// 设置后，加载的上下文发送给已有的规划器而不是打开新页面
+    context_sender: Option<std::sync::mpsc::Sender<FactorioContext>>,
+    /// 需要导出翻译的语言，第一个为默认语言
+    languages: Vec<String>,
+    /// 加载线程，失败时返回标题的翻译键与失败原因
+    thread: Option<std::thread::JoinHandle<Result<(), (&'static str, ContextLoadError)>>>,
+    /// 上次加载失败的标题与原因，重新加载前一直显示
+    error: Option<(&'static str, ContextLoadError)>,
+    monitor: LoadMonitor,
+    progress_receiver: Option<std::sync::mpsc::Receiver<LoadProgress>>,
+    /// 本次加载包含的阶段及其状态
+    stages: Vec<(LoadStage, StageStatus)>,
+    /// 自动检测到的游戏安装，首次显示时扫描
+    installs: Option<Vec<FactorioInstall>>,
+    /// 已保存的模组配置，首次显示时读取
+    profiles: Option<ModProfileLibrary>,
+    /// 选中的模组配置名
+    profile: Option<String>,
+    new_profile_name: String,
+    /// 上传的 data-raw-dump.json 的文件名与内容
+    uploaded_raw: Option<(String, Vec<u8>)>,
+    /// 上传的图标压缩包的文件名与内容
+    uploaded_archive: Option<(String, Vec<u8>)>,
+    /// 选择文件后经此送回内容，网页版的文件选择是异步的
+    upload_channel: Option<(
+        std::sync::mpsc::Sender<UploadedFile>,
+        std::sync::mpsc::Receiver<UploadedFile>,
+    )>,
 }
 
-impl Subview for FactorioContextCreatorView {
-    fn view(&mut self, ui: &mut egui::Ui) {
-        ui.vertical_centered(|ui| {
-            ui.heading("创建游戏上下文");
-            ui.separator();
+/// 创建上下文时可上传的文件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UploadKind {
+    RawDump,
+    IconArchive,
+}
+
+impl UploadKind {
+    fn filter(&self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            UploadKind::RawDump => ("JSON", &["json"]),
+            UploadKind::IconArchive => ("ZIP", &["zip"]),
+        }
+    }
+}
+
+type UploadedFile = (UploadKind, String, Vec<u8>);
+
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+impl FactorioContextCreatorView {
+    pub fn with_context_sender(mut self, sender: std::sync::mpsc::Sender<FactorioContext>) -> Self {
+        self.context_sender = Some(sender);
+        self
+    }
 
-            ui.label("选择游戏路径:");
-            if ui.button("浏览...").clicked()
-                && let Some(path) = rfd::FileDialog::new().pick_file()
+    fn destination(&self) -> Option<ContextDestination> {
+        if let Some(sender) = &self.context_sender {
+            Some(ContextDestination::Planner(sender.clone()))
+        } else {
+            self.subview_sender
+                .clone()
+                .map(ContextDestination::NewPlanner)
+        }
+    }
+
+    /// 准备新的进度通道，返回交给后台线程的汇报器
+    fn start_progress(&mut self, stages: &[LoadStage]) -> LoadMonitor {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.monitor = LoadMonitor::new(sender);
+        self.progress_receiver = Some(receiver);
+        self.stages = stages
+            .iter()
+            .map(|stage| (*stage, StageStatus::Pending))
+            .collect();
+        self.monitor.clone()
+    }
+
+    /// 选用一个检测到的安装，同时填入其模组文件夹
+    fn select_install(&mut self, install: &FactorioInstall) {
+        self.path = Some(install.executable.clone());
+        self.mod_path = install.mod_dir.clone();
+    }
+
+    /// 检测到的安装列表，首次扫描时若尚未选择路径则预填第一个安装
+    fn installs_view(&mut self, ui: &mut egui::Ui) {
+        if self.installs.is_none() {
+            let installs = detect_installs();
+            if self.path.is_none()
+                && let Some(install) = installs.first()
             {
-                self.path = Some(path);
+                self.select_install(install);
             }
-            if let Some(path) = &self.path {
-                ui.label(format!("已选择路径: {}", path.display()));
-                if path.to_string_lossy().contains("steam") {
-                    ui.label("若为 Steam 版本的游戏，请启动 Steam 再执行加载游戏上下文");
-                }
+            self.installs = Some(installs);
+        }
+        let installs = self.installs.clone().unwrap_or_default();
+        ui.horizontal(|ui| {
+            if installs.is_empty() {
+                ui.label(tr("creator.no_installs"));
             } else {
-                ui.label("未选择路径");
+                let selected = installs
+                    .iter()
+                    .find(|install| Some(&install.executable) == self.path.as_ref());
+                egui::ComboBox::from_id_salt("detected-installs")
+                    .selected_text(
+                        selected
+                            .map(|install| install.source.name())
+                            .unwrap_or_else(|| tr("creator.detected_installs")),
+                    )
+                    .show_ui(ui, |ui| {
+                        for install in &installs {
+                            let text = format!(
+                                "{} — {}",
+                                install.source.name(),
+                                install.executable.display()
+                            );
+                            if ui
+                                .selectable_label(Some(install) == selected, text)
+                                .clicked()
+                            {
+                                self.select_install(install);
+                            }
+                        }
+                    });
+            }
+            if ui.button(tr("creator.rescan")).clicked() {
+                self.installs = Some(detect_installs());
             }
+        });
+    }
 
-            ui.separator();
+    /// 选中且与当前模组路径一致的模组配置
+    fn active_profile(&self) -> Option<&ModProfile> {
+        let profile = self.profiles.as_ref()?.get(self.profile.as_ref()?)?;
+        (Some(&profile.mod_path) == self.mod_path.as_ref()).then_some(profile)
+    }
 
-            ui.label("选择Mod路径 (可选):");
-            if ui.button("浏览...").clicked() {
-                if let Some(mod_path) = rfd::FileDialog::new().pick_folder() {
-                    self.mod_path = Some(mod_path);
-                } else {
-                    self.mod_path = None;
+    fn save_profiles(&self) {
+        if let Some(profiles) = &self.profiles
+            && let Err(err) = profiles.save()
+        {
+            crate::toast::error(trf("creator.profile_save_failed", &[&format!("{:?}", err)]));
+        }
+    }
+
+    /// 模组配置的选择、保存与启用覆盖
+    fn profiles_view(&mut self, ui: &mut egui::Ui) {
+        let profiles = self.profiles.get_or_insert_with(ModProfileLibrary::load);
+        let names: Vec<String> = profiles.profiles.iter().map(|p| p.name.clone()).collect();
+        let active = self.active_profile().map(|profile| profile.name.clone());
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("mod-profiles")
+                .selected_text(
+                    active
+                        .clone()
+                        .unwrap_or_else(|| tr("creator.no_profile").into()),
+                )
+                .show_ui(ui, |ui| {
+                    if ui
+                        .selectable_label(active.is_none(), tr("creator.no_profile"))
+                        .clicked()
+                    {
+                        self.profile = None;
+                    }
+                    for name in &names {
+                        if ui
+                            .selectable_label(active.as_ref() == Some(name), name)
+                            .clicked()
+                        {
+                            self.mod_path = self
+                                .profiles
+                                .as_ref()
+                                .and_then(|profiles| profiles.get(name))
+                                .map(|profile| profile.mod_path.clone());
+                            self.profile = Some(name.clone());
+                        }
+                    }
+                });
+            if let Some(name) = &active
+                && ui.button(tr("creator.delete_profile")).clicked()
+            {
+                if let Some(profiles) = &mut self.profiles {
+                    profiles.remove(name);
+                }
+                self.profile = None;
+                self.save_profiles();
+            }
+        });
+        let Some(mod_path) = self.mod_path.clone() else {
+            ui.label(tr("creator.profile_need_mods"));
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_profile_name)
+                    .hint_text(tr("creator.profile_name")),
+            );
+            let name = self.new_profile_name.trim().to_string();
+            if ui
+                .add_enabled(
+                    !name.is_empty(),
+                    egui::Button::new(tr("creator.save_profile")),
+                )
+                .clicked()
+            {
+                let mut profile = ModProfile::new(name.clone(), mod_path);
+                if let Some(active) = self.active_profile() {
+                    profile.overrides = active.overrides.clone();
+                }
+                if let Some(profiles) = &mut self.profiles {
+                    profiles.upsert(profile);
+                }
+                self.profile = Some(name);
+                self.new_profile_name.clear();
+                self.save_profiles();
+            }
+        });
+        let Some(profile) = self.active_profile() else {
+            return;
+        };
+        let name = profile.name.clone();
+        match profile.mods() {
+            Ok(mods) => {
+                ui.label(tr("creator.profile_mods_hint"));
+                let mut changed = None;
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for (mod_name, enabled) in mods {
+                            let mut checked = enabled;
+                            if ui.checkbox(&mut checked, &mod_name).changed() {
+                                changed = Some((mod_name, checked));
+                            }
+                        }
+                    });
+                if let Some((mod_name, enabled)) = changed
+                    && let Some(profile) = self
+                        .profiles
+                        .as_mut()
+                        .and_then(|profiles| profiles.get_mut(&name))
+                {
+                    profile.overrides.insert(mod_name, enabled);
+                    self.save_profiles();
+                }
+            }
+            Err(err) => {
+                ui.colored_label(ui.visuals().error_fg_color, err.message());
+            }
+        }
+    }
+
+    /// 上次加载失败的原因、处理建议与游戏输出的摘录
+    fn error_view(&self, ui: &mut egui::Ui) {
+        let Some((title, err)) = &self.error else {
+            return;
+        };
+        ui.separator();
+        ui.colored_label(ui.visuals().error_fg_color, trf(title, &[&err.message()]));
+        if let Some(hint) = err.hint() {
+            ui.label(hint);
+        }
+        if let Some(excerpt) = err.excerpt() {
+            egui::CollapsingHeader::new(tr("load_error.output"))
+                .default_open(true)
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .max_height(200.0)
+                        .show(ui, |ui| {
+                            ui.add(
+                                egui::Label::new(egui::RichText::new(excerpt).monospace()).wrap(),
+                            );
+                        });
+                });
+        }
+    }
+
+    fn progress_view(&mut self, ui: &mut egui::Ui) {
+        if let Some(receiver) = &self.progress_receiver {
+            while let Ok(progress) = receiver.try_recv() {
+                if let Some((_, status)) = self
+                    .stages
+                    .iter_mut()
+                    .find(|(stage, _)| *stage == progress.stage)
+                {
+                    *status = progress.status;
                 }
             }
+        }
+        if self.stages.is_empty() {
+            return;
+        }
+        let done = self
+            .stages
+            .iter()
+            .filter(|(_, status)| *status == StageStatus::Done)
+            .count();
+        for (stage, status) in &self.stages {
+            let status_text = match status {
+                StageStatus::Pending => tr("stage.pending"),
+                StageStatus::Running => tr("stage.running"),
+                StageStatus::Done => tr("stage.done"),
+                StageStatus::Failed => tr("stage.failed"),
+            };
+            let text = trf("creator.stage_status", &[&stage.name(), &status_text]);
+            match status {
+                StageStatus::Running => ui.strong(text),
+                StageStatus::Failed => ui.colored_label(egui::Color32::LIGHT_RED, text),
+                _ => ui.label(text),
+            };
+        }
+        ui.add(
+            egui::ProgressBar::new(done as f32 / self.stages.len() as f32)
+                .show_percentage()
+                .animate(self.thread.is_some()),
+        );
+        if self.thread.is_some() {
+            if self.monitor.is_cancelled() {
+                ui.label(tr("creator.cancelling"));
+            } else if ui.button(tr("common.cancel")).clicked() {
+                self.monitor.cancel();
+            }
+        }
+    }
+}
+
+impl FactorioContextCreatorView {
+    /// 启动本地游戏导出数据，或读取上次导出的缓存
+    #[cfg(not(target_arch = "wasm32"))]
+    fn dump_view(&mut self, ui: &mut egui::Ui) {
+        ui.label(tr("creator.select_game"));
+        self.installs_view(ui);
+        if ui.button(tr("creator.browse")).clicked()
+            && let Some(path) = crate::file_dialog::FileDialog::new().pick_file()
+        {
+            self.path = Some(path);
+        }
+        if let Some(path) = &self.path {
+            ui.label(trf("creator.selected_game", &[&path.display()]));
+            if path.to_string_lossy().contains("steam") {
+                ui.label(tr("creator.steam_hint"));
+            }
+        } else {
+            ui.label(tr("creator.no_game"));
+        }
+
+        ui.separator();
 
-            if let Some(mod_path) = &self.mod_path {
-                ui.label(format!("已选择Mod路径: {}", mod_path.display()));
+        ui.label(tr("creator.select_mods"));
+        if ui.button(tr("creator.browse")).clicked() {
+            if let Some(mod_path) = crate::file_dialog::FileDialog::new().pick_folder() {
+                self.mod_path = Some(mod_path);
             } else {
-                ui.label("未选择Mod路径");
+                self.mod_path = None;
+            }
+        }
+
+        if let Some(mod_path) = &self.mod_path {
+            ui.label(trf("creator.selected_mods", &[&mod_path.display()]));
+        } else {
+            ui.label(tr("creator.no_mods"));
+        }
+
+        ui.collapsing(tr("creator.profiles"), |ui| self.profiles_view(ui));
+
+        ui.separator();
+
+        ui.collapsing(tr("creator.languages"), |ui| {
+            ui.label(tr("creator.languages_hint"));
+            if self.languages.is_empty() {
+                self.languages.push("zh-CN".to_string());
+            }
+            for (code, name) in GAME_LOCALES {
+                let mut checked = self.languages.iter().any(|lang| lang == code);
+                if ui
+                    .checkbox(&mut checked, format!("{} ({})", name, code))
+                    .changed()
+                {
+                    if checked {
+                        self.languages.push(code.to_string());
+                    } else if self.languages.len() > 1 {
+                        self.languages.retain(|lang| lang != code);
+                    }
+                }
             }
+        });
+
+        let mut can_load_context = true;
+        if self.path.is_none() {
+            ui.label(tr("creator.need_game"));
+            can_load_context = false;
+        }
+        if let Some(mod_path) = self.mod_path.as_ref()
+            && !mod_path.join("mod-list.json").exists()
+        {
+            ui.label(tr("creator.no_mod_list"));
+            can_load_context = false;
+        }
+
+        if self.thread.is_some() {
+            can_load_context = false;
+        }
+
+        ui.separator();
 
-            let mut can_load_context = true;
-            if self.path.is_none() {
-                ui.label("请选择游戏可执行文件以继续。");
-                can_load_context = false;
+        let load_button = ui.add_enabled(can_load_context, egui::Button::new(tr("creator.load")));
+        tutorial::anchor(TutorialStep::LoadContext, &load_button);
+        if load_button.clicked()
+            && let Some(path) = &self.path
+            && let Some(destination) = self.destination()
+            && let None = self.thread
+        {
+            let exe_path = path.clone().as_path().to_owned();
+            let mod_path = self.mod_path.clone().map(|p| p.as_path().to_owned());
+            let profile = self.active_profile().cloned();
+            let languages = self.languages.clone();
+            let monitor = self.start_progress(&LoadStage::ALL);
+            self.error = None;
+            self.thread = Some(std::thread::spawn(move || {
+                // 使用模组配置时，导出改用按配置生成的模组文件夹
+                let mod_path = match profile {
+                    Some(profile) => Some(
+                        profile
+                            .prepare()
+                            .map_err(|err| ("creator.load_failed", err))?,
+                    ),
+                    None => mod_path,
+                };
+                match FactorioContext::load_from_executable_path(
+                    &exe_path,
+                    mod_path.as_deref(),
+                    &languages.iter().map(String::as_str).collect::<Vec<_>>(),
+                    &monitor,
+                ) {
+                    Ok(ctx) => {
+                        destination.deliver(ctx);
+                        Ok(())
+                    }
+                    Err(_) if monitor.is_cancelled() => {
+                        crate::toast::info(tr("creator.load_cancelled"));
+                        Ok(())
+                    }
+                    Err(err) => Err(("creator.load_failed", err)),
+                }
+            }));
+        }
+
+        ui.separator();
+
+        if ui
+            .add_enabled(
+                self.thread.is_none(),
+                egui::Button::new(tr("creator.load_cached")),
+            )
+            .clicked()
+            && let Some(destination) = self.destination()
+            && let None = self.thread
+        {
+            let monitor = self.start_progress(&[LoadStage::Parse]);
+            self.error = None;
+            self.thread =
+                Some(std::thread::spawn(
+                    move || match FactorioContext::load_from_tmp_no_dump(
+                        &latest_dump_key(),
+                        &monitor,
+                    ) {
+                        Ok(ctx) => {
+                            destination.deliver(ctx);
+                            Ok(())
+                        }
+                        Err(_) if monitor.is_cancelled() => {
+                            crate::toast::info(tr("creator.load_cached_cancelled"));
+                            Ok(())
+                        }
+                        Err(err) => Err(("creator.load_cached_failed", err.into())),
+                    },
+                ));
+        }
+    }
+}
+
+impl FactorioContextCreatorView {
+    /// 选择要上传的文件，读取完成后从通道送回
+    fn pick_upload(&mut self, kind: UploadKind) {
+        let sender = self
+            .upload_channel
+            .get_or_insert_with(std::sync::mpsc::channel)
+            .0
+            .clone();
+        let (filter_name, extensions) = kind.filter();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = crate::file_dialog::FileDialog::new()
+            .add_filter(filter_name, extensions)
+            .pick_file()
+        {
+            match std::fs::read(&path) {
+                Ok(content) => {
+                    let name = path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let _ = sender.send((kind, name, content));
+                }
+                Err(err) => {
+                    crate::toast::error(trf("planner.read_failed", &[&path.display(), &err]))
+                }
             }
-            if let Some(mod_path) = self.mod_path.as_ref()
-                && !mod_path.join("mod-list.json").exists()
+        }
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Some(file) = rfd::AsyncFileDialog::new()
+                .add_filter(filter_name, extensions)
+                .pick_file()
+                .await
             {
-                ui.label("模组文件夹下未找到 mod-list.json。");
-                can_load_context = false;
+                let _ = sender.send((kind, file.file_name(), file.read().await));
+                crate::repaint::wake();
             }
+        });
+    }
 
-            if self.thread.is_some() {
-                ui.label("正在加载游戏上下文，请稍候...");
-                can_load_context = false;
+    /// 上传导出的数据与图标压缩包创建上下文，不需要本地安装游戏
+    fn upload_view(&mut self, ui: &mut egui::Ui) {
+        if let Some((_, receiver)) = &self.upload_channel {
+            while let Ok((kind, name, content)) = receiver.try_recv() {
+                match kind {
+                    UploadKind::RawDump => self.uploaded_raw = Some((name, content)),
+                    UploadKind::IconArchive => self.uploaded_archive = Some((name, content)),
+                }
             }
+        }
 
-            ui.separator();
+        #[cfg(not(target_arch = "wasm32"))]
+        ui.separator();
+        ui.label(tr("creator.upload_hint"));
+        let mut picked = None;
+        for (kind, label, file) in [
+            (
+                UploadKind::RawDump,
+                tr("creator.upload_raw"),
+                &self.uploaded_raw,
+            ),
+            (
+                UploadKind::IconArchive,
+                tr("creator.upload_icons"),
+                &self.uploaded_archive,
+            ),
+        ] {
+            ui.horizontal(|ui| {
+                if ui.button(label).clicked() {
+                    picked = Some(kind);
+                }
+                match file {
+                    Some((name, content)) => ui.label(format!(
+                        "{} ({:.1} MB)",
+                        name,
+                        content.len() as f64 / 1048576.0
+                    )),
+                    None => ui.weak(tr("creator.upload_none")),
+                };
+            });
+        }
+        if let Some(kind) = picked {
+            self.pick_upload(kind);
+        }
 
-            if ui
-                .add_enabled(can_load_context, egui::Button::new("加载游戏上下文"))
-                .clicked()
-                && let Some(path) = &self.path
-                && let Some(sender) = &self.subview_sender
-                && let None = self.thread
+        let can_load = (self.uploaded_raw.is_some() || self.uploaded_archive.is_some())
+            && self.thread.is_none();
+        if ui
+            .add_enabled(can_load, egui::Button::new(tr("creator.upload_load")))
+            .clicked()
+            && let Some(destination) = self.destination()
+        {
+            let raw = self
+                .uploaded_raw
+                .as_ref()
+                .map(|(_, content)| content.clone());
+            let archive = self
+                .uploaded_archive
+                .as_ref()
+                .map(|(_, content)| content.clone());
+            let task = move || {
+                let uploaded =
+                    FactorioContext::load_from_upload(raw.as_deref(), archive.as_deref())
+                        .map_err(|err| ("creator.upload_failed", err))?;
+                icon_cache::register_memory_icons(uploaded.icons);
+                destination.deliver(uploaded.ctx);
+                Ok(())
+            };
+            self.error = None;
+            // 网页版没有线程，解析会短暂阻塞界面
+            #[cfg(not(target_arch = "wasm32"))]
             {
-                let exe_path = path.clone().as_path().to_owned();
-                let mod_path = self.mod_path.clone().map(|p| p.as_path().to_owned());
-                let sender = sender.clone();
-                self.thread =
-                    Some(std::thread::spawn(
-                        move || match FactorioContext::load_from_executable_path(
-                            &exe_path,
-                            mod_path.as_deref(),
-                            None,
-                        ) {
-                            Ok(ctx) => {
-                                sender
-                                    .send(Box::new(PlannerView::new(ctx)))
-                                    .expect("Failed to send subview");
-                            }
-                            Err(e) => {
-                                crate::toast::error(format!("加载游戏上下文失败: {:?}", e));
-                            }
-                        },
-                    ));
+                self.thread = Some(std::thread::spawn(task));
+            }
+            #[cfg(target_arch = "wasm32")]
+            {
+                self.error = task().err();
             }
+        }
+    }
+}
 
+impl Subview for FactorioContextCreatorView {
+    fn view(&mut self, ui: &mut egui::Ui) {
+        ui.vertical_centered(|ui| {
+            ui.heading(tr("creator.title"));
             ui.separator();
 
-            if ui
-                .add_enabled(self.thread.is_none(), egui::Button::new("加载缓存上下文"))
-                .clicked()
-                && let Some(sender) = &self.subview_sender
-                && let None = self.thread
-            {
-                let sender = sender.clone();
-                self.thread =
-                    Some(std::thread::spawn(
-                        move || match FactorioContext::load_from_tmp_no_dump() {
-                            Ok(ctx) => {
-                                sender.send(Box::new(PlannerView::new(ctx))).unwrap();
-                            }
-                            Err(e) => {
-                                crate::toast::error(format!("加载缓存上下文失败: {:?}", e));
-                            }
-                        },
-                    ));
-            }
+            #[cfg(not(target_arch = "wasm32"))]
+            self.dump_view(ui);
+            self.upload_view(ui);
+
             if let Some(ref thread) = self.thread
                 && thread.is_finished()
             {
                 let thread = self.thread.take().unwrap();
-                thread.join().unwrap();
+                self.error = thread.join().unwrap().err();
             }
+
+            self.progress_view(ui);
+            self.error_view(ui);
         });
     }
 }
@@ -1021,3 +4821,584 @@ impl GameContextCreatorView for FactorioContextCreatorView {
         self.subview_sender = Some(sender);
     }
 }
+
+#[test]
+fn test_mechanic_id_roundtrip() {
+    let mut factory = FactoryInstance::new("测试".to_string());
+    for recipe in ["iron-gear-wheel", "copper-cable"] {
+        factory.mechanics.insert(
+            MechanicId::generate(),
+            Box::new(RecipeConfig {
+                recipe: recipe.into(),
+                machine: "assembling-machine-2".into(),
+                module_config: ModuleConfig::new(),
+                instance_fuel: None,
+            }),
+        );
+    }
+    let serialized = serde_json::to_string(&factory).unwrap();
+    let mut fields = serde_json::from_str::<serde_json::Value>(&serialized)
+        .unwrap()
+        .as_object()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>();
+    fields.sort();
+    let mut expected = FactoryInstance::SERIALIZED_FIELDS.to_vec();
+    expected.sort();
+    assert_eq!(fields, expected);
+    let deserialized = serde_json::from_str::<FactoryInstance>(&serialized).unwrap();
+    assert_eq!(
+        factory.mechanics.keys().collect::<Vec<_>>(),
+        deserialized.mechanics.keys().collect::<Vec<_>>()
+    );
+    let max_id = *deserialized.mechanics.keys().max().unwrap();
+    assert!(MechanicId::generate() > max_id);
+}
+
+#[test]
+fn test_target_sets() {
+    let mut factory = FactoryInstance::new("测试".to_string());
+    factory.target = vec![(GenericItem::Item("iron-gear-wheel".into()), 1.0)];
+    factory.target_sets.push(TargetPreset {
+        name: "战时".to_string(),
+        targets: vec![(GenericItem::Item("firearm-magazine".into()), 2.0)],
+    });
+    assert!(factory.switch_target_set(1));
+    assert_eq!(factory.target[0].1, 2.0);
+    let serialized = serde_json::to_string(&factory).unwrap();
+    let mut deserialized = serde_json::from_str::<FactoryInstance>(&serialized).unwrap();
+    assert_eq!(deserialized.active_target_set, 1);
+    assert!(deserialized.switch_target_set(0));
+    assert_eq!(deserialized.target, factory.target_sets[0].targets);
+    // 目标的约束方式随存档保存，只传给求解器当前目标中的物品
+    let magazine = GenericItem::Item("firearm-magazine".into());
+    factory
+        .target_modes
+        .insert(magazine.clone(), TargetMode::Maximize);
+    let deserialized =
+        serde_json::from_str::<FactoryInstance>(&serde_json::to_string(&factory).unwrap()).unwrap();
+    assert_eq!(deserialized.target_modes[&magazine], TargetMode::Maximize);
+}
+
+#[test]
+fn test_machine_count() {
+    assert_eq!(machine_count(0.0), (0, 0.0));
+    assert_eq!(machine_count(2.0000001), (2, 1.0));
+    let (count, utilization) = machine_count(2.25);
+    assert_eq!(count, 3);
+    assert!((utilization - 0.25).abs() < 1e-9);
+}
+
+#[test]
+fn test_locked_mechanics() {
+    let ctx = FactorioContext::test_load();
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let gear = GenericItem::Item("iron-gear-wheel".into());
+    let assembler = MechanicId::generate();
+    factory.mechanics.insert(
+        assembler,
+        Box::new(RecipeConfig {
+            recipe: "iron-gear-wheel".into(),
+            machine: "assembling-machine-2".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+    factory.target = vec![(gear.clone(), 1.0)];
+    factory.locked.insert(assembler, 2.0);
+    // 锁定的机制不参与优化，其物料流进入常数项
+    let (_, flows, _, constant, _, _) = factory.solver_args(&factory.target, &ctx);
+    assert!(!flows.contains_key(&assembler));
+    let per_machine = factory.mechanic_flow(factory.mechanics[&assembler].as_ref(), &ctx)[&gear];
+    assert!((constant[&gear] - per_machine * 2.0).abs() < 1e-9);
+    let mut solution = (Flow::new(), 0.0);
+    factory.complete_solution(&mut solution, &ctx);
+    assert_eq!(solution.0[&assembler], 2.0);
+    let serialized = serde_json::to_string(&factory).unwrap();
+    let deserialized = serde_json::from_str::<FactoryInstance>(&serialized).unwrap();
+    assert_eq!(deserialized.locked.values().collect::<Vec<_>>(), vec![&2.0]);
+    // 有锁定的机制时才能最大化，否则按至少处理
+    factory
+        .target_modes
+        .insert(gear.clone(), TargetMode::Maximize);
+    let (_, _, _, _, modes, _) = factory.solver_args(&factory.target, &ctx);
+    assert_eq!(modes[&gear], TargetMode::Maximize);
+    factory.locked.clear();
+    assert!(!factory.can_maximize());
+    let (_, _, _, _, modes, _) = factory.solver_args(&factory.target, &ctx);
+    assert_eq!(modes[&gear], TargetMode::AtLeast);
+}
+
+#[test]
+fn test_back_solve_targets() {
+    let ctx = FactorioContext::test_load();
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let gear = GenericItem::Item("iron-gear-wheel".into());
+    let assembler = MechanicId::generate();
+    factory.mechanics.insert(
+        assembler,
+        Box::new(RecipeConfig {
+            recipe: "iron-gear-wheel".into(),
+            machine: "assembling-machine-2".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+    factory.target = vec![(gear.clone(), 1.0)];
+    factory.solve_blocking(&ctx).unwrap();
+    let solution = factory.solution.0[&assembler];
+    assert!(solution > 0.0);
+    // 反推为 12 台后重新求解，目标按同样的比例放大
+    assert!(scale_targets_for_count(
+        &mut factory.target,
+        &mut factory.quality_targets,
+        solution,
+        12.0
+    ));
+    assert!((factory.target[0].1 - 12.0 / solution).abs() < 1e-9);
+    factory.solve_blocking(&ctx).unwrap();
+    assert!((factory.solution.0[&assembler] - 12.0).abs() < 1e-6);
+    // 不在方案中的机制无法按比例调整
+    assert!(!scale_targets_for_count(
+        &mut factory.target,
+        &mut [],
+        0.0,
+        12.0
+    ));
+    assert!(!scale_targets_for_count(&mut [], &mut [], solution, 12.0));
+    // 只有按品质展开的目标时同样按比例缩放
+    let mut quality_targets = vec![QualityTarget::new("iron-gear-wheel".into(), 0, 2.0)];
+    assert!(scale_targets_for_count(
+        &mut [],
+        &mut quality_targets,
+        2.0,
+        4.0
+    ));
+    assert!((quality_targets[0].total - 4.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_toggle_external() {
+    let ctx = FactorioContext::test_load();
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let gear = GenericItem::Item("iron-gear-wheel".into());
+    let plate = GenericItem::Item("iron-plate".into());
+    let assembler = MechanicId::generate();
+    factory.mechanics.insert(
+        assembler,
+        Box::new(RecipeConfig {
+            recipe: "iron-gear-wheel".into(),
+            machine: "assembling-machine-2".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+    factory.target = vec![(gear.clone(), 1.0)];
+    factory.external = vec![(plate.clone(), 2.0), (plate.clone(), 3.0)];
+    // 重复的条目一并移除
+    assert!(!factory.toggle_external(&plate));
+    assert!(factory.external.is_empty());
+    assert!(factory.toggle_external(&plate));
+    assert_eq!(factory.external, vec![(plate.clone(), 0.0)]);
+    assert!(factory.is_external(&plate));
+    factory.toggle_external(&plate);
+    // 铁板由工厂中的熔炉产出，铁矿没有产出它的机制，标记也不会改变求解结果
+    let furnace = MechanicId::generate();
+    factory.mechanics.insert(
+        furnace,
+        Box::new(RecipeConfig {
+            recipe: "iron-plate".into(),
+            machine: "electric-furnace".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+    let produced = factory.produced_items(&ctx);
+    assert!(can_toggle_external(&plate, false, &produced));
+    let ore = GenericItem::Item("iron-ore".into());
+    assert!(!can_toggle_external(&ore, false, &produced));
+    assert!(can_toggle_external(&ore, true, &produced));
+    assert!(!can_toggle_external(
+        &GenericItem::Electricity,
+        false,
+        &produced
+    ));
+    factory.solve_blocking(&ctx).unwrap();
+    assert!(factory.solution.0[&furnace] > 0.0);
+    // 改由额外输入供应后不再需要熔炉
+    assert!(factory.toggle_external(&plate));
+    factory.solve_blocking(&ctx).unwrap();
+    assert!(factory.solution.0[&assembler] > 0.0);
+    assert!(factory.solution.0.get(&furnace).copied().unwrap_or(0.0) < 1e-9);
+    assert!(factory.total_flow.get(&ore).copied().unwrap_or(0.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_notes_roundtrip() {
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let gear = MechanicId::generate();
+    factory.mechanics.insert(
+        gear,
+        Box::new(RecipeConfig {
+            recipe: "iron-gear-wheel".into(),
+            machine: "assembling-machine-2".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+    factory.notes = "供给红瓶生产线".to_string();
+    factory
+        .mechanic_notes
+        .insert(gear, "二级组装机足够，不需要插件".to_string());
+    // 已删除机制与空白备注不写入存档
+    factory
+        .mechanic_notes
+        .insert(MechanicId::generate(), "已删除".to_string());
+    let serialized = serde_json::to_string(&factory).unwrap();
+    let deserialized = serde_json::from_str::<FactoryInstance>(&serialized).unwrap();
+    assert_eq!(deserialized.notes, factory.notes);
+    assert_eq!(deserialized.mechanic_notes.len(), 1);
+    assert_eq!(
+        deserialized.mechanic_notes[&gear],
+        factory.mechanic_notes[&gear]
+    );
+}
+
+#[test]
+fn test_collapsed_roundtrip() {
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let gear = factory.insert_mechanic(
+        Box::new(RecipeConfig {
+            recipe: "iron-gear-wheel".into(),
+            machine: "assembling-machine-2".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+        false,
+    );
+    factory.collapsed.insert(gear);
+    // 已删除机制的折叠状态不写入存档
+    factory.collapsed.insert(MechanicId::generate());
+    let serialized = serde_json::to_string(&factory).unwrap();
+    let deserialized = serde_json::from_str::<FactoryInstance>(&serialized).unwrap();
+    assert_eq!(
+        deserialized.collapsed.into_iter().collect::<Vec<_>>(),
+        vec![gear]
+    );
+}
+
+#[test]
+fn test_insert_mechanic_adjacent() {
+    let recipe = |recipe: &str| -> Box<FactorioMechanic> {
+        Box::new(RecipeConfig {
+            recipe: recipe.into(),
+            machine: "assembling-machine-2".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        })
+    };
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let first = factory.insert_mechanic(recipe("iron-gear-wheel"), true);
+    let last = factory.insert_mechanic(recipe("copper-cable"), true);
+    // 从第一张卡片连续加入的机制依次排在它之后
+    factory.insert_after = Some(first);
+    let a = factory.insert_mechanic(recipe("electronic-circuit"), true);
+    let b = factory.insert_mechanic(recipe("pipe"), true);
+    assert_eq!(
+        factory.mechanics.keys().copied().collect::<Vec<_>>(),
+        vec![first, a, b, last]
+    );
+    // 关闭选项或来源已删除时加在末尾
+    factory.insert_after = Some(first);
+    let c = factory.insert_mechanic(recipe("pipe"), false);
+    factory.insert_after = Some(MechanicId::generate());
+    let d = factory.insert_mechanic(recipe("pipe"), true);
+    assert_eq!(factory.mechanics.get_index_of(&c), Some(4));
+    assert_eq!(factory.mechanics.get_index_of(&d), Some(5));
+}
+
+#[test]
+fn test_tag_status_roundtrip() {
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let serialized = serde_json::to_string(&factory).unwrap();
+    let deserialized = serde_json::from_str::<FactoryInstance>(&serialized).unwrap();
+    assert_eq!(deserialized.tag, None);
+    assert_eq!(deserialized.status, FactoryStatus::Draft);
+    factory.tag = Some(FactoryTag::Blue);
+    factory.status = FactoryStatus::NeedsUpdate;
+    let serialized = serde_json::to_string(&factory).unwrap();
+    assert!(serialized.contains("\"needs_update\""));
+    let deserialized = serde_json::from_str::<FactoryInstance>(&serialized).unwrap();
+    assert_eq!(deserialized.tag, Some(FactoryTag::Blue));
+    assert_eq!(deserialized.status, FactoryStatus::NeedsUpdate);
+}
+
+#[test]
+fn test_rate_unit() {
+    let mut factory = FactoryInstance::new("测试".to_string());
+    assert_eq!(factory.rate_unit(), RateUnit::global());
+    factory.rate_unit = Some(RateUnit::ExpressBelt);
+    let serialized = serde_json::to_string(&factory).unwrap();
+    let deserialized = serde_json::from_str::<FactoryInstance>(&serialized).unwrap();
+    assert_eq!(deserialized.rate_unit(), RateUnit::ExpressBelt);
+}
+
+#[test]
+fn test_requires_heating() {
+    let ctx = FactorioContext::test_load();
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let mechanic: Box<FactorioMechanic> = Box::new(RecipeConfig {
+        recipe: "iron-gear-wheel".into(),
+        machine: "assembling-machine-2".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+    });
+    assert!(
+        !factory
+            .mechanic_flow(mechanic.as_ref(), &ctx)
+            .contains_key(&GenericItem::Heat)
+    );
+    factory.requires_heating = true;
+    // 组装机 2 需要 100kW 的供暖
+    let flow = factory.mechanic_flow(mechanic.as_ref(), &ctx);
+    assert!((flow[&GenericItem::Heat] + 1e5).abs() < 1e-6);
+    let serialized = serde_json::to_string(&factory).unwrap();
+    let deserialized = serde_json::from_str::<FactoryInstance>(&serialized).unwrap();
+    assert!(deserialized.requires_heating);
+    // 加热塔作为反应堆提供热量，燃烧 40MW 的燃料产生 100MW 的热量
+    let tower = ReactorConfig {
+        machine: "heating-tower".into(),
+        layout: ReactorLayout::default(),
+        instance_fuel: Some("coal".into()),
+    }
+    .as_flow(&ctx);
+    assert!((tower[&GenericItem::Heat] - 1e8).abs() < 1e-3);
+    // 煤的燃料值为 4MJ
+    assert!((tower[&GenericItem::Item("coal".into())] + 10.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_route_collectors() {
+    let ctx = FactorioContext::test_load();
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let collector = factory.insert_mechanic(
+        Box::new(AsteroidCollectorConfig {
+            machine: "asteroid-collector".into(),
+            route: "nauvis".to_string(),
+            capture_fraction: 1.0,
+        }),
+        false,
+    );
+    let metallic = GenericItem::Item("metallic-asteroid-chunk".into());
+    let orbit = factory.solver_args(&[], &ctx).1[&collector].0[&metallic];
+    // 工厂改走航线后，收集器的产量随航线上的平均生成频率变化，缓存的物料流随之失效
+    factory.route = Some("nauvis-vulcanus".to_string());
+    assert_eq!(factory.apply_route(), 0);
+    let rate = ctx.route_asteroid_spawn_rates("nauvis-vulcanus")["metallic-asteroid-chunk"];
+    let (_, flows, _, constant, ..) = factory.solver_args(&[], &ctx);
+    assert!((flows[&collector].0[&metallic] - rate).abs() < 1e-9);
+    assert!(rate < orbit);
+    let capture = GenericItem::Custom {
+        name: "asteroid-capture:nauvis-vulcanus".to_string(),
+    };
+    assert_eq!(constant[&capture], 1.0);
+    assert_eq!(flows[&collector].0[&capture], -1.0);
+    // 多放收集器也捕获不到超过航线生成量的碎块
+    factory.insert_mechanic(
+        Box::new(AsteroidCollectorConfig {
+            machine: "asteroid-collector".into(),
+            route: "nauvis-vulcanus".to_string(),
+            capture_fraction: 1.0,
+        }),
+        false,
+    );
+    let solve = |amount: f64| {
+        let (target, flows, external, constant, target_modes, byproduct_policies) =
+            factory.solver_args(&[(metallic.clone(), amount)], &ctx);
+        SolverData::new(target, flows)
+            .with_external(external)
+            .with_constant(constant)
+            .with_target_modes(target_modes)
+            .with_byproduct_policies(byproduct_policies)
+            .solve()
+    };
+    assert!(solve(rate * 0.5).is_ok());
+    assert!(solve(rate * 1.5).is_err());
+    // 航线改为不限时收集器保留各自的航线
+    factory.route = None;
+    assert_eq!(factory.apply_route(), 2);
+    assert_eq!(
+        factory.solver_args(&[], &ctx).1[&collector].0[&metallic],
+        flows[&collector].0[&metallic]
+    );
+}
+
+#[test]
+fn test_location_heating() {
+    let ctx = FactorioContext::test_load();
+    let mut factory = FactoryInstance::new("测试".to_string());
+    let mechanic: Box<FactorioMechanic> = Box::new(RecipeConfig {
+        recipe: "iron-gear-wheel".into(),
+        machine: "assembling-machine-2".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+    });
+    // 选择埃奎罗后自动计入供暖
+    factory.location = Some("aquilo".to_string());
+    factory.apply_location_heating(&ctx);
+    assert!(factory.requires_heating);
+    let flow = factory.mechanic_flow(mechanic.as_ref(), &ctx);
+    assert!((flow[&GenericItem::Heat] + 1e5).abs() < 1e-6);
+    // 切换回新地星后不再需要供暖
+    factory.location = Some("nauvis".to_string());
+    factory.apply_location_heating(&ctx);
+    assert!(!factory.requires_heating);
+    assert!(
+        !factory
+            .mechanic_flow(mechanic.as_ref(), &ctx)
+            .contains_key(&GenericItem::Heat)
+    );
+}
+
+#[test]
+fn test_price_table_costs() {
+    let ore = GenericItem::Item("iron-ore".into());
+    let plate = GenericItem::Item("iron-plate".into());
+    let mut factory = FactoryInstance::new("价格表".to_string());
+    factory.external = vec![
+        (ore.clone(), 3.0),
+        (plate.clone(), 2.0),
+        (plate.clone(), 1.0),
+    ];
+    let table = PriceTable {
+        name: "矿物".to_string(),
+        prices: vec![(ore.clone(), 0.5)],
+    };
+    assert_eq!(factory.external_costs(None)[&ore], 3.0);
+    let costs = factory.external_costs(Some(&table));
+    // 表中列出的物品改用表中价格，其余仍为各项之和
+    assert_eq!(costs[&ore], 0.5);
+    assert_eq!(costs[&plate], 3.0);
+    factory.price_table = Some(table.name.clone());
+    let restored: FactoryInstance =
+        serde_json::from_str(&serde_json::to_string(&factory).unwrap()).unwrap();
+    assert_eq!(restored.price_table, factory.price_table);
+}
+
+#[test]
+fn test_grid_import() {
+    let ctx = FactorioContext::test_load();
+    let gear = GenericItem::Item("iron-gear-wheel".into());
+    let mut factory = FactoryInstance::new("电网".to_string());
+    factory.target = vec![(gear.clone(), 1.0)];
+    factory.external = vec![(GenericItem::Item("iron-plate".into()), 1.0)];
+    factory.mechanics.insert(
+        MechanicId::generate(),
+        Box::new(RecipeConfig {
+            recipe: "iron-gear-wheel".into(),
+            machine: "assembling-machine-2".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+    // 没有电力来源时无法满足组装机的耗电
+    factory.mechanics.insert(
+        MechanicId::generate(),
+        Box::new(SolarPowerConfig {
+            machine: "solar-panel".into(),
+            accumulator: None,
+            planet: "nauvis".to_string(),
+        }),
+    );
+    // 有发电机制时电力缺口必须由它补足，不从电网取电
+    factory.solve_blocking(&ctx).unwrap();
+    assert!(factory.grid_draw() < 1e-6);
+    assert!(
+        !factory
+            .external_costs(None)
+            .contains_key(&GenericItem::Electricity)
+    );
+    factory.grid_import = true;
+    factory.grid_price = 1e-6;
+    assert_eq!(
+        factory.external_costs(None)[&GenericItem::Electricity],
+        1e-12
+    );
+    // 电网足够便宜时全部耗电改从电网取用
+    factory.solve_blocking(&ctx).unwrap();
+    assert!(factory.total_flow[&gear] > 0.0);
+    assert!(factory.grid_draw() > 0.0);
+    assert_eq!(
+        factory.grid_draw(),
+        -factory.total_flow[&GenericItem::Electricity]
+    );
+    let restored: FactoryInstance =
+        serde_json::from_str(&serde_json::to_string(&factory).unwrap()).unwrap();
+    assert!(restored.grid_import);
+    assert_eq!(restored.grid_price, factory.grid_price);
+}
+
+#[test]
+fn test_fulgora_scrap_template() {
+    let ctx = FactorioContext::test_load();
+    let template = FACTORY_TEMPLATES
+        .iter()
+        .find(|template| template.scrap_economy)
+        .unwrap();
+    let mut factory = template.instantiate(&ctx);
+    assert!(factory.scrap_economy);
+    factory.solve_blocking(&ctx).unwrap();
+    // 废料经济模式下除目标外的物品与流体都不留盈余
+    for (item, amount) in &factory.total_flow {
+        if matches!(item, GenericItem::Item(..) | GenericItem::Fluid { .. })
+            && !factory.target.iter().any(|(target, _)| target == item)
+            && !factory.is_external(item)
+        {
+            assert!(amount.abs() < 1e-6, "{:?}: {}", item, amount);
+        }
+    }
+    let scrap = GenericItem::Item(SCRAP_ITEM.into());
+    assert!(factory.total_flow[&scrap] < 0.0);
+    let (_, _, _, _, _, policies) = factory.solver_args(&factory.target, &ctx);
+    assert_eq!(policies[&scrap], ByproductPolicy::Zero);
+    factory.scrap_economy = false;
+    let (_, _, _, _, _, policies) = factory.solver_args(&factory.target, &ctx);
+    assert!(policies.is_empty());
+    // 未选择消耗机制的限定策略不传给求解器
+    factory
+        .byproduct_policies
+        .insert(scrap.clone(), ByproductPolicy::Sinks(vec![]));
+    let (_, _, _, _, _, policies) = factory.solver_args(&factory.target, &ctx);
+    assert!(policies.is_empty());
+}
+
+#[test]
+fn test_quality_targets() {
+    let ctx = FactorioContext::test_load();
+    let mut factory = FactoryInstance::new("品质".to_string());
+    factory.mechanics.insert(
+        MechanicId::generate(),
+        Box::new(RecipeConfig {
+            recipe: "iron-gear-wheel".into(),
+            machine: "assembling-machine-2".into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }),
+    );
+    factory.external = vec![(GenericItem::Item("iron-plate".into()), 1.0)];
+    factory
+        .quality_targets
+        .push(QualityTarget::new("iron-gear-wheel".to_string(), 0, 2.0));
+    let (target, flows, _, _, _, _) = factory.solver_args(&factory.target, &ctx);
+    assert_eq!(target[&factory.quality_targets[0].aggregate()], 2.0);
+    // 只有普通品质有产出，只桥接这一个品质
+    assert_eq!(flows.len(), 2);
+    factory.solve_blocking(&ctx).unwrap();
+    let gear = GenericItem::Item("iron-gear-wheel".into());
+    assert!((factory.total_flow[&gear] - 2.0).abs() < 1e-6);
+    let restored: FactoryInstance =
+        serde_json::from_str(&serde_json::to_string(&factory).unwrap()).unwrap();
+    assert_eq!(restored.quality_targets, factory.quality_targets);
+}
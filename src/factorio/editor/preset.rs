@@ -0,0 +1,350 @@
+use crate::{
+    dyn_serde::save_to_file,
+    error::AppError,
+    factorio::{
+        common::*,
+        editor::modal::show_modal,
+        model::{
+            BoilerConfig, FactorioContext, GeneratorConfig, GenericItem, LightningConfig,
+            ModuleConfig, RecipeConfig, RecipeResult, get_workding_directory,
+            machine_fits_for_recipe,
+        },
+    },
+};
+
+lazy_static::lazy_static! {
+    pub static ref TARGET_PRESETS: std::sync::Mutex<TargetPresetLibrary> =
+        std::sync::Mutex::new(TargetPresetLibrary::load());
+}
+
+/// 一组命名的优化目标
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TargetPreset {
+    pub name: String,
+    pub targets: Vec<(GenericItem, f64)>,
+}
+
+impl TargetPreset {
+    /// 去掉当前上下文中不存在的物品，返回可用的目标与被跳过的数量
+    pub fn available_targets(&self, ctx: &FactorioContext) -> (Vec<(GenericItem, f64)>, usize) {
+        let (available, skipped): (Vec<_>, Vec<_>) =
+            self.targets
+                .iter()
+                .cloned()
+                .partition(|(item, _)| match item {
+                    GenericItem::Item(IdWithQuality(name, _)) => ctx.items.contains_key(name),
+                    GenericItem::Fluid { name, .. } => ctx.fluids.contains_key(name),
+                    GenericItem::Entity(IdWithQuality(name, _)) => ctx.entities.contains_key(name),
+                    _ => true,
+                });
+        (available, skipped.len())
+    }
+}
+
+/// 保存在工作目录下的目标预设库
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TargetPresetLibrary {
+    pub presets: Vec<TargetPreset>,
+}
+
+impl Default for TargetPresetLibrary {
+    fn default() -> Self {
+        let science_packs = [
+            "automation-science-pack",
+            "logistic-science-pack",
+            "military-science-pack",
+            "chemical-science-pack",
+            "production-science-pack",
+            "utility-science-pack",
+            "space-science-pack",
+        ];
+        let mall = ["transport-belt", "inserter", "assembling-machine-1"];
+        Self {
+            presets: vec![
+                TargetPreset {
+                    name: "60 SPM 全部科技包".to_string(),
+                    targets: science_packs
+                        .iter()
+                        .map(|name| (GenericItem::Item((*name).into()), 1.0))
+                        .collect(),
+                },
+                TargetPreset {
+                    name: "商场：传送带、机械臂、组装机".to_string(),
+                    targets: mall
+                        .iter()
+                        .map(|name| (GenericItem::Item((*name).into()), 0.1))
+                        .collect(),
+                },
+            ],
+        }
+    }
+}
+
+impl TargetPresetLibrary {
+    pub fn path() -> std::path::PathBuf {
+        get_workding_directory().join("target-presets.json")
+    }
+
+    /// 读取预设文件，文件不存在时使用内置预设
+    pub fn load() -> Self {
+        let path = Self::path();
+        if !path.exists() {
+            return Self::default();
+        }
+        match std::fs::read_to_string(&path)
+            .map_err(AppError::from)
+            .and_then(|content| serde_json::from_str(&content).map_err(AppError::from))
+        {
+            Ok(library) => library,
+            Err(err) => {
+                log::error!("读取目标预设文件 {} 失败: {:?}", path.display(), err);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), AppError> {
+        save_to_file(self, &Self::path())
+    }
+}
+
+/// 原油处理预设的配方：高级原油处理与两种裂解
+pub const OIL_BALANCER_RECIPES: [&str; 3] = [
+    "advanced-oil-processing",
+    "heavy-oil-cracking",
+    "light-oil-cracking",
+];
+
+/// 原油处理预设中需要完全裂解、不留盈余的流体
+pub const OIL_BALANCED_FLUIDS: [&str; 2] = ["heavy-oil", "light-oil"];
+
+/// 使用名称排序最前的可用机器构造配方机制，配方不存在或没有可用机器时返回 None
+fn recipe_mechanic(ctx: &FactorioContext, name: &str) -> Option<Box<FactorioMechanic>> {
+    let recipe = ctx.recipes.get(name)?;
+    let machine = ctx
+        .crafters
+        .values()
+        .filter(|crafter| machine_fits_for_recipe(crafter, recipe))
+        .min_by(|a, b| a.base.base.name.cmp(&b.base.base.name))?;
+    Some(Box::new(RecipeConfig {
+        recipe: name.into(),
+        machine: machine.base.base.name.as_str().into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+    }))
+}
+
+/// 构造原油处理预设的机制，每个配方使用名称排序最前的可用机器，缺少的配方跳过
+pub fn oil_balancer_mechanics(ctx: &FactorioContext) -> Vec<Box<FactorioMechanic>> {
+    OIL_BALANCER_RECIPES
+        .iter()
+        .filter_map(|name| recipe_mechanic(ctx, name))
+        .collect()
+}
+
+/// 废料经济模式的主要输入
+pub const SCRAP_ITEM: &str = "scrap";
+
+/// 废料经济模式中的重油，作为发电燃料的来源
+pub const SCRAP_FUEL_FLUID: &str = "heavy-oil";
+
+const SCRAP_RECYCLING_RECIPE: &str = "scrap-recycling";
+
+/// 以重油发电所需的配方：重油制固体燃料，冰融化为锅炉用水
+const SCRAP_POWER_RECIPES: [&str; 2] = ["solid-fuel-from-heavy-oil", "ice-melting"];
+
+/// 废料回收及其产物的逐级回收。回收为自身的物品（如冰、石头）每次只返还四分之一，
+/// 反复回收即可销毁，因此这些机制保证所有产物都有去处
+pub fn scrap_recycling_mechanics(ctx: &FactorioContext) -> Vec<Box<FactorioMechanic>> {
+    let mut recipes = vec![SCRAP_RECYCLING_RECIPE.to_string()];
+    let mut idx = 0;
+    while idx < recipes.len() {
+        if let Some(recipe) = ctx.recipes.get(&recipes[idx]) {
+            for result in recipe.results.iter() {
+                if let RecipeResult::Item(result) = result {
+                    let recycling = format!("{}-recycling", result.name);
+                    if ctx.recipes.contains_key(&recycling) && !recipes.contains(&recycling) {
+                        recipes.push(recycling);
+                    }
+                }
+            }
+        }
+        idx += 1;
+    }
+    recipes
+        .iter()
+        .filter_map(|name| recipe_mechanic(ctx, name))
+        .collect()
+}
+
+/// 以重油制成的固体燃料驱动锅炉与蒸汽机，锅炉用水由冰融化而来。融冰本身耗电，
+/// 单靠锅炉无法自给，因此另加 Fulgora 的集电器作为基础电源
+pub fn scrap_power_mechanics(ctx: &FactorioContext) -> Vec<Box<FactorioMechanic>> {
+    let mut mechanics = SCRAP_POWER_RECIPES
+        .iter()
+        .filter_map(|name| recipe_mechanic(ctx, name))
+        .collect::<Vec<_>>();
+    if ctx.boilers.contains_key("boiler") {
+        mechanics.push(Box::new(BoilerConfig {
+            machine: "boiler".into(),
+            instance_fuel: Some("solid-fuel".into()),
+        }));
+    }
+    if ctx.generators.contains_key("steam-engine") {
+        mechanics.push(Box::new(GeneratorConfig {
+            machine: "steam-engine".into(),
+        }));
+    }
+    if ctx.lightning_attractors.contains_key("lightning-collector")
+        && ctx.planets.contains_key("fulgora")
+    {
+        mechanics.push(Box::new(LightningConfig {
+            machine: "lightning-collector".into(),
+            planet: "fulgora".to_string(),
+            ..Default::default()
+        }));
+    }
+    mechanics
+}
+
+/// 目标预设的管理窗口，修改 targets 时设置 changed
+pub struct TargetPresetModal<'a> {
+    ctx: &'a FactorioContext,
+    targets: &'a mut Vec<(GenericItem, f64)>,
+    changed: Option<&'a mut bool>,
+}
+
+impl<'a> TargetPresetModal<'a> {
+    pub fn new(ctx: &'a FactorioContext, targets: &'a mut Vec<(GenericItem, f64)>) -> Self {
+        Self {
+            ctx,
+            targets,
+            changed: None,
+        }
+    }
+
+    pub fn notify_change(mut self, changed: &'a mut bool) -> Self {
+        self.changed = Some(changed);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct NewPresetName(String);
+
+impl egui::Widget for TargetPresetModal<'_> {
+    fn ui(mut self, ui: &mut egui::Ui) -> egui::Response {
+        let button = ui.button("目标预设……");
+        let id = button.id;
+        show_modal(id, button.clicked(), ui, |ui| {
+            ui.heading("目标预设");
+            let mut library = TARGET_PRESETS.lock().unwrap();
+            let mut library_changed = false;
+            let mut removed = None;
+            for (idx, preset) in library.presets.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "{}（{} 个目标）",
+                        preset.name,
+                        preset.targets.len()
+                    ));
+                    let mut apply = |append: bool| {
+                        let (targets, skipped) = preset.available_targets(self.ctx);
+                        if !append {
+                            self.targets.clear();
+                        }
+                        self.targets.extend(targets);
+                        if skipped > 0 {
+                            crate::toast::info(format!(
+                                "跳过了 {} 个当前上下文中不存在的物品",
+                                skipped
+                            ));
+                        }
+                        if let Some(changed) = &mut self.changed {
+                            **changed = true;
+                        }
+                    };
+                    if ui.button("替换").clicked() {
+                        apply(false);
+                    }
+                    if ui.button("追加").clicked() {
+                        apply(true);
+                    }
+                    if ui.button("删除").clicked() {
+                        removed = Some(idx);
+                    }
+                });
+            }
+            if let Some(idx) = removed {
+                library.presets.remove(idx);
+                library_changed = true;
+            }
+            ui.separator();
+            let mut name = ui
+                .memory(|mem| mem.data.get_temp::<NewPresetName>(id))
+                .unwrap_or_default()
+                .0;
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut name).hint_text("预设名称"));
+                if ui
+                    .add_enabled(
+                        !name.is_empty() && !self.targets.is_empty(),
+                        egui::Button::new("将当前目标保存为预设"),
+                    )
+                    .clicked()
+                {
+                    library.presets.retain(|preset| preset.name != name);
+                    library.presets.push(TargetPreset {
+                        name: std::mem::take(&mut name),
+                        targets: self.targets.clone(),
+                    });
+                    library_changed = true;
+                }
+            });
+            ui.memory_mut(|mem| mem.data.insert_temp(id, NewPresetName(name)));
+            if library_changed && let Err(err) = library.save() {
+                crate::toast::error(format!("保存目标预设失败: {:?}", err));
+            }
+        });
+        button
+    }
+}
+
+#[test]
+fn test_oil_balancer() {
+    use crate::solver::{ByproductPolicy, SolverData};
+    use indexmap::IndexMap;
+
+    let ctx = FactorioContext::test_load();
+    let mechanics = oil_balancer_mechanics(&ctx);
+    assert_eq!(mechanics.len(), OIL_BALANCER_RECIPES.len());
+    let fluid = |name: &str| GenericItem::Fluid {
+        name: name.to_string(),
+        temperature: None,
+    };
+    let flows = mechanics
+        .iter()
+        .enumerate()
+        .map(|(idx, mechanic)| (idx, (mechanic.as_flow(&ctx), 1.0)))
+        .collect::<IndexMap<_, _>>();
+    let policies = OIL_BALANCED_FLUIDS
+        .iter()
+        .map(|name| (fluid(name), ByproductPolicy::Zero))
+        .collect();
+    let (solution, _) = SolverData::new(
+        IndexMap::from([(fluid("petroleum-gas"), 10.0)]),
+        flows.clone(),
+    )
+    .with_byproduct_policies(policies)
+    .solve()
+    .unwrap();
+    // 重油与轻油全部裂解为石油气
+    for name in OIL_BALANCED_FLUIDS {
+        let balance: f64 = flows
+            .iter()
+            .map(|(idx, (flow, _))| flow.get(&fluid(name)).unwrap_or(&0.0) * solution[idx])
+            .sum();
+        assert!(balance.abs() < 1e-6, "{}: {}", name, balance);
+    }
+    assert!(solution.values().all(|count| *count > 0.0));
+}
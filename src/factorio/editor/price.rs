@@ -0,0 +1,408 @@
+use crate::{
+    concept::*,
+    dyn_serde::save_to_file,
+    error::AppError,
+    factorio::{
+        editor::{icon::GenericIcon, modal::*},
+        model::{FactorioContext, GenericItem, get_workding_directory},
+        style::card_frame,
+    },
+};
+
+lazy_static::lazy_static! {
+    pub static ref PRICE_TABLES: std::sync::Mutex<PriceTableLibrary> =
+        std::sync::Mutex::new(PriceTableLibrary::load());
+}
+
+/// 一张命名的价格表，为额外输入统一指定单位价值
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PriceTable {
+    pub name: String,
+    pub prices: Vec<(GenericItem, f64)>,
+}
+
+impl PriceTable {
+    /// 表中该物品的单位价值，重复列出时以最后一项为准
+    pub fn price_of(&self, item: &GenericItem) -> Option<f64> {
+        self.prices
+            .iter()
+            .rev()
+            .find(|(priced, _)| priced == item)
+            .map(|(_, price)| *price)
+    }
+
+    pub fn load_from(path: &std::path::Path) -> Result<Self, AppError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save_to(&self, path: &std::path::Path) -> Result<(), AppError> {
+        save_to_file(self, path)
+    }
+}
+
+/// 保存在工作目录下、所有工厂共用的价格表
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PriceTableLibrary {
+    pub tables: Vec<PriceTable>,
+}
+
+impl Default for PriceTableLibrary {
+    fn default() -> Self {
+        let item = |name: &str, price: f64| (GenericItem::Item(name.into()), price);
+        let fluid = |name: &str, price: f64| {
+            (
+                GenericItem::Fluid {
+                    name: name.to_string(),
+                    temperature: None,
+                },
+                price,
+            )
+        };
+        Self {
+            tables: vec![
+                PriceTable {
+                    name: "原版矿物权重".to_string(),
+                    prices: vec![
+                        item("iron-ore", 1.0),
+                        item("copper-ore", 1.0),
+                        item("coal", 1.0),
+                        item("stone", 1.0),
+                        item("uranium-ore", 5.0),
+                        fluid("crude-oil", 0.1),
+                        fluid("water", 0.01),
+                    ],
+                },
+                PriceTable {
+                    name: "Fulgora 废料经济".to_string(),
+                    prices: vec![item("scrap", 1.0), fluid("heavy-oil", 0.05)],
+                },
+            ],
+        }
+    }
+}
+
+impl PriceTableLibrary {
+    pub fn path() -> std::path::PathBuf {
+        get_workding_directory().join("price-tables.json")
+    }
+
+    /// 读取价格表文件，文件不存在时使用内置价格表
+    pub fn load() -> Self {
+        let path = Self::path();
+        if !path.exists() {
+            return Self::default();
+        }
+        match std::fs::read_to_string(&path)
+            .map_err(AppError::from)
+            .and_then(|content| serde_json::from_str(&content).map_err(AppError::from))
+        {
+            Ok(library) => library,
+            Err(err) => {
+                log::error!("读取价格表文件 {} 失败: {:?}", path.display(), err);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), AppError> {
+        save_to_file(self, &Self::path())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PriceTable> {
+        self.tables.iter().find(|table| table.name == name)
+    }
+
+    /// 不与已有价格表重名的名称，重名时追加序号
+    pub fn unique_name(&self, name: &str) -> String {
+        let mut candidate = name.to_string();
+        let mut index = 2;
+        while self.get(&candidate).is_some() {
+            candidate = format!("{} ({})", name, index);
+            index += 1;
+        }
+        candidate
+    }
+
+    /// 加入价格表，重名时自动改名，返回实际使用的名称
+    pub fn insert(&mut self, mut table: PriceTable) -> String {
+        table.name = self.unique_name(&table.name);
+        let name = table.name.clone();
+        self.tables.push(table);
+        name
+    }
+}
+
+/// 价格表编辑页面，修改后立即保存到价格表文件
+pub struct PriceTableEditorView {
+    ctx: FactorioContext,
+    selected: usize,
+}
+
+impl PriceTableEditorView {
+    /// 打开编辑页面，selected 为默认选中的价格表名称
+    pub fn new(ctx: FactorioContext, selected: Option<&str>) -> Self {
+        let selected = selected
+            .and_then(|name| {
+                PRICE_TABLES
+                    .lock()
+                    .unwrap()
+                    .tables
+                    .iter()
+                    .position(|table| table.name == name)
+            })
+            .unwrap_or(0);
+        Self { ctx, selected }
+    }
+
+    fn table_list(&mut self, ui: &mut egui::Ui, library: &mut PriceTableLibrary) -> bool {
+        let mut changed = false;
+        for (idx, table) in library.tables.iter().enumerate() {
+            ui.selectable_value(
+                &mut self.selected,
+                idx,
+                format!("{}（{} 项）", table.name, table.prices.len()),
+            );
+        }
+        ui.separator();
+        if ui.button("新建价格表").clicked() {
+            let name = library.unique_name("新价格表");
+            library.tables.push(PriceTable {
+                name,
+                prices: Vec::new(),
+            });
+            self.selected = library.tables.len() - 1;
+            changed = true;
+        }
+        if ui
+            .add_enabled(
+                self.selected < library.tables.len(),
+                egui::Button::new("复制当前价格表"),
+            )
+            .clicked()
+        {
+            let copy = library.tables[self.selected].clone();
+            library.insert(copy);
+            self.selected = library.tables.len() - 1;
+            changed = true;
+        }
+        if ui.button("导入……").clicked()
+            && let Some(path) = crate::file_dialog::FileDialog::new()
+                .add_filter("价格表", &["json"])
+                .pick_file()
+        {
+            match PriceTable::load_from(&path) {
+                Ok(table) => {
+                    let name = library.insert(table);
+                    self.selected = library.tables.len() - 1;
+                    changed = true;
+                    crate::toast::success(format!("已导入价格表 {}", name));
+                }
+                Err(err) => crate::toast::error(format!("导入价格表失败: {:?}", err)),
+            }
+        }
+        changed
+    }
+
+    fn table_editor(&mut self, ui: &mut egui::Ui, table: &mut PriceTable) -> (bool, bool) {
+        let ctx = &self.ctx;
+        let mut changed = false;
+        let mut deleted = false;
+        ui.horizontal(|ui| {
+            ui.label("名称");
+            changed |= ui
+                .text_edit_singleline(&mut table.name)
+                .on_hover_text("引用旧名称的工厂需要重新选择价格表")
+                .changed();
+            if ui
+                .button("导出……")
+                .on_hover_text("导出为单独的文件，供其他存档或其他人导入")
+                .clicked()
+                && let Some(path) = crate::file_dialog::FileDialog::new()
+                    .add_filter("价格表", &["json"])
+                    .set_file_name(format!("{}.json", table.name).as_str())
+                    .save_file()
+            {
+                match table.save_to(&path) {
+                    Ok(()) => crate::toast::success(format!("价格表已导出到 {}", path.display())),
+                    Err(err) => crate::toast::error(format!("导出价格表失败: {:?}", err)),
+                }
+            }
+            if ui.button("删除价格表").clicked() {
+                deleted = true;
+            }
+        });
+        ui.separator();
+        let id = ui.id().with("price-table").with(self.selected);
+        let mut removed = None;
+        for (idx, (item, price)) in table.prices.iter_mut().enumerate() {
+            let row_id = id.with(idx);
+            card_frame(ui).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    let icon = ui
+                        .add_sized(crate::settings::icon_cell(), GenericIcon::new(ctx, item))
+                        .interact(egui::Sense::click())
+                        .on_hover_text("点击选择物品");
+                    egui::ComboBox::from_id_salt(row_id.with("kind"))
+                        .selected_text(match item {
+                            GenericItem::Fluid { .. } => "流体",
+                            GenericItem::Entity(..) => "实体",
+                            _ => "物品",
+                        })
+                        .show_ui(ui, |ui| {
+                            changed |= ui
+                                .selectable_value(
+                                    item,
+                                    GenericItem::Item("item-unknown".into()),
+                                    "物品",
+                                )
+                                .changed();
+                            changed |= ui
+                                .selectable_value(
+                                    item,
+                                    GenericItem::Fluid {
+                                        name: "fluid-unknown".to_string(),
+                                        temperature: None,
+                                    },
+                                    "流体",
+                                )
+                                .changed();
+                            changed |= ui
+                                .selectable_value(
+                                    item,
+                                    GenericItem::Entity("entity-unknown".into()),
+                                    "实体",
+                                )
+                                .changed();
+                        });
+                    match item {
+                        GenericItem::Item(item_with_quality) => {
+                            ui.add(
+                                ItemWithQualitySelectorModal::new(
+                                    row_id.with("item"),
+                                    ctx,
+                                    "选择物品",
+                                    "item",
+                                )
+                                .with_toggle(icon.clicked())
+                                .with_current(item_with_quality)
+                                .notify_change(&mut changed),
+                            );
+                        }
+                        GenericItem::Fluid { name, .. } => {
+                            ui.add(
+                                ItemSelectorModal::new(
+                                    row_id.with("fluid"),
+                                    ctx,
+                                    "选择流体",
+                                    "fluid",
+                                )
+                                .with_toggle(icon.clicked())
+                                .with_current(name)
+                                .notify_change(&mut changed),
+                            );
+                        }
+                        GenericItem::Entity(entity_with_quality) => {
+                            ui.add(
+                                ItemWithQualitySelectorModal::new(
+                                    row_id.with("entity"),
+                                    ctx,
+                                    "选择实体",
+                                    "entity",
+                                )
+                                .with_toggle(icon.clicked())
+                                .with_current(entity_with_quality)
+                                .notify_change(&mut changed),
+                            );
+                        }
+                        _ => {}
+                    }
+                    ui.label("单位价值");
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(price)
+                                .range(0.0..=f64::MAX)
+                                .speed(0.01)
+                                .suffix("·秒"),
+                        )
+                        .changed();
+                    if ui.button("删除").clicked() {
+                        removed = Some(idx);
+                    }
+                });
+            });
+        }
+        if let Some(idx) = removed {
+            table.prices.remove(idx);
+            changed = true;
+        }
+        if ui.button("添加物品").clicked() {
+            table
+                .prices
+                .push((GenericItem::Item("item-unknown".into()), 1.0));
+            changed = true;
+        }
+        (changed, deleted)
+    }
+}
+
+impl Subview for PriceTableEditorView {
+    fn view(&mut self, ui: &mut egui::Ui) {
+        ui.heading("价格表");
+        ui.label("为额外输入统一指定单位价值；工厂选用价格表后，表中列出的物品按表中价格计算，其余仍使用工厂中填写的单位价值");
+        ui.separator();
+        let mut library = PRICE_TABLES.lock().unwrap();
+        let mut changed = false;
+        ui.columns(2, |columns| {
+            egui::ScrollArea::vertical()
+                .id_salt("price-table-list")
+                .show(&mut columns[0], |ui| {
+                    changed |= self.table_list(ui, &mut library);
+                });
+            if self.selected < library.tables.len() {
+                egui::ScrollArea::vertical()
+                    .id_salt("price-table-rows")
+                    .show(&mut columns[1], |ui| {
+                        let (table_changed, deleted) =
+                            self.table_editor(ui, &mut library.tables[self.selected]);
+                        changed |= table_changed;
+                        if deleted {
+                            library.tables.remove(self.selected);
+                            self.selected = self.selected.saturating_sub(1);
+                            changed = true;
+                        }
+                    });
+            } else {
+                columns[1].label("没有价格表");
+            }
+        });
+        if changed && let Err(err) = library.save() {
+            crate::toast::error(format!("保存价格表失败: {:?}", err));
+        }
+    }
+
+    fn name(&self) -> String {
+        "价格表".to_string()
+    }
+
+    fn description(&self) -> String {
+        "编辑所有工厂共用的额外输入价格表".to_string()
+    }
+}
+
+#[test]
+fn test_price_table_library() {
+    let mut library = PriceTableLibrary::default();
+    let ores = library.get("原版矿物权重").unwrap().clone();
+    assert_eq!(
+        ores.price_of(&GenericItem::Item("uranium-ore".into())),
+        Some(5.0)
+    );
+    assert_eq!(ores.price_of(&GenericItem::Item("scrap".into())), None);
+    // 导入同名价格表时改名，不覆盖已有的表
+    assert_eq!(library.insert(ores.clone()), "原版矿物权重 (2)");
+    assert_eq!(library.insert(ores.clone()), "原版矿物权重 (3)");
+    let json = serde_json::to_string(&library).unwrap();
+    let restored: PriceTableLibrary = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.tables, library.tables);
+}
@@ -0,0 +1,290 @@
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        editor::{icon::GenericIcon, modal::ItemSelectorModal, planner::target_mode_name},
+        format::compact_number,
+        model::*,
+    },
+    i18n::{tr, trf},
+    settings::RateUnit,
+    solver::TargetMode,
+};
+
+/// 按品质展开的目标：物品在不低于最低品质的各个品质下的产量之和满足同一个总量，
+/// 各品质的分配由求解器决定，适合刷品质的规划
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct QualityTarget {
+    pub item: String,
+    pub min_quality: u8,
+    pub total: f64,
+    #[serde(default)]
+    pub mode: TargetMode,
+}
+
+impl QualityTarget {
+    pub fn new(item: String, min_quality: u8, total: f64) -> Self {
+        Self {
+            item,
+            min_quality,
+            total,
+            mode: TargetMode::Exact,
+        }
+    }
+
+    /// 求解时代表总量的虚拟物品，各品质经桥接机制转换为它
+    pub fn aggregate(&self) -> GenericItem {
+        GenericItem::Custom {
+            name: format!("quality-target:{}:{}", self.item, self.min_quality),
+        }
+    }
+
+    /// 计入总量的各品质物品
+    pub fn members(&self, ctx: &FactorioContext) -> Vec<GenericItem> {
+        (self.min_quality as usize..ctx.qualities.len())
+            .map(|quality| GenericItem::Item(IdWithQuality(self.item.clone(), quality as u8)))
+            .collect()
+    }
+}
+
+/// 桥接机制的标识符从该值向下分配，与温度桥接（从 u64::MAX 向下）错开
+const BRIDGE_ID_BASE: u64 = u64::MAX - (1 << 32);
+
+/// 将各品质物品一比一转换为总量物品的零成本机制。只桥接 flows 中有产出的品质，
+/// 否则无来源的物品不受平衡约束，桥接会凭空得到它
+pub fn quality_target_bridges<'a>(
+    targets: &[QualityTarget],
+    ctx: &FactorioContext,
+    flows: impl IntoIterator<Item = &'a Flow<GenericItem>>,
+) -> Vec<(MechanicId, Flow<GenericItem>)> {
+    let produced = flows
+        .into_iter()
+        .flat_map(|flow| flow.iter())
+        .filter(|(_, amount)| **amount > 0.0)
+        .map(|(item, _)| item)
+        .collect::<std::collections::HashSet<_>>();
+    targets
+        .iter()
+        .flat_map(|target| {
+            let aggregate = target.aggregate();
+            target
+                .members(ctx)
+                .into_iter()
+                .filter(|member| produced.contains(member))
+                .map(move |member| Flow::from([(member, -1.0), (aggregate.clone(), 1.0)]))
+        })
+        .enumerate()
+        .map(|(index, flow)| (MechanicId(BRIDGE_ID_BASE - index as u64), flow))
+        .collect()
+}
+
+/// 目标卡片上的删除与收起操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityTargetAction {
+    Delete,
+    /// 收起为最低品质的单个目标
+    Collapse,
+}
+
+/// 按品质展开的目标卡片，展开显示各品质的求解产量
+pub struct QualityTargetCard<'a> {
+    ctx: &'a FactorioContext,
+    target: &'a mut QualityTarget,
+    total_flow: &'a Flow<GenericItem>,
+    /// 各品质分配的显示单位，取工厂的速率单位
+    rate_unit: RateUnit,
+    /// 是否允许选择最大化，没有产量上限时禁用
+    can_maximize: bool,
+    changed: Option<&'a mut bool>,
+}
+
+impl<'a> QualityTargetCard<'a> {
+    pub fn new(
+        ctx: &'a FactorioContext,
+        target: &'a mut QualityTarget,
+        total_flow: &'a Flow<GenericItem>,
+    ) -> Self {
+        Self {
+            ctx,
+            target,
+            total_flow,
+            rate_unit: RateUnit::global(),
+            can_maximize: true,
+            changed: None,
+        }
+    }
+
+    pub fn with_rate_unit(mut self, rate_unit: RateUnit) -> Self {
+        self.rate_unit = rate_unit;
+        self
+    }
+
+    pub fn with_maximize(mut self, can_maximize: bool) -> Self {
+        self.can_maximize = can_maximize;
+        self
+    }
+
+    pub fn notify_change(mut self, changed: &'a mut bool) -> Self {
+        self.changed = Some(changed);
+        self
+    }
+
+    pub fn show(self, ui: &mut egui::Ui) -> Option<QualityTargetAction> {
+        let ctx = self.ctx;
+        let mut changed = false;
+        let mut action = None;
+        let quality_name = |quality: usize| {
+            ctx.qualities
+                .get(quality)
+                .map(|prototype| ctx.get_display_name("quality", &prototype.base.name))
+                .unwrap_or_default()
+        };
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                let base = GenericItem::Item(IdWithQuality(
+                    self.target.item.clone(),
+                    self.target.min_quality,
+                ));
+                let icon = ui
+                    .add_sized(crate::settings::icon_cell(), GenericIcon::new(ctx, &base))
+                    .interact(egui::Sense::click())
+                    .on_hover_text(tr("quality_target.pick_hint"));
+                ui.add(
+                    ItemSelectorModal::new(
+                        icon.id.with("quality-target-item"),
+                        ctx,
+                        tr("quality_target.pick_title"),
+                        "item",
+                    )
+                    .with_toggle(icon.clicked())
+                    .with_current(&mut self.target.item)
+                    .notify_change(&mut changed),
+                );
+                ui.vertical(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(tr("quality_target.min_quality"));
+                        egui::ComboBox::from_id_salt(icon.id.with("quality-target-min"))
+                            .selected_text(quality_name(self.target.min_quality as usize))
+                            .show_ui(ui, |ui| {
+                                for quality in 0..ctx.qualities.len() {
+                                    changed |= ui
+                                        .selectable_value(
+                                            &mut self.target.min_quality,
+                                            quality as u8,
+                                            quality_name(quality),
+                                        )
+                                        .changed();
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(tr("quality_target.total"));
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut self.target.total)
+                                    .range(0.0..=f64::MAX)
+                                    .suffix(tr("unit.per_second")),
+                            )
+                            .changed();
+                        egui::ComboBox::from_id_salt(icon.id.with("quality-target-mode"))
+                            .selected_text(target_mode_name(self.target.mode))
+                            .show_ui(ui, |ui| {
+                                for mode in TargetMode::ALL {
+                                    if ui
+                                        .add_enabled(
+                                            self.can_maximize || mode != TargetMode::Maximize,
+                                            egui::Button::selectable(
+                                                self.target.mode == mode,
+                                                target_mode_name(mode),
+                                            ),
+                                        )
+                                        .on_disabled_hover_text(tr("planner.maximize_unbounded"))
+                                        .clicked()
+                                        && self.target.mode != mode
+                                    {
+                                        self.target.mode = mode;
+                                        changed = true;
+                                    }
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button(tr("common.delete")).clicked() {
+                            action = Some(QualityTargetAction::Delete);
+                        }
+                        if ui
+                            .button(tr("quality_target.collapse"))
+                            .on_hover_text(tr("quality_target.collapse_hint"))
+                            .clicked()
+                        {
+                            action = Some(QualityTargetAction::Collapse);
+                        }
+                    });
+                });
+            });
+            // 各品质共享同一个总量，显示求解得到的分配
+            ui.indent(self.target.aggregate(), |ui| {
+                for member in self.target.members(ctx) {
+                    let GenericItem::Item(IdWithQuality(_, quality)) = &member else {
+                        continue;
+                    };
+                    let amount = self.total_flow.get(&member).copied().unwrap_or(0.0);
+                    ui.horizontal(|ui| {
+                        ui.add(GenericIcon::new(ctx, &member).with_size(16.0));
+                        let mut text = trf(
+                            "quality_target.share",
+                            &[
+                                &quality_name(*quality as usize),
+                                &compact_number(amount * self.rate_unit.multiplier()),
+                                &self.rate_unit.suffix(),
+                            ],
+                        );
+                        if self.target.total > 0.0 {
+                            text += &trf(
+                                "quality_target.share_percent",
+                                &[&format!("{:.0}", amount / self.target.total * 100.0)],
+                            );
+                        }
+                        ui.label(text);
+                    });
+                }
+            });
+        });
+        if changed && let Some(notify) = self.changed {
+            *notify = true;
+        }
+        action
+    }
+}
+
+#[test]
+fn test_quality_target_bridges() {
+    use crate::solver::SolverData;
+    use indexmap::IndexMap;
+
+    let ctx = FactorioContext::test_load();
+    let target = QualityTarget::new("iron-gear-wheel".to_string(), 2, 3.0);
+    let members = target.members(&ctx);
+    assert_eq!(members.len(), ctx.qualities.len() - 2);
+    // 两种来源分别提供不同品质，总量由两者共同满足
+    let rare = members[0].clone();
+    let epic = members[1].clone();
+    let mut flows = IndexMap::from([
+        (MechanicId(1), (Flow::from([(rare.clone(), 1.0)]), 1.0)),
+        (MechanicId(2), (Flow::from([(epic.clone(), 1.0)]), 2.0)),
+    ]);
+    let bridges = quality_target_bridges(
+        std::slice::from_ref(&target),
+        &ctx,
+        flows.values().map(|(flow, _)| flow),
+    );
+    // 没有来源的更高品质不桥接
+    assert_eq!(bridges.len(), 2);
+    flows.extend(bridges.into_iter().map(|(id, flow)| (id, (flow, 0.0))));
+    let (solution, _) = SolverData::new(IndexMap::from([(target.aggregate(), 3.0)]), flows)
+        .solve()
+        .unwrap();
+    // 较便宜的稀有品质承担全部总量
+    assert!((solution[&MechanicId(1)] - 3.0).abs() < 1e-6);
+    assert!(solution[&MechanicId(2)].abs() < 1e-6);
+}
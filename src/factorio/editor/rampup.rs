@@ -0,0 +1,495 @@
+use std::{
+    any::Any,
+    collections::{HashSet, VecDeque},
+};
+
+use indexmap::IndexMap;
+
+use crate::{
+    concept::*,
+    factorio::{common::*, editor::planner::FactoryInstance, format::*, model::*},
+};
+
+/// 一次估算最多模拟的时长（秒）
+const MAX_SIMULATED_TIME: f64 = 7.0 * 24.0 * 3600.0;
+/// 一次估算最多推进的步数，超出时加大步长
+const MAX_STEPS: f64 = 50_000.0;
+/// 时间线最多保留的采样点数
+const TIMELINE_SAMPLES: usize = 200;
+
+/// 方案中的一组机器：每台的物料流、求解得到的台数，以及完成一次制作的延迟
+#[derive(Debug, Clone)]
+pub struct RampStage<I> {
+    pub flow: Flow<I>,
+    pub count: f64,
+    /// 原料投入后经过该秒数才得到产物，没有制作周期的机制为 0
+    pub latency: f64,
+}
+
+/// 生产一次性数量的估算结果
+#[derive(Debug, Clone)]
+pub struct RampEstimate<I> {
+    /// 达到目标数量所需的秒数，超出模拟时长时为 None
+    pub time: Option<f64>,
+    /// 按稳态速率计算、不考虑爬坡的秒数
+    pub steady_time: Option<f64>,
+    /// 目标物品库存随时间的变化
+    pub timeline: Vec<(f64, f64)>,
+    /// 各中间产物第一次产出的时间
+    pub first_output: IndexMap<I, f64>,
+}
+
+/// 按依赖排序：产出某物品的机器排在消耗它的机器之前，环中的机器保持原顺序
+fn stage_order<I: ItemIdent>(stages: &[RampStage<I>]) -> Vec<usize> {
+    let mut order = Vec::with_capacity(stages.len());
+    let mut placed = vec![false; stages.len()];
+    while order.len() < stages.len() {
+        let before = order.len();
+        for (idx, stage) in stages.iter().enumerate() {
+            if placed[idx] {
+                continue;
+            }
+            let waiting = stage.flow.iter().any(|(item, amount)| {
+                *amount < 0.0
+                    && stages.iter().enumerate().any(|(other, producer)| {
+                        other != idx
+                            && !placed[other]
+                            && producer.flow.get(item).is_some_and(|amount| *amount > 0.0)
+                    })
+            });
+            if !waiting {
+                placed[idx] = true;
+                order.push(idx);
+            }
+        }
+        if order.len() == before
+            && let Some(idx) = placed.iter().position(|placed| !placed)
+        {
+            placed[idx] = true;
+            order.push(idx);
+        }
+    }
+    order
+}
+
+/// 一批制作完成，产物计入库存
+fn release<I: ItemIdent>(
+    stage: &RampStage<I>,
+    crafts: f64,
+    time: f64,
+    stock: &mut Flow<I>,
+    estimate: &mut RampEstimate<I>,
+    target: &I,
+) {
+    for (item, amount) in &stage.flow {
+        if *amount > 0.0 {
+            *stock.entry(item.clone()).or_insert(0.0) += amount * crafts;
+            if crafts > 0.0 && item != target {
+                estimate.first_output.entry(item.clone()).or_insert(time);
+            }
+        }
+    }
+}
+
+/// 从给定库存开始模拟方案的运行，直到目标物品的库存达到 quantity。
+/// 方案中没有机器产出的物品视为无限供应；有机器产出的物品只能使用库存，
+/// 因此下游机器要等上游的第一批产物送达后才能开工，由此得到爬坡所需的时间。
+pub fn simulate_ramp_up<I: ItemIdent>(
+    stages: &[RampStage<I>],
+    initial_stock: &Flow<I>,
+    target: &I,
+    quantity: f64,
+) -> RampEstimate<I> {
+    let produced = stages
+        .iter()
+        .flat_map(|stage| stage.flow.iter())
+        .filter(|(_, amount)| **amount > 0.0)
+        .map(|(item, _)| item.clone())
+        .collect::<HashSet<_>>();
+    let mut stock = initial_stock.clone();
+    let initial = stock.get(target).cloned().unwrap_or(0.0);
+    let steady_rate = stages
+        .iter()
+        .map(|stage| stage.flow.get(target).cloned().unwrap_or(0.0) * stage.count)
+        .sum::<f64>();
+    let steady_time = (steady_rate > 1e-12).then(|| ((quantity - initial) / steady_rate).max(0.0));
+    let mut estimate = RampEstimate {
+        time: None,
+        steady_time,
+        timeline: vec![(0.0, initial)],
+        first_output: IndexMap::new(),
+    };
+    if initial >= quantity {
+        estimate.time = Some(0.0);
+        return estimate;
+    }
+    let Some(steady_time) = steady_time else {
+        return estimate;
+    };
+
+    let total_latency = stages.iter().map(|stage| stage.latency).sum::<f64>();
+    let horizon = (steady_time * 4.0 + total_latency * 2.0).min(MAX_SIMULATED_TIME);
+    let min_latency = stages
+        .iter()
+        .map(|stage| stage.latency)
+        .filter(|latency| *latency > 0.0)
+        .fold(f64::INFINITY, f64::min);
+    let dt = if min_latency.is_finite() {
+        (min_latency / 4.0).max(horizon / MAX_STEPS)
+    } else {
+        horizon / MAX_STEPS
+    };
+    let sample_every = ((horizon / dt) as usize / TIMELINE_SAMPLES).max(1);
+    let order = stage_order(stages);
+    // 各机器已投入原料、尚未产出的批次：（产出时间，制作次数）
+    let mut pending = vec![VecDeque::<(f64, f64)>::new(); stages.len()];
+
+    let mut time = 0.0;
+    let mut step = 0usize;
+    while time <= horizon {
+        for &idx in &order {
+            while let Some(&(at, crafts)) = pending[idx].front()
+                && at <= time + 1e-9
+            {
+                pending[idx].pop_front();
+                release(&stages[idx], crafts, at, &mut stock, &mut estimate, target);
+            }
+        }
+        let current = stock.get(target).cloned().unwrap_or(0.0);
+        if step.is_multiple_of(sample_every) {
+            estimate.timeline.push((time, current));
+        }
+        if current >= quantity - 1e-9 {
+            estimate.timeline.push((time, current));
+            estimate.time = Some(time);
+            break;
+        }
+        for &idx in &order {
+            let stage = &stages[idx];
+            let desired = stage.count * dt;
+            if desired <= 0.0 {
+                continue;
+            }
+            let fraction = stage
+                .flow
+                .iter()
+                .filter(|(item, amount)| **amount < 0.0 && produced.contains(*item))
+                .map(|(item, amount)| {
+                    let available = stock.get(item).cloned().unwrap_or(0.0).max(0.0);
+                    available / (-amount * desired)
+                })
+                .fold(1.0_f64, f64::min);
+            if fraction <= 0.0 {
+                continue;
+            }
+            let crafts = desired * fraction;
+            for (item, amount) in &stage.flow {
+                if *amount < 0.0 && produced.contains(item) {
+                    *stock.entry(item.clone()).or_insert(0.0) += amount * crafts;
+                }
+            }
+            if stage.latency > 0.0 {
+                pending[idx].push_back((time + stage.latency, crafts));
+            } else {
+                release(stage, crafts, time, &mut stock, &mut estimate, target);
+            }
+        }
+        time += dt;
+        step += 1;
+    }
+    estimate
+}
+
+/// 按工厂当前的求解结果整理模拟所需的数据
+pub fn ramp_stages(
+    factory: &FactoryInstance,
+    ctx: &FactorioContext,
+) -> Vec<RampStage<GenericItem>> {
+    factory
+        .mechanics
+        .iter()
+        .filter_map(|(id, mechanic)| {
+            let count = factory.solution.0.get(id).cloned().unwrap_or(0.0);
+            if count <= 1e-9 {
+                return None;
+            }
+            let latency = (mechanic.as_ref() as &dyn Any)
+                .downcast_ref::<RecipeConfig>()
+                .and_then(|recipe| recipe.cycle_time(ctx))
+                .unwrap_or(0.0);
+            Some(RampStage {
+                flow: factory.mechanic_flow(mechanic.as_ref(), ctx),
+                count,
+                latency,
+            })
+        })
+        .collect()
+}
+
+/// 爬坡估算视图：填写现有库存与一次性需求，估算所需时间
+pub struct RampUpView {
+    pub factory_name: String,
+    pub stages: Vec<RampStage<GenericItem>>,
+    /// 方案中有机器产出的物品，可作为目标并填写库存
+    pub items: Vec<GenericItem>,
+    pub names: IndexMap<GenericItem, String>,
+    pub target: usize,
+    pub quantity: f64,
+    pub stock: Flow<GenericItem>,
+    pub estimate: Option<RampEstimate<GenericItem>>,
+}
+
+impl RampUpView {
+    pub fn new(factory: &FactoryInstance, ctx: &FactorioContext) -> Self {
+        let stages = ramp_stages(factory, ctx);
+        let mut items = vec![];
+        for stage in &stages {
+            for (item, amount) in &stage.flow {
+                if *amount > 0.0 && !items.contains(item) {
+                    items.push(item.clone());
+                }
+            }
+        }
+        sort_generic_items_owned(&mut items, ctx);
+        let names = items
+            .iter()
+            .map(|item| (item.clone(), ctx.get_generic_item_display_name(item)))
+            .collect();
+        let target = factory
+            .target
+            .iter()
+            .find_map(|(item, _)| items.iter().position(|candidate| candidate == item))
+            .unwrap_or(0);
+        Self {
+            factory_name: factory.name.clone(),
+            stages,
+            items,
+            names,
+            target,
+            quantity: 1000.0,
+            stock: Flow::new(),
+            estimate: None,
+        }
+    }
+
+    fn name_of(&self, item: &GenericItem) -> String {
+        self.names
+            .get(item)
+            .cloned()
+            .unwrap_or_else(|| format!("{:?}", item))
+    }
+}
+
+/// 目标物品库存随时间变化的折线图
+fn timeline_chart(ui: &mut egui::Ui, timeline: &[(f64, f64)], quantity: f64) {
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(ui.available_width(), 200.0),
+        egui::Sense::hover(),
+    );
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    painter.rect_stroke(
+        rect,
+        4.0,
+        visuals.widgets.noninteractive.bg_stroke,
+        egui::StrokeKind::Inside,
+    );
+    let max_time = timeline.last().map_or(1.0, |(time, _)| *time).max(1e-6);
+    let max_value = timeline
+        .iter()
+        .map(|(_, value)| *value)
+        .fold(quantity, f64::max)
+        .max(1e-6);
+    let to_screen = |time: f64, value: f64| {
+        egui::pos2(
+            rect.left() + (time / max_time) as f32 * rect.width(),
+            rect.bottom() - (value / max_value) as f32 * rect.height(),
+        )
+    };
+    painter.hline(
+        rect.x_range(),
+        to_screen(0.0, quantity).y,
+        egui::Stroke::new(1.0, visuals.warn_fg_color),
+    );
+    let points = timeline
+        .iter()
+        .map(|(time, value)| to_screen(*time, *value))
+        .collect::<Vec<_>>();
+    painter.add(egui::Shape::line(
+        points,
+        egui::Stroke::new(2.0, visuals.selection.bg_fill),
+    ));
+    if let Some(pos) = response.hover_pos() {
+        let time = (pos.x - rect.left()) as f64 / rect.width() as f64 * max_time;
+        let value = timeline
+            .iter()
+            .take_while(|(sample, _)| *sample <= time)
+            .last()
+            .map_or(0.0, |(_, value)| *value);
+        response.on_hover_text(format!(
+            "{}：{}",
+            format_duration(time),
+            compact_number(value)
+        ));
+    }
+}
+
+impl Subview for RampUpView {
+    fn view(&mut self, ui: &mut egui::Ui) {
+        ui.heading(format!("爬坡估算：{}", self.factory_name));
+        ui.label("按当前方案的机器数量，从现有库存开始生产一次性数量所需的时间；方案中没有产出的原料视为无限供应");
+        ui.separator();
+        if self.items.is_empty() {
+            ui.label("方案尚未求解或没有任何产出");
+            return;
+        }
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("目标");
+                egui::ComboBox::from_id_salt("ramp-up-target")
+                    .selected_text(self.name_of(&self.items[self.target]))
+                    .show_ui(ui, |ui| {
+                        for (idx, item) in self.items.iter().enumerate() {
+                            let name = self
+                                .names
+                                .get(item)
+                                .cloned()
+                                .unwrap_or_else(|| format!("{:?}", item));
+                            ui.selectable_value(&mut self.target, idx, name);
+                        }
+                    });
+                ui.label("数量");
+                ui.add(
+                    egui::DragValue::new(&mut self.quantity)
+                        .range(0.0..=f64::INFINITY)
+                        .speed(10.0),
+                );
+                if ui.button("估算").clicked() {
+                    self.estimate = Some(simulate_ramp_up(
+                        &self.stages,
+                        &self.stock,
+                        &self.items[self.target],
+                        self.quantity,
+                    ));
+                }
+            });
+            egui::CollapsingHeader::new("现有库存")
+                .id_salt("ramp-up-stock")
+                .show(ui, |ui| {
+                    egui::Grid::new("ramp-up-stock-grid")
+                        .striped(true)
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            for item in &self.items {
+                                ui.label(self.names.get(item).cloned().unwrap_or_default());
+                                let amount = self.stock.entry(item.clone()).or_insert(0.0);
+                                ui.add(
+                                    egui::DragValue::new(amount)
+                                        .range(0.0..=f64::INFINITY)
+                                        .speed(1.0),
+                                );
+                                ui.end_row();
+                            }
+                        });
+                });
+            let Some(estimate) = &self.estimate else {
+                return;
+            };
+            ui.separator();
+            match (estimate.time, estimate.steady_time) {
+                (_, None) => {
+                    ui.label("方案没有净产出该物品，无法估算");
+                }
+                (None, Some(_)) => {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "在模拟时长内未能达到目标数量，可能有中间产物需要初始库存才能启动",
+                    );
+                }
+                (Some(time), Some(steady_time)) => {
+                    ui.strong(format!("预计用时 {}", format_duration(time)));
+                    ui.label(format!(
+                        "其中稳态生产 {}，爬坡额外用时 {}",
+                        format_duration(steady_time),
+                        format_duration(time - steady_time)
+                    ));
+                }
+            }
+            timeline_chart(ui, &estimate.timeline, self.quantity);
+            if !estimate.first_output.is_empty() {
+                ui.heading("中间产物首次产出");
+                egui::Grid::new("ramp-up-first-output")
+                    .striped(true)
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        let mut outputs = estimate.first_output.iter().collect::<Vec<_>>();
+                        outputs.sort_by(|a, b| a.1.total_cmp(b.1));
+                        for (item, time) in outputs {
+                            ui.label(self.name_of(item));
+                            ui.label(format_duration(*time));
+                            ui.end_row();
+                        }
+                    });
+            }
+        });
+    }
+
+    fn name(&self) -> String {
+        format!("爬坡估算 - {}", self.factory_name)
+    }
+
+    fn description(&self) -> String {
+        "从现有库存生产一次性数量所需的时间".to_string()
+    }
+}
+
+#[test]
+fn test_ramp_up() {
+    // 矿石 → 板（2 秒）→ 齿轮（1 秒），稳态每秒 1 个齿轮
+    let stages = vec![
+        RampStage {
+            flow: IndexMap::from([("plate", -2.0), ("gear", 1.0)]),
+            count: 1.0,
+            latency: 1.0,
+        },
+        RampStage {
+            flow: IndexMap::from([("ore", -1.0), ("plate", 1.0)]),
+            count: 2.0,
+            latency: 2.0,
+        },
+    ];
+    let estimate = simulate_ramp_up(&stages, &Flow::new(), &"gear", 100.0);
+    assert_eq!(estimate.steady_time, Some(100.0));
+    let time = estimate.time.unwrap();
+    // 两级延迟合计 3 秒
+    assert!((time - 103.0).abs() < 0.5, "{time}");
+    assert!((estimate.first_output["plate"] - 2.0).abs() < 1e-6);
+
+    // 已有的板可以立即开工，只剩齿轮一级的延迟
+    let stock = IndexMap::from([("plate", 1000.0)]);
+    let time = simulate_ramp_up(&stages, &stock, &"gear", 100.0)
+        .time
+        .unwrap();
+    assert!((time - 101.0).abs() < 0.5, "{time}");
+
+    // 库存已经足够
+    let stock = IndexMap::from([("gear", 100.0)]);
+    assert_eq!(
+        simulate_ramp_up(&stages, &stock, &"gear", 100.0).time,
+        Some(0.0)
+    );
+}
+
+#[test]
+fn test_recipe_cycle_time() {
+    let ctx = FactorioContext::test_load();
+    let config = RecipeConfig {
+        recipe: "iron-gear-wheel".into(),
+        machine: "assembling-machine-2".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+    };
+    // 0.5 秒的配方，二级组装机速度 0.75
+    let cycle = config.cycle_time(&ctx).unwrap();
+    assert!((cycle - 0.5 / 0.75).abs() < 1e-9, "{cycle}");
+}
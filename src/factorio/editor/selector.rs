@@ -1,17 +1,30 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use egui::Vec2;
 
-use crate::factorio::{IdWithQuality, editor::icon::*, model::*};
+use crate::{
+    factorio::{IdWithQuality, editor::icon::*, editor::usage::FactoryUsage, model::*},
+    settings::{RateUnit, Settings},
+};
+
+/// 搜索结果最多显示的行数
+const MAX_SEARCH_ROWS: usize = 6;
 
 #[derive(Debug, Clone, Default)]
 pub struct ItemSelectorStorage {
     pub group: usize,
     pub subgroup: usize,
+    /// 搜索框的内容，非空时以搜索结果代替分组
+    pub query: String,
 }
 
 pub type FilterFn<'a> = dyn Fn(&str, &FactorioContext) -> bool + 'a;
 pub type HoverUi<'a> = dyn Fn(&mut egui::Ui, &str, &FactorioContext) + 'a;
+/// 在图标右下角显示的角标文字，返回 None 时不显示
+pub type BadgeFn<'a> = dyn Fn(&str, &FactorioContext) -> Option<String> + 'a;
 
 pub struct ItemSelector<'a> {
     ctx: &'a FactorioContext,
@@ -20,7 +33,10 @@ pub struct ItemSelector<'a> {
     current: Option<&'a mut String>,
     output: Option<&'a mut Option<String>>,
     hover: Option<Box<HoverUi<'a>>>,
+    badge: Option<Box<BadgeFn<'a>>>,
     changed: Option<&'a mut bool>,
+    /// 正在编辑的工厂中已使用的条目，绘制时从界面状态中取出
+    usage: Option<Arc<FactoryUsage>>,
 }
 
 impl<'a> ItemSelector<'a> {
@@ -32,7 +48,9 @@ impl<'a> ItemSelector<'a> {
             current: None,
             output: None,
             hover: None,
+            badge: None,
             changed: None,
+            usage: None,
         }
     }
 
@@ -71,14 +89,106 @@ impl<'a> ItemSelector<'a> {
         self
     }
 
+    pub fn with_badge(
+        mut self,
+        badge: impl Fn(&str, &FactorioContext) -> Option<String> + 'a,
+    ) -> Self {
+        self.badge = Some(Box::new(badge));
+        self
+    }
+
     pub fn notify_change(mut self, changed: &'a mut bool) -> Self {
         self.changed = Some(changed);
         self
     }
 }
 
+impl ItemSelector<'_> {
+    /// 绘制单个条目的图标，左键点击时返回 true，右键点击时记录到 toggled 以切换收藏
+    fn item_button(
+        &self,
+        ui: &mut egui::Ui,
+        item_name: &str,
+        settings: &Settings,
+        toggled: &mut Option<String>,
+    ) -> bool {
+        // 条目数以千计时，滚动区域外的图标只占位，不加载纹理也不绘制
+        let size = Vec2::splat(32.0);
+        if !ui.is_rect_visible(egui::Rect::from_min_size(ui.cursor().min, size)) {
+            ui.allocate_space(size);
+            return false;
+        }
+        let mut button = ui
+            .add(Icon::new(self.ctx, self.item_type, item_name).with_size(32.0))
+            .interact(egui::Sense::click());
+        let usage = self.usage.as_deref();
+        if let Some(heat) = usage.and_then(|usage| usage.heat(self.item_type, item_name)) {
+            paint_usage(ui, button.rect, heat);
+        }
+        if let Some(badge) = &self.badge
+            && let Some(text) = badge(item_name, self.ctx)
+        {
+            paint_badge(ui, button.rect, &text);
+        }
+        if settings.is_favorite(self.item_type, item_name) {
+            paint_favorite(ui, button.rect);
+        }
+        let usage_text =
+            usage.and_then(|usage| usage.describe(self.item_type, item_name, RateUnit::global()));
+        if self.hover.is_some() || usage_text.is_some() {
+            button = button.on_hover_ui(|ui| {
+                if let Some(hover) = &self.hover {
+                    (hover)(ui, item_name, self.ctx);
+                } else {
+                    ui.label(self.ctx.get_display_name(self.item_type, item_name));
+                }
+                if let Some(text) = &usage_text {
+                    ui.separator();
+                    ui.label(text);
+                }
+            });
+        } else {
+            button = button.on_hover_text(self.ctx.get_display_name(self.item_type, item_name));
+        }
+        if button.secondary_clicked() {
+            *toggled = Some(item_name.to_string());
+        }
+        button.clicked()
+    }
+
+    /// 按固定列数排列一组条目，用于收藏、最近使用与搜索结果，返回左键点击的条目
+    fn item_grid(
+        &self,
+        ui: &mut egui::Ui,
+        id_salt: &str,
+        names: &[&str],
+        item_count: usize,
+        settings: &Settings,
+        toggled: &mut Option<String>,
+    ) -> Option<String> {
+        let mut clicked = None;
+        egui::Grid::new(id_salt)
+            .max_col_width(crate::settings::icon_cell()[0])
+            .min_col_width(crate::settings::icon_cell()[0])
+            .min_row_height(crate::settings::icon_cell()[1])
+            .spacing(Vec2 { x: 0.0, y: 0.0 })
+            .show(ui, |ui| {
+                for (idx, item_name) in names.iter().enumerate() {
+                    if (idx % item_count) == 0 && idx != 0 {
+                        ui.end_row();
+                    }
+                    if self.item_button(ui, item_name, settings, toggled) {
+                        clicked = Some(item_name.to_string());
+                    }
+                }
+            });
+        clicked
+    }
+}
+
 impl egui::Widget for ItemSelector<'_> {
-    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+    fn ui(mut self, ui: &mut egui::Ui) -> egui::Response {
+        self.usage = FactoryUsage::current(ui.ctx());
         let available_space = ui.available_size();
         let group_count = (available_space.x as usize / 70).max(4);
         let item_count = (available_space.x as usize / 35).max(8);
@@ -109,88 +219,185 @@ impl egui::Widget for ItemSelector<'_> {
             return ui.response().clone();
         }
         let order_info = &self.ctx.ordered_entries[self.item_type];
-        egui::Grid::new("group")
-            .min_row_height(64.0)
-            .min_col_width(64.0)
-            .max_col_width(64.0)
-            .spacing(Vec2 { x: 6.0, y: 6.0 })
-            .show(ui, |ui| {
-                let mut idx = 0;
-                for (i, group) in self.ctx.ordered_entries[self.item_type].iter().enumerate() {
-                    if (idx % group_count) == 0 && idx != 0 {
-                        ui.end_row();
-                    }
-                    let group_name = if group.0.is_empty() {
-                        "other".to_string()
-                    } else {
-                        group.0.clone()
-                    };
-                    if !filtered_group.contains_key(&i) {
-                        continue;
-                    }
-                    idx += 1;
-                    if ui
-                        .add(Icon::new(self.ctx, "item-group", &group_name).with_size(64.0))
-                        .interact(egui::Sense::click())
-                        .clicked()
-                    {
-                        storage.group = i;
-                        storage.subgroup = 0;
+        let settings = crate::settings::get();
+        let mut clicked = None;
+        let mut toggled = None;
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut storage.query)
+                    .hint_text("搜索")
+                    .desired_width(200.0),
+            )
+            .on_hover_text("按显示名或内部名模糊搜索，右键点击图标可收藏");
+            if !storage.query.is_empty() && ui.small_button("✖").clicked() {
+                storage.query.clear();
+            }
+            if let Some(usage) = &self.usage {
+                let (used, total) = order_info
+                    .iter()
+                    .flat_map(|group| group.1.iter())
+                    .flat_map(|subgroup| subgroup.1.iter())
+                    .filter(|name| (self.filter)(name, self.ctx))
+                    .fold((0, 0), |(used, total), name| {
+                        let used = used + usage.get(self.item_type, name).is_some() as usize;
+                        (used, total + 1)
+                    });
+                ui.weak(format!("已使用 {}/{}", used, total))
+                    .on_hover_text("当前工厂中使用过的条目，颜色越深引用的机制越多");
+            }
+        });
+
+        // 收藏与最近使用可能来自其他模组的上下文，只显示当前存在且满足条件的条目
+        let known = if settings.favorites.is_empty() && settings.recents.is_empty() {
+            HashSet::new()
+        } else {
+            order_info
+                .iter()
+                .flat_map(|group| group.1.iter())
+                .flat_map(|subgroup| subgroup.1.iter())
+                .map(String::as_str)
+                .collect::<HashSet<_>>()
+        };
+        let visible = |name: &&str| known.contains(name) && (self.filter)(name, self.ctx);
+        let favorites = settings
+            .favorites_of(self.item_type)
+            .filter(visible)
+            .collect::<Vec<_>>();
+        let recents = settings
+            .recents_of(self.item_type)
+            .filter(visible)
+            .collect::<Vec<_>>();
+        for (label, names, id_salt) in [
+            ("收藏", &favorites, "favorites"),
+            ("最近", &recents, "recents"),
+        ] {
+            if names.is_empty() {
+                continue;
+            }
+            ui.horizontal(|ui| {
+                ui.label(label);
+                clicked = self
+                    .item_grid(
+                        ui,
+                        id_salt,
+                        names,
+                        item_count.saturating_sub(2).max(4),
+                        &settings,
+                        &mut toggled,
+                    )
+                    .or(clicked.take());
+            });
+        }
+
+        if !storage.query.trim().is_empty() {
+            let mut matches = Vec::new();
+            for group in order_info.iter() {
+                for subgroup in group.1.iter() {
+                    for item_name in subgroup.1.iter() {
+                        let display_name = self.ctx.get_display_name(self.item_type, item_name);
+                        let score = fuzzy_match(&storage.query, &display_name)
+                            .max(fuzzy_match(&storage.query, item_name));
+                        if let Some(score) = score
+                            && (self.filter)(item_name, self.ctx)
+                        {
+                            matches.push((score, item_name.as_str()));
+                        }
                     }
                 }
-            });
-        egui::Grid::new("item")
-            .num_columns(item_count)
-            .max_col_width(35.0)
-            .min_col_width(35.0)
-            .min_row_height(35.0)
-            .spacing(Vec2 { x: 0.0, y: 0.0 })
-            .striped(true)
-            .show(ui, |ui| {
-                for (j, subgroup) in order_info[storage.group].1.iter().enumerate() {
+            }
+            // 稳定排序，分数相同时保持游戏内的顺序
+            matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+            matches.truncate(item_count * MAX_SEARCH_ROWS);
+            if matches.is_empty() {
+                ui.label("没有匹配的选项。");
+            } else {
+                let names = matches
+                    .into_iter()
+                    .map(|(_, name)| name)
+                    .collect::<Vec<_>>();
+                clicked = self
+                    .item_grid(ui, "search", &names, item_count, &settings, &mut toggled)
+                    .or(clicked.take());
+            }
+        } else {
+            egui::Grid::new("group")
+                .min_row_height(64.0)
+                .min_col_width(64.0)
+                .max_col_width(64.0)
+                .spacing(Vec2 { x: 6.0, y: 6.0 })
+                .show(ui, |ui| {
                     let mut idx = 0;
-                    for item_name in subgroup.1.iter() {
-                        if (idx % item_count) == 0 && idx != 0 {
+                    for (i, group) in order_info.iter().enumerate() {
+                        if (idx % group_count) == 0 && idx != 0 {
                             ui.end_row();
                         }
-                        if !(self.filter)(item_name, self.ctx) {
+                        let group_name = if group.0.is_empty() {
+                            "other".to_string()
+                        } else {
+                            group.0.clone()
+                        };
+                        if !filtered_group.contains_key(&i) {
                             continue;
                         }
                         idx += 1;
-                        let mut button = ui
-                            .add(
-                                Icon::new(self.ctx, self.item_type, &item_name.to_string())
-                                    .with_size(32.0),
-                            )
-                            .interact(egui::Sense::click());
-                        if let Some(hover) = &self.hover {
-                            button = button.on_hover_ui(|ui| (hover)(ui, item_name, self.ctx));
-                        } else {
-                            button = button.on_hover_text(
-                                self.ctx
-                                    .get_display_name(self.item_type, item_name)
-                                    .to_string(),
-                            );
+                        if ui
+                            .add(Icon::new(self.ctx, "item-group", &group_name).with_size(64.0))
+                            .interact(egui::Sense::click())
+                            .clicked()
+                        {
+                            storage.group = i;
+                            storage.subgroup = 0;
                         }
-
-                        if button.clicked() {
-                            storage.subgroup = j;
-                            if let Some(&mut ref mut selected_item) = self.current {
-                                *selected_item = item_name.clone();
+                    }
+                });
+            egui::Grid::new("item")
+                .num_columns(item_count)
+                .max_col_width(crate::settings::icon_cell()[0])
+                .min_col_width(crate::settings::icon_cell()[0])
+                .min_row_height(crate::settings::icon_cell()[1])
+                .spacing(Vec2 { x: 0.0, y: 0.0 })
+                .striped(true)
+                .show(ui, |ui| {
+                    for (j, subgroup) in order_info[storage.group].1.iter().enumerate() {
+                        let mut idx = 0;
+                        for item_name in subgroup.1.iter() {
+                            if (idx % item_count) == 0 && idx != 0 {
+                                ui.end_row();
                             }
-                            if let Some(&mut ref mut output) = self.output {
-                                *output = Some(item_name.clone());
+                            if !(self.filter)(item_name, self.ctx) {
+                                continue;
                             }
-                            if let Some(&mut ref mut changed) = self.changed {
-                                *changed = true;
+                            idx += 1;
+                            if self.item_button(ui, item_name, &settings, &mut toggled) {
+                                storage.subgroup = j;
+                                clicked = Some(item_name.clone());
                             }
                         }
+                        if idx != 0 {
+                            ui.end_row();
+                        }
                     }
-                    if idx != 0 {
-                        ui.end_row();
-                    }
-                }
+                });
+        }
+
+        if let Some(item_name) = toggled {
+            crate::settings::update(|settings| {
+                settings.toggle_favorite(self.item_type, &item_name)
             });
+        }
+        if let Some(item_name) = clicked {
+            crate::settings::update(|settings| settings.push_recent(self.item_type, &item_name));
+            if let Some(&mut ref mut selected_item) = self.current {
+                *selected_item = item_name.clone();
+            }
+            if let Some(&mut ref mut output) = self.output {
+                *output = Some(item_name);
+            }
+            if let Some(&mut ref mut changed) = self.changed {
+                *changed = true;
+            }
+        }
         ui.memory_mut(move |mem| {
             mem.data
                 .insert_temp::<ItemSelectorStorage>(id, storage.clone());
@@ -207,6 +414,7 @@ pub struct ItemWithQualitySelector<'a> {
     output: Option<&'a mut Option<IdWithQuality>>,
     forget: bool,
     hover: Option<Box<HoverUi<'a>>>,
+    badge: Option<Box<BadgeFn<'a>>>,
     changed: Option<&'a mut bool>,
 }
 
@@ -226,6 +434,7 @@ impl<'a> ItemWithQualitySelector<'a> {
             output: None,
             forget: false,
             hover: None,
+            badge: None,
             changed: None,
         }
     }
@@ -270,6 +479,14 @@ impl<'a> ItemWithQualitySelector<'a> {
         self
     }
 
+    pub fn with_badge(
+        mut self,
+        badge: impl Fn(&str, &FactorioContext) -> Option<String> + 'a,
+    ) -> Self {
+        self.badge = Some(Box::new(badge));
+        self
+    }
+
     pub fn notify_change(mut self, changed: &'a mut bool) -> Self {
         self.changed = Some(changed);
         self
@@ -288,13 +505,17 @@ impl<'a> egui::Widget for ItemWithQualitySelector<'a> {
         };
         let mut selecting_quality = None;
         let mut selecting_item = None;
-        quality_selector(ui, self.ctx, &mut selecting_quality);
+        let availability = QualityAvailability::current(ui.ctx());
+        quality_selector(ui, self.ctx, &availability, &mut selecting_quality);
         let mut widget = ItemSelector::new(self.ctx, self.item_type)
             .with_output(&mut selecting_item)
             .with_filter(self.filter);
         if let Some(hover) = self.hover {
             widget = widget.with_hover(hover);
         }
+        if let Some(badge) = self.badge {
+            widget = widget.with_badge(badge);
+        }
         ui.add(widget);
         if let Some(selected_item) = &selecting_item {
             storage.selected_item = Some(selected_item.clone());
@@ -330,24 +551,129 @@ impl<'a> egui::Widget for ItemWithQualitySelector<'a> {
     }
 }
 
-fn quality_selector(ui: &mut egui::Ui, ctx: &FactorioContext, selected_quality: &mut Option<u8>) {
+fn paint_badge(ui: &egui::Ui, rect: egui::Rect, text: &str) {
+    let painter = ui.painter();
+    let galley = painter.layout_no_wrap(
+        text.to_string(),
+        egui::FontId::proportional(10.0),
+        egui::Color32::WHITE,
+    );
+    let badge_rect = egui::Rect::from_min_size(
+        rect.right_bottom() - galley.size() - egui::vec2(2.0, 1.0),
+        galley.size() + egui::vec2(2.0, 0.0),
+    );
+    painter.rect_filled(badge_rect, 2.0, egui::Color32::from_black_alpha(160));
+    painter.galley(
+        badge_rect.min + egui::vec2(1.0, 0.0),
+        galley,
+        egui::Color32::WHITE,
+    );
+}
+
+/// 用底色标注当前工厂中已使用的条目，heat 越大颜色越深
+fn paint_usage(ui: &egui::Ui, rect: egui::Rect, heat: f32) {
+    let alpha = (40.0 + 100.0 * heat.clamp(0.0, 1.0)) as u8;
+    ui.painter().rect_filled(
+        rect,
+        2.0,
+        egui::Color32::from_rgba_unmultiplied(80, 200, 120, alpha),
+    );
+}
+
+/// 在收藏的图标左上角画一颗星
+fn paint_favorite(ui: &egui::Ui, rect: egui::Rect) {
+    ui.painter().text(
+        rect.left_top() + egui::vec2(1.0, 0.0),
+        egui::Align2::LEFT_TOP,
+        "★",
+        egui::FontId::proportional(10.0),
+        egui::Color32::GOLD,
+    );
+}
+
+/// 模糊匹配，查询中的字符按顺序出现在文本中即视为匹配，不区分大小写，分数越高越相关
+///
+/// 连续出现的子串优先于零散的字符，出现得越靠前越好。
+pub fn fuzzy_match(query: &str, text: &str) -> Option<i32> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let text = text.to_lowercase();
+    let chars = text.chars().collect::<Vec<_>>();
+    if let Some(pos) = text.find(&query) {
+        let pos = text[..pos].chars().count() as i32;
+        return Some(1000 - pos * 10 - chars.len() as i32);
+    }
+    let mut score = 0;
+    let mut next = 0;
+    let mut last = None;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let i = next + chars[next..].iter().position(|&c| c == q)?;
+        score += if last.is_some_and(|last| last + 1 == i) {
+            5
+        } else if i == 0 || matches!(chars[i - 1], '-' | '_' | ' ') {
+            3
+        } else {
+            1
+        };
+        last = Some(i);
+        next = i + 1;
+    }
+    Some(score)
+}
+
+/// 品质选择行，未解锁的品质变暗显示但仍可选择，便于提前规划
+fn quality_selector(
+    ui: &mut egui::Ui,
+    ctx: &FactorioContext,
+    availability: &QualityAvailability,
+    selected_quality: &mut Option<u8>,
+) {
     egui::Grid::new("quality")
-        .max_col_width(35.0)
-        .min_col_width(35.0)
-        .min_row_height(35.0)
+        .max_col_width(crate::settings::icon_cell()[0])
+        .min_col_width(crate::settings::icon_cell()[0])
+        .min_row_height(crate::settings::icon_cell()[1])
         .spacing(Vec2 { x: 0.0, y: 0.0 })
         .show(ui, |ui| {
             for (idx, quality) in ctx.qualities.iter().enumerate() {
-                let quality_button = ui
+                let name = ctx.get_display_name("quality", &quality.base.name);
+                let mut quality_button = ui
                     .add_sized(
                         [32.0, 32.0],
                         Icon::new(ctx, "quality", &quality.base.name).with_size(32.0),
                     )
-                    .on_hover_text(ctx.get_display_name("quality", &quality.base.name))
                     .interact(egui::Sense::click());
+                if availability.is_available(idx as u8) {
+                    quality_button = quality_button.on_hover_text(name);
+                } else {
+                    ui.painter().rect_filled(
+                        quality_button.rect,
+                        2.0,
+                        egui::Color32::from_black_alpha(160),
+                    );
+                    quality_button =
+                        quality_button.on_hover_text(format!("{}（该工厂尚未解锁）", name));
+                }
                 if quality_button.clicked() {
                     *selected_quality = Some(idx as u8);
                 }
             }
         });
 }
+
+#[test]
+fn test_fuzzy_match() {
+    assert!(fuzzy_match("gear", "iron-gear-wheel").is_some());
+    assert!(fuzzy_match("IGW", "iron-gear-wheel").is_some());
+    assert!(fuzzy_match("齿轮", "铁齿轮").is_some());
+    assert_eq!(fuzzy_match("wheel gear", "iron-gear-wheel"), None);
+    assert_eq!(fuzzy_match("copper", "iron-plate"), None);
+    // 子串优先于零散的字符，靠前的子串优先
+    let substring = fuzzy_match("plate", "iron-plate").unwrap();
+    let scattered = fuzzy_match("plate", "processing-unit-late").unwrap();
+    assert!(substring > scattered);
+    assert!(fuzzy_match("iron", "iron-plate") > fuzzy_match("iron", "hot-iron-plate"));
+    // 词首的字符优先于词中的字符
+    assert!(fuzzy_match("ip", "iron-plate") > fuzzy_match("ip", "lithium-plate"));
+}
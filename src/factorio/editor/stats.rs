@@ -0,0 +1,339 @@
+//! 导入游戏内的生产统计，与求解结果逐项对比。
+//!
+//! 统计数据由配套模组写出，格式如下，数量为统计区间内的累计值：
+//!
+//! ```json
+//! {
+//!     "tick": 1234567,
+//!     "interval": 600,
+//!     "items": { "iron-plate": { "produced": 12000, "consumed": 9000 } },
+//!     "fluids": { "water": { "produced": 72000, "consumed": 70000 } }
+//! }
+//! ```
+//!
+//! 游戏统计不区分品质，规划中的各品质流量会合并后再比较。
+
+use indexmap::IndexMap;
+
+use crate::{
+    concept::*,
+    dyn_serde::save_to_file,
+    error::AppError,
+    factorio::{common::*, editor::planner::FactoryInstance, format::*, model::*},
+    settings::RateUnit,
+};
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ProductionCount {
+    pub produced: f64,
+    pub consumed: f64,
+}
+
+/// 配套模组导出的生产统计
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct GameStatistics {
+    #[serde(default)]
+    pub tick: u64,
+    /// 统计区间的长度（秒）
+    pub interval: f64,
+    #[serde(default)]
+    pub items: IndexMap<String, ProductionCount>,
+    #[serde(default)]
+    pub fluids: IndexMap<String, ProductionCount>,
+}
+
+impl GameStatistics {
+    pub fn load(path: &std::path::Path) -> Result<Self, AppError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| AppError::Io(format!("读取文件 {} 失败：{}", path.display(), e)))?;
+        let stats: GameStatistics = serde_json::from_str(&content)
+            .map_err(|e| AppError::Io(format!("解析生产统计 {} 失败：{}", path.display(), e)))?;
+        if stats.interval <= 0.0 {
+            return Err(AppError::Io(format!(
+                "生产统计 {} 的统计区间无效：{}",
+                path.display(),
+                stats.interval
+            )));
+        }
+        Ok(stats)
+    }
+
+    /// 每秒的产出与消耗
+    pub fn rates(&self) -> IndexMap<StatsKey, ProductionCount> {
+        let to_rate = |count: &ProductionCount| ProductionCount {
+            produced: count.produced / self.interval,
+            consumed: count.consumed / self.interval,
+        };
+        self.items
+            .iter()
+            .map(|(name, count)| (StatsKey::Item(name.clone()), to_rate(count)))
+            .chain(
+                self.fluids
+                    .iter()
+                    .map(|(name, count)| (StatsKey::Fluid(name.clone()), to_rate(count))),
+            )
+            .collect()
+    }
+}
+
+/// 不区分品质与温度的物品标识
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(tag = "type", content = "name", rename_all = "lowercase")]
+pub enum StatsKey {
+    Item(String),
+    Fluid(String),
+}
+
+impl StatsKey {
+    pub fn from_generic_item(item: &GenericItem) -> Option<Self> {
+        match item {
+            GenericItem::Item(IdWithQuality(name, _)) => Some(StatsKey::Item(name.clone())),
+            GenericItem::Fluid { name, .. } => Some(StatsKey::Fluid(name.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn display_name(&self, ctx: &FactorioContext) -> String {
+        match self {
+            StatsKey::Item(name) => ctx.get_display_name("item", name),
+            StatsKey::Fluid(name) => ctx.get_display_name("fluid", name),
+        }
+    }
+}
+
+/// 方案中每秒的产出与消耗，按求解得到的机器数量计算
+pub fn planned_rates(
+    factory: &FactoryInstance,
+    ctx: &FactorioContext,
+) -> IndexMap<StatsKey, ProductionCount> {
+    let mut rates: IndexMap<StatsKey, ProductionCount> = IndexMap::new();
+    for (id, mechanic) in &factory.mechanics {
+        let count = factory.solution.0.get(id).cloned().unwrap_or(0.0);
+        if count < 1e-6 {
+            continue;
+        }
+        for (item, amount) in mechanic.as_flow(ctx) {
+            let Some(key) = StatsKey::from_generic_item(&item) else {
+                continue;
+            };
+            let entry = rates.entry(key).or_default();
+            if amount > 0.0 {
+                entry.produced += amount * count;
+            } else {
+                entry.consumed -= amount * count;
+            }
+        }
+    }
+    rates
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StatsComparisonRow {
+    #[serde(flatten)]
+    pub key: StatsKey,
+    pub planned: ProductionCount,
+    pub actual: ProductionCount,
+}
+
+impl StatsComparisonRow {
+    /// 实际产出与计划产出之比，计划中不生产该物品时为 None
+    pub fn fulfillment(&self) -> Option<f64> {
+        if self.planned.produced > 1e-6 {
+            Some(self.actual.produced / self.planned.produced)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_underperforming(&self, tolerance: f64) -> bool {
+        self.fulfillment()
+            .is_some_and(|fulfillment| fulfillment < 1.0 - tolerance)
+    }
+}
+
+/// 逐项对比计划与实际，未达标最严重的物品排在最前；只出现在游戏统计中的物品不列出
+pub fn compare_stats(
+    planned: &IndexMap<StatsKey, ProductionCount>,
+    actual: &IndexMap<StatsKey, ProductionCount>,
+) -> Vec<StatsComparisonRow> {
+    let mut rows = planned
+        .iter()
+        .map(|(key, planned)| StatsComparisonRow {
+            key: key.clone(),
+            planned: planned.clone(),
+            actual: actual.get(key).cloned().unwrap_or_default(),
+        })
+        .collect::<Vec<_>>();
+    rows.sort_by(|a, b| {
+        a.fulfillment()
+            .unwrap_or(f64::INFINITY)
+            .total_cmp(&b.fulfillment().unwrap_or(f64::INFINITY))
+    });
+    rows
+}
+
+/// 计划与实际生产统计的对比视图
+pub struct StatsComparisonView {
+    pub factory_name: String,
+    pub tick: u64,
+    pub rows: Vec<StatsComparisonRow>,
+    pub names: IndexMap<StatsKey, String>,
+    /// 实际产出低于计划的比例超过该值时视为未达标
+    pub tolerance: f64,
+    pub underperforming_only: bool,
+    /// 产出与消耗的显示单位
+    pub rate_unit: RateUnit,
+}
+
+impl StatsComparisonView {
+    pub fn new(factory: &FactoryInstance, stats: &GameStatistics, ctx: &FactorioContext) -> Self {
+        let rows = compare_stats(&planned_rates(factory, ctx), &stats.rates());
+        let names = rows
+            .iter()
+            .map(|row| (row.key.clone(), row.key.display_name(ctx)))
+            .collect();
+        Self {
+            factory_name: factory.name.clone(),
+            tick: stats.tick,
+            rows,
+            names,
+            tolerance: 0.05,
+            underperforming_only: false,
+            rate_unit: factory.rate_unit(),
+        }
+    }
+}
+
+impl Subview for StatsComparisonView {
+    fn view(&mut self, ui: &mut egui::Ui) {
+        ui.heading(format!("计划与实际：{}", self.factory_name));
+        ui.label(format!(
+            "游戏统计截至第 {} 刻（约 {:.1} 小时），速率均为每秒",
+            self.tick,
+            self.tick as f64 / 60.0 / 3600.0
+        ));
+        ui.horizontal(|ui| {
+            ui.label("容差");
+            ui.add(
+                egui::Slider::new(&mut self.tolerance, 0.0..=0.5)
+                    .custom_formatter(|value, _| format!("{:.0}%", value * 100.0))
+                    .custom_parser(|text| {
+                        text.trim_end_matches('%')
+                            .parse::<f64>()
+                            .ok()
+                            .map(|value| value / 100.0)
+                    }),
+            );
+            ui.checkbox(&mut self.underperforming_only, "只显示未达标的物品");
+            if ui.button("导出 JSON……").clicked()
+                && let Some(path) = crate::file_dialog::FileDialog::new()
+                    .add_filter("JSON", &["json"])
+                    .set_file_name(format!("{}-stats.json", self.factory_name).as_str())
+                    .save_file()
+            {
+                match save_to_file(&self.rows, &path) {
+                    Ok(()) => crate::toast::success(format!("对比结果已导出到 {}", path.display())),
+                    Err(err) => crate::toast::error(format!("导出失败：{:?}", err)),
+                }
+            }
+        });
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("stats-comparison")
+                .striped(true)
+                .num_columns(6)
+                .spacing([16.0, 4.0])
+                .show(ui, |ui| {
+                    ui.strong("物品");
+                    ui.strong("计划产出");
+                    ui.strong("实际产出");
+                    ui.strong("达成率");
+                    ui.strong("计划消耗");
+                    ui.strong("实际消耗");
+                    ui.end_row();
+                    for row in &self.rows {
+                        let underperforming = row.is_underperforming(self.tolerance);
+                        if self.underperforming_only && !underperforming {
+                            continue;
+                        }
+                        let name = self.names.get(&row.key).cloned().unwrap_or_default();
+                        if underperforming {
+                            ui.colored_label(egui::Color32::LIGHT_RED, name);
+                        } else {
+                            ui.label(name);
+                        }
+                        let rate_unit = match row.key {
+                            StatsKey::Item(_) => self.rate_unit,
+                            StatsKey::Fluid(_) => RateUnit::PerSecond,
+                        };
+                        ui.add(CompactLabel::rate(row.planned.produced, rate_unit));
+                        ui.add(CompactLabel::rate(row.actual.produced, rate_unit));
+                        match row.fulfillment() {
+                            Some(fulfillment) if underperforming => ui.colored_label(
+                                egui::Color32::LIGHT_RED,
+                                format!("{:.0}%", fulfillment * 100.0),
+                            ),
+                            Some(fulfillment) => ui.label(format!("{:.0}%", fulfillment * 100.0)),
+                            None => ui.label("-"),
+                        };
+                        ui.add(CompactLabel::rate(row.planned.consumed, rate_unit));
+                        ui.add(CompactLabel::rate(row.actual.consumed, rate_unit));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+
+    fn name(&self) -> String {
+        format!("计划与实际 - {}", self.factory_name)
+    }
+
+    fn description(&self) -> String {
+        "求解结果与游戏内生产统计的逐项对比".to_string()
+    }
+}
+
+#[test]
+fn test_compare_stats() {
+    let stats: GameStatistics = serde_json::from_str(
+        r#"{
+            "tick": 3600,
+            "interval": 60,
+            "items": { "iron-plate": { "produced": 600, "consumed": 300 } },
+            "fluids": { "water": { "produced": 6000 } }
+        }"#,
+    )
+    .unwrap();
+    let actual = stats.rates();
+    assert_eq!(actual[&StatsKey::Item("iron-plate".into())].produced, 10.0);
+    let planned = IndexMap::from([
+        (
+            StatsKey::Fluid("water".into()),
+            ProductionCount {
+                produced: 100.0,
+                consumed: 0.0,
+            },
+        ),
+        (
+            StatsKey::Item("iron-plate".into()),
+            ProductionCount {
+                produced: 20.0,
+                consumed: 5.0,
+            },
+        ),
+        (
+            StatsKey::Item("copper-cable".into()),
+            ProductionCount {
+                produced: 0.0,
+                consumed: 1.0,
+            },
+        ),
+    ]);
+    let rows = compare_stats(&planned, &actual);
+    assert_eq!(rows[0].key, StatsKey::Item("iron-plate".into()));
+    assert_eq!(rows[0].fulfillment(), Some(0.5));
+    assert!(rows[0].is_underperforming(0.05));
+    assert!(!rows[1].is_underperforming(0.05));
+    assert_eq!(rows[2].fulfillment(), None);
+}
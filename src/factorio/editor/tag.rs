@@ -0,0 +1,86 @@
+use crate::i18n::tr;
+
+/// 工厂的颜色标签，显示在标签栏中，可按标签筛选
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FactoryTag {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl FactoryTag {
+    pub const ALL: [FactoryTag; 6] = [
+        FactoryTag::Red,
+        FactoryTag::Orange,
+        FactoryTag::Yellow,
+        FactoryTag::Green,
+        FactoryTag::Blue,
+        FactoryTag::Purple,
+    ];
+
+    pub fn color(&self) -> egui::Color32 {
+        match self {
+            FactoryTag::Red => egui::Color32::from_rgb(0xE0, 0x4F, 0x4F),
+            FactoryTag::Orange => egui::Color32::from_rgb(0xE8, 0x8E, 0x3A),
+            FactoryTag::Yellow => egui::Color32::from_rgb(0xE0, 0xC8, 0x3C),
+            FactoryTag::Green => egui::Color32::from_rgb(0x5C, 0xB8, 0x5C),
+            FactoryTag::Blue => egui::Color32::from_rgb(0x4A, 0x90, 0xD9),
+            FactoryTag::Purple => egui::Color32::from_rgb(0x9B, 0x6A, 0xD6),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FactoryTag::Red => tr("tag.red"),
+            FactoryTag::Orange => tr("tag.orange"),
+            FactoryTag::Yellow => tr("tag.yellow"),
+            FactoryTag::Green => tr("tag.green"),
+            FactoryTag::Blue => tr("tag.blue"),
+            FactoryTag::Purple => tr("tag.purple"),
+        }
+    }
+
+    /// 标签栏中的色块
+    pub fn dot(&self) -> egui::RichText {
+        egui::RichText::new("●").color(self.color())
+    }
+}
+
+/// 工厂的完成状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FactoryStatus {
+    #[default]
+    Draft,
+    Final,
+    /// 游戏版本或上游工厂变化后需要重新检查
+    NeedsUpdate,
+}
+
+impl FactoryStatus {
+    pub const ALL: [FactoryStatus; 3] = [
+        FactoryStatus::Draft,
+        FactoryStatus::Final,
+        FactoryStatus::NeedsUpdate,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FactoryStatus::Draft => tr("status.draft"),
+            FactoryStatus::Final => tr("status.final"),
+            FactoryStatus::NeedsUpdate => tr("status.needs_update"),
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            FactoryStatus::Draft => "✏",
+            FactoryStatus::Final => "✔",
+            FactoryStatus::NeedsUpdate => "⚠",
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! 内置的示例工厂。模板只记录原型名称，实例化后按当前上下文校验，缺失的原型由迁移对话框替换。
+
+use indexmap::IndexMap;
+
+use crate::{
+    concept::MechanicId,
+    factorio::{
+        editor::{
+            migrate::{MissingPrototype, validate_factory},
+            planner::FactoryInstance,
+            preset::{
+                SCRAP_FUEL_FLUID, SCRAP_ITEM, scrap_power_mechanics, scrap_recycling_mechanics,
+            },
+        },
+        model::*,
+    },
+    solver::ByproductPolicy,
+};
+
+/// 模板中引用的物品或流体
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateItem {
+    Item(&'static str),
+    Fluid(&'static str),
+}
+
+impl TemplateItem {
+    pub fn to_generic(self) -> GenericItem {
+        match self {
+            TemplateItem::Item(name) => GenericItem::Item(name.into()),
+            TemplateItem::Fluid(name) => GenericItem::Fluid {
+                name: name.to_string(),
+                temperature: None,
+            },
+        }
+    }
+}
+
+/// 模板中的一个配方机制
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateRecipe {
+    pub recipe: &'static str,
+    pub machine: &'static str,
+    /// 燃烧型机器使用的燃料，None 时使用抽象燃料
+    pub fuel: Option<&'static str>,
+}
+
+const fn recipe(recipe: &'static str, machine: &'static str) -> TemplateRecipe {
+    TemplateRecipe {
+        recipe,
+        machine,
+        fuel: None,
+    }
+}
+
+/// 一个示例工厂
+#[derive(Debug, Clone, Copy)]
+pub struct FactoryTemplate {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub location: Option<&'static str>,
+    pub recipes: &'static [TemplateRecipe],
+    pub targets: &'static [(TemplateItem, f64)],
+    /// 额外输入及其单位价值
+    pub external: &'static [(TemplateItem, f64)],
+    /// 不允许盈余、必须全部消耗的物品
+    pub balanced: &'static [TemplateItem],
+    /// 开启废料经济模式，并按当前上下文补全废料的逐级回收与重油发电机制
+    pub scrap_economy: bool,
+}
+
+pub const FACTORY_TEMPLATES: [FactoryTemplate; 5] = [
+    FactoryTemplate {
+        name: "红瓶",
+        description: "由铁矿与铜矿冶炼并组装自动化科技包，每秒 1 个",
+        location: None,
+        recipes: &[
+            recipe("iron-plate", "stone-furnace"),
+            recipe("copper-plate", "stone-furnace"),
+            recipe("iron-gear-wheel", "assembling-machine-2"),
+            recipe("automation-science-pack", "assembling-machine-2"),
+        ],
+        targets: &[(TemplateItem::Item("automation-science-pack"), 1.0)],
+        external: &[
+            (TemplateItem::Item("iron-ore"), 1.0),
+            (TemplateItem::Item("copper-ore"), 1.0),
+        ],
+        balanced: &[],
+        scrap_economy: false,
+    },
+    FactoryTemplate {
+        name: "原油处理",
+        description: "高级原油处理配合重油、轻油裂解，全部转化为石油气",
+        location: None,
+        recipes: &[
+            recipe("advanced-oil-processing", "oil-refinery"),
+            recipe("heavy-oil-cracking", "chemical-plant"),
+            recipe("light-oil-cracking", "chemical-plant"),
+        ],
+        targets: &[(TemplateItem::Fluid("petroleum-gas"), 100.0)],
+        external: &[
+            (TemplateItem::Fluid("crude-oil"), 0.1),
+            (TemplateItem::Fluid("water"), 0.01),
+        ],
+        balanced: &[
+            TemplateItem::Fluid("heavy-oil"),
+            TemplateItem::Fluid("light-oil"),
+        ],
+        scrap_economy: false,
+    },
+    FactoryTemplate {
+        name: "Vulcanus 铸造",
+        description: "在铸造厂中由熔岩提炼铁水、铜水，再铸造铁板、铜板、齿轮与钢材",
+        location: Some("vulcanus"),
+        recipes: &[
+            recipe("molten-iron-from-lava", "foundry"),
+            recipe("molten-copper-from-lava", "foundry"),
+            recipe("casting-iron", "foundry"),
+            recipe("casting-copper", "foundry"),
+            recipe("casting-iron-gear-wheel", "foundry"),
+            recipe("casting-steel", "foundry"),
+        ],
+        targets: &[
+            (TemplateItem::Item("iron-plate"), 10.0),
+            (TemplateItem::Item("copper-plate"), 10.0),
+            (TemplateItem::Item("iron-gear-wheel"), 2.0),
+            (TemplateItem::Item("steel-plate"), 1.0),
+        ],
+        external: &[
+            (TemplateItem::Fluid("lava"), 0.01),
+            (TemplateItem::Item("calcite"), 1.0),
+        ],
+        balanced: &[],
+        scrap_economy: false,
+    },
+    FactoryTemplate {
+        name: "Gleba 生物链",
+        description: "加工玉丝果与果冻坚果，在生物室中制作生物通量，生物室以营养素为燃料",
+        location: Some("gleba"),
+        recipes: &[
+            TemplateRecipe {
+                recipe: "yumako-processing",
+                machine: "biochamber",
+                fuel: Some("nutrients"),
+            },
+            TemplateRecipe {
+                recipe: "jellynut-processing",
+                machine: "biochamber",
+                fuel: Some("nutrients"),
+            },
+            TemplateRecipe {
+                recipe: "nutrients-from-yumako-mash",
+                machine: "biochamber",
+                fuel: Some("nutrients"),
+            },
+            TemplateRecipe {
+                recipe: "bioflux",
+                machine: "biochamber",
+                fuel: Some("nutrients"),
+            },
+        ],
+        targets: &[(TemplateItem::Item("bioflux"), 1.0)],
+        external: &[
+            (TemplateItem::Item("yumako"), 1.0),
+            (TemplateItem::Item("jellynut"), 1.0),
+        ],
+        balanced: &[],
+        scrap_economy: false,
+    },
+    FactoryTemplate {
+        name: "Fulgora 废料",
+        description: "以废料与重油为输入，废料产物逐级回收销毁，重油制固体燃料发电，目标为钬矿",
+        location: Some("fulgora"),
+        recipes: &[],
+        targets: &[(TemplateItem::Item("holmium-ore"), 0.1)],
+        external: &[
+            (TemplateItem::Item(SCRAP_ITEM), 1.0),
+            (TemplateItem::Fluid(SCRAP_FUEL_FLUID), 0.05),
+        ],
+        balanced: &[],
+        scrap_economy: true,
+    },
+];
+
+impl FactoryTemplate {
+    /// 按模板创建工厂，当前上下文中没有的星球不作为位置，其余原型不检查是否存在
+    pub fn instantiate(&self, ctx: &FactorioContext) -> FactoryInstance {
+        let mut factory = FactoryInstance::new(self.name.to_string()).with_default_providers();
+        factory.notes = self.description.to_string();
+        factory.location = self
+            .location
+            .filter(|location| ctx.locations().iter().any(|known| known == location))
+            .map(str::to_string);
+        factory.target = self
+            .targets
+            .iter()
+            .map(|(item, amount)| (item.to_generic(), *amount))
+            .collect();
+        factory.external = self
+            .external
+            .iter()
+            .map(|(item, cost)| (item.to_generic(), *cost))
+            .collect();
+        for item in self.balanced {
+            factory
+                .byproduct_policies
+                .insert(item.to_generic(), ByproductPolicy::Zero);
+        }
+        for recipe in self.recipes {
+            factory.mechanics.insert(
+                MechanicId::generate(),
+                Box::new(RecipeConfig {
+                    recipe: recipe.recipe.into(),
+                    machine: recipe.machine.into(),
+                    module_config: ModuleConfig::new(),
+                    instance_fuel: recipe.fuel.map(|fuel| (fuel.to_string(), 0)),
+                }),
+            );
+        }
+        if self.scrap_economy {
+            factory.scrap_economy = true;
+            for mechanic in scrap_recycling_mechanics(ctx)
+                .into_iter()
+                .chain(scrap_power_mechanics(ctx))
+            {
+                factory.mechanics.insert(MechanicId::generate(), mechanic);
+            }
+        }
+        factory
+    }
+
+    /// 在当前上下文中缺失的原型，模组修改了配方时需要替换
+    pub fn missing(&self, ctx: &FactorioContext) -> IndexMap<MissingPrototype, usize> {
+        validate_factory(&self.instantiate(ctx), ctx)
+    }
+}
+
+#[test]
+fn test_factory_templates() {
+    let ctx = FactorioContext::test_load();
+    for template in FACTORY_TEMPLATES.iter() {
+        assert!(
+            template.missing(&ctx).is_empty(),
+            "{}: {:?}",
+            template.name,
+            template.missing(&ctx)
+        );
+        let mut factory = template.instantiate(&ctx);
+        assert_eq!(factory.location.as_deref(), template.location);
+        assert_eq!(factory.scrap_economy, template.scrap_economy);
+        if !template.scrap_economy {
+            assert_eq!(factory.mechanics.len(), template.recipes.len());
+        }
+        factory
+            .solve_blocking(&ctx)
+            .unwrap_or_else(|err| panic!("{}: {:?}", template.name, err));
+        for (item, _) in template.targets {
+            assert!(
+                factory.total_flow[&item.to_generic()] > 0.0,
+                "{}",
+                template.name
+            );
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    concept::*,
+    factorio::{common::*, format::compact_number, model::*},
+    settings::RateUnit,
+};
+
+/// 条目在当前工厂中的使用情况
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageEntry {
+    /// 引用该条目的机制数量
+    pub mechanics: usize,
+    /// 求解得到的台数，配方与实体有效
+    pub machines: f64,
+    /// 每秒产出，物品与流体有效
+    pub produced: f64,
+    /// 每秒消耗，物品与流体有效
+    pub consumed: f64,
+}
+
+/// 当前工厂已使用的配方、物品、流体与实体，按选择器的条目类型索引，在求解完成后更新
+#[derive(Debug, Clone, Default)]
+pub struct FactoryUsage {
+    entries: HashMap<String, Dict<UsageEntry>>,
+    /// 各类型中被最多机制引用的次数，用于换算热度
+    max_mechanics: HashMap<String, usize>,
+}
+
+impl FactoryUsage {
+    /// 按机制、机制的物料流与求解得到的倍数汇总
+    pub fn collect<'a>(
+        mechanics: impl IntoIterator<Item = (&'a FactorioMechanic, &'a Flow<GenericItem>, f64)>,
+        ctx: &FactorioContext,
+    ) -> Self {
+        let mut usage = FactoryUsage::default();
+        for (mechanic, flow, count) in mechanics {
+            if let Some(config) = (mechanic as &dyn Any).downcast_ref::<RecipeConfig>() {
+                let entry = usage.entry("recipe", &config.recipe.0);
+                entry.mechanics += 1;
+                entry.machines += count;
+            }
+            let mut seen = HashSet::new();
+            for (entity, amount) in mechanic_placed_entities(mechanic, ctx) {
+                let entry = usage.entry("entity", &entity);
+                if seen.insert(("entity", entity.clone())) {
+                    entry.mechanics += 1;
+                }
+                entry.machines += amount * count;
+            }
+            for (item, amount) in flow {
+                let (item_type, name) = match item {
+                    GenericItem::Item(IdWithQuality(name, _)) => ("item", name),
+                    GenericItem::Fluid { name, .. } => ("fluid", name),
+                    _ => continue,
+                };
+                let entry = usage.entry(item_type, name);
+                if seen.insert((item_type, name.clone())) {
+                    entry.mechanics += 1;
+                }
+                if *amount > 0.0 {
+                    entry.produced += amount * count;
+                } else {
+                    entry.consumed -= amount * count;
+                }
+            }
+        }
+        for (item_type, entries) in &usage.entries {
+            let max = entries.values().map(|entry| entry.mechanics).max();
+            usage
+                .max_mechanics
+                .insert(item_type.clone(), max.unwrap_or(0));
+        }
+        usage
+    }
+
+    fn entry(&mut self, item_type: &str, name: &str) -> &mut UsageEntry {
+        self.entries
+            .entry(item_type.to_string())
+            .or_default()
+            .entry(name.to_string())
+            .or_default()
+    }
+
+    pub fn get(&self, item_type: &str, name: &str) -> Option<&UsageEntry> {
+        self.entries.get(item_type)?.get(name)
+    }
+
+    /// 热度，0 到 1，引用越多越接近 1；未使用时为 None
+    pub fn heat(&self, item_type: &str, name: &str) -> Option<f32> {
+        let entry = self.get(item_type, name)?;
+        let max = self
+            .max_mechanics
+            .get(item_type)
+            .copied()
+            .unwrap_or(1)
+            .max(1);
+        Some(entry.mechanics as f32 / max as f32)
+    }
+
+    /// 悬停提示中显示的使用情况
+    pub fn describe(&self, item_type: &str, name: &str, unit: RateUnit) -> Option<String> {
+        let entry = self.get(item_type, name)?;
+        let mut text = format!("当前工厂中 {} 个机制使用", entry.mechanics);
+        match item_type {
+            "recipe" | "entity" => {
+                text += &format!("，共 {} 台", compact_number(entry.machines));
+            }
+            _ => {
+                let rate = |amount: f64| {
+                    format!(
+                        "{}{}",
+                        compact_number(amount * unit.multiplier()),
+                        unit.suffix()
+                    )
+                };
+                if entry.produced > 0.0 {
+                    text += &format!("，产出 {}", rate(entry.produced));
+                }
+                if entry.consumed > 0.0 {
+                    text += &format!("，消耗 {}", rate(entry.consumed));
+                }
+            }
+        }
+        Some(text)
+    }
+
+    fn memory_id() -> egui::Id {
+        egui::Id::new("factory-usage")
+    }
+
+    /// 在绘制工厂期间公开使用情况，其中打开的选择器据此标注已使用的条目
+    pub fn publish(usage: Arc<FactoryUsage>, ctx: &egui::Context) {
+        ctx.data_mut(|data| data.insert_temp(Self::memory_id(), usage));
+    }
+
+    /// 工厂绘制结束后撤回，避免其他页面的选择器看到
+    pub fn retract(ctx: &egui::Context) {
+        ctx.data_mut(|data| data.remove::<Arc<FactoryUsage>>(Self::memory_id()));
+    }
+
+    pub fn current(ctx: &egui::Context) -> Option<Arc<FactoryUsage>> {
+        ctx.data(|data| data.get_temp(Self::memory_id()))
+    }
+}
+
+#[test]
+fn test_factory_usage() {
+    let ctx = FactorioContext::test_load();
+    let gears: Box<FactorioMechanic> = Box::new(RecipeConfig {
+        recipe: "iron-gear-wheel".into(),
+        machine: "assembling-machine-2".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+    });
+    let plates: Box<FactorioMechanic> = Box::new(RecipeConfig {
+        recipe: "iron-plate".into(),
+        machine: "electric-furnace".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+    });
+    let gear_flow = gears.as_flow(&ctx);
+    let plate_flow = plates.as_flow(&ctx);
+    let usage = FactoryUsage::collect(
+        [
+            (gears.as_ref(), &gear_flow, 1.5),
+            (plates.as_ref(), &plate_flow, 2.0),
+        ],
+        &ctx,
+    );
+    let recipe = usage.get("recipe", "iron-gear-wheel").unwrap();
+    assert_eq!(recipe.mechanics, 1);
+    assert_eq!(recipe.machines, 1.5);
+    assert_eq!(
+        usage.get("entity", "electric-furnace").unwrap().machines,
+        2.0
+    );
+    // 铁板由熔炉产出、被组装机消耗，两个机制都引用
+    let plate = usage.get("item", "iron-plate").unwrap();
+    assert_eq!(plate.mechanics, 2);
+    assert!(plate.produced > 0.0 && plate.consumed > 0.0);
+    assert_eq!(usage.heat("item", "iron-plate"), Some(1.0));
+    assert_eq!(usage.heat("item", "iron-ore"), Some(0.5));
+    assert_eq!(usage.heat("recipe", "copper-cable"), None);
+    assert!(
+        usage
+            .describe("recipe", "iron-plate", RateUnit::PerSecond)
+            .unwrap()
+            .contains("共 2 台")
+    );
+}
@@ -1,82 +1,173 @@
+use crate::{i18n::trf, settings::RateUnit};
+
 const LARGE_UNITS: [&str; 11] = ["", "k", "M", "G", "T", "P", "E", "Z", "Y", "R", "Q"];
 
-pub fn signed_compact_number(num: f64) -> String {
-    if num.is_sign_negative() {
-        format!("-{}", compact_number(-num))
+/// 按量级选择单位显示时长，只保留两级单位
+pub fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.max(0.0);
+    if seconds < 60.0 {
+        trf("duration.seconds", &[&format!("{:.1}", seconds)])
+    } else if seconds < 3600.0 {
+        trf(
+            "duration.minutes",
+            &[
+                &((seconds / 60.0) as u64),
+                &format!("{:.0}", seconds % 60.0),
+            ],
+        )
+    } else if seconds < 86400.0 {
+        trf(
+            "duration.hours",
+            &[
+                &((seconds / 3600.0) as u64),
+                &(((seconds % 3600.0) / 60.0) as u64),
+            ],
+        )
     } else {
-        format!("+{}", compact_number(num))
+        trf(
+            "duration.days",
+            &[
+                &((seconds / 86400.0) as u64),
+                &(((seconds % 86400.0) / 3600.0) as u64),
+            ],
+        )
     }
 }
 
+pub fn signed_compact_number(num: f64) -> String {
+    NumberFormat::current().signed(num)
+}
+
 pub fn compact_number(num: f64) -> String {
-    let abs_num = num.abs();
-
-    match abs_num {
-        n if n < 1e-9 => String::from("0"),
-        n if n < 0.01 => format_with_unit(n * 1e6, "μ"),
-        n => {
-            let mut unit_idx = 0;
-            let mut n = n;
-            if n > 10000.0 {
-                while n > 1000.0 && unit_idx < LARGE_UNITS.len() - 1 {
-                    unit_idx += 1;
-                    n /= 1000.0;
+    NumberFormat::current().compact(num)
+}
+
+/// 数值的显示格式，由用户设置决定，所有数值标签都经由它格式化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub precision: usize,
+    /// 较大或较小的数值使用 k/M/G 与 μ 等单位前缀，否则完整显示或使用科学计数法
+    pub si_suffixes: bool,
+    /// 整数部分每三位插入逗号
+    pub thousands_separator: bool,
+    /// 分母较小的非整数显示为分数
+    pub exact_rational: bool,
+}
+
+impl NumberFormat {
+    pub fn current() -> Self {
+        let settings = crate::settings::get();
+        NumberFormat {
+            precision: settings.decimal_precision,
+            si_suffixes: settings.si_suffixes,
+            thousands_separator: settings.thousands_separator,
+            exact_rational: settings.exact_rational,
+        }
+    }
+
+    pub fn signed(&self, num: f64) -> String {
+        if num.is_sign_negative() {
+            format!("-{}", self.compact(-num))
+        } else {
+            format!("+{}", self.compact(num))
+        }
+    }
+
+    /// 按精度与单位前缀缩写的数值，不带符号
+    pub fn compact(&self, num: f64) -> String {
+        match num.abs() {
+            n if n < 1e-9 => String::from("0"),
+            n if n < 0.01 && self.si_suffixes => {
+                format_with_precision(n * 1e6, "μ", self.precision)
+            }
+            n if n < 0.01 => format!("{:.*e}", self.precision.saturating_sub(1), n),
+            n if n <= 10000.0
+                && self.exact_rational
+                && let Some(fraction) = crate::rational::format_fraction(n) =>
+            {
+                fraction
+            }
+            n if !self.si_suffixes => self.group(format_with_precision(n, "", self.precision)),
+            n => {
+                let mut unit_idx = 0;
+                let mut n = n;
+                if n > 10000.0 {
+                    while n > 1000.0 && unit_idx < LARGE_UNITS.len() - 1 {
+                        unit_idx += 1;
+                        n /= 1000.0;
+                    }
                 }
+                self.group(format_with_precision(
+                    n,
+                    LARGE_UNITS[unit_idx],
+                    self.precision,
+                ))
             }
-            format_with_unit(n, LARGE_UNITS[unit_idx])
         }
     }
-}
-fn format_with_unit(value: f64, unit: &str) -> String {
-    let abs_value = value.abs();
-    if unit.is_empty() {
-        if abs_value < 10.0 {
-            // 对于小于10的值，最多保留3位小数
-            let formatted = format!("{:.3}", value);
-            format!(
-                "{}{}",
-                formatted.trim_end_matches('0').trim_end_matches('.'),
-                unit
-            )
-        } else if abs_value < 100.0 {
-            // 对于10-100的值，最多保留2位小数
-            let formatted = format!("{:.2}", value);
-            format!(
-                "{}{}",
-                formatted.trim_end_matches('0').trim_end_matches('.'),
-                unit
-            )
-        } else if abs_value < 1000.0 {
-            // 对于大于100的值，最多保留1位小数
-            let formatted = format!("{:.1}", value);
-            format!(
-                "{}{}",
-                formatted.trim_end_matches('0').trim_end_matches('.'),
-                unit
-            )
+
+    /// 不做缩写的完整数值，用于悬停提示
+    pub fn full(&self, num: f64) -> String {
+        self.group(num.to_string())
+    }
+
+    fn group(&self, text: String) -> String {
+        if self.thousands_separator {
+            group_thousands(&text)
         } else {
-            // 对于大于等于1000的值，取整
-            format!("{}{}", value.round(), unit)
+            text
         }
-    } else if abs_value < 10.0 {
-        // 对于小于10的值，最多保留2位小数
-        let formatted = format!("{:.2}", value);
-        format!(
-            "{}{}",
-            formatted.trim_end_matches('0').trim_end_matches('.'),
-            unit
-        )
+    }
+}
+
+/// 在整数部分每三位插入逗号，保留符号、小数部分与单位
+fn group_thousands(text: &str) -> String {
+    let (sign, rest) = match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text),
+    };
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let (integer, tail) = rest.split_at(digits);
+    let mut grouped = String::with_capacity(text.len() + digits / 3);
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (digits - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    format!("{}{}{}", sign, grouped, tail)
+}
+
+/// 个位数最多保留 precision 位小数，数量级每增大一位少保留一位；带单位时再少保留一位
+fn format_with_precision(value: f64, unit: &str, precision: usize) -> String {
+    let abs_value = value.abs();
+    let magnitude = if abs_value < 10.0 {
+        0
     } else if abs_value < 100.0 {
-        // 对于10-100的值，最多保留1位小数
-        let formatted = format!("{:.1}", value);
+        1
+    } else if abs_value < 1000.0 {
+        2
+    } else {
+        3
+    };
+    let magnitude = if unit.is_empty() {
+        magnitude
+    } else {
+        magnitude + 1
+    };
+    let decimals = precision.saturating_sub(magnitude);
+    if magnitude >= 3 || decimals == 0 {
+        // 不保留小数时取整
+        format!("{}{}", value.round(), unit)
+    } else {
+        let formatted = format!("{:.*}", decimals, value);
         format!(
             "{}{}",
             formatted.trim_end_matches('0').trim_end_matches('.'),
             unit
         )
-    } else {
-        // 对于大于等于100的值，取整
-        format!("{}{}", value.round(), unit)
     }
 }
 #[derive(Debug, Clone)]
@@ -99,6 +190,11 @@ impl SignedCompactLabel {
         }
     }
 
+    /// 每秒的速率，换算到给定的速率单位后显示
+    pub fn rate(value: f64, unit: RateUnit) -> Self {
+        Self::new(value * unit.multiplier())
+    }
+
     pub fn with_format(mut self, format: &str) -> Self {
         self.format = Some(format.to_string());
         self
@@ -113,87 +209,63 @@ impl CompactLabel {
         }
     }
 
+    /// 每秒的速率，换算到给定的速率单位后显示
+    pub fn rate(value: f64, unit: RateUnit) -> Self {
+        Self::new(value * unit.multiplier())
+    }
+
     pub fn with_format(mut self, format: &str) -> Self {
         self.format = Some(format.to_string());
         self
     }
 }
 
+/// 加粗显示格式化后的数值，与完整数值不同时悬停显示完整数值
+#[cfg(feature = "gui")]
+fn compact_label_ui(
+    ui: &mut egui::Ui,
+    text: String,
+    format: Option<String>,
+    full: String,
+) -> egui::Response {
+    let shown = match format {
+        Some(format) => format.replace("{}", &text),
+        None => text.clone(),
+    };
+    let label =
+        ui.add(egui::Label::new(egui::RichText::new(shown).strong().size(
+            ui.style().text_styles[&egui::TextStyle::Body].size * 0.9,
+        )));
+    if text != full {
+        label.on_hover_text(full)
+    } else {
+        label
+    }
+}
+
+#[cfg(feature = "gui")]
 impl egui::Widget for SignedCompactLabel {
     fn ui(self, ui: &mut egui::Ui) -> egui::Response {
-        let text = signed_compact_number(self.value);
-        if let Some(format) = self.format {
-            let formatted_text = format.replace("{}", &text);
-            let label = ui.add(egui::Label::new(
-                egui::RichText::new(&formatted_text)
-                    .strong()
-                    .size(ui.style().text_styles[&egui::TextStyle::Body].size * 0.9),
-            ));
-            let parsed_number = text.parse::<f64>();
-            if parsed_number.is_err() {
-                label.on_hover_text(self.value.to_string())
-            } else if let Ok(n) = parsed_number
-                && f64::abs(n - self.value) > 1e-6
-            {
-                label.on_hover_text(self.value.to_string())
-            } else {
-                label
-            }
+        let number_format = NumberFormat::current();
+        let full = if self.value.is_sign_negative() {
+            number_format.full(self.value)
         } else {
-            let label =
-                ui.add(egui::Label::new(egui::RichText::new(&text).strong().size(
-                    ui.style().text_styles[&egui::TextStyle::Body].size * 0.9,
-                )));
-            let parsed_number = text.parse::<f64>();
-            if parsed_number.is_err() {
-                label.on_hover_text(self.value.to_string())
-            } else if let Ok(n) = parsed_number
-                && f64::abs(n - self.value) > 1e-6
-            {
-                label.on_hover_text(self.value.to_string())
-            } else {
-                label
-            }
-        }
+            format!("+{}", number_format.full(self.value))
+        };
+        compact_label_ui(ui, number_format.signed(self.value), self.format, full)
     }
 }
 
+#[cfg(feature = "gui")]
 impl egui::Widget for CompactLabel {
     fn ui(self, ui: &mut egui::Ui) -> egui::Response {
-        let text = compact_number(self.value);
-        if let Some(format) = self.format {
-            let formatted_text = format.replace("{}", &text);
-            let label = ui.add(egui::Label::new(
-                egui::RichText::new(&formatted_text)
-                    .strong()
-                    .size(ui.style().text_styles[&egui::TextStyle::Body].size * 0.9),
-            ));
-            let parsed_number = text.parse::<f64>();
-            if parsed_number.is_err() {
-                label.on_hover_text(self.value.to_string())
-            } else if let Ok(n) = parsed_number
-                && f64::abs(n - self.value) > 1e-6
-            {
-                label.on_hover_text(self.value.to_string())
-            } else {
-                label
-            }
-        } else {
-            let label =
-                ui.add(egui::Label::new(egui::RichText::new(&text).strong().size(
-                    ui.style().text_styles[&egui::TextStyle::Body].size * 0.9,
-                )));
-            let parsed_number = text.parse::<f64>();
-            if parsed_number.is_err() {
-                label.on_hover_text(self.value.to_string())
-            } else if let Ok(n) = parsed_number
-                && f64::abs(n - self.value) > 1e-6
-            {
-                label.on_hover_text(self.value.to_string())
-            } else {
-                label
-            }
-        }
+        let number_format = NumberFormat::current();
+        compact_label_ui(
+            ui,
+            number_format.compact(self.value),
+            self.format,
+            number_format.full(self.value),
+        )
     }
 }
 
@@ -204,4 +276,30 @@ fn test_compact_format() {
     dbg!(compact_number(1919810.1));
     dbg!(compact_number(123456789.1));
     dbg!(compact_number(0.00011));
+    assert_eq!(format_with_precision(1.23456, "", 3), "1.235");
+    assert_eq!(format_with_precision(12.3456, "", 3), "12.35");
+    assert_eq!(format_with_precision(1.23456, "", 1), "1.2");
+    assert_eq!(format_with_precision(12.3456, "", 1), "12");
+    assert_eq!(format_with_precision(1.5, "k", 0), "2k");
+    assert_eq!(format_with_precision(120.0, "", 0), "120");
+
+    let format = NumberFormat {
+        precision: 3,
+        si_suffixes: false,
+        thousands_separator: true,
+        exact_rational: false,
+    };
+    assert_eq!(format.compact(1919810.1), "1,919,810");
+    assert_eq!(format.compact(1234.5678), "1,235");
+    assert_eq!(format.compact(0.00011), "1.10e-4");
+    assert_eq!(format.signed(-12.3456), "-12.35");
+    assert_eq!(format.full(-1234567.25), "-1,234,567.25");
+    let format = NumberFormat {
+        si_suffixes: true,
+        ..format
+    };
+    assert_eq!(format.compact(1919810.1), "1.92M");
+    assert_eq!(format.compact(0.00011), "110μ");
+    assert_eq!(group_thousands("999"), "999");
+    assert_eq!(group_thousands("1000k"), "1,000k");
 }
@@ -1,11 +1,13 @@
 mod common;
 mod model;
 
+#[cfg(feature = "gui")]
 mod editor;
 mod format;
 
 // 重导出 model 下的所有结构体
 pub use common::*;
+#[cfg(feature = "gui")]
 pub use editor::*;
 pub use format::*;
 pub use model::*;
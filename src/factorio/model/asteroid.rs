@@ -0,0 +1,311 @@
+#[cfg(feature = "gui")]
+use crate::factorio::{editor::icon::Icon, modal::ItemWithQualitySelectorModal};
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        model::{context::*, energy::*, entity::*},
+    },
+};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AsteroidCollectorPrototype {
+    #[serde(flatten)]
+    pub base: EntityPrototype,
+
+    pub energy_source: EnergySource,
+    /// 机械臂静止时每刻消耗的能量
+    #[serde(default)]
+    pub passive_energy_usage: Option<EnergyAmount>,
+}
+
+impl HasPrototypeBase for AsteroidCollectorPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base.base
+    }
+}
+
+/// 太空平台边缘的小行星收集器，按星球轨道或航线上碎块的平均生成频率收集碎块
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:asteroid-collector")]
+pub struct AsteroidCollectorConfig {
+    pub machine: IdWithQuality,
+    /// 星球轨道或航线，工厂选择了航线时随工厂的航线变化
+    pub route: String,
+    /// 每台收集器捕获到的碎块占航线上生成量的比例，取决于平台宽度与收集器的排布
+    pub capture_fraction: f64,
+}
+
+impl Default for AsteroidCollectorConfig {
+    fn default() -> Self {
+        AsteroidCollectorConfig {
+            machine: ("entity-unknown".to_string(), 0).into(),
+            route: "nauvis".to_string(),
+            capture_fraction: 1.0,
+        }
+    }
+}
+
+impl AsteroidCollectorConfig {
+    /// 每台收集器每秒收集到的各种碎块，小行星本身需要先击碎，不计入
+    pub fn chunk_rates(&self, ctx: &FactorioContext) -> Vec<(String, f64)> {
+        if !ctx.asteroid_collectors.contains_key(&self.machine.0) {
+            return vec![];
+        }
+        ctx.route_asteroid_spawn_rates(&self.route)
+            .into_iter()
+            .filter(|(chunk, _)| ctx.items.contains_key(chunk))
+            .map(|(chunk, rate)| (chunk, rate * self.capture_fraction.clamp(0.0, 1.0)))
+            .collect()
+    }
+
+    /// 求解时代表航线上全部碎块的虚拟物品及每台收集器占用的份额。
+    /// 每条航线只供应 1 份，同一航线上的收集器合计最多捕获航线上生成的全部碎块
+    pub fn route_capture(&self, ctx: &FactorioContext) -> Option<(GenericItem, f64)> {
+        if !ctx.asteroid_collectors.contains_key(&self.machine.0) {
+            return None;
+        }
+        Some((
+            GenericItem::Custom {
+                name: format!("asteroid-capture:{}", self.route),
+            },
+            self.capture_fraction.clamp(0.0, 1.0),
+        ))
+    }
+}
+
+impl SolveContext for AsteroidCollectorConfig {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for AsteroidCollectorConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        let mut map = Flow::new();
+        let Some(collector) = ctx.asteroid_collectors.get(&self.machine.0) else {
+            return map;
+        };
+        if let Some(energy_usage) = &collector.passive_energy_usage {
+            let mut fulfillment = 1.0;
+            let energy_related_flow = energy_source_as_flow(
+                ctx,
+                &collector.energy_source,
+                energy_usage,
+                &Effect::default(),
+                &None,
+                &mut fulfillment,
+            );
+            for (key, value) in energy_related_flow.into_iter() {
+                index_map_update_entry(&mut map, key, value);
+            }
+        }
+        for (chunk, rate) in self.chunk_rates(ctx) {
+            index_map_update_entry(&mut map, GenericItem::Item(chunk.as_str().into()), rate);
+        }
+        map
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        ctx.asteroid_collectors
+            .get(&self.machine.0)
+            .map_or(16.0, |collector| {
+                collector
+                    .base
+                    .collision_box
+                    .as_ref()
+                    .map_or(1.0, BoundingBox::tile_area)
+            })
+    }
+}
+
+impl EditorView for AsteroidCollectorConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("机器"));
+                let entity_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0).with_quality(self.machine.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(if ctx.asteroid_collectors.contains_key(&self.machine.0) {
+                        ctx.get_display_name("entity", &self.machine.0)
+                    } else {
+                        "小行星收集器：未选择".into()
+                    });
+                ui.add(
+                    ItemWithQualitySelectorModal::new(
+                        entity_button.id,
+                        ctx,
+                        "选择小行星收集器",
+                        "entity",
+                    )
+                    .with_toggle(entity_button.clicked())
+                    .with_current(&mut self.machine)
+                    .with_filter(|s, f| f.asteroid_collectors.contains_key(s))
+                    .notify_change(&mut changed),
+                );
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.label("航线");
+                egui::ComboBox::new(ui.id().with("asteroid-route"), "")
+                    .selected_text(ctx.route_display_name(&self.route))
+                    .show_ui(ui, |ui| {
+                        for route in ctx.routes() {
+                            let text = ctx.route_display_name(&route);
+                            changed |= ui.selectable_value(&mut self.route, route, text).changed();
+                        }
+                    });
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.label("捕获比例");
+                changed |= ui
+                    .add(
+                        egui::Slider::new(&mut self.capture_fraction, 0.0..=1.0)
+                            .custom_formatter(|value, _| format!("{:.0}%", value * 100.0)),
+                    )
+                    .on_hover_text("每台收集器捕获到的碎块占航线上生成量的比例")
+                    .changed();
+            });
+            let rates = self.chunk_rates(ctx);
+            if !rates.is_empty() {
+                ui.separator();
+                ui.vertical(|ui| {
+                    for (chunk, rate) in rates {
+                        ui.label(format!(
+                            "{}：{:.3}/s",
+                            ctx.get_display_name("item", &chunk),
+                            rate
+                        ));
+                    }
+                });
+            }
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:asteroid-collector")]
+pub struct AsteroidCollectorConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+}
+
+impl Default for AsteroidCollectorConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AsteroidCollectorConfigProvider {
+    pub fn new() -> Self {
+        Self { sender: None }
+    }
+}
+
+impl SolveContext for AsteroidCollectorConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for AsteroidCollectorConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        if ui.button("添加小行星收集器").clicked() {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(AsteroidCollectorConfig::default()));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for AsteroidCollectorConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        let GenericItem::Item(IdWithQuality(name, 0)) = item else {
+            return vec![];
+        };
+        if value >= 0.0 {
+            return vec![];
+        }
+        // 选择该碎块生成最多的位置
+        let Some(collector) = ctx.asteroid_collectors.keys().min() else {
+            return vec![];
+        };
+        let route = ctx
+            .routes()
+            .into_iter()
+            .filter_map(|route| {
+                let rate = ctx.route_asteroid_spawn_rates(&route).get(name).copied()?;
+                Some((route, rate))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        let Some((route, _)) = route else {
+            return vec![];
+        };
+        vec![Box::new(AsteroidCollectorConfig {
+            machine: collector.as_str().into(),
+            route,
+            capture_fraction: 1.0,
+        })]
+    }
+}
+
+#[test]
+fn test_asteroid_collector() {
+    let ctx = FactorioContext::test_load();
+    let metallic = GenericItem::Item("metallic-asteroid-chunk".into());
+    let mut collector = AsteroidCollectorConfig {
+        machine: "asteroid-collector".into(),
+        route: "nauvis".to_string(),
+        capture_fraction: 1.0,
+    };
+    // 诺维斯轨道上金属碎块每刻生成的概率为 0.0125
+    let orbit = collector.as_flow(&ctx);
+    assert!((orbit[&metallic] - 0.0125 * 60.0).abs() < 1e-9);
+    // 小行星本身不会被收集，静止时消耗 200J/刻
+    assert!(!orbit.contains_key(&GenericItem::Item("medium-metallic-asteroid".into())));
+    assert!(orbit[&GenericItem::Electricity] < 0.0);
+    // 产量随航线的平均生成频率与捕获比例变化
+    collector.route = "nauvis-vulcanus".to_string();
+    collector.capture_fraction = 0.5;
+    let route = ctx.route_asteroid_spawn_rates("nauvis-vulcanus");
+    let flow = collector.as_flow(&ctx);
+    assert!((flow[&metallic] - route["metallic-asteroid-chunk"] * 0.5).abs() < 1e-9);
+    assert!(flow[&metallic] < orbit[&metallic]);
+    let hints = AsteroidCollectorConfigProvider::new().hint_populate(&ctx, &metallic, -1.0);
+    assert_eq!(hints.len(), 1);
+    assert!(hints[0].as_flow(&ctx)[&metallic] > 0.0);
+}
+
+crate::impl_register_deserializer!(
+    for AsteroidCollectorConfig
+    as "factorio:asteroid-collector"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for AsteroidCollectorConfigProvider
+    as "factorio:asteroid-collector"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
@@ -0,0 +1,303 @@
+#[cfg(feature = "gui")]
+use crate::factorio::{editor::icon::Icon, modal::ItemWithQualitySelectorModal};
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        model::{context::*, energy::*, entity::*},
+    },
+};
+
+/// 锅炉与热交换器，二者都是 boiler 类型，区别只在能源
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BoilerPrototype {
+    #[serde(flatten)]
+    pub base: EntityPrototype,
+
+    /// 每刻消耗的能量
+    pub energy_consumption: EnergyAmount,
+    pub energy_source: EnergySource,
+
+    /// 输入流体
+    pub fluid_box: FluidBox,
+    /// 输出流体
+    pub output_fluid_box: FluidBox,
+
+    /// 输出流体的温度
+    pub target_temperature: f64,
+}
+
+impl HasPrototypeBase for BoilerPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base.base
+    }
+}
+
+impl BoilerPrototype {
+    /// 每秒加热的流体量，按输出流体的热容计算，缺少热容或已达到目标温度时为 0
+    pub fn fluid_per_second(&self, ctx: &FactorioContext) -> f64 {
+        let fluid = |fluid_box: &FluidBox| {
+            fluid_box
+                .filter
+                .as_ref()
+                .and_then(|filter| ctx.fluids.get(filter))
+        };
+        let (Some(input), Some(output)) = (fluid(&self.fluid_box), fluid(&self.output_fluid_box))
+        else {
+            return 0.0;
+        };
+        let Some(heat_capacity) = &output.heat_capacity else {
+            return 0.0;
+        };
+        let temperature_diff = self.target_temperature - input.default_temperature;
+        if temperature_diff <= 0.0 || heat_capacity.amount <= 0.0 {
+            return 0.0;
+        }
+        self.energy_consumption.amount * 60.0 / heat_capacity.amount / temperature_diff
+    }
+}
+
+/// 将输入流体加热到目标温度后输出，如锅炉用水产生 165 度蒸汽，热交换器产生 500 度蒸汽
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:boiler")]
+pub struct BoilerConfig {
+    pub machine: IdWithQuality,
+    pub instance_fuel: Option<IdWithQuality>,
+}
+
+impl Default for BoilerConfig {
+    fn default() -> Self {
+        BoilerConfig {
+            machine: ("entity-unknown".to_string(), 0).into(),
+            instance_fuel: None,
+        }
+    }
+}
+
+impl SolveContext for BoilerConfig {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for BoilerConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        let mut map = Flow::new();
+        let Some(boiler) = ctx.boilers.get(&self.machine.0) else {
+            return map;
+        };
+        let mut fulfillment = 1.0;
+        let energy_related_flow = energy_source_as_flow(
+            ctx,
+            &boiler.energy_source,
+            &boiler.energy_consumption,
+            &Effect::default(),
+            &self
+                .instance_fuel
+                .as_ref()
+                .map(|id_with_quality| (id_with_quality.0.clone(), id_with_quality.1 as i32)),
+            &mut fulfillment,
+        );
+        for (key, value) in energy_related_flow.into_iter() {
+            index_map_update_entry(&mut map, key, value);
+        }
+        let amount = boiler.fluid_per_second(ctx) * fulfillment;
+        if let Some(input) = &boiler.fluid_box.filter {
+            index_map_update_entry(
+                &mut map,
+                GenericItem::Fluid {
+                    name: input.clone(),
+                    temperature: None,
+                },
+                -amount,
+            );
+        }
+        if let Some(output) = &boiler.output_fluid_box.filter {
+            index_map_update_entry(
+                &mut map,
+                GenericItem::Fluid {
+                    name: output.clone(),
+                    temperature: Some(boiler.target_temperature as i32),
+                },
+                amount,
+            );
+        }
+        map
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        ctx.boilers.get(&self.machine.0).map_or(16.0, |boiler| {
+            boiler
+                .base
+                .collision_box
+                .as_ref()
+                .map_or(1.0, BoundingBox::tile_area)
+        })
+    }
+}
+
+impl EditorView for BoilerConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("机器"));
+                let entity_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0).with_quality(self.machine.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(match ctx.boilers.get(&self.machine.0) {
+                        Some(boiler) => format!(
+                            "{}：{} 度",
+                            ctx.get_display_name("entity", &self.machine.0),
+                            boiler.target_temperature
+                        ),
+                        None => "锅炉：未选择".into(),
+                    });
+                ui.add(
+                    ItemWithQualitySelectorModal::new(
+                        entity_button.id,
+                        ctx,
+                        "选择锅炉或热交换器",
+                        "entity",
+                    )
+                    .with_toggle(entity_button.clicked())
+                    .with_current(&mut self.machine)
+                    .with_filter(|s, f| f.boilers.contains_key(s))
+                    .notify_change(&mut changed),
+                );
+            });
+        });
+        if changed {
+            self.instance_fuel = None;
+        }
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:boiler")]
+pub struct BoilerConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+}
+
+impl Default for BoilerConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BoilerConfigProvider {
+    pub fn new() -> Self {
+        Self { sender: None }
+    }
+}
+
+impl SolveContext for BoilerConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for BoilerConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        if ui.button("添加锅炉").clicked() {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(BoilerConfig::default()));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for BoilerConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        let GenericItem::Fluid { name, temperature } = item else {
+            return vec![];
+        };
+        if value >= 0.0 {
+            return vec![];
+        }
+        // 输出温度不低于要求的锅炉都可以提供该流体
+        let mut boilers = ctx
+            .boilers
+            .values()
+            .filter(|boiler| {
+                boiler.output_fluid_box.filter.as_ref() == Some(name)
+                    && temperature.is_none_or(|t| boiler.target_temperature >= t as f64)
+            })
+            .map(|boiler| &boiler.base.base.name)
+            .collect::<Vec<_>>();
+        boilers.sort();
+        boilers
+            .into_iter()
+            .map(|boiler| {
+                Box::new(BoilerConfig {
+                    machine: boiler.as_str().into(),
+                    instance_fuel: None,
+                })
+                    as Box<dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>>
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_boiler() {
+    let ctx = FactorioContext::test_load();
+    let steam = |temperature| GenericItem::Fluid {
+        name: "steam".to_string(),
+        temperature: Some(temperature),
+    };
+    let water = GenericItem::Fluid {
+        name: "water".to_string(),
+        temperature: None,
+    };
+    let boiler = BoilerConfig {
+        machine: "boiler".into(),
+        instance_fuel: None,
+    }
+    .as_flow(&ctx);
+    // 1.8MW 将水从 15 度加热到 165 度，每秒 60 单位
+    assert!((boiler[&steam(165)] - 60.0).abs() < 1e-6);
+    assert!((boiler[&water] + 60.0).abs() < 1e-6);
+    let heat_exchanger = BoilerConfig {
+        machine: "heat-exchanger".into(),
+        instance_fuel: None,
+    }
+    .as_flow(&ctx);
+    assert!((heat_exchanger[&steam(500)] - 1e7 / 200.0 / 485.0).abs() < 1e-6);
+    assert!((heat_exchanger[&GenericItem::Heat] + 1e7).abs() < 1e-6);
+    let provider = BoilerConfigProvider::new();
+    assert_eq!(provider.hint_populate(&ctx, &steam(165), -1.0).len(), 2);
+    assert_eq!(provider.hint_populate(&ctx, &steam(500), -1.0).len(), 1);
+}
+
+crate::impl_register_deserializer!(
+    for BoilerConfig
+    as "factorio:boiler"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for BoilerConfigProvider
+    as "factorio:boiler"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
@@ -0,0 +1,100 @@
+//! 上下文的二进制缓存。
+//!
+//! 首次解析原始数据后，将构建好的上下文以 MessagePack 格式写入工作目录，之后启动时直接读取。
+//! 文件开头是缓存头，记录缓存格式版本与模组列表、原始数据文件的哈希，任何一项不一致时缓存失效。
+
+use std::{
+    hash::{Hash, Hasher},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use crate::{error::AppError, factorio::model::FactorioContext};
+
+/// 缓存格式版本，原型结构发生变化时递增
+pub const CONTEXT_CACHE_VERSION: u32 = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct ContextCacheHeader {
+    version: u32,
+    key: u64,
+}
+
+/// 缓存文件放在上下文各自的导出目录中，见 context_dump_dir
+pub fn context_cache_path(dump_dir: &Path) -> PathBuf {
+    dump_dir.join("context-cache.bin")
+}
+
+/// 由模组列表与原始数据文件的大小、修改时间计算缓存键，原始数据文件不存在时返回 None
+pub fn context_cache_key(mods: &[(String, String)], raw_path: &Path) -> Option<u64> {
+    let metadata = std::fs::metadata(raw_path).ok()?;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    mods.hash(&mut hasher);
+    metadata.len().hash(&mut hasher);
+    metadata.modified().ok().hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+pub fn save_context_cache(ctx: &FactorioContext, path: &Path, key: u64) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::File::create(path)
+        .map_err(|e| AppError::Io(format!("创建缓存文件 {} 失败：{}", path.display(), e)))?;
+    let mut writer = BufWriter::new(file);
+    let header = ContextCacheHeader {
+        version: CONTEXT_CACHE_VERSION,
+        key,
+    };
+    rmp_serde::encode::write_named(&mut writer, &header)
+        .and_then(|_| rmp_serde::encode::write_named(&mut writer, ctx))
+        .map_err(|e| AppError::Io(format!("写入缓存文件 {} 失败：{}", path.display(), e)))?;
+    log::info!("上下文缓存已写入 {}", path.display());
+    Ok(())
+}
+
+/// 读取缓存，缓存不存在、已失效或损坏时返回 None
+pub fn load_context_cache(path: &Path, key: u64) -> Option<FactorioContext> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut reader = BufReader::new(file);
+    let header: ContextCacheHeader = rmp_serde::decode::from_read(&mut reader).ok()?;
+    if header
+        != (ContextCacheHeader {
+            version: CONTEXT_CACHE_VERSION,
+            key,
+        })
+    {
+        log::info!("上下文缓存已失效");
+        return None;
+    }
+    match rmp_serde::decode::from_read(&mut reader) {
+        Ok(ctx) => Some(ctx),
+        Err(err) => {
+            log::warn!("读取上下文缓存失败: {:?}", err);
+            None
+        }
+    }
+}
+
+#[test]
+fn test_context_cache() {
+    let ctx = FactorioContext::test_load();
+    // 带上进程号，避免同时运行的多个测试进程读写同一个文件
+    let path = std::env::temp_dir().join(format!(
+        "metatorio-test-context-cache-{}.bin",
+        std::process::id()
+    ));
+    save_context_cache(&ctx, &path, 42).unwrap();
+    assert!(load_context_cache(&path, 43).is_none());
+    let cached = load_context_cache(&path, 42)
+        .unwrap()
+        .build_order_info()
+        .build_producer_info();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(cached.entities.len(), ctx.entities.len());
+    assert_eq!(cached.qualities.len(), ctx.qualities.len());
+    assert_eq!(
+        serde_json::to_value(crate::factorio::model::ContextExport::new(&cached)).unwrap(),
+        serde_json::to_value(crate::factorio::model::ContextExport::new(&ctx)).unwrap()
+    );
+}
@@ -1,16 +1,16 @@
+use std::{collections::HashMap, fmt::Debug, hash::Hash, path::PathBuf};
+#[cfg(not(target_arch = "wasm32"))]
 use std::{
-    collections::HashMap,
     env,
-    fmt::Debug,
-    hash::Hash,
     io::Write,
-    path::PathBuf,
     process::{Command, Stdio},
 };
 
 use indexmap::IndexMap;
-use serde_json::Value;
+use serde_json::{Value, value::RawValue};
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::i18n::tr;
 use crate::{concept::*, error::AppError, factorio::*};
 
 pub const LOCALE_CATEGORIES: &[&str] = &[
@@ -24,30 +24,124 @@ pub const LOCALE_CATEGORIES: &[&str] = &[
     "quality",
     "recipe",
     "space-location",
+    "surface",
+    "surface-property",
     "technology",
     "tile",
 ];
 
-#[derive(Debug, Clone, Default)]
+/// 游戏支持的界面语言代码及其本地名称
+pub const GAME_LOCALES: &[(&str, &str)] = &[
+    ("zh-CN", "简体中文"),
+    ("zh-TW", "繁體中文"),
+    ("en", "English"),
+    ("ja", "日本語"),
+    ("ko", "한국어"),
+    ("ru", "Русский"),
+    ("de", "Deutsch"),
+    ("fr", "Français"),
+    ("es-ES", "Español"),
+    ("pt-BR", "Português (Brasil)"),
+    ("pl", "Polski"),
+    ("uk", "Українська"),
+];
+
+/// 语言代码对应的本地名称，未收录的代码原样返回
+pub fn locale_display_name(code: &str) -> &str {
+    GAME_LOCALES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, name)| *name)
+        .unwrap_or(code)
+}
+
+/// 一种语言下各类别原型的名称与描述
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct LocaleTable {
+    pub names: Dict<Dict<String>>,
+    pub descriptions: Dict<Dict<String>>,
+}
+
+impl LocaleTable {
+    /// 读取 --dump-prototype-locale 导出的各类别翻译文件，缺失的类别记为空表
+    pub fn load_dir(dir: &std::path::Path) -> Result<Self, AppError> {
+        let mut table = LocaleTable::default();
+        for locale_category in LOCALE_CATEGORIES.iter() {
+            log::info!("加载翻译类别 {}", locale_category);
+            let locale_path = dir.join(format!("{}-locale.json", locale_category));
+            if locale_path.exists() {
+                // name: a => A, b => B
+                // description: a => A desc, b => B desc
+                let locale_values: Dict<Dict<String>> = serde_json::from_str(
+                    &std::fs::read_to_string(&locale_path).map_err(|_| {
+                        AppError::ContextCreation(format!(
+                            "读取翻译数据文件失败: {:?}",
+                            locale_path
+                        ))
+                    })?,
+                )?;
+                table.names.insert(
+                    locale_category.to_string(),
+                    locale_values.get("names").cloned().unwrap_or_default(),
+                );
+                table.descriptions.insert(
+                    locale_category.to_string(),
+                    locale_values
+                        .get("descriptions")
+                        .cloned()
+                        .unwrap_or_default(),
+                );
+            } else {
+                table.names.insert(locale_category.to_string(), Dict::new());
+                table
+                    .descriptions
+                    .insert(locale_category.to_string(), Dict::new());
+                log::warn!("翻译类别 {} 的文件不存在，跳过", locale_category);
+            }
+        }
+        Ok(table)
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct FactorioContext {
     /// 模组信息
     pub mods: Vec<(String, String)>,
+    /// 导出数据所在目录的标识，见 context_dump_dir；上传创建的上下文为空
+    #[serde(default)]
+    pub dump_key: String,
     /// 图标路径
     pub icon_path: std::path::PathBuf,
-    /// 翻译信息
+    /// 当前语言的翻译信息
     pub localized_name: Dict<Dict<String>>,
     pub localized_description: Dict<Dict<String>>,
+    /// 已加载的全部语言，切换语言时从中复制到 localized_name 与 localized_description
+    pub locales: IndexMap<String, LocaleTable>,
+    /// 当前语言代码
+    pub locale: String,
     /// 排序参考依据
     pub groups: Dict<PrototypeBase>,
     pub subgroups: Dict<ItemSubgroup>,
 
     /// 地点
     pub planets: Dict<PlanetPrototype>,
+    /// 星球以外的地表，如太空平台
+    pub surfaces: Dict<SurfacePrototype>,
+    /// 地表属性
+    pub surface_properties: Dict<SurfacePropertyPrototype>,
+    /// 星球之间的航线
+    pub space_connections: Dict<SpaceConnectionPrototype>,
 
     /// 品质
     pub qualities: Vec<QualityPrototype>,
 
+    /// 火箭载重与默认物品重量
+    pub rocket_constants: RocketConstants,
+
+    /// 以下由 build_order_info 与 build_producer_info 生成，不写入缓存
+    #[serde(skip)]
     pub ordered_entries: HashMap<String, OrderInfo>,
+    #[serde(skip)]
     pub order_of_entries: HashMap<String, ReverseOrderInfo>,
 
     /// 被转化的物品集合
@@ -66,8 +160,89 @@ pub struct FactorioContext {
     pub resources: Dict<ResourcePrototype>,
     pub miners: Dict<MiningDrillPrototype>,
 
+    /// 抽水泵，从流体地块抽取流体
+    pub offshore_pumps: Dict<OffshorePumpPrototype>,
+
+    /// 小行星收集器，在太空平台上收集碎块
+    pub asteroid_collectors: Dict<AsteroidCollectorPrototype>,
+
+    /// 锅炉与热交换器，加热流体
+    pub boilers: Dict<BoilerPrototype>,
+
+    /// 反应堆，燃烧燃料产生热量
+    pub reactors: Dict<ReactorPrototype>,
+
+    /// 发电机，消耗流体发电
+    pub generators: Dict<GeneratorPrototype>,
+
+    /// 聚变反应堆，产生等离子体
+    pub fusion_reactors: Dict<FusionReactorPrototype>,
+
+    /// 聚变发电机，消耗等离子体发电
+    pub fusion_generators: Dict<FusionGeneratorPrototype>,
+
+    /// 研究中心，消耗科技包进行研究
+    pub labs: Dict<LabPrototype>,
+
+    /// 推进器，消耗燃料与氧化剂推动太空平台
+    pub thrusters: Dict<ThrusterPrototype>,
+
+    /// 炮塔，消耗弹药、电力或流体进行防御
+    pub turrets: Dict<TurretPrototype>,
+
+    /// 太阳能板
+    pub solar_panels: Dict<SolarPanelPrototype>,
+
+    /// 蓄电池
+    pub accumulators: Dict<AccumulatorPrototype>,
+
+    /// 避雷针与集电器
+    pub lightning_attractors: Dict<LightningAttractorPrototype>,
+
+    /// 闪电
+    pub lightnings: Dict<LightningPrototype>,
+
     /// 地块
     pub tiles: Dict<TilePrototype>,
+
+    /// 科技
+    pub technologies: Dict<TechnologyPrototype>,
+
+    /// 能够产出各物品、流体的配方与资源数量，按 "item" / "fluid" 分类
+    #[serde(skip)]
+    pub producer_counts: Dict<Dict<usize>>,
+
+    /// 产出、消耗各物品与流体的配方名，按 "item" / "fluid" 分类，由 build_order_info 生成
+    #[serde(skip)]
+    pub recipe_producers: Dict<Dict<Vec<String>>>,
+    #[serde(skip)]
+    pub recipe_consumers: Dict<Dict<Vec<String>>>,
+    /// 放置各实体的物品名
+    #[serde(skip)]
+    pub entity_placers: Dict<Vec<String>>,
+    /// 各物品的重量，由 build_producer_info 推算
+    #[serde(skip)]
+    pub item_weights: Dict<f64>,
+
+    /// 各原型的图标图层，按导出图标的文件夹分类，仅在合成缺失图标时使用
+    #[serde(skip)]
+    pub icon_specs: Dict<Dict<IconSpecification>>,
+}
+
+/// 反向索引中物品与流体的键，不区分品质与温度
+fn recipe_index_key(item: &GenericItem) -> Option<(&'static str, &str)> {
+    match item {
+        GenericItem::Item(IdWithQuality(name, _)) => Some(("item", name)),
+        GenericItem::Fluid { name, .. } => Some(("fluid", name)),
+        _ => None,
+    }
+}
+
+fn recipe_index_lookup<'a>(index: &'a Dict<Dict<Vec<String>>>, item: &GenericItem) -> &'a [String] {
+    recipe_index_key(item)
+        .and_then(|(category, name)| index.get(category)?.get(name))
+        .map(Vec::as_slice)
+        .unwrap_or_default()
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -78,114 +253,241 @@ pub struct ModInfo {
     pub enabled: bool,
 }
 
+/// 读取 mod-list.json 中启用的模组及其版本
+fn read_enabled_mods(
+    mod_list_json_path: &std::path::Path,
+) -> Result<Vec<(String, String)>, AppError> {
+    let mod_infos_json =
+        serde_json::from_str::<Value>(&std::fs::read_to_string(mod_list_json_path)?)?;
+    let mod_infos = serde_json::from_value::<Vec<ModInfo>>(
+        mod_infos_json
+            .get("mods")
+            .ok_or(AppError::ContextCreation(
+                "mod-list.json格式不正确".to_string(),
+            ))?
+            .clone(),
+    )?;
+    let mut mods = vec![];
+    for mod_info in mod_infos {
+        if mod_info.enabled {
+            log::info!("启用模组 {}", &mod_info.name);
+            mods.push((mod_info.name, mod_info.version));
+        }
+    }
+    Ok(mods)
+}
+
+/// 读取临时 config.ini 中的语言设置
+fn read_config_locale(config_path: &std::path::Path) -> Option<String> {
+    std::fs::read_to_string(config_path)
+        .ok()?
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("locale="))
+        .map(|lang| lang.trim().to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 pub fn get_workding_directory() -> PathBuf {
     env::current_exe().unwrap().parent().unwrap().to_path_buf()
 }
 
-impl FactorioContext {
-    pub fn test_load() -> Self {
-        let value = serde_json::from_str::<Value>(
-            std::fs::read_to_string("assets/data-raw-dump.json")
-                .unwrap()
-                .as_str(),
-        );
-        FactorioContext::load(&value.unwrap()).build_order_info()
+/// 网页版没有可执行文件所在的目录，读写文件都会失败并按文件不存在处理
+#[cfg(target_arch = "wasm32")]
+pub fn get_workding_directory() -> PathBuf {
+    PathBuf::new()
+}
+
+/// 各上下文导出数据与缓存所在的目录，不同的游戏与模组列表互不覆盖。
+/// 空的标识对应旧版本直接导出在 tmp 下的数据
+pub fn context_dump_dir(key: &str) -> PathBuf {
+    let tmp = get_workding_directory().join("tmp");
+    if key.is_empty() {
+        tmp
+    } else {
+        tmp.join("contexts").join(key)
     }
-    pub fn load(value: &Value) -> Self {
-        let groups: Dict<PrototypeBase> = serde_json::from_value(
-            value
-                .get("item-group")
-                .cloned()
-                .unwrap_or_else(|| Value::Object(serde_json::Map::new())),
-        )
-        .unwrap();
-        let subgroups: Dict<ItemSubgroup> = serde_json::from_value(
-            value
-                .get("item-subgroup")
-                .cloned()
-                .unwrap_or_else(|| Value::Object(serde_json::Map::new())),
-        )
-        .unwrap();
-        let mut items = Dict::<ItemPrototype>::new();
-        for item_type in ITEM_TYPES.iter() {
-            if let Some(item_values) = value.get(item_type) {
-                items.extend(
-                    serde_json::from_value::<Dict<ItemPrototype>>(item_values.clone()).unwrap(),
-                );
+}
+
+/// 由游戏可执行文件路径与模组列表文件的内容计算导出目录的标识
+pub fn context_dump_key(
+    executable_path: &std::path::Path,
+    mod_path: Option<&std::path::Path>,
+) -> String {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    executable_path.hash(&mut hasher);
+    mod_path
+        .and_then(|mod_path| std::fs::read(mod_path.join("mod-list.json")).ok())
+        .hash(&mut hasher);
+    format!("{:016x}", std::hash::Hasher::finish(&hasher))
+}
+
+/// 最近一次导出数据的目录标识，没有按标识区分的导出时使用旧版本的 tmp 目录
+pub fn latest_dump_key() -> String {
+    std::fs::read_dir(get_workding_directory().join("tmp/contexts"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let raw = entry.path().join("script-output/data-raw-dump.json");
+            let modified = std::fs::metadata(raw)
+                .and_then(|meta| meta.modified())
+                .ok()?;
+            Some((modified, entry.file_name().to_string_lossy().to_string()))
+        })
+        .max()
+        .map(|(_, key)| key)
+        .unwrap_or_default()
+}
+
+/// 计算器用到的原型类别，其余类别在解析时直接跳过
+const PROTOTYPE_CATEGORIES: &[&str] = &[
+    "item-group",
+    "item-subgroup",
+    "fluid",
+    "recipe",
+    "resource",
+    "mining-drill",
+    "module",
+    "beacon",
+    "quality",
+    "planet",
+    "lightning",
+    "surface",
+    "surface-property",
+    "tile",
+    "technology",
+    "utility-constants",
+    "space-connection",
+];
+
+/// 原始数据中用到的各个类别，保留为未解析的 JSON 文本
+///
+/// 同一类别可能要按多种原型解析（例如组装机既是实体也是制造设备），
+/// 先保存原文再分别解析，避免构建整个文件的 Value 树。
+struct RawCategories(HashMap<String, Box<RawValue>>);
+
+impl<'de> serde::Deserialize<'de> for RawCategories {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct RawCategoriesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for RawCategoriesVisitor {
+            type Value = RawCategories;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("以原型类别为键的对象")
             }
-        }
-        let mut entities = Dict::<EntityPrototype>::new();
-        for entity_type in ENTITY_TYPES.iter() {
-            if let Some(entity_values) = value.get(entity_type) {
-                entities.extend(
-                    serde_json::from_value::<Dict<EntityPrototype>>(entity_values.clone()).unwrap(),
-                );
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut categories = HashMap::new();
+                while let Some(key) = map.next_key::<String>()? {
+                    let key_str = key.as_str();
+                    if PROTOTYPE_CATEGORIES.contains(&key_str)
+                        || ITEM_TYPES.contains(&key_str)
+                        || ENTITY_TYPES.contains(&key_str)
+                        || CRAFTING_MACHINE_TYPES.contains(&key_str)
+                    {
+                        categories.insert(key, map.next_value::<Box<RawValue>>()?);
+                    } else {
+                        map.next_value::<serde::de::IgnoredAny>()?;
+                    }
+                }
+                Ok(RawCategories(categories))
             }
         }
-        let fluids: Dict<FluidPrototype> = serde_json::from_value(
-            value
-                .get("fluid")
-                .cloned()
-                .unwrap_or_else(|| Value::Object(serde_json::Map::new())),
-        )
-        .unwrap();
-        let recipes: Dict<RecipePrototype> = serde_json::from_value(
-            value
-                .get("recipe")
-                .cloned()
-                .unwrap_or_else(|| Value::Object(serde_json::Map::new())),
-        )
-        .unwrap();
-        let mut crafters = Dict::<CraftingMachinePrototype>::new();
-        for crafter_type in CRAFTING_MACHINE_TYPES.iter() {
-            if let Some(crafter_values) = value.get(crafter_type) {
-                crafters.extend(
-                    serde_json::from_value::<Dict<CraftingMachinePrototype>>(
-                        crafter_values.clone(),
-                    )
-                    .unwrap(),
-                );
-            }
+
+        deserializer.deserialize_map(RawCategoriesVisitor)
+    }
+}
+
+impl RawCategories {
+    /// 解析某个类别，类别不存在时返回空表
+    fn parse<T: serde::de::DeserializeOwned>(&self, category: &str) -> Result<Dict<T>, AppError> {
+        match self.0.get(category) {
+            Some(raw) => serde_json::from_str(raw.get()).map_err(|err| {
+                AppError::ContextCreation(format!("解析原型类别 {} 失败: {}", category, err))
+            }),
+            None => Ok(Dict::new()),
         }
+    }
+
+    /// 解析多个类别并合并
+    fn parse_all<T: serde::de::DeserializeOwned>(
+        &self,
+        categories: &[&str],
+    ) -> Result<Dict<T>, AppError> {
+        let mut merged = Dict::new();
+        for category in categories {
+            merged.extend(self.parse::<T>(category)?);
+        }
+        Ok(merged)
+    }
+}
+
+impl FactorioContext {
+    pub fn test_load() -> Self {
+        let file = std::fs::File::open("assets/data-raw-dump.json").unwrap();
+        FactorioContext::load(std::io::BufReader::new(file))
+            .unwrap()
+            .build_order_info()
+            .build_producer_info()
+    }
 
-        let resources: Dict<ResourcePrototype> = serde_json::from_value(
-            value
-                .get("resource")
-                .cloned()
-                .unwrap_or_else(|| Value::Object(serde_json::Map::new())),
-        )
-        .unwrap();
-        let miners: Dict<MiningDrillPrototype> = serde_json::from_value(
-            value
-                .get("mining-drill")
-                .cloned()
-                .unwrap_or_else(|| Value::Object(serde_json::Map::new())),
-        )
-        .unwrap();
-        let modules: Dict<ModulePrototype> = serde_json::from_value(
-            value
-                .get("module")
-                .cloned()
-                .unwrap_or_else(|| Value::Object(serde_json::Map::new())),
-        )
-        .unwrap();
-        let beacons: Dict<BeaconPrototype> = serde_json::from_value(
-            value
-                .get("beacon")
-                .cloned()
-                .unwrap_or_else(|| Value::Object(serde_json::Map::new())),
-        )
-        .unwrap();
+    /// 从原始数据流中构建上下文，只解析用到的原型类别
+    pub fn load(reader: impl std::io::Read) -> Result<Self, AppError> {
+        let raw: RawCategories = serde_json::from_reader(reader)
+            .map_err(|err| AppError::ContextCreation(format!("解析原始数据失败: {}", err)))?;
+        let groups: Dict<PrototypeBase> = raw.parse("item-group")?;
+        let subgroups: Dict<ItemSubgroup> = raw.parse("item-subgroup")?;
+        let items: Dict<ItemPrototype> = raw.parse_all(ITEM_TYPES)?;
+        let entities: Dict<EntityPrototype> = raw.parse_all(ENTITY_TYPES)?;
+        let fluids: Dict<FluidPrototype> = raw.parse("fluid")?;
+        let mut recipes: Dict<RecipePrototype> = raw.parse("recipe")?;
+        let crafters: Dict<CraftingMachinePrototype> = raw.parse_all(CRAFTING_MACHINE_TYPES)?;
+        generate_recycling_recipes(&mut recipes, &items, &crafters);
+        let resources: Dict<ResourcePrototype> = raw.parse("resource")?;
+        let miners: Dict<MiningDrillPrototype> = raw.parse("mining-drill")?;
+        let modules: Dict<ModulePrototype> = raw.parse("module")?;
+        let beacons: Dict<BeaconPrototype> = raw.parse("beacon")?;
+        let offshore_pumps: Dict<OffshorePumpPrototype> = raw.parse("offshore-pump")?;
+        let asteroid_collectors: Dict<AsteroidCollectorPrototype> =
+            raw.parse("asteroid-collector")?;
+        let boilers: Dict<BoilerPrototype> = raw.parse("boiler")?;
+        let reactors: Dict<ReactorPrototype> = raw.parse("reactor")?;
+        let generators: Dict<GeneratorPrototype> = raw.parse("generator")?;
+        let fusion_reactors: Dict<FusionReactorPrototype> = raw.parse("fusion-reactor")?;
+        let fusion_generators: Dict<FusionGeneratorPrototype> = raw.parse("fusion-generator")?;
+        let labs: Dict<LabPrototype> = raw.parse("lab")?;
+        let thrusters: Dict<ThrusterPrototype> = raw.parse("thruster")?;
+        let turrets: Dict<TurretPrototype> = raw.parse_all(TURRET_TYPES)?;
+        let solar_panels: Dict<SolarPanelPrototype> = raw.parse("solar-panel")?;
+        let accumulators: Dict<AccumulatorPrototype> = raw.parse("accumulator")?;
+        let lightning_attractors: Dict<LightningAttractorPrototype> =
+            raw.parse("lightning-attractor")?;
+        let lightnings: Dict<LightningPrototype> = raw.parse("lightning")?;
+        // 品质按 next 字段串成链表，从 normal 开始依次解析
+        let raw_qualities: Dict<Box<RawValue>> = raw.parse("quality")?;
         let mut qualities = vec![];
-        let mut cur_quality = value.get("quality").unwrap().get("normal").unwrap();
-        while !cur_quality.is_null() {
-            let quality: QualityPrototype = serde_json::from_value(cur_quality.clone()).unwrap();
-            qualities.push(quality.clone());
-            cur_quality = value
-                .get("quality")
-                .unwrap()
-                .get(quality.next.as_ref().unwrap_or(&"".to_string()))
-                .unwrap_or(&Value::Null)
+        let mut cur_quality = Some("normal".to_string());
+        while let Some(name) = cur_quality
+            && let Some(raw_quality) = raw_qualities.get(&name)
+        {
+            let quality: QualityPrototype =
+                serde_json::from_str(raw_quality.get()).map_err(|err| {
+                    AppError::ContextCreation(format!("解析品质 {} 失败: {}", name, err))
+                })?;
+            cur_quality = quality.next.clone();
+            qualities.push(quality);
+        }
+        if qualities.is_empty() {
+            return Err(AppError::ContextCreation(
+                "原始数据中没有 normal 品质".to_string(),
+            ));
         }
         for entity in entities.values() {
             if let Some(autoplace) = &entity.autoplace
@@ -197,20 +499,32 @@ impl FactorioContext {
                 }
             }
         }
-        let planets: Dict<PlanetPrototype> = serde_json::from_value(
-            value
-                .get("planet")
-                .cloned()
-                .unwrap_or_else(|| Value::Object(serde_json::Map::new())),
-        )
-        .unwrap();
-        let tiles: Dict<TilePrototype> = serde_json::from_value(
-            value
-                .get("tile")
-                .cloned()
-                .unwrap_or_else(|| Value::Object(serde_json::Map::new())),
-        )
-        .unwrap();
+        let planets: Dict<PlanetPrototype> = raw.parse("planet")?;
+        let surfaces: Dict<SurfacePrototype> = raw.parse("surface")?;
+        let surface_properties: Dict<SurfacePropertyPrototype> = raw.parse("surface-property")?;
+        let space_connections: Dict<SpaceConnectionPrototype> = raw.parse("space-connection")?;
+        let tiles: Dict<TilePrototype> = raw.parse("tile")?;
+        let technologies: Dict<TechnologyPrototype> = raw.parse("technology")?;
+        let rocket_constants = raw
+            .parse::<RocketConstants>("utility-constants")?
+            .remove("default")
+            .unwrap_or_default();
+        // 图标定义格式不规范时不影响加载，只是无法合成缺失的图标
+        let mut icon_specs: Dict<Dict<IconSpecification>> = Dict::new();
+        for (folder, categories) in [
+            ("item", ITEM_TYPES),
+            ("entity", ENTITY_TYPES),
+            ("fluid", &["fluid"]),
+            ("recipe", &["recipe"]),
+            ("technology", &["technology"]),
+        ] {
+            match raw.parse_all(categories) {
+                Ok(specs) => {
+                    icon_specs.insert(folder.to_string(), specs);
+                }
+                Err(err) => log::warn!("解析图标定义失败: {:?}", err),
+            }
+        }
         let ret = FactorioContext {
             qualities,
             groups,
@@ -224,27 +538,60 @@ impl FactorioContext {
             crafters,
             resources,
             miners,
+            offshore_pumps,
+            asteroid_collectors,
+            boilers,
+            reactors,
+            generators,
+            fusion_reactors,
+            fusion_generators,
+            labs,
+            thrusters,
+            turrets,
+            solar_panels,
+            accumulators,
+            lightning_attractors,
+            lightnings,
             planets,
+            surfaces,
+            surface_properties,
+            space_connections,
             tiles,
+            technologies,
+            rocket_constants,
+            icon_specs,
             ..Default::default()
         };
         ret.planets.iter().for_each(|(_, p)| {
             dbg!(p.collect_autoplaced(&ret));
         });
-        ret
+        Ok(ret)
     }
 
+    /// 启动游戏导出原始数据、翻译与图标后构建上下文，网页版不能启动进程
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn load_from_executable_path(
         executable_path: &std::path::Path,
         mod_path: Option<&std::path::Path>,
-        lang: Option<&str>,
-    ) -> Result<FactorioContext, AppError> {
+        languages: &[&str],
+        monitor: &LoadMonitor,
+    ) -> Result<FactorioContext, ContextLoadError> {
         // 此步较为复杂，调用方应该异步执行
+        if !executable_path.is_file() {
+            return Err(ContextLoadError::ExecutableNotFound(
+                executable_path.to_path_buf(),
+            ));
+        }
         // 1. 在这个软件的数据文件夹下（秉持绿色原理，创建在这个项目程序本身的同级文件里），创建一个config.cfg
-        let lang = lang.unwrap_or("zh-CN");
-        let self_path = get_workding_directory();
-        let config_path = self_path.join("tmp/config/config.ini");
-        let tmp_mod_list_json_path = self_path.join("tmp/mods/mod-list.json");
+        let languages = if languages.is_empty() {
+            &["zh-CN"]
+        } else {
+            languages
+        };
+        let dump_key = context_dump_key(executable_path, mod_path);
+        let dump_dir = context_dump_dir(&dump_key);
+        let config_path = dump_dir.join("config/config.ini");
+        let tmp_mod_list_json_path = dump_dir.join("mods/mod-list.json");
         log::info!("准备创建临时配置文件: {:?}", config_path);
         if tmp_mod_list_json_path.exists() {
             std::fs::remove_file(&tmp_mod_list_json_path)
@@ -255,69 +602,101 @@ impl FactorioContext {
                 .map_err(|err| AppError::ContextCreation(err.to_string()))?;
         }
         // 配置配置文件：写入到自定义的文件夹中避免和运行中的游戏抢锁
-        let mut config_file = std::fs::File::create(&config_path)?;
-
-        config_file.write_all(b"[path]\nwrite-data=")?;
-        config_file.write_all(self_path.join("tmp").as_os_str().as_encoded_bytes())?;
-        config_file.write_all(format!("\n[general]\nlocale={}", lang).as_bytes())?;
+        let write_config = |lang: &str| -> Result<(), ContextLoadError> {
+            let mut config_file = std::fs::File::create(&config_path)?;
+            config_file.write_all(b"[path]\nwrite-data=")?;
+            config_file.write_all(dump_dir.as_os_str().as_encoded_bytes())?;
+            config_file.write_all(format!("\n[general]\nlocale={}", lang).as_bytes())?;
+            Ok(())
+        };
+        write_config(languages[0])?;
 
         log::info!("创建 config.ini 成功");
-        let dump_raw_command = Command::new(executable_path)
-            .arg("--dump-data")
-            .arg("--config")
-            .arg(config_path.to_str().unwrap())
-            .args(if let Some(mod_path) = mod_path {
-                vec!["--mod-directory", mod_path.to_str().unwrap()]
-            } else {
-                vec![]
+        let log_path = dump_dir.join("factorio-current.log");
+        // 运行游戏导出数据，异常退出时带上标准错误或游戏日志的最后几行
+        let run_dump = |stage: LoadStage, command: &mut Command| -> Result<(), ContextLoadError> {
+            let outcome = monitor.run(command).map_err(|err| match err {
+                AppError::Io(_) | AppError::Custom(_) if !monitor.is_cancelled() => {
+                    ContextLoadError::ExecutableNotFound(executable_path.to_path_buf())
+                }
+                _ if monitor.is_cancelled() => ContextLoadError::Cancelled,
+                err => err.into(),
+            })?;
+            if outcome.success {
+                return Ok(());
+            }
+            let mut excerpt = output_excerpt(&outcome.stderr, EXCERPT_LINES);
+            if excerpt.is_empty() {
+                excerpt = output_excerpt(
+                    &std::fs::read_to_string(&log_path).unwrap_or_default(),
+                    EXCERPT_LINES,
+                );
+            }
+            Err(ContextLoadError::DumpFailed {
+                stage,
+                code: outcome.code,
+                excerpt,
+                log_path: log_path.clone(),
             })
-            .stdout(Stdio::null())
-            .output()?;
-        if !dump_raw_command.status.success() {
-            return Err(AppError::ContextCreation(
-                "导出原始数据失败".to_string(),
-            ));
-        }
+        };
+        let dump_command = |flags: &[&str]| {
+            let mut command = Command::new(executable_path);
+            command
+                .args(flags)
+                .arg("--config")
+                .arg(config_path.to_str().unwrap())
+                .args(if let Some(mod_path) = mod_path {
+                    vec!["--mod-directory", mod_path.to_str().unwrap()]
+                } else {
+                    vec![]
+                })
+                .stdout(Stdio::null());
+            command
+        };
+        monitor.stage(LoadStage::DumpData, || {
+            run_dump(LoadStage::DumpData, &mut dump_command(&["--dump-data"]))
+        })?;
         log::info!("导出原始数据成功");
-        crate::toast::info("导出原始数据成功");
-        let dump_locale_command = Command::new(executable_path)
-            .arg("--dump-prototype-locale")
-            .arg("--config")
-            .arg(config_path.to_str().unwrap())
-            .args(if let Some(mod_path) = mod_path {
-                vec!["--mod-directory", mod_path.to_str().unwrap()]
-            } else {
-                vec![]
-            })
-            .stdout(Stdio::null())
-            .output()?;
-        if !dump_locale_command.status.success() {
-            return Err(AppError::ContextCreation(
-                "导出翻译数据失败".to_string(),
-            ));
-        }
+        monitor.stage(LoadStage::DumpLocale, || {
+            // 每种语言导出一次，复制到各自的文件夹，最后恢复为首选语言
+            let locale_root = dump_dir.join("locale");
+            if locale_root.exists() {
+                std::fs::remove_dir_all(&locale_root)?;
+            }
+            for lang in languages {
+                write_config(lang)?;
+                // 清掉上一种语言的导出结果，以便判断这次是否导出了翻译
+                for locale_category in LOCALE_CATEGORIES.iter() {
+                    let file_name = format!("{}-locale.json", locale_category);
+                    let _ = std::fs::remove_file(dump_dir.join("script-output").join(&file_name));
+                }
+                run_dump(
+                    LoadStage::DumpLocale,
+                    &mut dump_command(&["--dump-prototype-locale"]),
+                )?;
+                let locale_dir = locale_root.join(lang);
+                std::fs::create_dir_all(&locale_dir)?;
+                let mut exported = false;
+                for locale_category in LOCALE_CATEGORIES.iter() {
+                    let file_name = format!("{}-locale.json", locale_category);
+                    let dumped = dump_dir.join("script-output").join(&file_name);
+                    if dumped.exists() {
+                        std::fs::copy(&dumped, locale_dir.join(&file_name))?;
+                        exported = true;
+                    }
+                }
+                if !exported {
+                    return Err(ContextLoadError::LocaleMissing(lang.to_string()));
+                }
+            }
+            write_config(languages[0])
+        })?;
         log::info!("导出翻译数据成功");
-        crate::toast::info("导出翻译数据成功");
-
-        let dump_icon_sprites_command = Command::new(executable_path)
-            .arg("--dump-icon-sprites")
-            .arg("--disable-audio")
-            .arg("--config")
-            .arg(config_path.to_str().unwrap())
-            .args(if let Some(mod_path) = mod_path {
-                vec!["--mod-directory", mod_path.to_str().unwrap()]
-            } else {
-                vec![]
-            })
-            .stdout(Stdio::null())
-            .output()?;
-        if !dump_icon_sprites_command.status.success() {
-            return Err(AppError::ContextCreation(
-                "导出图标数据失败".to_string(),
-            ));
-        }
+        monitor.stage(LoadStage::DumpIcons, || {
+            let flags = ["--dump-icon-sprites", "--disable-audio"];
+            run_dump(LoadStage::DumpIcons, &mut dump_command(&flags))
+        })?;
         log::info!("导出图标数据成功");
-        crate::toast::info("导出图标数据成功");
 
         if let Some(mod_path) = mod_path {
             // 把 mod-list.json 也复制过来
@@ -327,16 +706,26 @@ impl FactorioContext {
             }
         }
         // 扫描游戏可执行文件下，补充版本信息
-        let mut mod_infos_json =
-            serde_json::from_str::<Value>(&std::fs::read_to_string(&tmp_mod_list_json_path)?)?;
-        let mut mod_infos = serde_json::from_value::<Vec<ModInfo>>(
-            mod_infos_json
-                .get("mods")
-                .ok_or(AppError::ContextCreation(
-                    "mod-list.json格式不正确".to_string(),
-                ))?
-                .clone(),
-        )?;
+        let mod_list_error = |reason: String| ContextLoadError::ModListParse {
+            path: mod_path
+                .map(|mod_path| mod_path.join("mod-list.json"))
+                .unwrap_or_else(|| tmp_mod_list_json_path.clone()),
+            reason,
+        };
+        let mut mod_infos_json = std::fs::read_to_string(&tmp_mod_list_json_path)
+            .map_err(|err| mod_list_error(err.to_string()))
+            .and_then(|content| {
+                serde_json::from_str::<Value>(&content)
+                    .map_err(|err| mod_list_error(err.to_string()))
+            })?;
+        let mut mod_infos = mod_infos_json
+            .get("mods")
+            .cloned()
+            .ok_or_else(|| mod_list_error(tr("load_error.mod_list_no_mods").to_string()))
+            .and_then(|mods| {
+                serde_json::from_value::<Vec<ModInfo>>(mods)
+                    .map_err(|err| mod_list_error(err.to_string()))
+            })?;
         for mod_info in &mut mod_infos {
             if mod_info.enabled {
                 log::info!("处理模组信息 {:?}", mod_info);
@@ -425,7 +814,7 @@ impl FactorioContext {
                                     .ok_or(AppError::ContextCreation(
                                         "模组的info.json的version字段不是字符串".to_string(),
                                     ))?;
-                                let new_version = version_string_to_triplet(&version);
+                                let new_version = version_string_to_triplet(version);
                                 let old_version =
                                     version_string_to_triplet(mod_info.version.as_str());
                                 if old_version <= new_version {
@@ -448,95 +837,385 @@ impl FactorioContext {
             &tmp_mod_list_json_path,
             serde_json::to_string_pretty(&mod_infos_json)?,
         )?;
-        FactorioContext::load_from_tmp_no_dump()
+        // 游戏自带模组的图片在 data 下，用于合成导出结果中缺少的图标
+        let roots = SpriteRoots {
+            data_dir: Some(executable_path.join("../../../data")),
+            mod_dir: mod_path.map(|path| path.to_path_buf()),
+        };
+        Ok(monitor.stage(LoadStage::Parse, || Self::parse_tmp(&dump_key, &roots))?)
     }
 
-    pub fn load_from_tmp_no_dump() -> Result<FactorioContext, AppError> {
-        let self_path = get_workding_directory();
-        let raw_path = self_path.join("tmp/script-output/data-raw-dump.json");
-        let icon_path = self_path.join("tmp/script-output/");
-        let json_string = std::fs::read_to_string(&raw_path).map_err(|_| {
-            AppError::ContextCreation(format!(
-                "读取原始数据文件失败: {:?}",
-                raw_path.to_string_lossy()
-            ))
-        })?;
+    /// 读取 dump_key 对应目录中已导出的数据，不重新启动游戏
+    pub fn load_from_tmp_no_dump(
+        dump_key: &str,
+        monitor: &LoadMonitor,
+    ) -> Result<FactorioContext, AppError> {
+        monitor.stage(LoadStage::Parse, || {
+            Self::parse_tmp(dump_key, &SpriteRoots::default())
+        })
+    }
 
-        let json_value = serde_json::from_str::<Value>(&json_string).map_err(|_| {
+    fn parse_tmp(dump_key: &str, roots: &SpriteRoots) -> Result<FactorioContext, AppError> {
+        let dump_dir = context_dump_dir(dump_key);
+        let raw_path = dump_dir.join("script-output/data-raw-dump.json");
+        let icon_path = dump_dir.join("script-output/");
+        let mods = read_enabled_mods(&dump_dir.join("mods/mod-list.json"))?;
+        let cache_key = context_cache_key(&mods, &raw_path);
+        if let Some(key) = cache_key
+            && let Some(mut ctx) = load_context_cache(&context_cache_path(&dump_dir), key)
+        {
+            log::info!("从缓存加载上下文");
+            ctx.icon_path = icon_path;
+            ctx.dump_key = dump_key.to_string();
+            #[cfg(feature = "gui")]
+            crate::toast::success("从缓存加载数据完成");
+            return Ok(ctx);
+        }
+        let raw_file = std::fs::File::open(&raw_path).map_err(|_| {
             AppError::ContextCreation(format!(
-                "解析原始数据文件失败: {:?}",
+                "读取原始数据文件失败: {:?}",
                 raw_path.to_string_lossy()
             ))
         })?;
-        let mut ctx = FactorioContext::load(&json_value);
+        let mut ctx = FactorioContext::load(std::io::BufReader::with_capacity(1 << 20, raw_file))?;
+        let composed =
+            compose_missing_icons(&std::mem::take(&mut ctx.icon_specs), &icon_path, roots);
+        if composed > 0 {
+            log::info!("合成了 {} 个缺失的图标", composed);
+        }
         ctx.icon_path = icon_path;
-        for locale_category in LOCALE_CATEGORIES.iter() {
-            log::info!("加载翻译类别 {}", locale_category);
-            let locale_path =
-                self_path.join(format!("tmp/script-output/{}-locale.json", locale_category));
-            if locale_path.exists() {
-                // name: a => A, b => B
-                // description: a => A desc, b => B desc
-                let locale_values: Dict<Dict<String>> = serde_json::from_str(
-                    &std::fs::read_to_string(&locale_path).map_err(|_| {
-                        AppError::ContextCreation(format!(
-                            "读取翻译数据文件失败: {:?}",
-                            locale_path
-                        ))
-                    })?,
-                )?;
-                ctx.localized_name.insert(
-                    locale_category.to_string(),
-                    locale_values.get("names").cloned().unwrap_or_default(),
-                );
-                ctx.localized_description.insert(
-                    locale_category.to_string(),
-                    locale_values
-                        .get("descriptions")
-                        .cloned()
-                        .unwrap_or_default(),
-                );
-            } else {
-                ctx.localized_name
-                    .insert(locale_category.to_string(), Dict::new());
-                ctx.localized_description
-                    .insert(locale_category.to_string(), Dict::new());
-                log::warn!("翻译类别 {} 的文件不存在，跳过", locale_category);
+        // 各语言的翻译在 tmp/locale/<语言> 下；旧版本只在 script-output 中留有一份
+        let config_lang = read_config_locale(&dump_dir.join("config/config.ini"));
+        if let Ok(entries) = std::fs::read_dir(dump_dir.join("locale")) {
+            for entry in entries.flatten() {
+                if entry.path().is_dir() {
+                    let lang = entry.file_name().to_string_lossy().to_string();
+                    log::info!("加载语言 {}", lang);
+                    ctx.locales
+                        .insert(lang, LocaleTable::load_dir(&entry.path())?);
+                }
             }
         }
-        let mod_list_json_path = self_path.join("tmp/mods/mod-list.json");
-        let mod_infos_json =
-            serde_json::from_str::<Value>(&std::fs::read_to_string(&mod_list_json_path)?)?;
-        let mut mod_infos = serde_json::from_value::<Vec<ModInfo>>(
-            mod_infos_json
-                .get("mods")
-                .ok_or(AppError::ContextCreation(
-                    "mod-list.json格式不正确".to_string(),
-                ))?
-                .clone(),
-        )?;
-        for mod_info in &mut mod_infos {
-            // log::info!("加载模组信息 {:?}", mod_info);
-            if mod_info.enabled {
-                log::info!("启用模组 {}", &mod_info.name);
-                ctx.mods
-                    .push((mod_info.name.clone(), mod_info.version.clone()));
-            }
+        let config_lang = config_lang.unwrap_or_else(|| "zh-CN".to_string());
+        if !ctx.locales.contains_key(&config_lang) {
+            ctx.locales.insert(
+                config_lang.clone(),
+                LocaleTable::load_dir(&dump_dir.join("script-output"))?,
+            );
+        }
+        ctx.locales.sort_keys();
+        ctx.set_locale(&config_lang);
+        ctx.mods = mods;
+        ctx.dump_key = dump_key.to_string();
+        if let Some(key) = cache_key
+            && let Err(err) = save_context_cache(&ctx, &context_cache_path(&dump_dir), key)
+        {
+            log::warn!("写入上下文缓存失败: {:?}", err);
         }
+        #[cfg(feature = "gui")]
         crate::toast::success("加载数据完成");
         Ok(ctx)
     }
 
+    /// 切换到已加载的语言，语言未加载时返回 false
+    pub fn set_locale(&mut self, lang: &str) -> bool {
+        let Some(table) = self.locales.get(lang) else {
+            return false;
+        };
+        self.localized_name = table.names.clone();
+        self.localized_description = table.descriptions.clone();
+        self.locale = lang.to_string();
+        true
+    }
+
+    /// 可供工厂选择的地点：按名称排序的星球，随后是太空平台等其他地表
+    pub fn locations(&self) -> Vec<String> {
+        let mut planets = self.planets.keys().cloned().collect::<Vec<_>>();
+        planets.sort();
+        let mut surfaces = self.surfaces.keys().cloned().collect::<Vec<_>>();
+        surfaces.sort();
+        planets.extend(surfaces);
+        planets
+    }
+
+    pub fn location_display_name(&self, location: &str) -> String {
+        if self.surfaces.contains_key(location) {
+            self.get_display_name("surface", location)
+        } else {
+            self.get_display_name("space-location", location)
+        }
+    }
+
+    /// 太空平台可以停留或航行的位置：先是有小行星的星球轨道，再是星球之间的航线
+    pub fn routes(&self) -> Vec<String> {
+        let mut orbits = self
+            .planets
+            .values()
+            .filter(|planet| !planet.asteroid_spawn_definitions.is_empty())
+            .map(|planet| planet.base.name.clone())
+            .collect::<Vec<_>>();
+        orbits.sort();
+        let mut connections = self.space_connections.values().collect::<Vec<_>>();
+        connections
+            .sort_by(|a, b| (&a.base.order, &a.base.name).cmp(&(&b.base.order, &b.base.name)));
+        orbits.extend(
+            connections
+                .into_iter()
+                .map(|connection| connection.base.name.clone()),
+        );
+        orbits
+    }
+
+    /// 星球轨道显示为星球名，航线显示为两端的星球名
+    pub fn route_display_name(&self, route: &str) -> String {
+        match self.space_connections.get(route) {
+            Some(connection) => format!(
+                "{} – {}",
+                self.get_display_name("space-location", &connection.from),
+                self.get_display_name("space-location", &connection.to)
+            ),
+            None => self.get_display_name("space-location", route),
+        }
+    }
+
+    /// 星球轨道或航线上各种小行星与碎块平均每秒生成的概率，位置不存在时为空
+    pub fn route_asteroid_spawn_rates(&self, route: &str) -> IndexMap<String, f64> {
+        let definitions = match self.space_connections.get(route) {
+            Some(connection) => &connection.asteroid_spawn_definitions,
+            None => match self.planets.get(route) {
+                Some(planet) => &planet.asteroid_spawn_definitions,
+                None => return IndexMap::new(),
+            },
+        };
+        let mut rates = IndexMap::new();
+        for definition in definitions {
+            *rates.entry(definition.asteroid.clone()).or_insert(0.0) +=
+                definition.average_probability() * 60.0;
+        }
+        rates
+    }
+
+    /// 地点的地表属性值，地点未指定时取属性的默认值；地点不存在时返回 None
+    pub fn location_surface_property(&self, location: &str, property: &str) -> Option<f64> {
+        if let Some(planet) = self.planets.get(location) {
+            return Some(planet.surface_property(self, property));
+        }
+        let surface = self.surfaces.get(location)?;
+        surface
+            .surface_properties
+            .get(property)
+            .or_else(|| {
+                self.surface_properties
+                    .get(property)
+                    .map(|property| &property.default_value)
+            })
+            .cloned()
+            .or(Some(0.0))
+    }
+
+    /// 在地点上不满足的地表条件，地点不存在时视为全部满足
+    pub fn unmet_surface_conditions<'a>(
+        &self,
+        location: &str,
+        conditions: &'a [SurfaceCondition],
+    ) -> Vec<&'a SurfaceCondition> {
+        conditions
+            .iter()
+            .filter(|condition| {
+                self.location_surface_property(location, &condition.property)
+                    .is_some_and(|value| !condition.is_satisfied(value))
+            })
+            .collect()
+    }
+
     pub fn get_display_name(&self, category: &str, key: &str) -> String {
         self.localized_name
             .get(category)
-            .unwrap()
-            .get(key)
-            .unwrap_or(&format!("{} (unlocalized)", key))
-            .to_string()
+            .and_then(|names| names.get(key))
+            .cloned()
+            .unwrap_or_else(|| format!("{} (unlocalized)", key))
+    }
+
+    /// 带品质后缀的显示名，普通品质不加后缀
+    pub fn get_quality_suffixed_name(&self, category: &str, key: &str, quality: u8) -> String {
+        let name = self.get_display_name(category, key);
+        match self.qualities.get(quality as usize) {
+            Some(q) if quality > 0 => {
+                format!(
+                    "{}（{}）",
+                    name,
+                    self.get_display_name("quality", &q.base.name)
+                )
+            }
+            _ => name,
+        }
+    }
+
+    pub fn get_generic_item_display_name(&self, item: &GenericItem) -> String {
+        match item {
+            GenericItem::Item(IdWithQuality(name, quality)) => {
+                self.get_quality_suffixed_name("item", name, *quality)
+            }
+            GenericItem::Fluid { name, temperature } => match temperature {
+                Some(temperature) => {
+                    format!(
+                        "{}（{}°C）",
+                        self.get_display_name("fluid", name),
+                        temperature
+                    )
+                }
+                None => self.get_display_name("fluid", name),
+            },
+            GenericItem::Entity(IdWithQuality(name, quality)) => {
+                self.get_quality_suffixed_name("entity", name, *quality)
+            }
+            GenericItem::Heat => "热量".to_string(),
+            GenericItem::Electricity => "电力".to_string(),
+            GenericItem::FluidHeat { filter } => format!(
+                "流体热量：{}",
+                filter
+                    .as_ref()
+                    .map(|f| self.get_display_name("fluid", f))
+                    .unwrap_or("任意".to_string())
+            ),
+            GenericItem::FluidFuel { filter } => format!(
+                "流体燃料：{}",
+                filter
+                    .as_ref()
+                    .map(|f| self.get_display_name("fluid", f))
+                    .unwrap_or("任意".to_string())
+            ),
+            GenericItem::ItemFuel { category } => format!("物体燃料：{}", category),
+            GenericItem::RocketPayloadWeight => "重量载荷".to_string(),
+            GenericItem::RocketPayloadStack => "堆叠载荷".to_string(),
+            GenericItem::Pollution { name } => self.get_display_name("airborne-pollutant", name),
+            GenericItem::Custom { name } => format!("特殊：{}", name),
+        }
+    }
+
+    pub fn build_producer_info(mut self) -> Self {
+        let mut producer_counts: Dict<Dict<usize>> = Dict::new();
+        let mut count_results = |results: &[RecipeResult]| {
+            let mut produced = results
+                .iter()
+                .map(|result| match result {
+                    RecipeResult::Item(r) => ("item", r.name.as_str()),
+                    RecipeResult::Fluid(f) => ("fluid", f.name.as_str()),
+                })
+                .collect::<Vec<_>>();
+            // 同一配方多次产出同一物品时只计一次
+            produced.sort();
+            produced.dedup();
+            for (category, name) in produced {
+                *producer_counts
+                    .entry(category.to_string())
+                    .or_default()
+                    .entry(name.to_string())
+                    .or_default() += 1;
+            }
+        };
+        for recipe in self.recipes.values() {
+            count_results(&recipe.results);
+        }
+        for resource in self.resources.values() {
+            if let Some(mining) = resource.base.minable.as_ref() {
+                if let Some(results) = &mining.results {
+                    count_results(results);
+                } else if let Some(result) = &mining.result {
+                    count_results(&[RecipeResult::Item(ItemResult {
+                        name: result.clone(),
+                        ..Default::default()
+                    })]);
+                }
+            }
+        }
+        self.producer_counts = producer_counts;
+        self.item_weights = derive_item_weights(&self.items, &self.recipes, &self.rocket_constants);
+        self
+    }
+
+    /// 返回能够产出该物品（或流体）的配方与资源数量
+    pub fn producer_count(&self, category: &str, name: &str) -> usize {
+        self.producer_counts
+            .get(category)
+            .and_then(|counts| counts.get(name))
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// 产出该物品或流体的配方名，按名称排序，其他类型的物品返回空
+    pub fn producers_of(&self, item: &GenericItem) -> &[String] {
+        recipe_index_lookup(&self.recipe_producers, item)
+    }
+
+    /// 以该物品或流体为原料的配方名，按名称排序，其他类型的物品返回空
+    pub fn consumers_of(&self, item: &GenericItem) -> &[String] {
+        recipe_index_lookup(&self.recipe_consumers, item)
+    }
+
+    /// 放置后成为该实体的物品名，按名称排序
+    pub fn items_placing(&self, entity: &str) -> &[String] {
+        self.entity_placers
+            .get(entity)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn build_reverse_index(&mut self) {
+        let mut producers: Dict<Dict<Vec<String>>> = Dict::new();
+        let mut consumers: Dict<Dict<Vec<String>>> = Dict::new();
+        let insert =
+            |index: &mut Dict<Dict<Vec<String>>>, category: &str, name: &str, recipe: &str| {
+                index
+                    .entry(category.to_string())
+                    .or_default()
+                    .entry(name.to_string())
+                    .or_default()
+                    .push(recipe.to_string());
+            };
+        for (recipe_name, recipe) in &self.recipes {
+            for result in &recipe.results {
+                match result {
+                    RecipeResult::Item(r) => insert(&mut producers, "item", &r.name, recipe_name),
+                    RecipeResult::Fluid(f) => insert(&mut producers, "fluid", &f.name, recipe_name),
+                }
+            }
+            for ingredient in &recipe.ingredients {
+                match ingredient {
+                    RecipeIngredient::Item(i) => {
+                        insert(&mut consumers, "item", &i.name, recipe_name)
+                    }
+                    RecipeIngredient::Fluid(f) => {
+                        insert(&mut consumers, "fluid", &f.name, recipe_name)
+                    }
+                }
+            }
+        }
+        for index in [&mut producers, &mut consumers] {
+            for recipes in index.values_mut().flat_map(|names| names.values_mut()) {
+                recipes.sort();
+                recipes.dedup();
+            }
+        }
+        let mut placers: Dict<Vec<String>> = Dict::new();
+        for (item_name, item) in &self.items {
+            if let Some(entity) = &item.place_result {
+                placers
+                    .entry(entity.clone())
+                    .or_default()
+                    .push(item_name.clone());
+            }
+        }
+        for items in placers.values_mut() {
+            items.sort();
+        }
+        self.recipe_producers = producers;
+        self.recipe_consumers = consumers;
+        self.entity_placers = placers;
     }
 
     pub fn build_order_info(mut self) -> Self {
+        self.build_reverse_index();
         self.ordered_entries.insert(
             "item".to_string(),
             get_order_info(&self.items, &self.groups, &self.subgroups),
@@ -614,11 +1293,14 @@ impl FactorioContext {
         );
         // 没有 order 的 entity，从 item 派生
         for (entity_name, entity) in self.entities.iter_mut() {
-            for item in self.items.values() {
-                if item.place_result.as_ref() == Some(entity_name) {
-                    entity.base.subgroup = item.base.subgroup.clone();
-                    entity.base.order = item.base.order.clone();
-                }
+            if let Some(item) = self
+                .entity_placers
+                .get(entity_name)
+                .and_then(|items| items.last())
+                .and_then(|item| self.items.get(item))
+            {
+                entity.base.subgroup = item.base.subgroup.clone();
+                entity.base.order = item.base.order.clone();
             }
         }
         self.ordered_entries.insert(
@@ -629,6 +1311,14 @@ impl FactorioContext {
             "entity".into(),
             get_reverse_order_info(&self.ordered_entries["entity"]),
         );
+        self.ordered_entries.insert(
+            "tile".into(),
+            get_order_info(&self.tiles, &self.groups, &self.subgroups),
+        );
+        self.order_of_entries.insert(
+            "tile".into(),
+            get_reverse_order_info(&self.ordered_entries["tile"]),
+        );
         self
     }
 }
@@ -695,6 +1385,37 @@ fn test_load_context() {
     assert!(ctx.fluids.contains_key("water"));
     assert!(ctx.recipes.contains_key("iron-gear-wheel"));
     assert!(ctx.crafters.contains_key("assembling-machine-1"));
+    assert!(ctx.producer_count("item", "iron-plate") > 0);
+    assert!(ctx.producer_count("item", "iron-ore") > 0);
+    assert!(ctx.producer_count("fluid", "petroleum-gas") > 0);
+    let gear = GenericItem::Item("iron-gear-wheel".into());
+    assert!(
+        ctx.producers_of(&gear)
+            .contains(&"iron-gear-wheel".to_string())
+    );
+    assert!(
+        ctx.consumers_of(&gear)
+            .contains(&"assembling-machine-1".to_string())
+    );
+    assert_eq!(ctx.items_placing("stone-furnace"), ["stone-furnace"]);
     dbg!(ctx.recipes.get("electronic-circuit"));
     dbg!(ctx.crafters.get("oil-refinery"));
 }
+
+#[test]
+fn test_context_dump_dir() {
+    let mods = tempfile::TempDir::new().unwrap();
+    let exe = std::path::Path::new("factorio");
+    let vanilla = context_dump_key(exe, None);
+    std::fs::write(mods.path().join("mod-list.json"), r#"{"mods":[]}"#).unwrap();
+    let modded = context_dump_key(exe, Some(mods.path()));
+    // 不同的模组列表导出到不同的目录，缓存也随之分开
+    assert_ne!(vanilla, modded);
+    assert_eq!(modded, context_dump_key(exe, Some(mods.path())));
+    assert_ne!(context_dump_dir(&vanilla), context_dump_dir(&modded));
+    assert_ne!(
+        context_cache_path(&context_dump_dir(&vanilla)),
+        context_cache_path(&context_dump_dir(&modded))
+    );
+    assert!(context_dump_dir("").ends_with("tmp"));
+}
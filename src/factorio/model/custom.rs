@@ -0,0 +1,489 @@
+//! 用户自定义的机制。
+//!
+//! 在工作目录的 `mechanics` 文件夹中放入 JSON 文件即可定义新的机制，例如游戏外的
+//! 兑换、模组中本工具不认识的建筑等。每个文件可以是一个定义或定义的数组：
+//!
+//! ```json
+//! {
+//!     "name": "蒸汽换电",
+//!     "description": "每台每秒消耗 steam 换取电力",
+//!     "cost": 2,
+//!     "parameters": { "效率": { "default": 1, "min": 0, "max": 2 } },
+//!     "flows": [
+//!         { "type": "fluid", "name": "steam", "temperature": 165, "amount": -60 },
+//!         { "type": "electricity", "amount": 1800000, "scale": "效率" }
+//!     ]
+//! }
+//! ```
+//!
+//! 物料流的 `amount` 为每台每秒的数量，正数为产出、负数为消耗；设置 `scale` 时再乘以
+//! 对应参数的值。加入工厂的机制会保存完整的定义，定义文件被修改或删除也不影响已有的工厂。
+
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+
+#[cfg(feature = "gui")]
+use crate::factorio::{editor::icon::GenericIcon, format::SignedCompactLabel};
+use crate::{
+    concept::*,
+    error::AppError,
+    factorio::{
+        common::*,
+        model::{context::*, get_workding_directory},
+    },
+};
+
+fn default_cost() -> f64 {
+    1.0
+}
+
+/// 定义中的物料，写法比 [`GenericItem`] 的序列化格式更便于手写
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum UserItem {
+    Item {
+        name: String,
+        #[serde(default)]
+        quality: u8,
+    },
+    Fluid {
+        name: String,
+        temperature: Option<i32>,
+    },
+    Electricity,
+    Heat,
+    Pollution {
+        name: String,
+    },
+    /// 不对应游戏中任何物品的自定义物料，只在自定义机制之间流转
+    Custom {
+        name: String,
+    },
+}
+
+impl From<&UserItem> for GenericItem {
+    fn from(item: &UserItem) -> Self {
+        match item {
+            UserItem::Item { name, quality } => {
+                GenericItem::Item(IdWithQuality(name.clone(), *quality))
+            }
+            UserItem::Fluid { name, temperature } => GenericItem::Fluid {
+                name: name.clone(),
+                temperature: *temperature,
+            },
+            UserItem::Electricity => GenericItem::Electricity,
+            UserItem::Heat => GenericItem::Heat,
+            UserItem::Pollution { name } => GenericItem::Pollution { name: name.clone() },
+            UserItem::Custom { name } => GenericItem::Custom { name: name.clone() },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct UserFlow {
+    #[serde(flatten)]
+    pub item: UserItem,
+    /// 每台每秒的数量，正数为产出
+    pub amount: f64,
+    /// 乘以该参数的值，为空时不缩放
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct UserParameter {
+    pub default: f64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl UserParameter {
+    fn clamp(&self, value: f64) -> f64 {
+        let value = self.min.map_or(value, |min| value.max(min));
+        self.max.map_or(value, |max| value.min(max))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct UserMechanicDefinition {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    pub flows: Vec<UserFlow>,
+    /// 求解时每台的成本，与建筑占地的作用相同
+    #[serde(default = "default_cost")]
+    pub cost: f64,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub parameters: IndexMap<String, UserParameter>,
+}
+
+impl UserMechanicDefinition {
+    /// 检查引用的参数是否都有定义
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.name.trim().is_empty() {
+            return Err(AppError::Custom("自定义机制缺少名称".to_string()));
+        }
+        for flow in &self.flows {
+            if let Some(scale) = &flow.scale
+                && !self.parameters.contains_key(scale)
+            {
+                return Err(AppError::Custom(format!(
+                    "自定义机制 {} 引用了未定义的参数 {}",
+                    self.name, scale
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// 读取一个定义文件，文件内容可以是单个定义或定义的数组
+    pub fn load_file(path: &Path) -> Result<Vec<Self>, AppError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| AppError::Io(format!("读取文件 {} 失败：{}", path.display(), e)))?;
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| AppError::Io(format!("解析文件 {} 失败：{}", path.display(), e)))?;
+        let definitions: Vec<Self> = match value {
+            serde_json::Value::Array(_) => serde_json::from_value(value),
+            value => serde_json::from_value(value).map(|definition| vec![definition]),
+        }
+        .map_err(|e| AppError::Io(format!("解析文件 {} 失败：{}", path.display(), e)))?;
+        for definition in &definitions {
+            definition.validate()?;
+        }
+        Ok(definitions)
+    }
+}
+
+/// 从工作目录读取的所有自定义机制，读取失败的文件单独记录
+#[derive(Debug, Clone, Default)]
+pub struct UserMechanicLibrary {
+    pub definitions: Vec<UserMechanicDefinition>,
+    pub errors: Vec<AppError>,
+}
+
+impl UserMechanicLibrary {
+    pub fn directory() -> PathBuf {
+        get_workding_directory().join("mechanics")
+    }
+
+    pub fn load() -> Self {
+        Self::load_from(&Self::directory())
+    }
+
+    /// 按文件名顺序读取文件夹中的所有 JSON 文件，文件夹不存在时返回空的库
+    pub fn load_from(dir: &Path) -> Self {
+        let mut library = Self::default();
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return library;
+        };
+        let mut paths = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        for path in paths {
+            match UserMechanicDefinition::load_file(&path) {
+                Ok(definitions) => library.definitions.extend(definitions),
+                Err(err) => {
+                    log::warn!("读取自定义机制失败: {:?}", err);
+                    library.errors.push(err);
+                }
+            }
+        }
+        library
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "user:mechanic")]
+pub struct GenericUserMechanic {
+    pub definition: UserMechanicDefinition,
+    /// 参数的当前值，未列出的参数使用默认值
+    #[serde(default)]
+    pub values: IndexMap<String, f64>,
+}
+
+impl GenericUserMechanic {
+    pub fn new(definition: UserMechanicDefinition) -> Self {
+        Self {
+            definition,
+            values: IndexMap::new(),
+        }
+    }
+
+    pub fn value(&self, parameter: &str) -> f64 {
+        let Some(definition) = self.definition.parameters.get(parameter) else {
+            return 1.0;
+        };
+        definition.clamp(
+            self.values
+                .get(parameter)
+                .cloned()
+                .unwrap_or(definition.default),
+        )
+    }
+
+    /// 某物料每台每秒的数量，用于推荐机制
+    fn amount_of(&self, item: &GenericItem) -> f64 {
+        self.definition
+            .flows
+            .iter()
+            .filter(|flow| GenericItem::from(&flow.item) == *item)
+            .map(|flow| flow.amount * flow.scale.as_deref().map_or(1.0, |scale| self.value(scale)))
+            .sum()
+    }
+}
+
+impl SolveContext for GenericUserMechanic {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for GenericUserMechanic {
+    fn as_flow(&self, _ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        let mut map = Flow::new();
+        for flow in &self.definition.flows {
+            let scale = flow.scale.as_deref().map_or(1.0, |scale| self.value(scale));
+            index_map_update_entry(&mut map, (&flow.item).into(), flow.amount * scale);
+        }
+        map
+    }
+
+    fn cost(&self, _ctx: &Self::GameContext) -> f64 {
+        self.definition.cost
+    }
+}
+
+impl EditorView for GenericUserMechanic {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        let flow = self.as_flow(ctx);
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.strong(&self.definition.name)
+                    .on_hover_text(&self.definition.description);
+                let parameters = self.definition.parameters.clone();
+                for (name, parameter) in &parameters {
+                    let mut value = self.value(name);
+                    ui.horizontal(|ui| {
+                        ui.label(name);
+                        let widget = egui::DragValue::new(&mut value).speed(0.01).range(
+                            parameter.min.unwrap_or(f64::NEG_INFINITY)
+                                ..=parameter.max.unwrap_or(f64::INFINITY),
+                        );
+                        if ui.add(widget).changed() {
+                            self.values.insert(name.clone(), value);
+                            changed = true;
+                        }
+                    });
+                }
+            });
+            ui.separator();
+            for (item, amount) in &flow {
+                ui.vertical(|ui| {
+                    ui.add_sized(
+                        crate::settings::label_cell(),
+                        SignedCompactLabel::new(*amount),
+                    );
+                    ui.add_sized(crate::settings::icon_cell(), GenericIcon::new(ctx, item));
+                });
+            }
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "user:mechanic")]
+pub struct UserMechanicProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+    /// 每次创建或读取工厂时从工作目录重新读取
+    #[serde(skip, default = "UserMechanicLibrary::load")]
+    pub library: UserMechanicLibrary,
+}
+
+impl Default for UserMechanicProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UserMechanicProvider {
+    pub fn new() -> Self {
+        Self {
+            sender: None,
+            library: UserMechanicLibrary::load(),
+        }
+    }
+}
+
+impl SolveContext for UserMechanicProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for UserMechanicProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        ui.menu_button("添加自定义机制", |ui| {
+            if self.library.definitions.is_empty() {
+                ui.label(format!(
+                    "在 {} 中放入 JSON 文件来定义机制",
+                    UserMechanicLibrary::directory().display()
+                ));
+            }
+            for definition in &self.library.definitions {
+                let button = ui.button(&definition.name);
+                let button = if definition.description.is_empty() {
+                    button
+                } else {
+                    button.on_hover_text(&definition.description)
+                };
+                if button.clicked()
+                    && let Some(sender) = &self.sender
+                {
+                    let _ = sender.send(Box::new(GenericUserMechanic::new(definition.clone())));
+                    changed = true;
+                }
+            }
+            for err in &self.library.errors {
+                ui.colored_label(ui.visuals().error_fg_color, format!("{:?}", err));
+            }
+            ui.separator();
+            if ui.button("重新读取").clicked() {
+                self.library = UserMechanicLibrary::load();
+            }
+        });
+        changed
+    }
+}
+
+impl MechanicProvider for UserMechanicProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        _ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        self.library
+            .definitions
+            .iter()
+            .map(|definition| GenericUserMechanic::new(definition.clone()))
+            .filter(|mechanic| {
+                let amount = mechanic.amount_of(item);
+                (value < 0.0 && amount > 0.0) || (value > 0.0 && amount < 0.0)
+            })
+            .map(|mechanic| {
+                Box::new(mechanic)
+                    as Box<dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>>
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_user_mechanic() {
+    let ctx = FactorioContext::test_load();
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("a.json"),
+        r#"{
+            "name": "换电",
+            "parameters": { "效率": { "default": 1, "min": 0, "max": 2 } },
+            "flows": [
+                { "type": "fluid", "name": "steam", "temperature": 165, "amount": -60 },
+                { "type": "electricity", "amount": 1000, "scale": "效率" },
+                { "type": "electricity", "amount": 500 }
+            ]
+        }"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("b.json"),
+        r#"[{ "name": "铁板", "cost": 3, "flows": [{ "type": "item", "name": "iron-plate", "quality": 1, "amount": 2 }] }]"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("c.json"),
+        r#"{ "name": "坏的", "flows": [{ "type": "heat", "amount": 1, "scale": "不存在" }] }"#,
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("readme.txt"), "不是定义").unwrap();
+
+    let library = UserMechanicLibrary::load_from(dir.path());
+    assert_eq!(library.definitions.len(), 2);
+    assert_eq!(library.errors.len(), 1);
+
+    let mut mechanic = GenericUserMechanic::new(library.definitions[0].clone());
+    let flow = mechanic.as_flow(&ctx);
+    let steam = GenericItem::Fluid {
+        name: "steam".to_string(),
+        temperature: Some(165),
+    };
+    assert_eq!(flow[&steam], -60.0);
+    assert_eq!(flow[&GenericItem::Electricity], 1500.0);
+    // 超出范围的参数按上限计算
+    mechanic.values.insert("效率".to_string(), 5.0);
+    assert_eq!(mechanic.as_flow(&ctx)[&GenericItem::Electricity], 2500.0);
+
+    let plate = GenericUserMechanic::new(library.definitions[1].clone());
+    assert_eq!(plate.cost(&ctx), 3.0);
+    assert_eq!(
+        plate.as_flow(&ctx)[&GenericItem::Item(IdWithQuality("iron-plate".to_string(), 1))],
+        2.0
+    );
+
+    // 保存的机制带有完整定义
+    let mut registry = crate::dyn_serde::DynDeserializeRegistry::<
+        dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>,
+    >::default();
+    GenericUserMechanic::register(&mut registry);
+    let restored = registry
+        .deserialize(serde_json::to_value(&mechanic).unwrap())
+        .unwrap();
+    assert_eq!(restored.as_flow(&ctx), mechanic.as_flow(&ctx));
+
+    let provider = UserMechanicProvider {
+        sender: None,
+        library,
+    };
+    assert_eq!(
+        provider
+            .hint_populate(&ctx, &GenericItem::Electricity, -1.0)
+            .len(),
+        1
+    );
+    assert!(
+        provider
+            .hint_populate(&ctx, &GenericItem::Electricity, 1.0)
+            .is_empty()
+    );
+    assert_eq!(provider.hint_populate(&ctx, &steam, 1.0).len(), 1);
+}
+
+crate::impl_register_deserializer!(
+    for GenericUserMechanic
+    as "user:mechanic"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for UserMechanicProvider
+    as "user:mechanic"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
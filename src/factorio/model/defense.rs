@@ -0,0 +1,488 @@
+#[cfg(feature = "gui")]
+use crate::factorio::{
+    editor::icon::Icon, format::compact_number, modal::ItemWithQualitySelectorModal,
+};
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        model::{context::*, entity::*},
+    },
+};
+
+/// 消耗弹药、电力或流体进行攻击的炮塔类别
+pub const TURRET_TYPES: &[&str] = &["ammo-turret", "electric-turret", "fluid-turret"];
+
+/// 持续运转的防御设施，一单位表示一组炮塔按设定的开火频率维持防御
+pub fn defense_upkeep() -> GenericItem {
+    GenericItem::Custom {
+        name: "defense-upkeep".to_string(),
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TurretAmmoType {
+    /// 电炮塔每次射击消耗的电能
+    pub energy_consumption: Option<EnergyAmount>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TurretFluid {
+    #[serde(rename = "type")]
+    pub fluid: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TurretAttackParameters {
+    /// 两次射击之间的间隔（刻）
+    pub cooldown: f64,
+    pub ammo_category: Option<String>,
+    #[serde(default = "default_ammo_consumption_modifier")]
+    pub ammo_consumption_modifier: f64,
+    pub ammo_type: Option<TurretAmmoType>,
+    /// 流体炮塔每次射击消耗的流体
+    pub fluid_consumption: Option<f64>,
+    /// 流体炮塔可以使用的流体
+    #[serde(default)]
+    pub fluids: Vec<TurretFluid>,
+}
+
+fn default_ammo_consumption_modifier() -> f64 {
+    1.0
+}
+
+/// 炮塔，按开火频率消耗弹药、电力或流体
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TurretPrototype {
+    #[serde(flatten)]
+    pub base: EntityPrototype,
+
+    pub attack_parameters: TurretAttackParameters,
+    pub energy_source: Option<EnergySource>,
+}
+
+impl HasPrototypeBase for TurretPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base.base
+    }
+}
+
+impl TurretPrototype {
+    pub fn uses_ammo(&self) -> bool {
+        self.base.base.r#type == "ammo-turret"
+    }
+
+    pub fn uses_fluid(&self) -> bool {
+        self.base.base.r#type == "fluid-turret"
+    }
+
+    /// 持续开火时每秒的射击次数
+    pub fn shots_per_second(&self) -> f64 {
+        60.0 / self.attack_parameters.cooldown.max(1.0)
+    }
+
+    /// 能够装填的弹药物品
+    pub fn accepts_ammo(&self, ctx: &FactorioContext, item: &str) -> bool {
+        self.uses_ammo()
+            && ctx
+                .items
+                .get(item)
+                .and_then(|item| item.ammo.as_ref())
+                .is_some_and(|ammo| {
+                    Some(&ammo.ammo_category) == self.attack_parameters.ammo_category.as_ref()
+                })
+    }
+
+    /// 新建配置时使用的弹药：弹药炮塔取名称排序最前的可用弹药，流体炮塔取第一种流体
+    pub fn default_ammo(&self, ctx: &FactorioContext) -> Option<String> {
+        if self.uses_fluid() {
+            return self
+                .attack_parameters
+                .fluids
+                .first()
+                .map(|fluid| fluid.fluid.clone());
+        }
+        let mut ammo = ctx
+            .items
+            .keys()
+            .filter(|item| self.accepts_ammo(ctx, item))
+            .collect::<Vec<_>>();
+        ammo.sort();
+        ammo.first().map(|item| item.to_string())
+    }
+}
+
+/// 一组炮塔的持续消耗，开火频率由用户估计，弹药、流体与射击电能按开火时间折算，待机耗电始终计入
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:defense")]
+pub struct DefenseConfig {
+    pub machine: IdWithQuality,
+    /// 炮塔数量
+    pub turrets: u32,
+    /// 弹药物品或流体，电炮塔不使用
+    pub ammo: Option<IdWithQuality>,
+    /// 开火时间占比（%）
+    pub fire_rate: f64,
+}
+
+impl Default for DefenseConfig {
+    fn default() -> Self {
+        DefenseConfig {
+            machine: ("entity-unknown".to_string(), 0).into(),
+            turrets: 1,
+            ammo: None,
+            fire_rate: 10.0,
+        }
+    }
+}
+
+impl DefenseConfig {
+    /// 指定炮塔的默认配置
+    pub fn for_turret(ctx: &FactorioContext, turret: &str) -> Self {
+        DefenseConfig {
+            machine: turret.into(),
+            ammo: ctx
+                .turrets
+                .get(turret)
+                .and_then(|prototype| prototype.default_ammo(ctx))
+                .map(|ammo| ammo.as_str().into()),
+            ..Default::default()
+        }
+    }
+
+    /// 全部炮塔每秒的平均射击次数
+    pub fn shots_per_second(&self, ctx: &FactorioContext) -> f64 {
+        ctx.turrets.get(&self.machine.0).map_or(0.0, |turret| {
+            turret.shots_per_second() * self.fire_rate.clamp(0.0, 100.0) / 100.0
+                * self.turrets as f64
+        })
+    }
+}
+
+impl SolveContext for DefenseConfig {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for DefenseConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        let mut map = Flow::new();
+        let Some(turret) = ctx.turrets.get(&self.machine.0) else {
+            return map;
+        };
+        let shots = self.shots_per_second(ctx);
+        let parameters = &turret.attack_parameters;
+        if let Some(ammo) = &self.ammo {
+            if turret.uses_ammo()
+                && let Some(property) = ctx.items.get(&ammo.0).and_then(|item| item.ammo.as_ref())
+            {
+                index_map_update_entry(
+                    &mut map,
+                    GenericItem::Item(ammo.clone()),
+                    -shots * parameters.ammo_consumption_modifier / property.magazine_size,
+                );
+            } else if turret.uses_fluid() {
+                index_map_update_entry(
+                    &mut map,
+                    GenericItem::Fluid {
+                        name: ammo.0.clone(),
+                        temperature: None,
+                    },
+                    -shots * parameters.fluid_consumption.unwrap_or(0.0),
+                );
+            }
+        }
+        if let Some(energy) = parameters
+            .ammo_type
+            .as_ref()
+            .and_then(|ammo_type| ammo_type.energy_consumption.as_ref())
+        {
+            index_map_update_entry(&mut map, GenericItem::Electricity, -shots * energy.amount);
+        }
+        if let Some(EnergySource::Electric(source)) = &turret.energy_source
+            && let Some(drain) = &source.drain
+        {
+            index_map_update_entry(
+                &mut map,
+                GenericItem::Electricity,
+                -drain.amount * 60.0 * self.turrets as f64,
+            );
+        }
+        index_map_update_entry(&mut map, defense_upkeep(), 1.0);
+        map
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        ctx.turrets.get(&self.machine.0).map_or(4.0, |turret| {
+            turret
+                .base
+                .collision_box
+                .as_ref()
+                .map_or(1.0, BoundingBox::tile_area)
+        }) * self.turrets as f64
+    }
+}
+
+impl EditorView for DefenseConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("炮塔"));
+                let entity_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0).with_quality(self.machine.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(match ctx.turrets.get(&self.machine.0) {
+                        Some(turret) => format!(
+                            "{}：每秒射击 {} 次",
+                            ctx.get_display_name("entity", &self.machine.0),
+                            compact_number(turret.shots_per_second())
+                        ),
+                        None => "炮塔：未选择".into(),
+                    });
+                let mut machine_changed = false;
+                ui.add(
+                    ItemWithQualitySelectorModal::new(entity_button.id, ctx, "选择炮塔", "entity")
+                        .with_toggle(entity_button.clicked())
+                        .with_current(&mut self.machine)
+                        .with_filter(|s, f| f.turrets.contains_key(s))
+                        .notify_change(&mut machine_changed),
+                );
+                // 换用弹药类别不同的炮塔时重新选择默认弹药
+                if machine_changed {
+                    if let Some(turret) = ctx.turrets.get(&self.machine.0) {
+                        let compatible = self.ammo.as_ref().is_some_and(|ammo| {
+                            turret.accepts_ammo(ctx, &ammo.0)
+                                || turret
+                                    .attack_parameters
+                                    .fluids
+                                    .iter()
+                                    .any(|fluid| fluid.fluid == ammo.0)
+                        });
+                        if !compatible {
+                            self.ammo = turret.default_ammo(ctx).map(|ammo| ammo.as_str().into());
+                        }
+                    }
+                    changed = true;
+                }
+            });
+            if let Some(turret) = ctx.turrets.get(&self.machine.0) {
+                if turret.uses_ammo() {
+                    ui.vertical(|ui| {
+                        ui.add_sized(crate::settings::label_cell(), egui::Label::new("弹药"));
+                        let mut ammo = self.ammo.clone().unwrap_or_else(|| "item-unknown".into());
+                        let ammo_button = ui
+                            .add_sized(
+                                crate::settings::icon_cell(),
+                                Icon::new(ctx, "item", &ammo.0).with_quality(ammo.1),
+                            )
+                            .interact(egui::Sense::click())
+                            .on_hover_text(ctx.get_display_name("item", &ammo.0));
+                        let mut ammo_changed = false;
+                        ui.add(
+                            ItemWithQualitySelectorModal::new(
+                                ammo_button.id,
+                                ctx,
+                                "选择弹药",
+                                "item",
+                            )
+                            .with_toggle(ammo_button.clicked())
+                            .with_current(&mut ammo)
+                            .with_filter(|s, f| turret.accepts_ammo(f, s))
+                            .notify_change(&mut ammo_changed),
+                        );
+                        if ammo_changed {
+                            self.ammo = Some(ammo);
+                            changed = true;
+                        }
+                    });
+                } else if turret.uses_fluid() {
+                    ui.vertical(|ui| {
+                        ui.add_sized(crate::settings::label_cell(), egui::Label::new("流体"));
+                        let selected = self
+                            .ammo
+                            .as_ref()
+                            .map(|ammo| ctx.get_display_name("fluid", &ammo.0))
+                            .unwrap_or_default();
+                        egui::ComboBox::from_id_salt("defense-fluid")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                for fluid in &turret.attack_parameters.fluids {
+                                    let current = self
+                                        .ammo
+                                        .as_ref()
+                                        .is_some_and(|ammo| ammo.0 == fluid.fluid);
+                                    if ui
+                                        .selectable_label(
+                                            current,
+                                            ctx.get_display_name("fluid", &fluid.fluid),
+                                        )
+                                        .clicked()
+                                        && !current
+                                    {
+                                        self.ammo = Some(fluid.fluid.as_str().into());
+                                        changed = true;
+                                    }
+                                }
+                            });
+                    });
+                }
+            }
+            ui.separator();
+            egui::Grid::new("defense-turrets")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("炮塔数量");
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut self.turrets).range(1..=u32::MAX))
+                        .changed();
+                    ui.end_row();
+                    ui.label("开火频率");
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.fire_rate)
+                                .range(0.0..=100.0)
+                                .speed(0.5)
+                                .suffix(" %"),
+                        )
+                        .on_hover_text("炮塔处于开火状态的时间占比，待机耗电不受影响")
+                        .changed();
+                    ui.end_row();
+                });
+            if ctx.turrets.contains_key(&self.machine.0) {
+                ui.separator();
+                ui.label(format!(
+                    "平均每秒射击 {} 次",
+                    compact_number(self.shots_per_second(ctx))
+                ));
+            }
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:defense")]
+pub struct DefenseConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+}
+
+impl Default for DefenseConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DefenseConfigProvider {
+    pub fn new() -> Self {
+        Self { sender: None }
+    }
+}
+
+impl SolveContext for DefenseConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for DefenseConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        if ui.button("添加防御设施").clicked() {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(DefenseConfig::default()));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for DefenseConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        if *item != defense_upkeep() || value >= 0.0 {
+            return vec![];
+        }
+        let mut turrets = ctx.turrets.keys().collect::<Vec<_>>();
+        turrets.sort();
+        turrets
+            .into_iter()
+            .map(|turret| {
+                Box::new(DefenseConfig::for_turret(ctx, turret))
+                    as Box<dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>>
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_defense() {
+    let ctx = FactorioContext::test_load();
+    // 机枪炮塔每 6 刻射击一次，每个弹匣 10 发
+    let gun = DefenseConfig {
+        fire_rate: 50.0,
+        turrets: 4,
+        ..DefenseConfig::for_turret(&ctx, "gun-turret")
+    };
+    assert_eq!(gun.ammo, Some("firearm-magazine".into()));
+    let flow = gun.as_flow(&ctx);
+    let magazine = GenericItem::Item("firearm-magazine".into());
+    assert!((flow[&magazine] + 2.0).abs() < 1e-9);
+    assert!(!flow.contains_key(&GenericItem::Electricity));
+    assert_eq!(flow[&defense_upkeep()], 1.0);
+
+    // 激光炮塔每 40 刻射击一次，每次 800kJ，另有 24kW 待机耗电
+    let laser = DefenseConfig {
+        fire_rate: 100.0,
+        ..DefenseConfig::for_turret(&ctx, "laser-turret")
+    };
+    assert_eq!(laser.ammo, None);
+    let flow = laser.as_flow(&ctx);
+    assert!((flow[&GenericItem::Electricity] + 1.2e6 + 2.4e4).abs() < 1e-3);
+    let idle = DefenseConfig {
+        fire_rate: 0.0,
+        ..laser
+    };
+    assert!((idle.as_flow(&ctx)[&GenericItem::Electricity] + 2.4e4).abs() < 1e-3);
+
+    // 火焰炮塔每 4 刻射击一次，每次 0.2 单位流体
+    let flamethrower = DefenseConfig {
+        fire_rate: 100.0,
+        ..DefenseConfig::for_turret(&ctx, "flamethrower-turret")
+    };
+    let flow = flamethrower.as_flow(&ctx);
+    let crude_oil = GenericItem::Fluid {
+        name: "crude-oil".to_string(),
+        temperature: None,
+    };
+    assert!((flow[&crude_oil] + 3.0).abs() < 1e-9);
+}
+
+crate::impl_register_deserializer!(
+    for DefenseConfig
+    as "factorio:defense"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for DefenseConfigProvider
+    as "factorio:defense"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
@@ -72,14 +72,12 @@ pub fn energy_source_as_flow(
         EnergySource::Burner(source) => {
             let energy_usage =
                 energy_usage.amount * 60.0 * (1.0 + effects.consumption) / source.effectivity; // 每秒的能量消耗
+            // 燃料在上下文中不存在或没有燃料值时按抽象燃料计算
             if let Some(actual_fuel) = instance_fuel
                 && let Some(fuel_prototype) = ctx.items.get(&actual_fuel.0)
+                && let Some(fuel_property) = fuel_prototype.burn.as_ref()
             {
                 // 使用具体燃料
-                let fuel_property = fuel_prototype
-                    .burn
-                    .as_ref()
-                    .expect("燃料在上下文中没有燃料值");
                 let fuel_burn_speed = energy_usage / fuel_property.fuel_value.amount; // 一个物品的能量值
 
                 index_map_update_entry(
@@ -124,16 +122,11 @@ pub fn energy_source_as_flow(
             let energy_usage =
                 energy_usage.amount * 60.0 * (1.0 + effects.consumption) / source.effectivity; // 每秒的能量消耗
             if source.burns_fluid {
-                if let Some(actual_fuel) = instance_fuel {
+                if let Some(actual_fuel) = instance_fuel
+                    && let Some(fuel_prototype) = ctx.fluids.get(&actual_fuel.0)
+                    && let Some(fuel_property) = fuel_prototype.fuel_value.as_ref()
+                {
                     // 使用具体燃料
-                    let fuel_prototype = ctx
-                        .fluids
-                        .get(&actual_fuel.0)
-                        .expect("燃料在上下文中不存在");
-                    let fuel_property = fuel_prototype
-                        .fuel_value
-                        .as_ref()
-                        .expect("燃料在上下文中没有燃料值");
                     let mut fuel_burn_speed = energy_usage / fuel_property.amount; // 一个物品的能量值
                     if fuel_burn_speed > source.fluid_usage_per_tick * 60.0
                         && source.fluid_usage_per_tick > 0.0
@@ -170,16 +163,11 @@ pub fn energy_source_as_flow(
                 // 燃烧流体作为燃料
             } else {
                 // 利用流体热能
-                if let Some(actual_fuel) = instance_fuel {
+                if let Some(actual_fuel) = instance_fuel
+                    && let Some(fuel_prototype) = ctx.fluids.get(&actual_fuel.0)
+                    && let Some(fuel_property) = fuel_prototype.heat_capacity.as_ref()
+                {
                     // 使用具体燃料
-                    let fuel_prototype = ctx
-                        .fluids
-                        .get(&actual_fuel.0)
-                        .expect("燃料在上下文中不存在");
-                    let fuel_property = fuel_prototype
-                        .heat_capacity
-                        .as_ref()
-                        .expect("燃料在上下文中没有比热容");
                     let mut temperature_diff =
                         actual_fuel.1 as f64 - fuel_prototype.default_temperature;
                     if !source.scale_fluid_usage
@@ -259,3 +247,18 @@ pub fn energy_source_as_flow(
     }
     map
 }
+
+/// 按名称取第一个属于给定燃料类别的物品
+pub fn default_burner_fuel(ctx: &FactorioContext, fuel_categories: &[String]) -> Option<String> {
+    ctx.items
+        .values()
+        .filter(|item| {
+            item.burn
+                .as_ref()
+                .and_then(|burn| burn.fuel_category.as_ref())
+                .is_some_and(|category| fuel_categories.contains(category))
+        })
+        .map(|item| &item.base.name)
+        .min()
+        .cloned()
+}
@@ -1,4 +1,7 @@
-use crate::factorio::{common::*, model::recipe::*};
+use crate::factorio::{
+    common::*,
+    model::{planet::SurfaceCondition, recipe::*},
+};
 
 pub const ENTITY_TYPES: &[&str] = &[
     // "arrow",
@@ -136,7 +139,7 @@ pub const ENTITY_TYPES: &[&str] = &[
     // "tile-ghost",
 ];
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct EntityPrototype {
     #[serde(flatten)]
     pub base: PrototypeBase,
@@ -148,9 +151,12 @@ pub struct EntityPrototype {
     pub minable: Option<MiningProperty>,
     /// 具有 Autoplace 属性的原型实体可以自动看作无限源
     pub autoplace: Option<AutoplaceSpecification>,
+    /// 只能放置在满足这些地表条件的地点
+    #[serde(default)]
+    pub surface_conditions: Vec<SurfaceCondition>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct MiningProperty {
     pub mining_time: f64,
 
@@ -171,7 +177,7 @@ impl HasPrototypeBase for EntityPrototype {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Default)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
 #[serde(default)]
 pub struct AutoplaceSpecification {
     pub control: String,
@@ -0,0 +1,267 @@
+//! 将上下文中与计算相关的数据导出为结构简单的 JSON，供表格或其他工具直接使用。
+
+use indexmap::IndexMap;
+
+use crate::factorio::{common::*, model::*};
+
+/// 导出格式的版本，字段含义发生变化时递增
+pub const CONTEXT_EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ContextExport {
+    pub version: u32,
+    pub mods: Vec<(String, String)>,
+    pub qualities: Vec<QualityExport>,
+    pub recipes: IndexMap<String, RecipeExport>,
+    pub crafting_machines: IndexMap<String, Vec<MachineExport>>,
+    pub mining_drills: IndexMap<String, Vec<MachineExport>>,
+    pub modules: IndexMap<String, Vec<ModuleExport>>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct QualityExport {
+    pub name: String,
+    pub level: f64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RecipeExport {
+    pub category: String,
+    /// 制作时间（秒）
+    pub energy_required: f64,
+    pub ingredients: Vec<AmountExport>,
+    pub results: Vec<ResultExport>,
+    pub allow_productivity: bool,
+    pub allow_quality: bool,
+    pub maximum_productivity: f64,
+    pub hidden: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AmountExport {
+    pub r#type: &'static str,
+    pub name: String,
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ResultExport {
+    pub r#type: &'static str,
+    pub name: String,
+    /// 考虑概率、随机数量后的单次期望产量
+    pub amount: f64,
+    /// 产能加成作用的部分
+    pub productivity_amount: f64,
+}
+
+/// 某一品质下的机器参数
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MachineExport {
+    pub quality: String,
+    /// 制作速度或采矿速度
+    pub speed: f64,
+    /// 功率（瓦）
+    pub energy_usage: f64,
+    pub module_slots: f64,
+    pub categories: Vec<String>,
+    pub base_effect: Effect,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ModuleExport {
+    pub quality: String,
+    pub category: String,
+    pub tier: f64,
+    pub effect: Effect,
+}
+
+fn sorted<T>(dict: &Dict<T>) -> Vec<(&String, &T)> {
+    let mut entries = dict.iter().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+fn export_recipe(recipe: &RecipePrototype) -> RecipeExport {
+    RecipeExport {
+        category: recipe.category().to_string(),
+        energy_required: recipe.energy_required,
+        ingredients: recipe
+            .ingredients
+            .iter()
+            .map(|ingredient| match ingredient {
+                RecipeIngredient::Item(item) => AmountExport {
+                    r#type: "item",
+                    name: item.name.clone(),
+                    amount: item.amount,
+                },
+                RecipeIngredient::Fluid(fluid) => AmountExport {
+                    r#type: "fluid",
+                    name: fluid.name.clone(),
+                    amount: fluid.amount,
+                },
+            })
+            .collect(),
+        results: recipe
+            .results
+            .iter()
+            .map(|result| match result {
+                RecipeResult::Item(item) => {
+                    let (amount, productivity_amount) = item.normalized_output();
+                    ResultExport {
+                        r#type: "item",
+                        name: item.name.clone(),
+                        amount,
+                        productivity_amount,
+                    }
+                }
+                RecipeResult::Fluid(fluid) => {
+                    let (amount, productivity_amount) = fluid.normalized_output();
+                    ResultExport {
+                        r#type: "fluid",
+                        name: fluid.name.clone(),
+                        amount,
+                        productivity_amount,
+                    }
+                }
+            })
+            .collect(),
+        allow_productivity: recipe.allow_productivity,
+        allow_quality: recipe.allow_quality,
+        maximum_productivity: recipe.maximum_productivity,
+        hidden: recipe.base.hidden,
+    }
+}
+
+fn export_crafter(ctx: &FactorioContext, crafter: &CraftingMachinePrototype) -> Vec<MachineExport> {
+    ctx.qualities
+        .iter()
+        .map(|quality| {
+            let name = &quality.base.name;
+            let speed = crafter.crafting_speed
+                * match &crafter.crafting_speed_quality_multiplier {
+                    Some(multiplier) => multiplier.get(name).cloned().unwrap_or(1.0),
+                    None => quality.crafting_machine_speed_multiplier(),
+                };
+            let energy_usage = crafter
+                .energy_usage
+                .as_ref()
+                .map_or(0.0, |energy| energy.amount * 60.0)
+                * if crafter.quality_affects_energy_usage {
+                    match &crafter.energy_usage_quality_multiplier {
+                        Some(multiplier) => multiplier.get(name).cloned().unwrap_or(1.0),
+                        None => quality.crafting_machine_energy_usage_multiplier(),
+                    }
+                } else {
+                    1.0
+                };
+            let module_slots = crafter.module_slots
+                + if crafter.quality_affects_module_slots {
+                    match &crafter.module_slots_quality_bonus {
+                        Some(bonus) => bonus.get(name).cloned().unwrap_or(0.0),
+                        None => quality.crafting_machine_module_slots_bonus(),
+                    }
+                } else {
+                    0.0
+                };
+            MachineExport {
+                quality: name.clone(),
+                speed,
+                energy_usage,
+                module_slots,
+                categories: crafter.crafting_categories.clone(),
+                base_effect: crafter
+                    .effect_receiver
+                    .clone()
+                    .unwrap_or_default()
+                    .base_effect,
+            }
+        })
+        .collect()
+}
+
+fn export_miner(ctx: &FactorioContext, miner: &MiningDrillPrototype) -> Vec<MachineExport> {
+    ctx.qualities
+        .iter()
+        .map(|quality| MachineExport {
+            quality: quality.base.name.clone(),
+            speed: miner.mining_speed,
+            energy_usage: miner
+                .energy_usage
+                .as_ref()
+                .map_or(0.0, |energy| energy.amount * 60.0),
+            module_slots: miner.module_slots
+                + if miner.quality_affects_module_slots {
+                    quality.mining_drill_module_slots_bonus()
+                } else {
+                    0.0
+                },
+            categories: miner.resource_categories.clone(),
+            base_effect: miner
+                .effect_receiver
+                .clone()
+                .unwrap_or_default()
+                .base_effect,
+        })
+        .collect()
+}
+
+impl ContextExport {
+    pub fn new(ctx: &FactorioContext) -> Self {
+        Self {
+            version: CONTEXT_EXPORT_VERSION,
+            mods: ctx.mods.clone(),
+            qualities: ctx
+                .qualities
+                .iter()
+                .map(|quality| QualityExport {
+                    name: quality.base.name.clone(),
+                    level: quality.level,
+                })
+                .collect(),
+            recipes: sorted(&ctx.recipes)
+                .into_iter()
+                .map(|(name, recipe)| (name.clone(), export_recipe(recipe)))
+                .collect(),
+            crafting_machines: sorted(&ctx.crafters)
+                .into_iter()
+                .map(|(name, crafter)| (name.clone(), export_crafter(ctx, crafter)))
+                .collect(),
+            mining_drills: sorted(&ctx.miners)
+                .into_iter()
+                .map(|(name, miner)| (name.clone(), export_miner(ctx, miner)))
+                .collect(),
+            modules: sorted(&ctx.modules)
+                .into_iter()
+                .map(|(name, module)| {
+                    (
+                        name.clone(),
+                        ctx.qualities
+                            .iter()
+                            .map(|quality| ModuleExport {
+                                quality: quality.base.name.clone(),
+                                category: module.category.clone(),
+                                tier: module.tier,
+                                effect: effects_under_quality(
+                                    &module.effect,
+                                    quality.default_multiplier(),
+                                ),
+                            })
+                            .collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+#[test]
+fn test_context_export() {
+    let ctx = FactorioContext::test_load();
+    let export = ContextExport::new(&ctx);
+    let gear = &export.recipes["iron-gear-wheel"];
+    assert_eq!(gear.results[0].amount, 1.0);
+    let assembler = &export.crafting_machines["assembling-machine-2"];
+    assert_eq!(assembler.len(), ctx.qualities.len());
+    assert!(assembler.last().unwrap().speed > assembler[0].speed);
+    assert!(serde_json::to_string(&export).is_ok());
+}
@@ -1,6 +1,14 @@
-use crate::factorio::common::*;
+use std::collections::{BTreeMap, BTreeSet};
 
-#[derive(Debug, Clone, serde::Deserialize)]
+use crate::{
+    concept::Flow,
+    factorio::{
+        common::*,
+        model::context::{FactorioContext, GenericItem},
+    },
+};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct FluidPrototype {
     #[serde(flatten)]
     pub base: PrototypeBase,
@@ -21,3 +29,77 @@ impl HasPrototypeBase for FluidPrototype {
         &self.base
     }
 }
+
+/// 流体温度按“不低于”匹配：高温流体可以充当同种流体中温度要求更低的原料。
+///
+/// items 为各个流中的流体及其数量，正数为产出，负数为需求。
+/// 返回把产出的流体转换为可接受它的需求的中转流，未标注温度的产出按默认温度计算，未标注温度的需求接受任意温度。
+pub fn fluid_temperature_bridges<'a>(
+    ctx: &FactorioContext,
+    items: impl IntoIterator<Item = (&'a GenericItem, f64)>,
+) -> Vec<Flow<GenericItem>> {
+    let mut produced = BTreeMap::<&str, BTreeSet<Option<i32>>>::new();
+    let mut required = BTreeMap::<&str, BTreeSet<Option<i32>>>::new();
+    for (item, amount) in items {
+        if let GenericItem::Fluid { name, temperature } = item {
+            if amount > 0.0 {
+                produced.entry(name).or_default().insert(*temperature);
+            } else if amount < 0.0 {
+                required.entry(name).or_default().insert(*temperature);
+            }
+        }
+    }
+    let mut bridges = vec![];
+    for (name, temperatures) in &produced {
+        let Some(requirements) = required.get(name) else {
+            continue;
+        };
+        let default_temperature = ctx
+            .fluids
+            .get(*name)
+            .map_or(0, |fluid| fluid.default_temperature as i32);
+        for produced_temperature in temperatures {
+            let actual = produced_temperature.unwrap_or(default_temperature);
+            for required_temperature in requirements {
+                if produced_temperature == required_temperature
+                    || required_temperature.is_some_and(|t| t > actual)
+                {
+                    continue;
+                }
+                bridges.push(Flow::from_iter([
+                    (
+                        GenericItem::Fluid {
+                            name: name.to_string(),
+                            temperature: *produced_temperature,
+                        },
+                        -1.0,
+                    ),
+                    (
+                        GenericItem::Fluid {
+                            name: name.to_string(),
+                            temperature: *required_temperature,
+                        },
+                        1.0,
+                    ),
+                ]));
+            }
+        }
+    }
+    bridges
+}
+
+#[test]
+fn test_fluid_temperature_bridges() {
+    let ctx = FactorioContext::test_load();
+    let steam = |temperature| GenericItem::Fluid {
+        name: "steam".to_string(),
+        temperature,
+    };
+    let (hot, warm, any) = (steam(Some(500)), steam(Some(165)), steam(None));
+    let bridges = fluid_temperature_bridges(&ctx, [(&hot, 1.0), (&warm, -1.0), (&any, -1.0)]);
+    assert_eq!(bridges.len(), 2);
+    assert!(bridges.iter().all(|bridge| bridge[&hot] == -1.0));
+    // 低温蒸汽不能满足高温需求，默认温度的流体也一样
+    assert!(fluid_temperature_bridges(&ctx, [(&warm, 1.0), (&hot, -1.0)]).is_empty());
+    assert!(fluid_temperature_bridges(&ctx, [(&any, 1.0), (&warm, -1.0)]).is_empty());
+}
@@ -0,0 +1,623 @@
+#[cfg(feature = "gui")]
+use crate::factorio::{editor::icon::Icon, modal::ItemWithQualitySelectorModal};
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        model::{context::*, energy::*, entity::*},
+    },
+};
+
+/// 等离子体携带的能量按其温度与热容折算，如 100 万度、25J 热容的等离子体每单位 25MJ
+fn plasma_energy_per_unit(ctx: &FactorioContext, fluid_box: &FluidBox) -> f64 {
+    fluid_box
+        .filter
+        .as_ref()
+        .and_then(|filter| ctx.fluids.get(filter))
+        .map_or(0.0, |fluid| {
+            fluid
+                .heat_capacity
+                .as_ref()
+                .map_or(0.0, |capacity| capacity.amount * fluid.default_temperature)
+        })
+}
+
+/// 流体的默认温度，用于标注机器输出的流体
+fn fluid_temperature(ctx: &FactorioContext, fluid_box: &FluidBox) -> Option<i32> {
+    fluid_box
+        .filter
+        .as_ref()
+        .and_then(|filter| ctx.fluids.get(filter))
+        .map(|fluid| fluid.default_temperature as i32)
+}
+
+/// 品质对聚变设备流体处理量与功率的倍率
+fn quality_multiplier(ctx: &FactorioContext, quality: u8) -> f64 {
+    ctx.qualities
+        .get(quality as usize)
+        .map_or(1.0, |quality| quality.default_multiplier())
+}
+
+/// 聚变反应堆，消耗电力、燃料与冷却液，产生等离子体
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FusionReactorPrototype {
+    #[serde(flatten)]
+    pub base: EntityPrototype,
+
+    /// 电力能源
+    pub energy_source: EnergySource,
+    /// 每刻消耗的电力
+    pub power_input: EnergyAmount,
+    /// 燃烧燃料的能源，燃料决定等离子体的产量
+    pub burner: BurnerEnergySource,
+    /// 每刻消耗的冷却液量，等于产生的等离子体量
+    pub max_fluid_usage: f64,
+    /// 输入冷却液
+    pub input_fluid_box: FluidBox,
+    /// 输出等离子体
+    pub output_fluid_box: FluidBox,
+}
+
+impl HasPrototypeBase for FusionReactorPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base.base
+    }
+}
+
+impl FusionReactorPrototype {
+    /// 每秒产生的等离子体量
+    pub fn plasma_per_second(&self, ctx: &FactorioContext, quality: u8) -> f64 {
+        self.max_fluid_usage * 60.0 * quality_multiplier(ctx, quality)
+    }
+
+    /// 每秒产生的等离子体所携带的能量，燃料按此功率消耗
+    pub fn power_output(&self, ctx: &FactorioContext, quality: u8) -> f64 {
+        self.plasma_per_second(ctx, quality) * plasma_energy_per_unit(ctx, &self.output_fluid_box)
+    }
+}
+
+/// 聚变发电机，消耗等离子体发电并输出热冷却液
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FusionGeneratorPrototype {
+    #[serde(flatten)]
+    pub base: EntityPrototype,
+
+    /// 输出电力的能源，包含输出功率上限
+    pub energy_source: ElectricEnergySource,
+    /// 每刻消耗的等离子体量，等于输出的热冷却液量
+    pub max_fluid_usage: f64,
+    /// 输入等离子体
+    pub input_fluid_box: FluidBox,
+    /// 输出热冷却液
+    pub output_fluid_box: FluidBox,
+}
+
+impl HasPrototypeBase for FusionGeneratorPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base.base
+    }
+}
+
+impl FusionGeneratorPrototype {
+    /// 每秒消耗的等离子体量
+    pub fn plasma_per_second(&self, ctx: &FactorioContext, quality: u8) -> f64 {
+        self.max_fluid_usage * 60.0 * quality_multiplier(ctx, quality)
+    }
+
+    /// 满负荷时每秒发电量，不超过输出功率上限
+    pub fn power_output(&self, ctx: &FactorioContext, quality: u8) -> f64 {
+        let power = self.plasma_per_second(ctx, quality)
+            * plasma_energy_per_unit(ctx, &self.input_fluid_box);
+        match &self.energy_source.output_flow_limit {
+            Some(limit) => power.min(limit.amount * 60.0 * quality_multiplier(ctx, quality)),
+            None => power,
+        }
+    }
+}
+
+/// 聚变反应堆满负荷运行，冷却液与等离子体一比一转换
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:fusion-reactor")]
+pub struct FusionReactorConfig {
+    pub machine: IdWithQuality,
+    pub instance_fuel: Option<IdWithQuality>,
+}
+
+impl Default for FusionReactorConfig {
+    fn default() -> Self {
+        FusionReactorConfig {
+            machine: ("entity-unknown".to_string(), 0).into(),
+            instance_fuel: None,
+        }
+    }
+}
+
+impl SolveContext for FusionReactorConfig {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for FusionReactorConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        let mut map = Flow::new();
+        let Some(reactor) = ctx.fusion_reactors.get(&self.machine.0) else {
+            return map;
+        };
+        let electric_flow = energy_source_as_flow(
+            ctx,
+            &reactor.energy_source,
+            &reactor.power_input,
+            &Effect::default(),
+            &None,
+            &mut 1.0,
+        );
+        for (key, value) in electric_flow.into_iter() {
+            index_map_update_entry(&mut map, key, value);
+        }
+        let mut fulfillment = 1.0;
+        let fuel_flow = energy_source_as_flow(
+            ctx,
+            &EnergySource::Burner(reactor.burner.clone()),
+            &EnergyAmount {
+                amount: reactor.power_output(ctx, self.machine.1) / 60.0,
+            },
+            &Effect::default(),
+            &self
+                .instance_fuel
+                .as_ref()
+                .map(|id_with_quality| (id_with_quality.0.clone(), id_with_quality.1 as i32)),
+            &mut fulfillment,
+        );
+        for (key, value) in fuel_flow.into_iter() {
+            index_map_update_entry(&mut map, key, value);
+        }
+        let amount = reactor.plasma_per_second(ctx, self.machine.1) * fulfillment;
+        if let Some(coolant) = &reactor.input_fluid_box.filter {
+            index_map_update_entry(
+                &mut map,
+                GenericItem::Fluid {
+                    name: coolant.clone(),
+                    temperature: None,
+                },
+                -amount,
+            );
+        }
+        if let Some(plasma) = &reactor.output_fluid_box.filter {
+            index_map_update_entry(
+                &mut map,
+                GenericItem::Fluid {
+                    name: plasma.clone(),
+                    temperature: fluid_temperature(ctx, &reactor.output_fluid_box),
+                },
+                amount,
+            );
+        }
+        map
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        ctx.fusion_reactors
+            .get(&self.machine.0)
+            .map_or(16.0, |reactor| {
+                reactor
+                    .base
+                    .collision_box
+                    .as_ref()
+                    .map_or(1.0, BoundingBox::tile_area)
+            })
+    }
+}
+
+impl EditorView for FusionReactorConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        let reactor = ctx.fusion_reactors.get(&self.machine.0);
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("机器"));
+                let entity_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0).with_quality(self.machine.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(match reactor {
+                        Some(reactor) => format!(
+                            "{}：{:.2} MW",
+                            ctx.get_quality_suffixed_name(
+                                "entity",
+                                &self.machine.0,
+                                self.machine.1
+                            ),
+                            reactor.power_output(ctx, self.machine.1) / 1e6
+                        ),
+                        None => "聚变反应堆：未选择".into(),
+                    });
+                ui.add(
+                    ItemWithQualitySelectorModal::new(
+                        entity_button.id,
+                        ctx,
+                        "选择聚变反应堆",
+                        "entity",
+                    )
+                    .with_toggle(entity_button.clicked())
+                    .with_current(&mut self.machine)
+                    .with_filter(|s, f| f.fusion_reactors.contains_key(s))
+                    .notify_change(&mut changed),
+                );
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("燃料"));
+                let mut fuel = self
+                    .instance_fuel
+                    .clone()
+                    .unwrap_or_else(|| "item-unknown".into());
+                let fuel_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "item", &fuel.0).with_quality(fuel.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(if self.instance_fuel.is_some() {
+                        ctx.get_display_name("item", &fuel.0)
+                    } else {
+                        "燃料：未选择".into()
+                    });
+                let categories = reactor
+                    .map(|reactor| reactor.burner.fuel_categories.clone())
+                    .unwrap_or_default();
+                let mut fuel_changed = false;
+                ui.add(
+                    ItemWithQualitySelectorModal::new(fuel_button.id, ctx, "选择燃料", "item")
+                        .with_toggle(fuel_button.clicked())
+                        .with_current(&mut fuel)
+                        .with_filter(move |s, f| {
+                            f.items
+                                .get(s)
+                                .and_then(|item| item.burn.as_ref())
+                                .and_then(|burn| burn.fuel_category.as_ref())
+                                .is_some_and(|category| categories.contains(category))
+                        })
+                        .notify_change(&mut fuel_changed),
+                );
+                if fuel_changed {
+                    self.instance_fuel = Some(fuel);
+                    changed = true;
+                }
+            });
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:fusion-reactor")]
+pub struct FusionReactorConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+}
+
+impl Default for FusionReactorConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FusionReactorConfigProvider {
+    pub fn new() -> Self {
+        Self { sender: None }
+    }
+}
+
+impl SolveContext for FusionReactorConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for FusionReactorConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        if ui.button("添加聚变反应堆").clicked() {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(FusionReactorConfig::default()));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for FusionReactorConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        let GenericItem::Fluid { name, .. } = item else {
+            return vec![];
+        };
+        if value >= 0.0 {
+            return vec![];
+        }
+        let mut reactors = ctx
+            .fusion_reactors
+            .values()
+            .filter(|reactor| reactor.output_fluid_box.filter.as_ref() == Some(name))
+            .collect::<Vec<_>>();
+        reactors.sort_by(|a, b| a.base.base.name.cmp(&b.base.base.name));
+        reactors
+            .into_iter()
+            .map(|reactor| {
+                Box::new(FusionReactorConfig {
+                    machine: reactor.base.base.name.as_str().into(),
+                    instance_fuel: default_burner_fuel(ctx, &reactor.burner.fuel_categories)
+                        .map(|fuel| fuel.as_str().into()),
+                })
+                    as Box<dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>>
+            })
+            .collect()
+    }
+}
+
+/// 聚变发电机满负荷运行，输出的热冷却液需要冷却后送回反应堆
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:fusion-generator")]
+pub struct FusionGeneratorConfig {
+    pub machine: IdWithQuality,
+}
+
+impl Default for FusionGeneratorConfig {
+    fn default() -> Self {
+        FusionGeneratorConfig {
+            machine: ("entity-unknown".to_string(), 0).into(),
+        }
+    }
+}
+
+impl SolveContext for FusionGeneratorConfig {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for FusionGeneratorConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        let mut map = Flow::new();
+        let Some(generator) = ctx.fusion_generators.get(&self.machine.0) else {
+            return map;
+        };
+        let amount = generator.plasma_per_second(ctx, self.machine.1);
+        if let Some(plasma) = &generator.input_fluid_box.filter {
+            index_map_update_entry(
+                &mut map,
+                GenericItem::Fluid {
+                    name: plasma.clone(),
+                    temperature: fluid_temperature(ctx, &generator.input_fluid_box),
+                },
+                -amount,
+            );
+        }
+        if let Some(coolant) = &generator.output_fluid_box.filter {
+            index_map_update_entry(
+                &mut map,
+                GenericItem::Fluid {
+                    name: coolant.clone(),
+                    temperature: fluid_temperature(ctx, &generator.output_fluid_box),
+                },
+                amount,
+            );
+        }
+        index_map_update_entry(
+            &mut map,
+            GenericItem::Electricity,
+            generator.power_output(ctx, self.machine.1),
+        );
+        map
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        ctx.fusion_generators
+            .get(&self.machine.0)
+            .map_or(16.0, |generator| {
+                generator
+                    .base
+                    .collision_box
+                    .as_ref()
+                    .map_or(1.0, BoundingBox::tile_area)
+            })
+    }
+}
+
+impl EditorView for FusionGeneratorConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("机器"));
+                let entity_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0).with_quality(self.machine.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(match ctx.fusion_generators.get(&self.machine.0) {
+                        Some(generator) => format!(
+                            "{}：{:.2} MW",
+                            ctx.get_quality_suffixed_name(
+                                "entity",
+                                &self.machine.0,
+                                self.machine.1
+                            ),
+                            generator.power_output(ctx, self.machine.1) / 1e6
+                        ),
+                        None => "聚变发电机：未选择".into(),
+                    });
+                ui.add(
+                    ItemWithQualitySelectorModal::new(
+                        entity_button.id,
+                        ctx,
+                        "选择聚变发电机",
+                        "entity",
+                    )
+                    .with_toggle(entity_button.clicked())
+                    .with_current(&mut self.machine)
+                    .with_filter(|s, f| f.fusion_generators.contains_key(s))
+                    .notify_change(&mut changed),
+                );
+            });
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:fusion-generator")]
+pub struct FusionGeneratorConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+}
+
+impl Default for FusionGeneratorConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FusionGeneratorConfigProvider {
+    pub fn new() -> Self {
+        Self { sender: None }
+    }
+}
+
+impl SolveContext for FusionGeneratorConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for FusionGeneratorConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        if ui.button("添加聚变发电机").clicked() {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(FusionGeneratorConfig::default()));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for FusionGeneratorConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        if *item != GenericItem::Electricity || value >= 0.0 {
+            return vec![];
+        }
+        let mut generators = ctx.fusion_generators.keys().collect::<Vec<_>>();
+        generators.sort();
+        generators
+            .into_iter()
+            .map(|generator| {
+                Box::new(FusionGeneratorConfig {
+                    machine: generator.as_str().into(),
+                })
+                    as Box<dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>>
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_fusion() {
+    let ctx = FactorioContext::test_load();
+    let plasma = GenericItem::Fluid {
+        name: "fusion-plasma".to_string(),
+        temperature: Some(1_000_000),
+    };
+    let cold = GenericItem::Fluid {
+        name: "fluoroketone-cold".to_string(),
+        temperature: None,
+    };
+    let hot = GenericItem::Fluid {
+        name: "fluoroketone-hot".to_string(),
+        temperature: Some(180),
+    };
+    let reactor = FusionReactorConfig {
+        machine: "fusion-reactor".into(),
+        instance_fuel: Some("fusion-power-cell".into()),
+    }
+    .as_flow(&ctx);
+    println!("{:?}", reactor);
+    // 每秒 4 单位冷却液转换为 4 单位等离子体，共 100MW，40GJ 的燃料棒每 400 秒消耗一根
+    assert!((reactor[&plasma] - 4.0).abs() < 1e-9);
+    assert!((reactor[&cold] + 4.0).abs() < 1e-9);
+    let cell = GenericItem::Item("fusion-power-cell".into());
+    assert!((reactor[&cell] + 1.0 / 400.0).abs() < 1e-9);
+    assert!(reactor[&GenericItem::Electricity] < 0.0);
+
+    let generator = |quality| {
+        FusionGeneratorConfig {
+            machine: ("fusion-generator".to_string(), quality).into(),
+        }
+        .as_flow(&ctx)
+    };
+    // 每台发电机消耗 2 单位等离子体，输出 50MW
+    let normal = generator(0);
+    assert!((normal[&plasma] + 2.0).abs() < 1e-9);
+    assert!((normal[&hot] - 2.0).abs() < 1e-9);
+    assert!((normal[&GenericItem::Electricity] - 5e7).abs() < 1e-3);
+    // 罕见品质处理量与功率均提高 30%
+    let uncommon = generator(1);
+    assert!((uncommon[&GenericItem::Electricity] - 6.5e7).abs() < 1e-3);
+    assert!((uncommon[&plasma] + 2.6).abs() < 1e-9);
+
+    let hints = FusionReactorConfigProvider::new().hint_populate(&ctx, &plasma, -1.0);
+    assert_eq!(hints.len(), 1);
+}
+
+crate::impl_register_deserializer!(
+    for FusionReactorConfig
+    as "factorio:fusion-reactor"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for FusionReactorConfigProvider
+    as "factorio:fusion-reactor"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for FusionGeneratorConfig
+    as "factorio:fusion-generator"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for FusionGeneratorConfigProvider
+    as "factorio:fusion-generator"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
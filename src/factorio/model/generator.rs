@@ -0,0 +1,264 @@
+#[cfg(feature = "gui")]
+use crate::factorio::{editor::icon::Icon, modal::ItemWithQualitySelectorModal};
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        model::{context::*, entity::*},
+    },
+};
+
+fn default_effectivity() -> f64 {
+    1.0
+}
+
+/// 发电机，如蒸汽机和汽轮机，消耗热流体或燃烧流体发电
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct GeneratorPrototype {
+    #[serde(flatten)]
+    pub base: EntityPrototype,
+
+    #[serde(default = "default_effectivity")]
+    pub effectivity: f64,
+    /// 每刻消耗的流体量
+    pub fluid_usage_per_tick: f64,
+    /// 可以利用的最高流体温度
+    pub maximum_temperature: f64,
+    pub fluid_box: FluidBox,
+    #[serde(default)]
+    pub burns_fluid: bool,
+    /// 每刻最大输出功率
+    pub max_power_output: Option<EnergyAmount>,
+}
+
+impl HasPrototypeBase for GeneratorPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base.base
+    }
+}
+
+impl GeneratorPrototype {
+    /// 满负荷时每秒发电量，燃烧流体时按燃料值计算，否则按流体从默认温度升到最高温度的热量计算
+    pub fn power_output(&self, ctx: &FactorioContext) -> f64 {
+        let Some(fluid) = self
+            .fluid_box
+            .filter
+            .as_ref()
+            .and_then(|filter| ctx.fluids.get(filter))
+        else {
+            return 0.0;
+        };
+        let energy_per_unit = if self.burns_fluid {
+            fluid.fuel_value.as_ref().map_or(0.0, |value| value.amount)
+        } else {
+            fluid.heat_capacity.as_ref().map_or(0.0, |capacity| {
+                capacity.amount * (self.maximum_temperature - fluid.default_temperature).max(0.0)
+            })
+        };
+        let power = self.fluid_usage_per_tick * 60.0 * energy_per_unit * self.effectivity;
+        match &self.max_power_output {
+            Some(limit) => power.min(limit.amount * 60.0),
+            None => power,
+        }
+    }
+}
+
+/// 发电机按最高温度满负荷运行，要求输入流体不低于该温度
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:generator")]
+pub struct GeneratorConfig {
+    pub machine: IdWithQuality,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig {
+            machine: ("entity-unknown".to_string(), 0).into(),
+        }
+    }
+}
+
+impl SolveContext for GeneratorConfig {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for GeneratorConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        let mut map = Flow::new();
+        let Some(generator) = ctx.generators.get(&self.machine.0) else {
+            return map;
+        };
+        let Some(fluid) = &generator.fluid_box.filter else {
+            return map;
+        };
+        index_map_update_entry(
+            &mut map,
+            GenericItem::Fluid {
+                name: fluid.clone(),
+                temperature: (!generator.burns_fluid)
+                    .then_some(generator.maximum_temperature as i32),
+            },
+            -generator.fluid_usage_per_tick * 60.0,
+        );
+        index_map_update_entry(
+            &mut map,
+            GenericItem::Electricity,
+            generator.power_output(ctx),
+        );
+        map
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        ctx.generators
+            .get(&self.machine.0)
+            .map_or(16.0, |generator| {
+                generator
+                    .base
+                    .collision_box
+                    .as_ref()
+                    .map_or(1.0, BoundingBox::tile_area)
+            })
+    }
+}
+
+impl EditorView for GeneratorConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("机器"));
+                let entity_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0).with_quality(self.machine.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(match ctx.generators.get(&self.machine.0) {
+                        Some(generator) => format!(
+                            "{}：{:.2} MW",
+                            ctx.get_display_name("entity", &self.machine.0),
+                            generator.power_output(ctx) / 1e6
+                        ),
+                        None => "发电机：未选择".into(),
+                    });
+                ui.add(
+                    ItemWithQualitySelectorModal::new(
+                        entity_button.id,
+                        ctx,
+                        "选择发电机",
+                        "entity",
+                    )
+                    .with_toggle(entity_button.clicked())
+                    .with_current(&mut self.machine)
+                    .with_filter(|s, f| f.generators.contains_key(s))
+                    .notify_change(&mut changed),
+                );
+            });
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:generator")]
+pub struct GeneratorConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+}
+
+impl Default for GeneratorConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GeneratorConfigProvider {
+    pub fn new() -> Self {
+        Self { sender: None }
+    }
+}
+
+impl SolveContext for GeneratorConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for GeneratorConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        if ui.button("添加发电机").clicked() {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(GeneratorConfig::default()));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for GeneratorConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        if *item != GenericItem::Electricity || value >= 0.0 {
+            return vec![];
+        }
+        let mut generators = ctx.generators.keys().collect::<Vec<_>>();
+        generators.sort();
+        generators
+            .into_iter()
+            .map(|generator| {
+                Box::new(GeneratorConfig {
+                    machine: generator.as_str().into(),
+                })
+                    as Box<dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>>
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_generator() {
+    let ctx = FactorioContext::test_load();
+    let turbine = GeneratorConfig {
+        machine: "steam-turbine".into(),
+    }
+    .as_flow(&ctx);
+    // 汽轮机每秒消耗 60 单位 500 度蒸汽，输出 5.82MW
+    let steam = GenericItem::Fluid {
+        name: "steam".to_string(),
+        temperature: Some(500),
+    };
+    assert_eq!(turbine[&steam], -60.0);
+    assert!((turbine[&GenericItem::Electricity] - 5.82e6).abs() < 1e-3);
+    let engine = GeneratorConfig {
+        machine: "steam-engine".into(),
+    }
+    .as_flow(&ctx);
+    assert!((engine[&GenericItem::Electricity] - 9e5).abs() < 1e-3);
+}
+
+crate::impl_register_deserializer!(
+    for GeneratorConfig
+    as "factorio:generator"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for GeneratorConfigProvider
+    as "factorio:generator"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
@@ -0,0 +1,255 @@
+//! 游戏安装位置的自动检测。
+//!
+//! 扫描 Steam 库（通过 `libraryfolders.vdf`）、GOG 与常见的独立/便携安装目录，
+//! 并为每个安装推断对应的模组文件夹，供创建上下文界面预填路径。
+
+use std::path::{Path, PathBuf};
+
+use crate::i18n::tr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InstallSource {
+    Steam,
+    Gog,
+    /// 数据目录位于安装目录内的便携版
+    Portable,
+    /// 官网下载的独立版
+    Standalone,
+}
+
+impl InstallSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            InstallSource::Steam => tr("install.steam"),
+            InstallSource::Gog => tr("install.gog"),
+            InstallSource::Portable => tr("install.portable"),
+            InstallSource::Standalone => tr("install.standalone"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FactorioInstall {
+    pub source: InstallSource,
+    pub executable: PathBuf,
+    /// 含有 mod-list.json 的模组文件夹，不存在时为 None
+    pub mod_dir: Option<PathBuf>,
+}
+
+/// 可执行文件相对于安装根目录的路径
+fn executable_in(root: &Path) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        root.join("bin/x64/factorio.exe")
+    } else if cfg!(target_os = "macos") {
+        root.join("factorio.app/Contents/MacOS/factorio")
+    } else {
+        root.join("bin/x64/factorio")
+    }
+}
+
+/// 从 Steam 的 libraryfolders.vdf 中读出所有库文件夹
+pub fn parse_library_folders(vdf: &str) -> Vec<PathBuf> {
+    vdf.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('"').filter(|field| !field.trim().is_empty());
+            if fields.next()? != "path" {
+                return None;
+            }
+            Some(PathBuf::from(fields.next()?.replace("\\\\", "\\")))
+        })
+        .collect()
+}
+
+fn steam_roots() -> Vec<PathBuf> {
+    let mut roots = vec![];
+    if cfg!(target_os = "windows") {
+        roots.push(PathBuf::from("C:/Program Files (x86)/Steam"));
+        roots.push(PathBuf::from("C:/Program Files/Steam"));
+    }
+    if let Some(home) = dirs::home_dir() {
+        if cfg!(target_os = "macos") {
+            roots.push(home.join("Library/Application Support/Steam"));
+        } else if !cfg!(target_os = "windows") {
+            roots.push(home.join(".steam/steam"));
+            roots.push(home.join(".local/share/Steam"));
+            roots.push(home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"));
+        }
+    }
+    roots
+}
+
+/// 所有 Steam 库中的游戏目录，找不到 libraryfolders.vdf 时退回到 Steam 根目录本身
+fn steam_candidates() -> Vec<PathBuf> {
+    let mut libraries = vec![];
+    for root in steam_roots() {
+        match std::fs::read_to_string(root.join("steamapps/libraryfolders.vdf")) {
+            Ok(vdf) => libraries.extend(parse_library_folders(&vdf)),
+            Err(_) => libraries.push(root),
+        }
+    }
+    libraries
+        .into_iter()
+        .map(|library| library.join("steamapps/common/Factorio"))
+        .collect()
+}
+
+fn gog_candidates() -> Vec<PathBuf> {
+    let mut candidates = vec![];
+    if cfg!(target_os = "windows") {
+        candidates.push(PathBuf::from("C:/GOG Games/Factorio"));
+        candidates.push(PathBuf::from(
+            "C:/Program Files (x86)/GOG Galaxy/Games/Factorio",
+        ));
+    } else if let Some(home) = dirs::home_dir() {
+        candidates.push(home.join("GOG Games/Factorio/game"));
+    }
+    candidates
+}
+
+fn standalone_candidates() -> Vec<PathBuf> {
+    let mut candidates = vec![];
+    if cfg!(target_os = "windows") {
+        candidates.push(PathBuf::from("C:/Program Files/Factorio"));
+    } else if cfg!(target_os = "macos") {
+        candidates.push(PathBuf::from("/Applications"));
+    } else {
+        candidates.push(PathBuf::from("/opt/factorio"));
+    }
+    if let Some(home) = dirs::home_dir() {
+        candidates.push(home.join("factorio"));
+        candidates.push(home.join("Factorio"));
+    }
+    candidates
+}
+
+/// 游戏默认的用户数据目录下的模组文件夹
+pub fn default_mod_dir() -> Option<PathBuf> {
+    let dir = if cfg!(target_os = "windows") {
+        dirs::data_dir()?.join("Factorio/mods")
+    } else if cfg!(target_os = "macos") {
+        dirs::data_dir()?.join("factorio/mods")
+    } else {
+        dirs::home_dir()?.join(".factorio/mods")
+    };
+    Some(dir)
+}
+
+/// 安装目录的 config-path.cfg 关闭了系统数据目录时，用户数据就在安装目录内
+fn is_portable(root: &Path) -> bool {
+    std::fs::read_to_string(root.join("config-path.cfg")).is_ok_and(|cfg| {
+        cfg.lines()
+            .any(|line| line.replace(' ', "") == "use-system-read-write-data-directories=false")
+    })
+}
+
+/// 检查一个候选目录，存在可执行文件时返回对应的安装
+pub fn inspect_install(
+    root: &Path,
+    source: InstallSource,
+    default_mods: Option<&Path>,
+) -> Option<FactorioInstall> {
+    let executable = executable_in(root);
+    if !executable.is_file() {
+        return None;
+    }
+    let (source, mod_dir) = if is_portable(root) {
+        (InstallSource::Portable, Some(root.join("mods")))
+    } else {
+        (source, default_mods.map(Path::to_path_buf))
+    };
+    Some(FactorioInstall {
+        source,
+        executable,
+        mod_dir: mod_dir.filter(|dir| dir.join("mod-list.json").is_file()),
+    })
+}
+
+/// 扫描常见位置，返回去重后的所有安装
+pub fn detect_installs() -> Vec<FactorioInstall> {
+    let default_mods = default_mod_dir();
+    let candidates = steam_candidates()
+        .into_iter()
+        .map(|root| (root, InstallSource::Steam))
+        .chain(
+            gog_candidates()
+                .into_iter()
+                .map(|root| (root, InstallSource::Gog)),
+        )
+        .chain(
+            standalone_candidates()
+                .into_iter()
+                .map(|root| (root, InstallSource::Standalone)),
+        );
+    let mut installs: Vec<FactorioInstall> = vec![];
+    let mut seen = std::collections::HashSet::new();
+    for (root, source) in candidates {
+        if let Some(install) = inspect_install(&root, source, default_mods.as_deref())
+            && seen.insert(
+                install
+                    .executable
+                    .canonicalize()
+                    .unwrap_or(install.executable.clone()),
+            )
+        {
+            installs.push(install);
+        }
+    }
+    installs
+}
+
+#[test]
+fn test_parse_library_folders() {
+    let vdf = r#""libraryfolders"
+{
+	"0"
+	{
+		"path"		"C:\\Program Files (x86)\\Steam"
+		"label"		""
+		"apps"
+		{
+			"427520"		"3402345"
+		}
+	}
+	"1"
+	{
+		"path"		"/mnt/games/SteamLibrary"
+	}
+}"#;
+    assert_eq!(
+        parse_library_folders(vdf),
+        vec![
+            PathBuf::from("C:\\Program Files (x86)\\Steam"),
+            PathBuf::from("/mnt/games/SteamLibrary"),
+        ]
+    );
+}
+
+#[test]
+fn test_inspect_install() {
+    let root = tempfile::tempdir().unwrap();
+    let mods = tempfile::tempdir().unwrap();
+    assert!(inspect_install(root.path(), InstallSource::Steam, None).is_none());
+
+    let executable = executable_in(root.path());
+    std::fs::create_dir_all(executable.parent().unwrap()).unwrap();
+    std::fs::write(&executable, "").unwrap();
+    let install = inspect_install(root.path(), InstallSource::Steam, Some(mods.path())).unwrap();
+    assert_eq!(install.source, InstallSource::Steam);
+    assert_eq!(install.mod_dir, None);
+
+    std::fs::write(mods.path().join("mod-list.json"), "{}").unwrap();
+    let install = inspect_install(root.path(), InstallSource::Steam, Some(mods.path())).unwrap();
+    assert_eq!(install.mod_dir.as_deref(), Some(mods.path()));
+
+    std::fs::write(
+        root.path().join("config-path.cfg"),
+        "config-path=__PATH__executable__/../../config\nuse-system-read-write-data-directories=false\n",
+    )
+    .unwrap();
+    std::fs::create_dir_all(root.path().join("mods")).unwrap();
+    std::fs::write(root.path().join("mods/mod-list.json"), "{}").unwrap();
+    let install =
+        inspect_install(root.path(), InstallSource::Standalone, Some(mods.path())).unwrap();
+    assert_eq!(install.source, InstallSource::Portable);
+    assert_eq!(install.mod_dir, Some(root.path().join("mods")));
+}
@@ -25,7 +25,7 @@ pub const ITEM_TYPES: &[&str] = &[
 ];
 
 /// 仅存储物品的基础属性，插件属性另行收集
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ItemPrototype {
     #[serde(flatten)]
     pub base: PrototypeBase,
@@ -45,11 +45,29 @@ pub struct ItemPrototype {
     #[serde(flatten)]
     pub plant: Option<PlantProperty>,
 
+    /// 弹药，由炮塔消耗
+    #[serde(flatten)]
+    pub ammo: Option<AmmoProperty>,
+
     /// Tile
     pub place_as_tile: Option<PlaceAsTileProperty>,
 
     /// Entity
     pub place_result: Option<String>,
+
+    /// 一组的数量
+    #[serde(default = "default_stack_size")]
+    pub stack_size: u32,
+
+    /// 重量（克），未定义时按配方推算，见 derive_item_weights
+    pub weight: Option<f64>,
+
+    /// 按配方推算重量时原料重量折算为产物重量的系数
+    pub ingredient_to_weight_coefficient: Option<f64>,
+}
+
+fn default_stack_size() -> u32 {
+    1
 }
 
 impl Default for ItemPrototype {
@@ -63,8 +81,12 @@ impl Default for ItemPrototype {
             spoil: None,
             burn: None,
             plant: None,
+            ammo: None,
             place_as_tile: None,
             place_result: None,
+            stack_size: default_stack_size(),
+            weight: None,
+            ingredient_to_weight_coefficient: None,
         }
     }
 }
@@ -75,13 +97,74 @@ impl HasPrototypeBase for ItemPrototype {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct SpoilProperty {
     pub spoil_ticks: f64,
     pub spoil_result: Option<String>,
+    /// 变质时触发的效果，如虫卵孵化出虫子
+    #[serde(default)]
+    pub spoil_to_trigger_result: Option<SpoilTriggerResult>,
+}
+
+/// 变质触发的效果只保留其中生成的实体
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SpoilTriggerResult {
+    /// 每变质这么多个物品触发一次
+    pub items_per_trigger: f64,
+    /// 每次触发生成的实体
+    pub created_entities: Vec<String>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+impl<'de> serde::Deserialize<'de> for SpoilTriggerResult {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = <serde_json::Value as serde::Deserialize>::deserialize(deserializer)?;
+        let items_per_trigger = value["items_per_trigger"].as_f64().unwrap_or(1.0);
+        // 缓存中已经是整理后的格式，原始数据则需要在触发器中查找生成实体的效果
+        let created_entities = match value.get("created_entities") {
+            Some(entities) => {
+                serde_json::from_value(entities.clone()).map_err(serde::de::Error::custom)?
+            }
+            None => {
+                let mut entities = vec![];
+                collect_created_entities(&value["trigger"], &mut entities);
+                entities
+            }
+        };
+        Ok(SpoilTriggerResult {
+            items_per_trigger,
+            created_entities,
+        })
+    }
+}
+
+/// 在触发器中查找直接生成的实体，生成失败时的备用效果不计入
+fn collect_created_entities(value: &serde_json::Value, entities: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Array(values) => {
+            for value in values {
+                collect_created_entities(value, entities);
+            }
+        }
+        serde_json::Value::Object(object) => {
+            if object.get("type").and_then(|t| t.as_str()) == Some("create-entity")
+                && let Some(name) = object.get("entity_name").and_then(|name| name.as_str())
+            {
+                entities.push(name.to_string());
+            }
+            for (key, value) in object {
+                if key != "non_colliding_fail_result" {
+                    collect_created_entities(value, entities);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct BurnProperty {
     pub fuel_value: EnergyAmount,
     pub burnt_result: Option<String>,
@@ -89,12 +172,24 @@ pub struct BurnProperty {
     pub fuel_emissions_multiplier: Option<f64>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AmmoProperty {
+    pub ammo_category: String,
+    /// 每个弹药物品可以射击的次数
+    #[serde(default = "default_magazine_size")]
+    pub magazine_size: f64,
+}
+
+fn default_magazine_size() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PlantProperty {
     pub plant_result: String,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PlaceAsTileProperty {
     pub result: String,
 }
@@ -0,0 +1,436 @@
+#[cfg(feature = "gui")]
+use crate::factorio::{
+    ModuleConfigEditor, editor::icon::Icon, modal::ItemWithQualitySelectorModal,
+};
+use crate::{
+    concept::*,
+    factorio::{
+        ModuleConfig,
+        common::*,
+        model::{MachineWhitelist, context::*, energy::*, entity::*, technology::*},
+    },
+};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LabPrototype {
+    #[serde(flatten)]
+    pub base: EntityPrototype,
+
+    pub energy_source: EnergySource,
+    pub energy_usage: EnergyAmount,
+
+    #[serde(default = "default_researching_speed")]
+    pub researching_speed: f64,
+
+    /// 可放入的科技包
+    #[serde(default)]
+    pub inputs: Vec<String>,
+
+    #[serde(default)]
+    pub effect_receiver: Option<EffectReceiver>,
+    #[serde(default)]
+    pub module_slots: f64,
+    #[serde(default)]
+    pub quality_affects_module_slots: bool,
+
+    pub allowed_effects: Option<EffectTypeLimitation>,
+
+    #[serde(deserialize_with = "option_as_vec_or_empty")]
+    #[serde(default)]
+    pub allowed_module_categories: Option<Vec<String>>,
+
+    /// 科技包的消耗比例，如生物研究中心只消耗一半
+    pub science_pack_drain_rate_percent: Option<f64>,
+}
+
+fn default_researching_speed() -> f64 {
+    1.0
+}
+
+impl HasPrototypeBase for LabPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base.base
+    }
+}
+
+impl LabPrototype {
+    /// 指定品质的研究中心的插件槽数量
+    pub fn module_slots(&self, ctx: &FactorioContext, quality: u8) -> usize {
+        let bonus = if self.quality_affects_module_slots {
+            ctx.qualities
+                .get(quality as usize)
+                .map_or(0.0, |quality| quality.lab_module_slots_bonus())
+        } else {
+            0.0
+        };
+        (self.module_slots + bonus) as usize
+    }
+
+    /// 研究中心能否研究该科技，即科技需要的科技包都能放入研究中心
+    pub fn can_research(&self, technology: &TechnologyPrototype) -> bool {
+        technology.unit.as_ref().is_some_and(|unit| {
+            unit.ingredients
+                .iter()
+                .all(|(pack, _)| self.inputs.contains(pack))
+        })
+    }
+}
+
+/// 表示完成一个研究单位的抽象物品，研究的产出以此计量
+pub fn research_unit(technology: &str) -> GenericItem {
+    GenericItem::Custom {
+        name: format!("research:{}", technology),
+    }
+}
+
+/// 研究中心研究一项科技，消耗科技包并产出研究单位
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:lab")]
+pub struct LabConfig {
+    pub technology: String,
+    pub machine: IdWithQuality,
+    pub module_config: ModuleConfig,
+    pub instance_fuel: Option<IdWithQuality>,
+}
+
+impl Default for LabConfig {
+    fn default() -> Self {
+        LabConfig {
+            technology: "technology-unknown".to_string(),
+            machine: ("entity-unknown".to_string(), 0).into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+        }
+    }
+}
+
+impl LabConfig {
+    /// 在插件效果之外加上研究速度与研究产能加成。
+    /// 研究速度与插件速度相乘，研究产能与插件产能相加；
+    /// 科技包按普通品质计，研究中心的品质提高研究速度并减少科技包的消耗
+    pub fn as_flow_with_bonuses(
+        &self,
+        ctx: &FactorioContext,
+        speed_bonus: f64,
+        productivity_bonus: f64,
+    ) -> Flow<GenericItem> {
+        let mut map = Flow::new();
+
+        let mut module_effects = self.module_config.get_effect(ctx).clamped();
+        module_effects.productivity += productivity_bonus;
+
+        let Some(lab) = ctx.labs.get(&self.machine.0) else {
+            return map;
+        };
+        module_effects = module_effects
+            + lab
+                .effect_receiver
+                .clone()
+                .unwrap_or_default()
+                .base_effect
+                .clone();
+        let quality = ctx.qualities.get(self.machine.1 as usize);
+        let mut base_speed = lab.researching_speed
+            * quality.map_or(1.0, |quality| quality.lab_research_speed_multiplier());
+        let drain_rate = lab.science_pack_drain_rate_percent.unwrap_or(100.0) / 100.0
+            * quality.map_or(1.0, |quality| quality.science_pack_drain_multiplier());
+        let energy_related_flow = energy_source_as_flow(
+            ctx,
+            &lab.energy_source,
+            &lab.energy_usage,
+            &module_effects,
+            &self
+                .instance_fuel
+                .as_ref()
+                .map(|id_with_quality| (id_with_quality.0.clone(), id_with_quality.1 as i32)),
+            &mut base_speed,
+        );
+        for (key, value) in energy_related_flow.into_iter() {
+            index_map_update_entry(&mut map, key, value);
+        }
+
+        let Some(unit) = ctx
+            .technologies
+            .get(&self.technology)
+            .and_then(|technology| technology.unit.as_ref())
+        else {
+            return map;
+        };
+        // 每秒完成的研究单位数
+        let units = base_speed * (1.0 + speed_bonus) * (1.0 + module_effects.speed) / unit.time;
+        for (pack, amount) in &unit.ingredients {
+            index_map_update_entry(
+                &mut map,
+                GenericItem::Item(IdWithQuality(pack.clone(), 0)),
+                -units * amount * drain_rate,
+            );
+        }
+        index_map_update_entry(
+            &mut map,
+            research_unit(&self.technology),
+            units * (1.0 + module_effects.productivity),
+        );
+        map
+    }
+}
+
+impl SolveContext for LabConfig {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for LabConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        self.as_flow_with_bonuses(ctx, 0.0, 0.0)
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        ctx.labs.get(&self.machine.0).map_or(9.0, |lab| {
+            lab.base
+                .collision_box
+                .as_ref()
+                .map_or(1.0, BoundingBox::tile_area)
+        })
+    }
+}
+
+impl EditorView for LabConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("机器"));
+                let entity_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0).with_quality(self.machine.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(if ctx.labs.contains_key(&self.machine.0) {
+                        ctx.get_display_name("entity", &self.machine.0)
+                    } else {
+                        "研究中心：未选择".into()
+                    });
+                let whitelist = MachineWhitelist::current(ui.ctx());
+                ui.add(
+                    ItemWithQualitySelectorModal::new(
+                        entity_button.id,
+                        ctx,
+                        "选择研究中心",
+                        "entity",
+                    )
+                    .with_toggle(entity_button.clicked())
+                    .with_current(&mut self.machine)
+                    .with_filter(|s, f| whitelist.is_allowed(s) && f.labs.contains_key(s))
+                    .notify_change(&mut changed),
+                );
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.label("科技");
+                let lab = ctx.labs.get(&self.machine.0);
+                let mut technologies = ctx
+                    .technologies
+                    .values()
+                    .filter(|technology| {
+                        lab.map_or(technology.unit.is_some(), |lab| {
+                            lab.can_research(technology)
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                technologies.sort_by(|a, b| {
+                    (&a.base.order, &a.base.name).cmp(&(&b.base.order, &b.base.name))
+                });
+                egui::ComboBox::from_id_salt(ui.id().with("lab-technology"))
+                    .selected_text(ctx.get_display_name("technology", &self.technology))
+                    .height(400.0)
+                    .show_ui(ui, |ui| {
+                        for technology in technologies {
+                            let name = &technology.base.name;
+                            changed |= ui
+                                .selectable_value(
+                                    &mut self.technology,
+                                    name.clone(),
+                                    ctx.get_display_name("technology", name),
+                                )
+                                .changed();
+                        }
+                    });
+                if let Some(unit) = ctx
+                    .technologies
+                    .get(&self.technology)
+                    .and_then(|technology| technology.unit.as_ref())
+                {
+                    ui.label(format!(
+                        "每单位 {} 秒，{} 种科技包",
+                        unit.time,
+                        unit.ingredients.len()
+                    ));
+                }
+            });
+            ui.separator();
+            if let Some(lab) = ctx.labs.get(&self.machine.0) {
+                ui.add(
+                    ModuleConfigEditor::new(
+                        ctx,
+                        &mut self.module_config,
+                        lab.module_slots(ctx, self.machine.1),
+                        &lab.allowed_effects,
+                        &lab.allowed_module_categories,
+                    )
+                    .notify_change(&mut changed),
+                );
+            }
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:lab")]
+pub struct LabConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+    /// 工厂可用的机器，推荐时只选用其中的研究中心
+    #[serde(skip, default)]
+    pub machine_whitelist: MachineWhitelist,
+}
+
+impl Default for LabConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LabConfigProvider {
+    pub fn new() -> Self {
+        Self {
+            sender: None,
+            machine_whitelist: MachineWhitelist::default(),
+        }
+    }
+}
+
+impl SolveContext for LabConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for LabConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        if ui.button("添加研究中心").clicked() {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(LabConfig::default()));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for LabConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        let GenericItem::Custom { name } = item else {
+            return vec![];
+        };
+        let Some(technology) = name
+            .strip_prefix("research:")
+            .and_then(|technology| ctx.technologies.get(technology))
+        else {
+            return vec![];
+        };
+        if value >= 0.0 {
+            return vec![];
+        }
+        let mut labs = ctx
+            .labs
+            .values()
+            .filter(|lab| {
+                lab.can_research(technology)
+                    && self.machine_whitelist.is_allowed(&lab.base.base.name)
+            })
+            .collect::<Vec<_>>();
+        labs.sort_by(|a, b| a.base.base.name.cmp(&b.base.base.name));
+        labs.into_iter()
+            .map(|lab| {
+                Box::new(LabConfig {
+                    technology: technology.base.name.clone(),
+                    machine: lab.base.base.name.as_str().into(),
+                    ..Default::default()
+                })
+                    as Box<dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>>
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_lab() {
+    let ctx = FactorioContext::test_load();
+    let unit = research_unit("logistic-science-pack");
+    let red = GenericItem::Item("automation-science-pack".into());
+    let hints = LabConfigProvider::new().hint_populate(&ctx, &unit, -1.0);
+    assert_eq!(hints.len(), 2);
+    // 每单位 5 秒、消耗 1 个自动化科技包
+    let mut lab = LabConfig {
+        technology: "logistic-science-pack".to_string(),
+        machine: "lab".into(),
+        ..Default::default()
+    };
+    let flow = lab.as_flow(&ctx);
+    assert!((flow[&unit] - 0.2).abs() < 1e-9);
+    assert!((flow[&red] + 0.2).abs() < 1e-9);
+    // 60kW 的工作功率与 2kW 的待机功耗
+    assert!((flow[&GenericItem::Electricity] + 6.2e4).abs() < 1e-6);
+    // 生物研究中心速度翻倍，科技包只消耗一半
+    lab.machine = "biolab".into();
+    let flow = lab.as_flow(&ctx);
+    assert!((flow[&unit] - 0.4).abs() < 1e-9);
+    assert!((flow[&red] + 0.2).abs() < 1e-9);
+    // 罕见品质的研究中心速度提高 30%，科技包消耗减少 1%
+    lab.machine = ("lab".to_string(), 1).into();
+    let flow = lab.as_flow(&ctx);
+    assert!((flow[&unit] - 0.26).abs() < 1e-9);
+    assert!((flow[&red] + 0.26 * 0.99).abs() < 1e-9);
+    // 研究速度与插件速度相乘，研究产能只增加产出
+    lab.machine = "lab".into();
+    let flow = lab.as_flow_with_bonuses(&ctx, 0.2, 0.1);
+    assert!((flow[&unit] - 0.24 * 1.1).abs() < 1e-9);
+    assert!((flow[&red] + 0.24).abs() < 1e-9);
+    // 全局加成中的研究科技同样作用于研究中心
+    let mut bonuses = GlobalBonuses::default();
+    bonuses
+        .research
+        .levels
+        .insert("research-speed-1".to_string(), 1);
+    let mechanic: Box<FactorioMechanic> = Box::new(lab);
+    let flow = bonuses.mechanic_flow(mechanic.as_ref(), &ctx);
+    assert!((flow[&unit] - 0.24).abs() < 1e-9);
+}
+
+crate::impl_register_deserializer!(
+    for LabConfig
+    as "factorio:lab"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for LabConfigProvider
+    as "factorio:lab"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
@@ -0,0 +1,361 @@
+#[cfg(feature = "gui")]
+use crate::factorio::{editor::icon::Icon, modal::ItemWithQualitySelectorModal};
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        model::{context::*, entity::*},
+    },
+};
+
+/// 一个区块的面积
+const CHUNK_AREA: f64 = 32.0 * 32.0;
+
+/// 闪电，每次落雷携带固定的能量
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LightningPrototype {
+    #[serde(flatten)]
+    pub base: PrototypeBase,
+
+    pub energy: EnergyAmount,
+}
+
+impl HasPrototypeBase for LightningPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base
+    }
+}
+
+/// 避雷针与集电器，吸引附近的落雷并按效率转化为电力
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LightningAttractorPrototype {
+    #[serde(flatten)]
+    pub base: EntityPrototype,
+
+    /// 吸引落雷的半径
+    #[serde(default)]
+    pub range_elongation: f64,
+    /// 落雷能量转化为电力的比例，为 0 时只保护建筑
+    #[serde(default)]
+    pub efficiency: f64,
+    pub energy_source: Option<ElectricEnergySource>,
+}
+
+impl HasPrototypeBase for LightningAttractorPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base.base
+    }
+}
+
+/// 在有雷暴的星球上铺设互不重叠的集电器，按平均落雷频率计算每座的平均发电量
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:lightning")]
+pub struct LightningConfig {
+    pub machine: IdWithQuality,
+    pub planet: String,
+    /// 一个昼夜周期中出现雷暴的比例，只在夜间打雷
+    pub storm_fraction: f64,
+}
+
+impl Default for LightningConfig {
+    fn default() -> Self {
+        LightningConfig {
+            machine: ("entity-unknown".to_string(), 0).into(),
+            planet: "fulgora".to_string(),
+            storm_fraction: 0.5,
+        }
+    }
+}
+
+impl LightningConfig {
+    /// 单座集电器每秒吸引的落雷次数
+    pub fn strikes_per_second(&self, ctx: &FactorioContext) -> f64 {
+        let (Some(attractor), Some(properties)) = (
+            ctx.lightning_attractors.get(&self.machine.0),
+            ctx.planets
+                .get(&self.planet)
+                .and_then(|planet| planet.lightning_properties.as_ref()),
+        ) else {
+            return 0.0;
+        };
+        let area = std::f64::consts::PI * attractor.range_elongation.powi(2);
+        properties.lightnings_per_chunk_per_tick * 60.0 / CHUNK_AREA
+            * area
+            * self.storm_fraction.clamp(0.0, 1.0)
+    }
+
+    /// 单座集电器的平均发电量
+    pub fn average_power(&self, ctx: &FactorioContext) -> f64 {
+        let (Some(attractor), Some(properties)) = (
+            ctx.lightning_attractors.get(&self.machine.0),
+            ctx.planets
+                .get(&self.planet)
+                .and_then(|planet| planet.lightning_properties.as_ref()),
+        ) else {
+            return 0.0;
+        };
+        let energies = properties
+            .lightning_types
+            .iter()
+            .filter_map(|lightning| ctx.lightnings.get(lightning))
+            .map(|lightning| lightning.energy.amount)
+            .collect::<Vec<_>>();
+        if energies.is_empty() {
+            return 0.0;
+        }
+        let energy = energies.iter().sum::<f64>() / energies.len() as f64;
+        self.strikes_per_second(ctx) * energy * attractor.efficiency
+    }
+}
+
+impl SolveContext for LightningConfig {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for LightningConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        let mut map = Flow::new();
+        let power = self.average_power(ctx);
+        if power > 0.0 {
+            index_map_update_entry(&mut map, GenericItem::Electricity, power);
+        }
+        map
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        ctx.lightning_attractors
+            .get(&self.machine.0)
+            .map_or(16.0, |attractor| {
+                attractor
+                    .base
+                    .collision_box
+                    .as_ref()
+                    .map_or(1.0, BoundingBox::tile_area)
+            })
+    }
+}
+
+impl EditorView for LightningConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("机器"));
+                let entity_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0).with_quality(self.machine.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(match ctx.lightning_attractors.get(&self.machine.0) {
+                        Some(attractor) => format!(
+                            "{}：半径 {}，效率 {:.0}%",
+                            ctx.get_display_name("entity", &self.machine.0),
+                            attractor.range_elongation,
+                            attractor.efficiency * 100.0
+                        ),
+                        None => "集电器：未选择".into(),
+                    });
+                ui.add(
+                    ItemWithQualitySelectorModal::new(
+                        entity_button.id,
+                        ctx,
+                        "选择集电器",
+                        "entity",
+                    )
+                    .with_toggle(entity_button.clicked())
+                    .with_current(&mut self.machine)
+                    .with_filter(|s, f| {
+                        f.lightning_attractors
+                            .get(s)
+                            .is_some_and(|attractor| attractor.efficiency > 0.0)
+                    })
+                    .notify_change(&mut changed),
+                );
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.label("星球");
+                let mut planets = ctx
+                    .planets
+                    .iter()
+                    .filter(|(_, planet)| planet.lightning_properties.is_some())
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>();
+                planets.sort();
+                egui::ComboBox::new(ui.id().with("lightning-planet"), "")
+                    .selected_text(ctx.get_display_name("space-location", &self.planet))
+                    .show_ui(ui, |ui| {
+                        for planet in planets {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut self.planet,
+                                    planet.clone(),
+                                    ctx.get_display_name("space-location", planet),
+                                )
+                                .changed();
+                        }
+                    });
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.label("雷暴时间");
+                changed |= ui
+                    .add(
+                        egui::Slider::new(&mut self.storm_fraction, 0.0..=1.0)
+                            .custom_formatter(|value, _| format!("{:.0}%", value * 100.0))
+                            .custom_parser(|text| {
+                                text.trim_end_matches('%')
+                                    .parse::<f64>()
+                                    .ok()
+                                    .map(|value| value / 100.0)
+                            }),
+                    )
+                    .changed();
+            });
+            let average = self.average_power(ctx);
+            if average > 0.0 {
+                ui.separator();
+                ui.vertical(|ui| {
+                    ui.label(format!(
+                        "平均 {:.2} MW，每分钟 {:.1} 次落雷",
+                        average / 1e6,
+                        self.strikes_per_second(ctx) * 60.0
+                    ));
+                    ui.label(format!("每 MW：{:.2} 座", 1e6 / average));
+                });
+            }
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:lightning")]
+pub struct LightningConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+}
+
+impl Default for LightningConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LightningConfigProvider {
+    pub fn new() -> Self {
+        Self { sender: None }
+    }
+}
+
+impl SolveContext for LightningConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for LightningConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        if ui.button("添加集电器").clicked() {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(LightningConfig::default()));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for LightningConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        if *item != GenericItem::Electricity || value >= 0.0 {
+            return vec![];
+        }
+        let mut planets = ctx
+            .planets
+            .iter()
+            .filter(|(_, planet)| planet.lightning_properties.is_some())
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        planets.sort();
+        let mut attractors = ctx
+            .lightning_attractors
+            .iter()
+            .filter(|(_, attractor)| attractor.efficiency > 0.0)
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        attractors.sort();
+        planets
+            .into_iter()
+            .flat_map(|planet| {
+                attractors.iter().map(move |attractor| {
+                    Box::new(LightningConfig {
+                        machine: attractor.as_str().into(),
+                        planet: planet.clone(),
+                        ..Default::default()
+                    })
+                        as Box<
+                            dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>,
+                        >
+                })
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_lightning() {
+    let ctx = FactorioContext::test_load();
+    let collector = LightningConfig {
+        machine: "lightning-collector".into(),
+        planet: "fulgora".to_string(),
+        storm_fraction: 1.0,
+    };
+    // 每区块每 10 秒落雷一次，半径 25 的集电器覆盖约 1963 格
+    let strikes = 0.1 / CHUNK_AREA * std::f64::consts::PI * 625.0;
+    assert!((collector.strikes_per_second(&ctx) - strikes).abs() < 1e-9);
+    // 每次落雷 1000MJ，集电器效率 40%
+    let flow = collector.as_flow(&ctx);
+    assert!((flow[&GenericItem::Electricity] - strikes * 4e8).abs() < 1e-3);
+    let half = LightningConfig {
+        storm_fraction: 0.5,
+        ..collector.clone()
+    };
+    assert!((half.average_power(&ctx) * 2.0 - collector.average_power(&ctx)).abs() < 1e-3);
+    // 没有雷暴的星球上不发电
+    let nauvis = LightningConfig {
+        planet: "nauvis".to_string(),
+        ..collector
+    };
+    assert!(nauvis.as_flow(&ctx).is_empty());
+    let hints = LightningConfigProvider::new().hint_populate(&ctx, &GenericItem::Electricity, -1.0);
+    assert_eq!(hints.len(), 2);
+}
+
+crate::impl_register_deserializer!(
+    for LightningConfig
+    as "factorio:lightning"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for LightningConfigProvider
+    as "factorio:lightning"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
@@ -0,0 +1,236 @@
+use crate::{
+    concept::*,
+    factorio::{common::*, model::context::*},
+};
+
+/// 物品进出机器的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum TransportMode {
+    /// 传送带加机械臂
+    #[default]
+    Belt,
+    /// 机械臂在机器之间直接传递
+    DirectInsertion,
+    /// 物流机器人
+    Robot,
+}
+
+impl TransportMode {
+    pub const ALL: [TransportMode; 3] = [
+        TransportMode::Belt,
+        TransportMode::DirectInsertion,
+        TransportMode::Robot,
+    ];
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TransportMode::Belt => "传送带",
+            TransportMode::DirectInsertion => "直接插入",
+            TransportMode::Robot => "物流机器人",
+        }
+    }
+}
+
+/// 每搬运一个物品带来的开销
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct OverheadCoefficients {
+    /// 每个物品消耗的电能（焦耳）
+    pub energy_per_item: f64,
+    /// 每秒一个物品的吞吐量折算的占地成本
+    pub cost_per_item: f64,
+}
+
+/// 物流开销模型：按机器的物品吞吐量增加耗电与成本，使不同设计的对比计入机械臂、传送带与机器人
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LogisticsOverhead {
+    pub enabled: bool,
+    pub mode: TransportMode,
+    pub belt: OverheadCoefficients,
+    pub direct_insertion: OverheadCoefficients,
+    pub robot: OverheadCoefficients,
+}
+
+impl Default for LogisticsOverhead {
+    fn default() -> Self {
+        // 快速机械臂每次搬运约 20kJ；机器人往返飞行约 50kJ，另需机械臂装卸
+        LogisticsOverhead {
+            enabled: false,
+            mode: TransportMode::Belt,
+            belt: OverheadCoefficients {
+                energy_per_item: 20e3,
+                cost_per_item: 0.5,
+            },
+            direct_insertion: OverheadCoefficients {
+                energy_per_item: 20e3,
+                cost_per_item: 0.2,
+            },
+            robot: OverheadCoefficients {
+                energy_per_item: 70e3,
+                cost_per_item: 0.1,
+            },
+        }
+    }
+}
+
+impl LogisticsOverhead {
+    pub fn coefficients(&self) -> &OverheadCoefficients {
+        match self.mode {
+            TransportMode::Belt => &self.belt,
+            TransportMode::DirectInsertion => &self.direct_insertion,
+            TransportMode::Robot => &self.robot,
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    fn coefficients_mut(&mut self, mode: TransportMode) -> &mut OverheadCoefficients {
+        match mode {
+            TransportMode::Belt => &mut self.belt,
+            TransportMode::DirectInsertion => &mut self.direct_insertion,
+            TransportMode::Robot => &mut self.robot,
+        }
+    }
+
+    /// 每秒进出机器的物品总数，流体走管道，不计入
+    pub fn throughput(flow: &Flow<GenericItem>) -> f64 {
+        flow.iter()
+            .filter(|(item, _)| matches!(item, GenericItem::Item(_)))
+            .map(|(_, amount)| amount.abs())
+            .sum()
+    }
+
+    /// 在物料流中加上搬运物品消耗的电力
+    pub fn apply(&self, flow: &mut Flow<GenericItem>) {
+        if !self.enabled {
+            return;
+        }
+        let power = Self::throughput(flow) * self.coefficients().energy_per_item;
+        if power > 0.0 {
+            index_map_update_entry(flow, GenericItem::Electricity, -power);
+        }
+    }
+
+    /// 搬运物品所需设施折算的额外成本
+    pub fn cost(&self, flow: &Flow<GenericItem>) -> f64 {
+        if !self.enabled {
+            return 0.0;
+        }
+        Self::throughput(flow) * self.coefficients().cost_per_item
+    }
+}
+
+#[cfg(feature = "gui")]
+pub struct LogisticsOverheadEditor<'a> {
+    pub overhead: &'a mut LogisticsOverhead,
+    pub changed: Option<&'a mut bool>,
+}
+
+#[cfg(feature = "gui")]
+impl<'a> LogisticsOverheadEditor<'a> {
+    pub fn new(overhead: &'a mut LogisticsOverhead) -> Self {
+        Self {
+            overhead,
+            changed: None,
+        }
+    }
+
+    pub fn notify_change(mut self, changed: &'a mut bool) -> Self {
+        self.changed = Some(changed);
+        self
+    }
+}
+
+#[cfg(feature = "gui")]
+impl egui::Widget for LogisticsOverheadEditor<'_> {
+    fn ui(mut self, ui: &mut egui::Ui) -> egui::Response {
+        let mut changed = false;
+        egui::CollapsingHeader::new("物流开销")
+            .id_salt("logistics-overhead")
+            .show(ui, |ui| {
+                changed |= ui
+                    .checkbox(&mut self.overhead.enabled, "计入物流开销")
+                    .on_hover_text("按每台机器每秒进出的物品数增加耗电与成本")
+                    .changed();
+                ui.add_enabled_ui(self.overhead.enabled, |ui| {
+                    ui.horizontal(|ui| {
+                        for mode in TransportMode::ALL {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut self.overhead.mode,
+                                    mode,
+                                    mode.display_name(),
+                                )
+                                .changed();
+                        }
+                    });
+                    egui::Grid::new("logistics-overhead-coefficients").show(ui, |ui| {
+                        ui.label("方式");
+                        ui.label("每个物品耗能");
+                        ui.label("每物品/秒成本");
+                        ui.end_row();
+                        for mode in TransportMode::ALL {
+                            let coefficients = self.overhead.coefficients_mut(mode);
+                            ui.label(mode.display_name());
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut coefficients.energy_per_item)
+                                        .range(0.0..=f64::INFINITY)
+                                        .speed(100.0)
+                                        .custom_formatter(|value, _| {
+                                            format!("{:.1}kJ", value / 1e3)
+                                        })
+                                        .custom_parser(|text| {
+                                            text.trim_end_matches("kJ")
+                                                .parse::<f64>()
+                                                .ok()
+                                                .map(|v| v * 1e3)
+                                        }),
+                                )
+                                .changed();
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(&mut coefficients.cost_per_item)
+                                        .range(0.0..=f64::INFINITY)
+                                        .speed(0.01),
+                                )
+                                .changed();
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        if changed && let Some(flag) = &mut self.changed {
+            **flag = true;
+        }
+        ui.response().clone()
+    }
+}
+
+#[test]
+fn test_logistics_overhead() {
+    let plate = GenericItem::Item("iron-plate".into());
+    let gear = GenericItem::Item("iron-gear-wheel".into());
+    let water = GenericItem::Fluid {
+        name: "water".to_string(),
+        temperature: None,
+    };
+    let mut flow = Flow::new();
+    flow.insert(plate, -1.0);
+    flow.insert(gear, 0.5);
+    flow.insert(water, -10.0);
+    flow.insert(GenericItem::Electricity, -75e3);
+    assert_eq!(LogisticsOverhead::throughput(&flow), 1.5);
+    // 未启用时不影响物料流与成本
+    let mut overhead = LogisticsOverhead::default();
+    let mut disabled = flow.clone();
+    overhead.apply(&mut disabled);
+    assert_eq!(disabled, flow);
+    assert_eq!(overhead.cost(&flow), 0.0);
+    overhead.enabled = true;
+    overhead.mode = TransportMode::Robot;
+    let cost = overhead.cost(&flow);
+    overhead.apply(&mut flow);
+    assert!((flow[&GenericItem::Electricity] + 75e3 + 1.5 * 70e3).abs() < 1e-6);
+    assert!((cost - 0.15).abs() < 1e-9);
+}
@@ -1,15 +1,22 @@
+#[cfg(feature = "gui")]
+use crate::factorio::{
+    ModuleConfigEditor,
+    format::{compact_number, format_duration},
+    icon::Icon,
+    modal::{ItemSelectorModal, ItemWithQualitySelectorModal},
+};
 use crate::{
     concept::{AsFlow, EditorView, Flow, Mechanic, MechanicProvider, MechanicSender, SolveContext},
     factorio::{
-        ModuleConfig, ModuleConfigEditor, calc_quality_distribution,
+        ModuleConfig, calc_quality_distribution,
         common::*,
-        icon::Icon,
-        modal::{ItemSelectorModal, ItemWithQualitySelectorModal},
-        model::{context::*, energy::*, entity::*, recipe::*},
+        model::{
+            MachineWhitelist, context::*, energy::*, entity::*, pumpjack::PumpjackConfig, recipe::*,
+        },
     },
 };
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ResourcePrototype {
     #[serde(flatten)]
     pub base: EntityPrototype,
@@ -18,6 +25,14 @@ pub struct ResourcePrototype {
 
     #[serde(default)]
     pub infinite: bool,
+    /// 无限资源的资源量下限与标准资源量，产量为资源量与标准资源量之比
+    #[serde(default)]
+    pub minimum: Option<f64>,
+    #[serde(default)]
+    pub normal: Option<f64>,
+    /// 无限资源每次开采减少的资源量
+    #[serde(default)]
+    pub infinite_depletion_amount: Option<f64>,
 }
 
 impl HasPrototypeBase for ResourcePrototype {
@@ -26,7 +41,7 @@ impl HasPrototypeBase for ResourcePrototype {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct MiningDrillPrototype {
     #[serde(flatten)]
     pub base: EntityPrototype,
@@ -51,12 +66,16 @@ pub struct MiningDrillPrototype {
     #[serde(default)]
     pub allowed_module_categories: Option<Vec<String>>,
 
-    #[serde(default)]
+    #[serde(default = "default_uses_force_mining_productivity_bonus")]
     pub uses_force_mining_productivity_bonus: bool,
 
     pub resource_drain_rate_percent: Option<f64>,
 }
 
+fn default_uses_force_mining_productivity_bonus() -> bool {
+    true
+}
+
 impl HasPrototypeBase for MiningDrillPrototype {
     fn base(&self) -> &PrototypeBase {
         &self.base.base
@@ -75,6 +94,10 @@ pub fn machine_fits_for_resource(
     )
 }
 
+/// 启用矿区储量时的初始值
+#[cfg(feature = "gui")]
+const DEFAULT_PATCH_SIZE: f64 = 1_000_000.0;
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type", rename = "factorio:mining")]
 pub struct MiningConfig {
@@ -82,6 +105,10 @@ pub struct MiningConfig {
     pub machine: IdWithQuality,
     pub module_config: ModuleConfig,
     pub instance_fuel: Option<IdWithQuality>,
+    /// 矿区的资源储量，用于估计矿区多久会开采完毕；None 表示不考虑枯竭。
+    /// 储量由用户填写，数据中标记为无限的资源同样按填写的储量估计
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch_size: Option<f64>,
 }
 
 impl Default for MiningConfig {
@@ -92,10 +119,87 @@ impl Default for MiningConfig {
             machine: ("entity-unknown".to_string(), 0).into(),
             module_config: ModuleConfig::default(),
             instance_fuel: None,
+            patch_size: None,
+        }
+    }
+}
+
+/// 工厂级的采矿设置，作用于工厂内所有的采矿机制
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct MiningSettings {
+    /// 已研究的采矿产能等级，每级增加 10% 产能
+    pub productivity_level: u32,
+    /// 无限资源的丰度，1.0 即 100%；有限资源的开采速度与丰度无关
+    pub richness: f64,
+}
+
+impl Default for MiningSettings {
+    fn default() -> Self {
+        Self {
+            productivity_level: 0,
+            richness: 1.0,
+        }
+    }
+}
+
+impl MiningSettings {
+    pub fn productivity_bonus(&self) -> f64 {
+        self.productivity_level as f64 * 0.1
+    }
+
+    /// 按设置计算机制的物料流，非采矿机制不受影响
+    pub fn mechanic_flow(
+        &self,
+        mechanic: &FactorioMechanic,
+        ctx: &FactorioContext,
+    ) -> Flow<GenericItem> {
+        let any = mechanic as &dyn std::any::Any;
+        if let Some(mining_config) = any.downcast_ref::<MiningConfig>() {
+            mining_config.as_flow_with_settings(ctx, self)
+        } else if let Some(pumpjack_config) = any.downcast_ref::<PumpjackConfig>() {
+            pumpjack_config.as_flow_with_settings(ctx, self)
+        } else {
+            mechanic.as_flow(ctx)
         }
     }
 }
 
+#[cfg(feature = "gui")]
+impl egui::Widget for &mut MiningSettings {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        ui.horizontal(|ui| {
+            let level = ui
+                .add(
+                    egui::DragValue::new(&mut self.productivity_level)
+                        .range(0..=1000)
+                        .prefix("采矿产能 "),
+                )
+                .on_hover_text(format!(
+                    "已研究的采矿产能等级，当前加成 {:.0}%",
+                    self.productivity_bonus() * 100.0
+                ));
+            let richness = ui
+                .add(
+                    egui::DragValue::new(&mut self.richness)
+                        .range(0.0..=f64::INFINITY)
+                        .speed(0.01)
+                        .custom_formatter(|value, _| format!("{:.0}%", value * 100.0))
+                        .custom_parser(|text| {
+                            text.trim_end_matches('%')
+                                .parse::<f64>()
+                                .ok()
+                                .map(|v| v / 100.0)
+                        })
+                        .prefix("资源丰度 "),
+                )
+                .on_hover_text("无限资源（如原油）的产出率，有限资源的开采速度与丰度无关");
+            level.union(richness)
+        })
+        .inner
+    }
+}
+
 impl SolveContext for MiningConfig {
     type GameContext = FactorioContext;
     type ItemIdentType = GenericItem;
@@ -103,6 +207,79 @@ impl SolveContext for MiningConfig {
 
 impl AsFlow for MiningConfig {
     fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        self.as_flow_with_settings(ctx, &MiningSettings::default())
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        if let Some(miner) = ctx.miners.get(&self.machine.0) {
+            miner
+                .base
+                .collision_box
+                .as_ref()
+                .map_or(1.0, |bounding_box| match bounding_box {
+                    BoundingBox::Struct {
+                        left_top,
+                        right_bottom,
+                        orientation: _,
+                    } => {
+                        f64::ceil(right_bottom.1 - left_top.1)
+                            * f64::ceil(right_bottom.0 - left_top.0)
+                    }
+                    BoundingBox::Pair(map_position, map_position1) => {
+                        f64::ceil(map_position1.1 - map_position.1)
+                            * f64::ceil(map_position1.0 - map_position.0)
+                    }
+                    BoundingBox::Triplet(map_position, map_position1, _) => {
+                        f64::ceil(map_position1.1 - map_position.1)
+                            * f64::ceil(map_position1.0 - map_position.0)
+                    }
+                })
+        } else {
+            16.0
+        }
+    }
+}
+
+impl MiningConfig {
+    /// 单台采矿机每秒消耗的资源量，已计入品质与采矿机自身的消耗减免。flow 为该机制的物料流
+    pub fn resource_drain(&self, flow: &Flow<GenericItem>) -> f64 {
+        -flow
+            .get(&GenericItem::Entity(IdWithQuality(
+                self.resource.clone(),
+                0,
+            )))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// machines 台采矿机开采完矿区所需的秒数，未设置储量或不消耗资源时为 None
+    pub fn patch_lifetime(&self, flow: &Flow<GenericItem>, machines: f64) -> Option<f64> {
+        let patch_size = self.patch_size?;
+        let drain = self.resource_drain(flow) * machines;
+        (drain > 0.0).then(|| patch_size / drain)
+    }
+
+    /// 矿区开采完毕共可得到的产物数量，产能越高、资源消耗越低，同样的储量产出越多
+    pub fn patch_yield(&self, flow: &Flow<GenericItem>) -> Option<f64> {
+        let patch_size = self.patch_size?;
+        let drain = self.resource_drain(flow);
+        if drain <= 0.0 {
+            return None;
+        }
+        let output = flow
+            .iter()
+            .filter(|(item, _)| matches!(item, GenericItem::Item(_) | GenericItem::Fluid { .. }))
+            .map(|(_, amount)| amount.max(0.0))
+            .sum::<f64>();
+        Some(patch_size * output / drain)
+    }
+
+    /// 按工厂的采矿设置计算物料流：采矿产能加在插件产能之上，无限资源的开采速度乘以丰度
+    pub fn as_flow_with_settings(
+        &self,
+        ctx: &FactorioContext,
+        settings: &MiningSettings,
+    ) -> Flow<GenericItem> {
         let mut map = Flow::new();
 
         let mut module_effects = self.module_config.get_effect(ctx).clamped();
@@ -111,7 +288,9 @@ impl AsFlow for MiningConfig {
 
         let quality_level = self.machine.1 as usize;
 
-        let mut drain_rate = ctx.qualities[quality_level].mining_drill_resource_drain_multiplier();
+        let mut drain_rate = ctx.qualities.get(quality_level).map_or(1.0, |quality| {
+            quality.mining_drill_resource_drain_multiplier()
+        });
 
         let miner = ctx.miners.get(&self.machine.0);
 
@@ -127,6 +306,9 @@ impl AsFlow for MiningConfig {
             // TODO: 确认游戏内的舍入方式
             drain_rate *= miner.resource_drain_rate_percent.unwrap_or(100.0) / 100.0;
 
+            if miner.uses_force_mining_productivity_bonus {
+                module_effects.productivity += settings.productivity_bonus();
+            }
             let energy_related_flow = energy_source_as_flow(
                 ctx,
                 &miner.energy_source,
@@ -158,6 +340,9 @@ impl AsFlow for MiningConfig {
         let mining_property = resource_ore.base.minable.as_ref().unwrap();
 
         base_speed /= mining_property.mining_time;
+        if resource_ore.infinite {
+            base_speed *= settings.richness;
+        }
 
         // 计算矿物实体本身的消耗
         index_map_update_entry(
@@ -257,38 +442,10 @@ impl AsFlow for MiningConfig {
         }
         map
     }
-
-    fn cost(&self, ctx: &Self::GameContext) -> f64 {
-        if let Some(miner) = ctx.miners.get(&self.machine.0) {
-            miner
-                .base
-                .collision_box
-                .as_ref()
-                .map_or(1.0, |bounding_box| match bounding_box {
-                    BoundingBox::Struct {
-                        left_top,
-                        right_bottom,
-                        orientation: _,
-                    } => {
-                        f64::ceil(right_bottom.1 - left_top.1)
-                            * f64::ceil(right_bottom.0 - left_top.0)
-                    }
-                    BoundingBox::Pair(map_position, map_position1) => {
-                        f64::ceil(map_position1.1 - map_position.1)
-                            * f64::ceil(map_position1.0 - map_position.0)
-                    }
-                    BoundingBox::Triplet(map_position, map_position1, _) => {
-                        f64::ceil(map_position1.1 - map_position.1)
-                            * f64::ceil(map_position1.0 - map_position.0)
-                    }
-                })
-        } else {
-            16.0
-        }
-    }
 }
 
 impl EditorView for MiningConfig {
+    #[cfg(feature = "gui")]
     fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
         let mut changed = false;
         ui.horizontal_wrapped(|ui| {
@@ -296,7 +453,10 @@ impl EditorView for MiningConfig {
                 ui.label("开采");
 
                 let resource_button = ui
-                    .add_sized([35.0, 35.0], Icon::new(ctx, "entity", &self.resource))
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.resource),
+                    )
                     .interact(egui::Sense::click())
                     .on_hover_text(format!(
                         "矿物：{}",
@@ -323,9 +483,12 @@ impl EditorView for MiningConfig {
             }
             ui.separator();
             ui.vertical(|ui| {
-                ui.add_sized([35.0, 15.0], egui::Label::new("机器"));
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("机器"));
                 let entity_button = ui
-                    .add_sized([35.0, 35.0], Icon::new(ctx, "entity", &self.machine.0))
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0),
+                    )
                     .interact(egui::Sense::click())
                     .on_hover_text(if ctx.miners.contains_key(&self.machine.0) {
                         ctx.get_display_name("entity", &self.machine.0)
@@ -334,6 +497,7 @@ impl EditorView for MiningConfig {
                     });
 
                 if let Some(resource_proto) = ctx.resources.get(&self.resource) {
+                    let whitelist = MachineWhitelist::current(ui.ctx());
                     ui.add(
                         ItemWithQualitySelectorModal::new(
                             entity_button.id,
@@ -345,7 +509,8 @@ impl EditorView for MiningConfig {
                         .with_current(&mut self.machine)
                         .with_filter(|s, f| {
                             if let Some(miner) = f.miners.get(s) {
-                                machine_fits_for_resource(miner, resource_proto)
+                                whitelist.is_allowed(s)
+                                    && machine_fits_for_resource(miner, resource_proto)
                             } else {
                                 false
                             }
@@ -368,6 +533,34 @@ impl EditorView for MiningConfig {
                     .notify_change(&mut changed),
                 );
             }
+            if ctx.resources.contains_key(&self.resource) {
+                ui.separator();
+                ui.vertical(|ui| {
+                    let mut limited = self.patch_size.is_some();
+                    if ui
+                        .checkbox(&mut limited, "矿区储量")
+                        .on_hover_text("填写矿区的资源总量，估计按方案开采多久会枯竭")
+                        .changed()
+                    {
+                        self.patch_size = limited.then_some(DEFAULT_PATCH_SIZE);
+                        changed = true;
+                    }
+                    if let Some(patch_size) = &mut self.patch_size {
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(patch_size)
+                                    .range(0.0..=f64::INFINITY)
+                                    .speed(1000.0)
+                                    .custom_formatter(|value, _| compact_number(value)),
+                            )
+                            .changed();
+                        let flow = self.as_flow(ctx);
+                        if let Some(lifetime) = self.patch_lifetime(&flow, 1.0) {
+                            ui.label(format!("单台可开采 {}", format_duration(lifetime)));
+                        }
+                    }
+                });
+            }
         });
         // 先不判断
         changed
@@ -379,6 +572,9 @@ impl EditorView for MiningConfig {
 pub struct MiningConfigProvider {
     #[serde(skip)]
     pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+    /// 工厂可用的机器，推荐时只选用其中的采矿设备
+    #[serde(skip, default)]
+    pub machine_whitelist: MachineWhitelist,
 }
 
 impl Default for MiningConfigProvider {
@@ -389,7 +585,10 @@ impl Default for MiningConfigProvider {
 
 impl MiningConfigProvider {
     pub fn new() -> Self {
-        Self { sender: None }
+        Self {
+            sender: None,
+            machine_whitelist: MachineWhitelist::default(),
+        }
     }
 }
 
@@ -399,6 +598,7 @@ impl SolveContext for MiningConfigProvider {
 }
 
 impl EditorView for MiningConfigProvider {
+    #[cfg(feature = "gui")]
     fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
         if ui.button("添加采矿").clicked() {
             let mining_config = MiningConfig::default();
@@ -428,71 +628,41 @@ impl MechanicProvider for MiningConfigProvider {
     {
         let mut ret = vec![];
         if value < 0.0 {
-            // 提供生产方式
-            match item {
-                GenericItem::Item(IdWithQuality(name, _)) => {
-                    for resource in ctx.resources.values() {
-                        if let Some(mining) = resource.base.minable.as_ref() {
-                            if let Some(result) = &mining.result {
-                                if result == name {
-                                    let mut mining_config = MiningConfig {
-                                        resource: resource.base.base.name.clone(),
-                                        ..Default::default()
-                                    };
-                                    for miner in ctx.miners.values() {
-                                        if miner.resource_categories.contains(
-                                            resource
-                                                .category
-                                                .as_ref()
-                                                .unwrap_or(&"basic-solid".to_string()),
-                                        ) {
-                                            mining_config.machine =
-                                                (miner.base.base.name.clone(), 0).into();
-                                            break;
-                                        }
-                                    }
-                                    ret.push(Box::new(mining_config)
-                                        as Box<
-                                            dyn Mechanic<
-                                                    ItemIdentType = GenericItem,
-                                                    GameContext = FactorioContext,
-                                                >,
-                                        >);
-                                }
-                            } else {
-                                for res in mining.results.as_ref().unwrap().iter() {
-                                    if let RecipeResult::Item(r) = res
-                                        && &r.name == name
-                                    {
-                                        let mining_config = MiningConfig {
-                                            resource: resource.base.base.name.clone(),
-                                            machine: "entity-unknown".into(),
-                                            module_config: ModuleConfig::default(),
-                                            instance_fuel: None,
-                                        };
-                                        ret.push(Box::new(mining_config)
-                                            as Box<
-                                                dyn Mechanic<
-                                                        ItemIdentType = GenericItem,
-                                                        GameContext = FactorioContext,
-                                                    >,
-                                            >);
+            // 提供生产方式，流体资源由抽油机来源推荐
+            if let GenericItem::Item(IdWithQuality(name, _)) = item {
+                for resource in ctx.resources.values() {
+                    if let Some(mining) = resource.base.minable.as_ref() {
+                        if let Some(result) = &mining.result {
+                            if result == name {
+                                let mut mining_config = MiningConfig {
+                                    resource: resource.base.base.name.clone(),
+                                    ..Default::default()
+                                };
+                                for miner in ctx.miners.values().filter(|miner| {
+                                    self.machine_whitelist.is_allowed(&miner.base.base.name)
+                                }) {
+                                    if miner.resource_categories.contains(
+                                        resource
+                                            .category
+                                            .as_ref()
+                                            .unwrap_or(&"basic-solid".to_string()),
+                                    ) {
+                                        mining_config.machine =
+                                            (miner.base.base.name.clone(), 0).into();
+                                        break;
                                     }
                                 }
+                                ret.push(Box::new(mining_config)
+                                    as Box<
+                                        dyn Mechanic<
+                                                ItemIdentType = GenericItem,
+                                                GameContext = FactorioContext,
+                                            >,
+                                    >);
                             }
-                        }
-                    }
-                }
-                GenericItem::Fluid {
-                    name,
-                    temperature: _,
-                } => {
-                    for resource in ctx.resources.values() {
-                        if let Some(mining) = resource.base.minable.as_ref()
-                            && let Some(results) = &mining.results
-                        {
-                            for res in results.iter() {
-                                if let RecipeResult::Fluid(r) = res
+                        } else {
+                            for res in mining.results.as_ref().unwrap().iter() {
+                                if let RecipeResult::Item(r) = res
                                     && &r.name == name
                                 {
                                     let mining_config = MiningConfig {
@@ -500,6 +670,7 @@ impl MechanicProvider for MiningConfigProvider {
                                         machine: "entity-unknown".into(),
                                         module_config: ModuleConfig::default(),
                                         instance_fuel: None,
+                                        patch_size: None,
                                     };
                                     ret.push(Box::new(mining_config)
                                         as Box<
@@ -513,7 +684,6 @@ impl MechanicProvider for MiningConfigProvider {
                         }
                     }
                 }
-                _ => {}
             }
         } else {
             // TODO 提供消耗方式
@@ -530,6 +700,7 @@ fn test_mining_normalized() {
         machine: "big-mining-drill".into(),
         module_config: ModuleConfig::default(),
         instance_fuel: None,
+        patch_size: None,
     };
 
     let result = mining_config.as_flow(&ctx);
@@ -550,3 +721,89 @@ crate::impl_register_deserializer!(
     as "factorio:mining"
     => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
 );
+
+#[test]
+fn test_mining_settings() {
+    let ctx = FactorioContext::test_load();
+    let iron = MiningConfig {
+        resource: "iron-ore".to_string(),
+        machine: "electric-mining-drill".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+        patch_size: None,
+    };
+    let ore = GenericItem::Item("iron-ore".into());
+    let base = iron.as_flow(&ctx)[&ore];
+    // 电力采矿机每秒开采 0.5 个矿石
+    assert!((base - 0.5).abs() < 1e-9);
+    let mut settings = MiningSettings {
+        productivity_level: 5,
+        ..Default::default()
+    };
+    let boosted = iron.as_flow_with_settings(&ctx, &settings);
+    assert!((boosted[&ore] - 0.75).abs() < 1e-9);
+    // 原油是无限资源，产出率与丰度成正比
+    settings.richness = 2.0;
+    let oil = MiningConfig {
+        resource: "crude-oil".to_string(),
+        machine: "pumpjack".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+        patch_size: None,
+    };
+    let crude = GenericItem::Fluid {
+        name: "crude-oil".to_string(),
+        temperature: None,
+    };
+    let base = oil.as_flow(&ctx)[&crude];
+    let boosted = oil.as_flow_with_settings(&ctx, &settings)[&crude];
+    assert!((boosted - base * 2.0 * 1.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_patch_lifetime() {
+    let ctx = FactorioContext::test_load();
+    let mut iron = MiningConfig {
+        resource: "iron-ore".to_string(),
+        machine: "electric-mining-drill".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+        patch_size: None,
+    };
+    let flow = iron.as_flow(&ctx);
+    assert_eq!(iron.patch_lifetime(&flow, 1.0), None);
+    // 电力采矿机每秒消耗 0.5 资源，两台开采 1000 储量需要 1000 秒
+    iron.patch_size = Some(1000.0);
+    assert!((iron.resource_drain(&flow) - 0.5).abs() < 1e-9);
+    assert!((iron.patch_lifetime(&flow, 2.0).unwrap() - 1000.0).abs() < 1e-9);
+    assert!((iron.patch_yield(&flow).unwrap() - 1000.0).abs() < 1e-9);
+    // 采矿产能不增加资源消耗，同样的储量产出更多
+    let settings = MiningSettings {
+        productivity_level: 5,
+        ..Default::default()
+    };
+    let boosted = iron.as_flow_with_settings(&ctx, &settings);
+    assert!((iron.patch_yield(&boosted).unwrap() - 1500.0).abs() < 1e-9);
+    // 大型采矿机只消耗一半资源，高品质的采矿机消耗更少
+    let mut big = MiningConfig {
+        machine: "big-mining-drill".into(),
+        ..iron.clone()
+    };
+    let big_flow = big.as_flow(&ctx);
+    assert!(big.patch_yield(&big_flow).unwrap() > 1900.0);
+    let normal_lifetime = big.patch_lifetime(&big_flow, 1.0).unwrap();
+    big.machine = IdWithQuality("big-mining-drill".to_string(), 2);
+    let rare_flow = big.as_flow(&ctx);
+    assert!(big.resource_drain(&rare_flow) < big.resource_drain(&big_flow));
+    assert!(big.patch_lifetime(&rare_flow, 1.0).unwrap() > normal_lifetime);
+    // 储量会随配置一起保存，未设置时不写入
+    let json = serde_json::to_value(&iron).unwrap();
+    assert_eq!(json["patch_size"], 1000.0);
+    iron.patch_size = None;
+    assert!(
+        serde_json::to_value(&iron)
+            .unwrap()
+            .get("patch_size")
+            .is_none()
+    );
+}
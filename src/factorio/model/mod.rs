@@ -1,23 +1,77 @@
+mod asteroid;
+mod boiler;
+mod cache;
 mod context;
+mod custom;
+mod defense;
 mod energy;
 mod entity;
+mod export;
 mod fluid;
+mod fusion;
+mod generator;
+mod install;
 mod item;
+mod lab;
+mod lightning;
+mod logistics;
 mod mining;
 mod module;
+mod offshore;
 mod planet;
+mod profile;
+mod progress;
+mod pumpjack;
 mod quality;
+mod reactor;
 mod recipe;
+mod recycler;
+mod rocket;
+mod savegame;
+mod solar;
+mod spawner;
+mod sprite;
+mod technology;
+mod thruster;
 mod tile;
+mod upload;
+mod whitelist;
 
+pub use asteroid::*;
+pub use boiler::*;
+pub use cache::*;
 pub use context::*;
+pub use custom::*;
+pub use defense::*;
 pub use energy::*;
 pub use entity::*;
+pub use export::*;
 pub use fluid::*;
+pub use fusion::*;
+pub use generator::*;
+pub use install::*;
 pub use item::*;
+pub use lab::*;
+pub use lightning::*;
+pub use logistics::*;
 pub use mining::*;
 pub use module::*;
+pub use offshore::*;
 pub use planet::*;
+pub use profile::*;
+pub use progress::*;
+pub use pumpjack::*;
 pub use quality::*;
+pub use reactor::*;
 pub use recipe::*;
+pub use recycler::*;
+pub use rocket::*;
+pub use savegame::*;
+pub use solar::*;
+pub use spawner::*;
+pub use sprite::*;
+pub use technology::*;
+pub use thruster::*;
 pub use tile::*;
+pub use upload::*;
+pub use whitelist::*;
@@ -1,20 +1,24 @@
 use indexmap::IndexMap;
 
+#[cfg(feature = "gui")]
+use crate::factorio::{
+    editor::{
+        icon::{GenericIcon, Icon},
+        modal::show_modal,
+    },
+    format::CompactLabel,
+    modal::ItemWithQualitySelectorModal,
+    model::QualityPrototype,
+};
 use crate::{
-    concept::SolveContext,
+    concept::{Flow, SolveContext},
     factorio::{
         common::*,
-        editor::{
-            icon::{GenericIcon, Icon},
-            modal::show_modal,
-        },
-        format::CompactLabel,
-        modal::ItemWithQualitySelectorModal,
-        model::{context::*, entity::*},
+        model::{QualityAvailability, context::*, entity::*},
     },
 };
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ModulePrototype {
     #[serde(flatten)]
     pub base: PrototypeBase,
@@ -29,7 +33,7 @@ pub struct ModulePrototype {
     pub tier: f64,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct BeaconPrototype {
     #[serde(flatten)]
     pub base: EntityPrototype,
@@ -62,6 +66,13 @@ fn floor_to_percentage(value: f64) -> f64 {
     (value * 100.0).trunc() / 100.0
 }
 
+/// 插件品质的效果倍率，上下文中不存在的品质按普通品质计算
+fn quality_multiplier(ctx: &FactorioContext, quality: u8) -> f64 {
+    ctx.qualities
+        .get(quality as usize)
+        .map_or(1.0, |quality| quality.default_multiplier())
+}
+
 pub fn effects_under_quality(effect: &Effect, multiplier: f64) -> Effect {
     let mut effect = effect.clone();
     if effect.consumption < 0.0 {
@@ -87,7 +98,7 @@ pub fn effects_under_quality(effect: &Effect, multiplier: f64) -> Effect {
     effect
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum BeaconCounter {
     #[default]
@@ -116,6 +127,48 @@ impl ModuleConfig {
         }
     }
 
+    /// 去掉机器与配方不允许的插件，超出机器插件槽的插件一并去掉，不再有插件的插件塔也被移除
+    pub fn restricted(
+        &self,
+        ctx: &FactorioContext,
+        module_slots: usize,
+        allowed_effects: &Option<EffectTypeLimitation>,
+        allowed_module_categories: &Option<Vec<String>>,
+    ) -> ModuleConfig {
+        let allowed = |module: &IdWithQuality| {
+            ctx.modules.get(&module.0).is_some_and(|prototype| {
+                allowed_module_categories
+                    .as_ref()
+                    .is_none_or(|categories| categories.contains(&prototype.category))
+                    && module_effects_allowed(prototype, allowed_effects)
+            })
+        };
+        let mut modules = self
+            .modules
+            .iter()
+            .filter(|module| allowed(module))
+            .cloned()
+            .collect::<Vec<_>>();
+        modules.truncate(module_slots);
+        let beacons = self
+            .beacons
+            .iter()
+            .filter_map(|beacon| {
+                let modules = beacon
+                    .modules
+                    .iter()
+                    .filter(|(module, _)| allowed(module))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (!modules.is_empty()).then(|| BeaconConfig {
+                    modules,
+                    ..beacon.clone()
+                })
+            })
+            .collect();
+        ModuleConfig { modules, beacons }
+    }
+
     pub fn get_effect(&self, ctx: &FactorioContext) -> Effect {
         let mut total_effect = Effect::default();
         for module in &self.modules {
@@ -123,7 +176,7 @@ impl ModuleConfig {
                 total_effect = total_effect
                     + effects_under_quality(
                         &module_proto.effect,
-                        ctx.qualities[module.1 as usize].default_multiplier(),
+                        quality_multiplier(ctx, module.1),
                     );
             }
         }
@@ -136,8 +189,10 @@ impl ModuleConfig {
         for beacon_config in &self.beacons {
             if let Some(beacon_proto) = ctx.beacons.get(&beacon_config.beacon.0) {
                 let effective_module_slots = if beacon_proto.quality_affects_module_slots {
-                    let quality_bonus =
-                        ctx.qualities[beacon_config.beacon.1 as usize].beacon_module_slots_bonus();
+                    let quality_bonus = ctx
+                        .qualities
+                        .get(beacon_config.beacon.1 as usize)
+                        .map_or(0.0, |quality| quality.beacon_module_slots_bonus());
                     beacon_proto.module_slots as usize + quality_bonus as usize
                 } else {
                     beacon_proto.module_slots as usize
@@ -171,12 +226,15 @@ impl ModuleConfig {
                 };
                 let base_efficiency = beacon_proto.distribution_effectivity
                     + beacon_proto.distribution_effectivity_bonus_per_quality_level
-                        * ctx.qualities[beacon_config.beacon.1 as usize].level;
+                        * ctx
+                            .qualities
+                            .get(beacon_config.beacon.1 as usize)
+                            .map_or(0.0, |quality| quality.level);
                 for (module, count) in &beacon_config.modules {
                     if let Some(module_proto) = ctx.modules.get(&module.0) {
                         let module_effect = effects_under_quality(
                             &module_proto.effect,
-                            ctx.qualities[module.1 as usize].default_multiplier(),
+                            quality_multiplier(ctx, module.1),
                         );
                         let total_module_effect = module_effect
                             * (*count as f64)
@@ -197,6 +255,7 @@ impl SolveContext for ModuleConfig {
     type ItemIdentType = GenericItem;
 }
 
+#[cfg(feature = "gui")]
 pub struct ModuleConfigEditor<'a> {
     pub module_config: &'a mut ModuleConfig,
 
@@ -208,6 +267,7 @@ pub struct ModuleConfigEditor<'a> {
     pub changed: Option<&'a mut bool>,
 }
 
+#[cfg(feature = "gui")]
 impl<'a> ModuleConfigEditor<'a> {
     pub fn new(
         ctx: &'a FactorioContext,
@@ -252,6 +312,7 @@ fn module_effects_allowed(
     }
 }
 
+#[cfg(feature = "gui")]
 impl egui::Widget for ModuleConfigEditor<'_> {
     fn ui(mut self, ui: &mut egui::Ui) -> egui::Response {
         let button = ui
@@ -307,7 +368,10 @@ impl egui::Widget for ModuleConfigEditor<'_> {
                 ui.vertical(|ui| {
                     ui.spacing_mut().item_spacing = [3.0, 3.0].into();
                     ui.add_sized([32.0, 32.0], GenericIcon::new(self.ctx, &item));
-                    ui.add_sized([35.0, 15.0], CompactLabel::new(count as f64));
+                    ui.add_sized(
+                        crate::settings::label_cell(),
+                        CompactLabel::new(count as f64),
+                    );
                 });
             }
         });
@@ -320,7 +384,7 @@ impl egui::Widget for ModuleConfigEditor<'_> {
                         let mut deleted = false;
                         let icon = ui
                             .add_sized(
-                                [35.0, 35.0],
+                                crate::settings::icon_cell(),
                                 Icon::new(self.ctx, "item", &slot.0).with_quality(slot.1),
                             )
                             .interact(egui::Sense::click());
@@ -358,7 +422,7 @@ impl egui::Widget for ModuleConfigEditor<'_> {
                     for idx in len..self.module_slots {
                         let icon = ui
                             .add_sized(
-                                [35.0, 35.0],
+                                crate::settings::icon_cell(),
                                 Icon::new(self.ctx, "item", "empty-module-slot"),
                             )
                             .interact(egui::Sense::click());
@@ -416,7 +480,7 @@ impl egui::Widget for ModuleConfigEditor<'_> {
                         ui.vertical(|ui| {
                             let icon = ui
                                 .add_sized(
-                                    [35.0, 35.0],
+                                    crate::settings::icon_cell(),
                                     Icon::new(self.ctx, "entity", &beacon_config.beacon.0)
                                         .with_quality(beacon_config.beacon.1),
                                 )
@@ -451,7 +515,7 @@ impl egui::Widget for ModuleConfigEditor<'_> {
                                 ui.vertical(|ui| {
                                     let icon = ui
                                         .add_sized(
-                                            [35.0, 35.0],
+                                            crate::settings::icon_cell(),
                                             Icon::new(self.ctx, "item", &id.0).with_quality(id.1),
                                         )
                                         .on_hover_text(if self.ctx.modules.contains_key(&id.0) {
@@ -503,7 +567,7 @@ impl egui::Widget for ModuleConfigEditor<'_> {
                                             0
                                         };
                                     let amount_widget = ui.add_sized(
-                                        [35.0, 15.0],
+                                        crate::settings::label_cell(),
                                         egui::DragValue::new(amount)
                                             .range(
                                                 0..=(beacon_module_count * beacon_config.count
@@ -539,3 +603,390 @@ impl egui::Widget for ModuleConfigEditor<'_> {
         ui.response().clone()
     }
 }
+
+/// 品质插件的类别
+pub const QUALITY_MODULE_CATEGORY: &str = "quality";
+
+/// 已研究的插件等级与品质，以及推荐配方时默认填充插件的偏好
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ModuleResearch {
+    /// 各插件类别已解锁的最高等级，未记录的类别视为全部解锁
+    pub unlocked_tiers: IndexMap<String, u32>,
+    /// 推荐配方时用来填满插件槽的插件类别，None 表示不填充
+    pub preferred_category: Option<String>,
+    /// 手动指定的插件等级，覆盖按研究进度自动选择的结果
+    pub tier_override: Option<u32>,
+    /// 已解锁的品质
+    pub quality: QualityAvailability,
+}
+
+impl ModuleResearch {
+    pub fn max_tier(&self, category: &str) -> Option<u32> {
+        self.tier_override
+            .or_else(|| self.unlocked_tiers.get(category).cloned())
+    }
+
+    /// 在允许范围内选出已解锁的最高等级插件
+    pub fn best_module<'a>(
+        &self,
+        ctx: &'a FactorioContext,
+        category: &str,
+        allowed_effects: &Option<EffectTypeLimitation>,
+        allowed_module_categories: &Option<Vec<String>>,
+    ) -> Option<&'a ModulePrototype> {
+        let max_tier = self.max_tier(category);
+        ctx.modules
+            .values()
+            .filter(|module| module.category == category)
+            .filter(|module| max_tier.is_none_or(|max_tier| module.tier <= max_tier as f64))
+            .filter(|module| {
+                allowed_module_categories
+                    .as_ref()
+                    .is_none_or(|categories| categories.contains(&module.category))
+                    && module_effects_allowed(module, allowed_effects)
+            })
+            .max_by(|a, b| a.tier.total_cmp(&b.tier))
+    }
+
+    /// 按偏好生成填满插件槽的插件配置，没有偏好或没有可用插件时为空
+    pub fn fill_module_config(
+        &self,
+        ctx: &FactorioContext,
+        module_slots: usize,
+        allowed_effects: &Option<EffectTypeLimitation>,
+        allowed_module_categories: &Option<Vec<String>>,
+    ) -> ModuleConfig {
+        let mut module_config = ModuleConfig::new();
+        if let Some(category) = &self.preferred_category
+            && let Some(module) =
+                self.best_module(ctx, category, allowed_effects, allowed_module_categories)
+        {
+            module_config.modules = vec![IdWithQuality(module.base.name.clone(), 0); module_slots];
+        }
+        module_config
+    }
+}
+
+/// 上下文中出现的插件类别及其最高等级
+pub fn module_categories(ctx: &FactorioContext) -> IndexMap<String, u32> {
+    let mut categories = IndexMap::new();
+    for module in ctx.modules.values() {
+        let tier = categories.entry(module.category.clone()).or_insert(0);
+        *tier = (*tier).max(module.tier as u32);
+    }
+    categories.sort_keys();
+    categories
+}
+
+/// 插件与插件塔的成本核算。默认视为免费；开启后把每台机器用到的插件与插件塔
+/// 按摊销时长折算为持续的物品消耗计入求解，一次性的建造数量见建造清单
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ModuleCost {
+    pub enabled: bool,
+    /// 建造成本在这段时间（小时）内均摊
+    pub amortize_hours: f64,
+}
+
+impl Default for ModuleCost {
+    fn default() -> Self {
+        ModuleCost {
+            enabled: false,
+            amortize_hours: 10.0,
+        }
+    }
+}
+
+impl ModuleCost {
+    /// 每台机器用到的插件与插件塔物品。插件塔按每台机器独立计算，
+    /// 相邻机器共享插件塔时实际数量会更少
+    pub fn items(
+        ctx: &FactorioContext,
+        module_config: &ModuleConfig,
+    ) -> IndexMap<IdWithQuality, f64> {
+        let mut items = IndexMap::new();
+        for module in &module_config.modules {
+            *items.entry(module.clone()).or_insert(0.0) += 1.0;
+        }
+        for beacon in &module_config.beacons {
+            let count = beacon.count as f64;
+            let item = ctx
+                .items_placing(&beacon.beacon.0)
+                .first()
+                .cloned()
+                .unwrap_or_else(|| beacon.beacon.0.clone());
+            *items
+                .entry(IdWithQuality(item, beacon.beacon.1))
+                .or_insert(0.0) += count;
+            for (module, amount) in &beacon.modules {
+                *items.entry(module.clone()).or_insert(0.0) += *amount as f64 * count;
+            }
+        }
+        items
+    }
+
+    /// 开启后在每台机器的物料流中扣除摊销后的插件与插件塔
+    pub fn apply(
+        &self,
+        ctx: &FactorioContext,
+        module_config: &ModuleConfig,
+        flow: &mut Flow<GenericItem>,
+    ) {
+        if !self.enabled || self.amortize_hours <= 0.0 {
+            return;
+        }
+        let seconds = self.amortize_hours * 3600.0;
+        for (item, count) in Self::items(ctx, module_config) {
+            index_map_update_entry(flow, GenericItem::Item(item), -count / seconds);
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+pub struct ModuleCostEditor<'a> {
+    pub cost: &'a mut ModuleCost,
+    pub changed: Option<&'a mut bool>,
+}
+
+#[cfg(feature = "gui")]
+impl<'a> ModuleCostEditor<'a> {
+    pub fn new(cost: &'a mut ModuleCost) -> Self {
+        Self {
+            cost,
+            changed: None,
+        }
+    }
+
+    pub fn notify_change(mut self, changed: &'a mut bool) -> Self {
+        self.changed = Some(changed);
+        self
+    }
+}
+
+#[cfg(feature = "gui")]
+impl egui::Widget for ModuleCostEditor<'_> {
+    fn ui(mut self, ui: &mut egui::Ui) -> egui::Response {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            changed |= ui
+                .checkbox(&mut self.cost.enabled, "计入插件成本")
+                .on_hover_text(
+                    "把插件与插件塔按摊销时长折算为持续消耗的物品，一次性的数量见建造清单",
+                )
+                .changed();
+            ui.add_enabled_ui(self.cost.enabled, |ui| {
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.cost.amortize_hours)
+                            .range(0.1..=f64::INFINITY)
+                            .speed(0.5)
+                            .prefix("摊销 ")
+                            .suffix(" 小时"),
+                    )
+                    .changed();
+            });
+        });
+        if changed && let Some(flag) = &mut self.changed {
+            **flag = true;
+        }
+        ui.response().clone()
+    }
+}
+
+#[cfg(feature = "gui")]
+pub struct ModuleResearchEditor<'a> {
+    pub research: &'a mut ModuleResearch,
+    pub ctx: &'a FactorioContext,
+    pub changed: Option<&'a mut bool>,
+}
+
+#[cfg(feature = "gui")]
+impl<'a> ModuleResearchEditor<'a> {
+    pub fn new(ctx: &'a FactorioContext, research: &'a mut ModuleResearch) -> Self {
+        Self {
+            research,
+            ctx,
+            changed: None,
+        }
+    }
+
+    pub fn notify_change(mut self, changed: &'a mut bool) -> Self {
+        self.changed = Some(changed);
+        self
+    }
+}
+
+#[cfg(feature = "gui")]
+impl egui::Widget for ModuleResearchEditor<'_> {
+    fn ui(mut self, ui: &mut egui::Ui) -> egui::Response {
+        let mut changed = false;
+        let categories = module_categories(self.ctx);
+        ui.vertical(|ui| {
+            ui.label("已研究的插件等级");
+            egui::Grid::new("module-research").show(ui, |ui| {
+                for (category, max_tier) in &categories {
+                    ui.label(category);
+                    let mut all_unlocked = !self.research.unlocked_tiers.contains_key(category);
+                    if ui.checkbox(&mut all_unlocked, "全部解锁").changed() {
+                        if all_unlocked {
+                            self.research.unlocked_tiers.shift_remove(category);
+                        } else {
+                            self.research
+                                .unlocked_tiers
+                                .insert(category.clone(), *max_tier);
+                        }
+                        changed = true;
+                    }
+                    if let Some(tier) = self.research.unlocked_tiers.get_mut(category) {
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(tier)
+                                    .range(0..=*max_tier)
+                                    .prefix("等级 "),
+                            )
+                            .changed();
+                    }
+                    ui.end_row();
+                }
+            });
+            ui.separator();
+            ui.label("已解锁的品质");
+            ui.horizontal(|ui| {
+                let mut all_unlocked = self.research.quality.max_quality.is_none();
+                if ui.checkbox(&mut all_unlocked, "全部解锁").changed() {
+                    self.research.quality.max_quality = if all_unlocked { None } else { Some(0) };
+                    changed = true;
+                }
+                if let Some(max_quality) = &mut self.research.quality.max_quality {
+                    let name = |quality: &QualityPrototype| {
+                        self.ctx.get_display_name("quality", &quality.base.name)
+                    };
+                    egui::ComboBox::new("module-research-quality", "最高品质")
+                        .selected_text(
+                            self.ctx
+                                .qualities
+                                .get(*max_quality as usize)
+                                .map(name)
+                                .unwrap_or_default(),
+                        )
+                        .show_ui(ui, |ui| {
+                            for (idx, quality) in self.ctx.qualities.iter().enumerate() {
+                                changed |= ui
+                                    .selectable_value(max_quality, idx as u8, name(quality))
+                                    .changed();
+                            }
+                        });
+                }
+            });
+            changed |= ui
+                .checkbox(
+                    &mut self.research.quality.restrict_suggestions,
+                    "推荐配方时跳过未解锁的品质",
+                )
+                .on_hover_text("未勾选时只在品质选择器中标出未解锁的品质")
+                .changed();
+            ui.separator();
+            egui::ComboBox::new("module-research-category", "推荐配方时填充的插件")
+                .selected_text(
+                    self.research
+                        .preferred_category
+                        .clone()
+                        .unwrap_or("不填充".to_string()),
+                )
+                .show_ui(ui, |ui| {
+                    changed |= ui
+                        .selectable_value(&mut self.research.preferred_category, None, "不填充")
+                        .changed();
+                    for category in categories.keys() {
+                        changed |= ui
+                            .selectable_value(
+                                &mut self.research.preferred_category,
+                                Some(category.clone()),
+                                category,
+                            )
+                            .changed();
+                    }
+                });
+            ui.horizontal(|ui| {
+                let mut overridden = self.research.tier_override.is_some();
+                if ui.checkbox(&mut overridden, "手动指定等级").changed() {
+                    self.research.tier_override = overridden.then_some(1);
+                    changed = true;
+                }
+                if let Some(tier) = &mut self.research.tier_override {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(tier)
+                                .range(0..=u32::MAX)
+                                .prefix("等级 "),
+                        )
+                        .changed();
+                }
+            });
+        });
+        if changed && let Some(flag) = &mut self.changed {
+            **flag = true;
+        }
+        ui.response().clone()
+    }
+}
+
+#[test]
+fn test_module_research() {
+    let ctx = FactorioContext::test_load();
+    let mut research = ModuleResearch {
+        preferred_category: Some("productivity".to_string()),
+        ..Default::default()
+    };
+    let allowed_effects = Some(EffectTypeLimitation::new(true, true, true, true, true));
+    let best = research
+        .best_module(&ctx, "productivity", &allowed_effects, &None)
+        .unwrap();
+    research
+        .unlocked_tiers
+        .insert("productivity".to_string(), 1);
+    let unlocked = research
+        .best_module(&ctx, "productivity", &allowed_effects, &None)
+        .unwrap();
+    assert!(best.tier >= unlocked.tier);
+    assert_eq!(unlocked.tier, 1.0);
+    let module_config = research.fill_module_config(&ctx, 4, &allowed_effects, &None);
+    assert_eq!(module_config.modules.len(), 4);
+    assert_eq!(module_config.modules[0].0, unlocked.base.name);
+    research
+        .unlocked_tiers
+        .insert("productivity".to_string(), 0);
+    assert!(
+        research
+            .fill_module_config(&ctx, 4, &allowed_effects, &None)
+            .modules
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_module_cost() {
+    let ctx = FactorioContext::test_load();
+    let module_config = ModuleConfig {
+        modules: vec!["speed-module".into(); 2],
+        beacons: vec![BeaconConfig {
+            modules: vec![("speed-module".into(), 2)],
+            beacon: "beacon".into(),
+            count: 4,
+        }],
+    };
+    let items = ModuleCost::items(&ctx, &module_config);
+    assert_eq!(items[&IdWithQuality::from("speed-module")], 10.0);
+    assert_eq!(items[&IdWithQuality::from("beacon")], 4.0);
+    // 默认视为免费
+    let mut cost = ModuleCost::default();
+    let mut flow = Flow::new();
+    cost.apply(&ctx, &module_config, &mut flow);
+    assert!(flow.is_empty());
+    // 10 个插件在 10 小时内均摊
+    cost.enabled = true;
+    cost.apply(&ctx, &module_config, &mut flow);
+    let speed = GenericItem::Item("speed-module".into());
+    assert!((flow[&speed] + 10.0 / 36000.0).abs() < 1e-12);
+}
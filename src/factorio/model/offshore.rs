@@ -0,0 +1,287 @@
+#[cfg(feature = "gui")]
+use crate::factorio::{
+    editor::icon::Icon,
+    modal::{ItemSelectorModal, ItemWithQualitySelectorModal},
+};
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        model::{context::*, energy::*, entity::*},
+    },
+};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct OffshorePumpPrototype {
+    #[serde(flatten)]
+    pub base: EntityPrototype,
+
+    /// 每刻抽取的流体量
+    pub pumping_speed: f64,
+
+    pub energy_source: EnergySource,
+    #[serde(default)]
+    pub energy_usage: Option<EnergyAmount>,
+}
+
+impl HasPrototypeBase for OffshorePumpPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base.base
+    }
+}
+
+/// 放置在流体地块旁的抽水泵，抽取地块对应的流体，视为无限来源
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:offshore-pump")]
+pub struct OffshorePumpConfig {
+    pub tile: String,
+    pub machine: IdWithQuality,
+    pub instance_fuel: Option<IdWithQuality>,
+}
+
+impl Default for OffshorePumpConfig {
+    fn default() -> Self {
+        OffshorePumpConfig {
+            tile: "tile-unknown".to_string(),
+            machine: ("entity-unknown".to_string(), 0).into(),
+            instance_fuel: None,
+        }
+    }
+}
+
+impl OffshorePumpConfig {
+    /// 地块提供的流体
+    pub fn fluid<'a>(&self, ctx: &'a FactorioContext) -> Option<&'a String> {
+        ctx.tiles
+            .get(&self.tile)
+            .and_then(|tile| tile.fluid.as_ref())
+    }
+}
+
+impl SolveContext for OffshorePumpConfig {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for OffshorePumpConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        let mut map = Flow::new();
+        let Some(pump) = ctx.offshore_pumps.get(&self.machine.0) else {
+            return map;
+        };
+        // 能源不足时按比例降低抽取速度
+        let mut fulfillment = 1.0;
+        if let Some(energy_usage) = &pump.energy_usage {
+            let energy_related_flow = energy_source_as_flow(
+                ctx,
+                &pump.energy_source,
+                energy_usage,
+                &Effect::default(),
+                &self
+                    .instance_fuel
+                    .as_ref()
+                    .map(|id_with_quality| (id_with_quality.0.clone(), id_with_quality.1 as i32)),
+                &mut fulfillment,
+            );
+            for (key, value) in energy_related_flow.into_iter() {
+                index_map_update_entry(&mut map, key, value);
+            }
+        }
+        if let Some(fluid) = self.fluid(ctx) {
+            // 与不要求温度的配方原料对应，不标注温度
+            index_map_update_entry(
+                &mut map,
+                GenericItem::Fluid {
+                    name: fluid.clone(),
+                    temperature: None,
+                },
+                pump.pumping_speed * 60.0 * fulfillment,
+            );
+        }
+        map
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        ctx.offshore_pumps
+            .get(&self.machine.0)
+            .map_or(16.0, |pump| {
+                pump.base
+                    .collision_box
+                    .as_ref()
+                    .map_or(1.0, BoundingBox::tile_area)
+            })
+    }
+}
+
+impl EditorView for OffshorePumpConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.label("地块");
+                let tile_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "tile", &self.tile),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(match self.fluid(ctx) {
+                        Some(fluid) => format!(
+                            "{}：{}",
+                            ctx.get_display_name("tile", &self.tile),
+                            ctx.get_display_name("fluid", fluid)
+                        ),
+                        None => "地块：未选择".to_string(),
+                    });
+                ui.add(
+                    ItemSelectorModal::new(tile_button.id, ctx, "选择流体地块", "tile")
+                        .with_toggle(tile_button.clicked())
+                        .with_current(&mut self.tile)
+                        .with_filter(|s, f| f.tiles.get(s).is_some_and(|t| t.fluid.is_some()))
+                        .notify_change(&mut changed),
+                );
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("机器"));
+                let entity_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0).with_quality(self.machine.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(if ctx.offshore_pumps.contains_key(&self.machine.0) {
+                        ctx.get_display_name("entity", &self.machine.0)
+                    } else {
+                        "抽水泵：未选择".into()
+                    });
+                ui.add(
+                    ItemWithQualitySelectorModal::new(
+                        entity_button.id,
+                        ctx,
+                        "选择抽水泵",
+                        "entity",
+                    )
+                    .with_toggle(entity_button.clicked())
+                    .with_current(&mut self.machine)
+                    .with_filter(|s, f| f.offshore_pumps.contains_key(s))
+                    .notify_change(&mut changed),
+                );
+            });
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:offshore-pump")]
+pub struct OffshorePumpConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+}
+
+impl Default for OffshorePumpConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OffshorePumpConfigProvider {
+    pub fn new() -> Self {
+        Self { sender: None }
+    }
+}
+
+impl SolveContext for OffshorePumpConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for OffshorePumpConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        if ui.button("添加抽水泵").clicked() {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(OffshorePumpConfig::default()));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for OffshorePumpConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        let GenericItem::Fluid { name, .. } = item else {
+            return vec![];
+        };
+        if value >= 0.0 {
+            return vec![];
+        }
+        // 优先选择与流体同名的地块，其余按名称取第一个
+        let mut tiles = ctx
+            .tiles
+            .values()
+            .filter(|tile| tile.fluid.as_ref() == Some(name))
+            .map(|tile| &tile.base.name)
+            .collect::<Vec<_>>();
+        tiles.sort_by_key(|tile| (*tile != name, tile.as_str()));
+        let (Some(tile), Some(pump)) = (tiles.first(), ctx.offshore_pumps.keys().min()) else {
+            return vec![];
+        };
+        vec![Box::new(OffshorePumpConfig {
+            tile: (*tile).clone(),
+            machine: pump.as_str().into(),
+            instance_fuel: None,
+        })]
+    }
+}
+
+#[test]
+fn test_offshore_pump() {
+    let ctx = FactorioContext::test_load();
+    let provider = OffshorePumpConfigProvider::new();
+    let water = GenericItem::Fluid {
+        name: "water".to_string(),
+        temperature: None,
+    };
+    let hints = provider.hint_populate(&ctx, &water, -1.0);
+    assert_eq!(hints.len(), 1);
+    // 抽水泵每刻抽取 20 单位水
+    assert_eq!(hints[0].as_flow(&ctx).get(&water), Some(&1200.0));
+    let lava = OffshorePumpConfig {
+        tile: "lava".to_string(),
+        machine: "offshore-pump".into(),
+        instance_fuel: None,
+    };
+    assert!(lava.as_flow(&ctx).contains_key(&GenericItem::Fluid {
+        name: "lava".to_string(),
+        temperature: None,
+    }));
+}
+
+crate::impl_register_deserializer!(
+    for OffshorePumpConfig
+    as "factorio:offshore-pump"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for OffshorePumpConfigProvider
+    as "factorio:offshore-pump"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
@@ -4,7 +4,7 @@ use crate::factorio::{
     Dict, FactorioContext, GenericItem, HasPrototypeBase, IdWithQuality, PrototypeBase,
 };
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PlanetPrototype {
     #[serde(flatten)]
     pub base: PrototypeBase,
@@ -16,6 +16,93 @@ pub struct PlanetPrototype {
     pub pollutant_type: Option<String>,
     #[serde(default)]
     pub map_gen_settings: PlanetPrototypeMapGenSettings,
+
+    /// 地表属性，如昼夜周期与光照强度，缺失时取属性原型的默认值
+    #[serde(default)]
+    pub surface_properties: Dict<f64>,
+
+    /// 落雷设置，只有富尔戈拉这样有雷暴的星球才有
+    #[serde(default)]
+    pub lightning_properties: Option<LightningProperties>,
+
+    /// 停在星球轨道上时生成的小行星
+    #[serde(default)]
+    pub asteroid_spawn_definitions: Vec<AsteroidSpawnDefinition>,
+}
+
+/// 航线上某一位置的小行星生成频率，distance 为航线进度（0 ~ 1）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AsteroidSpawnPoint {
+    pub distance: f64,
+    /// 每刻生成的概率
+    pub probability: f64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AsteroidSpawnDefinition {
+    pub asteroid: String,
+    /// 为 asteroid-chunk 时生成的是小行星碎块，否则是小行星实体
+    #[serde(default, rename = "type")]
+    pub kind: Option<String>,
+    /// 星球轨道上的定义没有生成点，直接给出每刻生成的概率
+    #[serde(default)]
+    pub probability: f64,
+    #[serde(default)]
+    pub spawn_points: Vec<AsteroidSpawnPoint>,
+}
+
+impl AsteroidSpawnDefinition {
+    pub fn is_chunk(&self) -> bool {
+        self.kind.as_deref() == Some("asteroid-chunk")
+    }
+
+    /// 整条航线上平均每刻生成的概率
+    ///
+    /// 生成点之间按进度线性插值，第一个生成点之前与最后一个生成点之后取端点的值。
+    pub fn average_probability(&self) -> f64 {
+        let points = &self.spawn_points;
+        let (Some(first), Some(last)) = (points.first(), points.last()) else {
+            return self.probability;
+        };
+        let mut total = first.probability * first.distance.clamp(0.0, 1.0)
+            + last.probability * (1.0 - last.distance.clamp(0.0, 1.0));
+        for pair in points.windows(2) {
+            let width = (pair[1].distance - pair[0].distance).max(0.0);
+            total += (pair[0].probability + pair[1].probability) / 2.0 * width;
+        }
+        total
+    }
+}
+
+/// 星球之间的航线
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SpaceConnectionPrototype {
+    #[serde(flatten)]
+    pub base: PrototypeBase,
+
+    pub from: String,
+    pub to: String,
+    /// 航线长度（km）
+    #[serde(default)]
+    pub length: f64,
+    #[serde(default)]
+    pub asteroid_spawn_definitions: Vec<AsteroidSpawnDefinition>,
+}
+
+impl HasPrototypeBase for SpaceConnectionPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LightningProperties {
+    /// 每个区块每刻的平均落雷次数
+    pub lightnings_per_chunk_per_tick: f64,
+    /// 落雷点附近搜索吸引物的半径
+    pub search_radius: f64,
+    /// 可能出现的闪电原型
+    pub lightning_types: Vec<String>,
 }
 
 impl HasPrototypeBase for PlanetPrototype {
@@ -24,7 +111,66 @@ impl HasPrototypeBase for PlanetPrototype {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Default)]
+/// 地表属性，如气压、重力、昼夜周期
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SurfacePropertyPrototype {
+    #[serde(flatten)]
+    pub base: PrototypeBase,
+
+    pub default_value: f64,
+}
+
+impl HasPrototypeBase for SurfacePropertyPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base
+    }
+}
+
+/// 星球以外的地表，目前只有太空平台
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SurfacePrototype {
+    #[serde(flatten)]
+    pub base: PrototypeBase,
+
+    #[serde(default)]
+    pub surface_properties: Dict<f64>,
+}
+
+impl HasPrototypeBase for SurfacePrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base
+    }
+}
+
+/// 配方或实体对地表属性的要求，上下限均包含在内
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SurfaceCondition {
+    pub property: String,
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+impl SurfaceCondition {
+    pub fn is_satisfied(&self, value: f64) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+
+    /// 形如“气压 10 ~ 4000”的说明文字
+    pub fn describe(&self, ctx: &FactorioContext) -> String {
+        let name = ctx.get_display_name("surface-property", &self.property);
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min == max => format!("{} = {}", name, min),
+            (Some(min), Some(max)) => format!("{} {} ~ {}", name, min, max),
+            (Some(min), None) => format!("{} ≥ {}", name, min),
+            (None, Some(max)) => format!("{} ≤ {}", name, max),
+            (None, None) => name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
 pub struct PlanetPrototypeMapGenSettings {
     #[serde(default)]
     pub autoplace_controls: Dict<FrequencySizeRichness>,
@@ -32,7 +178,7 @@ pub struct PlanetPrototypeMapGenSettings {
     pub autoplace_settings: TypedAutoplaceSettings,
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Default)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
 pub struct TypedAutoplaceSettings {
     pub entity: AutoplaceSettings,
     pub tile: AutoplaceSettings,
@@ -40,7 +186,7 @@ pub struct TypedAutoplaceSettings {
     // pub decorative: AutoplaceSettings,
 }
 
-#[derive(Clone, serde::Deserialize, Default)]
+#[derive(Clone, serde::Serialize, serde::Deserialize, Default)]
 #[serde(default)]
 pub struct FrequencySizeRichness {
     pub frequency: Option<f64>,
@@ -64,7 +210,7 @@ impl Debug for FrequencySizeRichness {
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize, Default)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
 pub struct AutoplaceSettings {
     #[serde(default)]
     pub treat_missing_as_default: bool,
@@ -72,6 +218,19 @@ pub struct AutoplaceSettings {
 }
 
 impl PlanetPrototype {
+    /// 星球上的地表属性值，星球未指定时取属性的默认值
+    pub fn surface_property(&self, ctx: &FactorioContext, name: &str) -> f64 {
+        self.surface_properties
+            .get(name)
+            .or_else(|| {
+                ctx.surface_properties
+                    .get(name)
+                    .map(|property| &property.default_value)
+            })
+            .cloned()
+            .unwrap_or(0.0)
+    }
+
     pub fn collect_autoplaced(&self, ctx: &FactorioContext) -> HashSet<GenericItem> {
         let mut items = HashSet::new();
         for entity in ctx.entities.values() {
@@ -147,3 +306,61 @@ impl PlanetPrototype {
         items
     }
 }
+
+#[test]
+fn test_surface_conditions() {
+    use crate::factorio::{ModuleConfig, RecipeConfig, mechanic_surface_violations};
+    let ctx = FactorioContext::test_load();
+    assert_eq!(
+        ctx.location_surface_property("space-platform", "pressure"),
+        Some(0.0)
+    );
+    // 诺维斯没有指定气压，取默认值 1000
+    assert_eq!(
+        ctx.location_surface_property("nauvis", "pressure"),
+        Some(1000.0)
+    );
+    assert_eq!(ctx.location_surface_property("nowhere", "pressure"), None);
+    let smelting = RecipeConfig {
+        recipe: "iron-plate".into(),
+        machine: "stone-furnace".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: Some(("coal".to_string(), 0)),
+    };
+    assert!(mechanic_surface_violations(&smelting, &ctx, "nauvis").is_empty());
+    // 石炉需要气压，不能放在太空平台上
+    assert_eq!(
+        mechanic_surface_violations(&smelting, &ctx, "space-platform").len(),
+        1
+    );
+    // 回收机只能在磁场为 99 的富尔戈拉上制作
+    let recycler = RecipeConfig {
+        recipe: "recycler".into(),
+        machine: "assembling-machine-2".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+    };
+    assert_eq!(
+        mechanic_surface_violations(&recycler, &ctx, "nauvis").len(),
+        1
+    );
+    assert!(mechanic_surface_violations(&recycler, &ctx, "fulgora").is_empty());
+}
+
+#[test]
+fn test_asteroid_routes() {
+    let ctx = FactorioContext::test_load();
+    let routes = ctx.routes();
+    assert!(routes.contains(&"nauvis".to_string()));
+    assert!(routes.contains(&"nauvis-vulcanus".to_string()));
+    // 诺维斯轨道上只有碎块，每刻生成概率直接给出
+    let orbit = ctx.route_asteroid_spawn_rates("nauvis");
+    assert_eq!(orbit.len(), 3);
+    assert!((orbit["metallic-asteroid-chunk"] - 0.0125 * 60.0).abs() < 1e-9);
+    // 航线上按生成点插值取平均：0.1 之前取 0.0125，0.9 之后取 0.002，中间线性变化
+    let route = ctx.route_asteroid_spawn_rates("nauvis-vulcanus");
+    let average = 0.0125 * 0.1 + (0.0125 + 0.002) / 2.0 * 0.8 + 0.002 * 0.1;
+    assert!((route["metallic-asteroid-chunk"] - average * 60.0).abs() < 1e-9);
+    assert!(route.contains_key("medium-metallic-asteroid"));
+    assert!(ctx.route_asteroid_spawn_rates("nowhere").is_empty());
+}
@@ -0,0 +1,246 @@
+//! 创建上下文时使用的模组配置。
+//!
+//! 每个配置记录一个模组文件夹和对其 mod-list.json 的启用覆盖。加载前在工作目录下
+//! 生成一个独立的模组文件夹：写入覆盖后的 mod-list.json，并链接原文件夹中的模组，
+//! 这样切换配置不需要改动原文件夹。
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::{
+    dyn_serde::save_to_file,
+    error::AppError,
+    factorio::model::{ContextLoadError, get_workding_directory},
+};
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ModProfile {
+    pub name: String,
+    pub mod_path: PathBuf,
+    /// 模组名到是否启用，未列出的模组沿用 mod-list.json 的设置
+    #[serde(default)]
+    pub overrides: BTreeMap<String, bool>,
+}
+
+impl ModProfile {
+    pub fn new(name: impl Into<String>, mod_path: PathBuf) -> Self {
+        Self {
+            name: name.into(),
+            mod_path,
+            overrides: BTreeMap::new(),
+        }
+    }
+
+    /// 原 mod-list.json 中的模组及应用覆盖后的启用状态
+    pub fn mods(&self) -> Result<Vec<(String, bool)>, ContextLoadError> {
+        let list = self.apply_overrides(self.read_mod_list()?);
+        Ok(list["mods"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                Some((
+                    entry.get("name")?.as_str()?.to_string(),
+                    entry
+                        .get("enabled")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false),
+                ))
+            })
+            .collect())
+    }
+
+    fn read_mod_list(&self) -> Result<Value, ContextLoadError> {
+        let path = self.mod_path.join("mod-list.json");
+        let parse_error = |reason: String| ContextLoadError::ModListParse {
+            path: path.clone(),
+            reason,
+        };
+        let content = std::fs::read_to_string(&path).map_err(|err| parse_error(err.to_string()))?;
+        let list =
+            serde_json::from_str::<Value>(&content).map_err(|err| parse_error(err.to_string()))?;
+        if !list["mods"].is_array() {
+            return Err(parse_error(
+                crate::i18n::tr("load_error.mod_list_no_mods").to_string(),
+            ));
+        }
+        Ok(list)
+    }
+
+    /// 在 mod-list.json 上应用覆盖，保留其余字段；列表中没有的模组追加到末尾
+    pub fn apply_overrides(&self, mut list: Value) -> Value {
+        let Some(mods) = list["mods"].as_array_mut() else {
+            return list;
+        };
+        for (name, enabled) in &self.overrides {
+            match mods
+                .iter_mut()
+                .find(|entry| entry.get("name").and_then(Value::as_str) == Some(name))
+            {
+                Some(entry) => entry["enabled"] = Value::Bool(*enabled),
+                None => mods.push(serde_json::json!({ "name": name, "enabled": enabled })),
+            }
+        }
+        list
+    }
+
+    /// 生成的模组文件夹位置，文件名中不安全的字符替换为下划线
+    pub fn directory(&self) -> PathBuf {
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        get_workding_directory().join("tmp/mod-profiles").join(name)
+    }
+
+    /// 生成供导出使用的模组文件夹，返回其路径
+    pub fn prepare(&self) -> Result<PathBuf, ContextLoadError> {
+        let list = self.apply_overrides(self.read_mod_list()?);
+        let dir = self.directory();
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        std::fs::create_dir_all(&dir)?;
+        for entry in std::fs::read_dir(&self.mod_path)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            // mod-list.json 使用生成的版本，其余文件（包括 mod-settings.dat）照常链接
+            if name == "mod-list.json" {
+                continue;
+            }
+            link_or_copy(&entry.path(), &dir.join(&name))?;
+        }
+        std::fs::write(
+            dir.join("mod-list.json"),
+            serde_json::to_string_pretty(&list)?,
+        )?;
+        Ok(dir)
+    }
+}
+
+/// 优先使用符号链接，不支持时（例如 Windows 未开启开发者模式）退回到硬链接或复制
+fn link_or_copy(source: &Path, target: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(source, target)
+    }
+    #[cfg(windows)]
+    {
+        if source.is_dir() {
+            std::os::windows::fs::symlink_dir(source, target)
+        } else {
+            std::os::windows::fs::symlink_file(source, target)
+                .or_else(|_| std::fs::hard_link(source, target))
+                .or_else(|_| std::fs::copy(source, target).map(|_| ()))
+        }
+    }
+    // 网页版没有本地文件系统，模组配置无法生成
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (source, target);
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ModProfileLibrary {
+    pub profiles: Vec<ModProfile>,
+}
+
+impl ModProfileLibrary {
+    pub fn path() -> PathBuf {
+        get_workding_directory().join("mod-profiles.json")
+    }
+
+    pub fn load() -> Self {
+        let path = Self::path();
+        if !path.exists() {
+            return Self::default();
+        }
+        match std::fs::read_to_string(&path)
+            .map_err(AppError::from)
+            .and_then(|content| serde_json::from_str(&content).map_err(AppError::from))
+        {
+            Ok(library) => library,
+            Err(err) => {
+                log::error!("读取模组配置文件 {} 失败: {:?}", path.display(), err);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), AppError> {
+        save_to_file(self, &Self::path())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ModProfile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut ModProfile> {
+        self.profiles
+            .iter_mut()
+            .find(|profile| profile.name == name)
+    }
+
+    /// 添加配置，同名配置会被替换
+    pub fn upsert(&mut self, profile: ModProfile) {
+        match self.get_mut(&profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.profiles.retain(|profile| profile.name != name);
+    }
+}
+
+#[test]
+fn test_mod_profile_overrides() {
+    let mods = tempfile::tempdir().unwrap();
+    std::fs::write(
+        mods.path().join("mod-list.json"),
+        r#"{"mods":[{"name":"base","enabled":true},{"name":"space-age","enabled":true},{"name":"pyalienlife","enabled":false}]}"#,
+    )
+    .unwrap();
+    let mut profile = ModProfile::new("vanilla", mods.path().to_path_buf());
+    profile.overrides.insert("space-age".to_string(), false);
+    profile.overrides.insert("extra".to_string(), true);
+    assert_eq!(
+        profile.mods().unwrap(),
+        vec![
+            ("base".to_string(), true),
+            ("space-age".to_string(), false),
+            ("pyalienlife".to_string(), false),
+            ("extra".to_string(), true),
+        ]
+    );
+
+    let mut library = ModProfileLibrary::default();
+    library.upsert(profile.clone());
+    profile.overrides.clear();
+    library.upsert(profile);
+    assert_eq!(library.profiles.len(), 1);
+    assert!(library.get("vanilla").unwrap().overrides.is_empty());
+    library.remove("vanilla");
+    assert!(library.profiles.is_empty());
+
+    let broken = tempfile::tempdir().unwrap();
+    std::fs::write(broken.path().join("mod-list.json"), "{}").unwrap();
+    assert!(matches!(
+        ModProfile::new("broken", broken.path().to_path_buf()).mods(),
+        Err(ContextLoadError::ModListParse { .. })
+    ));
+}
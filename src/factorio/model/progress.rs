@@ -0,0 +1,305 @@
+//! 上下文加载的进度汇报与取消。
+//!
+//! 加载在后台线程执行，每进入或完成一个阶段就通过通道发出一条进度消息；
+//! 界面线程可随时请求取消，正在运行的游戏进程会被终止。
+//! 加载失败时返回 [`ContextLoadError`]，由创建界面显示原因与处理建议。
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    io::Read,
+    process::{Command, Stdio},
+    time::Duration,
+};
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+    },
+};
+
+use crate::{
+    error::AppError,
+    i18n::{tr, trf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoadStage {
+    DumpData,
+    DumpLocale,
+    DumpIcons,
+    Parse,
+}
+
+impl LoadStage {
+    pub const ALL: [LoadStage; 4] = [
+        LoadStage::DumpData,
+        LoadStage::DumpLocale,
+        LoadStage::DumpIcons,
+        LoadStage::Parse,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LoadStage::DumpData => tr("stage.dump_data"),
+            LoadStage::DumpLocale => tr("stage.dump_locale"),
+            LoadStage::DumpIcons => tr("stage.dump_icons"),
+            LoadStage::Parse => tr("stage.parse"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadProgress {
+    pub stage: LoadStage,
+    pub status: StageStatus,
+}
+
+/// 游戏进程的运行结果
+#[derive(Debug, Clone, Default)]
+pub struct ProcessOutcome {
+    pub success: bool,
+    pub code: Option<i32>,
+    /// 进程的标准错误输出
+    pub stderr: String,
+}
+
+/// 错误摘录保留的行数
+pub const EXCERPT_LINES: usize = 12;
+
+/// 取输出的最后若干个非空行
+pub fn output_excerpt(output: &str, lines: usize) -> String {
+    let tail = output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>();
+    tail[tail.len().saturating_sub(lines)..].join("\n")
+}
+
+/// 加载上下文失败的原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContextLoadError {
+    /// 选择的游戏可执行文件不存在或无法启动
+    ExecutableNotFound(PathBuf),
+    /// 游戏导出数据时异常退出
+    DumpFailed {
+        stage: LoadStage,
+        code: Option<i32>,
+        /// 标准错误输出或游戏日志的最后几行
+        excerpt: String,
+        log_path: PathBuf,
+    },
+    /// mod-list.json 不存在或格式不正确
+    ModListParse {
+        path: PathBuf,
+        reason: String,
+    },
+    /// 游戏没有导出该语言的翻译
+    LocaleMissing(String),
+    /// 用户取消了加载
+    Cancelled,
+    Other(String),
+}
+
+impl ContextLoadError {
+    pub fn message(&self) -> String {
+        match self {
+            ContextLoadError::ExecutableNotFound(path) => {
+                trf("load_error.exe_not_found", &[&path.display()])
+            }
+            ContextLoadError::DumpFailed { stage, code, .. } => trf(
+                "load_error.dump_failed",
+                &[
+                    &stage.name(),
+                    &code.map_or("?".to_string(), |code| code.to_string()),
+                ],
+            ),
+            ContextLoadError::ModListParse { path, reason } => {
+                trf("load_error.mod_list", &[&path.display(), reason])
+            }
+            ContextLoadError::LocaleMissing(language) => {
+                trf("load_error.locale_missing", &[language])
+            }
+            ContextLoadError::Cancelled => tr("load_error.cancelled").to_string(),
+            ContextLoadError::Other(reason) => reason.clone(),
+        }
+    }
+
+    /// 用户可以采取的处理办法
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            ContextLoadError::ExecutableNotFound(_) => {
+                Some(tr("load_error.exe_not_found_hint").to_string())
+            }
+            ContextLoadError::DumpFailed { log_path, .. } => {
+                Some(trf("load_error.dump_failed_hint", &[&log_path.display()]))
+            }
+            ContextLoadError::ModListParse { .. } => {
+                Some(tr("load_error.mod_list_hint").to_string())
+            }
+            ContextLoadError::LocaleMissing(_) => {
+                Some(tr("load_error.locale_missing_hint").to_string())
+            }
+            ContextLoadError::Cancelled | ContextLoadError::Other(_) => None,
+        }
+    }
+
+    /// 附带的输出摘录
+    pub fn excerpt(&self) -> Option<&str> {
+        match self {
+            ContextLoadError::DumpFailed { excerpt, .. } if !excerpt.is_empty() => Some(excerpt),
+            _ => None,
+        }
+    }
+}
+
+impl From<AppError> for ContextLoadError {
+    fn from(err: AppError) -> Self {
+        match err {
+            AppError::ContextCreation(reason) | AppError::Io(reason) | AppError::Custom(reason) => {
+                ContextLoadError::Other(reason)
+            }
+            err => ContextLoadError::Other(format!("{:?}", err)),
+        }
+    }
+}
+
+impl From<std::io::Error> for ContextLoadError {
+    fn from(err: std::io::Error) -> Self {
+        ContextLoadError::Other(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for ContextLoadError {
+    fn from(err: serde_json::Error) -> Self {
+        ContextLoadError::Other(err.to_string())
+    }
+}
+
+impl From<ContextLoadError> for AppError {
+    fn from(err: ContextLoadError) -> Self {
+        AppError::ContextCreation(err.message())
+    }
+}
+
+/// 后台加载线程持有的进度汇报器，克隆后共享同一个取消标志
+#[derive(Debug, Clone, Default)]
+pub struct LoadMonitor {
+    sender: Option<Sender<LoadProgress>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl LoadMonitor {
+    pub fn new(sender: Sender<LoadProgress>) -> Self {
+        Self {
+            sender: Some(sender),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn report(&self, stage: LoadStage, status: StageStatus) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(LoadProgress { stage, status });
+            crate::repaint::wake();
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// 已请求取消时返回错误，用于在阶段之间提前退出
+    pub fn check(&self) -> Result<(), AppError> {
+        if self.is_cancelled() {
+            Err(AppError::ContextCreation("已取消加载".to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// 执行一个阶段，根据结果汇报完成或失败
+    pub fn stage<T, E: From<AppError>>(
+        &self,
+        stage: LoadStage,
+        f: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.check()?;
+        self.report(stage, StageStatus::Running);
+        let result = f();
+        self.report(
+            stage,
+            if result.is_ok() {
+                StageStatus::Done
+            } else {
+                StageStatus::Failed
+            },
+        );
+        result
+    }
+
+    /// 运行外部进程直到结束，期间请求取消则终止进程
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn run(&self, command: &mut Command) -> Result<ProcessOutcome, AppError> {
+        let mut child = command.stderr(Stdio::piped()).spawn()?;
+        // 在另一个线程读取标准错误，避免输出过多时填满管道阻塞进程
+        let stderr_reader = child.stderr.take().map(|mut stderr| {
+            std::thread::spawn(move || {
+                let mut output = String::new();
+                let _ = stderr.read_to_string(&mut output);
+                output
+            })
+        });
+        loop {
+            if let Some(status) = child.try_wait()? {
+                let stderr = stderr_reader
+                    .and_then(|reader| reader.join().ok())
+                    .unwrap_or_default();
+                return Ok(ProcessOutcome {
+                    success: status.success(),
+                    code: status.code(),
+                    stderr,
+                });
+            }
+            if self.is_cancelled() {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(AppError::ContextCreation("已取消加载".to_string()));
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+#[test]
+fn test_context_load_error() {
+    let output = "line 1\n\nline 2\nline 3\n";
+    assert_eq!(output_excerpt(output, 2), "line 2\nline 3");
+    assert_eq!(output_excerpt(output, 10), "line 1\nline 2\nline 3");
+    assert_eq!(output_excerpt("", 3), "");
+    let err = ContextLoadError::DumpFailed {
+        stage: LoadStage::DumpData,
+        code: Some(1),
+        excerpt: "Error: mod conflict".to_string(),
+        log_path: PathBuf::from("factorio-current.log"),
+    };
+    assert!(err.message().contains('1'));
+    assert!(err.hint().unwrap().contains("factorio-current.log"));
+    assert_eq!(err.excerpt(), Some("Error: mod conflict"));
+    // 其他错误转换为通用错误，原因保持不变
+    let other: ContextLoadError = AppError::ContextCreation("失败".to_string()).into();
+    assert_eq!(other, ContextLoadError::Other("失败".to_string()));
+    assert_eq!(other.hint(), None);
+}
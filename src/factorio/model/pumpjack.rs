@@ -0,0 +1,412 @@
+#[cfg(feature = "gui")]
+use crate::factorio::{
+    ModuleConfigEditor,
+    editor::icon::Icon,
+    modal::{ItemSelectorModal, ItemWithQualitySelectorModal},
+};
+use crate::{
+    concept::*,
+    factorio::{
+        ModuleConfig,
+        common::*,
+        model::{MachineWhitelist, context::*, mining::*, recipe::RecipeResult},
+    },
+};
+
+/// 资源原型没有给出下限时使用的产量下限，与原油一致
+pub const DEFAULT_YIELD_FLOOR: f64 = 0.2;
+
+/// 开采无限流体资源（原油、锂卤水等）的抽油机。
+/// 每口油井的产量与油井的资源量成正比，开采使资源量逐渐减少，但不会低于资源的下限
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:pumpjack")]
+pub struct PumpjackConfig {
+    pub resource: String,
+    pub machine: IdWithQuality,
+    pub module_config: ModuleConfig,
+    pub instance_fuel: Option<IdWithQuality>,
+    /// 油井当前的产量，1.0 即 100%
+    pub field_yield: f64,
+    /// 按已开采该小时数后衰减的产量规划，0 表示按当前产量
+    #[serde(default)]
+    pub elapsed_hours: f64,
+}
+
+impl Default for PumpjackConfig {
+    fn default() -> Self {
+        PumpjackConfig {
+            resource: "entity-unknown".to_string(),
+            machine: ("entity-unknown".to_string(), 0).into(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: None,
+            field_yield: 1.0,
+            elapsed_hours: 0.0,
+        }
+    }
+}
+
+/// 开采结果含有流体的资源
+pub fn is_fluid_resource(resource: &ResourcePrototype) -> bool {
+    resource
+        .base
+        .minable
+        .as_ref()
+        .and_then(|minable| minable.results.as_ref())
+        .is_some_and(|results| {
+            results
+                .iter()
+                .any(|result| matches!(result, RecipeResult::Fluid(_)))
+        })
+}
+
+impl PumpjackConfig {
+    fn as_mining(&self) -> MiningConfig {
+        MiningConfig {
+            resource: self.resource.clone(),
+            machine: self.machine.clone(),
+            module_config: self.module_config.clone(),
+            instance_fuel: self.instance_fuel.clone(),
+            patch_size: None,
+        }
+    }
+
+    /// 资源的产量下限，即资源量下限与标准资源量之比
+    pub fn yield_floor(&self, ctx: &FactorioContext) -> f64 {
+        ctx.resources
+            .get(&self.resource)
+            .and_then(|resource| Some(resource.minimum? / resource.normal?))
+            .filter(|floor| floor.is_finite())
+            .unwrap_or(DEFAULT_YIELD_FLOOR)
+    }
+
+    /// 开采 elapsed_hours 小时后的产量。每次开采使资源量减少 infinite_depletion_amount，
+    /// 开采速度又与资源量成正比，因此产量按指数衰减，直到下限为止
+    pub fn effective_yield(&self, ctx: &FactorioContext) -> f64 {
+        let floor = self.yield_floor(ctx);
+        let initial = self.field_yield.max(floor);
+        let Some(resource) = ctx.resources.get(&self.resource) else {
+            return initial;
+        };
+        let (Some(depletion), Some(normal)) = (resource.infinite_depletion_amount, resource.normal)
+        else {
+            return initial;
+        };
+        if depletion <= 0.0 || normal <= 0.0 || self.elapsed_hours <= 0.0 {
+            return initial;
+        }
+        // 产量为 100% 时每秒开采的次数
+        let settings = MiningSettings {
+            richness: 1.0,
+            ..Default::default()
+        };
+        let cycles = self
+            .as_mining()
+            .resource_drain(&self.as_mining().as_flow_with_settings(ctx, &settings));
+        let rate = depletion * cycles / normal;
+        (initial * (-rate * self.elapsed_hours * 3600.0).exp()).max(floor)
+    }
+
+    /// 按工厂的采矿设置计算物料流，产量取代工厂的资源丰度
+    pub fn as_flow_with_settings(
+        &self,
+        ctx: &FactorioContext,
+        settings: &MiningSettings,
+    ) -> Flow<GenericItem> {
+        let settings = MiningSettings {
+            richness: self.effective_yield(ctx),
+            ..settings.clone()
+        };
+        self.as_mining().as_flow_with_settings(ctx, &settings)
+    }
+}
+
+impl SolveContext for PumpjackConfig {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for PumpjackConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        self.as_flow_with_settings(ctx, &MiningSettings::default())
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        self.as_mining().cost(ctx)
+    }
+}
+
+impl EditorView for PumpjackConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.label("油井");
+                let resource_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.resource),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(format!(
+                        "资源：{}",
+                        ctx.get_display_name("entity", &self.resource)
+                    ));
+                ui.add(
+                    ItemSelectorModal::new(resource_button.id, ctx, "选择流体资源", "entity")
+                        .with_toggle(resource_button.clicked())
+                        .with_current(&mut self.resource)
+                        .with_filter(|s, f| f.resources.get(s).is_some_and(is_fluid_resource))
+                        .notify_change(&mut changed),
+                );
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("机器"));
+                let entity_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0).with_quality(self.machine.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(if ctx.miners.contains_key(&self.machine.0) {
+                        ctx.get_display_name("entity", &self.machine.0)
+                    } else {
+                        "抽油机：未选择".into()
+                    });
+                if let Some(resource_proto) = ctx.resources.get(&self.resource) {
+                    let whitelist = MachineWhitelist::current(ui.ctx());
+                    ui.add(
+                        ItemWithQualitySelectorModal::new(
+                            entity_button.id,
+                            ctx,
+                            "选择抽油机",
+                            "entity",
+                        )
+                        .with_toggle(entity_button.clicked())
+                        .with_current(&mut self.machine)
+                        .with_filter(|s, f| {
+                            whitelist.is_allowed(s)
+                                && f.miners.get(s).is_some_and(|miner| {
+                                    machine_fits_for_resource(miner, resource_proto)
+                                })
+                        })
+                        .notify_change(&mut changed),
+                    );
+                }
+            });
+            ui.separator();
+            if let Some(miner) = ctx.miners.get(&self.machine.0) {
+                ui.add(
+                    ModuleConfigEditor::new(
+                        ctx,
+                        &mut self.module_config,
+                        miner.module_slots as usize,
+                        &miner.allowed_effects,
+                        &miner.allowed_module_categories,
+                    )
+                    .notify_change(&mut changed),
+                );
+                ui.separator();
+            }
+            ui.vertical(|ui| {
+                let floor = self.yield_floor(ctx);
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.field_yield)
+                            .range(floor..=f64::INFINITY)
+                            .speed(0.01)
+                            .custom_formatter(|value, _| format!("{:.0}%", value * 100.0))
+                            .custom_parser(|text| {
+                                text.trim_end_matches('%')
+                                    .parse::<f64>()
+                                    .ok()
+                                    .map(|v| v / 100.0)
+                            })
+                            .prefix("产量 "),
+                    )
+                    .on_hover_text(format!("油井当前的产量，不低于下限 {:.0}%", floor * 100.0))
+                    .changed();
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.elapsed_hours)
+                            .range(0.0..=f64::INFINITY)
+                            .speed(1.0)
+                            .prefix("已开采 ")
+                            .suffix(" 小时"),
+                    )
+                    .on_hover_text("按开采该时长后衰减的产量规划")
+                    .changed();
+                let effective = self.effective_yield(ctx);
+                if (effective - self.field_yield).abs() > 1e-9 {
+                    ui.label(format!("规划产量 {:.1}%", effective * 100.0));
+                }
+            });
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:pumpjack")]
+pub struct PumpjackConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+    /// 工厂可用的机器，推荐时只选用其中的抽油机
+    #[serde(skip, default)]
+    pub machine_whitelist: MachineWhitelist,
+}
+
+impl Default for PumpjackConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PumpjackConfigProvider {
+    pub fn new() -> Self {
+        Self {
+            sender: None,
+            machine_whitelist: MachineWhitelist::default(),
+        }
+    }
+}
+
+impl SolveContext for PumpjackConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for PumpjackConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        if ui.button("添加抽油机").clicked() {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(PumpjackConfig::default()));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for PumpjackConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        let GenericItem::Fluid { name, .. } = item else {
+            return vec![];
+        };
+        if value >= 0.0 {
+            return vec![];
+        }
+        let mut resources = ctx
+            .resources
+            .values()
+            .filter(|resource| {
+                resource
+                    .base
+                    .minable
+                    .as_ref()
+                    .and_then(|minable| minable.results.as_ref())
+                    .is_some_and(|results| {
+                        results.iter().any(|result| {
+                            matches!(result, RecipeResult::Fluid(fluid) if &fluid.name == name)
+                        })
+                    })
+            })
+            .collect::<Vec<_>>();
+        resources.sort_by(|a, b| a.base.base.name.cmp(&b.base.base.name));
+        resources
+            .into_iter()
+            .filter_map(|resource| {
+                let machine = ctx
+                    .miners
+                    .values()
+                    .filter(|miner| {
+                        machine_fits_for_resource(miner, resource)
+                            && self.machine_whitelist.is_allowed(&miner.base.base.name)
+                    })
+                    .map(|miner| &miner.base.base.name)
+                    .min()?;
+                Some(Box::new(PumpjackConfig {
+                    resource: resource.base.base.name.clone(),
+                    machine: machine.as_str().into(),
+                    ..Default::default()
+                })
+                    as Box<
+                        dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>,
+                    >)
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_pumpjack() {
+    let ctx = FactorioContext::test_load();
+    let crude = GenericItem::Fluid {
+        name: "crude-oil".to_string(),
+        temperature: None,
+    };
+    let hints = PumpjackConfigProvider::new().hint_populate(&ctx, &crude, -1.0);
+    assert_eq!(hints.len(), 1);
+    let full = hints[0].as_flow(&ctx)[&crude];
+    assert!(full > 0.0);
+    // 产量与油井产量成正比，且不低于 20% 的下限
+    let mut pumpjack = PumpjackConfig {
+        resource: "crude-oil".to_string(),
+        machine: "pumpjack".into(),
+        field_yield: 0.5,
+        ..Default::default()
+    };
+    assert!((pumpjack.yield_floor(&ctx) - 0.2).abs() < 1e-9);
+    assert!((pumpjack.as_flow(&ctx)[&crude] - full * 0.5).abs() < 1e-9);
+    pumpjack.field_yield = 0.05;
+    assert!((pumpjack.as_flow(&ctx)[&crude] - full * 0.2).abs() < 1e-9);
+    // 开采使产量衰减，但不会低于下限
+    let mut depleting = ctx.clone();
+    depleting
+        .resources
+        .get_mut("crude-oil")
+        .unwrap()
+        .infinite_depletion_amount = Some(10.0);
+    pumpjack.field_yield = 1.0;
+    // 每秒开采一次，每次减少 10，标准资源量 300000，一小时后约为 e^-0.12
+    pumpjack.elapsed_hours = 1.0;
+    let decayed = pumpjack.effective_yield(&depleting);
+    assert!((decayed - (-0.12f64).exp()).abs() < 1e-6);
+    pumpjack.elapsed_hours = 1e6;
+    assert!((pumpjack.effective_yield(&depleting) - 0.2).abs() < 1e-9);
+    // 采矿产能同样作用于抽油机
+    let settings = MiningSettings {
+        productivity_level: 5,
+        richness: 3.0,
+    };
+    pumpjack.elapsed_hours = 0.0;
+    let boosted = pumpjack.as_flow_with_settings(&ctx, &settings)[&crude];
+    assert!((boosted - full * 1.5).abs() < 1e-9);
+}
+
+crate::impl_register_deserializer!(
+    for PumpjackConfig
+    as "factorio:pumpjack"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for PumpjackConfigProvider
+    as "factorio:pumpjack"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
@@ -1,6 +1,6 @@
 use crate::factorio::common::*;
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[allow(dead_code)]
 pub struct QualityPrototype {
     #[serde(flatten)]
@@ -117,6 +117,46 @@ impl HasPrototypeBase for QualityPrototype {
     }
 }
 
+/// 工厂已解锁的品质，用于在选择器中标出尚不能生产的品质
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct QualityAvailability {
+    /// 已解锁的最高品质下标，None 表示全部解锁
+    pub max_quality: Option<u8>,
+    /// 推荐配方时跳过未解锁的品质
+    pub restrict_suggestions: bool,
+}
+
+impl QualityAvailability {
+    pub fn is_available(&self, quality: u8) -> bool {
+        self.max_quality
+            .is_none_or(|max_quality| quality <= max_quality)
+    }
+
+    /// 推荐配方时是否应当跳过该品质
+    pub fn excludes(&self, quality: u8) -> bool {
+        self.restrict_suggestions && !self.is_available(quality)
+    }
+
+    #[cfg(feature = "gui")]
+    fn memory_id() -> egui::Id {
+        egui::Id::new("quality-availability")
+    }
+
+    /// 设置之后绘制的品质选择器所用的品质范围，由工厂在绘制前设置
+    #[cfg(feature = "gui")]
+    pub fn publish(self, ctx: &egui::Context) {
+        ctx.data_mut(|data| data.insert_temp(Self::memory_id(), self));
+    }
+
+    /// 当前工厂的品质范围，没有工厂设置时视为全部解锁
+    #[cfg(feature = "gui")]
+    pub fn current(ctx: &egui::Context) -> Self {
+        ctx.data(|data| data.get_temp(Self::memory_id()))
+            .unwrap_or_default()
+    }
+}
+
 pub fn calc_quality_distribution(
     qualities: &[QualityPrototype],
     quality_bonus: f64,
@@ -154,3 +194,21 @@ fn test_calc_quality_distribution() {
     dbg!(calc_quality_distribution(&ctx.qualities, 100.0, 0, 4));
     dbg!(calc_quality_distribution(&ctx.qualities, 200.0, 0, 4));
 }
+
+#[test]
+fn test_quality_availability() {
+    let all = QualityAvailability::default();
+    assert!(all.is_available(4));
+    assert!(!all.excludes(4));
+    let mut uncommon = QualityAvailability {
+        max_quality: Some(1),
+        restrict_suggestions: false,
+    };
+    assert!(uncommon.is_available(1));
+    assert!(!uncommon.is_available(2));
+    // 不限制推荐时只做标记
+    assert!(!uncommon.excludes(2));
+    uncommon.restrict_suggestions = true;
+    assert!(uncommon.excludes(2));
+    assert!(!uncommon.excludes(0));
+}
@@ -0,0 +1,361 @@
+#[cfg(feature = "gui")]
+use crate::factorio::{editor::icon::Icon, modal::ItemWithQualitySelectorModal};
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        model::{context::*, energy::*, entity::*},
+    },
+};
+
+/// 反应堆，燃烧燃料产生热量，相邻的反应堆互相提供加成
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ReactorPrototype {
+    #[serde(flatten)]
+    pub base: EntityPrototype,
+
+    /// 每刻消耗的能量
+    pub consumption: EnergyAmount,
+    pub energy_source: EnergySource,
+
+    /// 每个工作中的相邻反应堆提供的额外产热比例
+    #[serde(default)]
+    pub neighbour_bonus: f64,
+}
+
+impl HasPrototypeBase for ReactorPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base.base
+    }
+}
+
+impl ReactorPrototype {
+    pub fn fuel_categories(&self) -> &[String] {
+        match &self.energy_source {
+            EnergySource::Burner(source) => &source.fuel_categories,
+            _ => &[],
+        }
+    }
+
+    /// 按名称取第一个可用的燃料
+    pub fn default_fuel(&self, ctx: &FactorioContext) -> Option<String> {
+        default_burner_fuel(ctx, self.fuel_categories())
+    }
+}
+
+/// 反应堆按矩形排布，相邻加成取所有反应堆的平均值
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReactorLayout {
+    pub rows: u32,
+    pub columns: u32,
+}
+
+impl Default for ReactorLayout {
+    fn default() -> Self {
+        ReactorLayout {
+            rows: 1,
+            columns: 1,
+        }
+    }
+}
+
+impl ReactorLayout {
+    /// 平均每个反应堆的相邻反应堆数量，如 2x2 排布时为 2
+    pub fn average_neighbours(&self) -> f64 {
+        let rows = self.rows.max(1) as f64;
+        let columns = self.columns.max(1) as f64;
+        2.0 * (rows * (columns - 1.0) + columns * (rows - 1.0)) / (rows * columns)
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:reactor")]
+pub struct ReactorConfig {
+    pub machine: IdWithQuality,
+    #[serde(default)]
+    pub layout: ReactorLayout,
+    pub instance_fuel: Option<IdWithQuality>,
+}
+
+impl Default for ReactorConfig {
+    fn default() -> Self {
+        ReactorConfig {
+            machine: ("entity-unknown".to_string(), 0).into(),
+            layout: ReactorLayout::default(),
+            instance_fuel: None,
+        }
+    }
+}
+
+impl SolveContext for ReactorConfig {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for ReactorConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        let mut map = Flow::new();
+        let Some(reactor) = ctx.reactors.get(&self.machine.0) else {
+            return map;
+        };
+        let mut fulfillment = 1.0;
+        // consumption 是燃料的消耗速度，燃烧效率只放大产热，如加热塔以 250% 的效率产热
+        let effectivity = match &reactor.energy_source {
+            EnergySource::Burner(source) => source.effectivity,
+            _ => 1.0,
+        };
+        let heat = reactor.consumption.amount * effectivity;
+        // 燃料消耗与相邻加成无关，加成只增加产热
+        let energy_related_flow = energy_source_as_flow(
+            ctx,
+            &reactor.energy_source,
+            &EnergyAmount { amount: heat },
+            &Effect::default(),
+            &self
+                .instance_fuel
+                .as_ref()
+                .map(|id_with_quality| (id_with_quality.0.clone(), id_with_quality.1 as i32)),
+            &mut fulfillment,
+        );
+        for (key, value) in energy_related_flow.into_iter() {
+            index_map_update_entry(&mut map, key, value);
+        }
+        let bonus = 1.0 + reactor.neighbour_bonus * self.layout.average_neighbours();
+        index_map_update_entry(
+            &mut map,
+            GenericItem::Heat,
+            heat * 60.0 * bonus * fulfillment,
+        );
+        map
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        ctx.reactors.get(&self.machine.0).map_or(16.0, |reactor| {
+            reactor
+                .base
+                .collision_box
+                .as_ref()
+                .map_or(1.0, BoundingBox::tile_area)
+        })
+    }
+}
+
+impl EditorView for ReactorConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        let reactor = ctx.reactors.get(&self.machine.0);
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("机器"));
+                let entity_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0).with_quality(self.machine.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(if reactor.is_some() {
+                        ctx.get_display_name("entity", &self.machine.0)
+                    } else {
+                        "反应堆：未选择".into()
+                    });
+                ui.add(
+                    ItemWithQualitySelectorModal::new(
+                        entity_button.id,
+                        ctx,
+                        "选择反应堆",
+                        "entity",
+                    )
+                    .with_toggle(entity_button.clicked())
+                    .with_current(&mut self.machine)
+                    .with_filter(|s, f| f.reactors.contains_key(s))
+                    .notify_change(&mut changed),
+                );
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("燃料"));
+                let mut fuel = self
+                    .instance_fuel
+                    .clone()
+                    .unwrap_or_else(|| "item-unknown".into());
+                let fuel_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "item", &fuel.0).with_quality(fuel.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(if self.instance_fuel.is_some() {
+                        ctx.get_display_name("item", &fuel.0)
+                    } else {
+                        "燃料：未选择".into()
+                    });
+                let categories = reactor
+                    .map(|reactor| reactor.fuel_categories().to_vec())
+                    .unwrap_or_default();
+                let mut fuel_changed = false;
+                ui.add(
+                    ItemWithQualitySelectorModal::new(fuel_button.id, ctx, "选择燃料", "item")
+                        .with_toggle(fuel_button.clicked())
+                        .with_current(&mut fuel)
+                        .with_filter(move |s, f| {
+                            f.items
+                                .get(s)
+                                .and_then(|item| item.burn.as_ref())
+                                .and_then(|burn| burn.fuel_category.as_ref())
+                                .is_some_and(|category| categories.contains(category))
+                        })
+                        .notify_change(&mut fuel_changed),
+                );
+                if fuel_changed {
+                    self.instance_fuel = Some(fuel);
+                    changed = true;
+                }
+            });
+            if reactor.is_some_and(|reactor| reactor.neighbour_bonus > 0.0) {
+                ui.separator();
+                ui.vertical(|ui| {
+                    ui.label("排布");
+                    ui.horizontal(|ui| {
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut self.layout.rows).range(1..=16))
+                            .changed();
+                        ui.label("×");
+                        changed |= ui
+                            .add(egui::DragValue::new(&mut self.layout.columns).range(1..=16))
+                            .changed();
+                    });
+                    ui.label(format!(
+                        "平均相邻 {:.2} 座",
+                        self.layout.average_neighbours()
+                    ));
+                });
+            }
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:reactor")]
+pub struct ReactorConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+}
+
+impl Default for ReactorConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReactorConfigProvider {
+    pub fn new() -> Self {
+        Self { sender: None }
+    }
+}
+
+impl SolveContext for ReactorConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for ReactorConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        if ui.button("添加反应堆").clicked() {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(ReactorConfig::default()));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for ReactorConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        if *item != GenericItem::Heat || value >= 0.0 {
+            return vec![];
+        }
+        let mut reactors = ctx.reactors.values().collect::<Vec<_>>();
+        reactors.sort_by(|a, b| a.base.base.name.cmp(&b.base.base.name));
+        reactors
+            .into_iter()
+            .map(|reactor| {
+                // 有相邻加成的反应堆默认按 2x2 排布
+                let layout = if reactor.neighbour_bonus > 0.0 {
+                    ReactorLayout {
+                        rows: 2,
+                        columns: 2,
+                    }
+                } else {
+                    ReactorLayout::default()
+                };
+                Box::new(ReactorConfig {
+                    machine: reactor.base.base.name.as_str().into(),
+                    layout,
+                    instance_fuel: reactor.default_fuel(ctx).map(|fuel| fuel.as_str().into()),
+                })
+                    as Box<dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>>
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_reactor() {
+    let ctx = FactorioContext::test_load();
+    let reactor = |rows, columns| ReactorConfig {
+        machine: "nuclear-reactor".into(),
+        layout: ReactorLayout { rows, columns },
+        instance_fuel: Some("uranium-fuel-cell".into()),
+    };
+    let single = reactor(1, 1).as_flow(&ctx);
+    println!("{:?}", single);
+    assert!((single[&GenericItem::Heat] - 4e7).abs() < 1e-3);
+    // 8GJ 的燃料棒每 200 秒消耗一根，产生一根乏燃料棒
+    let fuel = GenericItem::Item("uranium-fuel-cell".into());
+    let depleted = GenericItem::Item("depleted-uranium-fuel-cell".into());
+    assert!((single[&fuel] + 0.005).abs() < 1e-9);
+    assert!((single[&depleted] - 0.005).abs() < 1e-9);
+    // 2x2 排布时每座反应堆有两个相邻反应堆，产热变为三倍
+    let square = reactor(2, 2).as_flow(&ctx);
+    assert!((square[&GenericItem::Heat] - 1.2e8).abs() < 1e-3);
+    assert_eq!(square[&fuel], single[&fuel]);
+    assert_eq!(
+        ReactorLayout {
+            rows: 2,
+            columns: 4
+        }
+        .average_neighbours(),
+        2.5
+    );
+    let hints = ReactorConfigProvider::new().hint_populate(&ctx, &GenericItem::Heat, -1.0);
+    assert!(!hints.is_empty());
+}
+
+crate::impl_register_deserializer!(
+    for ReactorConfig
+    as "factorio:reactor"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for ReactorConfigProvider
+    as "factorio:reactor"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
@@ -1,27 +1,38 @@
 use std::fmt::Debug;
 
+#[cfg(feature = "gui")]
+use crate::factorio::{
+    editor::{
+        hover::PrototypeHover,
+        icon::{GenericIcon, Icon},
+    },
+    format::CompactLabel,
+    modal::{ItemWithQualitySelectorModal, show_modal},
+    model::{
+        module::{ModuleConfigEditor, ModuleResearchEditor},
+        rocket::RocketPayloadView,
+    },
+};
 use crate::{
     concept::*,
     factorio::{
         common::*,
-        editor::{
-            hover::PrototypeHover,
-            icon::{GenericIcon, Icon},
-        },
-        modal::ItemWithQualitySelectorModal,
         model::{
             context::{FactorioContext, GenericItem},
             energy::energy_source_as_flow,
             entity::EntityPrototype,
-            module::{ModuleConfig, ModuleConfigEditor},
+            module::{ModuleConfig, ModuleResearch, QUALITY_MODULE_CATEGORY},
+            planet::SurfaceCondition,
             quality::calc_quality_distribution,
+            recycler::recycling_recipe_name,
+            whitelist::MachineWhitelist,
         },
     },
 };
 
 use crate::factorio::common::{as_vec_or_empty, option_as_vec_or_empty};
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 pub struct RecipePrototype {
     #[serde(flatten)]
@@ -73,6 +84,12 @@ pub struct RecipePrototype {
 
     /// 是否允许使用增加品质的插件
     pub allow_quality: bool,
+
+    /// 只能在满足这些地表条件的地点制作
+    pub surface_conditions: Vec<SurfaceCondition>,
+
+    /// 是否为产物自动生成回收配方
+    pub auto_recycle: bool,
 }
 
 impl Default for RecipePrototype {
@@ -102,11 +119,13 @@ impl Default for RecipePrototype {
             allow_productivity: false,
             allow_pollution: true,
             allow_quality: true,
+            surface_conditions: Vec::new(),
+            auto_recycle: true,
         }
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum RecipeIngredient {
     /// 物品原料
@@ -115,13 +134,13 @@ pub enum RecipeIngredient {
     Fluid(FluidIngredient),
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ItemIngredient {
     pub name: String,
     pub amount: f64,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct FluidIngredient {
     pub name: String,
     pub amount: f64,
@@ -131,7 +150,7 @@ pub struct FluidIngredient {
     pub fluidbox_index: Option<f64>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum RecipeResult {
     /// 物品产物
@@ -140,13 +159,25 @@ pub enum RecipeResult {
     Fluid(FluidResult),
 }
 
+impl RecipePrototype {
+    /// 配方类别，未设置时为 crafting
+    pub fn category(&self) -> &str {
+        self.category.as_deref().unwrap_or("crafting")
+    }
+
+    pub fn with_category(mut self, category: &str) -> Self {
+        self.category = Some(category.to_string());
+        self
+    }
+}
+
 impl HasPrototypeBase for RecipePrototype {
     fn base(&self) -> &PrototypeBase {
         &self.base
     }
 }
 
-#[derive(Clone, serde::Deserialize)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 pub struct ItemResult {
     pub name: String,
@@ -246,9 +277,25 @@ impl ItemResult {
             }
         }
     }
+
+    /// 单次制作产量的一阶矩与二阶矩，数量范围视为均匀分布的整数，忽略 extra_count_fraction
+    pub fn output_moments(&self) -> (f64, f64) {
+        let prob = self.probability;
+        let (mean, second) = match self.amount {
+            Some(amount) => (amount.floor(), amount.floor().powi(2)),
+            None => {
+                let min = self.amount_min.unwrap_or(0.0).floor();
+                let max = self.amount_max.unwrap_or(min).floor().max(min);
+                let mean = (min + max) / 2.0;
+                let variance = ((max - min + 1.0).powi(2) - 1.0) / 12.0;
+                (mean, variance + mean * mean)
+            }
+        };
+        (prob * mean, prob * second)
+    }
 }
 
-#[derive(Clone, serde::Deserialize)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 pub struct FluidResult {
     pub name: String,
@@ -315,6 +362,10 @@ impl FluidResult {
                     None => min,
                 };
                 let max = f64::max(max, min);
+                // 上下限相同时积分区间为零，直接按固定产量计算
+                if max == min {
+                    return (min * prob, f64::max((min - ignore) * prob, 0.0));
+                }
                 let productivity = f64::max(
                     // 积分均值
                     (max - ignore + f64::max(min - ignore, 0.0))
@@ -332,7 +383,7 @@ impl FluidResult {
 
 pub const CRAFTING_MACHINE_TYPES: &[&str] = &["assembling-machine", "furnace", "rocket-silo"];
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct CraftingMachinePrototype {
     #[serde(flatten)]
     pub base: EntityPrototype,
@@ -373,6 +424,9 @@ pub struct CraftingMachinePrototype {
     pub input_limit: Option<f64>,
     #[serde(alias = "result_inventory_size", alias = "max_item_product_count")]
     pub output_limit: Option<f64>,
+    /// 火箭发射井每枚火箭需要的火箭组件数量
+    #[serde(default)]
+    pub rocket_parts_required: Option<f64>,
 }
 
 impl HasPrototypeBase for CraftingMachinePrototype {
@@ -381,10 +435,72 @@ impl HasPrototypeBase for CraftingMachinePrototype {
     }
 }
 
+impl RecipePrototype {
+    /// 同时出现在原料与产物中的物品或流体，数量取两者中较小的一方
+    ///
+    /// 这部分原料每次制作后都会返还，只需在机器中预存，不构成持续的消耗。
+    pub fn catalysts(&self) -> Vec<RecipeIngredient> {
+        self.ingredients
+            .iter()
+            .filter_map(|ingredient| {
+                let returned = self
+                    .results
+                    .iter()
+                    .filter_map(|result| match (ingredient, result) {
+                        (RecipeIngredient::Item(ingredient), RecipeResult::Item(result))
+                            if result.name == ingredient.name =>
+                        {
+                            Some(result.normalized_output().0)
+                        }
+                        (RecipeIngredient::Fluid(ingredient), RecipeResult::Fluid(result))
+                            if result.name == ingredient.name =>
+                        {
+                            Some(result.normalized_output().0)
+                        }
+                        _ => None,
+                    })
+                    .sum::<f64>();
+                if returned <= 0.0 {
+                    return None;
+                }
+                let mut catalyst = ingredient.clone();
+                match &mut catalyst {
+                    RecipeIngredient::Item(item) => item.amount = item.amount.min(returned),
+                    RecipeIngredient::Fluid(fluid) => fluid.amount = fluid.amount.min(returned),
+                }
+                Some(catalyst)
+            })
+            .collect()
+    }
+
+    /// 物品或流体是否为该配方的催化剂，不区分品质与温度
+    pub fn is_catalyst(&self, item: &GenericItem) -> bool {
+        self.catalysts()
+            .iter()
+            .any(|catalyst| match (catalyst, item) {
+                (RecipeIngredient::Item(catalyst), GenericItem::Item(IdWithQuality(name, _))) => {
+                    catalyst.name == *name
+                }
+                (RecipeIngredient::Fluid(catalyst), GenericItem::Fluid { name, .. }) => {
+                    catalyst.name == *name
+                }
+                _ => false,
+            })
+    }
+}
+
 pub fn machine_fits_for_recipe(
     crafter: &CraftingMachinePrototype,
     recipe: &RecipePrototype,
 ) -> bool {
+    // 火箭发射井等机器只能执行固定的配方
+    if crafter
+        .fixed_recipe
+        .as_ref()
+        .is_some_and(|fixed| *fixed != recipe.base.name)
+    {
+        return false;
+    }
     if crafter
         .crafting_categories
         .contains(recipe.category.as_ref().unwrap_or(&"crafting".to_string()))
@@ -401,6 +517,46 @@ pub fn machine_fits_for_recipe(
     false
 }
 
+/// 机器固定执行的配方品质，未固定品质时为 None
+pub fn machine_fixed_quality(
+    ctx: &FactorioContext,
+    crafter: &CraftingMachinePrototype,
+) -> Option<u8> {
+    let fixed = crafter.fixed_quality.as_ref()?;
+    ctx.qualities
+        .iter()
+        .position(|quality| quality.base.name == *fixed)
+        .map(|index| index as u8)
+}
+
+/// 机器的固定配方或固定品质与配方不符时给出原因
+pub fn fixed_recipe_violation(
+    ctx: &FactorioContext,
+    crafter: &CraftingMachinePrototype,
+    recipe: &IdWithQuality,
+) -> Option<String> {
+    let machine = ctx.get_display_name("entity", &crafter.base.base.name);
+    if let Some(fixed) = &crafter.fixed_recipe
+        && *fixed != recipe.0
+    {
+        return Some(format!(
+            "{} 只能执行配方 {}",
+            machine,
+            ctx.get_display_name("recipe", fixed)
+        ));
+    }
+    if let Some(quality) = machine_fixed_quality(ctx, crafter)
+        && quality != recipe.1
+    {
+        return Some(format!(
+            "{} 只能执行{}品质的配方",
+            machine,
+            ctx.get_display_name("quality", &ctx.qualities[quality as usize].base.name)
+        ));
+    }
+    None
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type", rename = "factorio:recipe")]
 pub struct RecipeConfig {
@@ -433,9 +589,159 @@ impl Default for RecipeConfig {
 
 impl AsFlow for RecipeConfig {
     fn as_flow(&self, ctx: &FactorioContext) -> Flow<Self::ItemIdentType> {
+        self.as_flow_with_productivity(ctx, 0.0)
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        if let Some(crafter) = ctx.crafters.get(&self.machine.0) {
+            crafter
+                .base
+                .collision_box
+                .as_ref()
+                .map_or(1.0, |bounding_box| match bounding_box {
+                    BoundingBox::Struct {
+                        left_top,
+                        right_bottom,
+                        orientation: _,
+                    } => {
+                        f64::ceil(right_bottom.1 - left_top.1)
+                            * f64::ceil(right_bottom.0 - left_top.0)
+                    }
+                    BoundingBox::Pair(map_position, map_position1) => {
+                        f64::ceil(map_position1.1 - map_position.1)
+                            * f64::ceil(map_position1.0 - map_position.0)
+                    }
+                    BoundingBox::Triplet(map_position, map_position1, _) => {
+                        f64::ceil(map_position1.1 - map_position.1)
+                            * f64::ceil(map_position1.0 - map_position.0)
+                    }
+                })
+        } else {
+            16.0
+        }
+    }
+}
+
+impl RecipeConfig {
+    /// 每台机器需要预存的催化剂，键与 as_flow 中原料的键一致
+    pub fn catalyst_stock(&self, ctx: &FactorioContext) -> Flow<GenericItem> {
+        let mut stock = Flow::new();
+        let Some(recipe) = ctx.recipes.get(&self.recipe.0) else {
+            return stock;
+        };
+        for catalyst in recipe.catalysts() {
+            match catalyst {
+                RecipeIngredient::Item(item) => index_map_update_entry(
+                    &mut stock,
+                    GenericItem::Item(IdWithQuality(item.name, self.recipe.1)),
+                    item.amount,
+                ),
+                RecipeIngredient::Fluid(fluid) => index_map_update_entry(
+                    &mut stock,
+                    GenericItem::Fluid {
+                        name: fluid.name,
+                        temperature: fluid
+                            .temperature
+                            .or(fluid.min_temperature)
+                            .map(|x| x as i32),
+                    },
+                    fluid.amount,
+                ),
+            }
+        }
+        stock
+    }
+
+    /// 每台机器完成一次制作所需的秒数，由非催化剂原料的消耗速率反推，没有原料的配方返回 None
+    pub fn cycle_time(&self, ctx: &FactorioContext) -> Option<f64> {
+        let recipe = ctx.recipes.get(&self.recipe.0)?;
+        let flow = self.as_flow(ctx);
+        let catalysts = self.catalyst_stock(ctx);
+        recipe.ingredients.iter().find_map(|ingredient| {
+            let (key, amount) = match ingredient {
+                RecipeIngredient::Item(item) => (
+                    GenericItem::Item(IdWithQuality(item.name.clone(), self.recipe.1)),
+                    item.amount,
+                ),
+                RecipeIngredient::Fluid(fluid) => (
+                    GenericItem::Fluid {
+                        name: fluid.name.clone(),
+                        temperature: fluid
+                            .temperature
+                            .or(fluid.min_temperature)
+                            .map(|x| x as i32),
+                    },
+                    fluid.amount,
+                ),
+            };
+            if catalysts.contains_key(&key) {
+                return None;
+            }
+            let rate = -flow.get(&key)?;
+            (rate > 0.0).then(|| amount / rate)
+        })
+    }
+
+    /// 每台机器各产物产量的方差增长速率（每秒），来自产物的概率、数量范围与品质分布。
+    /// 品质分布与产能倍数由 as_flow 的平均产量反推，产能带来的额外制作视为同样随机；催化剂产物不计。
+    pub fn output_variance(&self, ctx: &FactorioContext) -> Flow<GenericItem> {
+        let mut variance = Flow::new();
+        let Some(recipe) = ctx.recipes.get(&self.recipe.0) else {
+            return variance;
+        };
+        let flow = self.as_flow(ctx);
+        let catalysts = self.catalyst_stock(ctx);
+        for result in &recipe.results {
+            let RecipeResult::Item(item) = result else {
+                continue;
+            };
+            if catalysts.contains_key(&GenericItem::Item(IdWithQuality(
+                item.name.clone(),
+                self.recipe.1,
+            ))) {
+                continue;
+            }
+            let (mean, second) = item.output_moments();
+            if mean <= 0.0 {
+                continue;
+            }
+            let qualities = flow
+                .iter()
+                .filter_map(|(key, amount)| match key {
+                    GenericItem::Item(IdWithQuality(name, _)) if *name == item.name => {
+                        Some((key, *amount))
+                    }
+                    _ => None,
+                })
+                .filter(|(_, amount)| *amount > 0.0)
+                .collect::<Vec<_>>();
+            let total = qualities.iter().map(|(_, amount)| amount).sum::<f64>();
+            if total <= 0.0 {
+                continue;
+            }
+            // 含产能在内的等效制作速率
+            let effective_crafts = total / mean;
+            for (key, amount) in qualities {
+                let share = amount / total;
+                let single = share * second - (share * mean).powi(2);
+                if single > 1e-12 {
+                    index_map_update_entry(&mut variance, key.clone(), single * effective_crafts);
+                }
+            }
+        }
+        variance
+    }
+
+    /// 在插件与机器自带的产能之外加上研究带来的产能，合计仍受配方的产能上限约束
+    pub fn as_flow_with_productivity(
+        &self,
+        ctx: &FactorioContext,
+        productivity_bonus: f64,
+    ) -> Flow<GenericItem> {
         let mut map = Flow::new();
 
         let mut module_effects = self.module_config.get_effect(ctx).clamped();
+        module_effects.productivity += productivity_bonus;
 
         let mut base_speed = 1.0;
 
@@ -452,12 +758,18 @@ impl AsFlow for RecipeConfig {
             base_speed = crafter.crafting_speed;
             let quality_level = self.machine.1 as usize;
             if let Some(multiplier) = &crafter.crafting_speed_quality_multiplier {
-                let quality = &ctx.qualities[quality_level].base.name;
-                let speed_multiplier = multiplier.get(quality).cloned().unwrap_or(1.0);
+                let speed_multiplier = ctx
+                    .qualities
+                    .get(quality_level)
+                    .and_then(|quality| multiplier.get(&quality.base.name))
+                    .cloned()
+                    .unwrap_or(1.0);
                 base_speed *= speed_multiplier;
             } else {
-                let quality = &ctx.qualities[quality_level];
-                base_speed *= quality.crafting_machine_speed_multiplier();
+                base_speed *= ctx
+                    .qualities
+                    .get(quality_level)
+                    .map_or(1.0, |quality| quality.crafting_machine_speed_multiplier());
             }
             let energy_related_flow = energy_source_as_flow(
                 ctx,
@@ -475,6 +787,7 @@ impl AsFlow for RecipeConfig {
             }
         }
 
+        // 催化剂同时计入原料与产物，两者相互抵消后只剩净流量，所需的库存见 catalyst_stock
         if let Some(recipe) = ctx.recipes.get(&self.recipe.0) {
             base_speed /= recipe.energy_required;
 
@@ -490,9 +803,13 @@ impl AsFlow for RecipeConfig {
                         );
                     }
                     RecipeIngredient::Fluid(fluid) => {
+                        // 温度范围只取下限，更高温度的流体由求解时的温度中转满足
                         let key = GenericItem::Fluid {
                             name: fluid.name.clone(),
-                            temperature: fluid.temperature.map(|x| x as i32),
+                            temperature: fluid
+                                .temperature
+                                .or(fluid.min_temperature)
+                                .map(|x| x as i32),
                         };
                         index_map_update_entry(
                             &mut map,
@@ -559,35 +876,6 @@ impl AsFlow for RecipeConfig {
         }
         map
     }
-
-    fn cost(&self, ctx: &Self::GameContext) -> f64 {
-        if let Some(crafter) = ctx.crafters.get(&self.machine.0) {
-            crafter
-                .base
-                .collision_box
-                .as_ref()
-                .map_or(1.0, |bounding_box| match bounding_box {
-                    BoundingBox::Struct {
-                        left_top,
-                        right_bottom,
-                        orientation: _,
-                    } => {
-                        f64::ceil(right_bottom.1 - left_top.1)
-                            * f64::ceil(right_bottom.0 - left_top.0)
-                    }
-                    BoundingBox::Pair(map_position, map_position1) => {
-                        f64::ceil(map_position1.1 - map_position.1)
-                            * f64::ceil(map_position1.0 - map_position.0)
-                    }
-                    BoundingBox::Triplet(map_position, map_position1, _) => {
-                        f64::ceil(map_position1.1 - map_position.1)
-                            * f64::ceil(map_position1.0 - map_position.0)
-                    }
-                })
-        } else {
-            16.0
-        }
-    }
 }
 
 #[test]
@@ -597,7 +885,7 @@ fn test_recipe_normalized() {
         recipe: ("iron-gear-wheel".to_string(), 0).into(),
         machine: "assembling-machine-1".into(),
         module_config: ModuleConfig::new(),
-        instance_fuel: Some(("nutrients".to_string(), 0).into()),
+        instance_fuel: Some(("nutrients".to_string(), 0)),
     };
     let result = recipe_config.as_flow(&ctx);
     println!("Recipe Result: {:?}", result);
@@ -606,7 +894,233 @@ fn test_recipe_normalized() {
     println!("Recipe Result with Location: {:?}", result_with_location);
 }
 
+#[test]
+fn test_catalyst_productivity() {
+    let ctx = FactorioContext::test_load();
+    let kovarex = RecipeConfig {
+        recipe: "kovarex-enrichment-process".into(),
+        machine: "centrifuge".into(),
+        module_config: ModuleConfig {
+            modules: vec!["productivity-module-3".into(); 2],
+            beacons: vec![],
+        },
+        instance_fuel: None,
+    };
+    let flow = kovarex.as_flow(&ctx);
+    let u235 = flow[&GenericItem::Item("uranium-235".into())];
+    let u238 = flow[&GenericItem::Item("uranium-238".into())];
+    // 产能只作用于每次多出的 1 个铀 235，作为催化剂的 40 个不计入
+    assert!((u235 / u238 + 1.2 / 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_catalysts() {
+    let ctx = FactorioContext::test_load();
+    let u235 = GenericItem::Item("uranium-235".into());
+    let heavy_oil = GenericItem::Fluid {
+        name: "heavy-oil".to_string(),
+        temperature: None,
+    };
+    let kovarex = RecipeConfig {
+        recipe: "kovarex-enrichment-process".into(),
+        machine: "centrifuge".into(),
+        ..Default::default()
+    };
+    // 铀 238 每次消耗 5 个，其中 2 个返还
+    assert_eq!(
+        kovarex.catalyst_stock(&ctx),
+        Flow::from([
+            (u235.clone(), 40.0),
+            (GenericItem::Item("uranium-238".into()), 2.0)
+        ])
+    );
+    let liquefaction = &ctx.recipes["coal-liquefaction"];
+    assert!(liquefaction.is_catalyst(&heavy_oil));
+    assert!(ctx.recipes["iron-gear-wheel"].catalysts().is_empty());
+    // 铀 235 的缺口不推荐需要铀 235 才能运转的浓缩配方
+    let provider = RecipeConfigProvider::new();
+    let recipes = |item: &GenericItem| {
+        provider
+            .hint_populate(&ctx, item, -1.0)
+            .iter()
+            .filter_map(|mechanic| {
+                (mechanic.as_ref() as &dyn std::any::Any)
+                    .downcast_ref::<RecipeConfig>()
+                    .map(|config| config.recipe.0.clone())
+            })
+            .collect::<Vec<_>>()
+    };
+    let u235_recipes = recipes(&u235);
+    assert!(u235_recipes.contains(&"uranium-processing".to_string()));
+    assert!(!u235_recipes.contains(&"kovarex-enrichment-process".to_string()));
+    assert!(!recipes(&heavy_oil).contains(&"coal-liquefaction".to_string()));
+}
+
+#[test]
+fn test_quality_restricted_hints() {
+    let ctx = FactorioContext::test_load();
+    let mut provider = RecipeConfigProvider::new();
+    provider.module_research.quality.max_quality = Some(1);
+    let rare_gear = GenericItem::Item(IdWithQuality("iron-gear-wheel".to_string(), 2));
+    // 只标记时仍然推荐，限制后不再推荐未解锁品质的配方
+    assert!(!provider.hint_populate(&ctx, &rare_gear, -1.0).is_empty());
+    provider.module_research.quality.restrict_suggestions = true;
+    assert!(provider.hint_populate(&ctx, &rare_gear, -1.0).is_empty());
+    let gear = GenericItem::Item("iron-gear-wheel".into());
+    assert!(!provider.hint_populate(&ctx, &gear, -1.0).is_empty());
+}
+
+#[test]
+fn test_quality_path_hints() {
+    let ctx = FactorioContext::test_load();
+    let provider = RecipeConfigProvider::new();
+    let recipes = |item: &GenericItem| {
+        provider
+            .hint_populate(&ctx, item, -1.0)
+            .iter()
+            .filter_map(|hint| {
+                (hint.as_ref() as &dyn std::any::Any)
+                    .downcast_ref::<RecipeConfig>()
+                    .map(|config| (config.recipe.clone(), config.module_config.modules.len()))
+            })
+            .collect::<Vec<_>>()
+    };
+    // 稀有齿轮的缺口还可以用优秀或普通品质的原料配合品质插件制作
+    let rare_gear = recipes(&GenericItem::Item(IdWithQuality(
+        "iron-gear-wheel".to_string(),
+        2,
+    )));
+    let gear = |quality| IdWithQuality("iron-gear-wheel".to_string(), quality);
+    assert!(rare_gear.iter().any(|(recipe, _)| *recipe == gear(2)));
+    for quality in [0, 1] {
+        assert!(
+            rare_gear
+                .iter()
+                .any(|(recipe, modules)| *recipe == gear(quality) && *modules > 0)
+        );
+    }
+    // 回收产物包含自身的物品可以回收升级
+    let uncommon_plate = recipes(&GenericItem::Item(IdWithQuality(
+        "iron-plate".to_string(),
+        1,
+    )));
+    assert!(uncommon_plate.iter().any(|(recipe, modules)| {
+        *recipe == IdWithQuality(recycling_recipe_name("iron-plate"), 0) && *modules > 0
+    }));
+    // 普通品质的物品没有品质路线
+    let plain_gear = recipes(&GenericItem::Item("iron-gear-wheel".into()));
+    assert!(plain_gear.iter().all(|(recipe, _)| recipe.1 == 0));
+}
+
+#[test]
+fn test_hint_preferred_machine() {
+    let ctx = FactorioContext::test_load();
+    let mut provider = RecipeConfigProvider::new();
+    let gear = GenericItem::Item("iron-gear-wheel".into());
+    let machine = |provider: &RecipeConfigProvider| {
+        provider
+            .hint_populate(&ctx, &gear, -1.0)
+            .iter()
+            .filter_map(|hint| (hint.as_ref() as &dyn std::any::Any).downcast_ref::<RecipeConfig>())
+            .find(|config| config.recipe.0 == "iron-gear-wheel")
+            .map(|config| config.machine.0.clone())
+            .unwrap()
+    };
+    // 没有偏好时结果稳定，有偏好时选用工厂中已有的机器
+    let default = machine(&provider);
+    assert_eq!(default, machine(&provider));
+    provider.preferred_machines = vec!["assembling-machine-3".to_string()];
+    assert_eq!(machine(&provider), "assembling-machine-3");
+    // 不支持该配方类别的偏好被跳过
+    provider.preferred_machines = vec!["chemical-plant".to_string()];
+    assert_eq!(machine(&provider), default);
+    // 白名单之外的机器不会被推荐，即使它在工厂中已有
+    provider.preferred_machines = vec!["assembling-machine-3".to_string()];
+    provider.machine_whitelist.allowed = Some(
+        ["assembling-machine-1", "assembling-machine-2"]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+    );
+    assert_eq!(machine(&provider), "assembling-machine-1");
+}
+
+#[test]
+fn test_fixed_recipe() {
+    let mut ctx = FactorioContext::test_load();
+    let silo = &ctx.crafters["rocket-silo"];
+    assert!(machine_fits_for_recipe(silo, &ctx.recipes["rocket-part"]));
+    assert!(fixed_recipe_violation(&ctx, silo, &"rocket-part".into()).is_none());
+    assert!(fixed_recipe_violation(&ctx, silo, &"iron-gear-wheel".into()).is_some());
+    // 限定配方与品质的组装机只用于对应品质的该配方
+    let crafter = ctx.crafters.get_mut("assembling-machine-2").unwrap();
+    crafter.fixed_recipe = Some("iron-gear-wheel".to_string());
+    crafter.fixed_quality = Some("uncommon".to_string());
+    let crafter = &ctx.crafters["assembling-machine-2"];
+    assert!(machine_fits_for_recipe(
+        crafter,
+        &ctx.recipes["iron-gear-wheel"]
+    ));
+    assert!(!machine_fits_for_recipe(
+        crafter,
+        &ctx.recipes["copper-cable"]
+    ));
+    assert_eq!(machine_fixed_quality(&ctx, crafter), Some(1));
+    assert!(fixed_recipe_violation(&ctx, crafter, &"iron-gear-wheel".into()).is_some());
+    assert!(
+        fixed_recipe_violation(&ctx, crafter, &("iron-gear-wheel".to_string(), 1).into()).is_none()
+    );
+    let mut provider = RecipeConfigProvider::new();
+    provider.preferred_machines = vec!["assembling-machine-2".to_string()];
+    let machine = |quality: u8| {
+        provider
+            .hint_populate(
+                &ctx,
+                &GenericItem::Item(("iron-gear-wheel".to_string(), quality).into()),
+                -1.0,
+            )
+            .iter()
+            .filter_map(|hint| (hint.as_ref() as &dyn std::any::Any).downcast_ref::<RecipeConfig>())
+            .find(|config| config.recipe == ("iron-gear-wheel".to_string(), quality).into())
+            .map(|config| config.machine.0.clone())
+            .unwrap()
+    };
+    assert_ne!(machine(0), "assembling-machine-2");
+    assert_eq!(machine(1), "assembling-machine-2");
+}
+
+/// 机器与配方共同限定的插件效果与插件类别
+pub fn recipe_module_limits(
+    crafter: &CraftingMachinePrototype,
+    recipe: &RecipePrototype,
+) -> (Option<EffectTypeLimitation>, Option<Vec<String>>) {
+    let allowed_effects = EffectTypeLimitation::new(
+        recipe.allow_consumption,
+        recipe.allow_speed,
+        recipe.allow_productivity,
+        recipe.allow_pollution,
+        recipe.allow_quality,
+    )
+    .intersect(
+        crafter
+            .allowed_effects
+            .as_ref()
+            .unwrap_or(&EffectTypeLimitation::default()),
+    );
+    let allowed_module_categories = match (
+        crafter.allowed_module_categories.as_ref(),
+        recipe.allowed_module_categories.as_ref(),
+    ) {
+        (None, None) => None,
+        (None, Some(_)) => recipe.allowed_module_categories.clone(),
+        (Some(_), None) => crafter.allowed_module_categories.clone(),
+        (Some(a), Some(b)) => Some([a.to_vec().as_slice(), b.to_vec().as_slice()].concat()),
+    };
+    (Some(allowed_effects), allowed_module_categories)
+}
+
 impl EditorView for RecipeConfig {
+    #[cfg(feature = "gui")]
     fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
         let mut changed = false;
         ui.horizontal_top(|ui| {
@@ -615,15 +1129,16 @@ impl EditorView for RecipeConfig {
 
                 let recipe_button = ui
                     .add_sized(
-                        [35.0, 35.0],
+                        crate::settings::icon_cell(),
                         Icon::new(ctx, "recipe", &self.recipe.0).with_quality(self.recipe.1),
                     )
                     .interact(egui::Sense::click())
                     .on_hover_ui(|ui| {
-                        ui.add(PrototypeHover {
-                            ctx,
-                            prototype: ctx.recipes.get(&self.recipe.0).unwrap(),
-                        });
+                        if let Some(prototype) = ctx.recipes.get(&self.recipe.0) {
+                            ui.add(PrototypeHover { ctx, prototype });
+                        } else {
+                            ui.label(format!("未知配方: {}", self.recipe.0));
+                        }
                     });
                 ui.add(
                     ItemWithQualitySelectorModal::new(recipe_button.id, ctx, "选择配方", "recipe")
@@ -642,7 +1157,9 @@ impl EditorView for RecipeConfig {
             if changed {
                 // TODO 读取用户设定的偏好
                 if let Some(crafter) = ctx.crafters.get(&self.machine.0)
-                    && !machine_fits_for_recipe(crafter, ctx.recipes.get(&self.recipe.0).unwrap())
+                    && let Some(recipe) = ctx.recipes.get(&self.recipe.0)
+                    && (!machine_fits_for_recipe(crafter, recipe)
+                        || fixed_recipe_violation(ctx, crafter, &self.recipe).is_some())
                 {
                     self.machine = "entity-unknown".into();
                     self.instance_fuel = None;
@@ -651,10 +1168,10 @@ impl EditorView for RecipeConfig {
             }
             ui.separator();
             ui.vertical(|ui| {
-                ui.add_sized([35.0, 15.0], egui::Label::new("机器"));
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("机器"));
                 let entity_button = ui
                     .add_sized(
-                        [35.0, 35.0],
+                        crate::settings::icon_cell(),
                         GenericIcon::new(
                             ctx,
                             &GenericItem::Entity(IdWithQuality(
@@ -670,7 +1187,9 @@ impl EditorView for RecipeConfig {
                         "组装机：未选择".to_string()
                     });
 
-                let recipe_prototype = ctx.recipes.get(self.recipe.0.as_str()).unwrap();
+                let recipe_prototype = ctx.recipes.get(self.recipe.0.as_str());
+                let recipe_quality = self.recipe.1;
+                let whitelist = MachineWhitelist::current(ui.ctx());
                 let widget = ItemWithQualitySelectorModal::new(
                     entity_button.id,
                     ctx,
@@ -679,8 +1198,13 @@ impl EditorView for RecipeConfig {
                 )
                 .with_toggle(entity_button.clicked())
                 .with_filter(|crafter_name, ctx| {
-                    if let Some(crafter) = ctx.crafters.get(crafter_name) {
-                        return machine_fits_for_recipe(crafter, recipe_prototype);
+                    if let Some(crafter) = ctx.crafters.get(crafter_name)
+                        && let Some(recipe_prototype) = recipe_prototype
+                    {
+                        return whitelist.is_allowed(crafter_name)
+                            && machine_fits_for_recipe(crafter, recipe_prototype)
+                            && machine_fixed_quality(ctx, crafter)
+                                .is_none_or(|quality| quality == recipe_quality);
                     }
                     false
                 })
@@ -694,42 +1218,51 @@ impl EditorView for RecipeConfig {
             if let Some(crafter) = ctx.crafters.get(&self.machine.0)
                 && let Some(recipe) = ctx.recipes.get(&self.recipe.0)
             {
-                let allowed_effects = EffectTypeLimitation::new(
-                    recipe.allow_consumption,
-                    recipe.allow_speed,
-                    recipe.allow_productivity,
-                    recipe.allow_pollution,
-                    recipe.allow_quality,
-                )
-                .intersect(
-                    crafter
-                        .allowed_effects
-                        .as_ref()
-                        .unwrap_or(&EffectTypeLimitation::default()),
-                );
-                let allowed_module_categories = match (
-                    crafter.allowed_module_categories.as_ref(),
-                    recipe.allowed_module_categories.as_ref(),
-                ) {
-                    (None, None) => &None,
-                    (None, Some(_)) => &recipe.allowed_module_categories,
-                    (Some(_), None) => &crafter.allowed_module_categories,
-                    (Some(a), Some(b)) => {
-                        &Some([a.to_vec().as_slice(), b.to_vec().as_slice()].concat())
-                    }
-                };
+                let (allowed_effects, allowed_module_categories) =
+                    recipe_module_limits(crafter, recipe);
 
                 ui.add(
                     ModuleConfigEditor::new(
                         ctx,
                         &mut self.module_config,
                         crafter.module_slots as usize,
-                        &Some(allowed_effects),
-                        allowed_module_categories,
+                        &allowed_effects,
+                        &allowed_module_categories,
                     )
                     .notify_change(&mut changed),
                 );
             };
+
+            if let Some(crafter) = ctx.crafters.get(&self.machine.0)
+                && crafter.base.base.r#type == "rocket-silo"
+            {
+                ui.separator();
+                ui.add(RocketPayloadView { ctx, silo: crafter });
+            }
+
+            let stock = self.catalyst_stock(ctx);
+            if !stock.is_empty() {
+                ui.separator();
+                ui.vertical(|ui| {
+                    ui.label("催化剂").on_hover_text(
+                        "每台机器需要预先投入的数量，运转时全部返还，不计入净物料流",
+                    );
+                    ui.horizontal(|ui| {
+                        for (item, amount) in &stock {
+                            ui.vertical(|ui| {
+                                ui.add_sized(
+                                    crate::settings::label_cell(),
+                                    CompactLabel::new(*amount),
+                                );
+                                ui.add_sized(
+                                    crate::settings::icon_cell(),
+                                    GenericIcon::new(ctx, item),
+                                );
+                            });
+                        }
+                    });
+                });
+            }
         });
 
         changed
@@ -741,6 +1274,15 @@ impl EditorView for RecipeConfig {
 pub struct RecipeConfigProvider {
     #[serde(skip, default)]
     pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+    /// 推荐配方时按研究进度填充插件
+    #[serde(default)]
+    pub module_research: ModuleResearch,
+    /// 工厂中已在使用的制造机器，按使用次数降序排列，推荐配方时优先选用
+    #[serde(skip, default)]
+    pub preferred_machines: Vec<String>,
+    /// 工厂可用的机器，推荐配方时只选用其中的机器
+    #[serde(skip, default)]
+    pub machine_whitelist: MachineWhitelist,
 }
 
 impl Default for RecipeConfigProvider {
@@ -751,7 +1293,96 @@ impl Default for RecipeConfigProvider {
 
 impl RecipeConfigProvider {
     pub fn new() -> Self {
-        Self { sender: None }
+        Self {
+            sender: None,
+            module_research: ModuleResearch::default(),
+            preferred_machines: Vec::new(),
+            machine_whitelist: MachineWhitelist::default(),
+        }
+    }
+
+    /// 白名单中支持该配方类别、且固定配方与固定品质不冲突的机器，按推荐的优先顺序排列：
+    /// 先是工厂中已有的机器，其余按制造速度从低到高排列，同速按名称排序以保证结果稳定
+    fn hint_machines<'a>(
+        &self,
+        ctx: &'a FactorioContext,
+        category: &str,
+        recipe: &IdWithQuality,
+    ) -> Vec<&'a CraftingMachinePrototype> {
+        let supports = |crafter: &&CraftingMachinePrototype| {
+            crafter
+                .crafting_categories
+                .iter()
+                .any(|supported| supported == category)
+                && fixed_recipe_violation(ctx, crafter, recipe).is_none()
+                && self.machine_whitelist.is_allowed(&crafter.base.base.name)
+        };
+        let mut machines = self
+            .preferred_machines
+            .iter()
+            .filter_map(|name| ctx.crafters.get(name))
+            .filter(supports)
+            .collect::<Vec<_>>();
+        let mut rest = ctx
+            .crafters
+            .values()
+            .filter(supports)
+            .filter(|crafter| !self.preferred_machines.contains(&crafter.base.base.name))
+            .collect::<Vec<_>>();
+        rest.sort_by(|a, b| {
+            a.crafting_speed
+                .total_cmp(&b.crafting_speed)
+                .then_with(|| a.base.base.name.cmp(&b.base.base.name))
+        });
+        machines.extend(rest);
+        machines
+    }
+
+    /// 以指定品质的原料推荐配方。quality_modules 为真时选用第一台能装品质插件的机器，
+    /// 用已解锁的最高级品质插件填满插件槽，都不能装时返回 None；否则按研究进度的偏好填充插件
+    fn hint_config(
+        &self,
+        ctx: &FactorioContext,
+        recipe_proto: &RecipePrototype,
+        quality: u8,
+        quality_modules: bool,
+    ) -> Option<RecipeConfig> {
+        let mut recipe_config = RecipeConfig {
+            recipe: (recipe_proto.base.name.clone(), quality).into(),
+            ..Default::default()
+        };
+        let category = recipe_proto
+            .category
+            .as_ref()
+            .map_or("crafting", |s| s.as_str());
+        let machines = self.hint_machines(ctx, category, &recipe_config.recipe);
+        if quality_modules {
+            let (machine, module) = machines.into_iter().find_map(|machine| {
+                let (allowed_effects, allowed_module_categories) =
+                    recipe_module_limits(machine, recipe_proto);
+                let module = self.module_research.best_module(
+                    ctx,
+                    QUALITY_MODULE_CATEGORY,
+                    &allowed_effects,
+                    &allowed_module_categories,
+                )?;
+                (machine.module_slots >= 1.0).then_some((machine, module))
+            })?;
+            recipe_config.machine = (machine.base.base.name.clone(), 0).into();
+            recipe_config.module_config.modules =
+                vec![IdWithQuality(module.base.name.clone(), 0); machine.module_slots as usize];
+        } else if let Some(machine) = machines.first() {
+            recipe_config.machine = (machine.base.base.name.clone(), 0).into();
+            let (allowed_effects, allowed_module_categories) =
+                recipe_module_limits(machine, recipe_proto);
+            recipe_config.module_config = self.module_research.fill_module_config(
+                ctx,
+                machine.module_slots as usize,
+                &allowed_effects,
+                &allowed_module_categories,
+            );
+        }
+        Some(recipe_config)
     }
 }
 
@@ -772,88 +1403,97 @@ impl MechanicProvider for RecipeConfigProvider {
         value: f64,
     ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
     {
-        let item_name = match item {
-            GenericItem::Item(IdWithQuality(name, _)) => name,
-            GenericItem::Fluid {
-                name,
-                temperature: _,
-            } => name,
-            _ => return vec![], // Not an item or fluid, do nothing.
-        };
         let quality = match item {
             GenericItem::Item(IdWithQuality(_, quality)) => *quality,
             _ => 0,
         };
+        if self.module_research.quality.excludes(quality) {
+            return vec![];
+        }
 
-        let mut suggestions = Vec::new();
-
-        for recipe_proto in ctx.recipes.values() {
-            let matches = if recipe_proto.base.hidden {
-                false
-            } else if value < 0.0 {
-                // We have a deficit, need recipes that PRODUCE this item
-                recipe_proto.results.iter().any(|result| match result {
-                    RecipeResult::Item(r) => &r.name == item_name,
-                    RecipeResult::Fluid(r) => &r.name == item_name,
-                })
-            } else {
-                // We have a surplus, need recipes that CONSUME this item
-                recipe_proto
-                    .ingredients
-                    .iter()
-                    .any(|ingredient| match ingredient {
-                        RecipeIngredient::Item(i) => &i.name == item_name,
-                        RecipeIngredient::Fluid(i) => &i.name == item_name,
-                    })
-            };
-
-            if matches {
-                let mut recipe_config = RecipeConfig {
-                    recipe: (recipe_proto.base.name.clone(), quality).into(),
-                    ..Default::default()
-                };
-                // Try to find a suitable machine
-                let category = recipe_proto
-                    .category
-                    .as_ref()
-                    .map_or("crafting", |s| s.as_str());
-                if let Some(machine) = ctx
-                    .crafters
-                    .values()
-                    .find(|crafter| crafter.crafting_categories.contains(&category.to_string()))
-                {
-                    recipe_config.machine = (machine.base.base.name.clone(), 0).into();
-                }
-                let actual_produce = recipe_config.as_flow(ctx).get(item).cloned().unwrap_or(0.0);
-                if (value < 0.0 && actual_produce <= 0.0) || (value > 0.0 && actual_produce >= 0.0)
-                {
-                    // This recipe does not actually help with the deficit/surplus
+        // 缺口时找产出该物品的配方，盈余时找消耗该物品的配方
+        let candidates = if value < 0.0 {
+            ctx.producers_of(item)
+        } else {
+            ctx.consumers_of(item)
+        }
+        .iter()
+        .filter_map(|name| ctx.recipes.get(name))
+        // 以缺口物品为催化剂的配方需要先有该物品才能运转，不能弥补缺口
+        .filter(|recipe_proto| !recipe_proto.base.hidden)
+        .filter(|recipe_proto| !(value < 0.0 && recipe_proto.is_catalyst(item)))
+        .collect::<Vec<_>>();
+        let mut configs = candidates
+            .iter()
+            .filter_map(|recipe_proto| self.hint_config(ctx, recipe_proto, quality, false))
+            .collect::<Vec<_>>();
+
+        // 高品质物品的缺口还可以由低品质原料配合品质插件制作，
+        // 或者回收低品质的该物品升级得到（只有回收产物包含自身的物品才能直接得到）
+        if value < 0.0
+            && quality > 0
+            && let GenericItem::Item(IdWithQuality(name, _)) = item
+        {
+            let recycling = ctx.recipes.get(&recycling_recipe_name(name));
+            for lower in (0..quality).rev() {
+                if self.module_research.quality.excludes(lower) {
                     continue;
                 }
-                suggestions.push(Box::new(recipe_config)
+                configs.extend(
+                    candidates
+                        .iter()
+                        .copied()
+                        .chain(recycling)
+                        .filter_map(|recipe_proto| {
+                            self.hint_config(ctx, recipe_proto, lower, true)
+                        }),
+                );
+            }
+        }
+
+        configs
+            .into_iter()
+            .filter(|recipe_config| {
+                // 只保留确实能弥补缺口或消耗盈余的配方
+                let actual = recipe_config.as_flow(ctx).get(item).cloned().unwrap_or(0.0);
+                (value < 0.0 && actual > 0.0) || (value > 0.0 && actual < 0.0)
+            })
+            .map(|recipe_config| {
+                Box::new(recipe_config)
                     as Box<
                         dyn Mechanic<
                                 ItemIdentType = Self::ItemIdentType,
                                 GameContext = Self::GameContext,
                             >,
-                    >);
-            }
-        }
-
-        suggestions
+                    >
+            })
+            .collect()
     }
 }
 
 impl EditorView for RecipeConfigProvider {
-    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
-        if ui.button("添加配方").clicked() {
-            let new_config = RecipeConfig::default();
-            if let Some(sender) = &self.sender {
-                let _ = sender.send(Box::new(new_config));
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            if ui.button("添加配方").clicked() {
+                let new_config = RecipeConfig::default();
+                if let Some(sender) = &self.sender {
+                    let _ = sender.send(Box::new(new_config));
+                }
+                changed = true;
             }
-            return true;
-        }
-        false
+            let button = ui
+                .button("研究进度")
+                .on_hover_text("按已研究的插件等级与品质推荐配方并填充插件");
+            show_modal(button.id, button.clicked(), ui, |ui| {
+                ui.add(
+                    ModuleResearchEditor::new(ctx, &mut self.module_research)
+                        .notify_change(&mut changed),
+                );
+            });
+        });
+        changed
     }
 }
 
@@ -868,3 +1508,40 @@ crate::impl_register_deserializer!(
     as "factorio:recipe"
     => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
 );
+
+#[test]
+fn test_output_variance() {
+    let ctx = FactorioContext::test_load();
+    let processing = RecipeConfig {
+        recipe: "uranium-processing".into(),
+        machine: "centrifuge".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+    };
+    let u235 = GenericItem::Item("uranium-235".into());
+    let flow = processing.as_flow(&ctx);
+    let variance = processing.output_variance(&ctx);
+    // 每次制作以 0.007 的概率产出 1 个，方差为 p(1-p)
+    let crafts = flow[&u235] / 0.007;
+    assert!((variance[&u235] - crafts * 0.007 * 0.993).abs() < 1e-12);
+
+    let ranged = ItemResult {
+        amount_min: Some(1.0),
+        amount_max: Some(3.0),
+        probability: 0.5,
+        ..Default::default()
+    };
+    // 均匀取 1~3：均值 2，二阶矩 14/3
+    let (mean, second) = ranged.output_moments();
+    assert!((mean - 1.0).abs() < 1e-12);
+    assert!((second - 7.0 / 3.0).abs() < 1e-12);
+
+    // 固定产量的配方没有波动
+    let gear = RecipeConfig {
+        recipe: "iron-gear-wheel".into(),
+        machine: "assembling-machine-2".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+    };
+    assert!(gear.output_variance(&ctx).is_empty());
+}
@@ -0,0 +1,316 @@
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        model::{context::*, item::*, module::ModuleConfig, recipe::*},
+    },
+};
+
+/// 回收配方的制造类别
+pub const RECYCLING_CATEGORY: &str = "recycling";
+
+/// 回收只返还原料的四分之一
+pub const RECYCLING_RETURN: f64 = 0.25;
+
+/// 回收的耗时为原配方的十六分之一
+const RECYCLING_TIME_DIVISOR: f64 = 16.0;
+
+/// 没有来源配方时，自我回收按 0.5 秒的制作时间计算
+const DEFAULT_SELF_RECYCLING_TIME: f64 = 0.5;
+
+/// 熔炼等配方的产物不能拆回原料，只能自我回收
+const IRREVERSIBLE_CATEGORIES: &[&str] = &["smelting"];
+
+pub fn recycling_recipe_name(item: &str) -> String {
+    format!("{}-recycling", item)
+}
+
+pub fn is_recycler(crafter: &CraftingMachinePrototype) -> bool {
+    crafter
+        .crafting_categories
+        .iter()
+        .any(|category| category == RECYCLING_CATEGORY)
+}
+
+/// 可以拆回原料的配方及其产量，流体原料在回收时直接丢弃
+fn reversible_output<'a>(
+    recipe: &'a RecipePrototype,
+    items: &Dict<ItemPrototype>,
+) -> Option<(&'a str, f64)> {
+    if !recipe.auto_recycle
+        || recipe.base.parameter
+        || recipe.category() == RECYCLING_CATEGORY
+        || IRREVERSIBLE_CATEGORIES.contains(&recipe.category())
+    {
+        return None;
+    }
+    let [RecipeResult::Item(result)] = recipe.results.as_slice() else {
+        return None;
+    };
+    // 科技包只能自我回收
+    let item = items.get(&result.name)?;
+    if item.base.r#type == "tool" {
+        return None;
+    }
+    let amount = result.amount.filter(|amount| *amount > 0.0)?;
+    Some((result.name.as_str(), amount))
+}
+
+/// 数量可以是小数，整数部分必定产出，小数部分按概率额外产出一个
+fn recycling_result(name: &str, amount: f64) -> RecipeResult {
+    RecipeResult::Item(ItemResult {
+        name: name.to_string(),
+        amount: Some(amount),
+        extra_count_fraction: amount.fract(),
+        ..Default::default()
+    })
+}
+
+fn recycling_recipe(
+    item: &str,
+    results: Vec<RecipeResult>,
+    energy_required: f64,
+) -> RecipePrototype {
+    let mut recipe = RecipePrototype::default().with_category(RECYCLING_CATEGORY);
+    recipe.base = PrototypeBase {
+        r#type: "recipe".to_string(),
+        name: recycling_recipe_name(item),
+        hidden: true,
+        ..Default::default()
+    };
+    recipe.ingredients = vec![RecipeIngredient::Item(ItemIngredient {
+        name: item.to_string(),
+        amount: 1.0,
+    })];
+    recipe.results = results;
+    recipe.energy_required = energy_required / RECYCLING_TIME_DIVISOR;
+    recipe.auto_recycle = false;
+    recipe
+}
+
+/// 为还没有回收配方的物品生成回收配方
+///
+/// 能拆解的物品按来源配方返还四分之一的原料，其余物品自我回收，有四分之一的概率保留自身。
+/// 原始数据中已有的回收配方保持不变；没有任何回收设备时不生成。
+pub fn generate_recycling_recipes(
+    recipes: &mut Dict<RecipePrototype>,
+    items: &Dict<ItemPrototype>,
+    crafters: &Dict<CraftingMachinePrototype>,
+) {
+    if !crafters.values().any(is_recycler) {
+        return;
+    }
+    // 每个物品的来源配方优先取同名配方，否则取名称排序最前的
+    let mut recipe_names = recipes.keys().collect::<Vec<_>>();
+    recipe_names.sort();
+    let mut sources: Dict<(&RecipePrototype, f64)> = Dict::new();
+    for name in recipe_names {
+        let recipe = &recipes[name];
+        let Some((item, amount)) = reversible_output(recipe, items) else {
+            continue;
+        };
+        if recipe.base.name == item {
+            sources.insert(item.to_string(), (recipe, amount));
+        } else {
+            sources.entry(item.to_string()).or_insert((recipe, amount));
+        }
+    }
+    let mut generated = vec![];
+    for item in items.values() {
+        let name = &item.base.name;
+        if item.base.parameter || recipes.contains_key(&recycling_recipe_name(name)) {
+            continue;
+        }
+        let reversed = sources.get(name).map(|(recipe, amount)| {
+            let results = recipe
+                .ingredients
+                .iter()
+                .filter_map(|ingredient| match ingredient {
+                    RecipeIngredient::Item(ingredient) => Some(recycling_result(
+                        &ingredient.name,
+                        ingredient.amount / amount * RECYCLING_RETURN,
+                    )),
+                    RecipeIngredient::Fluid(_) => None,
+                })
+                .collect::<Vec<_>>();
+            (results, recipe.energy_required)
+        });
+        let recipe = match reversed {
+            Some((results, energy_required)) if !results.is_empty() => {
+                recycling_recipe(name, results, energy_required)
+            }
+            _ => {
+                let energy_required = recipes
+                    .get(name)
+                    .map_or(DEFAULT_SELF_RECYCLING_TIME, |recipe| recipe.energy_required);
+                let result = RecipeResult::Item(ItemResult {
+                    name: name.clone(),
+                    amount: Some(1.0),
+                    probability: RECYCLING_RETURN,
+                    ignored_by_stats: Some(1.0),
+                    ..Default::default()
+                });
+                recycling_recipe(name, vec![result], energy_required)
+            }
+        };
+        generated.push(recipe);
+    }
+    for recipe in generated {
+        recipes.insert(recipe.base.name.clone(), recipe);
+    }
+}
+
+/// 把盈余的物品送进回收机，回收配方由 RecipeConfig 计算
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:recycler")]
+pub struct RecyclerConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+}
+
+impl Default for RecyclerConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RecyclerConfigProvider {
+    pub fn new() -> Self {
+        Self { sender: None }
+    }
+}
+
+fn recyclers(ctx: &FactorioContext) -> Vec<&CraftingMachinePrototype> {
+    let mut recyclers = ctx
+        .crafters
+        .values()
+        .filter(|crafter| is_recycler(crafter))
+        .collect::<Vec<_>>();
+    recyclers.sort_by(|a, b| a.base.base.name.cmp(&b.base.base.name));
+    recyclers
+}
+
+impl SolveContext for RecyclerConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for RecyclerConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        if ui.button("添加回收").clicked() {
+            let recipe_config = RecipeConfig {
+                machine: recyclers(ctx).first().map_or_else(
+                    || "entity-unknown".into(),
+                    |recycler| recycler.base.base.name.as_str().into(),
+                ),
+                ..Default::default()
+            };
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(recipe_config));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for RecyclerConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        // 只为盈余的物品推荐回收
+        let GenericItem::Item(IdWithQuality(name, quality)) = item else {
+            return vec![];
+        };
+        if value <= 0.0 {
+            return vec![];
+        }
+        let recipe = recycling_recipe_name(name);
+        if !ctx.recipes.contains_key(&recipe) {
+            return vec![];
+        }
+        recyclers(ctx)
+            .into_iter()
+            .map(|recycler| {
+                Box::new(RecipeConfig {
+                    recipe: (recipe.clone(), *quality).into(),
+                    machine: recycler.base.base.name.as_str().into(),
+                    module_config: ModuleConfig::new(),
+                    instance_fuel: None,
+                })
+                    as Box<dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>>
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_recycling_recipes() {
+    let ctx = FactorioContext::test_load();
+    let mut recipes = ctx.recipes.clone();
+    recipes.retain(|_, recipe| recipe.category() != RECYCLING_CATEGORY);
+    generate_recycling_recipes(&mut recipes, &ctx.items, &ctx.crafters);
+    // 生成的回收配方与原始数据中的一致
+    let outputs = |recipes: &Dict<RecipePrototype>, name: &str| {
+        let recipe = &recipes[&recycling_recipe_name(name)];
+        let mut outputs = recipe
+            .results
+            .iter()
+            .filter_map(|result| match result {
+                RecipeResult::Item(result) => {
+                    Some((result.name.clone(), result.normalized_output().0))
+                }
+                RecipeResult::Fluid(_) => None,
+            })
+            .collect::<Vec<_>>();
+        outputs.sort_by(|a, b| a.0.cmp(&b.0));
+        (outputs, recipe.energy_required)
+    };
+    for item in [
+        "iron-gear-wheel",
+        "electronic-circuit",
+        "speed-module",
+        // 流体原料在回收时丢弃，其余原料照常返还
+        "processing-unit",
+        "battery",
+        "iron-plate",
+        "automation-science-pack",
+    ] {
+        assert_eq!(
+            outputs(&recipes, item),
+            outputs(&ctx.recipes, item),
+            "{}",
+            item
+        );
+    }
+    // 盈余的齿轮可以回收，品质保持不变
+    let gear = GenericItem::Item(IdWithQuality("iron-gear-wheel".to_string(), 2));
+    let hints = RecyclerConfigProvider::new().hint_populate(&ctx, &gear, 1.0);
+    assert_eq!(hints.len(), 1);
+    let flow = hints[0].as_flow(&ctx);
+    assert!(flow[&gear] < 0.0);
+    assert!(flow[&GenericItem::Item(IdWithQuality("iron-plate".to_string(), 2))] > 0.0);
+    assert!(
+        RecyclerConfigProvider::new()
+            .hint_populate(&ctx, &gear, -1.0)
+            .is_empty()
+    );
+}
+
+crate::impl_register_deserializer!(
+    for RecyclerConfigProvider
+    as "factorio:recycler"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
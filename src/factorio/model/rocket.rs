@@ -0,0 +1,268 @@
+use std::collections::HashSet;
+
+use crate::factorio::{
+    common::*,
+    model::{context::*, item::*, recipe::*, recycler::RECYCLING_CATEGORY},
+};
+#[cfg(feature = "gui")]
+use crate::factorio::{
+    editor::icon::Icon, format::compact_number, modal::ItemWithQualitySelectorModal,
+};
+
+/// 未定义 ingredient_to_weight_coefficient 时，原料重量折算为产物重量的系数
+const DEFAULT_INGREDIENT_TO_WEIGHT_COEFFICIENT: f64 = 0.5;
+
+/// 工具常量中与火箭载重相关的部分，重量的单位为克
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct RocketConstants {
+    /// 无法按配方推算重量的物品使用的重量
+    pub default_item_weight: f64,
+    /// 每枚火箭的载重
+    pub rocket_lift_weight: f64,
+}
+
+impl Default for RocketConstants {
+    fn default() -> Self {
+        RocketConstants {
+            default_item_weight: 100.0,
+            rocket_lift_weight: 1_000_000.0,
+        }
+    }
+}
+
+/// 推算重量所用的来源配方及其产量：优先取同名配方，否则取名称排序最前的
+fn weight_sources(recipes: &Dict<RecipePrototype>) -> Dict<(&RecipePrototype, f64)> {
+    let mut recipe_names = recipes.keys().collect::<Vec<_>>();
+    recipe_names.sort();
+    let mut sources: Dict<(&RecipePrototype, f64)> = Dict::new();
+    for name in recipe_names {
+        let recipe = &recipes[name];
+        if recipe.base.parameter || recipe.category() == RECYCLING_CATEGORY {
+            continue;
+        }
+        for result in &recipe.results {
+            let RecipeResult::Item(result) = result else {
+                continue;
+            };
+            let amount = result.normalized_output().0;
+            if amount <= 0.0 {
+                continue;
+            }
+            if recipe.base.name == result.name {
+                sources.insert(result.name.clone(), (recipe, amount));
+            } else {
+                sources
+                    .entry(result.name.clone())
+                    .or_insert((recipe, amount));
+            }
+        }
+    }
+    sources
+}
+
+/// 按火箭载重取整：一枚火箭装得下整组时按整组数向下取整，否则按单个数量向下取整，
+/// 使每枚火箭恰好装满整数个物品
+fn rocket_rounded_weight(weight: f64, stack_size: u32, rocket_lift_weight: f64) -> f64 {
+    if weight <= 0.0 || rocket_lift_weight <= 0.0 {
+        return weight;
+    }
+    let stack_size = stack_size.max(1) as f64;
+    let per_rocket = (rocket_lift_weight / weight).floor();
+    if per_rocket >= stack_size {
+        rocket_lift_weight / ((per_rocket / stack_size).floor() * stack_size)
+    } else if per_rocket >= 1.0 {
+        rocket_lift_weight / per_rocket
+    } else {
+        weight
+    }
+}
+
+struct WeightDeriver<'a> {
+    items: &'a Dict<ItemPrototype>,
+    sources: Dict<(&'a RecipePrototype, f64)>,
+    constants: &'a RocketConstants,
+    weights: Dict<f64>,
+    visiting: HashSet<String>,
+}
+
+impl WeightDeriver<'_> {
+    fn weight(&mut self, name: &str) -> f64 {
+        if let Some(weight) = self.weights.get(name) {
+            return *weight;
+        }
+        let default = self.constants.default_item_weight;
+        let Some(item) = self.items.get(name) else {
+            return default;
+        };
+        if let Some(weight) = item.weight {
+            self.weights.insert(name.to_string(), weight);
+            return weight;
+        }
+        let Some((recipe, amount)) = self.sources.get(name).copied() else {
+            self.weights.insert(name.to_string(), default);
+            return default;
+        };
+        // 配方互相依赖时，回到环上的物品按默认重量计算
+        if !self.visiting.insert(name.to_string()) {
+            return default;
+        }
+        let recipe_weight = recipe
+            .ingredients
+            .iter()
+            .filter_map(|ingredient| match ingredient {
+                RecipeIngredient::Item(ingredient) => Some(ingredient),
+                RecipeIngredient::Fluid(_) => None,
+            })
+            .map(|ingredient| self.weight(&ingredient.name) * ingredient.amount)
+            .sum::<f64>();
+        self.visiting.remove(name);
+        let weight = if recipe_weight <= 0.0 {
+            default
+        } else {
+            let coefficient = item
+                .ingredient_to_weight_coefficient
+                .unwrap_or(DEFAULT_INGREDIENT_TO_WEIGHT_COEFFICIENT);
+            rocket_rounded_weight(
+                recipe_weight * coefficient / amount,
+                item.stack_size,
+                self.constants.rocket_lift_weight,
+            )
+        };
+        self.weights.insert(name.to_string(), weight);
+        weight
+    }
+}
+
+/// 按游戏规则确定所有物品的重量
+///
+/// 原型定义了 weight 时直接使用；没有配方产出时取默认重量；否则把来源配方中物品原料的重量
+/// 之和（流体不计）乘以 ingredient_to_weight_coefficient 再除以产量，最后按火箭载重取整。
+pub fn derive_item_weights(
+    items: &Dict<ItemPrototype>,
+    recipes: &Dict<RecipePrototype>,
+    constants: &RocketConstants,
+) -> Dict<f64> {
+    let mut deriver = WeightDeriver {
+        items,
+        sources: weight_sources(recipes),
+        constants,
+        weights: Dict::new(),
+        visiting: HashSet::new(),
+    };
+    for name in items.keys() {
+        deriver.weight(name);
+    }
+    deriver.weights
+}
+
+impl FactorioContext {
+    /// 物品的重量（克），未知物品取默认重量
+    pub fn item_weight(&self, item: &str) -> f64 {
+        self.item_weights
+            .get(item)
+            .copied()
+            .unwrap_or(self.rocket_constants.default_item_weight)
+    }
+
+    /// 一枚火箭能装载的物品数量，一个也装不下时为 0
+    pub fn items_per_rocket(&self, item: &str) -> f64 {
+        let weight = self.item_weight(item);
+        if weight <= 0.0 {
+            return f64::INFINITY;
+        }
+        // 重量由载重除以整数得到，加上一点余量避免浮点误差少算一个
+        (self.rocket_constants.rocket_lift_weight / weight + 1e-9).floor()
+    }
+
+    /// 发射一整组物品需要的火箭数量，可以是小数
+    pub fn rockets_per_stack(&self, item: &str) -> f64 {
+        let stack_size = self
+            .items
+            .get(item)
+            .map_or(1, |prototype| prototype.stack_size) as f64;
+        if self.rocket_constants.rocket_lift_weight <= 0.0 {
+            return f64::INFINITY;
+        }
+        stack_size * self.item_weight(item) / self.rocket_constants.rocket_lift_weight
+    }
+}
+
+/// 火箭发射井中选择载荷并显示每枚火箭的装载数量，载荷只保存在界面状态中
+#[cfg(feature = "gui")]
+pub struct RocketPayloadView<'a> {
+    pub ctx: &'a FactorioContext,
+    pub silo: &'a CraftingMachinePrototype,
+}
+
+#[cfg(feature = "gui")]
+impl egui::Widget for RocketPayloadView<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let ctx = self.ctx;
+        let lift = ctx.rocket_constants.rocket_lift_weight;
+        let memory_id = ui.id().with("rocket-payload");
+        let mut payload: IdWithQuality = ui
+            .data(|data| data.get_temp(memory_id))
+            .unwrap_or_else(|| "item-unknown".into());
+        ui.vertical(|ui| {
+            ui.label("火箭载荷").on_hover_text(format!(
+                "每枚火箭需要 {} 个火箭组件，载重 {}kg",
+                compact_number(self.silo.rocket_parts_required.unwrap_or(1.0)),
+                compact_number(lift / 1000.0)
+            ));
+            let payload_button = ui
+                .add_sized(
+                    crate::settings::icon_cell(),
+                    Icon::new(ctx, "item", &payload.0),
+                )
+                .interact(egui::Sense::click())
+                .on_hover_text("选择要发射的物品");
+            let mut changed = false;
+            ui.add(
+                ItemWithQualitySelectorModal::new(payload_button.id, ctx, "选择载荷", "item")
+                    .with_toggle(payload_button.clicked())
+                    .with_current(&mut payload)
+                    .notify_change(&mut changed),
+            );
+            if changed {
+                ui.data_mut(|data| data.insert_temp(memory_id, payload.clone()));
+            }
+            if ctx.items.contains_key(&payload.0) {
+                let weight = ctx.item_weight(&payload.0);
+                ui.label(format!("单个重量 {}kg", compact_number(weight / 1000.0)));
+                ui.label(format!(
+                    "每枚火箭 {} 个",
+                    compact_number(ctx.items_per_rocket(&payload.0))
+                ));
+                ui.label(format!(
+                    "每组需 {} 枚火箭",
+                    compact_number(ctx.rockets_per_stack(&payload.0))
+                ));
+            }
+        });
+        ui.response().clone()
+    }
+}
+
+#[test]
+fn test_item_weights() {
+    let ctx = FactorioContext::test_load();
+    assert_eq!(ctx.rocket_constants.rocket_lift_weight, 1_000_000.0);
+    assert_eq!(ctx.rocket_constants.default_item_weight, 100.0);
+    // 定义了重量
+    assert_eq!(ctx.item_weight("iron-ore"), 2000.0);
+    assert_eq!(ctx.item_weight("rocket-part"), 20000.0);
+    // 1 个铁矿熔炼出 1 个铁板：2000 × 0.5，每枚火箭 1000 个
+    assert_eq!(ctx.item_weight("iron-plate"), 1000.0);
+    assert_eq!(ctx.items_per_rocket("iron-plate"), 1000.0);
+    assert_eq!(ctx.rockets_per_stack("iron-plate"), 0.1);
+    // 铜线定义了 250，电路板 (1000 + 3 × 250) × 0.28 = 490，每枚火箭 2040 个，按整组取整为 2000 个
+    assert_eq!(ctx.item_weight("copper-cable"), 250.0);
+    assert_eq!(ctx.item_weight("electronic-circuit"), 500.0);
+    assert_eq!(ctx.items_per_rocket("electronic-circuit"), 2000.0);
+    // 每枚火箭 1142 个，按整组取整为 1000 个
+    assert_eq!(rocket_rounded_weight(875.0, 200, 1e6), 1000.0);
+    // 装不下整组时按单个取整
+    assert_eq!(rocket_rounded_weight(300_000.0, 10, 1e6), 1e6 / 3.0);
+    assert_eq!(rocket_rounded_weight(2e6, 1, 1e6), 2e6);
+}
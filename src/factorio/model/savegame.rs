@@ -0,0 +1,227 @@
+//! 从游戏存档导入研究进度。
+//!
+//! 存档的 level.dat 是未公开的二进制格式，这里改为读取配套模组或在游戏控制台中执行
+//! [`EXPORT_COMMAND`] 后写出的 JSON：其中记录了各科技的研究状态与采矿产能加成。
+//! 导入时据此设置工厂的加成科技、采矿产能等级、插件等级与已解锁的品质。
+
+use indexmap::IndexMap;
+use serde_json::Value;
+
+use crate::{
+    error::AppError,
+    factorio::model::{FactorioContext, GlobalBonuses, ModuleResearch, TechnologyEffect},
+};
+
+/// 导出文件的默认文件名，位于游戏用户数据目录的 script-output 下
+pub const EXPORT_FILE_NAME: &str = "metatorio-research.json";
+
+/// 在游戏控制台执行的导出命令，会禁用本存档的成就
+pub const EXPORT_COMMAND: &str = r#"/c local f = game.player.force local t = {} for n, x in pairs(f.technologies) do if x.researched or x.level > x.prototype.level then t[n] = {researched = x.researched, level = x.level, base_level = x.prototype.level} end end helpers.write_file("metatorio-research.json", helpers.table_to_json({technologies = t, mining_drill_productivity_bonus = f.mining_drill_productivity_bonus}))"#;
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TechnologyState {
+    pub researched: bool,
+    /// 当前等级，无限研究完成一级后加一
+    pub level: u32,
+    /// 科技原型的起始等级
+    #[serde(default = "default_base_level")]
+    pub base_level: u32,
+}
+
+fn default_base_level() -> u32 {
+    1
+}
+
+impl TechnologyState {
+    /// 已完成的研究次数
+    pub fn researched_count(&self) -> u32 {
+        self.level.saturating_sub(self.base_level) + self.researched as u32
+    }
+}
+
+/// 导出文件的内容
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ResearchSnapshot {
+    /// 已研究或研究过若干级的科技
+    #[serde(deserialize_with = "map_or_empty")]
+    pub technologies: IndexMap<String, TechnologyState>,
+    pub mining_drill_productivity_bonus: f64,
+}
+
+/// Lua 的空表会被导出为 `[]`
+fn map_or_empty<'de, D>(deserializer: D) -> Result<IndexMap<String, TechnologyState>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match <Value as serde::Deserialize>::deserialize(deserializer)? {
+        Value::Array(array) if array.is_empty() => Ok(IndexMap::new()),
+        value => serde_json::from_value(value).map_err(serde::de::Error::custom),
+    }
+}
+
+/// 一次导入的结果，用于提示用户
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResearchImportSummary {
+    /// 设置了研究次数的加成科技数
+    pub bonus_technologies: usize,
+    pub mining_productivity_level: u32,
+    /// 已解锁的最高品质下标
+    pub max_quality: u8,
+    /// 存档中有、当前上下文中没有的科技
+    pub unknown_technologies: usize,
+}
+
+impl ResearchSnapshot {
+    pub fn parse(content: &str) -> Result<Self, AppError> {
+        Ok(serde_json::from_str(content)?)
+    }
+
+    pub fn load(path: &std::path::Path) -> Result<Self, AppError> {
+        if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+        {
+            return Err(AppError::Io(format!(
+                "无法直接读取存档 {}，请先在游戏中导出研究进度，再选择导出的 {}",
+                path.display(),
+                EXPORT_FILE_NAME
+            )));
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| AppError::Io(format!("读取文件 {} 失败：{}", path.display(), e)))?;
+        Self::parse(&content)
+            .map_err(|e| AppError::Io(format!("解析研究进度 {} 失败：{:?}", path.display(), e)))
+    }
+
+    pub fn researched_count(&self, name: &str) -> u32 {
+        self.technologies
+            .get(name)
+            .map(TechnologyState::researched_count)
+            .unwrap_or(0)
+    }
+
+    /// 把研究进度写入工厂的加成与插件研究设置，覆盖原有的设置
+    pub fn apply(
+        &self,
+        ctx: &FactorioContext,
+        bonuses: &mut GlobalBonuses,
+        module_research: &mut ModuleResearch,
+    ) -> ResearchImportSummary {
+        let mut summary = ResearchImportSummary {
+            unknown_technologies: self
+                .technologies
+                .keys()
+                .filter(|name| !ctx.technologies.contains_key(*name))
+                .count(),
+            ..Default::default()
+        };
+
+        bonuses.research.levels.clear();
+        let mut unlocked_recipes = std::collections::HashSet::new();
+        let mut unlocked_qualities = std::collections::HashSet::new();
+        for (name, technology) in &ctx.technologies {
+            let count = self.researched_count(name);
+            if count == 0 {
+                continue;
+            }
+            if technology
+                .effects
+                .iter()
+                .any(TechnologyEffect::is_rate_bonus)
+            {
+                bonuses.research.levels.insert(name.clone(), count);
+                summary.bonus_technologies += 1;
+            }
+            for effect in &technology.effects {
+                match effect {
+                    TechnologyEffect::UnlockRecipe { recipe } => {
+                        unlocked_recipes.insert(recipe.as_str());
+                    }
+                    TechnologyEffect::UnlockQuality { quality } => {
+                        unlocked_qualities.insert(quality.as_str());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        summary.mining_productivity_level = (self.mining_drill_productivity_bonus / 0.1)
+            .round()
+            .max(0.0) as u32;
+        bonuses.mining.productivity_level = summary.mining_productivity_level;
+
+        // 插件的配方已解锁即视为该等级已解锁，没有任何等级解锁的类别记为 0
+        module_research.unlocked_tiers.clear();
+        for module in ctx.modules.values() {
+            let tier = module_research
+                .unlocked_tiers
+                .entry(module.category.clone())
+                .or_insert(0);
+            if unlocked_recipes.contains(module.base.name.as_str()) {
+                *tier = (*tier).max(module.tier as u32);
+            }
+        }
+
+        // 品质链从普通品质开始，遇到第一个未解锁的品质为止
+        summary.max_quality = ctx
+            .qualities
+            .iter()
+            .enumerate()
+            .skip(1)
+            .take_while(|(_, quality)| unlocked_qualities.contains(quality.base.name.as_str()))
+            .last()
+            .map(|(idx, _)| idx as u8)
+            .unwrap_or(0);
+        module_research.quality.max_quality = Some(summary.max_quality);
+
+        summary
+    }
+}
+
+#[test]
+fn test_research_import() {
+    let ctx = FactorioContext::test_load();
+    let snapshot = ResearchSnapshot::parse(
+        r#"{
+            "technologies": {
+                "steel-plate-productivity": {"researched": false, "level": 4, "base_level": 1},
+                "research-speed-1": {"researched": true, "level": 1, "base_level": 1},
+                "speed-module": {"researched": true, "level": 1, "base_level": 1},
+                "speed-module-2": {"researched": true, "level": 1, "base_level": 1},
+                "quality-module": {"researched": true, "level": 1},
+                "legendary-quality": {"researched": true, "level": 1},
+                "mining-productivity-3": {"researched": false, "level": 5, "base_level": 3},
+                "some-modded-technology": {"researched": true, "level": 1}
+            },
+            "mining_drill_productivity_bonus": 0.4
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(snapshot.researched_count("steel-plate-productivity"), 3);
+    assert_eq!(snapshot.researched_count("mining-productivity-3"), 2);
+    assert_eq!(snapshot.researched_count("automation"), 0);
+
+    let mut bonuses = GlobalBonuses::default();
+    let mut module_research = ModuleResearch::default();
+    let summary = snapshot.apply(&ctx, &mut bonuses, &mut module_research);
+    assert_eq!(bonuses.research.levels["steel-plate-productivity"], 3);
+    assert_eq!(bonuses.research.levels["research-speed-1"], 1);
+    assert_eq!(bonuses.mining.productivity_level, 4);
+    assert_eq!(module_research.unlocked_tiers["speed"], 2);
+    assert_eq!(module_research.unlocked_tiers["productivity"], 0);
+    assert_eq!(module_research.unlocked_tiers["quality"], 1);
+    // 史诗品质未研究，传说品质虽已研究也不算解锁
+    let rare = ctx
+        .qualities
+        .iter()
+        .position(|quality| quality.base.name == "rare")
+        .unwrap();
+    assert_eq!(summary.max_quality as usize, rare);
+    assert_eq!(summary.unknown_technologies, 1);
+
+    let empty =
+        ResearchSnapshot::parse(r#"{"technologies": [], "mining_drill_productivity_bonus": 0}"#)
+            .unwrap();
+    assert!(empty.technologies.is_empty());
+}
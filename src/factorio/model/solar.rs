@@ -0,0 +1,393 @@
+#[cfg(feature = "gui")]
+use crate::factorio::{editor::icon::Icon, modal::ItemWithQualitySelectorModal};
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        model::{context::*, entity::*},
+    },
+};
+
+/// 一个昼夜周期中满光照的比例
+const DAY_FRACTION: f64 = 0.5;
+/// 黄昏与黎明各自的比例，光照在其间线性变化
+const TWILIGHT_FRACTION: f64 = 0.2;
+/// 完全无光照的比例
+const NIGHT_FRACTION: f64 = 0.1;
+
+/// 太阳能板在一个周期内的平均出力与峰值之比
+pub const SOLAR_AVERAGE_RATIO: f64 = DAY_FRACTION + TWILIGHT_FRACTION;
+
+/// 以平均功率持续供电时，每个周期内蓄电池需要补足的能量与峰值功率乘周期长度之比
+///
+/// 夜间完全由蓄电池供电，黄昏与黎明中光照低于平均值的部分也需要补足。
+pub fn solar_buffer_ratio() -> f64 {
+    SOLAR_AVERAGE_RATIO * NIGHT_FRACTION
+        + SOLAR_AVERAGE_RATIO * SOLAR_AVERAGE_RATIO * TWILIGHT_FRACTION
+}
+
+/// 太阳能板，白天按光照发电
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SolarPanelPrototype {
+    #[serde(flatten)]
+    pub base: EntityPrototype,
+
+    /// 每刻的峰值发电量
+    pub production: EnergyAmount,
+}
+
+impl HasPrototypeBase for SolarPanelPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base.base
+    }
+}
+
+/// 蓄电池，储存白天多余的电力
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AccumulatorPrototype {
+    #[serde(flatten)]
+    pub base: EntityPrototype,
+
+    pub energy_source: ElectricEnergySource,
+}
+
+impl HasPrototypeBase for AccumulatorPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base.base
+    }
+}
+
+impl AccumulatorPrototype {
+    /// 按品质计算的储能容量
+    pub fn capacity(&self, ctx: &FactorioContext, quality: u8) -> f64 {
+        let capacity = self
+            .energy_source
+            .buffer_capacity
+            .as_ref()
+            .map_or(0.0, |capacity| capacity.amount);
+        capacity
+            * ctx
+                .qualities
+                .get(quality as usize)
+                .map_or(1.0, |quality| quality.accumulator_capacity_multiplier())
+    }
+}
+
+/// 一块太阳能板及其在夜间持续供电所需的蓄电池，按所在星球的光照与昼夜周期计算平均出力
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:solar-power")]
+pub struct SolarPowerConfig {
+    pub machine: IdWithQuality,
+    pub accumulator: Option<IdWithQuality>,
+    pub planet: String,
+}
+
+impl Default for SolarPowerConfig {
+    fn default() -> Self {
+        SolarPowerConfig {
+            machine: ("entity-unknown".to_string(), 0).into(),
+            accumulator: None,
+            planet: "nauvis".to_string(),
+        }
+    }
+}
+
+impl SolarPowerConfig {
+    /// 光照强度的百分比与昼夜周期的秒数
+    fn surface(&self, ctx: &FactorioContext) -> (f64, f64) {
+        match ctx.planets.get(&self.planet) {
+            Some(planet) => (
+                planet.surface_property(ctx, "solar-power"),
+                planet.surface_property(ctx, "day-night-cycle") / 60.0,
+            ),
+            None => (100.0, 0.0),
+        }
+    }
+
+    /// 单块太阳能板在所在星球的峰值功率
+    pub fn peak_power(&self, ctx: &FactorioContext) -> f64 {
+        let Some(panel) = ctx.solar_panels.get(&self.machine.0) else {
+            return 0.0;
+        };
+        let quality = ctx
+            .qualities
+            .get(self.machine.1 as usize)
+            .map_or(1.0, |quality| quality.default_multiplier());
+        panel.production.amount * 60.0 * quality * self.surface(ctx).0 / 100.0
+    }
+
+    /// 单块太阳能板在一个周期内的平均功率
+    pub fn average_power(&self, ctx: &FactorioContext) -> f64 {
+        self.peak_power(ctx) * SOLAR_AVERAGE_RATIO
+    }
+
+    /// 按平均功率持续供电时，每块太阳能板需要的蓄电池数量
+    pub fn accumulators_per_panel(&self, ctx: &FactorioContext) -> f64 {
+        let Some(accumulator) = &self.accumulator else {
+            return 0.0;
+        };
+        let capacity = ctx
+            .accumulators
+            .get(&accumulator.0)
+            .map_or(0.0, |prototype| prototype.capacity(ctx, accumulator.1));
+        if capacity <= 0.0 {
+            return 0.0;
+        }
+        self.peak_power(ctx) * self.surface(ctx).1 * solar_buffer_ratio() / capacity
+    }
+}
+
+impl SolveContext for SolarPowerConfig {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for SolarPowerConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        let mut map = Flow::new();
+        let power = self.average_power(ctx);
+        if power > 0.0 {
+            index_map_update_entry(&mut map, GenericItem::Electricity, power);
+        }
+        map
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        let area = |entity: Option<&EntityPrototype>| {
+            entity.map_or(16.0, |entity| {
+                entity
+                    .collision_box
+                    .as_ref()
+                    .map_or(1.0, BoundingBox::tile_area)
+            })
+        };
+        let panel = area(
+            ctx.solar_panels
+                .get(&self.machine.0)
+                .map(|panel| &panel.base),
+        );
+        let accumulator = self.accumulator.as_ref().map_or(0.0, |accumulator| {
+            area(
+                ctx.accumulators
+                    .get(&accumulator.0)
+                    .map(|accumulator| &accumulator.base),
+            )
+        });
+        panel + accumulator * self.accumulators_per_panel(ctx)
+    }
+}
+
+impl EditorView for SolarPowerConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("机器"));
+                let entity_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0).with_quality(self.machine.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(if ctx.solar_panels.contains_key(&self.machine.0) {
+                        ctx.get_display_name("entity", &self.machine.0)
+                    } else {
+                        "太阳能板：未选择".into()
+                    });
+                ui.add(
+                    ItemWithQualitySelectorModal::new(
+                        entity_button.id,
+                        ctx,
+                        "选择太阳能板",
+                        "entity",
+                    )
+                    .with_toggle(entity_button.clicked())
+                    .with_current(&mut self.machine)
+                    .with_filter(|s, f| f.solar_panels.contains_key(s))
+                    .notify_change(&mut changed),
+                );
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("蓄电池"));
+                let mut accumulator = self
+                    .accumulator
+                    .clone()
+                    .unwrap_or_else(|| "entity-unknown".into());
+                let accumulator_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &accumulator.0).with_quality(accumulator.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(if self.accumulator.is_some() {
+                        ctx.get_display_name("entity", &accumulator.0)
+                    } else {
+                        "蓄电池：未选择".into()
+                    });
+                let mut accumulator_changed = false;
+                ui.add(
+                    ItemWithQualitySelectorModal::new(
+                        accumulator_button.id,
+                        ctx,
+                        "选择蓄电池",
+                        "entity",
+                    )
+                    .with_toggle(accumulator_button.clicked())
+                    .with_current(&mut accumulator)
+                    .with_filter(|s, f| f.accumulators.contains_key(s))
+                    .notify_change(&mut accumulator_changed),
+                );
+                if accumulator_changed {
+                    self.accumulator = Some(accumulator);
+                    changed = true;
+                }
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.label("星球");
+                let mut planets = ctx.planets.keys().collect::<Vec<_>>();
+                planets.sort();
+                egui::ComboBox::new(ui.id().with("solar-planet"), "")
+                    .selected_text(ctx.get_display_name("space-location", &self.planet))
+                    .show_ui(ui, |ui| {
+                        for planet in planets {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut self.planet,
+                                    planet.clone(),
+                                    ctx.get_display_name("space-location", planet),
+                                )
+                                .changed();
+                        }
+                    });
+            });
+            let average = self.average_power(ctx);
+            if average > 0.0 {
+                ui.separator();
+                ui.vertical(|ui| {
+                    ui.label(format!("平均 {:.1} kW", average / 1e3));
+                    let panels = 1e6 / average;
+                    ui.label(format!(
+                        "每 MW：{:.1} 块太阳能板，{:.1} 个蓄电池",
+                        panels,
+                        panels * self.accumulators_per_panel(ctx)
+                    ));
+                });
+            }
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:solar-power")]
+pub struct SolarPowerConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+}
+
+impl Default for SolarPowerConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SolarPowerConfigProvider {
+    pub fn new() -> Self {
+        Self { sender: None }
+    }
+}
+
+impl SolveContext for SolarPowerConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for SolarPowerConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        if ui.button("添加太阳能").clicked() {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(SolarPowerConfig::default()));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for SolarPowerConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        if *item != GenericItem::Electricity || value >= 0.0 {
+            return vec![];
+        }
+        let accumulator = ctx
+            .accumulators
+            .keys()
+            .min()
+            .map(|name| name.as_str().into());
+        let mut panels = ctx.solar_panels.keys().collect::<Vec<_>>();
+        panels.sort();
+        panels
+            .into_iter()
+            .map(|panel| {
+                Box::new(SolarPowerConfig {
+                    machine: panel.as_str().into(),
+                    accumulator: accumulator.clone(),
+                    ..Default::default()
+                })
+                    as Box<dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>>
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_solar_power() {
+    let ctx = FactorioContext::test_load();
+    let nauvis = SolarPowerConfig {
+        machine: "solar-panel".into(),
+        accumulator: Some("accumulator".into()),
+        planet: "nauvis".to_string(),
+    };
+    // 60kW 的太阳能板平均出力 42kW
+    let flow = nauvis.as_flow(&ctx);
+    assert!((flow[&GenericItem::Electricity] - 4.2e4).abs() < 1e-6);
+    // 420 秒的昼夜周期下每块太阳能板需要约 0.85 个 5MJ 的蓄电池
+    let ratio = 6e4 * 420.0 * 0.168 / 5e6;
+    assert!((nauvis.accumulators_per_panel(&ctx) - ratio).abs() < 1e-9);
+    // 伏尔甘努斯光照为 400%，周期更短
+    let vulcanus = SolarPowerConfig {
+        planet: "vulcanus".to_string(),
+        ..nauvis.clone()
+    };
+    assert!((vulcanus.average_power(&ctx) - 1.68e5).abs() < 1e-6);
+    assert!(vulcanus.accumulators_per_panel(&ctx) < nauvis.accumulators_per_panel(&ctx));
+}
+
+crate::impl_register_deserializer!(
+    for SolarPowerConfig
+    as "factorio:solar-power"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for SolarPowerConfigProvider
+    as "factorio:solar-power"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
@@ -0,0 +1,338 @@
+#[cfg(feature = "gui")]
+use crate::factorio::{editor::icon::Icon, modal::ItemWithQualitySelectorModal};
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        model::{context::*, energy::*, module::ModuleConfig, recipe::*},
+    },
+};
+
+/// 俘获的虫巢所用的制造类别，这类设备只执行固定配方，以食物为燃料
+pub const SPAWNER_CRAFTING_CATEGORY: &str = "captive-spawner-process";
+
+pub fn is_spawner(crafter: &CraftingMachinePrototype) -> bool {
+    crafter.fixed_recipe.is_some()
+        && crafter
+            .crafting_categories
+            .iter()
+            .any(|category| category == SPAWNER_CRAFTING_CATEGORY)
+}
+
+fn spawner_fuel_categories(crafter: &CraftingMachinePrototype) -> &[String] {
+    match &crafter.energy_source {
+        EnergySource::Burner(source) => &source.fuel_categories,
+        _ => &[],
+    }
+}
+
+/// 俘获的虫巢：消耗生物通量等食物持续产卵，未及时使用的虫卵变质后孵化出虫子
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:spawner")]
+pub struct SpawnerConfig {
+    pub machine: IdWithQuality,
+    pub instance_fuel: Option<IdWithQuality>,
+    /// 产出的虫卵中放置到变质的比例
+    #[serde(default)]
+    pub spoil_fraction: f64,
+}
+
+impl Default for SpawnerConfig {
+    fn default() -> Self {
+        SpawnerConfig {
+            machine: ("entity-unknown".to_string(), 0).into(),
+            instance_fuel: None,
+            spoil_fraction: 0.0,
+        }
+    }
+}
+
+impl SolveContext for SpawnerConfig {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for SpawnerConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        let Some(crafter) = ctx
+            .crafters
+            .get(&self.machine.0)
+            .filter(|crafter| is_spawner(crafter))
+        else {
+            return Flow::new();
+        };
+        let recipe = crafter.fixed_recipe.clone().unwrap_or_default();
+        // 产卵本身就是固定配方的制作，能耗与燃料按制造设备计算
+        let mut map = RecipeConfig {
+            recipe: recipe.into(),
+            machine: self.machine.clone(),
+            module_config: ModuleConfig::new(),
+            instance_fuel: self
+                .instance_fuel
+                .as_ref()
+                .map(|fuel| (fuel.0.clone(), fuel.1 as i32)),
+        }
+        .as_flow(ctx);
+        if self.spoil_fraction <= 0.0 {
+            return map;
+        }
+        let products = map
+            .iter()
+            .filter(|(_, amount)| **amount > 0.0)
+            .filter_map(|(item, amount)| match item {
+                GenericItem::Item(id) => Some((id.clone(), *amount)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for (product, amount) in products {
+            let Some(spoil) = ctx
+                .items
+                .get(&product.0)
+                .and_then(|item| item.spoil.as_ref())
+            else {
+                continue;
+            };
+            let spoiled = amount * self.spoil_fraction.min(1.0);
+            index_map_update_entry(&mut map, GenericItem::Item(product.clone()), -spoiled);
+            if let Some(result) = &spoil.spoil_result {
+                index_map_update_entry(
+                    &mut map,
+                    GenericItem::Item(IdWithQuality(result.clone(), product.1)),
+                    spoiled,
+                );
+            }
+            if let Some(trigger) = &spoil.spoil_to_trigger_result {
+                for entity in &trigger.created_entities {
+                    index_map_update_entry(
+                        &mut map,
+                        GenericItem::Entity(entity.as_str().into()),
+                        spoiled / trigger.items_per_trigger.max(1.0),
+                    );
+                }
+            }
+        }
+        map
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        ctx.crafters.get(&self.machine.0).map_or(16.0, |crafter| {
+            crafter
+                .base
+                .collision_box
+                .as_ref()
+                .map_or(1.0, BoundingBox::tile_area)
+        })
+    }
+}
+
+impl EditorView for SpawnerConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        let crafter = ctx
+            .crafters
+            .get(&self.machine.0)
+            .filter(|crafter| is_spawner(crafter));
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("机器"));
+                let entity_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0).with_quality(self.machine.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(if crafter.is_some() {
+                        ctx.get_display_name("entity", &self.machine.0)
+                    } else {
+                        "虫巢：未选择".into()
+                    });
+                ui.add(
+                    ItemWithQualitySelectorModal::new(entity_button.id, ctx, "选择虫巢", "entity")
+                        .with_toggle(entity_button.clicked())
+                        .with_current(&mut self.machine)
+                        .with_filter(|s, f| f.crafters.get(s).is_some_and(is_spawner))
+                        .notify_change(&mut changed),
+                );
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("食物"));
+                let mut fuel = self
+                    .instance_fuel
+                    .clone()
+                    .unwrap_or_else(|| "item-unknown".into());
+                let fuel_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "item", &fuel.0).with_quality(fuel.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(if self.instance_fuel.is_some() {
+                        ctx.get_display_name("item", &fuel.0)
+                    } else {
+                        "食物：未选择".into()
+                    });
+                let categories = crafter
+                    .map(|crafter| spawner_fuel_categories(crafter).to_vec())
+                    .unwrap_or_default();
+                let mut fuel_changed = false;
+                ui.add(
+                    ItemWithQualitySelectorModal::new(fuel_button.id, ctx, "选择食物", "item")
+                        .with_toggle(fuel_button.clicked())
+                        .with_current(&mut fuel)
+                        .with_filter(move |s, f| {
+                            f.items
+                                .get(s)
+                                .and_then(|item| item.burn.as_ref())
+                                .and_then(|burn| burn.fuel_category.as_ref())
+                                .is_some_and(|category| categories.contains(category))
+                        })
+                        .notify_change(&mut fuel_changed),
+                );
+                if fuel_changed {
+                    self.instance_fuel = Some(fuel);
+                    changed = true;
+                }
+            });
+            ui.separator();
+            ui.vertical(|ui| {
+                ui.label("变质比例");
+                changed |= ui
+                    .add(
+                        egui::Slider::new(&mut self.spoil_fraction, 0.0..=1.0)
+                            .custom_formatter(|value, _| format!("{:.0}%", value * 100.0)),
+                    )
+                    .on_hover_text("未被使用、放置到变质的虫卵比例，变质的虫卵会孵化出敌对的虫子")
+                    .changed();
+            });
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:spawner")]
+pub struct SpawnerConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+}
+
+impl Default for SpawnerConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpawnerConfigProvider {
+    pub fn new() -> Self {
+        Self { sender: None }
+    }
+}
+
+impl SolveContext for SpawnerConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for SpawnerConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        if ui.button("添加虫巢").clicked() {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(SpawnerConfig::default()));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for SpawnerConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        let GenericItem::Item(IdWithQuality(name, 0)) = item else {
+            return vec![];
+        };
+        if value >= 0.0 {
+            return vec![];
+        }
+        let mut spawners = ctx
+            .crafters
+            .values()
+            .filter(|crafter| is_spawner(crafter))
+            .filter(|crafter| {
+                crafter
+                    .fixed_recipe
+                    .as_ref()
+                    .and_then(|recipe| ctx.recipes.get(recipe))
+                    .is_some_and(|recipe| {
+                        recipe.results.iter().any(|result| {
+                            matches!(result, RecipeResult::Item(result) if result.name == *name)
+                        })
+                    })
+            })
+            .collect::<Vec<_>>();
+        spawners.sort_by(|a, b| a.base.base.name.cmp(&b.base.base.name));
+        spawners
+            .into_iter()
+            .map(|crafter| {
+                Box::new(SpawnerConfig {
+                    machine: crafter.base.base.name.as_str().into(),
+                    instance_fuel: default_burner_fuel(ctx, spawner_fuel_categories(crafter))
+                        .map(|fuel| fuel.as_str().into()),
+                    spoil_fraction: 0.0,
+                })
+                    as Box<dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>>
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_spawner() {
+    let ctx = FactorioContext::test_load();
+    let mut spawner = SpawnerConfig {
+        machine: "captive-biter-spawner".into(),
+        instance_fuel: Some("bioflux".into()),
+        spoil_fraction: 0.0,
+    };
+    let egg = GenericItem::Item("biter-egg".into());
+    let bioflux = GenericItem::Item("bioflux".into());
+    // 每 10 秒产出 5 个虫卵，100kW 的功率每 60 秒消耗一个 6MJ 的生物通量
+    let flow = spawner.as_flow(&ctx);
+    assert!((flow[&egg] - 0.5).abs() < 1e-9);
+    assert!((flow[&bioflux] + 1.0 / 60.0).abs() < 1e-9);
+    // 一半虫卵变质，每 25 个孵化出一只大型虫子
+    spawner.spoil_fraction = 0.5;
+    let flow = spawner.as_flow(&ctx);
+    assert!((flow[&egg] - 0.25).abs() < 1e-9);
+    let biter = GenericItem::Entity("big-biter".into());
+    assert!((flow[&biter] - 0.01).abs() < 1e-9);
+    let hints = SpawnerConfigProvider::new().hint_populate(&ctx, &egg, -1.0);
+    assert_eq!(hints.len(), 1);
+}
+
+crate::impl_register_deserializer!(
+    for SpawnerConfig
+    as "factorio:spawner"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for SpawnerConfigProvider
+    as "factorio:spawner"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
@@ -0,0 +1,235 @@
+//! 原型图标的图层定义与合成。
+//!
+//! 游戏导出的图标偶尔缺少个别原型，多见于用 icons 数组层叠出来的模组内容。
+//! 此时按原型定义的图层，从游戏与模组目录读取原始图片，依次缩放、着色、平移后叠加为一张图标。
+
+use std::path::{Path, PathBuf};
+
+use image::{RgbaImage, imageops};
+
+use crate::factorio::common::*;
+
+/// 合成图标的边长（像素）
+pub const COMPOSED_ICON_SIZE: u32 = 64;
+
+/// 游戏中图标画布的边长（单位），图层的 shift 以此为单位
+const ICON_CANVAS_UNITS: f64 = 32.0;
+
+/// 颜色，可以写作对象或数组，分量大于 1 时按 0-255 解释
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum IconTint {
+    Rgba {
+        #[serde(default)]
+        r: f64,
+        #[serde(default)]
+        g: f64,
+        #[serde(default)]
+        b: f64,
+        a: Option<f64>,
+    },
+    Array(Vec<f64>),
+}
+
+impl IconTint {
+    pub fn normalized(&self) -> [f64; 4] {
+        let [r, g, b, a] = match self {
+            IconTint::Rgba { r, g, b, a } => [*r, *g, *b, a.unwrap_or(f64::NAN)],
+            IconTint::Array(values) => [
+                values.first().cloned().unwrap_or(0.0),
+                values.get(1).cloned().unwrap_or(0.0),
+                values.get(2).cloned().unwrap_or(0.0),
+                values.get(3).cloned().unwrap_or(f64::NAN),
+            ],
+        };
+        let factor = if r > 1.0 || g > 1.0 || b > 1.0 || a > 1.0 {
+            255.0
+        } else {
+            1.0
+        };
+        let a = if a.is_nan() { factor } else { a };
+        [r / factor, g / factor, b / factor, a / factor]
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct IconLayer {
+    /// 图片路径，形如 __base__/graphics/icons/iron-plate.png
+    pub icon: String,
+    /// 图片中第一级贴图的边长，默认 64
+    pub icon_size: Option<f64>,
+    pub tint: Option<IconTint>,
+    /// 相对画布中心的偏移（单位）
+    pub shift: Option<(f64, f64)>,
+    /// 默认使图层铺满画布
+    pub scale: Option<f64>,
+}
+
+/// 原型的图标定义，icons 优先于 icon
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct IconSpecification {
+    pub icon: Option<String>,
+    pub icon_size: Option<f64>,
+    pub icons: Option<Vec<IconLayer>>,
+}
+
+impl IconSpecification {
+    pub fn layers(&self) -> Vec<IconLayer> {
+        match (&self.icons, &self.icon) {
+            (Some(icons), _) => icons.clone(),
+            (None, Some(icon)) => vec![IconLayer {
+                icon: icon.clone(),
+                icon_size: self.icon_size,
+                tint: None,
+                shift: None,
+                scale: None,
+            }],
+            (None, None) => vec![],
+        }
+    }
+}
+
+/// 查找图层图片所在的目录：游戏自带模组在 data 下，其他模组在模组目录下
+#[derive(Debug, Clone, Default)]
+pub struct SpriteRoots {
+    pub data_dir: Option<PathBuf>,
+    pub mod_dir: Option<PathBuf>,
+}
+
+impl SpriteRoots {
+    /// 将 __mod__/path 解析为磁盘上的文件，压缩包中的模组无法读取，返回 None
+    pub fn resolve(&self, icon: &str) -> Option<PathBuf> {
+        let rest = icon.strip_prefix("__")?;
+        let (mod_name, path) = rest.split_once("__/")?;
+        let mut candidates = vec![];
+        if let Some(data_dir) = &self.data_dir {
+            candidates.push(data_dir.join(mod_name).join(path));
+        }
+        if let Some(mod_dir) = &self.mod_dir {
+            candidates.push(mod_dir.join(mod_name).join(path));
+            // 解压后的模组文件夹可能带有版本号
+            if let Ok(entries) = std::fs::read_dir(mod_dir) {
+                for entry in entries.flatten() {
+                    if entry.path().is_dir()
+                        && entry
+                            .file_name()
+                            .to_string_lossy()
+                            .starts_with(&format!("{}_", mod_name))
+                    {
+                        candidates.push(entry.path().join(path));
+                    }
+                }
+            }
+        }
+        candidates.into_iter().find(|candidate| candidate.exists())
+    }
+}
+
+fn apply_tint(image: &mut RgbaImage, tint: [f64; 4]) {
+    for pixel in image.pixels_mut() {
+        for (channel, factor) in pixel.0.iter_mut().zip(tint) {
+            *channel = (*channel as f64 * factor).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+/// 按图层合成图标，任一图层的图片无法读取时返回 None
+pub fn compose_icon(
+    layers: &[IconLayer],
+    resolve: impl Fn(&str) -> Option<PathBuf>,
+) -> Option<RgbaImage> {
+    if layers.is_empty() {
+        return None;
+    }
+    let mut canvas = RgbaImage::new(COMPOSED_ICON_SIZE, COMPOSED_ICON_SIZE);
+    let pixels_per_unit = COMPOSED_ICON_SIZE as f64 / ICON_CANVAS_UNITS;
+    for layer in layers {
+        let source = image::open(resolve(&layer.icon)?).ok()?.to_rgba8();
+        // 图片可能附带多级贴图，只取左上角的第一级
+        let icon_size = layer.icon_size.unwrap_or(64.0).max(1.0) as u32;
+        let source = imageops::crop_imm(
+            &source,
+            0,
+            0,
+            icon_size.min(source.width()),
+            icon_size.min(source.height()),
+        )
+        .to_image();
+        let scale = layer.scale.unwrap_or(ICON_CANVAS_UNITS / icon_size as f64);
+        let size = (icon_size as f64 * scale * pixels_per_unit)
+            .round()
+            .max(1.0) as u32;
+        let mut scaled = imageops::resize(&source, size, size, imageops::FilterType::Triangle);
+        if let Some(tint) = &layer.tint {
+            apply_tint(&mut scaled, tint.normalized());
+        }
+        let (shift_x, shift_y) = layer.shift.unwrap_or((0.0, 0.0));
+        let center = COMPOSED_ICON_SIZE as f64 / 2.0;
+        let x = center + shift_x * pixels_per_unit - size as f64 / 2.0;
+        let y = center + shift_y * pixels_per_unit - size as f64 / 2.0;
+        imageops::overlay(&mut canvas, &scaled, x.round() as i64, y.round() as i64);
+    }
+    Some(canvas)
+}
+
+/// 为导出结果中缺少图标的原型合成图标，写入 icon_path/类别/名称.png，返回合成的数量
+pub fn compose_missing_icons(
+    specs: &Dict<Dict<IconSpecification>>,
+    icon_path: &Path,
+    roots: &SpriteRoots,
+) -> usize {
+    let mut composed = 0;
+    for (category, prototypes) in specs {
+        for (name, spec) in prototypes {
+            let target = icon_path.join(category).join(format!("{}.png", name));
+            if target.exists() {
+                continue;
+            }
+            let Some(image) = compose_icon(&spec.layers(), |icon| roots.resolve(icon)) else {
+                continue;
+            };
+            let saved = std::fs::create_dir_all(target.parent().unwrap())
+                .map_err(|err| err.to_string())
+                .and_then(|_| image.save(&target).map_err(|err| err.to_string()));
+            match saved {
+                Ok(()) => composed += 1,
+                Err(err) => log::warn!("写入合成图标 {} 失败: {}", target.display(), err),
+            }
+        }
+    }
+    composed
+}
+
+#[test]
+fn test_compose_icon() {
+    let dir = std::env::temp_dir().join("metatorio-test-sprite");
+    std::fs::create_dir_all(&dir).unwrap();
+    RgbaImage::from_pixel(16, 16, image::Rgba([255, 255, 255, 255]))
+        .save(dir.join("white.png"))
+        .unwrap();
+    let layer = |tint, shift, scale| IconLayer {
+        icon: "__test__/white.png".to_string(),
+        icon_size: Some(16.0),
+        tint,
+        shift,
+        scale,
+    };
+    let layers = vec![
+        layer(None, None, None),
+        layer(
+            Some(IconTint::Array(vec![255.0, 0.0, 0.0])),
+            Some((8.0, 8.0)),
+            Some(0.5),
+        ),
+    ];
+    assert!(SpriteRoots::default().resolve(&layers[0].icon).is_none());
+    let image = compose_icon(&layers, |icon| {
+        Some(dir.join(icon.strip_prefix("__test__/")?))
+    })
+    .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(image.dimensions(), (COMPOSED_ICON_SIZE, COMPOSED_ICON_SIZE));
+    // 底层铺满画布，右下角的小图层被染成红色
+    assert_eq!(image.get_pixel(8, 8).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(48, 48).0, [255, 0, 0, 255]);
+}
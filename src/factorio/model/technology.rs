@@ -0,0 +1,421 @@
+use indexmap::IndexMap;
+
+use crate::{
+    concept::Flow,
+    factorio::{
+        common::*,
+        model::{FactorioContext, GenericItem, LabConfig, MiningSettings, RecipeConfig},
+    },
+};
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TechnologyPrototype {
+    #[serde(flatten)]
+    pub base: PrototypeBase,
+
+    /// 前置科技
+    #[serde(default, deserialize_with = "as_vec_or_empty")]
+    pub prerequisites: Vec<String>,
+
+    /// 研究完成后的效果
+    #[serde(default, deserialize_with = "as_vec_or_empty")]
+    pub effects: Vec<TechnologyEffect>,
+
+    /// 研究所需的科技包，由触发条件解锁的科技没有此项
+    pub unit: Option<TechnologyUnit>,
+
+    /// 由游戏内事件触发解锁的条件
+    pub research_trigger: Option<ResearchTrigger>,
+
+    /// 可重复研究的最高等级
+    pub max_level: Option<TechnologyMaxLevel>,
+
+    /// 是否为同一系列科技的升级
+    #[serde(default)]
+    pub upgrade: bool,
+}
+
+impl HasPrototypeBase for TechnologyPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base
+    }
+}
+
+impl TechnologyPrototype {
+    /// 研究后解锁的配方
+    pub fn unlocked_recipes(&self) -> impl Iterator<Item = &String> {
+        self.effects.iter().filter_map(|effect| match effect {
+            TechnologyEffect::UnlockRecipe { recipe } => Some(recipe),
+            _ => None,
+        })
+    }
+
+    /// 是否为无限研究
+    pub fn is_infinite(&self) -> bool {
+        matches!(&self.max_level, Some(TechnologyMaxLevel::Named(level)) if level == "infinite")
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum TechnologyEffect {
+    UnlockRecipe {
+        recipe: String,
+    },
+    ChangeRecipeProductivity {
+        recipe: String,
+        change: f64,
+    },
+    MiningDrillProductivityBonus {
+        modifier: f64,
+    },
+    LaboratoryProductivity {
+        modifier: f64,
+    },
+    LaboratorySpeed {
+        modifier: f64,
+    },
+    UnlockQuality {
+        quality: String,
+    },
+    UnlockSpaceLocation {
+        space_location: String,
+    },
+    /// 与产能计算无关的其他效果
+    #[serde(other)]
+    Other,
+}
+
+impl TechnologyEffect {
+    /// 是否为按研究等级叠加、影响产出速率的加成
+    pub fn is_rate_bonus(&self) -> bool {
+        matches!(
+            self,
+            TechnologyEffect::ChangeRecipeProductivity { .. }
+                | TechnologyEffect::LaboratoryProductivity { .. }
+                | TechnologyEffect::LaboratorySpeed { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TechnologyUnit {
+    /// 固定的研究次数
+    pub count: Option<f64>,
+    /// 按等级 L 计算研究次数的公式，用于无限研究
+    pub count_formula: Option<String>,
+    /// 每次研究消耗的科技包及数量
+    pub ingredients: Vec<(String, f64)>,
+    /// 每次研究的耗时（秒）
+    pub time: f64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ResearchTrigger {
+    pub r#type: String,
+    pub count: Option<f64>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum TechnologyMaxLevel {
+    Level(f64),
+    Named(String),
+}
+
+/// 已研究的加成科技，每个科技记录研究的次数，未记录的视为未研究
+///
+/// 采矿产能由 MiningSettings 单独设置，这里只记录配方产能与研究中心相关的科技。
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ResearchBonuses {
+    pub levels: IndexMap<String, u32>,
+}
+
+impl ResearchBonuses {
+    /// 已研究科技的效果，与对应的研究次数一起给出
+    fn researched_effects<'a>(
+        &'a self,
+        ctx: &'a FactorioContext,
+    ) -> impl Iterator<Item = (&'a TechnologyEffect, f64)> {
+        self.levels.iter().flat_map(|(name, level)| {
+            ctx.technologies
+                .get(name)
+                .into_iter()
+                .flat_map(|technology| technology.effects.iter())
+                .map(|effect| (effect, *level as f64))
+        })
+    }
+
+    /// 配方的研究产能加成
+    pub fn recipe_productivity(&self, ctx: &FactorioContext, recipe: &str) -> f64 {
+        self.researched_effects(ctx)
+            .map(|(effect, level)| match effect {
+                TechnologyEffect::ChangeRecipeProductivity {
+                    recipe: target,
+                    change,
+                } if target == recipe => change * level,
+                _ => 0.0,
+            })
+            .sum()
+    }
+
+    /// 按配方汇总的研究产能加成，只列出有加成的配方
+    pub fn recipe_productivities(&self, ctx: &FactorioContext) -> IndexMap<String, f64> {
+        let mut productivities = IndexMap::new();
+        for (effect, level) in self.researched_effects(ctx) {
+            if let TechnologyEffect::ChangeRecipeProductivity { recipe, change } = effect {
+                *productivities.entry(recipe.clone()).or_insert(0.0) += change * level;
+            }
+        }
+        productivities.retain(|_, productivity| *productivity != 0.0);
+        productivities
+    }
+
+    /// 研究中心的产能加成
+    pub fn lab_productivity(&self, ctx: &FactorioContext) -> f64 {
+        self.researched_effects(ctx)
+            .map(|(effect, level)| match effect {
+                TechnologyEffect::LaboratoryProductivity { modifier } => modifier * level,
+                _ => 0.0,
+            })
+            .sum()
+    }
+
+    /// 研究中心的速度加成
+    pub fn lab_speed(&self, ctx: &FactorioContext) -> f64 {
+        self.researched_effects(ctx)
+            .map(|(effect, level)| match effect {
+                TechnologyEffect::LaboratorySpeed { modifier } => modifier * level,
+                _ => 0.0,
+            })
+            .sum()
+    }
+}
+
+/// 可在全局加成面板中设置的科技，按排序字段排列
+pub fn bonus_technologies(ctx: &FactorioContext) -> Vec<&TechnologyPrototype> {
+    let mut technologies = ctx
+        .technologies
+        .values()
+        .filter(|technology| {
+            technology
+                .effects
+                .iter()
+                .any(TechnologyEffect::is_rate_bonus)
+        })
+        .collect::<Vec<_>>();
+    technologies.sort_by(|a, b| (&a.base.order, &a.base.name).cmp(&(&b.base.order, &b.base.name)));
+    technologies
+}
+
+/// 工厂级的全局加成：采矿设置与已研究的加成科技
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GlobalBonuses {
+    pub mining: MiningSettings,
+    pub research: ResearchBonuses,
+}
+
+impl GlobalBonuses {
+    /// 按加成计算机制的物料流，不受加成影响的机制直接使用原本的物料流
+    pub fn mechanic_flow(
+        &self,
+        mechanic: &FactorioMechanic,
+        ctx: &FactorioContext,
+    ) -> Flow<GenericItem> {
+        let any = mechanic as &dyn std::any::Any;
+        if let Some(recipe_config) = any.downcast_ref::<RecipeConfig>() {
+            recipe_config.as_flow_with_productivity(
+                ctx,
+                self.research
+                    .recipe_productivity(ctx, &recipe_config.recipe.0),
+            )
+        } else if let Some(lab_config) = any.downcast_ref::<LabConfig>() {
+            lab_config.as_flow_with_bonuses(
+                ctx,
+                self.research.lab_speed(ctx),
+                self.research.lab_productivity(ctx),
+            )
+        } else {
+            self.mining.mechanic_flow(mechanic, ctx)
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+pub struct GlobalBonusesEditor<'a> {
+    pub bonuses: &'a mut GlobalBonuses,
+    pub ctx: &'a FactorioContext,
+    pub changed: Option<&'a mut bool>,
+}
+
+#[cfg(feature = "gui")]
+impl<'a> GlobalBonusesEditor<'a> {
+    pub fn new(ctx: &'a FactorioContext, bonuses: &'a mut GlobalBonuses) -> Self {
+        Self {
+            bonuses,
+            ctx,
+            changed: None,
+        }
+    }
+
+    pub fn notify_change(mut self, changed: &'a mut bool) -> Self {
+        self.changed = Some(changed);
+        self
+    }
+}
+
+#[cfg(feature = "gui")]
+impl egui::Widget for GlobalBonusesEditor<'_> {
+    fn ui(mut self, ui: &mut egui::Ui) -> egui::Response {
+        let mut changed = false;
+        egui::CollapsingHeader::new("全局加成")
+            .id_salt("global-bonuses")
+            .show(ui, |ui| {
+                changed |= ui.add(&mut self.bonuses.mining).changed();
+                ui.label(format!(
+                    "研究速度 +{:.0}%，研究产能 +{:.0}%",
+                    self.bonuses.research.lab_speed(self.ctx) * 100.0,
+                    self.bonuses.research.lab_productivity(self.ctx) * 100.0
+                ));
+                let productivities = self.bonuses.research.recipe_productivities(self.ctx);
+                if !productivities.is_empty() {
+                    // 研究产能与插件、机器的产能相加后受配方的产能上限约束
+                    egui::CollapsingHeader::new(format!(
+                        "配方产能（{} 个配方）",
+                        productivities.len()
+                    ))
+                    .id_salt("global-bonuses-recipe-productivity")
+                    .show(ui, |ui| {
+                        egui::Grid::new("global-bonuses-recipe-productivity-grid").show(ui, |ui| {
+                            for (recipe, productivity) in &productivities {
+                                ui.label(self.ctx.get_display_name("recipe", recipe));
+                                ui.label(format!("+{:.0}%", productivity * 100.0));
+                                match self.ctx.recipes.get(recipe) {
+                                    Some(prototype)
+                                        if *productivity >= prototype.maximum_productivity =>
+                                    {
+                                        ui.colored_label(
+                                            egui::Color32::YELLOW,
+                                            format!(
+                                                "已达上限 +{:.0}%",
+                                                prototype.maximum_productivity * 100.0
+                                            ),
+                                        );
+                                    }
+                                    Some(prototype) => {
+                                        ui.label(format!(
+                                            "上限 +{:.0}%",
+                                            prototype.maximum_productivity * 100.0
+                                        ));
+                                    }
+                                    None => {
+                                        ui.label("");
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("global-bonuses-research").show(ui, |ui| {
+                            for technology in bonus_technologies(self.ctx) {
+                                let name = &technology.base.name;
+                                ui.label(self.ctx.get_display_name("technology", name));
+                                let mut level =
+                                    self.bonuses.research.levels.get(name).cloned().unwrap_or(0);
+                                // 非无限研究只能研究一次
+                                let max_level = if technology.is_infinite() {
+                                    u32::MAX
+                                } else {
+                                    1
+                                };
+                                if ui
+                                    .add(
+                                        egui::DragValue::new(&mut level)
+                                            .range(0..=max_level)
+                                            .prefix("次数 "),
+                                    )
+                                    .changed()
+                                {
+                                    if level == 0 {
+                                        self.bonuses.research.levels.shift_remove(name);
+                                    } else {
+                                        self.bonuses.research.levels.insert(name.clone(), level);
+                                    }
+                                    changed = true;
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+            });
+        if changed && let Some(flag) = &mut self.changed {
+            **flag = true;
+        }
+        ui.response().clone()
+    }
+}
+
+#[test]
+fn test_technology_parse() {
+    let ctx = crate::factorio::model::FactorioContext::test_load();
+    let automation = &ctx.technologies["automation"];
+    assert!(
+        automation
+            .unlocked_recipes()
+            .any(|r| r == "assembling-machine-1")
+    );
+    assert!(ctx.technologies.values().any(|t| t.is_infinite()));
+    assert!(
+        ctx.technologies
+            .values()
+            .any(|t| t.research_trigger.is_some())
+    );
+}
+
+#[test]
+fn test_research_bonuses() {
+    use crate::factorio::ModuleConfig;
+    let ctx = crate::factorio::model::FactorioContext::test_load();
+    let mut bonuses = GlobalBonuses::default();
+    bonuses
+        .research
+        .levels
+        .insert("steel-plate-productivity".to_string(), 5);
+    bonuses
+        .research
+        .levels
+        .insert("research-speed-1".to_string(), 1);
+    assert!((bonuses.research.recipe_productivity(&ctx, "steel-plate") - 0.5).abs() < 1e-9);
+    assert!((bonuses.research.recipe_productivity(&ctx, "casting-steel") - 0.5).abs() < 1e-9);
+    assert_eq!(
+        bonuses.research.recipe_productivity(&ctx, "iron-plate"),
+        0.0
+    );
+    assert!((bonuses.research.lab_speed(&ctx) - 0.2).abs() < 1e-9);
+    let steel: Box<FactorioMechanic> = Box::new(RecipeConfig {
+        recipe: "steel-plate".into(),
+        machine: "electric-furnace".into(),
+        module_config: ModuleConfig::new(),
+        instance_fuel: None,
+    });
+    let plate = GenericItem::Item("steel-plate".into());
+    let base = steel.as_flow(&ctx)[&plate];
+    let boosted = bonuses.mechanic_flow(steel.as_ref(), &ctx)[&plate];
+    assert!((boosted - base * 1.5).abs() < 1e-9);
+    // 研究 40 次后加成为 +400%，超出钢板配方 +300% 的产能上限
+    bonuses
+        .research
+        .levels
+        .insert("steel-plate-productivity".to_string(), 40);
+    let productivities = bonuses.research.recipe_productivities(&ctx);
+    assert!((productivities["steel-plate"] - 4.0).abs() < 1e-9);
+    assert!(!productivities.contains_key("iron-plate"));
+    let cap = ctx.recipes["steel-plate"].maximum_productivity;
+    let capped = bonuses.mechanic_flow(steel.as_ref(), &ctx)[&plate];
+    assert!((capped - base * (1.0 + cap)).abs() < 1e-9);
+}
@@ -0,0 +1,457 @@
+#[cfg(feature = "gui")]
+use crate::factorio::{
+    editor::icon::Icon, format::compact_number, modal::ItemWithQualitySelectorModal,
+};
+use crate::{
+    concept::*,
+    factorio::{
+        common::*,
+        model::{context::*, entity::*},
+    },
+};
+
+/// 平台加速度公式中的常数，取自工具常量 space_platform_acceleration_expression：
+/// (thrust / (1 + weight / 10000000) - ((1500 * speed * speed + 1500 * abs(speed)) * (width * 0.5) + 10000) * sign(speed)) / weight / 60
+const WEIGHT_THRUST_FALLOFF: f64 = 1e7;
+const DRAG_COEFFICIENT: f64 = 1500.0;
+const BASE_DRAG: f64 = 10000.0;
+
+/// 推进器每秒消耗 1 单位燃料与氧化剂、效率为 1 时，公式中的推力
+const THRUST_PER_FLUID_USAGE: f64 = 1e6;
+
+/// 持续航行的平台，一单位表示一个平台以目标速度航行
+pub fn platform_travel() -> GenericItem {
+    GenericItem::Custom {
+        name: "platform-travel".to_string(),
+    }
+}
+
+/// 推进器在某个燃料充满程度下的表现
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ThrusterPerformance {
+    pub fluid_volume: f64,
+    /// 每刻消耗的燃料与氧化剂
+    pub fluid_usage: f64,
+    pub effectivity: f64,
+}
+
+/// 推进器，同时消耗燃料与氧化剂推动太空平台，供给越多推力越大、效率越低
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ThrusterPrototype {
+    #[serde(flatten)]
+    pub base: EntityPrototype,
+
+    pub min_performance: ThrusterPerformance,
+    pub max_performance: ThrusterPerformance,
+    pub fuel_fluid_box: FluidBox,
+    pub oxidizer_fluid_box: FluidBox,
+}
+
+impl HasPrototypeBase for ThrusterPrototype {
+    fn base(&self) -> &PrototypeBase {
+        &self.base.base
+    }
+}
+
+impl ThrusterPrototype {
+    /// 效率在最低与最高表现之间随消耗量线性变化
+    fn effectivity(&self, usage: f64) -> f64 {
+        let (min, max) = (&self.min_performance, &self.max_performance);
+        if max.fluid_usage <= min.fluid_usage {
+            return min.effectivity;
+        }
+        let ratio = (usage - min.fluid_usage) / (max.fluid_usage - min.fluid_usage);
+        min.effectivity + (max.effectivity - min.effectivity) * ratio
+    }
+
+    /// 每刻消耗 usage 单位燃料时的推力
+    pub fn thrust(&self, usage: f64) -> f64 {
+        usage * 60.0 * self.effectivity(usage) * THRUST_PER_FLUID_USAGE
+    }
+
+    /// 推力最大时的消耗量，效率下降较快时不一定是最高表现
+    fn peak_usage(&self) -> f64 {
+        let (min, max) = (&self.min_performance, &self.max_performance);
+        if max.fluid_usage <= min.fluid_usage {
+            return min.fluid_usage;
+        }
+        let slope = (max.effectivity - min.effectivity) / (max.fluid_usage - min.fluid_usage);
+        if slope >= 0.0 {
+            return max.fluid_usage;
+        }
+        let intercept = min.effectivity - slope * min.fluid_usage;
+        (-intercept / (2.0 * slope)).clamp(min.fluid_usage, max.fluid_usage)
+    }
+
+    pub fn max_thrust(&self) -> f64 {
+        self.thrust(self.peak_usage())
+    }
+
+    /// 产生指定推力所需的平均每刻消耗量，推力达不到时返回 None
+    ///
+    /// 低于最低表现的推力靠间歇工作实现，消耗量按最低表现的比例折算。
+    pub fn usage_for_thrust(&self, thrust: f64) -> Option<f64> {
+        let min_usage = self.min_performance.fluid_usage;
+        let min_thrust = self.thrust(min_usage);
+        if thrust <= min_thrust {
+            if min_thrust <= 0.0 {
+                return Some(0.0);
+            }
+            return Some(min_usage * thrust.max(0.0) / min_thrust);
+        }
+        let (mut low, mut high) = (min_usage, self.peak_usage());
+        // 留出浮点误差，恰好以最高速度航行时仍视为可以达到
+        if thrust > self.thrust(high) * (1.0 + 1e-9) {
+            return None;
+        }
+        // 推力在最低表现与峰值之间单调增加，二分求解
+        for _ in 0..64 {
+            let mid = (low + high) / 2.0;
+            if self.thrust(mid) < thrust {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        Some(high)
+    }
+}
+
+/// 太空平台以恒定速度持续航行，推进器的燃料与氧化剂消耗随平台重量、宽度与速度变化
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:thruster")]
+pub struct ThrusterConfig {
+    pub machine: IdWithQuality,
+    /// 平台上的推进器数量
+    pub thrusters: u32,
+    /// 平台重量（吨）
+    pub platform_weight: f64,
+    /// 平台宽度（格），决定阻力大小
+    pub platform_width: f64,
+    /// 目标航行速度（km/s）
+    pub speed: f64,
+}
+
+impl Default for ThrusterConfig {
+    fn default() -> Self {
+        ThrusterConfig {
+            machine: ("entity-unknown".to_string(), 0).into(),
+            thrusters: 2,
+            platform_weight: 1000.0,
+            platform_width: 20.0,
+            speed: 50.0,
+        }
+    }
+}
+
+impl ThrusterConfig {
+    /// 公式中的重量单位为千克
+    fn weight_factor(&self) -> f64 {
+        1.0 + self.platform_weight * 1000.0 / WEIGHT_THRUST_FALLOFF
+    }
+
+    /// 以目标速度匀速航行时，全部推进器需要的总推力
+    pub fn required_thrust(&self) -> f64 {
+        let speed = self.speed.max(0.0);
+        let drag =
+            DRAG_COEFFICIENT * (speed * speed + speed) * self.platform_width * 0.5 + BASE_DRAG;
+        drag * self.weight_factor()
+    }
+
+    /// 推进器全力工作时的最高速度（km/s），推力不足以克服基础阻力时为 0
+    pub fn max_speed(&self, ctx: &FactorioContext) -> f64 {
+        let Some(thruster) = ctx.thrusters.get(&self.machine.0) else {
+            return 0.0;
+        };
+        let thrust = thruster.max_thrust() * self.thrusters as f64 / self.weight_factor();
+        let coefficient = DRAG_COEFFICIENT * self.platform_width * 0.5;
+        if thrust <= BASE_DRAG || coefficient <= 0.0 {
+            return 0.0;
+        }
+        // 解 v² + v = (推力 - 基础阻力) / 阻力系数
+        let c = (thrust - BASE_DRAG) / coefficient;
+        ((1.0 + 4.0 * c).sqrt() - 1.0) / 2.0
+    }
+
+    /// 每台推进器平均每刻的消耗量，达不到目标速度时按峰值推力计算
+    pub fn usage_per_thruster(&self, ctx: &FactorioContext) -> (f64, bool) {
+        let Some(thruster) = ctx.thrusters.get(&self.machine.0) else {
+            return (0.0, false);
+        };
+        if self.thrusters == 0 {
+            return (0.0, false);
+        }
+        let thrust = self.required_thrust() / self.thrusters as f64;
+        match thruster.usage_for_thrust(thrust) {
+            Some(usage) => (usage, true),
+            None => (thruster.peak_usage(), false),
+        }
+    }
+}
+
+impl SolveContext for ThrusterConfig {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl AsFlow for ThrusterConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        let mut map = Flow::new();
+        let Some(thruster) = ctx.thrusters.get(&self.machine.0) else {
+            return map;
+        };
+        let (usage, _) = self.usage_per_thruster(ctx);
+        let consumption = usage * 60.0 * self.thrusters as f64;
+        for fluid_box in [&thruster.fuel_fluid_box, &thruster.oxidizer_fluid_box] {
+            if let Some(fluid) = &fluid_box.filter {
+                index_map_update_entry(
+                    &mut map,
+                    GenericItem::Fluid {
+                        name: fluid.clone(),
+                        temperature: None,
+                    },
+                    -consumption,
+                );
+            }
+        }
+        index_map_update_entry(&mut map, platform_travel(), 1.0);
+        map
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        ctx.thrusters.get(&self.machine.0).map_or(16.0, |thruster| {
+            thruster
+                .base
+                .collision_box
+                .as_ref()
+                .map_or(1.0, BoundingBox::tile_area)
+        }) * self.thrusters as f64
+    }
+}
+
+impl EditorView for ThrusterConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.vertical(|ui| {
+                ui.add_sized(crate::settings::label_cell(), egui::Label::new("推进器"));
+                let entity_button = ui
+                    .add_sized(
+                        crate::settings::icon_cell(),
+                        Icon::new(ctx, "entity", &self.machine.0).with_quality(self.machine.1),
+                    )
+                    .interact(egui::Sense::click())
+                    .on_hover_text(match ctx.thrusters.get(&self.machine.0) {
+                        Some(thruster) => format!(
+                            "{}：每秒最多消耗 {} 燃料与氧化剂",
+                            ctx.get_display_name("entity", &self.machine.0),
+                            compact_number(thruster.max_performance.fluid_usage * 60.0)
+                        ),
+                        None => "推进器：未选择".into(),
+                    });
+                ui.add(
+                    ItemWithQualitySelectorModal::new(
+                        entity_button.id,
+                        ctx,
+                        "选择推进器",
+                        "entity",
+                    )
+                    .with_toggle(entity_button.clicked())
+                    .with_current(&mut self.machine)
+                    .with_filter(|s, f| f.thrusters.contains_key(s))
+                    .notify_change(&mut changed),
+                );
+            });
+            ui.separator();
+            egui::Grid::new("thruster-platform")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("推进器数量");
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut self.thrusters).range(1..=u32::MAX))
+                        .changed();
+                    ui.end_row();
+                    ui.label("平台重量");
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.platform_weight)
+                                .range(0.0..=f64::INFINITY)
+                                .speed(10.0)
+                                .suffix(" t"),
+                        )
+                        .changed();
+                    ui.end_row();
+                    ui.label("平台宽度");
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.platform_width)
+                                .range(0.0..=f64::INFINITY)
+                                .speed(1.0)
+                                .suffix(" 格"),
+                        )
+                        .on_hover_text("平台在航行方向上的最大宽度，越宽阻力越大")
+                        .changed();
+                    ui.end_row();
+                    ui.label("航行速度");
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.speed)
+                                .range(0.0..=f64::INFINITY)
+                                .speed(1.0)
+                                .suffix(" km/s"),
+                        )
+                        .changed();
+                    ui.end_row();
+                });
+            if ctx.thrusters.contains_key(&self.machine.0) {
+                ui.separator();
+                ui.vertical(|ui| {
+                    let (usage, reachable) = self.usage_per_thruster(ctx);
+                    ui.label(format!("每台推进器消耗 {}/s", compact_number(usage * 60.0)));
+                    let max_speed =
+                        format!("最高速度 {} km/s", compact_number(self.max_speed(ctx)));
+                    if reachable {
+                        ui.label(max_speed);
+                    } else {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            format!("达不到目标速度，{}", max_speed),
+                        );
+                    }
+                });
+            }
+        });
+        changed
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "factorio:thruster")]
+pub struct ThrusterConfigProvider {
+    #[serde(skip)]
+    pub sender: Option<MechanicSender<GenericItem, FactorioContext>>,
+}
+
+impl Default for ThrusterConfigProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ThrusterConfigProvider {
+    pub fn new() -> Self {
+        Self { sender: None }
+    }
+}
+
+impl SolveContext for ThrusterConfigProvider {
+    type GameContext = FactorioContext;
+    type ItemIdentType = GenericItem;
+}
+
+impl EditorView for ThrusterConfigProvider {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, _ctx: &Self::GameContext) -> bool {
+        if ui.button("添加平台航行").clicked() {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(Box::new(ThrusterConfig::default()));
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl MechanicProvider for ThrusterConfigProvider {
+    fn set_mechanic_sender(
+        &mut self,
+        sender: MechanicSender<Self::ItemIdentType, Self::GameContext>,
+    ) {
+        self.sender = Some(sender);
+    }
+
+    fn hint_populate(
+        &self,
+        ctx: &Self::GameContext,
+        item: &Self::ItemIdentType,
+        value: f64,
+    ) -> Vec<Box<dyn Mechanic<ItemIdentType = Self::ItemIdentType, GameContext = Self::GameContext>>>
+    {
+        if *item != platform_travel() || value >= 0.0 {
+            return vec![];
+        }
+        let mut thrusters = ctx.thrusters.keys().collect::<Vec<_>>();
+        thrusters.sort();
+        thrusters
+            .into_iter()
+            .map(|thruster| {
+                Box::new(ThrusterConfig {
+                    machine: thruster.as_str().into(),
+                    ..Default::default()
+                })
+                    as Box<dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>>
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_thruster() {
+    let ctx = FactorioContext::test_load();
+    let thruster = &ctx.thrusters["thruster"];
+    // 最低表现每秒 6 单位、效率 1，最高表现每秒 120 单位、效率 0.51
+    assert!((thruster.thrust(0.1) - 6e6).abs() < 1e-3);
+    assert!((thruster.thrust(2.0) - 6.12e7).abs() < 1e-3);
+    assert!(thruster.max_thrust() >= thruster.thrust(2.0));
+    let usage = thruster.usage_for_thrust(3e7).unwrap();
+    assert!((thruster.thrust(usage) - 3e7).abs() < 1e-3);
+    // 低于最低推力时间歇工作
+    assert!((thruster.usage_for_thrust(3e6).unwrap() - 0.05).abs() < 1e-9);
+    assert!(thruster.usage_for_thrust(1.2e8).is_none());
+
+    let config = ThrusterConfig {
+        machine: "thruster".into(),
+        ..Default::default()
+    };
+    let flow = config.as_flow(&ctx);
+    let (usage, reachable) = config.usage_per_thruster(&ctx);
+    assert!(reachable);
+    let fuel = GenericItem::Fluid {
+        name: "thruster-fuel".to_string(),
+        temperature: None,
+    };
+    let oxidizer = GenericItem::Fluid {
+        name: "thruster-oxidizer".to_string(),
+        temperature: None,
+    };
+    assert!((flow[&fuel] + usage * 120.0).abs() < 1e-9);
+    assert_eq!(flow[&fuel], flow[&oxidizer]);
+    assert_eq!(flow[&platform_travel()], 1.0);
+    // 以最高速度航行时推进器恰好全力工作
+    let fastest = ThrusterConfig {
+        speed: config.max_speed(&ctx),
+        ..config.clone()
+    };
+    let (usage, reachable) = fastest.usage_per_thruster(&ctx);
+    assert!(reachable);
+    assert!((thruster.thrust(usage) - thruster.max_thrust()).abs() < 1.0);
+    // 更快则达不到
+    let faster = ThrusterConfig {
+        speed: fastest.speed + 1.0,
+        ..config
+    };
+    assert!(!faster.usage_per_thruster(&ctx).1);
+}
+
+crate::impl_register_deserializer!(
+    for ThrusterConfig
+    as "factorio:thruster"
+    => dyn Mechanic<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
+
+crate::impl_register_deserializer!(
+    for ThrusterConfigProvider
+    as "factorio:thruster"
+    => dyn MechanicProvider<ItemIdentType = GenericItem, GameContext = FactorioContext>
+);
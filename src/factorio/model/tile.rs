@@ -1,6 +1,6 @@
 use crate::factorio::{AutoplaceSpecification, HasPrototypeBase, PrototypeBase};
 
-#[derive(Debug, Clone, serde::Deserialize, Default)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Default)]
 pub struct TilePrototype {
     #[serde(flatten)]
     pub base: PrototypeBase,
@@ -0,0 +1,301 @@
+//! 从上传的文件创建上下文，不需要启动游戏进程，网页版只能使用这种方式。
+//!
+//! 需要的文件与本地导出的结果相同：
+//! - `data-raw-dump.json`，可以单独上传，也可以放在压缩包中；
+//! - 图标压缩包，即把导出的 script-output 文件夹打包成 zip，其中的
+//!   `<类别>/<名称>.png` 为图标，`<类别>-locale.json` 为翻译。
+
+use std::{io::Read, path::PathBuf};
+
+use indexmap::IndexMap;
+
+use crate::{
+    error::AppError,
+    factorio::{
+        common::Dict,
+        model::{ContextLoadError, FactorioContext, LOCALE_CATEGORIES, LocaleTable},
+    },
+};
+
+/// 上传数据中图标所在的虚拟目录，图标内容保存在内存中
+pub const UPLOAD_ICON_ROOT: &str = "upload/script-output";
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset.checked_add(2)?)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset.checked_add(4)?)?.try_into().ok()?,
+    ))
+}
+
+/// 读取 zip 压缩包中的所有文件，只支持不压缩与 deflate 两种方式，不支持 zip64
+pub fn read_zip_entries(data: &[u8]) -> Result<IndexMap<String, Vec<u8>>, AppError> {
+    let invalid = |reason: &str| AppError::Io(format!("无法读取压缩包：{}", reason));
+    // 偏移量来自上传的文件，32 位平台上相加可能溢出
+    let add = |offsets: &[usize], reason: &str| {
+        offsets
+            .iter()
+            .try_fold(0usize, |sum, offset| sum.checked_add(*offset))
+            .ok_or_else(|| invalid(reason))
+    };
+    // 目录结束记录位于末尾，其后最多跟着 65535 字节的注释
+    let end = (0..=data.len().saturating_sub(22))
+        .rev()
+        .take(22 + u16::MAX as usize)
+        .find(|offset| read_u32(data, *offset) == Some(0x06054b50))
+        .ok_or_else(|| invalid("不是 zip 文件"))?;
+    let count = read_u16(data, end + 10).ok_or_else(|| invalid("目录损坏"))? as usize;
+    let mut offset = read_u32(data, end + 16).ok_or_else(|| invalid("目录损坏"))? as usize;
+    if offset == u32::MAX as usize {
+        return Err(invalid("不支持 zip64"));
+    }
+    let mut entries = IndexMap::new();
+    for _ in 0..count {
+        if read_u32(data, offset) != Some(0x02014b50) {
+            return Err(invalid("目录损坏"));
+        }
+        let field = |at: usize| {
+            read_u16(data, add(&[offset, at], "目录损坏")?).ok_or_else(|| invalid("目录损坏"))
+        };
+        let method = field(10)?;
+        let name_len = field(28)? as usize;
+        let extra_len = field(30)? as usize;
+        let comment_len = field(32)? as usize;
+        let compressed_size =
+            read_u32(data, add(&[offset, 20], "目录损坏")?).ok_or_else(|| invalid("目录损坏"))?;
+        let local = read_u32(data, add(&[offset, 42], "目录损坏")?)
+            .ok_or_else(|| invalid("目录损坏"))? as usize;
+        let name_start = add(&[offset, 46], "目录损坏")?;
+        let name = data
+            .get(name_start..add(&[name_start, name_len], "目录损坏")?)
+            .map(|name| String::from_utf8_lossy(name).replace('\\', "/"))
+            .ok_or_else(|| invalid("目录损坏"))?;
+        offset = add(&[name_start, name_len, extra_len, comment_len], "目录损坏")?;
+        if name.ends_with('/') {
+            continue;
+        }
+        if read_u32(data, local) != Some(0x04034b50) {
+            return Err(invalid("文件头损坏"));
+        }
+        let local_field = |at: usize| {
+            read_u16(data, add(&[local, at], "文件头损坏")?).ok_or_else(|| invalid("文件头损坏"))
+        };
+        let start = add(
+            &[
+                local,
+                30,
+                local_field(26)? as usize,
+                local_field(28)? as usize,
+            ],
+            "文件头损坏",
+        )?;
+        let raw = data
+            .get(start..add(&[start, compressed_size as usize], "文件内容不完整")?)
+            .ok_or_else(|| invalid("文件内容不完整"))?;
+        let content = match method {
+            0 => raw.to_vec(),
+            8 => {
+                let mut content = vec![];
+                flate2::read::DeflateDecoder::new(raw).read_to_end(&mut content)?;
+                content
+            }
+            method => {
+                return Err(invalid(&format!(
+                    "{} 使用了不支持的压缩方式 {}",
+                    name, method
+                )));
+            }
+        };
+        entries.insert(name, content);
+    }
+    Ok(entries)
+}
+
+/// 去掉压缩包内路径前的 script-output 等外层文件夹，只保留相对于导出目录的部分
+fn relative_path(name: &str) -> &str {
+    match name.rfind("script-output/") {
+        Some(pos) => &name[pos + "script-output/".len()..],
+        None => name,
+    }
+}
+
+/// 从压缩包中读取各类别的翻译，没有任何翻译文件时返回 None
+fn locale_from_entries(
+    entries: &IndexMap<String, Vec<u8>>,
+) -> Result<Option<LocaleTable>, AppError> {
+    let mut table = LocaleTable::default();
+    let mut found = false;
+    for category in LOCALE_CATEGORIES {
+        let file_name = format!("{}-locale.json", category);
+        let content = entries
+            .iter()
+            .find(|(name, _)| name.rsplit('/').next() == Some(file_name.as_str()))
+            .map(|(_, content)| content);
+        let mut values: Dict<Dict<String>> = Dict::new();
+        if let Some(content) = content {
+            values = serde_json::from_slice(content)?;
+            found = true;
+        }
+        table.names.insert(
+            category.to_string(),
+            values.get("names").cloned().unwrap_or_default(),
+        );
+        table.descriptions.insert(
+            category.to_string(),
+            values.get("descriptions").cloned().unwrap_or_default(),
+        );
+    }
+    Ok(found.then_some(table))
+}
+
+/// 上传数据构建出的上下文与需要放入内存的图标
+pub struct UploadedContext {
+    pub ctx: FactorioContext,
+    /// 图标的虚拟路径与文件内容
+    pub icons: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl FactorioContext {
+    /// 从上传的原始数据与图标压缩包构建上下文，原始数据为空时从压缩包中寻找
+    pub fn load_from_upload(
+        raw_dump: Option<&[u8]>,
+        archive: Option<&[u8]>,
+    ) -> Result<UploadedContext, ContextLoadError> {
+        let entries = archive
+            .map(read_zip_entries)
+            .transpose()?
+            .unwrap_or_default();
+        let raw_dump = raw_dump
+            .or_else(|| {
+                entries
+                    .iter()
+                    .find(|(name, _)| relative_path(name) == "data-raw-dump.json")
+                    .map(|(_, content)| content.as_slice())
+            })
+            .ok_or_else(|| {
+                ContextLoadError::Other(
+                    "没有上传 data-raw-dump.json，压缩包中也没有找到".to_string(),
+                )
+            })?;
+        let mut ctx = FactorioContext::load(raw_dump)?;
+        let icon_root = PathBuf::from(UPLOAD_ICON_ROOT);
+        ctx.icon_path = icon_root.clone();
+        // 压缩包中的翻译不带语言信息，与本地加载时一样默认记为 zh-CN
+        if let Some(table) = locale_from_entries(&entries)? {
+            ctx.locales.insert("zh-CN".to_string(), table);
+            ctx.set_locale("zh-CN");
+        }
+        let icons = entries
+            .into_iter()
+            .filter(|(name, _)| name.ends_with(".png"))
+            .map(|(name, content)| (icon_root.join(relative_path(&name)), content))
+            .collect();
+        Ok(UploadedContext { ctx, icons })
+    }
+}
+
+/// 生成测试用的 zip，偶数下标的文件用 deflate 压缩
+#[cfg(test)]
+fn build_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    use std::io::Write;
+    let mut data = vec![];
+    let mut directory = vec![];
+    for (idx, (name, content)) in files.iter().enumerate() {
+        let (method, stored) = if idx % 2 == 0 {
+            let mut encoder =
+                flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+            encoder.write_all(content).unwrap();
+            (8u16, encoder.finish().unwrap())
+        } else {
+            (0u16, content.to_vec())
+        };
+        let local = data.len() as u32;
+        data.extend(0x04034b50u32.to_le_bytes());
+        data.extend([0; 4]);
+        data.extend(method.to_le_bytes());
+        data.extend([0; 8]);
+        data.extend((stored.len() as u32).to_le_bytes());
+        data.extend((content.len() as u32).to_le_bytes());
+        data.extend((name.len() as u16).to_le_bytes());
+        data.extend([0; 2]);
+        data.extend(name.as_bytes());
+        data.extend(&stored);
+
+        directory.extend(0x02014b50u32.to_le_bytes());
+        directory.extend([0; 6]);
+        directory.extend(method.to_le_bytes());
+        directory.extend([0; 8]);
+        directory.extend((stored.len() as u32).to_le_bytes());
+        directory.extend((content.len() as u32).to_le_bytes());
+        directory.extend((name.len() as u16).to_le_bytes());
+        directory.extend([0; 12]);
+        directory.extend(local.to_le_bytes());
+        directory.extend(name.as_bytes());
+    }
+    let directory_offset = data.len() as u32;
+    let directory_len = directory.len() as u32;
+    data.extend(directory);
+    data.extend(0x06054b50u32.to_le_bytes());
+    data.extend([0; 4]);
+    data.extend((files.len() as u16).to_le_bytes());
+    data.extend((files.len() as u16).to_le_bytes());
+    data.extend(directory_len.to_le_bytes());
+    data.extend(directory_offset.to_le_bytes());
+    data.extend([0; 2]);
+    data
+}
+
+#[test]
+fn test_read_zip_entries() {
+    let zip = build_zip(&[
+        ("script-output/item/iron-plate.png", b"png bytes"),
+        (
+            "script-output/item-locale.json",
+            br#"{"names":{"iron-plate":"Iron"}}"#,
+        ),
+    ]);
+    let entries = read_zip_entries(&zip).unwrap();
+    assert_eq!(entries["script-output/item/iron-plate.png"], b"png bytes");
+    assert_eq!(
+        relative_path("script-output/item/iron-plate.png"),
+        "item/iron-plate.png"
+    );
+    let table = locale_from_entries(&entries).unwrap().unwrap();
+    assert_eq!(table.names["item"]["iron-plate"], "Iron");
+    assert!(table.names["fluid"].is_empty());
+    assert!(read_zip_entries(b"not a zip").is_err());
+    // 损坏的长度与偏移量只会报错，不会越界
+    let mut broken = zip.clone();
+    let end = broken.len() - 22;
+    let directory = u32::from_le_bytes(broken[end + 16..end + 20].try_into().unwrap()) as usize;
+    broken[directory + 20..directory + 24].copy_from_slice(&u32::MAX.to_le_bytes());
+    broken[directory + 28..directory + 30].copy_from_slice(&u16::MAX.to_le_bytes());
+    assert!(read_zip_entries(&broken).is_err());
+}
+
+#[test]
+fn test_load_from_upload() {
+    let raw = std::fs::read("assets/data-raw-dump.json").unwrap();
+    let zip = build_zip(&[
+        ("factorio/script-output/data-raw-dump.json", &raw),
+        ("factorio/script-output/item/iron-plate.png", b"png bytes"),
+    ]);
+    let uploaded = FactorioContext::load_from_upload(None, Some(&zip)).unwrap();
+    assert!(uploaded.ctx.items.contains_key("iron-plate"));
+    assert_eq!(uploaded.ctx.icon_path, PathBuf::from(UPLOAD_ICON_ROOT));
+    assert_eq!(
+        uploaded.icons,
+        vec![(
+            PathBuf::from(UPLOAD_ICON_ROOT).join("item/iron-plate.png"),
+            b"png bytes".to_vec()
+        )]
+    );
+    assert!(matches!(
+        FactorioContext::load_from_upload(None, None),
+        Err(ContextLoadError::Other(_))
+    ));
+}
@@ -0,0 +1,164 @@
+use indexmap::IndexSet;
+
+use crate::factorio::model::context::*;
+
+/// 工厂可用的机器，游戏前期只有少数机器时用来限制机器选择器与推荐的范围。
+/// 只约束制造设备、采矿设备与研究中心，其余实体不受影响
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct MachineWhitelist {
+    /// 允许使用的机器，None 表示全部可用
+    pub allowed: Option<IndexSet<String>>,
+}
+
+impl MachineWhitelist {
+    /// 受白名单约束的机器，按实体的排序字段排列
+    pub fn machines(ctx: &FactorioContext) -> Vec<&String> {
+        let mut machines = ctx
+            .crafters
+            .keys()
+            .chain(ctx.miners.keys())
+            .chain(ctx.labs.keys())
+            .collect::<Vec<_>>();
+        machines.sort_by_cached_key(|name| {
+            (
+                ctx.entities
+                    .get(*name)
+                    .map(|entity| entity.base.order.clone())
+                    .unwrap_or_default(),
+                name.to_string(),
+            )
+        });
+        machines.dedup();
+        machines
+    }
+
+    pub fn is_allowed(&self, machine: &str) -> bool {
+        self.allowed
+            .as_ref()
+            .is_none_or(|allowed| allowed.contains(machine))
+    }
+
+    #[cfg(feature = "gui")]
+    fn memory_id() -> egui::Id {
+        egui::Id::new("machine-whitelist")
+    }
+
+    /// 设置之后绘制的机器选择器所用的白名单，由工厂在绘制前设置
+    #[cfg(feature = "gui")]
+    pub fn publish(&self, ctx: &egui::Context) {
+        ctx.data_mut(|data| data.insert_temp(Self::memory_id(), self.clone()));
+    }
+
+    /// 当前工厂的白名单，没有工厂设置时视为全部可用
+    #[cfg(feature = "gui")]
+    pub fn current(ctx: &egui::Context) -> Self {
+        ctx.data(|data| data.get_temp(Self::memory_id()))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "gui")]
+pub struct MachineWhitelistEditor<'a> {
+    pub whitelist: &'a mut MachineWhitelist,
+    pub ctx: &'a FactorioContext,
+    pub changed: Option<&'a mut bool>,
+}
+
+#[cfg(feature = "gui")]
+impl<'a> MachineWhitelistEditor<'a> {
+    pub fn new(ctx: &'a FactorioContext, whitelist: &'a mut MachineWhitelist) -> Self {
+        Self {
+            whitelist,
+            ctx,
+            changed: None,
+        }
+    }
+
+    pub fn notify_change(mut self, changed: &'a mut bool) -> Self {
+        self.changed = Some(changed);
+        self
+    }
+}
+
+#[cfg(feature = "gui")]
+impl egui::Widget for MachineWhitelistEditor<'_> {
+    fn ui(mut self, ui: &mut egui::Ui) -> egui::Response {
+        let mut changed = false;
+        let title = match &self.whitelist.allowed {
+            Some(allowed) => format!("可用机器（{} 种）", allowed.len()),
+            None => "可用机器".to_string(),
+        };
+        egui::CollapsingHeader::new(title)
+            .id_salt("machine-whitelist")
+            .show(ui, |ui| {
+                let mut all = self.whitelist.allowed.is_none();
+                if ui
+                    .checkbox(&mut all, "全部可用")
+                    .on_hover_text("取消后只能选择、推荐勾选的制造设备、采矿设备与研究中心")
+                    .changed()
+                {
+                    // 从全部可用开始，逐个取消尚未解锁的机器
+                    self.whitelist.allowed = (!all).then(|| {
+                        MachineWhitelist::machines(self.ctx)
+                            .into_iter()
+                            .cloned()
+                            .collect()
+                    });
+                    changed = true;
+                }
+                let Some(allowed) = &mut self.whitelist.allowed else {
+                    return;
+                };
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for machine in MachineWhitelist::machines(self.ctx) {
+                            let mut enabled = allowed.contains(machine);
+                            if ui
+                                .checkbox(
+                                    &mut enabled,
+                                    self.ctx.get_display_name("entity", machine),
+                                )
+                                .changed()
+                            {
+                                if enabled {
+                                    allowed.insert(machine.clone());
+                                } else {
+                                    allowed.shift_remove(machine);
+                                }
+                                changed = true;
+                            }
+                        }
+                    });
+            });
+        if changed && let Some(flag) = &mut self.changed {
+            **flag = true;
+        }
+        ui.response().clone()
+    }
+}
+
+#[test]
+fn test_machine_whitelist() {
+    let ctx = FactorioContext::test_load();
+    let machines = MachineWhitelist::machines(&ctx);
+    assert!(machines.contains(&&"assembling-machine-1".to_string()));
+    assert!(machines.contains(&&"electric-mining-drill".to_string()));
+    assert!(machines.contains(&&"lab".to_string()));
+    let mut whitelist = MachineWhitelist::default();
+    assert!(whitelist.is_allowed("assembling-machine-3"));
+    whitelist.allowed = Some(
+        ["assembling-machine-1", "stone-furnace"]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+    );
+    assert!(whitelist.is_allowed("stone-furnace"));
+    assert!(!whitelist.is_allowed("assembling-machine-3"));
+    let serialized = serde_json::to_string(&whitelist).unwrap();
+    assert_eq!(
+        serde_json::from_str::<MachineWhitelist>(&serialized).unwrap(),
+        whitelist
+    );
+}
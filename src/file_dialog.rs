@@ -0,0 +1,34 @@
+//! 原生文件对话框。
+//!
+//! 网页版不能访问本地文件系统，rfd 也只提供异步的上传对话框，这里提供同名的替身，
+//! 选择文件时总是返回 None，依赖文件路径的导入导出在网页版中不起作用。
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use rfd::FileDialog;
+
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Clone, Default)]
+pub struct FileDialog;
+
+#[cfg(target_arch = "wasm32")]
+impl FileDialog {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn add_filter(self, _name: impl Into<String>, _extensions: &[impl ToString]) -> Self {
+        self
+    }
+
+    pub fn set_file_name(self, _file_name: impl Into<String>) -> Self {
+        self
+    }
+
+    pub fn pick_file(self) -> Option<std::path::PathBuf> {
+        None
+    }
+
+    pub fn save_file(self) -> Option<std::path::PathBuf> {
+        None
+    }
+}
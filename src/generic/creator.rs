@@ -0,0 +1,275 @@
+use std::sync::mpsc::Sender;
+
+use crate::{
+    concept::*,
+    generic::{GenericGameContext, GenericPlannerView, GenericRecipe},
+    i18n::{tr, trf},
+};
+
+/// 自定义游戏的上下文创建界面，可以手动编辑物品与配方，也可以从 CSV 导入
+pub struct GenericContextCreatorView {
+    ctx: GenericGameContext,
+    csv: String,
+    new_item: String,
+    new_recipe: String,
+    message: Option<String>,
+    error: Option<String>,
+    subview_sender: Option<Sender<Box<dyn Subview>>>,
+}
+
+impl Default for GenericContextCreatorView {
+    fn default() -> Self {
+        Self {
+            ctx: GenericGameContext::new(tr("generic.default_name")),
+            csv: String::new(),
+            new_item: String::new(),
+            new_recipe: String::new(),
+            message: None,
+            error: None,
+            subview_sender: None,
+        }
+    }
+}
+
+impl GenericContextCreatorView {
+    fn import_csv(&mut self, text: &str) {
+        match GenericGameContext::from_csv(self.ctx.name.clone(), text) {
+            Ok(ctx) => {
+                self.message = Some(trf(
+                    "generic.imported",
+                    &[&ctx.items.len(), &ctx.recipes.len()],
+                ));
+                self.ctx = ctx;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("{:?}", e)),
+        }
+    }
+
+    fn file_view(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label(tr("generic.name"));
+            ui.text_edit_singleline(&mut self.ctx.name);
+            if ui.button(tr("generic.save")).clicked() {
+                match self.ctx.save() {
+                    Ok(path) => {
+                        self.message = Some(trf("generic.saved", &[&path.display()]));
+                        self.error = None;
+                    }
+                    Err(e) => self.error = Some(format!("{:?}", e)),
+                }
+            }
+            ui.menu_button(tr("generic.load"), |ui| {
+                let saved = GenericGameContext::list_saved();
+                if saved.is_empty() {
+                    ui.label(tr("generic.no_saved"));
+                }
+                for path in saved {
+                    let label = path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    if ui.button(label).clicked() {
+                        match GenericGameContext::load(&path) {
+                            Ok(ctx) => {
+                                self.ctx = ctx;
+                                self.error = None;
+                            }
+                            Err(e) => self.error = Some(format!("{:?}", e)),
+                        }
+                    }
+                }
+            });
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui.button(tr("generic.import_file")).clicked()
+                && let Some(path) = crate::file_dialog::FileDialog::new()
+                    .add_filter("CSV", &["csv"])
+                    .pick_file()
+            {
+                match std::fs::read_to_string(&path) {
+                    Ok(text) => self.import_csv(&text),
+                    Err(e) => self.error = Some(format!("{}: {}", path.display(), e)),
+                }
+            }
+        });
+    }
+
+    fn csv_view(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(tr("generic.csv"))
+            .id_salt("generic_csv")
+            .show(ui, |ui| {
+                ui.label(trf("generic.csv_hint", &[&GenericGameContext::CSV_HEADER]));
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.csv)
+                        .code_editor()
+                        .desired_rows(6)
+                        .desired_width(f32::INFINITY),
+                );
+                ui.horizontal(|ui| {
+                    if ui.button(tr("generic.import_csv")).clicked() {
+                        let text = self.csv.clone();
+                        self.import_csv(&text);
+                    }
+                    if ui.button(tr("generic.export_csv")).clicked() {
+                        self.csv = self.ctx.to_csv();
+                    }
+                });
+            });
+    }
+
+    fn items_view(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(trf("generic.items", &[&self.ctx.items.len()]))
+            .id_salt("generic_items")
+            .default_open(true)
+            .show(ui, |ui| {
+                let mut removed = None;
+                ui.horizontal_wrapped(|ui| {
+                    for item in &self.ctx.items {
+                        if ui
+                            .button(format!("{} ✖", item))
+                            .on_hover_text(tr("generic.remove_item"))
+                            .clicked()
+                        {
+                            removed = Some(item.clone());
+                        }
+                    }
+                });
+                if let Some(item) = removed {
+                    self.ctx.items.shift_remove(&item);
+                    for recipe in &mut self.ctx.recipes {
+                        recipe.items.shift_remove(&item);
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.new_item);
+                    let name = self.new_item.trim().to_string();
+                    if ui
+                        .add_enabled(
+                            !name.is_empty() && !self.ctx.items.contains(&name),
+                            egui::Button::new(tr("generic.add_item")),
+                        )
+                        .clicked()
+                    {
+                        self.ctx.items.insert(name);
+                        self.new_item.clear();
+                    }
+                });
+            });
+    }
+
+    fn recipes_view(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(trf("generic.recipes", &[&self.ctx.recipes.len()]))
+            .id_salt("generic_recipes")
+            .default_open(true)
+            .show(ui, |ui| {
+                let items = self.ctx.items.clone();
+                let mut removed = None;
+                for (idx, recipe) in self.ctx.recipes.iter_mut().enumerate() {
+                    ui.push_id(idx, |ui| {
+                        ui.group(|ui| {
+                            ui.horizontal_wrapped(|ui| {
+                                ui.text_edit_singleline(&mut recipe.name);
+                                ui.label(tr("generic.time"));
+                                ui.add(
+                                    egui::DragValue::new(&mut recipe.time)
+                                        .speed(0.05)
+                                        .range(0.001..=f64::INFINITY),
+                                );
+                                ui.label(tr("generic.cost"));
+                                ui.add(
+                                    egui::DragValue::new(&mut recipe.cost)
+                                        .speed(0.05)
+                                        .range(0.0..=f64::INFINITY),
+                                );
+                                if ui.button(tr("generic.remove_recipe")).clicked() {
+                                    removed = Some(idx);
+                                }
+                            });
+                            let mut removed_item = None;
+                            for (item, amount) in recipe.items.iter_mut() {
+                                ui.horizontal(|ui| {
+                                    ui.add(egui::DragValue::new(amount).speed(0.1));
+                                    ui.label(item);
+                                    if ui.small_button("✖").clicked() {
+                                        removed_item = Some(item.clone());
+                                    }
+                                });
+                            }
+                            if let Some(item) = removed_item {
+                                recipe.items.shift_remove(&item);
+                            }
+                            ui.menu_button(tr("generic.add_ingredient"), |ui| {
+                                for item in &items {
+                                    if !recipe.items.contains_key(item) && ui.button(item).clicked()
+                                    {
+                                        recipe.items.insert(item.clone(), -1.0);
+                                    }
+                                }
+                            });
+                        });
+                    });
+                }
+                if let Some(idx) = removed {
+                    self.ctx.recipes.remove(idx);
+                }
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.new_recipe);
+                    let name = self.new_recipe.trim().to_string();
+                    if ui
+                        .add_enabled(
+                            !name.is_empty() && self.ctx.recipe(&name).is_none(),
+                            egui::Button::new(tr("generic.add_recipe")),
+                        )
+                        .clicked()
+                    {
+                        self.ctx.recipes.push(GenericRecipe::new(name));
+                        self.new_recipe.clear();
+                    }
+                });
+                ui.label(tr("generic.amount_hint"));
+            });
+    }
+}
+
+impl Subview for GenericContextCreatorView {
+    fn view(&mut self, ui: &mut egui::Ui) {
+        ui.vertical_centered(|ui| {
+            ui.heading(tr("generic.title"));
+        });
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            self.file_view(ui);
+            self.csv_view(ui);
+            self.items_view(ui);
+            self.recipes_view(ui);
+            ui.separator();
+            if ui
+                .add_enabled(
+                    !self.ctx.recipes.is_empty(),
+                    egui::Button::new(tr("generic.open_planner")),
+                )
+                .clicked()
+                && let Some(sender) = &self.subview_sender
+            {
+                self.ctx.collect_items();
+                let _ = sender.send(Box::new(GenericPlannerView::new(self.ctx.clone())));
+            }
+            if let Some(message) = &self.message {
+                ui.label(message);
+            }
+            if let Some(error) = &self.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        });
+    }
+
+    fn name(&self) -> String {
+        tr("generic.title").to_string()
+    }
+}
+
+impl GameContextCreatorView for GenericContextCreatorView {
+    fn set_subview_sender(&mut self, sender: Sender<Box<dyn Subview>>) {
+        self.subview_sender = Some(sender);
+    }
+}
@@ -0,0 +1,11 @@
+#[cfg(feature = "gui")]
+mod creator;
+mod model;
+#[cfg(feature = "gui")]
+mod planner;
+
+#[cfg(feature = "gui")]
+pub use creator::*;
+pub use model::*;
+#[cfg(feature = "gui")]
+pub use planner::*;
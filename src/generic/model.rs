@@ -0,0 +1,361 @@
+use std::path::{Path, PathBuf};
+
+use indexmap::{IndexMap, IndexSet};
+
+use crate::{
+    concept::*, dyn_serde::save_to_file, error::AppError, factorio::get_workding_directory,
+};
+
+fn default_one() -> f64 {
+    1.0
+}
+
+/// 手动定义的配方，物品数量为每次制作的数量，负数为消耗
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GenericRecipe {
+    pub name: String,
+    /// 每次制作的秒数
+    #[serde(default = "default_one")]
+    pub time: f64,
+    pub items: IndexMap<String, f64>,
+    /// 求解时每台机器的代价
+    #[serde(default = "default_one")]
+    pub cost: f64,
+}
+
+impl GenericRecipe {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            time: 1.0,
+            items: IndexMap::new(),
+            cost: 1.0,
+        }
+    }
+
+    /// 一台机器每秒的物料流
+    pub fn flow(&self) -> Flow<String> {
+        if self.time <= 0.0 {
+            return Flow::new();
+        }
+        self.items
+            .iter()
+            .map(|(item, amount)| (item.clone(), amount / self.time))
+            .collect()
+    }
+}
+
+/// 非异星工厂游戏的上下文，物品与配方全部由用户定义
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GenericGameContext {
+    pub name: String,
+    pub items: IndexSet<String>,
+    pub recipes: Vec<GenericRecipe>,
+}
+
+/// 按 RFC 4180 拆分一行 CSV，支持双引号包裹与转义
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+        .into_iter()
+        .map(|field| field.trim().to_string())
+        .collect()
+}
+
+fn quote_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl GenericGameContext {
+    pub const CSV_HEADER: &str = "recipe,time,item,amount,cost";
+
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn recipe(&self, name: &str) -> Option<&GenericRecipe> {
+        self.recipes.iter().find(|recipe| recipe.name == name)
+    }
+
+    /// 把配方中出现但未登记的物品加入物品列表
+    pub fn collect_items(&mut self) {
+        for recipe in &self.recipes {
+            for item in recipe.items.keys() {
+                if !self.items.contains(item) {
+                    self.items.insert(item.clone());
+                }
+            }
+        }
+    }
+
+    /// 从 CSV 导入，第一行为表头，必须包含 recipe、item、amount 列，time 与 cost 列可选。
+    /// 每行为配方的一种物品，同名配方的各行合并，time 与 cost 取该配方第一个非空的值。
+    pub fn from_csv(name: impl Into<String>, text: &str) -> Result<Self, AppError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let (_, header) = lines
+            .next()
+            .ok_or_else(|| AppError::Custom("CSV 为空".to_string()))?;
+        let header = split_csv_line(header)
+            .into_iter()
+            .map(|field| field.to_lowercase())
+            .collect::<Vec<_>>();
+        let column = |name: &str| header.iter().position(|field| field == name);
+        let (Some(recipe_col), Some(item_col), Some(amount_col)) =
+            (column("recipe"), column("item"), column("amount"))
+        else {
+            return Err(AppError::Custom(format!(
+                "CSV 表头缺少 recipe、item 或 amount 列，应为 {}",
+                Self::CSV_HEADER
+            )));
+        };
+        let (time_col, cost_col) = (column("time"), column("cost"));
+
+        let mut ctx = Self::new(name);
+        for (line_no, line) in lines {
+            let fields = split_csv_line(line);
+            let field = |col: usize| fields.get(col).map(String::as_str).unwrap_or("");
+            let number = |col: Option<usize>| -> Result<Option<f64>, AppError> {
+                match col.map(field).filter(|value| !value.is_empty()) {
+                    Some(value) => value.parse().map(Some).map_err(|_| {
+                        AppError::Custom(format!("第 {} 行：{} 不是数字", line_no + 1, value))
+                    }),
+                    None => Ok(None),
+                }
+            };
+            let recipe_name = field(recipe_col);
+            let item = field(item_col);
+            if recipe_name.is_empty() || item.is_empty() {
+                return Err(AppError::Custom(format!(
+                    "第 {} 行：配方名与物品名不能为空",
+                    line_no + 1
+                )));
+            }
+            let amount = number(Some(amount_col))?.unwrap_or(0.0);
+            let (time, cost) = (number(time_col)?, number(cost_col)?);
+            let index = match ctx
+                .recipes
+                .iter()
+                .position(|recipe| recipe.name == recipe_name)
+            {
+                Some(index) => index,
+                None => {
+                    let mut recipe = GenericRecipe::new(recipe_name);
+                    recipe.time = time.unwrap_or(1.0);
+                    recipe.cost = cost.unwrap_or(1.0);
+                    ctx.recipes.push(recipe);
+                    ctx.recipes.len() - 1
+                }
+            };
+            *ctx.recipes[index]
+                .items
+                .entry(item.to_string())
+                .or_insert(0.0) += amount;
+        }
+        ctx.collect_items();
+        Ok(ctx)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", Self::CSV_HEADER);
+        for recipe in &self.recipes {
+            for (item, amount) in &recipe.items {
+                csv.push_str(&format!(
+                    "{},{},{},{},{}\n",
+                    quote_csv_field(&recipe.name),
+                    recipe.time,
+                    quote_csv_field(item),
+                    amount,
+                    recipe.cost
+                ));
+            }
+        }
+        csv
+    }
+
+    /// 保存的自定义游戏所在的文件夹
+    pub fn directory() -> PathBuf {
+        get_workding_directory().join("games")
+    }
+
+    pub fn path(&self) -> PathBuf {
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        Self::directory().join(format!("{}.json", name))
+    }
+
+    pub fn save(&self) -> Result<PathBuf, AppError> {
+        let path = self.path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        save_to_file(self, &path)?;
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, AppError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| AppError::Io(format!("读取文件 {} 失败：{}", path.display(), e)))?;
+        serde_json::from_str(&content)
+            .map_err(|e| AppError::Io(format!("解析文件 {} 失败：{}", path.display(), e)))
+    }
+
+    /// 已保存的自定义游戏，按文件名排序
+    pub fn list_saved() -> Vec<PathBuf> {
+        let Ok(entries) = std::fs::read_dir(Self::directory()) else {
+            return vec![];
+        };
+        let mut paths = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    }
+}
+
+/// 自定义游戏中的一组机器，按配方与速度倍率运行
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "generic:recipe")]
+pub struct GenericRecipeConfig {
+    pub recipe: String,
+    /// 机器的速度倍率
+    #[serde(default = "default_one")]
+    pub speed: f64,
+}
+
+impl GenericRecipeConfig {
+    pub fn new(recipe: impl Into<String>) -> Self {
+        Self {
+            recipe: recipe.into(),
+            speed: 1.0,
+        }
+    }
+}
+
+impl SolveContext for GenericRecipeConfig {
+    type GameContext = GenericGameContext;
+    type ItemIdentType = String;
+}
+
+impl AsFlow for GenericRecipeConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        ctx.recipe(&self.recipe)
+            .map(|recipe| {
+                recipe
+                    .flow()
+                    .into_iter()
+                    .map(|(item, amount)| (item, amount * self.speed))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn cost(&self, ctx: &Self::GameContext) -> f64 {
+        ctx.recipe(&self.recipe).map_or(1.0, |recipe| recipe.cost)
+    }
+}
+
+impl EditorView for GenericRecipeConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.strong(&self.recipe);
+            ui.label(crate::i18n::tr("generic.speed"));
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.speed)
+                        .speed(0.01)
+                        .range(0.0..=f64::INFINITY),
+                )
+                .changed();
+            ui.separator();
+            for (item, amount) in self.as_flow(ctx) {
+                ui.label(format!(
+                    "{} {}",
+                    item,
+                    crate::factorio::signed_compact_number(amount)
+                ));
+            }
+        });
+        changed
+    }
+}
+
+#[test]
+fn test_generic_context_csv() {
+    let csv = "Recipe,Time,Item,Amount\n\
+        smelt,2,ore,-1\n\
+        smelt,,plate,1\n\
+        \"gear, small\",1,plate,-2\n\
+        \"gear, small\",1,\"gear \"\"A\"\"\",1\n";
+    let ctx = GenericGameContext::from_csv("test", csv).unwrap();
+    assert_eq!(ctx.recipes.len(), 2);
+    assert_eq!(ctx.recipes[0].time, 2.0);
+    assert_eq!(ctx.recipes[0].flow()["plate"], 0.5);
+    assert_eq!(ctx.recipes[1].name, "gear, small");
+    assert!(ctx.items.contains("gear \"A\""));
+    assert_eq!(ctx.items.len(), 3);
+
+    let round_trip = GenericGameContext::from_csv("test", &ctx.to_csv()).unwrap();
+    assert_eq!(round_trip, ctx);
+    assert!(GenericGameContext::from_csv("test", "name,value\na,1").is_err());
+    assert!(GenericGameContext::from_csv("test", "recipe,item,amount\na,b,x").is_err());
+
+    // 矿石没有来源，求解时视为外部输入
+    let mechanics = [
+        GenericRecipeConfig::new("smelt"),
+        GenericRecipeConfig {
+            recipe: "gear, small".to_string(),
+            speed: 2.0,
+        },
+    ];
+    let flows = mechanics
+        .iter()
+        .enumerate()
+        .map(|(idx, mechanic)| (idx, (mechanic.as_flow(&ctx), mechanic.cost(&ctx))))
+        .collect();
+    let target = IndexMap::from([("gear \"A\"".to_string(), 1.0)]);
+    let (solution, _) = crate::solver::SolverData::new(target, flows)
+        .solve()
+        .unwrap();
+    assert!((solution[&1] - 0.5).abs() < 1e-6);
+    assert!((solution[&0] - 4.0).abs() < 1e-6);
+}
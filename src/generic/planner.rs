@@ -0,0 +1,204 @@
+use indexmap::IndexMap;
+
+use crate::{
+    concept::*,
+    factorio::{compact_number, signed_compact_number},
+    generic::{GenericGameContext, GenericRecipeConfig},
+    i18n::{tr, trf},
+    solver::SolverData,
+};
+
+pub type GenericMechanic = dyn Mechanic<ItemIdentType = String, GameContext = GenericGameContext>;
+
+/// 自定义游戏的规划界面，只有目标、配方与求解结果，不依赖任何异星工厂的数据
+pub struct GenericPlannerView {
+    key: SubviewKey,
+    ctx: GenericGameContext,
+    targets: IndexMap<String, f64>,
+    mechanics: IndexMap<MechanicId, Box<GenericMechanic>>,
+    new_target: String,
+    solution: Option<Result<(Flow<MechanicId>, f64), String>>,
+}
+
+impl GenericPlannerView {
+    pub fn new(ctx: GenericGameContext) -> Self {
+        Self {
+            key: SubviewKey::next(),
+            new_target: ctx.items.first().cloned().unwrap_or_default(),
+            ctx,
+            targets: IndexMap::new(),
+            mechanics: IndexMap::new(),
+            solution: None,
+        }
+    }
+
+    fn solve(&mut self) {
+        if self.targets.is_empty() {
+            self.solution = None;
+            return;
+        }
+        let flows = self
+            .mechanics
+            .iter()
+            .map(|(id, mechanic)| (*id, (mechanic.as_flow(&self.ctx), mechanic.cost(&self.ctx))))
+            .collect();
+        self.solution = Some(
+            SolverData::new(self.targets.clone(), flows)
+                .solve()
+                .map_err(|e| format!("{:?}", e)),
+        );
+    }
+
+    /// 按求解结果汇总的物品净流量
+    fn total_flow(&self, counts: &Flow<MechanicId>) -> Flow<String> {
+        let mut total = Flow::new();
+        for (id, mechanic) in &self.mechanics {
+            let count = counts.get(id).cloned().unwrap_or(0.0);
+            for (item, amount) in mechanic.as_flow(&self.ctx) {
+                *total.entry(item).or_insert(0.0) += amount * count;
+            }
+        }
+        total.retain(|_, amount| amount.abs() > 1e-9);
+        total
+    }
+
+    fn targets_view(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.strong(tr("generic.targets"));
+        let mut removed = None;
+        for (item, amount) in self.targets.iter_mut() {
+            ui.horizontal(|ui| {
+                ui.label(item);
+                changed |= ui
+                    .add(egui::DragValue::new(amount).speed(0.1).suffix("/s"))
+                    .changed();
+                if ui.small_button("✖").clicked() {
+                    removed = Some(item.clone());
+                }
+            });
+        }
+        if let Some(item) = removed {
+            self.targets.shift_remove(&item);
+            changed = true;
+        }
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("generic_new_target")
+                .selected_text(&self.new_target)
+                .show_ui(ui, |ui| {
+                    for item in &self.ctx.items {
+                        ui.selectable_value(&mut self.new_target, item.clone(), item);
+                    }
+                });
+            if ui
+                .add_enabled(
+                    !self.new_target.is_empty() && !self.targets.contains_key(&self.new_target),
+                    egui::Button::new(tr("generic.add_target")),
+                )
+                .clicked()
+            {
+                self.targets.insert(self.new_target.clone(), 1.0);
+                changed = true;
+            }
+        });
+        changed
+    }
+
+    fn mechanics_view(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.strong(tr("generic.mechanics"));
+        let counts = match &self.solution {
+            Some(Ok((counts, _))) => counts.clone(),
+            _ => Flow::new(),
+        };
+        let mut removed = None;
+        for (id, mechanic) in self.mechanics.iter_mut() {
+            ui.push_id(id, |ui| {
+                ui.group(|ui| {
+                    ui.horizontal(|ui| {
+                        if let Some(count) = counts.get(id) {
+                            ui.strong(format!("× {}", compact_number(*count)));
+                        }
+                        if ui.small_button("✖").clicked() {
+                            removed = Some(*id);
+                        }
+                    });
+                    changed |= mechanic.editor_view(ui, &self.ctx);
+                });
+            });
+        }
+        if let Some(id) = removed {
+            self.mechanics.shift_remove(&id);
+            changed = true;
+        }
+        ui.menu_button(tr("generic.add_mechanic"), |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for recipe in &self.ctx.recipes {
+                        if ui.button(&recipe.name).clicked() {
+                            self.mechanics.insert(
+                                MechanicId::generate(),
+                                Box::new(GenericRecipeConfig::new(recipe.name.clone())),
+                            );
+                            changed = true;
+                        }
+                    }
+                });
+        });
+        changed
+    }
+
+    fn solution_view(&self, ui: &mut egui::Ui) {
+        match &self.solution {
+            None => {
+                ui.label(tr("generic.no_target"));
+            }
+            Some(Err(error)) => {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+            Some(Ok((counts, cost))) => {
+                ui.label(trf("generic.total_cost", &[&compact_number(*cost)]));
+                ui.strong(tr("generic.total_flow"));
+                for (item, amount) in self.total_flow(counts) {
+                    ui.label(format!("{} {}/s", item, signed_compact_number(amount)));
+                }
+            }
+        }
+    }
+}
+
+impl Subview for GenericPlannerView {
+    fn view(&mut self, ui: &mut egui::Ui) {
+        let mut changed = false;
+        ui.columns(2, |columns| {
+            egui::ScrollArea::vertical()
+                .id_salt("generic_planner_left")
+                .show(&mut columns[0], |ui| {
+                    changed |= self.targets_view(ui);
+                    ui.separator();
+                    changed |= self.mechanics_view(ui);
+                });
+            egui::ScrollArea::vertical()
+                .id_salt("generic_planner_right")
+                .show(&mut columns[1], |ui| self.solution_view(ui));
+        });
+        if changed {
+            self.solve();
+        }
+    }
+
+    fn name(&self) -> String {
+        trf("generic.planner_name", &[&self.ctx.name])
+    }
+
+    fn description(&self) -> String {
+        trf(
+            "generic.planner_description",
+            &[&self.ctx.items.len(), &self.ctx.recipes.len()],
+        )
+    }
+
+    fn key(&self) -> Option<SubviewKey> {
+        Some(self.key)
+    }
+}
@@ -0,0 +1,526 @@
+//! 界面文字的多语言支持。
+//!
+//! 界面文字按消息编号登记在 [`MESSAGES`] 中，绘制时通过 [`tr`] 取当前语言的版本。
+//! 游戏原型的名称由上下文中的翻译数据提供，不在此处登记。
+
+use std::{collections::HashMap, sync::RwLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Language {
+    #[default]
+    Zh,
+    En,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::Zh, Language::En];
+
+    /// 由游戏的语言代码推断界面语言，中文以外的语言都使用英文
+    pub fn from_locale(code: &str) -> Self {
+        if code.starts_with("zh") {
+            Language::Zh
+        } else {
+            Language::En
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Zh => "中文",
+            Language::En => "English",
+        }
+    }
+}
+
+/// 消息编号与各语言的文字，顺序与 [`Language`] 一致
+#[rustfmt::skip]
+pub const MESSAGES: &[(&str, [&str; 2])] = &[
+    ("menu.file", ["文件", "File"]),
+    ("menu.context", ["上下文", "Context"]),
+    ("menu.language", ["语言", "Language"]),
+    ("language.game", ["游戏内容", "Game content"]),
+    ("language.ui", ["界面", "Interface"]),
+    ("language.none_loaded", ["上下文中没有其他语言的翻译", "No other languages loaded in this context"]),
+    ("creator.languages", ["翻译语言", "Languages"]),
+    ("creator.languages_hint", ["第一个选中的语言为默认语言，每种语言都需要额外导出一次翻译数据", "The first selected language is the default; each language needs its own locale dump"]),
+    ("inspector.summary", ["产出 {}，消耗 {}，净 {} {}", "Produced {}, consumed {}, net {} {}"]),
+    ("inspector.producers", ["生产者", "Producers"]),
+    ("inspector.consumers", ["消耗者", "Consumers"]),
+    ("inspector.none", ["无", "None"]),
+    ("inspector.jump_hint", ["跳转到该机制并高亮", "Scroll to and highlight this mechanic"]),
+    ("inspector.filter_hint", ["筛选条件隐藏的机制无法跳转", "Mechanics hidden by the filter cannot be scrolled to"]),
+    ("inspector.total_icon_hint", ["左键：建议配方；右键：替代方案；Shift+左键：检查物料流", "Click: suggestions; right-click: alternatives; Shift+click: inspect flow"]),
+    ("inspector.card_icon_hint", ["左键：检查物料流；右键：建议配方", "Click: inspect flow; right-click: suggestions"]),
+    ("planner.target_set_name", ["目标模式名称", "Target mode name"]),
+    ("planner.new_target_set", ["新建模式", "New mode"]),
+    ("planner.new_target_set_hint", ["复制当前目标作为新的目标模式", "Copy the current targets into a new target mode"]),
+    ("planner.delete_target_set", ["删除模式", "Delete mode"]),
+    ("planner.compare_target_sets", ["对比各模式", "Compare modes"]),
+    ("planner.compare_target_sets_hint", ["分别求解每个目标模式，对比机器数量", "Solve each target mode separately and compare machine counts"]),
+    ("planner.nothing_to_complete", ["没有需要补全的缺口物品", "No missing ingredients to complete"]),
+    ("planner.save_snapshot", ["保存快照", "Save snapshot"]),
+    ("planner.save_snapshot_hint", ["记录当前的求解结果，修改后可与之对比", "Record the current solution to compare against after editing"]),
+    ("planner.snapshot_saved", ["已保存当前方案的快照", "Snapshot of the current plan saved"]),
+    ("planner.compare_snapshot", ["与快照对比", "Compare with snapshot"]),
+    ("planner.build_list", ["建造清单", "Build list"]),
+    ("planner.export_ingame", ["导出到游戏……", "Export to game…"]),
+    ("planner.export_ingame_hint", ["生成配套模组使用的方案字符串与常量运算器蓝图", "Generate a plan string for the companion mod and a constant combinator blueprint"]),
+    ("planner.build_list_hint", ["统计方案所需的建筑与插件，并展开为制作它们的原料", "Count the buildings and modules the plan needs and expand them into ingredients"]),
+    ("planner.variance", ["波动分析", "Variance"]),
+    ("planner.variance_hint", ["按概率、数量范围与品质分布计算产物的波动，显示在平均速率下方", "Show the fluctuation of probabilistic, ranged and quality outputs below the mean rates"]),
+    ("planner.variance_window", ["平滑窗口", "Smoothing window"]),
+    ("planner.variance_item", ["{} 秒内的产量标准差约为 {}，平滑到平均速率约需 {} 的缓冲（99%）", "Output over {} s varies by about {} (std. dev.); smoothing it to the mean rate needs a buffer of about {} (99%)"]),
+    ("planner.ramp_up", ["爬坡估算", "Ramp-up estimate"]),
+    ("planner.ramp_up_hint", ["根据现有库存估算生产一次性数量（如一项研究所需的科技包）需要多长时间", "Estimate how long the plan takes to produce a one-off quantity (e.g. science packs for a research) from current stockpiles"]),
+    ("planner.compare_stats", ["对比游戏统计……", "Compare with game statistics…"]),
+    ("planner.compare_stats_hint", ["导入配套模组导出的生产统计，与当前方案逐项对比", "Import production statistics exported by the companion mod and compare them with the plan"]),
+    ("planner.stats_filter", ["生产统计", "Production statistics"]),
+    ("planner.import_research", ["导入研究进度……", "Import research…"]),
+    ("planner.import_research_hint", ["从游戏导出的研究进度设置加成科技、采矿产能、插件等级与已解锁的品质", "Set bonus technologies, mining productivity, module tiers and unlocked qualities from research exported by the game"]),
+    ("planner.import_research_steps", ["存档无法直接读取。请使用配套模组导出，或在游戏控制台执行下面的命令（会禁用本存档的成就），然后选择游戏数据目录 script-output 下的导出文件。导入会覆盖当前的研究设置。", "Saves cannot be read directly. Export with the companion mod, or run the command below in the game console (this disables achievements for the save), then choose the exported file under script-output in the game data directory. Importing replaces the current research settings."]),
+    ("planner.copy_command", ["复制命令", "Copy command"]),
+    ("planner.choose_export", ["选择导出文件……", "Choose exported file…"]),
+    ("planner.research_filter", ["研究进度", "Research"]),
+    ("planner.research_imported", ["已导入研究进度：{} 项加成科技，采矿产能 {} 级，最高品质 {}", "Research imported: {} bonus technologies, mining productivity level {}, highest quality {}"]),
+    ("planner.research_unknown", ["{} 项科技不在当前游戏上下文中，已忽略", "{} technologies are not in the current game context and were ignored"]),
+    ("common.delete", ["删除", "Delete"]),
+    ("common.copy", ["复制", "Copy"]),
+    ("common.cut", ["剪切", "Cut"]),
+    ("common.duplicate", ["副本", "Duplicate"]),
+    ("planner.unsolved", ["待解", "Unsolved"]),
+    ("planner.targets", ["优化目标", "Targets"]),
+    ("planner.producible_only", ["仅显示可生产的物品", "Only show producible items"]),
+    ("planner.producible_only_hint", ["隐藏没有任何配方或资源能产出的物品，这类物品作为目标通常会导致无解", "Hide items that no recipe or resource produces; such targets usually make the plan infeasible"]),
+    ("item_kind.item", ["物品", "Item"]),
+    ("item_kind.fluid", ["流体", "Fluid"]),
+    ("item_kind.entity", ["实体", "Entity"]),
+    ("planner.select_item", ["选择物品", "Select item"]),
+    ("planner.select_fluid", ["选择流体", "Select fluid"]),
+    ("planner.select_entity", ["选择实体", "Select entity"]),
+    ("planner.copy_hint", ["复制到系统剪贴板，可粘贴到其他工厂或其他程序实例", "Copy to the system clipboard to paste into another factory or app instance"]),
+    ("planner.cut_hint", ["复制到系统剪贴板并删除", "Copy to the system clipboard and delete"]),
+    ("planner.copied", ["已复制到剪贴板", "Copied to clipboard"]),
+    ("planner.copy_all", ["复制全部机制", "Copy all mechanics"]),
+    ("planner.copy_all_hint", ["将本工厂的全部机制复制到系统剪贴板", "Copy every mechanic of this factory to the system clipboard"]),
+    ("planner.copied_all", ["已复制 {} 个机制到剪贴板", "Copied {} mechanics to clipboard"]),
+    ("planner.paste", ["粘贴机制", "Paste mechanics"]),
+    ("planner.paste_hint", ["也可以在没有输入框获得焦点时直接按 Ctrl+V", "You can also press Ctrl+V while no text field is focused"]),
+    ("planner.paste_modal_hint", ["在下方粘贴复制的机制文本", "Paste the copied mechanics text below"]),
+    ("planner.pasted", ["已粘贴 {} 个机制", "Pasted {} mechanics"]),
+    ("planner.paste_skipped", ["跳过了 {} 个无法在当前数据中使用的机制：{}", "Skipped {} mechanics unusable with the current data: {}"]),
+    ("planner.select", ["选择", "Select"]),
+    ("planner.select_hint", ["选中后可批量编辑", "Select for bulk editing"]),
+    ("planner.selected_count", ["已选 {} 个", "{} selected"]),
+    ("planner.select_all", ["全选", "Select all"]),
+    ("planner.clear_selection", ["清除选择", "Clear selection"]),
+    ("planner.bulk_edit", ["批量编辑", "Bulk edit"]),
+    ("planner.bulk_edit_hint", ["为选中的配方统一设置机器、插件或燃料", "Set the machine, modules or fuel of all selected recipes"]),
+    ("planner.bulk_edited", ["已修改 {} 个配方，{} 个配方部分设置不适用而保持原样", "Edited {} recipes; {} kept some settings that did not apply"]),
+    ("planner.oil_balancer", ["原油处理预设", "Oil processing preset"]),
+    ("planner.oil_balancer_hint", ["添加高级原油处理与重油、轻油裂解，并要求重油、轻油全部裂解", "Add advanced oil processing with heavy and light oil cracking, and require heavy and light oil to be fully cracked"]),
+    ("planner.oil_balancer_added", ["已添加 {} 个原油处理机制", "Added {} oil processing mechanics"]),
+    ("planner.oil_balancer_missing", ["当前数据中没有原油处理配方", "No oil processing recipes in the current data"]),
+    ("planner.byproduct_policy", ["副产品策略", "By-product policy"]),
+    ("planner.byproduct_empty", ["没有盈余的物品", "No surplus items"]),
+    ("planner.byproduct_no_sinks", ["没有消耗该物品的机制", "No mechanic consumes this item"]),
+    ("planner.byproduct_sinks_empty", ["至少选择一个消耗机制，否则不限制盈余", "Select at least one sink; until then surplus is allowed"]),
+    ("byproduct.allow", ["允许盈余", "Allow surplus"]),
+    ("byproduct.zero", ["必须用尽", "Must be zero"]),
+    ("byproduct.sinks", ["仅限指定消耗", "Specific sinks only"]),
+    ("planner.target_mode", ["约束", "Constraint"]),
+    ("planner.target_mode_hint", ["最大化时产量需受锁定的机制等条件限制，否则求解结果无界", "Maximizing needs a bound such as locked mechanics, otherwise the result is unbounded"]),
+    ("planner.maximize_unbounded", ["锁定至少一个机制作为产量上限后才能最大化", "Lock at least one mechanic to bound production before maximizing"]),
+    ("target_mode.exact", ["恰好", "Exact"]),
+    ("target_mode.at_least", ["至少", "At least"]),
+    ("target_mode.maximize", ["最大化", "Maximize"]),
+    ("planner.target_rate", ["目标产量", "Target rate"]),
+    ("unit.per_second", ["/秒", "/s"]),
+    ("planner.add_target", ["添加目标产物", "Add target"]),
+    ("planner.add_quality_target", ["添加品质目标", "Add quality target"]),
+    ("planner.expand_quality", ["≥ 品质", "≥ quality"]),
+    ("quality_target.pick_hint", ["点击选择物品", "Click to choose an item"]),
+    ("quality_target.pick_title", ["选择物品", "Choose item"]),
+    ("quality_target.min_quality", ["品质不低于", "Quality at least"]),
+    ("quality_target.total", ["总量", "Total"]),
+    ("quality_target.collapse", ["收起", "Collapse"]),
+    ("quality_target.collapse_hint", ["改为最低品质的单个目标", "Turn into a single target at the minimum quality"]),
+    ("quality_target.share", ["{}：{}{}", "{}: {}{}"]),
+    ("quality_target.share_percent", ["（{}%）", " ({}%)"]),
+    ("planner.expand_quality_hint", ["展开为不低于该品质的各品质目标，共享同一个总量，各品质的分配由求解器决定", "Expand into one target per quality at or above this one, sharing a single total; the solver decides how it is split"]),
+    ("planner.external", ["额外输入", "External inputs"]),
+    ("planner.external_entity_hint", ["⚠️ 指完成机制所消耗的实体资源（主要是矿物），不包括为了完成机制所需要收集的组装机、采矿机、插件塔等。", "⚠️ Entity resources consumed by the mechanics (mostly ores), not the assemblers, mining drills, beacons and so on needed to build them."]),
+    ("planner.mark_external", ["外部供应", "Supplied"]),
+    ("planner.mark_external_hint", ["标记为已在其他地方生产：以零成本加入额外输入，工厂中产出它的机制随之减少，成本可在额外输入中修改", "Mark as produced elsewhere: adds it to the external inputs at zero cost, so the mechanics producing it here shrink; you can change the cost there"]),
+    ("planner.unmark_external_hint", ["已由额外输入供应，点击移除", "Supplied by an external input; click to remove it"]),
+    ("planner.unit_cost", ["单位价值", "Unit cost"]),
+    ("planner.price_table", ["价格表", "Price table"]),
+    ("planner.price_table_none", ["不使用", "None"]),
+    ("planner.price_table_missing", ["价格表 {} 不存在，使用各项自己的单位价值", "Price table {} no longer exists; using each entry's own unit cost"]),
+    ("planner.price_from_table", ["单位价值来自所选价格表，在价格表中修改", "Unit cost comes from the selected price table; edit it there"]),
+    ("planner.edit_price_tables", ["编辑价格表……", "Edit price tables…"]),
+    ("planner.edit_price_tables_hint", ["编辑所有工厂共用的价格表，支持导入与导出", "Edit price tables shared by all factories, with import and export"]),
+    ("unit.cost_second", ["·秒", "·s"]),
+    ("unit.cost_mj", ["/MJ", "/MJ"]),
+    ("planner.grid_import", ["允许电网取电", "Allow grid import"]),
+    ("planner.grid_import_hint", ["电力缺口自动从电网补足，按每兆焦的价格计入总代价，无需添加发电机制或电力额外输入", "Cover electricity deficits from the grid at a price per MJ, without adding generator mechanics or an electricity external input"]),
+    ("planner.grid_draw", ["电网净取电：{}（每秒代价 {}）", "Net grid draw: {} (cost {} per second)"]),
+    ("planner.grid_draw_hint", ["耗电减去本工厂发电后需要从电网取用的功率", "Power drawn from the grid after subtracting this factory's own generation"]),
+    ("planner.add_external", ["添加外部输入", "Add external input"]),
+    ("planner.mechanics", ["游戏机制", "Mechanics"]),
+    ("planner.display_multiplier", ["倍数", "Multiplier"]),
+    ("planner.display_multiplier_hint", ["显示求解得到的倍数与总速率", "Show the solved multiplier and total rates"]),
+    ("planner.display_machine_count", ["机器台数", "Machine count"]),
+    ("planner.display_machine_count_hint", ["显示所需机器台数、最后一台的利用率与每台速率", "Show machines needed, utilization of the last machine and per-machine rates"]),
+    ("planner.machine_count", ["{} 台", "{} machines"]),
+    ("planner.collapse_card", ["折叠为单行摘要", "Collapse to a one-line summary"]),
+    ("planner.expand_card", ["展开完整卡片", "Expand the full card"]),
+    ("planner.collapse_all", ["全部折叠", "Collapse all"]),
+    ("planner.expand_all", ["全部展开", "Expand all"]),
+    ("planner.machine_count_hint", ["求解倍数：{}", "Solved multiplier: {}"]),
+    ("planner.patch_lifetime", ["矿区可开采 {}", "Patch lasts {}"]),
+    ("planner.patch_lifetime_hint", ["按当前方案的采矿机数量计算，每秒消耗 {} 资源；矿区共可产出 {} 个产物", "Based on the planned number of drills, draining {} resource per second; the patch yields {} products in total"]),
+    ("planner.last_machine_utilization", ["末台 {}%", "Last {}%"]),
+    ("tag.menu", ["颜色标签", "Color tag"]),
+    ("tag.none", ["无", "None"]),
+    ("tag.all", ["显示全部工厂", "Show all factories"]),
+    ("tag.filter_hint", ["按颜色标签筛选标签栏中的工厂", "Filter factory tabs by color tag"]),
+    ("tag.red", ["红色", "Red"]),
+    ("tag.orange", ["橙色", "Orange"]),
+    ("tag.yellow", ["黄色", "Yellow"]),
+    ("tag.green", ["绿色", "Green"]),
+    ("tag.blue", ["蓝色", "Blue"]),
+    ("tag.purple", ["紫色", "Purple"]),
+    ("filter.text_hint", ["筛选配方、机器、物品……", "Filter recipes, machines, items…"]),
+    ("filter.deficits", ["有缺口", "Deficits"]),
+    ("filter.deficits_hint", ["只显示消耗了净缺口物品的机制", "Only show mechanics that consume items with a net deficit"]),
+    ("filter.burner_fuel", ["燃烧燃料", "Burns fuel"]),
+    ("filter.burner_fuel_hint", ["只显示使用燃料的机制", "Only show mechanics that burn fuel"]),
+    ("filter.quality", ["高品质", "Quality"]),
+    ("filter.quality_hint", ["只显示涉及普通以上品质的机制", "Only show mechanics involving above-normal quality"]),
+    ("filter.location", ["地点不符", "Wrong location"]),
+    ("filter.location_hint", ["只显示配方或机器不满足工厂所在地点地表条件的机制", "Only show mechanics whose recipe or machine does not meet the surface conditions of the factory location"]),
+    ("filter.clear", ["清除", "Clear"]),
+    ("status.menu", ["状态", "Status"]),
+    ("status.draft", ["草稿", "Draft"]),
+    ("status.final", ["定稿", "Final"]),
+    ("status.needs_update", ["需要更新", "Needs update"]),
+    ("planner.notes", ["备注", "Notes"]),
+    ("planner.notes_hint", ["记录设计思路，例如选择这台机器或这些插件的原因", "Why this design? e.g. the reason for this machine or module choice"]),
+    ("planner.lock", ["锁定台数", "Lock count"]),
+    ("planner.lock_hint", ["固定为给定的台数（如已经建好的机器），求解时只优化其余机制", "Fix this mechanic at the given count (e.g. machines already built) and optimize the rest around it"]),
+    ("planner.back_solve", ["✏ 反推", "✏ Back-solve"]),
+    ("planner.back_solve_hint", ["输入期望的台数，锁定该机制或按比例调整目标", "Type a desired machine count to lock this mechanic or rescale the targets"]),
+    ("planner.back_solve_target", ["调整目标", "Scale targets"]),
+    ("planner.back_solve_lock_mode", ["模式：锁定台数，目标不变，其余机制重新求解", "Mode: lock the count, keep the targets and re-solve the rest"]),
+    ("planner.back_solve_target_mode", ["模式：按比例缩放全部目标，使该机制需要输入的台数，并解除锁定", "Mode: rescale all targets so this mechanic needs the given count, and unlock it"]),
+    ("planner.back_solve_apply", ["应用", "Apply"]),
+    ("planner.back_solve_unavailable", ["该机制不在当前方案中或工厂没有目标，无法按比例调整目标", "This mechanic is not part of the current solution or the factory has no targets, so the targets cannot be rescaled"]),
+    ("planner.rate_unit", ["速率单位", "Rate unit"]),
+    ("planner.rate_unit_global", ["跟随全局设置", "Use global setting"]),
+    ("planner.location", ["地点", "Location"]),
+    ("planner.location_hint", ["工厂所在的星球或太空平台，用于检查配方与机器的地表条件", "The planet or space platform the factory is on, used to check the surface conditions of recipes and machines"]),
+    ("planner.location_any", ["不限", "Any"]),
+    ("planner.route", ["航线", "Route"]),
+    ("planner.route_hint", ["太空平台停留的星球轨道或航行的航线，决定小行星的生成频率", "The planet orbit a space platform stays at or the route it travels, which determines how often asteroids spawn"]),
+    ("planner.asteroid_spawn_rates", ["平均生成频率", "Average spawn rate"]),
+    ("planner.route_cleared", ["航线改为不限，{} 台小行星收集器保留原来的航线，可在卡片上分别选择", "Route cleared; {} asteroid collectors keep their previous route and can be changed on their cards"]),
+    ("planner.location_blocked", ["⚠ 地点不符", "⚠ Wrong location"]),
+    ("planner.requires_heating", ["地表需要供暖", "Surface requires heating"]),
+    ("planner.requires_heating_hint", ["在寒冷的星球上，每台放置的机器都会持续消耗热量，可用加热塔供暖", "On cold planets such as Aquilo every placed machine continuously consumes heat, which heating towers can supply"]),
+    ("planner.scrap_economy", ["废料经济模式", "Scrap economy mode"]),
+    ("planner.scrap_economy_hint", ["所有物品与流体都不允许盈余，多余的产物必须由机制消耗，例如反复回收为自身直至销毁", "No item or fluid may be left over; every byproduct must be consumed by a mechanic, such as recycling it into itself until nothing remains"]),
+    ("planner.recipe_config", ["配方配置", "Recipe configuration"]),
+    ("planner.auto_complete", ["自动补全缺失原料", "Auto-complete missing ingredients"]),
+    ("planner.auto_complete_hint", ["为缺口物品逐个添加生产方式，直到只剩需要开采的原料", "Add producers for each missing item until only raw resources remain"]),
+    ("planner.chain_preference", ["偏好", "Preference"]),
+    ("context.vanilla", ["原版", "Vanilla"]),
+    ("context.add_title", ["添加上下文", "Add context"]),
+    ("context.browse", ["浏览原型数据……", "Browse prototypes…"]),
+    ("context.add", ["添加上下文……", "Add context…"]),
+    ("context.remove", ["移除当前上下文", "Remove current context"]),
+    ("context.remove_disabled_hint", ["至少保留一个上下文，且不能移除仍有工厂使用的上下文", "At least one context must remain, and contexts still used by factories cannot be removed"]),
+    ("planner.new_factory", ["新建工厂", "New factory"]),
+    ("planner.dashboard", ["工厂总览", "Factory dashboard"]),
+    ("planner.dashboard_hint", ["汇总全部打开的工厂的耗电、污染、原料、机器数量与科技包消耗", "Summarize power, pollution, raw resources, machine counts and science across all open factories"]),
+    ("planner.dashboard_missing", ["工厂 {} 已关闭", "Factory {} has been closed"]),
+    ("planner.new_factory_name", ["新工厂", "New factory"]),
+    ("planner.template_gallery", ["示例工厂……", "Example factories…"]),
+    ("planner.template_gallery_hint", ["从内置的示例工厂新建，当前上下文缺少的配方或机器可在新建前替换", "Create a factory from a bundled example; recipes or machines missing from the current context can be substituted first"]),
+    ("planner.template_create", ["新建", "Create"]),
+    ("planner.load_factory", ["从文件加载工厂……", "Load factory from file…"]),
+    ("planner.factory_filter", ["异星工厂规划配置", "Factorio planner config"]),
+    ("planner.export_context", ["导出上下文数据……", "Export context data…"]),
+    ("planner.export_context_hint", ["将配方、机器与插件的数值导出为 JSON，供表格或其他工具使用", "Export recipe, machine and module values as JSON for spreadsheets or other tools"]),
+    ("common.clear", ["清空", "Clear"]),
+    ("planner.clear_solver_cache", ["清空求解缓存", "Clear the solver cache"]),
+    ("context.switch", ["切换上下文", "Switch context"]),
+    ("common.save", ["保存", "Save"]),
+    ("common.save_as", ["另存为……", "Save as…"]),
+    ("common.save_new", ["保存……", "Save…"]),
+    ("notification.jump", ["点击跳转到相关页面", "Click to open the related page"]),
+    ("common.close", ["关闭", "Close"]),
+    ("planner.no_factory", ["没有工厂\n", "No factories\n"]),
+    ("planner.no_factory_hint", ["点击上方的文件菜单新建工厂或加载一个工厂存档。", "Use the File menu above to create a factory or load a saved one."]),
+    ("planner.name", ["异星工厂 - 工厂规划器", "Factorio - Factory planner"]),
+    ("planner.mods_in_use", ["使用以下模组: ", "Mods in use: "]),
+    ("load_error.exe_not_found", ["找不到或无法启动游戏可执行文件 {}", "Game executable not found or could not be started: {}"]),
+    ("load_error.exe_not_found_hint", ["请选择 Factorio 安装目录下 bin/x64 中的 factorio 可执行文件", "Choose the factorio executable under bin/x64 in the Factorio install directory"]),
+    ("load_error.dump_failed", ["{}失败，游戏退出码 {}", "{} failed, game exit code {}"]),
+    ("load_error.dump_failed_hint", ["请确认游戏没有在运行，并且模组与游戏版本匹配。完整日志见 {}", "Make sure the game is not running and the mods match the game version. Full log: {}"]),
+    ("load_error.mod_list", ["无法读取模组列表 {}：{}", "Could not read the mod list {}: {}"]),
+    ("load_error.mod_list_no_mods", ["缺少 mods 字段", "missing the mods field"]),
+    ("load_error.mod_list_hint", ["请选择包含 mod-list.json 的模组文件夹，或在游戏中打开一次模组界面重新生成该文件", "Choose the mods folder containing mod-list.json, or open the mods screen in game once to regenerate it"]),
+    ("load_error.locale_missing", ["游戏没有导出语言 {} 的翻译", "The game exported no translations for language {}"]),
+    ("load_error.locale_missing_hint", ["该语言可能不受游戏或模组支持，可以在语言列表中取消勾选后重试", "The language may not be supported by the game or mods; untick it in the language list and retry"]),
+    ("load_error.cancelled", ["已取消加载", "Loading cancelled"]),
+    ("load_error.output", ["游戏输出", "Game output"]),
+    ("stage.pending", ["等待中", "Pending"]),
+    ("stage.running", ["进行中……", "Running…"]),
+    ("stage.done", ["完成", "Done"]),
+    ("stage.failed", ["失败", "Failed"]),
+    ("creator.cancelling", ["正在取消……", "Cancelling…"]),
+    ("common.cancel", ["取消", "Cancel"]),
+    ("creator.title", ["创建游戏上下文", "Create game context"]),
+    ("creator.select_game", ["选择游戏路径:", "Select the game executable:"]),
+    ("creator.browse", ["浏览...", "Browse..."]),
+    ("creator.steam_hint", ["若为 Steam 版本的游戏，请启动 Steam 再执行加载游戏上下文", "For the Steam version, start Steam before loading the game context"]),
+    ("creator.detected_installs", ["选择检测到的安装", "Choose a detected installation"]),
+    ("creator.no_installs", ["未在常见位置检测到游戏安装", "No installation found in the usual locations"]),
+    ("creator.rescan", ["重新扫描", "Rescan"]),
+    ("install.steam", ["Steam", "Steam"]),
+    ("install.gog", ["GOG", "GOG"]),
+    ("install.portable", ["便携版", "Portable"]),
+    ("install.standalone", ["独立版", "Standalone"]),
+    ("creator.profiles", ["模组配置", "Mod profiles"]),
+    ("creator.no_profile", ["不使用配置", "No profile"]),
+    ("creator.delete_profile", ["删除配置", "Delete profile"]),
+    ("creator.profile_need_mods", ["选择模组路径后可保存为配置", "Select a mod directory to save it as a profile"]),
+    ("creator.profile_name", ["配置名称", "Profile name"]),
+    ("creator.save_profile", ["保存为配置", "Save as profile"]),
+    ("creator.profile_mods_hint", ["勾选的模组会在加载时启用，原文件夹中的 mod-list.json 不会被修改", "Checked mods are enabled when loading; the mod-list.json in the original folder is left untouched"]),
+    ("creator.profile_save_failed", ["保存模组配置失败: {}", "Failed to save mod profiles: {}"]),
+    ("creator.no_game", ["未选择路径", "No path selected"]),
+    ("creator.select_mods", ["选择Mod路径 (可选):", "Select the mod directory (optional):"]),
+    ("creator.no_mods", ["未选择Mod路径", "No mod directory selected"]),
+    ("creator.need_game", ["请选择游戏可执行文件以继续。", "Select the game executable to continue."]),
+    ("creator.no_mod_list", ["模组文件夹下未找到 mod-list.json。", "mod-list.json was not found in the mod directory."]),
+    ("creator.load", ["加载游戏上下文", "Load game context"]),
+    ("creator.load_cancelled", ["已取消加载游戏上下文", "Loading the game context was cancelled"]),
+    ("creator.load_cached", ["加载缓存上下文", "Load cached context"]),
+    ("creator.upload_hint", ["或上传导出的 data-raw-dump.json 与打包成 zip 的 script-output 文件夹（图标与翻译）", "Or upload an exported data-raw-dump.json and the script-output folder zipped (icons and locale)"]),
+    ("creator.upload_raw", ["上传 data-raw-dump.json", "Upload data-raw-dump.json"]),
+    ("creator.upload_icons", ["上传图标压缩包", "Upload icon archive"]),
+    ("creator.upload_none", ["未选择", "Not selected"]),
+    ("creator.upload_load", ["从上传的文件加载", "Load from uploaded files"]),
+    ("creator.load_cached_cancelled", ["已取消加载缓存上下文", "Loading the cached context was cancelled"]),
+    ("stage.dump_data", ["导出原始数据", "Dump raw data"]),
+    ("stage.dump_locale", ["导出翻译数据", "Dump locale"]),
+    ("stage.dump_icons", ["导出图标数据", "Dump icons"]),
+    ("stage.parse", ["解析数据", "Parse data"]),
+    ("app.creator_factorio", ["异星工厂", "Factorio"]),
+    ("app.creator_generic", ["自定义游戏", "Custom game"]),
+    ("app.creator_satisfactory", ["幸福工厂", "Satisfactory"]),
+    ("app.low_power", ["低功耗模式", "Low power mode"]),
+    ("app.low_power_hint", ["空闲时停止持续重绘，有交互或计算结果时再刷新", "Stop repainting while idle; refresh on interaction or new results"]),
+    ("app.tutorial", ["新手引导", "Tutorial"]),
+    ("app.check_update", ["检查更新", "Check for updates"]),
+    ("app.up_to_date", ["当前已是最新版本。", "Already up to date."]),
+    ("app.update", ["更新", "Update"]),
+    ("app.restart_required", ["更新已下载完成，请重启应用以应用更新。", "The update has been downloaded. Restart the app to apply it."]),
+    ("app.restart", ["重启应用", "Restart app"]),
+    ("app.update_failed", ["更新检查失败", "Update check failed"]),
+    ("app.repository", ["Github 仓库", "GitHub repository"]),
+    ("app.creator_hint", ["点击以选择该游戏环境，右键显示额外菜单", "Click to select this game environment, right-click for more"]),
+    ("app.font_license", ["字体协议", "Font license"]),
+    ("app.reload_icons", ["重新加载图标", "Reload icons"]),
+    ("app.settings", ["设置", "Settings"]),
+    ("settings.title", ["设置", "Settings"]),
+    ("settings.description", ["主题、缩放与数值显示", "Theme, zoom and number display"]),
+    ("settings.theme", ["主题", "Theme"]),
+    ("settings.theme_system", ["跟随系统", "System"]),
+    ("settings.theme_dark", ["深色", "Dark"]),
+    ("settings.theme_light", ["浅色", "Light"]),
+    ("settings.zoom_factor", ["界面缩放", "Zoom"]),
+    ("settings.icon_size", ["图标尺寸", "Icon size"]),
+    ("settings.decimal_precision", ["小数位数", "Decimal places"]),
+    ("settings.decimal_precision_hint", ["个位数的数值最多保留的小数位数，数值越大保留越少", "Maximum decimal places for single-digit numbers; larger numbers keep fewer"]),
+    ("settings.rate_unit", ["速率单位", "Rate unit"]),
+    ("settings.rate_unit_hint", ["物料流的显示单位，输入的目标产量仍以每秒计", "Unit for displayed flows; target amounts are still entered per second"]),
+    ("settings.rate_express_belt", [" 条蓝带", " express belts"]),
+    ("settings.si_suffixes", ["单位前缀", "SI suffixes"]),
+    ("settings.si_suffixes_hint", ["较大的数值缩写为 k/M/G，较小的数值使用 μ；关闭时完整显示大数，小数使用科学计数法。悬停数值可查看完整精度", "Abbreviate large numbers with k/M/G and small ones with μ; when off, large numbers are shown in full and small ones in scientific notation. Hover a value to see full precision"]),
+    ("settings.thousands_separator", ["千位分隔符", "Thousands separator"]),
+    ("settings.number_preview", ["数值预览", "Number preview"]),
+    ("settings.exact_rational", ["精确有理数", "Exact rationals"]),
+    ("settings.exact_rational_hint", ["线性规划仍以浮点求解，结果吸附为有理数后精确累加物料流，消除 0.9999999 之类的误差；分母较小的数值显示为分数", "The LP is still solved in floating point, but results are snapped to rationals and flows are summed exactly, removing artifacts like 0.9999999; values with small denominators are shown as fractions"]),
+    ("settings.insert_adjacent", ["新机制插入到来源旁", "Insert next to source"]),
+    ("settings.insert_adjacent_hint", ["从机制卡片的物品建议或复制得到的机制插入到该卡片之后，而不是列表末尾", "Mechanics added from a card's item suggestions or duplicated from a card are inserted right after it instead of at the end"]),
+    ("settings.restore_workspace", ["恢复上次的会话", "Restore last session"]),
+    ("settings.restore_workspace_hint", ["启动时重新打开上次的页面与已保存的工厂，规划器使用缓存的上下文", "Reopen the last pages and saved factories on startup; planners use the cached context"]),
+    ("workspace.restore_failed", ["无法恢复规划器，读取缓存的上下文失败：{}", "Could not restore the planner, failed to read the cached context: {}"]),
+    ("settings.reset", ["恢复默认", "Reset to defaults"]),
+    ("settings.saved_to", ["设置已自动保存到 {}", "Settings are saved automatically to {}"]),
+    ("item_kind.heat", ["热量", "Heat"]),
+    ("item_kind.electricity", ["电力", "Electricity"]),
+    ("item_kind.fluid_heat", ["流体热量", "Fluid heat"]),
+    ("item_kind.fluid_fuel", ["流体燃料", "Fluid fuel"]),
+    ("item_kind.item_fuel", ["物体燃料", "Item fuel"]),
+    ("item_kind.payload_weight", ["重量载荷", "Payload weight"]),
+    ("item_kind.payload_stack", ["堆叠载荷", "Payload stack"]),
+    ("item_kind.pollution", ["污染", "Pollution"]),
+    ("item_kind.special", ["特殊", "Special"]),
+    ("common.list_separator", ["、", ", "]),
+    ("planner.completion_added", ["已添加 {} 个机制，剩余 {} 种原料", "Added {} mechanics, {} raw materials remain"]),
+    ("planner.completion_unresolved", ["{} 种物品找不到合适的生产方式：{}", "No suitable producer for {} items: {}"]),
+    ("planner.total_flow", ["总代价: {} | 总物料流（{}）", "Total cost: {} | Total flow ({})"]),
+    ("planner.filtered", ["已筛选：显示 {} / {} 个机制", "Filtered: showing {} / {} mechanics"]),
+    ("planner.target_set_default_name", ["模式 {}", "Mode {}"]),
+    ("context.switched", ["工厂 {} 已切换到上下文 {}", "Factory {} switched to context {}"]),
+    ("context.migration_source", ["工厂 {}", "Factory {}"]),
+    ("context.added", ["已添加上下文 {}", "Context {} added"]),
+    ("context.entry", ["{}（{} 个工厂）", "{} ({} factories)"]),
+    ("context.entry_hint", ["新建或加载的工厂将使用此上下文\n{}", "New and loaded factories use this context\n{}"]),
+    ("planner.restore_skipped", ["{} 需要迁移，恢复会话时已跳过，请手动打开", "{} needs migration and was skipped while restoring the session; open it manually"]),
+    ("planner.save_failed", ["保存 {} 失败：{}", "Failed to save {}: {}"]),
+    ("planner.solve_finished", ["工厂 {} 求解完成，用时 {} 秒", "Factory {} solved in {} s"]),
+    ("planner.solve_failed", ["工厂 {} 求解失败：{}", "Factory {} failed to solve: {}"]),
+    ("planner.context_loaded", ["上下文 {} 加载完成", "Context {} loaded"]),
+    ("planner.factory_loaded", ["从 {} 加载了新工厂", "Loaded a new factory from {}"]),
+    ("planner.read_failed", ["无法读取文件 {}: {}", "Cannot read file {}: {}"]),
+    ("planner.parse_failed", ["无法解析文件 {}: {}", "Cannot parse file {}: {}"]),
+    ("planner.context_exported", ["上下文数据已导出到 {}", "Context data exported to {}"]),
+    ("common.export_failed", ["导出失败：{}", "Export failed: {}"]),
+    ("planner.solver_pool", ["求解线程：{} 个，排队 {} 个", "Solver threads: {}, {} queued"]),
+    ("planner.last_solve", ["上次求解耗时 {} ms{}", "Last solve took {} ms{}"]),
+    ("planner.solver_telemetry", ["已完成 {} 次求解，{} 次过时请求被跳过", "{} solves completed, {} stale requests skipped"]),
+    ("planner.solver_cache", ["求解缓存：命中 {} / 未命中 {}，已缓存 {} 个结果", "Solver cache: {} hits / {} misses, {} results cached"]),
+    ("planner.factory_saved", ["工厂已保存到 {}", "Factory saved to {}"]),
+    ("creator.stage_status", ["{}：{}", "{}: {}"]),
+    ("creator.selected_game", ["已选择路径: {}", "Selected path: {}"]),
+    ("creator.selected_mods", ["已选择Mod路径: {}", "Selected mod directory: {}"]),
+    ("creator.load_failed", ["加载游戏上下文失败: {}", "Failed to load game context: {}"]),
+    ("creator.load_cached_failed", ["加载缓存上下文失败: {}", "Failed to load cached context: {}"]),
+    ("creator.upload_failed", ["从上传的文件创建上下文失败: {}", "Failed to create context from uploaded files: {}"]),
+    ("generic.title", ["自定义游戏", "Custom game"]),
+    ("generic.default_name", ["我的游戏", "My game"]),
+    ("generic.name", ["游戏名称", "Game name"]),
+    ("generic.save", ["保存", "Save"]),
+    ("generic.saved", ["已保存到 {}", "Saved to {}"]),
+    ("generic.load", ["读取已保存的游戏", "Load saved game"]),
+    ("generic.no_saved", ["还没有保存过自定义游戏", "No saved custom games yet"]),
+    ("generic.import_file", ["从 CSV 文件导入", "Import CSV file"]),
+    ("generic.imported", ["已导入 {} 种物品、{} 个配方", "Imported {} items and {} recipes"]),
+    ("generic.csv", ["CSV 导入与导出", "CSV import and export"]),
+    ("generic.csv_hint", ["表头为 {}，每行为配方的一种物品，数量为负表示消耗；time 与 cost 可省略", "Header is {}; each row is one item of a recipe, negative amounts are consumed; time and cost are optional"]),
+    ("generic.import_csv", ["导入", "Import"]),
+    ("generic.export_csv", ["导出", "Export"]),
+    ("generic.items", ["物品（{}）", "Items ({})"]),
+    ("generic.add_item", ["添加物品", "Add item"]),
+    ("generic.remove_item", ["删除该物品，并从所有配方中移除", "Remove this item from the game and all recipes"]),
+    ("generic.recipes", ["配方（{}）", "Recipes ({})"]),
+    ("generic.time", ["耗时（秒）", "Time (s)"]),
+    ("generic.cost", ["代价", "Cost"]),
+    ("generic.remove_recipe", ["删除配方", "Remove recipe"]),
+    ("generic.add_ingredient", ["添加物品", "Add item"]),
+    ("generic.add_recipe", ["添加配方", "Add recipe"]),
+    ("generic.amount_hint", ["数量为每次制作的数量，负数为消耗，正数为产出", "Amounts are per craft: negative is consumed, positive is produced"]),
+    ("generic.open_planner", ["打开规划器", "Open planner"]),
+    ("generic.planner_name", ["规划：{}", "Plan: {}"]),
+    ("generic.planner_description", ["{} 种物品，{} 个配方", "{} items, {} recipes"]),
+    ("generic.targets", ["目标产量", "Targets"]),
+    ("generic.add_target", ["添加目标", "Add target"]),
+    ("generic.mechanics", ["配方", "Recipes"]),
+    ("generic.add_mechanic", ["添加配方", "Add recipe"]),
+    ("generic.speed", ["速度倍率", "Speed"]),
+    ("generic.no_target", ["添加目标后自动求解", "Add a target to solve"]),
+    ("generic.total_cost", ["总代价：{}", "Total cost: {}"]),
+    ("generic.total_flow", ["物料净流量", "Net item flow"]),
+    ("satisfactory.title", ["幸福工厂", "Satisfactory"]),
+    ("satisfactory.docs_hint", ["选择游戏安装目录下 CommunityResources/Docs/ 中的 Docs.json（或 en-US.json）", "Pick Docs.json (or en-US.json) from CommunityResources/Docs/ in the game installation"]),
+    ("satisfactory.pick_docs", ["选择 Docs.json", "Pick Docs.json"]),
+    ("satisfactory.load_cache", ["读取上次的数据", "Load cached data"]),
+    ("satisfactory.loaded", ["已读取 {} 种物品、{} 种生产建筑、{} 个配方", "Loaded {} items, {} production buildings and {} recipes"]),
+    ("satisfactory.open_planner", ["打开规划器", "Open planner"]),
+    ("satisfactory.planner_name", ["幸福工厂规划", "Satisfactory plan"]),
+    ("satisfactory.planner_description", ["{} 种物品，{} 个配方", "{} items, {} recipes"]),
+    ("satisfactory.targets", ["目标产量", "Targets"]),
+    ("satisfactory.add_target", ["添加目标", "Add target"]),
+    ("satisfactory.mechanics", ["配方", "Recipes"]),
+    ("satisfactory.add_mechanic", ["添加配方", "Add recipe"]),
+    ("satisfactory.clock", ["时钟速度", "Clock speed"]),
+    ("satisfactory.no_target", ["添加目标后自动求解", "Add a target to solve"]),
+    ("satisfactory.total_flow", ["物料净流量", "Net item flow"]),
+    ("app.build_hash", ["[构建] Git 哈希: {}", "[Build] Git hash: {}"]),
+    ("app.frame_time", ["[性能] 帧生成时间: {}ms", "[Perf] Frame time: {}ms"]),
+    ("app.version", ["当前版本: {}", "Current version: {}"]),
+    ("app.update_check_failed", ["更新检查失败: {}", "Update check failed: {}"]),
+    ("app.new_version", ["可更新新版本: {}", "New version available: {}"]),
+    ("app.fetch_release_failed", ["获取最新版本失败: {}", "Failed to fetch the latest release: {}"]),
+    ("duration.seconds", ["{} 秒", "{} s"]),
+    ("duration.minutes", ["{} 分 {} 秒", "{} min {} s"]),
+    ("duration.hours", ["{} 小时 {} 分", "{} h {} min"]),
+    ("duration.days", ["{} 天 {} 小时", "{} d {} h"]),
+    ("solver.no_recipe", ["这个物品没有相关配方： {}", "No recipe produces this item: {}"]),
+    ("solver.unbounded", ["无界。存在能够无限产生目标物品且不增加消耗的配方组合。", "Unbounded. Some combination of recipes produces the targets indefinitely without extra consumption."]),
+    ("solver.maximize_unbounded", ["无界。最大化的目标没有上限，请锁定限制产量的机制。", "Unbounded. The maximized target has no upper bound; lock a mechanic that limits production."]),
+    ("solver.infeasible", ["无解。不存在能够满足目标物品需求的配方组合。", "Infeasible. No combination of recipes satisfies the target demand."]),
+    ("solver.unknown_error", ["求解过程中发生未知错误。", "An unknown error occurred while solving."]),
+    ("solver.internal_error", ["求解过程中发生内部错误：{}", "An internal error occurred while solving: {}"]),
+];
+
+lazy_static::lazy_static! {
+    static ref LANGUAGE: RwLock<Language> = RwLock::new(Language::default());
+    static ref TABLE: HashMap<&'static str, [&'static str; 2]> = MESSAGES.iter().cloned().collect();
+}
+
+pub fn language() -> Language {
+    *LANGUAGE.read().unwrap()
+}
+
+pub fn set_language(language: Language) {
+    *LANGUAGE.write().unwrap() = language;
+}
+
+/// 取消息在当前语言下的文字，未登记的编号原样返回
+pub fn tr(id: &'static str) -> &'static str {
+    match TABLE.get(id) {
+        Some(texts) => texts[language() as usize],
+        None => id,
+    }
+}
+
+/// 取消息在当前语言下的文字，并依次用参数替换其中的 `{}`
+pub fn trf(id: &'static str, args: &[&dyn std::fmt::Display]) -> String {
+    let mut parts = tr(id).split("{}");
+    let mut result = parts.next().unwrap_or_default().to_string();
+    let mut args = args.iter();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            result.push_str(&arg.to_string());
+        }
+        result.push_str(part);
+    }
+    result
+}
+
+#[test]
+fn test_messages() {
+    let mut ids = std::collections::HashSet::new();
+    for (id, texts) in MESSAGES {
+        assert!(ids.insert(*id), "重复的消息编号 {}", id);
+        assert!(texts.iter().all(|text| !text.is_empty()), "{} 缺少翻译", id);
+        assert!(
+            texts
+                .iter()
+                .all(|text| text.matches("{}").count() == texts[0].matches("{}").count()),
+            "{} 的各语言占位符数量不一致",
+            id
+        );
+    }
+    assert_eq!(Language::from_locale("zh-TW"), Language::Zh);
+    assert_eq!(Language::from_locale("de"), Language::En);
+    assert_eq!(
+        trf("planner.filtered", &[&3, &5]),
+        "已筛选：显示 3 / 5 个机制"
+    );
+}
@@ -0,0 +1,28 @@
+//! 规划器的核心：游戏上下文模型、各类生产机制与求解器。
+//!
+//! 关闭默认的 `gui` 特性时不依赖 egui，可供命令行工具、网络服务或测试直接链接：
+//! 用 [`factorio::FactorioContext`] 读取导出的原型数据，以 [`concept::Mechanic`]
+//! 描述生产机制，再交给 [`solver::SolverData`] 求解。界面、编辑器与自动更新等模块
+//! 只在开启 `gui` 特性时编译。
+
+pub mod concept;
+pub mod dyn_serde;
+pub mod error;
+pub mod factorio;
+#[cfg(feature = "gui")]
+pub mod file_dialog;
+pub mod generic;
+pub mod i18n;
+pub mod rational;
+pub mod repaint;
+pub mod satisfactory;
+pub mod settings;
+pub mod solver;
+#[cfg(feature = "gui")]
+pub mod toast;
+#[cfg(feature = "gui")]
+pub mod tutorial;
+#[cfg(all(feature = "gui", not(target_arch = "wasm32")))]
+pub mod update;
+#[cfg(feature = "gui")]
+pub mod workspace;
@@ -1,21 +1,23 @@
+#[cfg(not(target_arch = "wasm32"))]
 use mimalloc::MiMalloc;
 
-use crate::update::*;
+use metatorio_core::{
+    concept, factorio, generic, i18n, repaint, satisfactory, settings, toast, tutorial, workspace,
+};
 
+#[cfg(not(target_arch = "wasm32"))]
+use metatorio_core::error;
+
+#[cfg(not(target_arch = "wasm32"))]
+use metatorio_core::update::{self, *};
+
+#[cfg(not(target_arch = "wasm32"))]
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
 // Git 版本信息
 include!(concat!(env!("OUT_DIR"), "/git_hash.rs"));
 
-pub mod concept;
-pub mod dyn_serde;
-pub mod error;
-pub mod factorio;
-pub mod solver;
-pub mod toast;
-pub mod update;
-
 pub struct MainPage {
     pub creators: Vec<(String, Box<dyn concept::GameContextCreatorView>)>,
     pub subviews: Vec<Box<dyn concept::Subview>>,
@@ -26,12 +28,29 @@ pub struct MainPage {
 
     pub exp_cpu_usage: f32,
 
+    /// 空闲时停止持续重绘，仅在交互或后台消息到达时刷新
+    pub low_power: bool,
+    pub idle_tracker: repaint::IdleTracker,
+
+    /// 网页版没有自动更新
+    #[cfg(not(target_arch = "wasm32"))]
     pub suitable_release: Result<self_update::update::Release, error::AppError>,
+    #[cfg(not(target_arch = "wasm32"))]
     pub response_receiver:
         std::sync::mpsc::Receiver<Result<self_update::update::Release, error::AppError>>,
+    #[cfg(not(target_arch = "wasm32"))]
     pub request_sender: std::sync::mpsc::Sender<NetworkRequest>,
+
+    /// 右上角的通知，点击后跳转到对应页面
+    pub notifications: toast::NotificationCenter,
+
+    /// 上次保存的工作区，页面变化时重新保存
+    pub workspace: workspace::Workspace,
+    /// 正在恢复上次的工作区时，完成后收到应选中的页面
+    pub restore_receiver: Option<std::sync::mpsc::Receiver<Option<usize>>>,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 pub enum NetworkRequest {
     FetchReleases,
     SelfUpdate,
@@ -40,58 +59,8 @@ pub enum NetworkRequest {
 impl Default for MainPage {
     fn default() -> Self {
         let (subview_sender, subview_receiver) = std::sync::mpsc::channel();
-        let (network_response_tx, network_response_rx) = std::sync::mpsc::channel();
-        let (network_request_tx, network_request_rx) = std::sync::mpsc::channel();
-        std::thread::spawn(move || -> Result<(), error::AppError> {
-            log::info!("网络线程已启动");
-            while let Ok(request) = network_request_rx.recv() {
-                let update_downloader = create_update_downloader()?;
-                match request {
-                    NetworkRequest::FetchReleases => {
-                        let release = self_update::update::ReleaseUpdate::get_latest_release(
-                            &update_downloader,
-                        );
-                        match release {
-                            Ok(release) => {
-                                if get_download_progress() != DownloadProgress::Pending {
-                                    if get_download_progress() == DownloadProgress::Completed {
-                                        network_response_tx
-                                            .send(Err(error::AppError::RestartRequired))?;
-                                    }
-                                    log::warn!("已有更新正在进行中，忽略新的更新请求");
-                                    continue;
-                                }
-
-                                if release.version != self_update::cargo_crate_version!() {
-                                    log::info!("获取到最新版本: {}", release.version);
-                                    network_response_tx.send(Ok(release)).unwrap();
-                                } else {
-                                    log::info!("当前已是最新版本");
-                                    network_response_tx.send(Err(error::AppError::UpToDate))?;
-                                }
-                            }
-                            Err(err) => {
-                                log::error!("获取最新版本失败: {:?}", err);
-                                network_response_tx.send(Err(error::AppError::Update(format!(
-                                    "获取最新版本失败: {:?}",
-                                    err
-                                ))))?;
-                            }
-                        }
-                    }
-                    NetworkRequest::SelfUpdate => {
-                        if get_download_progress() != DownloadProgress::Pending {
-                            log::warn!("已有更新正在进行中，忽略新的更新请求");
-                            continue;
-                        }
-                        set_download_progress(DownloadProgress::InProgress(0, 0));
-                        std::thread::spawn(|| update::update().unwrap());
-                    }
-                }
-            }
-            log::info!("网络线程已退出");
-            Ok(())
-        });
+        #[cfg(not(target_arch = "wasm32"))]
+        let (network_request_tx, network_response_rx) = spawn_network_thread();
         Self {
             creators: vec![],
             subview_receiver,
@@ -99,13 +68,86 @@ impl Default for MainPage {
             selected: 0,
             subviews: vec![],
             exp_cpu_usage: 0.0,
+            low_power: true,
+            idle_tracker: repaint::IdleTracker::default(),
+            #[cfg(not(target_arch = "wasm32"))]
             suitable_release: Err(error::AppError::None),
+            #[cfg(not(target_arch = "wasm32"))]
             request_sender: network_request_tx,
+            #[cfg(not(target_arch = "wasm32"))]
             response_receiver: network_response_rx,
+            notifications: toast::NotificationCenter::new(),
+            workspace: workspace::Workspace::default(),
+            restore_receiver: None,
         }
     }
 }
 
+/// 启动处理检查更新与自动更新请求的网络线程
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(clippy::type_complexity)]
+fn spawn_network_thread() -> (
+    std::sync::mpsc::Sender<NetworkRequest>,
+    std::sync::mpsc::Receiver<Result<self_update::update::Release, error::AppError>>,
+) {
+    let (network_response_tx, network_response_rx) = std::sync::mpsc::channel();
+    let (network_request_tx, network_request_rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || -> Result<(), error::AppError> {
+        log::info!("网络线程已启动");
+        while let Ok(request) = network_request_rx.recv() {
+            let update_downloader = create_update_downloader()?;
+            match request {
+                NetworkRequest::FetchReleases => {
+                    let release =
+                        self_update::update::ReleaseUpdate::get_latest_release(&update_downloader);
+                    match release {
+                        Ok(release) => {
+                            if get_download_progress() != DownloadProgress::Pending {
+                                if get_download_progress() == DownloadProgress::Completed {
+                                    network_response_tx
+                                        .send(Err(error::AppError::RestartRequired))?;
+                                    repaint::wake();
+                                }
+                                log::warn!("已有更新正在进行中，忽略新的更新请求");
+                                continue;
+                            }
+
+                            if release.version != self_update::cargo_crate_version!() {
+                                log::info!("获取到最新版本: {}", release.version);
+                                network_response_tx.send(Ok(release)).unwrap();
+                                repaint::wake();
+                            } else {
+                                log::info!("当前已是最新版本");
+                                network_response_tx.send(Err(error::AppError::UpToDate))?;
+                                repaint::wake();
+                            }
+                        }
+                        Err(err) => {
+                            log::error!("获取最新版本失败: {:?}", err);
+                            network_response_tx.send(Err(error::AppError::Update(i18n::trf(
+                                "app.fetch_release_failed",
+                                &[&format!("{:?}", err)],
+                            ))))?;
+                            repaint::wake();
+                        }
+                    }
+                }
+                NetworkRequest::SelfUpdate => {
+                    if get_download_progress() != DownloadProgress::Pending {
+                        log::warn!("已有更新正在进行中，忽略新的更新请求");
+                        continue;
+                    }
+                    set_download_progress(DownloadProgress::InProgress(0, 0));
+                    std::thread::spawn(|| update::update().unwrap());
+                }
+            }
+        }
+        log::info!("网络线程已退出");
+        Ok(())
+    });
+    (network_request_tx, network_response_rx)
+}
+
 impl MainPage {
     pub fn add_creator(
         &mut self,
@@ -117,30 +159,137 @@ impl MainPage {
     }
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         add_font(&cc.egui_ctx);
+        repaint::set_context(&cc.egui_ctx);
+        settings::init(&cc.egui_ctx);
+        tutorial::start_if_first_run();
         let mut ret = Self {
-            creators: vec![(
-                "异星工厂".to_string(),
-                Box::new(factorio::planner::FactorioContextCreatorView::default()),
-            )],
+            creators: vec![
+                (
+                    i18n::tr("app.creator_factorio").to_string(),
+                    Box::new(factorio::planner::FactorioContextCreatorView::default()),
+                ),
+                (
+                    i18n::tr("app.creator_generic").to_string(),
+                    Box::new(generic::GenericContextCreatorView::default()),
+                ),
+                (
+                    i18n::tr("app.creator_satisfactory").to_string(),
+                    Box::new(satisfactory::SatisfactoryContextCreatorView::default()),
+                ),
+            ],
             ..Default::default()
         };
         for creator in &mut ret.creators {
             creator.1.set_subview_sender(ret.subview_sender.clone());
         }
+        if settings::get().restore_workspace {
+            let workspace = workspace::Workspace::load();
+            if !workspace.entries.is_empty() {
+                ret.restore_receiver = Some(workspace.clone().restore(ret.subview_sender.clone()));
+            }
+            ret.workspace = workspace;
+        }
         ret
     }
+
+    /// 检查更新与自动更新
+    #[cfg(not(target_arch = "wasm32"))]
+    fn update_view(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        if ui.button(i18n::tr("app.check_update")).clicked() {
+            self.request_sender
+                .send(NetworkRequest::FetchReleases)
+                .unwrap();
+        }
+        let response = self.response_receiver.try_recv();
+        if let Ok(response) = response {
+            self.suitable_release = response;
+            match self.suitable_release {
+                Ok(_) => {}
+                Err(ref err) => match err {
+                    error::AppError::UpToDate => {
+                        toast::success(i18n::tr("app.up_to_date"));
+                    }
+                    error::AppError::None => {}
+                    err => {
+                        toast::error(i18n::trf(
+                            "app.update_check_failed",
+                            &[&format!("{:?}", err)],
+                        ));
+                    }
+                },
+            }
+        }
+        match &mut self.suitable_release {
+            Ok(release) => {
+                ui.label(i18n::trf("app.new_version", &[&release.version]));
+                if ui.button(i18n::tr("app.update")).clicked() {
+                    self.request_sender
+                        .send(NetworkRequest::SelfUpdate)
+                        .unwrap();
+                }
+            }
+            Err(err) => match err {
+                error::AppError::None => {}
+                error::AppError::UpToDate => {
+                    ui.label(i18n::tr("app.up_to_date"));
+                }
+                error::AppError::RestartRequired => {
+                    ui.colored_label(egui::Color32::YELLOW, i18n::tr("app.restart_required"));
+                    if ui.button(i18n::tr("app.restart")).clicked() {
+                        // 新进程独立运行，当前进程随即退出，不需要等待
+                        #[allow(clippy::zombie_processes)]
+                        std::process::Command::new(std::env::current_exe().unwrap())
+                            .spawn()
+                            .unwrap();
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                }
+                _err => {
+                    ui.colored_label(egui::Color32::RED, i18n::tr("app.update_failed"));
+                }
+            },
+        }
+    }
+
+    /// 恢复完成后切换到上次选中的页面，之后页面有变化就保存工作区
+    fn sync_workspace(&mut self) {
+        if let Some(receiver) = &self.restore_receiver {
+            match receiver.try_recv() {
+                Ok(selected) => {
+                    // 恢复的页面都先于完成消息发出
+                    while let Ok(subview) = self.subview_receiver.try_recv() {
+                        self.subviews.push(subview);
+                    }
+                    if let Some(selected) = selected {
+                        self.selected = self.creators.len() + selected;
+                    }
+                    self.restore_receiver = None;
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => return,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    self.restore_receiver = None;
+                }
+            }
+        }
+        let selected = self.selected.checked_sub(self.creators.len());
+        let workspace = workspace::Workspace::capture(&self.subviews, selected);
+        if workspace != self.workspace {
+            workspace.save();
+            self.workspace = workspace;
+        }
+    }
 }
 
 impl eframe::App for MainPage {
     fn update(&mut self, ctx: &eframe::egui::Context, frame: &mut eframe::Frame) {
-        let mut request_repaint = true;
+        let mut request_repaint = self.idle_tracker.update(ctx) || !self.low_power;
         ctx.input(|i| {
             if i.viewport().minimized.unwrap_or_default() {
                 request_repaint = false;
             }
         });
         if request_repaint {
-            ctx.request_repaint_after_secs(0.1);
+            ctx.request_repaint_after_secs(repaint::ACTIVE_REPAINT_SECS);
         }
         let cpu_usage = frame.info().cpu_usage.unwrap_or(0.0);
         self.exp_cpu_usage = self.exp_cpu_usage * 31.0 / 32.0 + cpu_usage / 32.0;
@@ -148,77 +297,39 @@ impl eframe::App for MainPage {
             .width_range(200.0..=280.0)
             .show(ctx, |ui| {
                 ui.heading("切向量化");
-                ui.label(format!("[构建] Git 哈希: {}", GIT_HASH));
-                ui.label(format!(
-                    "[性能] 帧生成时间: {:.2}ms",
-                    self.exp_cpu_usage * 1000.0
+                ui.label(i18n::trf("app.build_hash", &[&GIT_HASH]));
+                ui.label(i18n::trf(
+                    "app.frame_time",
+                    &[&format!("{:.2}", self.exp_cpu_usage * 1000.0)],
                 ));
-                ui.separator();
-                ui.label(format!("当前版本: {}", self_update::cargo_crate_version!()));
-                if ui.button("检查更新").clicked() {
-                    self.request_sender
-                        .send(NetworkRequest::FetchReleases)
-                        .unwrap();
-                }
-                let response = self.response_receiver.try_recv();
-                match response {
-                    Ok(response) => {
-                        self.suitable_release = response;
-                        match self.suitable_release {
-                            Ok(_) => {
-
-                            }
-                            Err(ref err) => match err {
-                                error::AppError::UpToDate => {
-                                    toast::success("当前已是最新版本。");
-                                }
-                                error::AppError::None => {
-                                    
-                                }
-                                err => {
-                                    toast::error(format!("更新检查失败: {:?}", err));
-                                }
-                            },
-                        }
-                    }
-                    _ => {}
+                ui.checkbox(&mut self.low_power, i18n::tr("app.low_power"))
+                    .on_hover_text(i18n::tr("app.low_power_hint"));
+                if ui.button(i18n::tr("app.tutorial")).clicked() {
+                    tutorial::start();
                 }
-                match &mut self.suitable_release {
-                    Ok(release) => {
-                        ui.label(format!("可更新新版本: {}", release.version));
-                        if ui.button("更新").clicked() {
-                            self.request_sender
-                                .send(NetworkRequest::SelfUpdate)
+                if ui.button(i18n::tr("app.settings")).clicked() {
+                    // 设置页已经打开时直接切换过去
+                    let name = i18n::tr("settings.title");
+                    match self
+                        .subviews
+                        .iter()
+                        .position(|subview| subview.name() == name)
+                    {
+                        Some(index) => self.selected = index + self.creators.len(),
+                        None => {
+                            self.subview_sender
+                                .send(Box::new(settings::SettingsView))
                                 .unwrap();
+                            self.selected = self.creators.len() + self.subviews.len();
                         }
                     }
-                    Err(err) => match err {
-                        error::AppError::None => {}
-                        error::AppError::UpToDate => {
-                            ui.label("当前已是最新版本。");
-                        }
-                        error::AppError::RestartRequired => {
-                            ui.colored_label(
-                                egui::Color32::YELLOW,
-                                "更新已下载完成，请重启应用以应用更新。",
-                            );
-                            if ui.button("重启应用").clicked() {
-                                std::process::Command::new(std::env::current_exe().unwrap())
-                                    .spawn()
-                                    .unwrap();
-                                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                            }
-                        }
-                        _err => {
-                            ui.colored_label(
-                                egui::Color32::RED,
-                                format!("更新检查失败"),
-                            );
-                        }
-                    },
                 }
+                ui.separator();
+                ui.label(i18n::trf("app.version", &[&env!("CARGO_PKG_VERSION")]));
+                #[cfg(not(target_arch = "wasm32"))]
+                self.update_view(ui, ctx);
                 ui.add(egui::Hyperlink::from_label_and_url(
-                    "Github 仓库",
+                    i18n::tr("app.repository"),
                     "https://github.com/cyx2015s/metatorio-calc",
                 ));
                 ui.separator();
@@ -228,7 +339,7 @@ impl eframe::App for MainPage {
                     .for_each(|(i, creator)| {
                         if ui
                             .selectable_label(self.selected == i, &creator.0)
-                            .on_hover_text_at_pointer("点击以选择该游戏环境，右键显示额外菜单")
+                            .on_hover_text_at_pointer(i18n::tr("app.creator_hint"))
                             .clicked()
                         {
                             self.selected = i;
@@ -254,7 +365,7 @@ impl eframe::App for MainPage {
                     idx += 1;
                     let mut deleted = false;
                     label.context_menu(|ui| {
-                        if ui.button("关闭").clicked() {
+                        if ui.button(i18n::tr("common.close")).clicked() {
                             deleted = true;
                         }
                     });
@@ -270,14 +381,17 @@ impl eframe::App for MainPage {
                         .get_temp::<bool>(egui::Id::new("font"))
                         .unwrap_or(false)
                 });
-                if ui.checkbox(&mut show_font_license, "字体协议").clicked() {
+                if ui
+                    .checkbox(&mut show_font_license, i18n::tr("app.font_license"))
+                    .clicked()
+                {
                     ui.memory_mut(|mem| {
                         mem.data
                             .insert_temp::<bool>(egui::Id::new("font"), !show_font_license);
                     });
                 }
                 if show_font_license {
-                    egui::Window::new("字体协议")
+                    egui::Window::new(i18n::tr("app.font_license"))
                         .open(&mut show_font_license)
                         .show(ctx, |ui| {
                             egui::ScrollArea::vertical().show(ui, |ui| {
@@ -285,7 +399,7 @@ impl eframe::App for MainPage {
                             });
                         });
                 }
-                if ui.button("重新加载图标").clicked() {
+                if ui.button(i18n::tr("app.reload_icons")).clicked() {
                     ui.ctx().forget_all_images();
                 }
                 ui.memory_mut(|mem| {
@@ -293,6 +407,7 @@ impl eframe::App for MainPage {
                         .insert_temp(egui::Id::new("font"), show_font_license);
                 })
             });
+        self.sync_workspace();
         if self.selected < self.creators.len() {
             egui::CentralPanel::default().show(ctx, |ui| {
                 self.creators[self.selected].1.view(ui);
@@ -302,7 +417,17 @@ impl eframe::App for MainPage {
                 self.subviews[self.selected - self.creators.len()].view(ui);
             });
         }
+        tutorial::show(ctx);
         toast::TOASTS.lock().unwrap().show(ctx);
+        let notification_target = self.notifications.show(ctx);
+        if let Some(key) = concept::SubviewKey::take_focus_request(ctx).or(notification_target)
+            && let Some(index) = self
+                .subviews
+                .iter()
+                .position(|subview| subview.key() == Some(key))
+        {
+            self.selected = self.creators.len() + index;
+        }
     }
 }
 
@@ -323,6 +448,18 @@ fn add_font(ctx: &egui::Context) {
     ));
 }
 
+/// 原生与网页版共用的界面初始化
+fn create_app(cc: &eframe::CreationContext<'_>) -> Box<dyn eframe::App> {
+    egui_extras::install_image_loaders(&cc.egui_ctx);
+    cc.egui_ctx.all_styles_mut(|style| {
+        style.interaction.tooltip_delay = 0.2;
+        style.interaction.tooltip_grace_time = 1.0;
+        style.interaction.show_tooltips_only_when_still = false;
+    });
+    Box::new(MainPage::new(cc))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
         .format_module_path(true)
@@ -330,6 +467,17 @@ fn main() {
         .format_file(false)
         .format_line_number(true)
         .init();
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Some(command) = factorio::metrics::MetricsCommand::parse(&args) {
+        match command.and_then(|command| command.run()) {
+            Ok(text) => print!("{}", text),
+            Err(err) => {
+                eprintln!("导出指标失败: {:?}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
     log::info!("应用程序启动");
     let icon_image = image::load_from_memory(include_bytes!("../assets/icon.png")).unwrap();
     eframe::run_native(
@@ -349,15 +497,37 @@ fn main() {
 
             ..Default::default()
         },
-        Box::new(|cc| {
-            egui_extras::install_image_loaders(&cc.egui_ctx);
-            cc.egui_ctx.all_styles_mut(|style| {
-                style.interaction.tooltip_delay = 0.2;
-                style.interaction.tooltip_grace_time = 1.0;
-                style.interaction.show_tooltips_only_when_still = false;
-            });
-            Ok(Box::new(MainPage::new(cc)))
-        }),
+        Box::new(|cc| Ok(create_app(cc))),
     )
     .unwrap();
 }
+
+/// 网页版入口，界面画在 index.html 中 id 为 the_canvas_id 的画布上
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    use eframe::wasm_bindgen::JsCast;
+
+    eframe::WebLogger::init(log::LevelFilter::Info).ok();
+    log::info!("应用程序启动");
+    wasm_bindgen_futures::spawn_local(async {
+        let canvas = eframe::web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.get_element_by_id("the_canvas_id"))
+            .and_then(|element| {
+                element
+                    .dyn_into::<eframe::web_sys::HtmlCanvasElement>()
+                    .ok()
+            })
+            .expect("页面中没有 id 为 the_canvas_id 的画布");
+        if let Err(err) = eframe::WebRunner::new()
+            .start(
+                canvas,
+                eframe::WebOptions::default(),
+                Box::new(|cc| Ok(create_app(cc))),
+            )
+            .await
+        {
+            log::error!("网页版启动失败: {:?}", err);
+        }
+    });
+}
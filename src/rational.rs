@@ -0,0 +1,118 @@
+use std::hash::Hash;
+
+use num_rational::Ratio;
+use num_traits::{CheckedAdd, CheckedMul};
+
+use crate::concept::Flow;
+
+/// 精确计算使用的有理数
+pub type Rational = Ratio<i64>;
+
+/// 吸附时允许的最大分母。配方时间、速度加成与产量都是较短的小数，乘积的分母远小于该值
+pub const MAX_DENOMINATOR: i64 = 1 << 20;
+
+/// 吸附时允许的相对误差，在该误差内找不到分母足够小的分数时保持浮点
+const RELATIVE_TOLERANCE: f64 = 1e-10;
+
+/// 分母不超过该值的非整数显示为分数
+pub const DISPLAY_MAX_DENOMINATOR: i64 = 16;
+
+/// 将浮点数吸附到分母不超过 MAX_DENOMINATOR 的最简有理数。
+/// 以连分数逐项逼近，取第一个误差在容差内的渐近分数；找不到时返回 None
+pub fn snap(value: f64) -> Option<Rational> {
+    if !value.is_finite() || value.abs() >= i64::MAX as f64 / 2.0 {
+        return None;
+    }
+    let tolerance = (value.abs() * RELATIVE_TOLERANCE).max(f64::EPSILON);
+    let (mut h0, mut h1) = (0i64, 1i64);
+    let (mut k0, mut k1) = (1i64, 0i64);
+    let mut rest = value;
+    loop {
+        let a = rest.floor();
+        let h = (a as i64).checked_mul(h1)?.checked_add(h0)?;
+        let k = (a as i64).checked_mul(k1)?.checked_add(k0)?;
+        if k > MAX_DENOMINATOR {
+            return None;
+        }
+        (h0, h1, k0, k1) = (h1, h, k1, k);
+        if (value - h as f64 / k as f64).abs() <= tolerance {
+            return Some(Rational::new(h, k));
+        }
+        let fraction = rest - a;
+        if fraction <= 0.0 {
+            return None;
+        }
+        rest = 1.0 / fraction;
+    }
+}
+
+pub fn to_f64(value: Rational) -> f64 {
+    *value.numer() as f64 / *value.denom() as f64
+}
+
+/// 能吸附的数值替换为有理数对应的浮点数，否则保持原值
+pub fn snap_value(value: f64) -> f64 {
+    snap(value).map_or(value, to_f64)
+}
+
+/// 精确计算各物料流按系数相加的结果。
+/// 能吸附的项以有理数累加，相互抵消的物品恰为零并被移除；无法吸附或溢出的物品退回浮点累加
+pub fn exact_flow_sum<'a, T>(terms: impl IntoIterator<Item = (&'a Flow<T>, f64)>) -> Flow<T>
+where
+    T: Eq + Hash + Clone + 'a,
+{
+    let mut exact: indexmap::IndexMap<T, Option<Rational>> = indexmap::IndexMap::new();
+    let mut inexact: Flow<T> = Flow::new();
+    for (flow, coefficient) in terms {
+        let snapped_coefficient = snap(coefficient);
+        for (key, value) in flow {
+            *inexact.entry(key.clone()).or_insert(0.0) += value * coefficient;
+            let entry = exact.entry(key.clone()).or_insert(Some(Rational::from(0)));
+            *entry = match (*entry, snapped_coefficient, snap(*value)) {
+                (Some(sum), Some(c), Some(v)) => c
+                    .checked_mul(&v)
+                    .and_then(|product| sum.checked_add(&product)),
+                _ => None,
+            };
+        }
+    }
+    inexact
+        .into_iter()
+        .filter_map(|(key, value)| match exact.get(&key).cloned().flatten() {
+            Some(sum) => (sum != Rational::from(0)).then(|| (key, to_f64(sum))),
+            None => Some((key, value)),
+        })
+        .collect()
+}
+
+/// 数值恰为分母较小的非整数时返回分数形式，如 7/3
+pub fn format_fraction(value: f64) -> Option<String> {
+    let ratio = snap(value)?;
+    if ratio.is_integer() || *ratio.denom() > DISPLAY_MAX_DENOMINATOR {
+        return None;
+    }
+    Some(format!("{}/{}", ratio.numer(), ratio.denom()))
+}
+
+#[test]
+fn test_rational_snap() {
+    assert_eq!(snap(0.1 + 0.2), Some(Rational::new(3, 10)));
+    assert_eq!(snap(0.9999999999999), Some(Rational::from(1)));
+    assert_eq!(snap(1.0 / 3.0), Some(Rational::new(1, 3)));
+    assert_eq!(snap(-2.5), Some(Rational::new(-5, 2)));
+    assert_eq!(snap(1.0 / 1_048_583.0), None);
+    assert_eq!(snap(f64::NAN), None);
+    assert_eq!(format_fraction(7.0 / 3.0).as_deref(), Some("7/3"));
+    assert_eq!(format_fraction(2.0), None);
+    assert_eq!(format_fraction(1.0 / 17.0), None);
+
+    // 浮点相加残留的微小余量在精确累加中恰为零
+    let a: Flow<&str> = [("plate", 1.0 / 3.0), ("ore", -0.1)].into_iter().collect();
+    let b: Flow<&str> = [("plate", -1.0), ("gear", 0.2)].into_iter().collect();
+    let c: Flow<&str> = [("rare", 1.0 / 1_048_583.0)].into_iter().collect();
+    let sum = exact_flow_sum([(&a, 3.0), (&b, 1.0), (&c, 1.0)]);
+    assert!(!sum.contains_key("plate"));
+    assert_eq!(sum["ore"], -0.3);
+    assert_eq!(sum["gear"], 0.2);
+    assert_eq!(sum["rare"], 1.0 / 1_048_583.0);
+}
@@ -0,0 +1,55 @@
+//! 空闲时降低重绘频率，后台线程产生结果时再唤醒界面。
+//!
+//! 关闭 gui 特性时没有界面，[`wake`] 不做任何事。
+
+#[cfg(feature = "gui")]
+lazy_static::lazy_static! {
+    static ref EGUI_CTX: std::sync::Mutex<Option<egui::Context>> = std::sync::Mutex::new(None);
+}
+
+/// 无交互超过该时长（秒）后进入空闲状态
+pub const IDLE_AFTER_SECS: f64 = 2.0;
+
+/// 活跃状态下的重绘间隔（秒）
+pub const ACTIVE_REPAINT_SECS: f32 = 0.1;
+
+#[cfg(feature = "gui")]
+pub fn set_context(ctx: &egui::Context) {
+    *EGUI_CTX.lock().unwrap() = Some(ctx.clone());
+}
+
+/// 从任意线程请求界面重绘，用于求解线程、网络线程等发出消息之后
+pub fn wake() {
+    #[cfg(feature = "gui")]
+    if let Some(ctx) = EGUI_CTX.lock().unwrap().as_ref() {
+        ctx.request_repaint();
+    }
+}
+
+/// 记录用户交互时间，判断当前是否处于空闲状态
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone, Default)]
+pub struct IdleTracker {
+    pub last_interaction: f64,
+}
+
+#[cfg(feature = "gui")]
+impl IdleTracker {
+    /// 每帧调用一次，返回是否仍处于活跃状态
+    pub fn update(&mut self, ctx: &egui::Context) -> bool {
+        let (now, interacted) = ctx.input(|i| {
+            (
+                i.time,
+                !i.events.is_empty()
+                    || i.pointer.is_moving()
+                    || i.pointer.any_down()
+                    || !i.keys_down.is_empty()
+                    || i.raw_scroll_delta != egui::Vec2::ZERO,
+            )
+        });
+        if interacted {
+            self.last_interaction = now;
+        }
+        now - self.last_interaction < IDLE_AFTER_SECS
+    }
+}
@@ -0,0 +1,128 @@
+use std::sync::{
+    Arc,
+    mpsc::{Receiver, Sender},
+};
+
+use crate::{
+    concept::*,
+    i18n::{tr, trf},
+    satisfactory::{SatisfactoryContext, SatisfactoryPlannerView},
+};
+
+type DocsChannel = (Sender<Vec<u8>>, Receiver<Vec<u8>>);
+
+/// 选择 Docs.json 创建幸福工厂的上下文，读取结果会缓存到工作目录
+#[derive(Default)]
+pub struct SatisfactoryContextCreatorView {
+    ctx: Option<Arc<SatisfactoryContext>>,
+    /// 网页版的文件选择是异步的，选中的文件内容经由该通道送回
+    upload_channel: Option<DocsChannel>,
+    error: Option<String>,
+    subview_sender: Option<Sender<Box<dyn Subview>>>,
+}
+
+impl SatisfactoryContextCreatorView {
+    fn pick_docs(&mut self) {
+        let sender = self
+            .upload_channel
+            .get_or_insert_with(std::sync::mpsc::channel)
+            .0
+            .clone();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = crate::file_dialog::FileDialog::new()
+            .add_filter("Docs.json", &["json"])
+            .pick_file()
+        {
+            match std::fs::read(&path) {
+                Ok(content) => {
+                    let _ = sender.send(content);
+                }
+                Err(err) => self.error = Some(format!("{}: {}", path.display(), err)),
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Some(file) = rfd::AsyncFileDialog::new()
+                .add_filter("Docs.json", &["json"])
+                .pick_file()
+                .await
+            {
+                let _ = sender.send(file.read().await);
+                crate::repaint::wake();
+            }
+        });
+    }
+
+    fn receive_docs(&mut self) {
+        let Some((_, receiver)) = &self.upload_channel else {
+            return;
+        };
+        while let Ok(content) = receiver.try_recv() {
+            match SatisfactoryContext::from_docs(&content) {
+                Ok(ctx) => {
+                    if let Err(e) = ctx.save_cache() {
+                        log::warn!("缓存幸福工厂上下文失败：{:?}", e);
+                    }
+                    self.ctx = Some(Arc::new(ctx));
+                    self.error = None;
+                }
+                Err(e) => self.error = Some(format!("{:?}", e)),
+            }
+        }
+    }
+}
+
+impl Subview for SatisfactoryContextCreatorView {
+    fn view(&mut self, ui: &mut egui::Ui) {
+        self.receive_docs();
+        ui.vertical_centered(|ui| {
+            ui.heading(tr("satisfactory.title"));
+            ui.separator();
+            ui.label(tr("satisfactory.docs_hint"));
+            ui.horizontal(|ui| {
+                if ui.button(tr("satisfactory.pick_docs")).clicked() {
+                    self.pick_docs();
+                }
+                if ui
+                    .add_enabled(
+                        SatisfactoryContext::cache_path().exists(),
+                        egui::Button::new(tr("satisfactory.load_cache")),
+                    )
+                    .clicked()
+                {
+                    match SatisfactoryContext::load_cache() {
+                        Ok(ctx) => {
+                            self.ctx = Some(Arc::new(ctx));
+                            self.error = None;
+                        }
+                        Err(e) => self.error = Some(format!("{:?}", e)),
+                    }
+                }
+            });
+            if let Some(ctx) = &self.ctx {
+                ui.label(trf(
+                    "satisfactory.loaded",
+                    &[&ctx.items.len(), &ctx.buildings.len(), &ctx.recipes.len()],
+                ));
+                if ui.button(tr("satisfactory.open_planner")).clicked()
+                    && let Some(sender) = &self.subview_sender
+                {
+                    let _ = sender.send(Box::new(SatisfactoryPlannerView::new(ctx.clone())));
+                }
+            }
+            if let Some(error) = &self.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        });
+    }
+
+    fn name(&self) -> String {
+        tr("satisfactory.title").to_string()
+    }
+}
+
+impl GameContextCreatorView for SatisfactoryContextCreatorView {
+    fn set_subview_sender(&mut self, sender: Sender<Box<dyn Subview>>) {
+        self.subview_sender = Some(sender);
+    }
+}
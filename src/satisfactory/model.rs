@@ -0,0 +1,401 @@
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+
+use crate::{
+    concept::*, dyn_serde::save_to_file, error::AppError, factorio::get_workding_directory,
+};
+
+/// 电力作为一种物品参与求解，单位为 MW
+pub const POWER: &str = "Power";
+
+/// 超频时耗电量随时钟速度的指数增长
+const POWER_EXPONENT: f64 = 1.321928;
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SatisfactoryItem {
+    pub display_name: String,
+    /// 液体与气体在 Docs.json 中以升记录，读取时已换算为立方米
+    pub fluid: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SatisfactoryBuilding {
+    pub display_name: String,
+    /// 100% 时钟速度下的耗电量，单位为 MW
+    pub power: f64,
+    pub speed: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SatisfactoryRecipe {
+    pub display_name: String,
+    /// 每次制作的秒数
+    pub duration: f64,
+    pub ingredients: IndexMap<String, f64>,
+    pub products: IndexMap<String, f64>,
+    /// 可以制作该配方的建筑，只保留 Docs.json 中登记过的生产建筑
+    pub produced_in: Vec<String>,
+}
+
+/// 从幸福工厂的 Docs.json 读取的游戏上下文，以类名作为物品、建筑与配方的标识
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SatisfactoryContext {
+    pub items: IndexMap<String, SatisfactoryItem>,
+    pub buildings: IndexMap<String, SatisfactoryBuilding>,
+    pub recipes: IndexMap<String, SatisfactoryRecipe>,
+}
+
+/// Docs.json 随游戏以带 BOM 的 UTF-16 LE 发布，也兼容另存为 UTF-8 的文件
+fn decode_docs(bytes: &[u8]) -> Result<String, AppError> {
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => {
+            let units = rest
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect::<Vec<_>>();
+            String::from_utf16(&units)
+                .map_err(|e| AppError::Io(format!("Docs.json 编码错误：{}", e)))
+        }
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8(rest.to_vec())
+            .map_err(|e| AppError::Io(format!("Docs.json 编码错误：{}", e))),
+        _ => String::from_utf8(bytes.to_vec())
+            .map_err(|e| AppError::Io(format!("Docs.json 编码错误：{}", e))),
+    }
+}
+
+/// 从类路径中取出类名，如 `"/Game/.../Build_SmelterMk1.Build_SmelterMk1_C"` 取 `Build_SmelterMk1_C`
+fn class_name(path: &str) -> String {
+    let path = path.trim_matches(|c: char| c == '"' || c == '\'' || c.is_whitespace());
+    path.rsplit('.')
+        .next()
+        .unwrap_or(path)
+        .trim_matches(|c: char| c == '"' || c == '\'')
+        .to_string()
+}
+
+/// 解析 `((ItemClass=...,Amount=3),(ItemClass=...,Amount=2))` 形式的物品列表
+fn parse_item_amounts(text: &str) -> IndexMap<String, f64> {
+    lazy_static::lazy_static! {
+        static ref ITEM_AMOUNT: regex::Regex =
+            regex::Regex::new(r"ItemClass=([^,]+),Amount=([\d.]+)").unwrap();
+    }
+    let mut amounts = IndexMap::new();
+    for capture in ITEM_AMOUNT.captures_iter(text) {
+        if let Ok(amount) = capture[2].parse::<f64>() {
+            *amounts.entry(class_name(&capture[1])).or_insert(0.0) += amount;
+        }
+    }
+    amounts
+}
+
+/// 解析 `("/Game/...A_C","/Game/...B_C")` 形式的类路径列表
+fn parse_class_list(text: &str) -> Vec<String> {
+    text.trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .filter(|path| !path.trim().is_empty())
+        .map(class_name)
+        .collect()
+}
+
+fn string_field<'a>(class: &'a serde_json::Value, field: &str) -> &'a str {
+    class
+        .get(field)
+        .and_then(|value| value.as_str())
+        .unwrap_or("")
+}
+
+fn number_field(class: &serde_json::Value, field: &str) -> f64 {
+    string_field(class, field).trim().parse().unwrap_or(0.0)
+}
+
+impl SatisfactoryContext {
+    /// 解析 Docs.json 的内容。
+    /// 带有 mForm 字段的类视为物品，FGBuildableManufacturer 类视为生产建筑，FGRecipe 类视为配方；
+    /// 只能在手持制作台或建造枪中使用的配方会被忽略。
+    pub fn from_docs(bytes: &[u8]) -> Result<Self, AppError> {
+        let text = decode_docs(bytes)?;
+        let native_classes: Vec<serde_json::Value> = serde_json::from_str(&text)
+            .map_err(|e| AppError::Io(format!("解析 Docs.json 失败：{}", e)))?;
+        let mut ctx = Self::default();
+        let mut recipes = vec![];
+        for native_class in &native_classes {
+            let native_name = string_field(native_class, "NativeClass");
+            let Some(classes) = native_class.get("Classes").and_then(|c| c.as_array()) else {
+                continue;
+            };
+            for class in classes {
+                let name = string_field(class, "ClassName").to_string();
+                let display_name = string_field(class, "mDisplayName").to_string();
+                if native_name.contains("FGRecipe'") {
+                    recipes.push((name, display_name, class));
+                } else if native_name.contains("FGBuildableManufacturer") {
+                    let speed = number_field(class, "mManufacturingSpeed");
+                    ctx.buildings.insert(
+                        name,
+                        SatisfactoryBuilding {
+                            display_name,
+                            power: number_field(class, "mPowerConsumption"),
+                            speed: if speed > 0.0 { speed } else { 1.0 },
+                        },
+                    );
+                } else if let Some(form) = class.get("mForm").and_then(|form| form.as_str()) {
+                    let fluid = form == "RF_LIQUID" || form == "RF_GAS";
+                    ctx.items.insert(
+                        name,
+                        SatisfactoryItem {
+                            display_name,
+                            fluid,
+                        },
+                    );
+                }
+            }
+        }
+        for (name, display_name, class) in recipes {
+            let produced_in = parse_class_list(string_field(class, "mProducedIn"))
+                .into_iter()
+                .filter(|building| ctx.buildings.contains_key(building))
+                .collect::<Vec<_>>();
+            if produced_in.is_empty() {
+                continue;
+            }
+            let recipe = SatisfactoryRecipe {
+                display_name,
+                duration: number_field(class, "mManufactoringDuration"),
+                ingredients: ctx
+                    .convert_amounts(parse_item_amounts(string_field(class, "mIngredients"))),
+                products: ctx.convert_amounts(parse_item_amounts(string_field(class, "mProduct"))),
+                produced_in,
+            };
+            ctx.recipes.insert(name, recipe);
+        }
+        if ctx.recipes.is_empty() {
+            return Err(AppError::Custom(
+                "Docs.json 中没有找到任何生产配方".to_string(),
+            ));
+        }
+        Ok(ctx)
+    }
+
+    /// 液体数量从升换算为立方米
+    fn convert_amounts(&self, amounts: IndexMap<String, f64>) -> IndexMap<String, f64> {
+        amounts
+            .into_iter()
+            .map(|(item, amount)| {
+                let fluid = self.items.get(&item).is_some_and(|item| item.fluid);
+                (item, if fluid { amount / 1000.0 } else { amount })
+            })
+            .collect()
+    }
+
+    pub fn item_name<'a>(&'a self, item: &'a str) -> &'a str {
+        match self.items.get(item) {
+            Some(item) => &item.display_name,
+            None => item,
+        }
+    }
+
+    /// 读取后缓存的上下文，下次启动时无需再次选择 Docs.json
+    pub fn cache_path() -> PathBuf {
+        get_workding_directory().join("satisfactory.json")
+    }
+
+    pub fn save_cache(&self) -> Result<(), AppError> {
+        save_to_file(self, &Self::cache_path())
+    }
+
+    pub fn load_cache() -> Result<Self, AppError> {
+        Self::load(&Self::cache_path())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, AppError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| AppError::Io(format!("读取文件 {} 失败：{}", path.display(), e)))?;
+        serde_json::from_str(&content)
+            .map_err(|e| AppError::Io(format!("解析文件 {} 失败：{}", path.display(), e)))
+    }
+}
+
+fn default_clock() -> f64 {
+    1.0
+}
+
+/// 一组按同一配方与时钟速度运行的建筑，物料流单位为每分钟
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename = "satisfactory:recipe")]
+pub struct SatisfactoryRecipeConfig {
+    pub recipe: String,
+    pub building: String,
+    /// 时钟速度，1.0 为 100%，最高 2.5
+    #[serde(default = "default_clock")]
+    pub clock: f64,
+}
+
+impl SatisfactoryRecipeConfig {
+    pub fn new(recipe: impl Into<String>, ctx: &SatisfactoryContext) -> Self {
+        let recipe = recipe.into();
+        let building = ctx
+            .recipes
+            .get(&recipe)
+            .and_then(|recipe| recipe.produced_in.first().cloned())
+            .unwrap_or_default();
+        Self {
+            recipe,
+            building,
+            clock: 1.0,
+        }
+    }
+}
+
+impl SolveContext for SatisfactoryRecipeConfig {
+    type GameContext = SatisfactoryContext;
+    type ItemIdentType = String;
+}
+
+impl AsFlow for SatisfactoryRecipeConfig {
+    fn as_flow(&self, ctx: &Self::GameContext) -> Flow<Self::ItemIdentType> {
+        let mut flow = Flow::new();
+        let (Some(recipe), Some(building)) = (
+            ctx.recipes.get(&self.recipe),
+            ctx.buildings.get(&self.building),
+        ) else {
+            return flow;
+        };
+        if recipe.duration <= 0.0 {
+            return flow;
+        }
+        let crafts_per_minute = 60.0 / recipe.duration * building.speed * self.clock;
+        for (item, amount) in &recipe.ingredients {
+            *flow.entry(item.clone()).or_insert(0.0) -= amount * crafts_per_minute;
+        }
+        for (item, amount) in &recipe.products {
+            *flow.entry(item.clone()).or_insert(0.0) += amount * crafts_per_minute;
+        }
+        if building.power > 0.0 {
+            flow.insert(
+                POWER.to_string(),
+                -building.power * self.clock.powf(POWER_EXPONENT),
+            );
+        }
+        flow
+    }
+}
+
+impl EditorView for SatisfactoryRecipeConfig {
+    #[cfg(feature = "gui")]
+    fn editor_view(&mut self, ui: &mut egui::Ui, ctx: &Self::GameContext) -> bool {
+        let mut changed = false;
+        let Some(recipe) = ctx.recipes.get(&self.recipe) else {
+            ui.colored_label(ui.visuals().error_fg_color, &self.recipe);
+            return false;
+        };
+        ui.horizontal_wrapped(|ui| {
+            ui.strong(&recipe.display_name);
+            let building_name = |building: &str| {
+                ctx.buildings
+                    .get(building)
+                    .map_or(building.to_string(), |b| b.display_name.clone())
+            };
+            egui::ComboBox::from_id_salt("satisfactory_building")
+                .selected_text(building_name(&self.building))
+                .show_ui(ui, |ui| {
+                    for building in &recipe.produced_in {
+                        changed |= ui
+                            .selectable_value(
+                                &mut self.building,
+                                building.clone(),
+                                building_name(building),
+                            )
+                            .changed();
+                    }
+                });
+            ui.label(crate::i18n::tr("satisfactory.clock"));
+            let mut percent = self.clock * 100.0;
+            if ui
+                .add(
+                    egui::DragValue::new(&mut percent)
+                        .speed(1.0)
+                        .range(1.0..=250.0)
+                        .suffix("%"),
+                )
+                .changed()
+            {
+                self.clock = percent / 100.0;
+                changed = true;
+            }
+        });
+        ui.horizontal_wrapped(|ui| {
+            for (item, amount) in self.as_flow(ctx) {
+                ui.label(format!(
+                    "{} {}",
+                    ctx.item_name(&item),
+                    crate::factorio::signed_compact_number(amount)
+                ));
+            }
+        });
+        changed
+    }
+}
+
+#[test]
+fn test_satisfactory_docs() {
+    let docs = r#"[
+        {"NativeClass": "/Script/CoreUObject.Class'/Script/FactoryGame.FGResourceDescriptor'",
+         "Classes": [
+            {"ClassName": "Desc_OreIron_C", "mDisplayName": "Iron Ore", "mForm": "RF_SOLID"},
+            {"ClassName": "Desc_Water_C", "mDisplayName": "Water", "mForm": "RF_LIQUID"}
+         ]},
+        {"NativeClass": "/Script/CoreUObject.Class'/Script/FactoryGame.FGItemDescriptor'",
+         "Classes": [
+            {"ClassName": "Desc_IronIngot_C", "mDisplayName": "Iron Ingot", "mForm": "RF_SOLID"}
+         ]},
+        {"NativeClass": "/Script/CoreUObject.Class'/Script/FactoryGame.FGBuildableManufacturer'",
+         "Classes": [
+            {"ClassName": "Build_SmelterMk1_C", "mDisplayName": "Smelter",
+             "mPowerConsumption": "4.000000", "mManufacturingSpeed": "1.000000"}
+         ]},
+        {"NativeClass": "/Script/CoreUObject.Class'/Script/FactoryGame.FGRecipe'",
+         "Classes": [
+            {"ClassName": "Recipe_IngotIron_C", "mDisplayName": "Iron Ingot",
+             "mIngredients": "((ItemClass=\"/Script/Engine.BlueprintGeneratedClass'/Game/FactoryGame/Resource/RawResources/OreIron/Desc_OreIron.Desc_OreIron_C'\",Amount=1))",
+             "mProduct": "((ItemClass=\"/Script/Engine.BlueprintGeneratedClass'/Game/FactoryGame/Resource/Parts/IronIngot/Desc_IronIngot.Desc_IronIngot_C'\",Amount=1))",
+             "mManufactoringDuration": "2.000000",
+             "mProducedIn": "(\"/Game/FactoryGame/Buildable/Factory/SmelterMk1/Build_SmelterMk1.Build_SmelterMk1_C\",\"/Game/FactoryGame/Buildable/-Shared/WorkBench/BP_WorkBenchComponent.BP_WorkBenchComponent_C\")"},
+            {"ClassName": "Recipe_WetIngot_C", "mDisplayName": "Wet Ingot",
+             "mIngredients": "((ItemClass=BlueprintGeneratedClass'\"/Game/FactoryGame/Resource/RawResources/Water/Desc_Water.Desc_Water_C\"',Amount=2000))",
+             "mProduct": "((ItemClass=BlueprintGeneratedClass'\"/Game/FactoryGame/Resource/Parts/IronIngot/Desc_IronIngot.Desc_IronIngot_C\"',Amount=1))",
+             "mManufactoringDuration": "1.000000",
+             "mProducedIn": "(\"/Game/FactoryGame/Buildable/Factory/SmelterMk1/Build_SmelterMk1.Build_SmelterMk1_C\")"},
+            {"ClassName": "Recipe_HandOnly_C", "mDisplayName": "Hand only",
+             "mIngredients": "", "mProduct": "", "mManufactoringDuration": "1.000000",
+             "mProducedIn": "(\"/Game/FactoryGame/Equipment/BuildGun/BP_BuildGun.BP_BuildGun_C\")"}
+         ]}
+    ]"#;
+    let mut utf16 = vec![0xFF, 0xFE];
+    utf16.extend(docs.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+    let ctx = SatisfactoryContext::from_docs(&utf16).unwrap();
+    assert_eq!(
+        ctx,
+        SatisfactoryContext::from_docs(docs.as_bytes()).unwrap()
+    );
+    assert_eq!(ctx.items.len(), 3);
+    assert_eq!(ctx.recipes.len(), 2);
+    assert_eq!(
+        ctx.recipes["Recipe_IngotIron_C"].produced_in,
+        ["Build_SmelterMk1_C"]
+    );
+    assert_eq!(
+        ctx.recipes["Recipe_WetIngot_C"].ingredients["Desc_Water_C"],
+        2.0
+    );
+
+    let mut config = SatisfactoryRecipeConfig::new("Recipe_IngotIron_C", &ctx);
+    let flow = config.as_flow(&ctx);
+    assert_eq!(flow["Desc_OreIron_C"], -30.0);
+    assert_eq!(flow["Desc_IronIngot_C"], 30.0);
+    assert_eq!(flow[POWER], -4.0);
+    config.clock = 2.0;
+    let flow = config.as_flow(&ctx);
+    assert_eq!(flow["Desc_IronIngot_C"], 60.0);
+    assert!((flow[POWER] + 10.0).abs() < 1e-3);
+}
@@ -0,0 +1,227 @@
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+
+use crate::{
+    concept::*,
+    factorio::{compact_number, signed_compact_number},
+    i18n::{tr, trf},
+    satisfactory::{POWER, SatisfactoryContext, SatisfactoryRecipeConfig},
+    solver::SolverData,
+};
+
+pub type SatisfactoryMechanic =
+    dyn Mechanic<ItemIdentType = String, GameContext = SatisfactoryContext>;
+
+/// 幸福工厂的规划界面，物料流单位为每分钟，电力单位为 MW
+pub struct SatisfactoryPlannerView {
+    key: SubviewKey,
+    ctx: Arc<SatisfactoryContext>,
+    targets: IndexMap<String, f64>,
+    mechanics: IndexMap<MechanicId, Box<SatisfactoryMechanic>>,
+    new_target: String,
+    recipe_filter: String,
+    solution: Option<Result<(Flow<MechanicId>, f64), String>>,
+}
+
+impl SatisfactoryPlannerView {
+    pub fn new(ctx: Arc<SatisfactoryContext>) -> Self {
+        Self {
+            key: SubviewKey::next(),
+            ctx,
+            targets: IndexMap::new(),
+            mechanics: IndexMap::new(),
+            new_target: String::new(),
+            recipe_filter: String::new(),
+            solution: None,
+        }
+    }
+
+    fn solve(&mut self) {
+        if self.targets.is_empty() {
+            self.solution = None;
+            return;
+        }
+        let flows = self
+            .mechanics
+            .iter()
+            .map(|(id, mechanic)| (*id, (mechanic.as_flow(&self.ctx), mechanic.cost(&self.ctx))))
+            .collect();
+        self.solution = Some(
+            SolverData::new(self.targets.clone(), flows)
+                .solve()
+                .map_err(|e| format!("{:?}", e)),
+        );
+    }
+
+    /// 按求解结果汇总的物品净流量
+    fn total_flow(&self, counts: &Flow<MechanicId>) -> Flow<String> {
+        let mut total = Flow::new();
+        for (id, mechanic) in &self.mechanics {
+            let count = counts.get(id).cloned().unwrap_or(0.0);
+            for (item, amount) in mechanic.as_flow(&self.ctx) {
+                *total.entry(item).or_insert(0.0) += amount * count;
+            }
+        }
+        total.retain(|_, amount| amount.abs() > 1e-9);
+        total
+    }
+
+    fn unit(item: &str) -> &'static str {
+        if item == POWER { "MW" } else { "/min" }
+    }
+
+    fn targets_view(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.strong(tr("satisfactory.targets"));
+        let mut removed = None;
+        for (item, amount) in self.targets.iter_mut() {
+            ui.horizontal(|ui| {
+                ui.label(self.ctx.item_name(item));
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(amount)
+                            .speed(1.0)
+                            .suffix(Self::unit(item)),
+                    )
+                    .changed();
+                if ui.small_button("✖").clicked() {
+                    removed = Some(item.clone());
+                }
+            });
+        }
+        if let Some(item) = removed {
+            self.targets.shift_remove(&item);
+            changed = true;
+        }
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("satisfactory_new_target")
+                .selected_text(self.ctx.item_name(&self.new_target))
+                .height(400.0)
+                .show_ui(ui, |ui| {
+                    for (name, item) in &self.ctx.items {
+                        ui.selectable_value(&mut self.new_target, name.clone(), &item.display_name);
+                    }
+                });
+            if ui
+                .add_enabled(
+                    !self.new_target.is_empty() && !self.targets.contains_key(&self.new_target),
+                    egui::Button::new(tr("satisfactory.add_target")),
+                )
+                .clicked()
+            {
+                self.targets.insert(self.new_target.clone(), 60.0);
+                changed = true;
+            }
+        });
+        changed
+    }
+
+    fn mechanics_view(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.strong(tr("satisfactory.mechanics"));
+        let counts = match &self.solution {
+            Some(Ok((counts, _))) => counts.clone(),
+            _ => Flow::new(),
+        };
+        let mut removed = None;
+        for (id, mechanic) in self.mechanics.iter_mut() {
+            ui.push_id(id, |ui| {
+                ui.group(|ui| {
+                    ui.horizontal(|ui| {
+                        if let Some(count) = counts.get(id) {
+                            ui.strong(format!("× {}", compact_number(*count)));
+                        }
+                        if ui.small_button("✖").clicked() {
+                            removed = Some(*id);
+                        }
+                    });
+                    changed |= mechanic.editor_view(ui, &self.ctx);
+                });
+            });
+        }
+        if let Some(id) = removed {
+            self.mechanics.shift_remove(&id);
+            changed = true;
+        }
+        ui.menu_button(tr("satisfactory.add_mechanic"), |ui| {
+            ui.text_edit_singleline(&mut self.recipe_filter);
+            let filter = self.recipe_filter.to_lowercase();
+            egui::ScrollArea::vertical()
+                .max_height(400.0)
+                .show(ui, |ui| {
+                    for (name, recipe) in &self.ctx.recipes {
+                        if !recipe.display_name.to_lowercase().contains(&filter) {
+                            continue;
+                        }
+                        if ui.button(&recipe.display_name).clicked() {
+                            self.mechanics.insert(
+                                MechanicId::generate(),
+                                Box::new(SatisfactoryRecipeConfig::new(name.clone(), &self.ctx)),
+                            );
+                            changed = true;
+                        }
+                    }
+                });
+        });
+        changed
+    }
+
+    fn solution_view(&self, ui: &mut egui::Ui) {
+        match &self.solution {
+            None => {
+                ui.label(tr("satisfactory.no_target"));
+            }
+            Some(Err(error)) => {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+            Some(Ok((counts, _))) => {
+                ui.strong(tr("satisfactory.total_flow"));
+                for (item, amount) in self.total_flow(counts) {
+                    ui.label(format!(
+                        "{} {}{}",
+                        self.ctx.item_name(&item),
+                        signed_compact_number(amount),
+                        Self::unit(&item)
+                    ));
+                }
+            }
+        }
+    }
+}
+
+impl Subview for SatisfactoryPlannerView {
+    fn view(&mut self, ui: &mut egui::Ui) {
+        let mut changed = false;
+        ui.columns(2, |columns| {
+            egui::ScrollArea::vertical()
+                .id_salt("satisfactory_planner_left")
+                .show(&mut columns[0], |ui| {
+                    changed |= self.targets_view(ui);
+                    ui.separator();
+                    changed |= self.mechanics_view(ui);
+                });
+            egui::ScrollArea::vertical()
+                .id_salt("satisfactory_planner_right")
+                .show(&mut columns[1], |ui| self.solution_view(ui));
+        });
+        if changed {
+            self.solve();
+        }
+    }
+
+    fn name(&self) -> String {
+        tr("satisfactory.planner_name").to_string()
+    }
+
+    fn description(&self) -> String {
+        trf(
+            "satisfactory.planner_description",
+            &[&self.ctx.items.len(), &self.ctx.recipes.len()],
+        )
+    }
+
+    fn key(&self) -> Option<SubviewKey> {
+        Some(self.key)
+    }
+}
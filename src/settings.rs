@@ -0,0 +1,486 @@
+//! 应用设置：主题、缩放、图标尺寸、数值精度与速率单位，以及选择器的收藏与最近使用。
+//!
+//! 设置保存在工作目录下的 settings.json 中，启动时由 `init` 读取并应用到界面，
+//! 各处绘制时通过 [`get`] 取当前值，`SettingsView` 负责编辑并在修改后立即保存。
+
+use std::sync::RwLock;
+
+#[cfg(feature = "gui")]
+use crate::{concept::Subview, i18n::trf, workspace::WorkspaceEntry};
+use crate::{
+    dyn_serde::save_to_file,
+    factorio::{GenericItem, get_workding_directory},
+    i18n::tr,
+};
+
+lazy_static::lazy_static! {
+    static ref SETTINGS: RwLock<Settings> = RwLock::new(Settings::default());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum Theme {
+    /// 跟随系统
+    #[default]
+    System,
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::System, Theme::Dark, Theme::Light];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::System => tr("settings.theme_system"),
+            Theme::Dark => tr("settings.theme_dark"),
+            Theme::Light => tr("settings.theme_light"),
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    fn preference(&self) -> egui::ThemePreference {
+        match self {
+            Theme::System => egui::ThemePreference::System,
+            Theme::Dark => egui::ThemePreference::Dark,
+            Theme::Light => egui::ThemePreference::Light,
+        }
+    }
+}
+
+/// 极速传送带两侧合计的运力（个/秒）
+pub const EXPRESS_BELT_THROUGHPUT: f64 = 45.0;
+
+/// 物料流的显示单位，求解与存档始终以每秒为单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum RateUnit {
+    #[default]
+    PerSecond,
+    PerMinute,
+    PerHour,
+    /// 折算为满载的极速传送带条数
+    ExpressBelt,
+}
+
+impl RateUnit {
+    pub const ALL: [RateUnit; 4] = [
+        RateUnit::PerSecond,
+        RateUnit::PerMinute,
+        RateUnit::PerHour,
+        RateUnit::ExpressBelt,
+    ];
+
+    /// 每秒的数值换算到该单位的倍数
+    pub fn multiplier(&self) -> f64 {
+        match self {
+            RateUnit::PerSecond => 1.0,
+            RateUnit::PerMinute => 60.0,
+            RateUnit::PerHour => 3600.0,
+            RateUnit::ExpressBelt => 1.0 / EXPRESS_BELT_THROUGHPUT,
+        }
+    }
+
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            RateUnit::PerSecond => "/s",
+            RateUnit::PerMinute => "/min",
+            RateUnit::PerHour => "/h",
+            RateUnit::ExpressBelt => tr("settings.rate_express_belt"),
+        }
+    }
+
+    /// 单位选择器中显示的名称
+    pub fn name(&self) -> &'static str {
+        self.suffix().trim_start()
+    }
+
+    /// 全局设置中的默认单位
+    pub fn global() -> Self {
+        get().rate_unit
+    }
+
+    /// 该物料实际显示用的单位：只有物品换算，电力、热量与流体等保持每秒
+    pub fn for_item(self, item: &GenericItem) -> Self {
+        match item {
+            GenericItem::Item(_) => self,
+            _ => RateUnit::PerSecond,
+        }
+    }
+}
+
+/// 选择器中收藏或最近选过的条目，item_type 为选择器的条目类型，如 item、recipe
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PinnedEntry {
+    pub item_type: String,
+    pub name: String,
+}
+
+impl PinnedEntry {
+    pub fn new(item_type: &str, name: &str) -> Self {
+        PinnedEntry {
+            item_type: item_type.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    fn is(&self, item_type: &str, name: &str) -> bool {
+        self.item_type == item_type && self.name == name
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: Theme,
+    /// 界面整体缩放倍数
+    pub zoom_factor: f32,
+    /// 物品图标的边长（点）
+    pub icon_size: f32,
+    /// 个位数的数值最多保留的小数位数，数量级越大保留越少
+    pub decimal_precision: usize,
+    /// 较大或较小的数值使用 k/M/G 与 μ 等单位前缀缩写
+    pub si_suffixes: bool,
+    /// 数值的整数部分每三位插入逗号
+    pub thousands_separator: bool,
+    pub rate_unit: RateUnit,
+    /// 求解结果吸附为有理数后再累加物料流，恰好相等的量相互抵消，较小分母的数值显示为分数
+    pub exact_rational: bool,
+    /// 选择器中收藏的物品与配方
+    pub favorites: Vec<PinnedEntry>,
+    /// 选择器中最近选过的条目，最新的在前
+    pub recents: Vec<PinnedEntry>,
+    /// 从机制卡片建议或复制的机制插入到来源卡片之后，而不是末尾
+    pub insert_adjacent: bool,
+    /// 启动时恢复上次打开的页面与工厂
+    pub restore_workspace: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            theme: Theme::System,
+            zoom_factor: 1.0,
+            icon_size: 32.0,
+            decimal_precision: 3,
+            si_suffixes: true,
+            thousands_separator: false,
+            rate_unit: RateUnit::PerSecond,
+            exact_rational: false,
+            insert_adjacent: true,
+            favorites: Vec::new(),
+            recents: Vec::new(),
+            restore_workspace: true,
+        }
+    }
+}
+
+impl Settings {
+    pub const ZOOM_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+    pub const ICON_SIZE_RANGE: std::ops::RangeInclusive<f32> = 16.0..=64.0;
+    pub const MAX_DECIMAL_PRECISION: usize = 6;
+    /// 每种条目保留的最近使用数量
+    pub const MAX_RECENTS: usize = 12;
+
+    pub fn path() -> std::path::PathBuf {
+        get_workding_directory().join("settings.json")
+    }
+
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_json::from_str::<Settings>(&content).ok())
+            .unwrap_or_default()
+            .sanitized()
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = save_to_file(self, &Self::path()) {
+            log::error!("保存设置失败: {:?}", err);
+        }
+    }
+
+    /// 手动编辑的设置文件可能超出范围
+    fn sanitized(mut self) -> Self {
+        if !self.zoom_factor.is_finite() {
+            self.zoom_factor = 1.0;
+        }
+        if !self.icon_size.is_finite() {
+            self.icon_size = 32.0;
+        }
+        self.zoom_factor = self
+            .zoom_factor
+            .clamp(*Self::ZOOM_RANGE.start(), *Self::ZOOM_RANGE.end());
+        self.icon_size = self
+            .icon_size
+            .clamp(*Self::ICON_SIZE_RANGE.start(), *Self::ICON_SIZE_RANGE.end());
+        self.decimal_precision = self.decimal_precision.min(Self::MAX_DECIMAL_PRECISION);
+        self
+    }
+
+    pub fn is_favorite(&self, item_type: &str, name: &str) -> bool {
+        self.favorites.iter().any(|entry| entry.is(item_type, name))
+    }
+
+    pub fn toggle_favorite(&mut self, item_type: &str, name: &str) {
+        if self.is_favorite(item_type, name) {
+            self.favorites.retain(|entry| !entry.is(item_type, name));
+        } else {
+            self.favorites.push(PinnedEntry::new(item_type, name));
+        }
+    }
+
+    /// 记录一次选择，移到最前并只保留同类条目中最近的若干个
+    pub fn push_recent(&mut self, item_type: &str, name: &str) {
+        self.recents.retain(|entry| !entry.is(item_type, name));
+        self.recents.insert(0, PinnedEntry::new(item_type, name));
+        let mut count = 0;
+        self.recents.retain(|entry| {
+            if entry.item_type != item_type {
+                return true;
+            }
+            count += 1;
+            count <= Self::MAX_RECENTS
+        });
+    }
+
+    /// 某类条目的收藏，按收藏的先后排列
+    pub fn favorites_of<'a>(&'a self, item_type: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.favorites
+            .iter()
+            .filter(move |entry| entry.item_type == item_type)
+            .map(|entry| entry.name.as_str())
+    }
+
+    /// 某类条目的最近使用，最新的在前
+    pub fn recents_of<'a>(&'a self, item_type: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.recents
+            .iter()
+            .filter(move |entry| entry.item_type == item_type)
+            .map(|entry| entry.name.as_str())
+    }
+
+    /// 将主题与缩放应用到界面，其余设置在绘制时读取
+    #[cfg(feature = "gui")]
+    pub fn apply(&self, ctx: &egui::Context) {
+        ctx.set_theme(self.theme.preference());
+        ctx.set_zoom_factor(self.zoom_factor);
+    }
+}
+
+/// 读取保存的设置并应用到界面，启动时调用一次
+#[cfg(feature = "gui")]
+pub fn init(ctx: &egui::Context) {
+    let settings = Settings::load();
+    settings.apply(ctx);
+    *SETTINGS.write().unwrap() = settings;
+}
+
+pub fn get() -> Settings {
+    SETTINGS.read().unwrap().clone()
+}
+
+/// 替换当前设置，应用到界面并保存
+#[cfg(feature = "gui")]
+pub fn set(settings: Settings, ctx: &egui::Context) {
+    let settings = settings.sanitized();
+    settings.apply(ctx);
+    settings.save();
+    *SETTINGS.write().unwrap() = settings;
+}
+
+/// 修改当前设置并保存，用于收藏等不涉及界面外观的设置
+pub fn update(f: impl FnOnce(&mut Settings)) {
+    let mut settings = SETTINGS.write().unwrap();
+    f(&mut settings);
+    settings.save();
+}
+
+/// 图标所在格子的尺寸，比图标略大以留出边距
+pub fn icon_cell() -> [f32; 2] {
+    let size = SETTINGS.read().unwrap().icon_size + 3.0;
+    [size, size]
+}
+
+/// 图标上方数值标签的尺寸，与图标格子等宽
+pub fn label_cell() -> [f32; 2] {
+    [icon_cell()[0], 15.0]
+}
+
+#[cfg(feature = "gui")]
+#[derive(Debug, Default)]
+pub struct SettingsView;
+
+#[cfg(feature = "gui")]
+impl Subview for SettingsView {
+    fn view(&mut self, ui: &mut egui::Ui) {
+        let mut settings = get();
+        ui.heading(tr("settings.title"));
+        egui::Grid::new("settings-grid")
+            .num_columns(2)
+            .spacing([24.0, 8.0])
+            .show(ui, |ui| {
+                ui.label(tr("settings.theme"));
+                ui.horizontal(|ui| {
+                    for theme in Theme::ALL {
+                        ui.selectable_value(&mut settings.theme, theme, theme.name());
+                    }
+                });
+                ui.end_row();
+
+                ui.label(tr("settings.zoom_factor"));
+                ui.add(
+                    egui::Slider::new(&mut settings.zoom_factor, Settings::ZOOM_RANGE)
+                        .step_by(0.05)
+                        .custom_formatter(|value, _| format!("{:.0}%", value * 100.0)),
+                );
+                ui.end_row();
+
+                ui.label(tr("settings.icon_size"));
+                ui.add(
+                    egui::Slider::new(&mut settings.icon_size, Settings::ICON_SIZE_RANGE)
+                        .step_by(1.0)
+                        .suffix("px"),
+                );
+                ui.end_row();
+
+                ui.label(tr("settings.decimal_precision"))
+                    .on_hover_text(tr("settings.decimal_precision_hint"));
+                ui.add(egui::Slider::new(
+                    &mut settings.decimal_precision,
+                    0..=Settings::MAX_DECIMAL_PRECISION,
+                ));
+                ui.end_row();
+
+                ui.label(tr("settings.si_suffixes"))
+                    .on_hover_text(tr("settings.si_suffixes_hint"));
+                ui.checkbox(&mut settings.si_suffixes, "");
+                ui.end_row();
+
+                ui.label(tr("settings.thousands_separator"));
+                ui.checkbox(&mut settings.thousands_separator, "");
+                ui.end_row();
+
+                ui.label(tr("settings.number_preview"));
+                ui.label(
+                    [1234567.891, 12.3456, 0.000123]
+                        .map(crate::factorio::compact_number)
+                        .join("   "),
+                );
+                ui.end_row();
+
+                ui.label(tr("settings.rate_unit"))
+                    .on_hover_text(tr("settings.rate_unit_hint"));
+                ui.horizontal(|ui| {
+                    for unit in RateUnit::ALL {
+                        ui.selectable_value(&mut settings.rate_unit, unit, unit.name());
+                    }
+                });
+                ui.end_row();
+
+                ui.label(tr("settings.exact_rational"))
+                    .on_hover_text(tr("settings.exact_rational_hint"));
+                ui.checkbox(&mut settings.exact_rational, "");
+                ui.end_row();
+
+                ui.label(tr("settings.insert_adjacent"))
+                    .on_hover_text(tr("settings.insert_adjacent_hint"));
+                ui.checkbox(&mut settings.insert_adjacent, "");
+                ui.end_row();
+
+                ui.label(tr("settings.restore_workspace"))
+                    .on_hover_text(tr("settings.restore_workspace_hint"));
+                ui.checkbox(&mut settings.restore_workspace, "");
+                ui.end_row();
+            });
+        ui.separator();
+        if ui.button(tr("settings.reset")).clicked() {
+            // 收藏与最近使用不在此页编辑，重置时保留
+            settings = Settings {
+                favorites: settings.favorites,
+                recents: settings.recents,
+                ..Settings::default()
+            };
+        }
+        ui.label(trf(
+            "settings.saved_to",
+            &[&Settings::path().display().to_string()],
+        ));
+        if settings != get() {
+            set(settings, ui.ctx());
+        }
+    }
+
+    fn name(&self) -> String {
+        tr("settings.title").to_string()
+    }
+
+    fn description(&self) -> String {
+        tr("settings.description").to_string()
+    }
+
+    fn workspace_entry(&self) -> Option<WorkspaceEntry> {
+        Some(WorkspaceEntry::Settings)
+    }
+}
+
+#[test]
+fn test_settings_roundtrip() {
+    let settings = Settings {
+        theme: Theme::Dark,
+        zoom_factor: 1.25,
+        icon_size: 40.0,
+        decimal_precision: 2,
+        si_suffixes: false,
+        thousands_separator: true,
+        rate_unit: RateUnit::PerMinute,
+        exact_rational: true,
+        insert_adjacent: false,
+        favorites: vec![PinnedEntry::new("item", "iron-plate")],
+        recents: vec![PinnedEntry::new("recipe", "iron-gear-wheel")],
+        restore_workspace: false,
+    };
+    let json = serde_json::to_string(&settings).unwrap();
+    assert_eq!(serde_json::from_str::<Settings>(&json).unwrap(), settings);
+    // 缺少的字段取默认值，超出范围的值被限制
+    let partial: Settings = serde_json::from_str(r#"{"zoom_factor": 10.0}"#).unwrap();
+    let partial = partial.sanitized();
+    assert_eq!(partial.zoom_factor, 3.0);
+    assert_eq!(partial.decimal_precision, 3);
+    assert!(partial.si_suffixes && !partial.thousands_separator);
+    assert!(partial.restore_workspace);
+    assert!(!partial.exact_rational);
+    assert!(partial.insert_adjacent);
+    assert_eq!(RateUnit::PerHour.multiplier(), 3600.0);
+    assert_eq!(90.0 * RateUnit::ExpressBelt.multiplier(), 2.0);
+    // 电力与流体不按传送带或每分钟换算
+    let plate = GenericItem::Item("iron-plate".into());
+    assert_eq!(RateUnit::PerMinute.for_item(&plate), RateUnit::PerMinute);
+    assert_eq!(
+        RateUnit::ExpressBelt.for_item(&GenericItem::Electricity),
+        RateUnit::PerSecond
+    );
+}
+
+#[test]
+fn test_favorites_and_recents() {
+    let mut settings = Settings::default();
+    settings.toggle_favorite("item", "iron-plate");
+    settings.toggle_favorite("recipe", "iron-plate");
+    assert!(settings.is_favorite("item", "iron-plate"));
+    settings.toggle_favorite("item", "iron-plate");
+    assert!(!settings.is_favorite("item", "iron-plate"));
+    assert!(settings.is_favorite("recipe", "iron-plate"));
+    // 最近使用去重并置顶，每类只保留有限个数
+    settings.push_recent("recipe", "iron-gear-wheel");
+    for i in 0..Settings::MAX_RECENTS + 3 {
+        settings.push_recent("item", &format!("item-{i}"));
+    }
+    settings.push_recent("item", "item-5");
+    let recents = settings.recents_of("item").collect::<Vec<_>>();
+    assert_eq!(recents.len(), Settings::MAX_RECENTS);
+    assert_eq!(recents[0], "item-5");
+    assert_eq!(recents.iter().filter(|name| **name == "item-5").count(), 1);
+    assert_eq!(
+        settings.recents_of("recipe").collect::<Vec<_>>(),
+        vec!["iron-gear-wheel"]
+    );
+}
@@ -3,9 +3,12 @@ use indexmap::IndexMap;
 
 use crate::concept::{Flow, ItemIdent};
 use crate::error::AppError;
-use std::collections::{HashMap, HashSet};
+use crate::i18n::{tr, trf};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Debug;
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 
 pub fn flow_add<T>(a: &Flow<T>, b: &Flow<T>, c: f64) -> Flow<T>
 where
@@ -19,12 +22,39 @@ where
     result
 }
 
-/// 返回值仅用作 AsFlowEditor 的唯一标识符
-#[allow(clippy::borrowed_box)]
-pub fn box_as_ptr<T: ?Sized>(b: &Box<T>) -> usize {
-    &**b as *const T as *const () as usize
+/// 目标物品的约束方式
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Default, serde::Serialize, serde::Deserialize,
+)]
+pub enum TargetMode {
+    /// 净产量恰好等于目标
+    #[default]
+    Exact,
+    /// 净产量不低于目标
+    AtLeast,
+    /// 在不低于目标的前提下尽可能多地生产，产量受锁定的机制等条件限制
+    Maximize,
 }
 
+impl TargetMode {
+    pub const ALL: [TargetMode; 3] = [TargetMode::Exact, TargetMode::AtLeast, TargetMode::Maximize];
+}
+
+/// 非目标物品盈余的处理方式
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, serde::Serialize, serde::Deserialize)]
+pub enum ByproductPolicy<R> {
+    /// 允许盈余
+    #[default]
+    Allow,
+    /// 不允许盈余，产出必须全部被消耗
+    Zero,
+    /// 不允许盈余，且只能由列出的机制消耗
+    Sinks(Vec<R>),
+}
+
+/// 最大化目标的每单位产量在目标函数中抵消的代价，远大于一般配方的代价，使产量优先于代价
+const MAXIMIZE_WEIGHT: f64 = 1e6;
+
 #[derive(Debug, Clone)]
 pub struct SolverData<I, R>
 where
@@ -33,13 +63,123 @@ where
 {
     target: Flow<I>,
     flows: IndexMap<R, (Flow<I>, f64)>,
-    external: Flow<I>, //  输入特定物品消耗的价值
+    external: Flow<I>,                                   //  输入特定物品消耗的价值
+    constant: Flow<I>, //  数量已固定的机制产生的物料流，作为各物品平衡的常数项
+    target_modes: IndexMap<I, TargetMode>, //  未列出的目标按 Exact 处理
+    byproduct_policies: IndexMap<I, ByproductPolicy<R>>, //  未列出的物品允许盈余
 }
 
 pub type BasicSolverArgs<I, R> = (Flow<I>, IndexMap<R, (Flow<I>, f64)>);
-pub type SolverArgs<I, R> = (Flow<I>, IndexMap<R, (Flow<I>, f64)>, Flow<I>);
+pub type SolverArgs<I, R> = (
+    Flow<I>,
+    IndexMap<R, (Flow<I>, f64)>,
+    Flow<I>,
+    Flow<I>,
+    IndexMap<I, TargetMode>,
+    IndexMap<I, ByproductPolicy<R>>,
+);
 pub type SolverSolution<R> = Result<(Flow<R>, f64), AppError>;
 
+/// 超过该秒数的求解视为耗时较长，完成时通知用户
+pub const SLOW_SOLVE_SECS: f64 = 2.0;
+
+/// 计算求解参数的内容哈希，浮点数按位参与哈希
+pub fn solver_args_hash<I, R>(args: &SolverArgs<I, R>) -> u64
+where
+    I: ItemIdent,
+    R: ItemIdent,
+{
+    fn hash_flow<I: Hash>(flow: &Flow<I>, hasher: &mut impl Hasher) {
+        flow.len().hash(hasher);
+        for (item, amount) in flow {
+            item.hash(hasher);
+            amount.to_bits().hash(hasher);
+        }
+    }
+    let (target, flows, external, constant, target_modes, byproduct_policies) = args;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    hash_flow(target, &mut hasher);
+    flows.len().hash(&mut hasher);
+    for (recipe_id, (flow, cost)) in flows {
+        recipe_id.hash(&mut hasher);
+        hash_flow(flow, &mut hasher);
+        cost.to_bits().hash(&mut hasher);
+    }
+    hash_flow(external, &mut hasher);
+    hash_flow(constant, &mut hasher);
+    target_modes.len().hash(&mut hasher);
+    for (item, mode) in target_modes {
+        item.hash(&mut hasher);
+        mode.hash(&mut hasher);
+    }
+    byproduct_policies.len().hash(&mut hasher);
+    for (item, policy) in byproduct_policies {
+        item.hash(&mut hasher);
+        policy.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// 以求解参数的内容哈希为键的求解结果缓存，可在多个求解线程间共享
+#[derive(Debug)]
+pub struct SolverCache<R>
+where
+    R: ItemIdent,
+{
+    solutions: std::sync::Mutex<IndexMap<u64, SolverSolution<R>>>,
+    capacity: usize,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl<R> SolverCache<R>
+where
+    R: ItemIdent,
+{
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            solutions: std::sync::Mutex::new(IndexMap::new()),
+            capacity,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn get(&self, hash: u64) -> Option<SolverSolution<R>> {
+        let solution = self.solutions.lock().unwrap().get(&hash).cloned();
+        if solution.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        solution
+    }
+
+    pub fn insert(&self, hash: u64, solution: SolverSolution<R>) {
+        let mut solutions = self.solutions.lock().unwrap();
+        // 超出容量时淘汰最早插入的结果
+        while solutions.len() >= self.capacity && !solutions.is_empty() {
+            solutions.shift_remove_index(0);
+        }
+        solutions.insert(hash, solution);
+    }
+
+    pub fn clear(&self) {
+        self.solutions.lock().unwrap().clear();
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    /// 返回 (命中次数, 未命中次数, 已缓存的结果数)
+    pub fn stats(&self) -> (usize, usize, usize) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+            self.solutions.lock().unwrap().len(),
+        )
+    }
+}
+
 impl<I, R> SolverData<I, R>
 where
     I: ItemIdent,
@@ -50,6 +190,9 @@ where
             target,
             flows,
             external: IndexMap::new(),
+            constant: IndexMap::new(),
+            target_modes: IndexMap::new(),
+            byproduct_policies: IndexMap::new(),
         }
     }
 
@@ -58,6 +201,24 @@ where
         self
     }
 
+    pub fn with_constant(mut self, constant: Flow<I>) -> Self {
+        self.constant.extend(constant);
+        self
+    }
+
+    pub fn with_target_modes(mut self, target_modes: IndexMap<I, TargetMode>) -> Self {
+        self.target_modes.extend(target_modes);
+        self
+    }
+
+    pub fn with_byproduct_policies(
+        mut self,
+        byproduct_policies: IndexMap<I, ByproductPolicy<R>>,
+    ) -> Self {
+        self.byproduct_policies.extend(byproduct_policies);
+        self
+    }
+
     pub fn solve(&self) -> Result<(Flow<R>, f64), AppError> {
         let mut problem_variables = good_lp::ProblemVariables::new();
         let mut flow_vars = HashMap::new();
@@ -67,6 +228,9 @@ where
             flow_vars.insert(recipe_id.clone(), var);
         }
         let mut item_balances = HashMap::new();
+        for (item_id, &amount) in &self.constant {
+            item_balances.insert(item_id.clone(), good_lp::Expression::from(amount));
+        }
 
         for (recipe_id, flow) in &self.flows {
             let var = flow_vars.get(recipe_id).unwrap();
@@ -96,22 +260,55 @@ where
         for item in self.external.keys() {
             no_providers.remove(item);
         }
+        for (item_id, &amount) in &self.constant {
+            if amount > 0.0 {
+                no_providers.remove(item_id);
+            }
+        }
         let mut targets = Vec::new();
+        let mut maximized = good_lp::Expression::from(0.0);
         for (item_id, &amount) in &self.target {
             let balance = item_balances.get(item_id);
             if let Some(expr) = balance {
-                targets.push(expr.clone().eq(amount));
+                match self.target_modes.get(item_id).copied().unwrap_or_default() {
+                    TargetMode::Exact => targets.push(expr.clone().eq(amount)),
+                    TargetMode::AtLeast => targets.push(expr.clone().geq(amount)),
+                    TargetMode::Maximize => {
+                        targets.push(expr.clone().geq(amount));
+                        maximized += expr.clone();
+                    }
+                }
             } else {
-                return Err(AppError::Solver(format!(
-                    "这个物品没有相关配方： {:?}",
-                    item_id
+                return Err(AppError::Solver(trf(
+                    "solver.no_recipe",
+                    &[&format!("{:?}", item_id)],
                 )));
             }
         }
         let mut constraints = Vec::new();
         for (item_id, expr) in &item_balances {
-            if !self.target.contains_key(item_id) && !no_providers.contains(item_id) {
-                constraints.push(expr.clone().geq(0.0));
+            if self.target.contains_key(item_id) || no_providers.contains(item_id) {
+                continue;
+            }
+            match self.byproduct_policies.get(item_id) {
+                None | Some(ByproductPolicy::Allow) => constraints.push(expr.clone().geq(0.0)),
+                Some(ByproductPolicy::Zero) => constraints.push(expr.clone().eq(0.0)),
+                Some(ByproductPolicy::Sinks(sinks)) => {
+                    constraints.push(expr.clone().eq(0.0));
+                    // 机制与常数的产出只能由列出的机制消耗，未列出的机制只能消耗额外输入的部分，
+                    // 它们本身仍可以为其他物品运行
+                    let mut byproduct = good_lp::Expression::from(
+                        self.constant.get(item_id).copied().unwrap_or(0.0).max(0.0),
+                    );
+                    for (recipe_id, (flow, _)) in &self.flows {
+                        if let Some(&amount) = flow.get(item_id)
+                            && (amount > 0.0 || sinks.contains(recipe_id))
+                        {
+                            byproduct += amount * flow_vars[recipe_id];
+                        }
+                    }
+                    constraints.push(byproduct.eq(0.0));
+                }
             }
         }
         for source_var in source_vars.values() {
@@ -127,7 +324,7 @@ where
             optimization_expr += *cost * *var;
         }
         let solution = problem_variables
-            .minimise(&optimization_expr)
+            .minimise(optimization_expr.clone() - MAXIMIZE_WEIGHT * maximized)
             .using(good_lp::default_solver)
             .with_all(targets)
             .with_all(constraints)
@@ -144,14 +341,18 @@ where
             }
             Err(err) => {
                 let err_string = match err {
-                    good_lp::ResolutionError::Unbounded => {
-                        "无界。存在能够无限产生目标物品且不增加消耗的配方组合。".to_string()
-                    }
-                    good_lp::ResolutionError::Infeasible => {
-                        "无解。不存在能够满足目标物品需求的配方组合。".to_string()
+                    good_lp::ResolutionError::Unbounded
+                        if self
+                            .target_modes
+                            .values()
+                            .any(|mode| *mode == TargetMode::Maximize) =>
+                    {
+                        tr("solver.maximize_unbounded").to_string()
                     }
-                    good_lp::ResolutionError::Other(_) => "求解过程中发生未知错误。".to_string(),
-                    good_lp::ResolutionError::Str(s) => format!("求解过程中发生内部错误：{}", s),
+                    good_lp::ResolutionError::Unbounded => tr("solver.unbounded").to_string(),
+                    good_lp::ResolutionError::Infeasible => tr("solver.infeasible").to_string(),
+                    good_lp::ResolutionError::Other(_) => tr("solver.unknown_error").to_string(),
+                    good_lp::ResolutionError::Str(s) => trf("solver.internal_error", &[&s]),
                 };
                 if !no_providers.is_empty() {
                     let mut no_providers = no_providers.iter().collect::<Vec<_>>();
@@ -162,41 +363,307 @@ where
             }
         }
     }
+}
 
-    pub fn make_basic_solver_thread(
-        solution_tx: std::sync::mpsc::Sender<SolverSolution<R>>,
-        arg_rx: std::sync::mpsc::Receiver<BasicSolverArgs<I, R>>,
-    ) {
-        std::thread::spawn(move || {
-            log::info!("求解线程启动");
-            while let Ok((target, flows)) = arg_rx.recv() {
-                let solver_data = SolverData::new(target, flows);
-                // log::info!("收到了新的计算请求……");
-                if solution_tx.send(solver_data.solve()).is_err() {
-                    // 接收方已关闭，退出线程
-                    break;
+/// 求解线程的运行统计，在求解线程与界面间共享
+#[derive(Debug)]
+pub struct SolverTelemetry {
+    /// 最近一次求解的耗时（微秒），尚未求解时为 u64::MAX
+    last_micros: AtomicU64,
+    solves: AtomicUsize,
+    /// 开始求解前就被更新的请求取代而跳过的请求数
+    skipped: AtomicUsize,
+    busy: AtomicBool,
+}
+
+impl Default for SolverTelemetry {
+    fn default() -> Self {
+        Self {
+            last_micros: AtomicU64::new(u64::MAX),
+            solves: AtomicUsize::new(0),
+            skipped: AtomicUsize::new(0),
+            busy: AtomicBool::new(false),
+        }
+    }
+}
+
+impl SolverTelemetry {
+    pub fn last_duration(&self) -> Option<std::time::Duration> {
+        match self.last_micros.load(Ordering::Relaxed) {
+            u64::MAX => None,
+            micros => Some(std::time::Duration::from_micros(micros)),
+        }
+    }
+
+    pub fn solves(&self) -> usize {
+        self.solves.load(Ordering::Relaxed)
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.busy.load(Ordering::Relaxed)
+    }
+}
+
+type SlowSolveCallback<R> = Box<dyn Fn(&SolverSolution<R>, std::time::Duration) + Send + Sync>;
+
+/// 一个求解请求方（通常是一个工厂）在线程池中的状态
+struct SolverClient<I, R>
+where
+    I: ItemIdent,
+    R: ItemIdent,
+{
+    /// 尚未开始求解的最新请求及其序号，新请求到达时直接替换
+    pending: std::sync::Mutex<Option<(u64, SolverArgs<I, R>)>>,
+    submitted: AtomicU64,
+    /// 已送出结果的最大序号，较早请求的结果晚到时被丢弃
+    delivered: std::sync::Mutex<u64>,
+    closed: AtomicBool,
+    solution_tx: std::sync::mpsc::Sender<SolverSolution<R>>,
+    on_slow_solve: SlowSolveCallback<R>,
+    telemetry: SolverTelemetry,
+}
+
+struct SolverPoolShared<I, R>
+where
+    I: ItemIdent,
+    R: ItemIdent,
+{
+    /// 有待求解请求的请求方，每个请求方最多出现一次
+    queue: std::sync::Mutex<VecDeque<Arc<SolverClient<I, R>>>>,
+    ready: std::sync::Condvar,
+    shutdown: AtomicBool,
+    cache: Option<Arc<SolverCache<R>>>,
+    alive: AtomicUsize,
+}
+
+/// 共享的求解线程池。固定数量的工作线程为所有请求方求解，请求方关闭后其请求与结果被丢弃，
+/// 不再为每个工厂各自创建常驻线程
+pub struct SolverPool<I, R>
+where
+    I: ItemIdent,
+    R: ItemIdent,
+{
+    shared: Arc<SolverPoolShared<I, R>>,
+    threads: std::sync::Mutex<Vec<std::thread::JoinHandle<()>>>,
+}
+
+impl<I, R> SolverPool<I, R>
+where
+    I: ItemIdent,
+    R: ItemIdent,
+{
+    /// 启动 workers 个工作线程，cache 不为空时先查询缓存，求解结果也写入缓存
+    ///
+    /// 网页版不能创建线程，不启动工作线程，请求在提交时直接于界面线程求解
+    pub fn new(workers: usize, cache: Option<Arc<SolverCache<R>>>) -> Self {
+        let shared = Arc::new(SolverPoolShared {
+            queue: std::sync::Mutex::new(VecDeque::new()),
+            ready: std::sync::Condvar::new(),
+            shutdown: AtomicBool::new(false),
+            cache,
+            alive: AtomicUsize::new(0),
+        });
+        #[cfg(target_arch = "wasm32")]
+        let workers = {
+            let _ = workers;
+            0
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let workers = workers.max(1);
+        let threads = (0..workers)
+            .map(|index| {
+                let shared = shared.clone();
+                shared.alive.fetch_add(1, Ordering::Relaxed);
+                std::thread::Builder::new()
+                    .name(format!("solver-{index}"))
+                    .spawn(move || {
+                        log::info!("求解线程启动");
+                        Self::worker(&shared);
+                        shared.alive.fetch_sub(1, Ordering::Relaxed);
+                        log::info!("求解线程退出");
+                    })
+                    .expect("无法创建求解线程")
+            })
+            .collect();
+        Self {
+            shared,
+            threads: std::sync::Mutex::new(threads),
+        }
+    }
+
+    fn worker(shared: &SolverPoolShared<I, R>) {
+        loop {
+            let client = {
+                let mut queue = shared.queue.lock().unwrap();
+                loop {
+                    if shared.shutdown.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    if let Some(client) = queue.pop_front() {
+                        break client;
+                    }
+                    queue = shared.ready.wait(queue).unwrap();
+                }
+            };
+            Self::solve_pending(shared, &client);
+        }
+    }
+
+    /// 求解请求方最新的请求并送出结果
+    fn solve_pending(shared: &SolverPoolShared<I, R>, client: &SolverClient<I, R>) {
+        let Some((sequence, args)) = client.pending.lock().unwrap().take() else {
+            return;
+        };
+        if client.closed.load(Ordering::Relaxed) {
+            return;
+        }
+        client.telemetry.busy.store(true, Ordering::Relaxed);
+        let start = web_time::Instant::now();
+        let hash = solver_args_hash(&args);
+        let solution = match shared.cache.as_ref().and_then(|cache| cache.get(hash)) {
+            Some(solution) => solution,
+            None => {
+                let (target, flows, external, constant, target_modes, byproduct_policies) = args;
+                let solution = SolverData::new(target, flows)
+                    .with_external(external)
+                    .with_constant(constant)
+                    .with_target_modes(target_modes)
+                    .with_byproduct_policies(byproduct_policies)
+                    .solve();
+                if start.elapsed().as_secs_f64() >= SLOW_SOLVE_SECS {
+                    (client.on_slow_solve)(&solution, start.elapsed());
+                }
+                if let Some(cache) = &shared.cache {
+                    cache.insert(hash, solution.clone());
                 }
+                solution
             }
-            log::info!("求解线程退出");
-        });
+        };
+        let telemetry = &client.telemetry;
+        telemetry
+            .last_micros
+            .store(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+        telemetry.solves.fetch_add(1, Ordering::Relaxed);
+        telemetry.busy.store(false, Ordering::Relaxed);
+        // 在 delivered 锁内检查关闭标记，句柄丢弃后不会再有结果送出
+        let mut delivered = client.delivered.lock().unwrap();
+        if !client.closed.load(Ordering::Relaxed) && sequence > *delivered {
+            *delivered = sequence;
+            let _ = client.solution_tx.send(solution);
+            crate::repaint::wake();
+        }
     }
 
-    pub fn make_solver_thread(
+    /// 登记一个请求方，结果经由 solution_tx 送回，未命中缓存且耗时超过 SLOW_SOLVE_SECS 时
+    /// 调用 on_slow_solve，便于用户在其他页面等待时得到通知
+    pub fn register(
+        &self,
         solution_tx: std::sync::mpsc::Sender<SolverSolution<R>>,
-        arg_rx: std::sync::mpsc::Receiver<SolverArgs<I, R>>,
-    ) {
-        std::thread::spawn(move || {
-            log::info!("求解线程启动");
-            while let Ok((target, flows, external)) = arg_rx.recv() {
-                let solver_data = SolverData::new(target, flows).with_external(external);
-                // log::info!("收到了新的计算请求……");
-                if solution_tx.send(solver_data.solve()).is_err() {
-                    // 接收方已关闭，退出线程
-                    break;
-                }
+        on_slow_solve: impl Fn(&SolverSolution<R>, std::time::Duration) + Send + Sync + 'static,
+    ) -> SolverHandle<I, R> {
+        SolverHandle {
+            client: Arc::new(SolverClient {
+                pending: std::sync::Mutex::new(None),
+                submitted: AtomicU64::new(0),
+                delivered: std::sync::Mutex::new(0),
+                closed: AtomicBool::new(false),
+                solution_tx,
+                on_slow_solve: Box::new(on_slow_solve),
+                telemetry: SolverTelemetry::default(),
+            }),
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// 返回 (存活的工作线程数, 排队等待求解的请求方数)
+    pub fn stats(&self) -> (usize, usize) {
+        (
+            self.shared.alive.load(Ordering::Relaxed),
+            self.shared.queue.lock().unwrap().len(),
+        )
+    }
+
+    /// 通知所有工作线程在完成手头的求解后退出，并等待其结束
+    pub fn shutdown(&self) {
+        self.shared.shutdown.store(true, Ordering::Relaxed);
+        self.shared.ready.notify_all();
+        for thread in self.threads.lock().unwrap().drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// 请求方持有的句柄，丢弃时取消尚未开始的请求，正在进行的求解结果也不再送出
+pub struct SolverHandle<I, R>
+where
+    I: ItemIdent,
+    R: ItemIdent,
+{
+    client: Arc<SolverClient<I, R>>,
+    shared: Arc<SolverPoolShared<I, R>>,
+}
+
+impl<I, R> SolverHandle<I, R>
+where
+    I: ItemIdent,
+    R: ItemIdent,
+{
+    /// 提交求解请求，尚未开始的旧请求被替换，不再求解
+    pub fn submit(&self, args: SolverArgs<I, R>) {
+        let sequence = self.client.submitted.fetch_add(1, Ordering::Relaxed) + 1;
+        let replaced = self
+            .client
+            .pending
+            .lock()
+            .unwrap()
+            .replace((sequence, args))
+            .is_some();
+        if replaced {
+            self.client
+                .telemetry
+                .skipped
+                .fetch_add(1, Ordering::Relaxed);
+        } else {
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                self.shared
+                    .queue
+                    .lock()
+                    .unwrap()
+                    .push_back(self.client.clone());
+                self.shared.ready.notify_one();
             }
-            log::info!("求解线程退出");
-        });
+            // 没有工作线程时在提交方的线程中立即求解
+            #[cfg(target_arch = "wasm32")]
+            SolverPool::solve_pending(&self.shared, &self.client);
+        }
+    }
+
+    pub fn telemetry(&self) -> &SolverTelemetry {
+        &self.client.telemetry
+    }
+}
+
+impl<I, R> Drop for SolverHandle<I, R>
+where
+    I: ItemIdent,
+    R: ItemIdent,
+{
+    fn drop(&mut self) {
+        {
+            let _delivered = self.client.delivered.lock().unwrap();
+            self.client.closed.store(true, Ordering::Relaxed);
+        }
+        self.client.pending.lock().unwrap().take();
+        self.shared
+            .queue
+            .lock()
+            .unwrap()
+            .retain(|client| !Arc::ptr_eq(client, &self.client));
     }
 }
 
@@ -211,3 +678,159 @@ where
 {
     SolverData::new(target, flows).solve()
 }
+
+#[test]
+fn test_solver_cache() {
+    let flows: IndexMap<usize, (Flow<&str>, f64)> = IndexMap::from([
+        (0, (IndexMap::from([("ore", -1.0), ("plate", 1.0)]), 1.0)),
+        (1, (IndexMap::from([("ore", 1.0)]), 1.0)),
+    ]);
+    let args = (
+        IndexMap::from([("plate", 2.0)]),
+        flows,
+        IndexMap::new(),
+        IndexMap::new(),
+        IndexMap::new(),
+        IndexMap::new(),
+    );
+    let mut changed = args.clone();
+    changed.0.insert("plate", 3.0);
+    assert_eq!(solver_args_hash(&args), solver_args_hash(&args.clone()));
+    assert_ne!(solver_args_hash(&args), solver_args_hash(&changed));
+
+    let cache = SolverCache::new(1);
+    assert!(cache.get(solver_args_hash(&args)).is_none());
+    let (target, flows, external, constant, target_modes, byproduct_policies) = args.clone();
+    let solution = SolverData::new(target, flows)
+        .with_external(external)
+        .with_constant(constant)
+        .with_target_modes(target_modes)
+        .with_byproduct_policies(byproduct_policies)
+        .solve();
+    cache.insert(solver_args_hash(&args), solution.clone());
+    assert_eq!(cache.get(solver_args_hash(&args)), Some(solution));
+    cache.insert(solver_args_hash(&changed), Err(AppError::None));
+    assert!(cache.get(solver_args_hash(&args)).is_none());
+    assert_eq!(cache.stats(), (1, 2, 1));
+}
+
+#[test]
+fn test_solver_constant() {
+    // 已固定的 1 台熔炉每秒产出 1 个铁板，其余 2 个由可变的熔炉补足
+    let flows: IndexMap<usize, (Flow<&str>, f64)> = IndexMap::from([
+        (0, (IndexMap::from([("ore", -1.0), ("plate", 1.0)]), 1.0)),
+        (1, (IndexMap::from([("ore", 1.0)]), 1.0)),
+    ]);
+    let (solution, _) = SolverData::new(IndexMap::from([("plate", 3.0)]), flows)
+        .with_constant(IndexMap::from([("ore", -1.0), ("plate", 1.0)]))
+        .solve()
+        .unwrap();
+    assert!((solution[&0] - 2.0).abs() < 1e-6);
+    assert!((solution[&1] - 3.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_target_modes() {
+    // 矿石只能来自固定的 4 个单位，熔炼 1 个矿石得到 1 个铁板，组装 2 个铁板得到 1 个齿轮
+    let flows: IndexMap<usize, (Flow<&str>, f64)> = IndexMap::from([
+        (0, (IndexMap::from([("ore", -1.0), ("plate", 1.0)]), 1.0)),
+        (1, (IndexMap::from([("plate", -2.0), ("gear", 1.0)]), 1.0)),
+    ]);
+    let solve = |modes: IndexMap<&'static str, TargetMode>| {
+        SolverData::new(IndexMap::from([("gear", 1.0)]), flows.clone())
+            .with_constant(IndexMap::from([("ore", 4.0)]))
+            .with_target_modes(modes)
+            .solve()
+            .unwrap()
+    };
+    let (exact, cost) = solve(IndexMap::new());
+    assert!((exact[&1] - 1.0).abs() < 1e-6);
+    assert!((cost - 3.0).abs() < 1e-6);
+    let (at_least, _) = solve(IndexMap::from([("gear", TargetMode::AtLeast)]));
+    assert!((at_least[&1] - 1.0).abs() < 1e-6);
+    // 最大化时用尽全部矿石，代价不含最大化项
+    let (maximized, cost) = solve(IndexMap::from([("gear", TargetMode::Maximize)]));
+    assert!((maximized[&1] - 2.0).abs() < 1e-6);
+    assert!((cost - 6.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_byproduct_policies() {
+    // 裂解产出 1 个重油和 1 个轻油，重油可以直接烧掉，也可以转化为轻油
+    let flows: IndexMap<usize, (Flow<&str>, f64)> = IndexMap::from([
+        (0, (IndexMap::from([("heavy", 1.0), ("light", 1.0)]), 1.0)),
+        (1, (IndexMap::from([("heavy", -1.0)]), 1.0)),
+        (2, (IndexMap::from([("heavy", -1.0), ("light", 1.0)]), 1.0)),
+    ]);
+    let solve = |policies: IndexMap<&'static str, ByproductPolicy<usize>>| {
+        SolverData::new(IndexMap::from([("light", 2.0)]), flows.clone())
+            .with_byproduct_policies(policies)
+            .solve()
+            .unwrap()
+    };
+    // 允许盈余时代价最低的方案是只裂解
+    let (allow, cost) = solve(IndexMap::new());
+    assert!((allow[&0] - 2.0).abs() < 1e-6);
+    assert!((cost - 2.0).abs() < 1e-6);
+    // 不允许盈余时，重油转化为轻油更划算
+    let (zero, _) = solve(IndexMap::from([("heavy", ByproductPolicy::Zero)]));
+    assert!((zero[&0] - 1.0).abs() < 1e-6);
+    assert!((zero[&2] - 1.0).abs() < 1e-6);
+    // 只允许烧掉重油
+    let (sinks, _) = solve(IndexMap::from([("heavy", ByproductPolicy::Sinks(vec![1]))]));
+    assert!((sinks[&0] - 2.0).abs() < 1e-6);
+    assert!((sinks[&1] - 2.0).abs() < 1e-6);
+    assert!(sinks[&2].abs() < 1e-6);
+    // 有额外输入的重油时，未列出的转化机制仍可以消耗这部分重油
+    let (external, _) = SolverData::new(IndexMap::from([("light", 2.0)]), flows.clone())
+        .with_external(IndexMap::from([("heavy", 0.1)]))
+        .with_byproduct_policies(IndexMap::from([("heavy", ByproductPolicy::Sinks(vec![1]))]))
+        .solve()
+        .unwrap();
+    assert!((external[&2] - 2.0).abs() < 1e-6);
+    assert!(external[&0].abs() < 1e-6);
+}
+
+#[test]
+fn test_solver_pool() {
+    type Args = SolverArgs<&'static str, usize>;
+    let args = |plate: f64| -> Args {
+        (
+            IndexMap::from([("plate", plate)]),
+            IndexMap::from([(0, (IndexMap::from([("ore", -1.0), ("plate", 1.0)]), 1.0))]),
+            IndexMap::new(),
+            IndexMap::new(),
+            IndexMap::new(),
+            IndexMap::new(),
+        )
+    };
+    let pool = SolverPool::new(2, None);
+    let (solution_tx, solution_rx) = std::sync::mpsc::channel();
+    let handle = pool.register(solution_tx, |_, _| {});
+    for plate in 1..=20 {
+        handle.submit(args(plate as f64));
+    }
+    // 最后一个请求的结果一定送达，且不会被更早请求的结果覆盖
+    let mut last = None;
+    while let Ok(solution) = solution_rx.recv_timeout(std::time::Duration::from_secs(5)) {
+        last = Some(solution.unwrap().0[&0]);
+        if last == Some(20.0) {
+            break;
+        }
+    }
+    assert_eq!(last, Some(20.0));
+    // 关闭请求方后不再送出结果，丢弃前已完成的求解至多送出一次
+    let (closed_tx, closed_rx) = std::sync::mpsc::channel();
+    let closed = pool.register(closed_tx, |_, _| {});
+    closed.submit(args(1.0));
+    drop(closed);
+    let delivered_before_close = closed_rx.try_iter().count();
+    pool.shutdown();
+    assert_eq!(pool.stats(), (0, 0));
+    assert!(delivered_before_close <= 1);
+    assert!(closed_rx.try_recv().is_err());
+    assert!(solution_rx.try_recv().is_err());
+    let telemetry = handle.telemetry();
+    assert_eq!(telemetry.solves() + telemetry.skipped(), 20);
+    assert!(telemetry.last_duration().is_some());
+}
@@ -1,5 +1,15 @@
+//! 通知：后台线程与界面代码通过 [`notify`] 发送的提示，由主界面的 [`NotificationCenter`] 显示。
+//!
+//! 下载更新的进度仍使用 egui_toast 的自定义提示显示。
+
+use std::sync::mpsc::{Receiver, Sender};
+
+#[cfg(not(target_arch = "wasm32"))]
 use egui_toast::ToastKind;
 
+use crate::concept::SubviewKey;
+
+#[cfg(not(target_arch = "wasm32"))]
 use crate::update::{DownloadProgress, get_download_progress};
 
 lazy_static::lazy_static! {
@@ -7,99 +17,264 @@ lazy_static::lazy_static! {
 }
 
 pub fn setup_toasts() -> egui_toast::Toasts {
-    egui_toast::Toasts::new()
-        .anchor(egui::Align2::RIGHT_TOP, (-10.0, 10.0))
-        .custom_contents(ToastKind::Custom(0), |ui, toast| {
-            match get_download_progress() {
-                DownloadProgress::Pending => {
-                    log::info!("更新下载等待中");
-                    toast.close();
-                    return ui.response().clone();
-                }
-                DownloadProgress::InProgress(current, total) => {
-                    let inner_margin = 10.0;
-                    let frame = egui::Frame::window(ui.style());
-                    let response = frame
-                        .inner_margin(inner_margin)
-                        .stroke(egui::Stroke::NONE)
-                        .show(ui, |ui| {
-                            ui.horizontal(|ui| {
-                                let progress = if total == 0 {
-                                    0.0
-                                } else {
-                                    current as f32 / total as f32
-                                };
-                                ui.vertical_centered(|ui| {
-                                    ui.label("正在下载更新...");
-                                    ui.add(
-                                        egui::ProgressBar::new(progress)
-                                            .fill(if current == total && total != 0 {
-                                                egui::Color32::GREEN
-                                            } else {
-                                                egui::Color32::LIGHT_BLUE
-                                            })
-                                            .desired_width(128.0)
-                                            .text(format!(
-                                                "{} KB/ {} KB",
-                                                (current) / 1024,
-                                                (total) / 1024,
-                                            )),
-                                    );
-                                });
-                            })
+    let toasts = egui_toast::Toasts::new().anchor(egui::Align2::RIGHT_TOP, (-10.0, 10.0));
+    // 网页版没有自动更新
+    #[cfg(not(target_arch = "wasm32"))]
+    let toasts = toasts.custom_contents(ToastKind::Custom(0), |ui, toast| {
+        match get_download_progress() {
+            DownloadProgress::Pending => {
+                log::info!("更新下载等待中");
+                toast.close();
+                ui.response().clone()
+            }
+            DownloadProgress::InProgress(current, total) => {
+                let inner_margin = 10.0;
+                let frame = egui::Frame::window(ui.style());
+                let response = frame
+                    .inner_margin(inner_margin)
+                    .stroke(egui::Stroke::NONE)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            let progress = if total == 0 {
+                                0.0
+                            } else {
+                                current as f32 / total as f32
+                            };
+                            ui.vertical_centered(|ui| {
+                                ui.label("正在下载更新...");
+                                ui.add(
+                                    egui::ProgressBar::new(progress)
+                                        .fill(if current == total && total != 0 {
+                                            egui::Color32::GREEN
+                                        } else {
+                                            egui::Color32::LIGHT_BLUE
+                                        })
+                                        .desired_width(128.0)
+                                        .text(format!(
+                                            "{} KB/ {} KB",
+                                            (current) / 1024,
+                                            (total) / 1024,
+                                        )),
+                                );
+                            });
                         })
-                        .response;
-
-                    // Draw the frame's stroke last
-                    let frame_shape = egui::Shape::Rect(egui::epaint::RectShape::stroke(
-                        response.rect,
-                        frame.corner_radius,
-                        ui.visuals().window_stroke,
-                        egui::StrokeKind::Inside,
-                    ));
-                    ui.painter().add(frame_shape);
-
-                    return response;
-                }
-                DownloadProgress::Completed => {
-                    log::info!("更新下载完成");
-                    toast.kind = ToastKind::Success;
-                    toast.text = "更新下载完成。".into();
-                    toast.options.duration_in_seconds(3.0);
-                    return ui.response().clone();
-                }
+                    })
+                    .response;
+
+                // Draw the frame's stroke last
+                let frame_shape = egui::Shape::Rect(egui::epaint::RectShape::stroke(
+                    response.rect,
+                    frame.corner_radius,
+                    ui.visuals().window_stroke,
+                    egui::StrokeKind::Inside,
+                ));
+                ui.painter().add(frame_shape);
+
+                response
+            }
+            DownloadProgress::Completed => {
+                log::info!("更新下载完成");
+                toast.kind = ToastKind::Success;
+                toast.text = "更新下载完成。".into();
+                toast.options.duration_in_seconds(3.0);
+                ui.response().clone()
             }
-        })
+        }
+    });
+    toasts
 }
 
-pub fn success(text: impl Into<egui::WidgetText>) {
-    TOASTS.lock().unwrap().add(egui_toast::Toast {
-        kind: egui_toast::ToastKind::Success,
-        text: text.into(),
-        options: egui_toast::ToastOptions::default().duration_in_seconds(3.0),
-        style: egui_toast::ToastStyle {
-            success_icon: "√".into(),
-            ..Default::default()
-        },
-    });
+/// 通知的严重程度，决定图标、颜色与停留时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
 }
 
-pub fn info(text: impl Into<egui::WidgetText>) {
-    TOASTS.lock().unwrap().add(egui_toast::Toast {
-        kind: egui_toast::ToastKind::Info,
-        text: text.into(),
-        options: egui_toast::ToastOptions::default().duration_in_seconds(3.0),
-        style: egui_toast::ToastStyle::default(),
-    });
+impl Severity {
+    fn icon(&self) -> &'static str {
+        match self {
+            Severity::Info => "ℹ",
+            Severity::Success => "√",
+            Severity::Warning => "⚠",
+            Severity::Error => "❗",
+        }
+    }
+
+    fn color(&self, visuals: &egui::Visuals) -> egui::Color32 {
+        match self {
+            Severity::Info => visuals.text_color(),
+            Severity::Success => egui::Color32::from_rgb(0, 200, 80),
+            Severity::Warning => visuals.warn_fg_color,
+            Severity::Error => visuals.error_fg_color,
+        }
+    }
+
+    /// 停留的秒数，鼠标悬停时暂停计时
+    pub fn duration_secs(&self) -> f64 {
+        match self {
+            Severity::Info | Severity::Success => 3.0,
+            Severity::Warning => 6.0,
+            Severity::Error => 10.0,
+        }
+    }
 }
 
-pub fn error(text: impl Into<egui::WidgetText>) {
-    TOASTS.lock().unwrap().add(egui_toast::Toast {
-        kind: egui_toast::ToastKind::Error,
-        text: text.into(),
-        options: egui_toast::ToastOptions::default().duration_in_seconds(10.0),
-        style: egui_toast::ToastStyle::default(),
-    });
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub severity: Severity,
+    pub text: String,
+    /// 点击后切换到的页面
+    pub target: Option<SubviewKey>,
+}
+
+impl Notification {
+    pub fn new(severity: Severity, text: impl Into<String>) -> Self {
+        Self {
+            severity,
+            text: text.into(),
+            target: None,
+        }
+    }
+
+    pub fn with_target(mut self, target: impl Into<Option<SubviewKey>>) -> Self {
+        self.target = target.into();
+        self
+    }
+}
+
+type NotificationChannel = (
+    Sender<Notification>,
+    std::sync::Mutex<Option<Receiver<Notification>>>,
+);
+
+lazy_static::lazy_static! {
+    /// 任意线程都可以发送通知，接收端由主界面的 NotificationCenter 取走
+    static ref NOTIFICATIONS: NotificationChannel = {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        (sender, std::sync::Mutex::new(Some(receiver)))
+    };
+}
+
+/// 发送一条通知，可以在后台线程中调用
+pub fn notify(notification: Notification) {
+    log::info!("通知: {}", notification.text);
+    let _ = NOTIFICATIONS.0.send(notification);
+    crate::repaint::wake();
+}
+
+pub fn success(text: impl Into<String>) {
+    notify(Notification::new(Severity::Success, text));
+}
+
+pub fn info(text: impl Into<String>) {
+    notify(Notification::new(Severity::Info, text));
+}
+
+pub fn warning(text: impl Into<String>) {
+    notify(Notification::new(Severity::Warning, text));
+}
+
+pub fn error(text: impl Into<String>) {
+    notify(Notification::new(Severity::Error, text));
+}
+
+struct ActiveNotification {
+    notification: Notification,
+    remaining_secs: f64,
+}
+
+/// 主界面右上角的通知列表，新通知在最上方，点击带有目标页面的通知会跳转过去
+#[derive(Default)]
+pub struct NotificationCenter {
+    receiver: Option<Receiver<Notification>>,
+    active: Vec<ActiveNotification>,
+}
+
+impl NotificationCenter {
+    /// 同时最多显示的通知数量，更早的通知直接移除
+    pub const MAX_VISIBLE: usize = 6;
+
+    pub fn new() -> Self {
+        Self {
+            receiver: NOTIFICATIONS.1.lock().unwrap().take(),
+            active: Vec::new(),
+        }
+    }
+
+    fn receive(&mut self) {
+        let Some(receiver) = &self.receiver else {
+            return;
+        };
+        while let Ok(notification) = receiver.try_recv() {
+            // 连续的相同通知只保留一条并重新计时
+            self.active
+                .retain(|active| active.notification != notification);
+            self.active.insert(
+                0,
+                ActiveNotification {
+                    remaining_secs: notification.severity.duration_secs(),
+                    notification,
+                },
+            );
+        }
+        self.active.truncate(Self::MAX_VISIBLE);
+    }
+
+    /// 绘制通知，返回本帧被点击的通知指向的页面
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<SubviewKey> {
+        self.receive();
+        let dt = ctx.input(|input| input.unstable_dt) as f64;
+        let mut offset = egui::vec2(-10.0, 10.0);
+        let mut clicked = None;
+        for (idx, active) in self.active.iter_mut().enumerate() {
+            let notification = &active.notification;
+            let mut closed = false;
+            let response = egui::Area::new(egui::Id::new("notification").with(idx))
+                .anchor(egui::Align2::RIGHT_TOP, offset)
+                .order(egui::Order::Foreground)
+                .show(ctx, |ui| {
+                    egui::Frame::window(ui.style())
+                        .inner_margin(10.0)
+                        .show(ui, |ui| {
+                            ui.set_max_width(360.0);
+                            ui.horizontal(|ui| {
+                                ui.colored_label(
+                                    notification.severity.color(ui.visuals()),
+                                    notification.severity.icon(),
+                                );
+                                ui.add(egui::Label::new(&notification.text).wrap());
+                                closed = ui.small_button("×").clicked();
+                            });
+                        })
+                        .response
+                })
+                .inner;
+            let response = if notification.target.is_some() {
+                response
+                    .interact(egui::Sense::click())
+                    .on_hover_cursor(egui::CursorIcon::PointingHand)
+                    .on_hover_text(crate::i18n::tr("notification.jump"))
+            } else {
+                response
+            };
+            if response.clicked() {
+                clicked = notification.target;
+                closed = true;
+            }
+            if closed {
+                active.remaining_secs = 0.0;
+            } else if !response.hovered() {
+                active.remaining_secs -= dt;
+                ctx.request_repaint_after_secs(active.remaining_secs.max(0.0) as f32);
+            }
+            offset.y += response.rect.height() + 10.0;
+        }
+        self.active.retain(|active| active.remaining_secs > 0.0);
+        clicked
+    }
 }
 
 pub fn download() {
@@ -113,4 +288,30 @@ pub fn download() {
             .duration(None),
         style: egui_toast::ToastStyle::default(),
     });
+    crate::repaint::wake();
+}
+
+#[test]
+fn test_notification_center() {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut center = NotificationCenter {
+        receiver: Some(receiver),
+        active: Vec::new(),
+    };
+    let target = SubviewKey::next();
+    sender
+        .send(Notification::new(Severity::Error, "保存失败").with_target(target))
+        .unwrap();
+    for _ in 0..NotificationCenter::MAX_VISIBLE + 2 {
+        sender
+            .send(Notification::new(Severity::Info, "求解完成"))
+            .unwrap();
+    }
+    center.receive();
+    // 重复的通知合并为一条，新的在前
+    assert_eq!(center.active.len(), 2);
+    assert_eq!(center.active[0].notification.text, "求解完成");
+    assert_eq!(center.active[1].notification.target, Some(target));
+    assert_eq!(center.active[1].remaining_secs, 10.0);
+    assert_ne!(SubviewKey::next(), target);
 }
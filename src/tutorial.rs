@@ -0,0 +1,203 @@
+//! 首次运行时的交互式引导：高亮当前步骤对应的控件，并在角落显示步骤列表。
+//!
+//! 各界面在绘制控件时调用 [`anchor`] 登记控件位置，在用户完成操作时调用 [`complete`]，
+//! 主界面在每帧最后调用 [`show`] 绘制高亮框与步骤窗口。
+
+use std::collections::HashMap;
+
+use crate::{dyn_serde::save_to_file, factorio::get_workding_directory};
+
+lazy_static::lazy_static! {
+    static ref TUTORIAL: std::sync::Mutex<Tutorial> = std::sync::Mutex::new(Tutorial::default());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TutorialStep {
+    LoadContext,
+    CreateFactory,
+    AddTarget,
+    AcceptHint,
+    ReadSolution,
+}
+
+impl TutorialStep {
+    pub const ALL: [TutorialStep; 5] = [
+        TutorialStep::LoadContext,
+        TutorialStep::CreateFactory,
+        TutorialStep::AddTarget,
+        TutorialStep::AcceptHint,
+        TutorialStep::ReadSolution,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            TutorialStep::LoadContext => "加载游戏上下文",
+            TutorialStep::CreateFactory => "新建工厂",
+            TutorialStep::AddTarget => "添加优化目标",
+            TutorialStep::AcceptHint => "采纳推荐配方",
+            TutorialStep::ReadSolution => "阅读求解结果",
+        }
+    }
+
+    pub fn instruction(&self) -> &'static str {
+        match self {
+            TutorialStep::LoadContext => {
+                "选择游戏可执行文件后点击「加载游戏上下文」，或直接加载上次缓存的上下文。加载完成后左侧会出现工厂规划器。"
+            }
+            TutorialStep::CreateFactory => {
+                "在左侧列表中选择「异星工厂 - 工厂规划器」，然后点击「文件」菜单中的「新建工厂」。"
+            }
+            TutorialStep::AddTarget => {
+                "点击「添加目标产物」，再点击目标的图标选择想要生产的物品，并填写每秒的产量。"
+            }
+            TutorialStep::AcceptHint => {
+                "右键点击目标或总物料流中的物品图标，在推荐配方中点击「添加」。"
+            }
+            TutorialStep::ReadSolution => {
+                "求解完成后，总物料流中正数为产出、负数为缺口，每个配方卡片上显示所需的机器数量。"
+            }
+        }
+    }
+}
+
+/// 引导是否已经完成过，保存在工作目录下
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct TutorialRecord {
+    pub finished: bool,
+}
+
+impl TutorialRecord {
+    pub fn path() -> std::path::PathBuf {
+        get_workding_directory().join("tutorial.json")
+    }
+
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Tutorial {
+    pub active: bool,
+    pub current: usize,
+    /// 本帧中各步骤对应控件的位置
+    anchors: HashMap<TutorialStep, egui::Rect>,
+}
+
+impl Tutorial {
+    pub fn current_step(&self) -> Option<TutorialStep> {
+        if self.active {
+            TutorialStep::ALL.get(self.current).copied()
+        } else {
+            None
+        }
+    }
+
+    fn advance(&mut self) {
+        self.current += 1;
+        if self.current >= TutorialStep::ALL.len() {
+            self.finish();
+        }
+    }
+
+    fn finish(&mut self) {
+        self.active = false;
+        self.anchors.clear();
+        if let Err(err) = save_to_file(&TutorialRecord { finished: true }, &TutorialRecord::path())
+        {
+            log::error!("保存引导进度失败: {:?}", err);
+        }
+    }
+}
+
+/// 从第一步开始引导
+pub fn start() {
+    let mut tutorial = TUTORIAL.lock().unwrap();
+    tutorial.active = true;
+    tutorial.current = 0;
+    tutorial.anchors.clear();
+}
+
+/// 首次运行时自动开始引导
+pub fn start_if_first_run() {
+    if !TutorialRecord::load().finished {
+        start();
+    }
+}
+
+/// 登记某个步骤对应的控件，只在该步骤进行中时生效
+pub fn anchor(step: TutorialStep, response: &egui::Response) {
+    let mut tutorial = TUTORIAL.lock().unwrap();
+    if tutorial.current_step() == Some(step) {
+        tutorial.anchors.insert(step, response.rect);
+    }
+}
+
+/// 用户完成了某个步骤的操作，若正好是当前步骤则进入下一步
+pub fn complete(step: TutorialStep) {
+    let mut tutorial = TUTORIAL.lock().unwrap();
+    if tutorial.current_step() == Some(step) {
+        tutorial.advance();
+        crate::repaint::wake();
+    }
+}
+
+/// 绘制高亮框与步骤窗口，在每帧所有界面绘制完成后调用
+pub fn show(ctx: &egui::Context) {
+    let mut tutorial = TUTORIAL.lock().unwrap();
+    let Some(step) = tutorial.current_step() else {
+        return;
+    };
+    if let Some(rect) = tutorial.anchors.get(&step) {
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("tutorial-highlight"),
+        ));
+        painter.rect_stroke(
+            rect.expand(4.0),
+            4.0,
+            egui::Stroke::new(2.0, egui::Color32::YELLOW),
+            egui::StrokeKind::Outside,
+        );
+    }
+    tutorial.anchors.clear();
+    let mut next = false;
+    let mut skip = false;
+    egui::Window::new("新手引导")
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-16.0, -16.0])
+        .resizable(false)
+        .collapsible(true)
+        .show(ctx, |ui| {
+            ui.set_max_width(280.0);
+            for (idx, other) in TutorialStep::ALL.iter().enumerate() {
+                let text = format!("{}. {}", idx + 1, other.title());
+                if idx < tutorial.current {
+                    ui.label(egui::RichText::new(format!("✔ {}", text)).weak());
+                } else if idx == tutorial.current {
+                    ui.label(egui::RichText::new(format!("▶ {}", text)).strong());
+                } else {
+                    ui.label(format!("  {}", text));
+                }
+            }
+            ui.separator();
+            ui.label(step.instruction());
+            ui.horizontal(|ui| {
+                let last = tutorial.current + 1 == TutorialStep::ALL.len();
+                if ui.button(if last { "完成" } else { "下一步" }).clicked() {
+                    next = true;
+                }
+                if ui.button("跳过引导").clicked() {
+                    skip = true;
+                }
+            });
+        });
+    if skip {
+        tutorial.finish();
+    } else if next {
+        tutorial.advance();
+    }
+}
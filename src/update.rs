@@ -315,6 +315,7 @@ pub fn get_download_progress() -> DownloadProgress {
 
 pub fn set_download_progress(progress: DownloadProgress) {
     *DOWNLOAD_PROGRESS.lock() = progress;
+    crate::repaint::wake();
 }
 
 pub fn update() -> Result<(), crate::error::AppError> {
@@ -0,0 +1,228 @@
+//! 工作区：上次打开的页面与其中的工厂，启动时据此恢复规划会话。
+//!
+//! 保存在工作目录下的 workspace.json 中，页面或工厂变化时立即写入，异常退出也不会丢失。
+//! 规划器的各个上下文从各自的导出目录恢复，从未保存过的工厂没有文件，无法恢复。
+
+use std::{
+    path::PathBuf,
+    sync::mpsc::{Receiver, Sender},
+};
+
+use crate::{
+    concept::Subview,
+    dyn_serde::save_to_file,
+    error::AppError,
+    factorio::{
+        FactorioContext, LoadMonitor, get_workding_directory, latest_dump_key, planner::PlannerView,
+    },
+    i18n::trf,
+    settings::SettingsView,
+};
+
+/// 可以恢复的页面
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type")]
+pub enum WorkspaceEntry {
+    Settings,
+    FactorioPlanner {
+        /// 打开的工厂文件，按标签顺序排列
+        factories: Vec<PathBuf>,
+        selected_factory: usize,
+        /// 各上下文导出数据的标识，见 context_dump_dir
+        #[serde(default)]
+        contexts: Vec<String>,
+        /// 各工厂绑定的上下文在 contexts 中的下标，与 factories 一一对应
+        #[serde(default)]
+        factory_contexts: Vec<usize>,
+    },
+}
+
+/// 规划器页面的上下文标识，以及各工厂文件与其上下文在前者中的下标
+pub type PlannerContexts<'a> = (Vec<String>, Vec<(&'a PathBuf, usize)>);
+
+impl WorkspaceEntry {
+    /// 规划器页面中各上下文的导出数据标识与各工厂绑定的上下文下标。
+    /// 旧版本的工作区没有记录上下文，全部使用最近一次导出的数据
+    pub fn planner_contexts(&self) -> Option<PlannerContexts<'_>> {
+        let WorkspaceEntry::FactorioPlanner {
+            factories,
+            contexts,
+            factory_contexts,
+            ..
+        } = self
+        else {
+            return None;
+        };
+        let keys = if contexts.is_empty() {
+            vec![latest_dump_key()]
+        } else {
+            contexts.clone()
+        };
+        let factories = factories
+            .iter()
+            .enumerate()
+            .map(|(idx, path)| {
+                let context = factory_contexts.get(idx).copied().unwrap_or(0);
+                (path, context.min(keys.len() - 1))
+            })
+            .collect();
+        Some((keys, factories))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Workspace {
+    pub entries: Vec<WorkspaceEntry>,
+    /// 选中的页面在 entries 中的下标，None 表示选中了上下文创建页
+    pub selected: Option<usize>,
+}
+
+impl Workspace {
+    pub fn path() -> PathBuf {
+        get_workding_directory().join("workspace.json")
+    }
+
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_json::from_str::<Workspace>(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// 读取指定的工作区文件，与 load 不同，读取或解析失败时返回错误
+    pub fn load_from(path: &std::path::Path) -> Result<Self, AppError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| AppError::Io(format!("读取文件 {} 失败：{}", path.display(), e)))?;
+        serde_json::from_str(&content)
+            .map_err(|e| AppError::Io(format!("解析工作区 {} 失败：{}", path.display(), e)))
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = save_to_file(self, &Self::path()) {
+            log::error!("保存工作区失败: {:?}", err);
+        }
+    }
+
+    /// 由当前打开的页面生成工作区，selected 为选中页面在 subviews 中的下标
+    pub fn capture(subviews: &[Box<dyn Subview>], selected: Option<usize>) -> Self {
+        let mut workspace = Workspace::default();
+        for (idx, subview) in subviews.iter().enumerate() {
+            if let Some(entry) = subview.workspace_entry() {
+                if selected == Some(idx) {
+                    workspace.selected = Some(workspace.entries.len());
+                }
+                workspace.entries.push(entry);
+            }
+        }
+        workspace
+    }
+
+    /// 在后台按顺序重建页面并发送到 sender，全部发送后通过返回的通道告知应选中第几个恢复的页面
+    pub fn restore(self, sender: Sender<Box<dyn Subview>>) -> Receiver<Option<usize>> {
+        let (done_sender, done_receiver) = std::sync::mpsc::channel();
+        let task = move || {
+            let mut restored = 0;
+            let mut selected = None;
+            for (idx, entry) in self.entries.iter().enumerate() {
+                let subview: Box<dyn Subview> = match entry {
+                    WorkspaceEntry::Settings => Box::new(SettingsView),
+                    WorkspaceEntry::FactorioPlanner {
+                        selected_factory, ..
+                    } => {
+                        let (keys, factories) = entry.planner_contexts().unwrap();
+                        // 每个上下文从各自的导出目录读取，读取失败的上下文及其工厂被跳过
+                        let mut contexts = Vec::new();
+                        let mut indices = Vec::new();
+                        for key in &keys {
+                            match FactorioContext::load_from_tmp_no_dump(
+                                key,
+                                &LoadMonitor::default(),
+                            ) {
+                                Ok(ctx) => {
+                                    indices.push(Some(contexts.len()));
+                                    contexts.push(ctx);
+                                }
+                                Err(err) => {
+                                    crate::toast::error(trf(
+                                        "workspace.restore_failed",
+                                        &[&format!("{:?}", err)],
+                                    ));
+                                    indices.push(None);
+                                }
+                            }
+                        }
+                        if contexts.is_empty() {
+                            continue;
+                        }
+                        let factories = factories
+                            .into_iter()
+                            .filter_map(|(path, context)| {
+                                indices[context].map(|context| (path.clone(), context))
+                            })
+                            .collect();
+                        Box::new(
+                            PlannerView::restore(contexts, factories, *selected_factory)
+                                .with_subview_sender(sender.clone()),
+                        )
+                    }
+                };
+                if self.selected == Some(idx) {
+                    selected = Some(restored);
+                }
+                if sender.send(subview).is_err() {
+                    return;
+                }
+                restored += 1;
+            }
+            let _ = done_sender.send(selected);
+            crate::repaint::wake();
+        };
+        // 网页版没有线程，在界面线程中依次恢复
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(task);
+        #[cfg(target_arch = "wasm32")]
+        task();
+        done_receiver
+    }
+}
+
+#[test]
+fn test_workspace_roundtrip() {
+    let workspace = Workspace {
+        entries: vec![
+            WorkspaceEntry::FactorioPlanner {
+                factories: vec![PathBuf::from("a.fpc"), PathBuf::from("b.fpc")],
+                selected_factory: 1,
+                contexts: vec!["vanilla".to_string(), "space-age".to_string()],
+                factory_contexts: vec![0, 1],
+            },
+            WorkspaceEntry::Settings,
+        ],
+        selected: Some(0),
+    };
+    let json = serde_json::to_string(&workspace).unwrap();
+    assert_eq!(serde_json::from_str::<Workspace>(&json).unwrap(), workspace);
+    // 各工厂从自己绑定的上下文恢复
+    let (keys, factories) = workspace.entries[0].planner_contexts().unwrap();
+    assert_eq!(keys, ["vanilla", "space-age"]);
+    assert_eq!(factories[1], (&PathBuf::from("b.fpc"), 1));
+    // 旧版本的工作区没有记录上下文，全部绑定到同一个上下文
+    let legacy: Workspace = serde_json::from_str(
+        r#"{"entries":[{"type":"FactorioPlanner","factories":["a.fpc"],"selected_factory":0}]}"#,
+    )
+    .unwrap();
+    let (keys, factories) = legacy.entries[0].planner_contexts().unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(factories, [(&PathBuf::from("a.fpc"), 0)]);
+    // 无法恢复的页面不计入，选中的下标随之调整
+    struct Transient;
+    impl Subview for Transient {
+        fn view(&mut self, _ui: &mut egui::Ui) {}
+    }
+    let subviews: Vec<Box<dyn Subview>> = vec![Box::new(Transient), Box::new(SettingsView)];
+    let captured = Workspace::capture(&subviews, Some(1));
+    assert_eq!(captured.entries, vec![WorkspaceEntry::Settings]);
+    assert_eq!(captured.selected, Some(0));
+    assert_eq!(Workspace::capture(&subviews, Some(0)).selected, None);
+}